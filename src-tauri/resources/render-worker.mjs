@@ -0,0 +1,112 @@
+// Long-lived mermaid renderer driven by the desktop app.
+//
+// Reads one JSON request per line on stdin and answers with one JSON line
+// on stdout. Chromium is launched once and reused for every render, which
+// avoids the Node and browser start-up cost of running mmdc each time.
+//
+// Environment:
+//   MERMAID_CLI_DIR           @mermaid-js/mermaid-cli package directory
+//   MERMAID_PUPPETEER_CONFIG  optional puppeteer launch options as JSON
+
+import { readFile } from 'node:fs/promises'
+import { createRequire } from 'node:module'
+import { join } from 'node:path'
+import { createInterface } from 'node:readline'
+import { pathToFileURL } from 'node:url'
+
+function send(message) {
+  process.stdout.write(JSON.stringify(message) + '\n')
+}
+
+function fatal(error) {
+  process.stderr.write(`render worker: ${error && error.stack ? error.stack : error}\n`)
+  process.exit(1)
+}
+
+async function loadCli(cliDir) {
+  const manifest = JSON.parse(await readFile(join(cliDir, 'package.json'), 'utf8'))
+  const exported = manifest.exports && manifest.exports['.']
+  const entry =
+    (typeof exported === 'string' ? exported : exported && (exported.import || exported.default)) ||
+    manifest.main ||
+    'src/index.js'
+  return import(pathToFileURL(join(cliDir, entry)).href)
+}
+
+async function browserMemory(browser) {
+  const pid = browser.process() && browser.process().pid
+  if (!pid || process.platform !== 'linux') return 0
+  try {
+    const status = await readFile(`/proc/${pid}/status`, 'utf8')
+    const match = status.match(/VmRSS:\s+(\d+) kB/)
+    return match ? Number(match[1]) * 1024 : 0
+  } catch {
+    return 0
+  }
+}
+
+async function memory(browser) {
+  return process.memoryUsage().rss + (await browserMemory(browser))
+}
+
+async function main() {
+  const cliDir = process.env.MERMAID_CLI_DIR
+  if (!cliDir) throw new Error('MERMAID_CLI_DIR is not set')
+
+  const require = createRequire(join(cliDir, 'package.json'))
+  const { renderMermaid } = await loadCli(cliDir)
+  const puppeteer = (await import(pathToFileURL(require.resolve('puppeteer')).href)).default
+
+  const launchOptions = JSON.parse(process.env.MERMAID_PUPPETEER_CONFIG || '{}')
+  const browser = await puppeteer.launch({ headless: 'shell', ...launchOptions })
+  // Without a browser every request would fail; let the app start a fresh worker
+  browser.on('disconnected', () => fatal('browser disconnected'))
+
+  async function handle(request) {
+    switch (request.type) {
+      case 'render': {
+        const { data } = await renderMermaid(browser, request.code, 'svg', {
+          viewport: {
+            width: request.width || 800,
+            height: request.height || 600,
+            deviceScaleFactor: 1,
+          },
+          backgroundColor: request.background || 'white',
+          mermaidConfig: request.config || {},
+          myCSS: request.css || undefined,
+          svgId: request.svgId || undefined,
+        })
+        return { svg: new TextDecoder().decode(data), memory: await memory(browser) }
+      }
+      case 'ping':
+        return { memory: await memory(browser) }
+      case 'shutdown':
+        await browser.close().catch(() => {})
+        process.exit(0)
+        break
+      default:
+        throw new Error(`unknown request type: ${request.type}`)
+    }
+  }
+
+  // Requests are handled one at a time, in order
+  let queue = Promise.resolve()
+  const lines = createInterface({ input: process.stdin })
+  lines.on('line', (line) => {
+    if (!line.trim()) return
+    queue = queue.then(async () => {
+      let request
+      try {
+        request = JSON.parse(line)
+        send({ id: request.id, ok: true, ...(await handle(request)) })
+      } catch (error) {
+        send({ id: request ? request.id : 0, ok: false, error: String(error && error.message ? error.message : error) })
+      }
+    })
+  })
+  lines.on('close', () => browser.close().finally(() => process.exit(0)))
+
+  send({ id: 0, ok: true, ready: true })
+}
+
+main().catch(fatal)
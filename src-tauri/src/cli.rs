@@ -0,0 +1,208 @@
+use crate::models::{DiagramFormat, DiagramOptions, TextOptions};
+use crate::services::dot_service::export_dot;
+use crate::services::drawio_service::export_drawio;
+use crate::services::excalidraw_service::export_excalidraw;
+use crate::services::text_service::render_text;
+use crate::services::RenderService;
+use serde_json::Value;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "Usage: render <input.mmd|-> [--format <format>] [--output <path>] \
+[--theme <name>] [--ascii] [--width <columns>]";
+
+/// Arguments of the `render` subcommand
+#[derive(Debug, Default, PartialEq)]
+struct RenderArgs {
+    input: String,
+    format: Option<DiagramFormat>,
+    output: Option<PathBuf>,
+    theme: Option<String>,
+    text: TextOptions,
+}
+
+/// Run a headless subcommand instead of the GUI. Returns the exit code, or
+/// `None` when the arguments are not a subcommand and the app should start.
+pub fn run(args: &[String]) -> Option<i32> {
+    if args.first().map(String::as_str) != Some("render") {
+        return None;
+    }
+    attach_console();
+
+    match parse_args(&args[1..]).and_then(|args| render(&args)) {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+/// Release builds on Windows use the GUI subsystem and start without a
+/// console, so attach to the one the command was run from for the output
+/// and errors to show up there
+#[cfg(target_os = "windows")]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // Fails harmlessly when a console is already attached or there is none
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn attach_console() {}
+
+fn parse_args(args: &[String]) -> Result<RenderArgs, String> {
+    let mut parsed = RenderArgs::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--format" | "-f" => parsed.format = Some(parse_format(&value(arg)?)?),
+            "--output" | "-o" => parsed.output = Some(PathBuf::from(value(arg)?)),
+            "--theme" | "-t" => parsed.theme = Some(value(arg)?),
+            "--ascii" => parsed.text.ascii = true,
+            "--width" | "-w" => {
+                let width = value(arg)?;
+                parsed.text.max_width = Some(
+                    width
+                        .parse()
+                        .map_err(|_| format!("Invalid width: {}", width))?,
+                );
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("Unknown option: {}\n{}", arg, USAGE))
+            }
+            _ if parsed.input.is_empty() => parsed.input = arg.clone(),
+            _ => return Err(format!("Unexpected argument: {}\n{}", arg, USAGE)),
+        }
+    }
+
+    if parsed.input.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(parsed)
+}
+
+fn parse_format(name: &str) -> Result<DiagramFormat, String> {
+    let name = match name.to_lowercase().as_str() {
+        "txt" => "text".to_string(),
+        "gv" => "dot".to_string(),
+        "jpeg" => "jpg".to_string(),
+        name => name.to_string(),
+    };
+    serde_json::from_value(Value::String(name.clone()))
+        .map_err(|_| format!("Unknown format: {}", name))
+}
+
+fn render(args: &RenderArgs) -> Result<(), String> {
+    let code = if args.input == "-" {
+        let mut code = String::new();
+        std::io::stdin()
+            .read_to_string(&mut code)
+            .map_err(|e| format!("Failed to read stdin: {}", e))?;
+        code
+    } else {
+        std::fs::read_to_string(&args.input)
+            .map_err(|e| format!("Failed to read {}: {}", args.input, e))?
+    };
+
+    // The format follows the output extension, and source formats go to stdout
+    let format = match (&args.format, &args.output) {
+        (Some(format), _) => format.clone(),
+        (None, Some(output)) => output
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(parse_format)
+            .transpose()?
+            .unwrap_or(DiagramFormat::Text),
+        (None, None) => DiagramFormat::Text,
+    };
+    let Some(output) = &args.output else {
+        let source = match format {
+            DiagramFormat::Text => render_text(&code, &args.text)?,
+            DiagramFormat::Dot => export_dot(&code)?,
+            DiagramFormat::Drawio => {
+                export_drawio(&code, &DiagramOptions::svg(args.theme.clone()))?
+            }
+            DiagramFormat::Excalidraw => {
+                export_excalidraw(&code, &DiagramOptions::svg(args.theme.clone()))?
+            }
+            _ => return Err(format!("--output is needed for {} output", format)),
+        };
+        print!("{}", source);
+        return Ok(());
+    };
+
+    let options = DiagramOptions {
+        format,
+        text: Some(args.text.clone()),
+        ..DiagramOptions::svg(args.theme.clone())
+    };
+    let options = match options.format {
+        DiagramFormat::Svg => options,
+        _ if options.format.is_source() => options,
+        _ => DiagramOptions {
+            background: "white".to_string(),
+            ..options
+        },
+    };
+    let result = RenderService::new().generate_diagram(&code, &options, Path::new(output));
+    match result.success {
+        true => Ok(()),
+        false => Err(result
+            .error_message
+            .unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = parse_args(&args("flow.mmd --ascii -w 60 -o out.png --theme dark")).unwrap();
+        assert_eq!(
+            parsed,
+            RenderArgs {
+                input: "flow.mmd".to_string(),
+                format: None,
+                output: Some(PathBuf::from("out.png")),
+                theme: Some("dark".to_string()),
+                text: TextOptions {
+                    ascii: true,
+                    max_width: Some(60),
+                },
+            }
+        );
+        assert_eq!(
+            parse_args(&args("- --format txt")).unwrap().format,
+            Some(DiagramFormat::Text)
+        );
+        assert_eq!(parse_format("gv"), Ok(DiagramFormat::Dot));
+        assert_eq!(parse_format("drawio"), Ok(DiagramFormat::Drawio));
+        assert_eq!(parse_format("excalidraw"), Ok(DiagramFormat::Excalidraw));
+
+        assert!(run(&args("--verbose")).is_none());
+        assert!(parse_args(&args("a.mmd b.mmd")).is_err());
+        assert!(parse_args(&args("a.mmd --width wide")).is_err());
+        assert!(parse_args(&args("a.mmd --format gif")).is_err());
+        assert!(parse_args(&args("--ascii")).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::commands::diagram::RenderServiceState;
+use crate::commands::file_ops::FileServiceState;
+use crate::models::{DiagramDiff, DiagramOptions, DiagramStats, GanttSchedule, StatsExportFormat};
+use crate::services::{diff_service, gantt_service, parse_diagram, stats_service, RenderService};
+use crate::utils::path::ensure_directory_exists;
+use tauri::{command, State};
+
+/// Diff two versions of a diagram, optionally rendering the highlighted combination
+pub(crate) fn build_diff(
+    old: &str,
+    new: &str,
+    render: bool,
+    theme: Option<String>,
+    service: &mut RenderService,
+) -> Result<DiagramDiff, String> {
+    let old_diagram =
+        parse_diagram(old).map_err(|e| format!("Failed to parse old version: {}", e))?;
+    let new_diagram =
+        parse_diagram(new).map_err(|e| format!("Failed to parse new version: {}", e))?;
+
+    let mut diff = diff_service::diff_diagrams(&old_diagram, &new_diagram);
+    if diff.has_changes() {
+        log::info!(
+            "Diagram diff: {} added, {} removed, {} changed",
+            diff.summary.added,
+            diff.summary.removed,
+            diff.summary.changed
+        );
+    } else {
+        log::info!("Diagram diff: no changes");
+    }
+
+    if render {
+        let source = diff_service::highlight_source(&old_diagram, &new_diagram)?;
+        log::debug!("Highlighted diff source:\n{}", source);
+        diff.highlighted_svg = Some(service.render_svg(&source, &DiagramOptions::svg(theme))?);
+    }
+
+    Ok(diff)
+}
+
+#[command]
+pub async fn diff_diagrams(
+    old: String,
+    new: String,
+    render: Option<bool>,
+    theme: Option<String>,
+    service: State<'_, RenderServiceState>,
+) -> Result<DiagramDiff, String> {
+    let mut service = service.lock().unwrap();
+    build_diff(&old, &new, render.unwrap_or(false), theme, &mut service)
+}
+
+#[command]
+pub async fn get_diagram_stats(path: String) -> Result<DiagramStats, String> {
+    log::info!("Computing diagram statistics: {}", path);
+    let stats = stats_service::file_stats(&path);
+    match stats.error {
+        Some(error) => Err(error),
+        None => Ok(stats),
+    }
+}
+
+#[command]
+pub async fn get_directory_stats(
+    directory_path: String,
+    recursive: bool,
+    export_format: Option<StatsExportFormat>,
+    output_path: Option<String>,
+    service: State<'_, FileServiceState>,
+) -> Result<Vec<DiagramStats>, String> {
+    log::info!("Computing statistics for directory: {}", directory_path);
+
+    let paths = service
+        .lock()
+        .unwrap()
+        .find_mermaid_files(&directory_path, recursive)?;
+    let stats: Vec<DiagramStats> = paths.iter().map(stats_service::file_stats).collect();
+
+    if let Some(output_path) = output_path {
+        let format = export_format.unwrap_or(StatsExportFormat::Json);
+        let content = stats_service::export_stats(&stats, format)?;
+        let output_path = std::path::PathBuf::from(output_path);
+        if let Some(parent) = output_path.parent() {
+            ensure_directory_exists(parent)
+                .map_err(|e| format!("Failed to ensure output directory exists: {}", e))?;
+        }
+        std::fs::write(&output_path, content)
+            .map_err(|e| format!("Failed to write statistics: {}", e))?;
+        log::info!("Statistics exported to {}", output_path.display());
+    }
+
+    Ok(stats)
+}
+
+/// Start and end dates of every task in a gantt chart
+#[command]
+pub async fn get_gantt_schedule(code: String) -> Result<GanttSchedule, String> {
+    let schedule = gantt_service::schedule(&code)?;
+    log::info!("Resolved gantt schedule of {} tasks", schedule.tasks.len());
+    Ok(schedule)
+}
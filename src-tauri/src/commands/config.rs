@@ -0,0 +1,48 @@
+use crate::models::{ConfigPreset, MermaidConfig, ResolvedConfig};
+use crate::services::config_service::{self, ConfigPresetStore};
+use std::path::Path;
+use tauri::command;
+
+/// Check a config, returning warnings for settings mermaid would ignore
+#[command]
+pub async fn validate_mermaid_config(config: MermaidConfig) -> Result<Vec<String>, String> {
+    Ok(config_service::validate_config(&config))
+}
+
+/// Merge the global config, the workspace's `mermaid.config.json` and the
+/// file's `%%{init}%%` directives
+#[command]
+pub async fn resolve_mermaid_config(
+    code: String,
+    global: Option<MermaidConfig>,
+    workspace_path: Option<String>,
+) -> Result<ResolvedConfig, String> {
+    let workspace = match workspace_path {
+        Some(ref path) => config_service::load_workspace_config(Path::new(path))?,
+        None => None,
+    };
+    config_service::resolve_config(global.as_ref(), workspace.as_ref(), &code)
+}
+
+#[command]
+pub async fn list_config_presets() -> Result<Vec<ConfigPreset>, String> {
+    ConfigPresetStore::default_location()?.list()
+}
+
+#[command]
+pub async fn load_config_preset(name: String) -> Result<ConfigPreset, String> {
+    ConfigPresetStore::default_location()?.load(&name)
+}
+
+#[command]
+pub async fn save_config_preset(preset: ConfigPreset) -> Result<String, String> {
+    log::info!("Saving config preset: {}", preset.name);
+    let path = ConfigPresetStore::default_location()?.save(&preset)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[command]
+pub async fn delete_config_preset(name: String) -> Result<(), String> {
+    log::info!("Deleting config preset: {}", name);
+    ConfigPresetStore::default_location()?.delete(&name)
+}
@@ -1,22 +1,65 @@
-use crate::models::{DiagramOptions, DiagramResult, DiagramFormat};
-use crate::services::MmdcService;
-use crate::utils::path::{generate_output_path, ensure_directory_exists};
+use crate::commands::file_ops::FileServiceState;
+use crate::models::{
+    DiagramFormat, DiagramOptions, DiagramResult, MermaidConfig, MmdcDiagnosis, MmdcSettings,
+    PdfDiagram, PdfDocumentOptions, PdfDocumentResult, PreviewOptions, SvgProcessOptions,
+    TextOptions,
+};
+use crate::services::dot_service::export_dot;
+use crate::services::drawio_service::export_drawio;
+use crate::services::svg_service::process_svg;
+use crate::services::text_service::render_text;
+use crate::services::theme_service::{self, ThemeStore};
+use crate::services::{parse_diagram, pdf_service, RenderService};
+use crate::utils::path::{ensure_directory_exists, generate_output_path};
+use std::path::PathBuf;
 use std::sync::Mutex;
+use std::time::Instant;
 use tauri::{command, State};
 
-pub type MmdcServiceState = Mutex<MmdcService>;
+pub type RenderServiceState = Mutex<RenderService>;
 
 #[command]
-pub async fn check_mmdc(service: State<'_, MmdcServiceState>) -> Result<String, String> {
+pub async fn check_mmdc(service: State<'_, RenderServiceState>) -> Result<String, String> {
     let mut service = service.lock().unwrap();
-    service.check_availability()
+    service.mmdc().check_availability()
+}
+
+/// Run every mmdc candidate and report why each one does or does not work
+#[command]
+pub async fn diagnose_mmdc(
+    service: State<'_, RenderServiceState>,
+) -> Result<MmdcDiagnosis, String> {
+    let mut service = service.lock().unwrap();
+    Ok(service.mmdc().diagnose())
+}
+
+#[command]
+pub async fn get_mmdc_settings(
+    service: State<'_, RenderServiceState>,
+) -> Result<MmdcSettings, String> {
+    let mut service = service.lock().unwrap();
+    Ok(service.mmdc().settings())
+}
+
+/// Save the mmdc settings and report the mmdc now in use
+#[command]
+pub async fn set_mmdc_settings(
+    settings: MmdcSettings,
+    service: State<'_, RenderServiceState>,
+) -> Result<String, String> {
+    log::info!("Updating mmdc settings: {:?}", settings);
+    let mut service = service.lock().unwrap();
+    if let Err(e) = service.mmdc().configure(settings) {
+        log::warn!("mmdc not found with the new settings: {}", e);
+    }
+    service.mmdc().check_availability()
 }
 
 #[command]
 pub async fn generate_diagram(
     code: String,
     options: DiagramOptions,
-    service: State<'_, MmdcServiceState>,
+    service: State<'_, RenderServiceState>,
 ) -> Result<DiagramResult, String> {
     log::info!("Generating diagram with format: {}", options.format);
     log::debug!("Code length: {}", code.len());
@@ -49,7 +92,7 @@ pub async fn generate_diagram_to_file(
     code: String,
     options: DiagramOptions,
     output_path: String,
-    service: State<'_, MmdcServiceState>,
+    service: State<'_, RenderServiceState>,
 ) -> Result<DiagramResult, String> {
     log::info!("Generating diagram to custom path: {}", output_path);
     log::debug!("Code length: {}", code.len());
@@ -73,55 +116,208 @@ pub async fn generate_diagram_to_file(
     Ok(service.generate_diagram(&code, &options, &output_path))
 }
 
+#[command]
+pub async fn generate_diagram_formats(
+    code: String,
+    options: DiagramOptions,
+    formats: Vec<DiagramFormat>,
+    output_directory: String,
+    file_name: String,
+    service: State<'_, RenderServiceState>,
+) -> Result<Vec<DiagramResult>, String> {
+    log::info!(
+        "Generating {} formats into: {}",
+        formats.len(),
+        output_directory
+    );
+
+    if code.trim().is_empty() {
+        return Ok(vec![DiagramResult::error(
+            "Empty mermaid code provided".to_string(),
+            0,
+        )]);
+    }
+
+    let output_dir = std::path::PathBuf::from(output_directory);
+    ensure_directory_exists(&output_dir)
+        .map_err(|e| format!("Failed to ensure output directory exists: {}", e))?;
+
+    let outputs: Vec<(DiagramFormat, std::path::PathBuf)> = formats
+        .into_iter()
+        .map(|format| {
+            let path = output_dir.join(format!("{}.{}", file_name, format));
+            (format, path)
+        })
+        .collect();
+
+    let mut service = service.lock().unwrap();
+    Ok(service.generate_formats(&code, &options, &outputs))
+}
+
+/// Render the editor preview. The SVG is sized to the diagram, scaled by
+/// `options.scale`, with the theme's background on the root element.
 #[command]
 pub async fn generate_preview_svg(
     code: String,
-    theme: Option<String>,
-    service: State<'_, MmdcServiceState>,
+    options: Option<PreviewOptions>,
+    service: State<'_, RenderServiceState>,
 ) -> Result<String, String> {
     if code.trim().is_empty() {
         return Err("Empty mermaid code provided".to_string());
     }
-    
-    let mut service = service.lock().unwrap();
-    
-    let background = match theme.as_deref() {
-        Some("dark") => "#0f0f23",
-        Some("light") => "#ffffff",
-        _ => "transparent"
-    };
 
+    let preview = options.unwrap_or_default();
     let options = DiagramOptions {
-        format: DiagramFormat::Svg,
-        width: Some(800),
-        height: Some(600),
-        background: background.to_string(),
-        theme: theme.clone(),
+        width: preview.viewport_width,
+        height: preview.viewport_height,
+        background: preview.background.clone().unwrap_or_default(),
+        mermaid_config: preview
+            .font_family
+            .clone()
+            .map(|font_family| MermaidConfig {
+                font_family: Some(font_family),
+                ..MermaidConfig::default()
+            }),
+        ..DiagramOptions::svg(None)
     };
-    
-    let output_dir = std::env::temp_dir();
-    let output_path = output_dir.join(format!("preview_{}.svg", 
-        std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
+
+    // `theme` names a theme pack such as the built-in "light" and "dark";
+    // anything else is passed to mermaid as a theme name
+    let pack = preview.theme.as_deref().and_then(|id| {
+        ThemeStore::default_location()
+            .and_then(|store| store.get(id))
+            .ok()
+    });
+    let mut options = match pack {
+        Some(pack) => theme_service::apply_theme(&pack, &options)?,
+        None => DiagramOptions {
+            theme: preview.theme.clone(),
+            ..options
+        },
+    };
+    if options.background.trim().is_empty() {
+        options.background = "transparent".to_string();
+    }
+
+    options.svg_processing = Some(SvgProcessOptions {
+        background: Some(options.background.clone()),
+        fit_content: true,
+        scale: preview.scale,
+        ..SvgProcessOptions::default()
+    });
+
+    let mut service = service.lock().unwrap();
+    service.render_svg(&code, &options)
+}
+
+#[command]
+pub async fn postprocess_svg(
+    svg: String,
+    code: String,
+    options: Option<SvgProcessOptions>,
+) -> Result<String, String> {
+    process_svg(&svg, &code, &options.unwrap_or_default())
+}
+
+/// Render a flowchart or sequence diagram as text art, e.g. for pasting
+/// into a code comment
+#[command]
+pub async fn render_text_diagram(
+    code: String,
+    options: Option<TextOptions>,
+) -> Result<String, String> {
+    render_text(&code, &options.unwrap_or_default())
+}
+
+/// Export a flowchart as Graphviz DOT source
+#[command]
+pub async fn export_dot_diagram(code: String) -> Result<String, String> {
+    export_dot(&code)
+}
+
+/// Export a flowchart as a draw.io file, laid out by the native renderer
+#[command]
+pub async fn export_drawio_diagram(
+    code: String,
+    options: Option<DiagramOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_else(|| DiagramOptions::svg(None));
+    export_drawio(&code, &theme_service::resolve_theme_pack(&options)?)
+}
+
+/// Export several diagrams into one PDF, one diagram per page. Diagrams come
+/// from the given paths followed by the mermaid files found in `directory_path`.
+#[command]
+pub async fn generate_pdf_document(
+    paths: Vec<String>,
+    directory_path: Option<String>,
+    recursive: Option<bool>,
+    output_path: String,
+    options: PdfDocumentOptions,
+    service: State<'_, RenderServiceState>,
+    file_service: State<'_, FileServiceState>,
+) -> Result<PdfDocumentResult, String> {
+    let start_time = Instant::now();
+
+    let mut files: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+    if let Some(directory) = directory_path {
+        let mut found = file_service
+            .lock()
             .unwrap()
-            .as_millis()
-    ));
-    
-    let result = service.generate_diagram(&code, &options, &output_path);
-    
-    if result.success {
-        match std::fs::read_to_string(&output_path) {
-            Ok(mut svg_content) => {
-                if theme.as_deref() == Some("dark") {
-                    svg_content = svg_content.replace("<svg", &format!("<svg style=\"background-color: {}\"", background));
+            .find_mermaid_files(&directory, recursive.unwrap_or(false))?;
+        found.sort();
+        files.extend(found);
+    }
+
+    if files.is_empty() {
+        return Err("No diagrams selected".to_string());
+    }
+    log::info!("Exporting {} diagrams to PDF: {}", files.len(), output_path);
+
+    // The page supplies the background, so diagrams render transparent
+    let render_options = DiagramOptions::svg(options.theme.clone());
+    let diagrams: Vec<PdfDiagram> = {
+        let mut service = service.lock().unwrap();
+        files
+            .iter()
+            .map(|path| {
+                let code = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read file: {}", e));
+                let title = code
+                    .as_ref()
+                    .ok()
+                    .and_then(|code| parse_diagram(code).ok())
+                    .and_then(|diagram| diagram.title)
+                    .unwrap_or_else(|| {
+                        path.file_stem()
+                            .map(|stem| stem.to_string_lossy().to_string())
+                            .unwrap_or_default()
+                    });
+
+                PdfDiagram {
+                    path: path.to_string_lossy().to_string(),
+                    title,
+                    svg: code.and_then(|code| service.render_raster_svg(&code, &render_options)),
                 }
+            })
+            .collect()
+    };
 
-                let _ = std::fs::remove_file(&output_path);
-                Ok(svg_content)
-            }
-            Err(e) => Err(format!("Failed to read SVG: {}", e))
-        }
-    } else {
-        Err(result.error_message.unwrap_or("Unknown error".to_string()))
+    let (content, entries) = pdf_service::build_pdf_document(&diagrams, &options)?;
+
+    let output_path = PathBuf::from(output_path);
+    if let Some(parent) = output_path.parent() {
+        ensure_directory_exists(parent)
+            .map_err(|e| format!("Failed to ensure output directory exists: {}", e))?;
     }
-}
\ No newline at end of file
+    std::fs::write(&output_path, &content)
+        .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))?;
+
+    Ok(PdfDocumentResult {
+        output_path: output_path.to_string_lossy().to_string(),
+        page_count: entries.iter().filter_map(|e| e.page).max().unwrap_or(0),
+        entries,
+        file_size: content.len() as u64,
+        generation_time: start_time.elapsed().as_millis() as u64,
+    })
+}
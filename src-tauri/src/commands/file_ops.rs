@@ -1,5 +1,12 @@
-use crate::models::{FileOperationResult, MermaidFile, RecentFile};
+use crate::models::{
+    FileOperationResult, ImportedDiagram, MermaidFile, RecentFile, SqlImportOptions,
+};
+use crate::services::dot_service::import_dot;
+use crate::services::drawio_service::import_drawio;
+use crate::services::plantuml_service::import_plantuml;
+use crate::services::sql_service::import_sql;
 use crate::services::FileService;
+use std::path::Path;
 use std::sync::Mutex;
 use tauri::{command, State};
 
@@ -82,4 +89,96 @@ pub async fn find_mermaid_files_in_directory(
     
     log::info!("Found {} mermaid files", path_strings.len());
     Ok(path_strings)
-}
\ No newline at end of file
+}
+
+/// Convert PlantUML source to Mermaid, e.g. to open it as a new buffer
+#[command]
+pub async fn convert_plantuml(code: String) -> Result<ImportedDiagram, String> {
+    let imported = import_plantuml(&code)?;
+    log::info!(
+        "Converted PlantUML diagram ({} constructs skipped)",
+        imported.skipped.len()
+    );
+    Ok(imported)
+}
+
+/// Convert a Graphviz graph to a Mermaid flowchart
+#[command]
+pub async fn convert_dot(code: String) -> Result<ImportedDiagram, String> {
+    let imported = import_dot(&code)?;
+    log::info!(
+        "Converted DOT graph ({} constructs skipped)",
+        imported.skipped.len()
+    );
+    Ok(imported)
+}
+
+/// Convert a draw.io diagram to a Mermaid flowchart
+#[command]
+pub async fn convert_drawio(code: String) -> Result<ImportedDiagram, String> {
+    let imported = import_drawio(&code)?;
+    log::info!(
+        "Converted draw.io diagram ({} constructs skipped)",
+        imported.skipped.len()
+    );
+    Ok(imported)
+}
+
+/// Convert the tables of a SQL schema to a Mermaid entity relationship diagram
+#[command]
+pub async fn convert_sql(
+    code: String,
+    options: Option<SqlImportOptions>,
+) -> Result<ImportedDiagram, String> {
+    let imported = import_sql(&code, &options.unwrap_or_default())?;
+    log::info!(
+        "Converted SQL schema ({} constructs skipped)",
+        imported.skipped.len()
+    );
+    Ok(imported)
+}
+
+/// Read a diagram file and convert it to Mermaid, e.g. to open it as a new
+/// buffer
+#[command]
+pub async fn import_diagram_file(
+    path: String,
+    options: Option<SqlImportOptions>,
+    service: State<'_, FileServiceState>,
+) -> Result<ImportedDiagram, String> {
+    log::info!("Importing diagram file: {}", path);
+    let service = service.lock().unwrap();
+    let imported = service.import_file(&path, &options.unwrap_or_default())?;
+    log::info!(
+        "Imported {} ({} constructs skipped)",
+        path,
+        imported.skipped.len()
+    );
+    Ok(imported)
+}
+
+#[command]
+pub async fn import_plantuml_directory(
+    directory_path: String,
+    recursive: bool,
+    output_directory: Option<String>,
+    overwrite: Option<bool>,
+    service: State<'_, FileServiceState>,
+) -> Result<Vec<ImportedDiagram>, String> {
+    log::info!(
+        "Importing PlantUML files from: {} (recursive: {})",
+        directory_path,
+        recursive
+    );
+
+    let service = service.lock().unwrap();
+    let results = service.import_plantuml_directory(
+        &directory_path,
+        recursive,
+        output_directory.as_deref().map(Path::new),
+        overwrite.unwrap_or(false),
+    )?;
+
+    log::info!("Imported {} PlantUML files", results.len());
+    Ok(results)
+}
@@ -0,0 +1,88 @@
+use crate::commands::analysis::build_diff;
+use crate::commands::diagram::RenderServiceState;
+use crate::models::{CommitInfo, DiagramOptions, FileRevision, RevisionComparison, RevisionView};
+use crate::services::GitService;
+use tauri::{command, State};
+
+/// Load a file from git, or from disk when no revision is given. The working
+/// copy is read directly, since viewing history should not add the file to
+/// the recent files.
+fn load_revision(path: &str, revision: Option<String>) -> Result<FileRevision, String> {
+    let content = match revision {
+        Some(ref revision) => GitService::new().read_file_at_revision(path, revision)?,
+        None => {
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?
+        }
+    };
+
+    Ok(FileRevision {
+        path: path.to_string(),
+        revision,
+        content,
+    })
+}
+
+#[command]
+pub async fn list_file_history(
+    path: String,
+    limit: Option<usize>,
+) -> Result<Vec<CommitInfo>, String> {
+    log::info!("Listing git history for: {}", path);
+    let history = GitService::new().file_history(&path, limit)?;
+    log::info!("Found {} commits", history.len());
+    Ok(history)
+}
+
+#[command]
+pub async fn load_file_revision(
+    path: String,
+    revision: Option<String>,
+) -> Result<FileRevision, String> {
+    log::info!(
+        "Loading {} at {}",
+        path,
+        revision.as_deref().unwrap_or("working copy")
+    );
+    load_revision(&path, revision)
+}
+
+#[command]
+pub async fn compare_file_revisions(
+    path: String,
+    old_revision: Option<String>,
+    new_revision: Option<String>,
+    view: RevisionView,
+    theme: Option<String>,
+    service: State<'_, RenderServiceState>,
+) -> Result<RevisionComparison, String> {
+    let old = load_revision(&path, old_revision)?;
+    let new = load_revision(&path, new_revision)?;
+
+    let mut service = service.lock().unwrap();
+    let mut comparison = RevisionComparison {
+        old,
+        new,
+        old_svg: None,
+        new_svg: None,
+        diff: None,
+    };
+
+    match view {
+        RevisionView::SideBySide => {
+            let options = DiagramOptions::svg(theme);
+            comparison.old_svg = Some(service.render_svg(&comparison.old.content, &options)?);
+            comparison.new_svg = Some(service.render_svg(&comparison.new.content, &options)?);
+        }
+        RevisionView::Diff => {
+            comparison.diff = Some(build_diff(
+                &comparison.old.content,
+                &comparison.new.content,
+                true,
+                theme,
+                &mut service,
+            )?);
+        }
+    }
+
+    Ok(comparison)
+}
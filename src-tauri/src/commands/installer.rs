@@ -0,0 +1,64 @@
+use crate::commands::diagram::RenderServiceState;
+use crate::models::{InstallSource, ManagedInstall};
+use crate::services::installer_service::MermaidCliInstaller;
+use tauri::{command, AppHandle, Emitter, State};
+
+/// Event carrying `InstallProgress` updates during `install_mmdc`
+pub const INSTALL_PROGRESS_EVENT: &str = "mmdc-install-progress";
+
+#[command]
+pub async fn list_mmdc_installs() -> Result<Vec<ManagedInstall>, String> {
+    MermaidCliInstaller::default_location()?.list()
+}
+
+/// Install mermaid-cli into the app data directory and start using it
+#[command]
+pub async fn install_mmdc(
+    source: InstallSource,
+    app: AppHandle,
+    service: State<'_, RenderServiceState>,
+) -> Result<ManagedInstall, String> {
+    log::info!("Installing mermaid-cli from {:?}", source);
+    let install = MermaidCliInstaller::default_location()?.install(&source, |progress| {
+        log::debug!("Install progress: {}", progress.message);
+        if let Err(e) = app.emit(INSTALL_PROGRESS_EVENT, progress) {
+            log::warn!("Failed to send install progress: {}", e);
+        }
+    })?;
+
+    let mut service = service.lock().unwrap();
+    service.mmdc().rediscover()?;
+    Ok(install)
+}
+
+#[command]
+pub async fn verify_mmdc_install(version: String) -> Result<String, String> {
+    MermaidCliInstaller::default_location()?.verify(&version)
+}
+
+#[command]
+pub async fn set_active_mmdc_install(
+    version: String,
+    service: State<'_, RenderServiceState>,
+) -> Result<ManagedInstall, String> {
+    let install = MermaidCliInstaller::default_location()?.set_active(&version)?;
+    let mut service = service.lock().unwrap();
+    service.mmdc().rediscover()?;
+    Ok(install)
+}
+
+#[command]
+pub async fn uninstall_mmdc(
+    version: String,
+    service: State<'_, RenderServiceState>,
+) -> Result<(), String> {
+    log::info!("Uninstalling mermaid-cli {}", version);
+    MermaidCliInstaller::default_location()?.uninstall(&version)?;
+
+    // Another install may take over, or none is left
+    let mut service = service.lock().unwrap();
+    if let Err(e) = service.mmdc().rediscover() {
+        log::warn!("No mmdc available after uninstall: {}", e);
+    }
+    Ok(())
+}
@@ -1,14 +1,43 @@
+pub mod analysis;
+pub mod config;
 pub mod diagram;
 pub mod file_ops;
+pub mod history;
+pub mod installer;
+pub mod renderer;
 pub mod system;
+pub mod theme;
+pub mod worker;
 
 // Re-export specific items we actually use
-pub use diagram::{check_mmdc, generate_diagram, generate_diagram_to_file, MmdcServiceState, generate_preview_svg};
+pub use analysis::{diff_diagrams, get_diagram_stats, get_directory_stats, get_gantt_schedule};
+pub use config::{
+    delete_config_preset, list_config_presets, load_config_preset, resolve_mermaid_config,
+    save_config_preset, validate_mermaid_config,
+};
+pub use diagram::{
+    check_mmdc, diagnose_mmdc, export_dot_diagram, export_drawio_diagram, generate_diagram,
+    generate_diagram_formats, generate_diagram_to_file, generate_pdf_document,
+    generate_preview_svg, get_mmdc_settings, postprocess_svg, render_text_diagram,
+    set_mmdc_settings, RenderServiceState,
+};
 pub use file_ops::{
-    clear_recent_files, find_mermaid_files_in_directory, get_file_info, get_recent_files,
-    read_mermaid_file, validate_file_path, write_mermaid_file, FileServiceState,
+    clear_recent_files, convert_dot, convert_drawio, convert_plantuml, convert_sql,
+    find_mermaid_files_in_directory, get_file_info, get_recent_files, import_diagram_file,
+    import_plantuml_directory, read_mermaid_file, validate_file_path, write_mermaid_file,
+    FileServiceState,
+};
+pub use history::{compare_file_revisions, list_file_history, load_file_revision};
+pub use installer::{
+    install_mmdc, list_mmdc_installs, set_active_mmdc_install, uninstall_mmdc, verify_mmdc_install,
 };
+pub use renderer::{get_renderer_settings, list_renderers, set_renderer_settings};
 pub use system::{
-    get_default_directories, get_environment_variable, get_system_info, greet, 
+    get_default_directories, get_environment_variable, get_system_info, greet,
     list_environment_variables, open_file_location,
-};
\ No newline at end of file
+};
+pub use theme::{
+    apply_theme_pack, export_theme_pack, install_theme_pack, list_theme_packs, remove_theme_pack,
+    render_theme_previews,
+};
+pub use worker::{configure_render_worker, get_render_worker_status, restart_render_worker};
@@ -0,0 +1,31 @@
+use crate::commands::diagram::RenderServiceState;
+use crate::models::{RendererInfo, RendererSettings};
+use tauri::{command, State};
+
+/// Every renderer with its capabilities and whether it can be used now
+#[command]
+pub async fn list_renderers(
+    service: State<'_, RenderServiceState>,
+) -> Result<Vec<RendererInfo>, String> {
+    let mut service = service.lock().unwrap();
+    Ok(service.renderers())
+}
+
+#[command]
+pub async fn get_renderer_settings(
+    service: State<'_, RenderServiceState>,
+) -> Result<RendererSettings, String> {
+    let service = service.lock().unwrap();
+    Ok(service.settings().clone())
+}
+
+#[command]
+pub async fn set_renderer_settings(
+    settings: RendererSettings,
+    service: State<'_, RenderServiceState>,
+) -> Result<Vec<RendererInfo>, String> {
+    log::info!("Updating renderer settings: {:?}", settings);
+    let mut service = service.lock().unwrap();
+    service.configure(settings)?;
+    Ok(service.renderers())
+}
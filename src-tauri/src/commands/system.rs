@@ -103,4 +103,4 @@ pub struct DefaultDirectoriesInfo {
 pub struct EnvVar {
     pub key: String,
     pub value: String,
-}
\ No newline at end of file
+}
@@ -0,0 +1,56 @@
+use crate::commands::diagram::RenderServiceState;
+use crate::models::{DiagramOptions, ThemePack, ThemePreview};
+use crate::services::theme_service::{self, ThemeStore};
+use std::path::Path;
+use tauri::{command, State};
+
+#[command]
+pub async fn list_theme_packs() -> Result<Vec<ThemePack>, String> {
+    ThemeStore::default_location()?.list()
+}
+
+/// Install a theme pack from a folder or `.zip` file
+#[command]
+pub async fn install_theme_pack(source_path: String) -> Result<ThemePack, String> {
+    log::info!("Installing theme pack from: {}", source_path);
+    let pack = ThemeStore::default_location()?.install(Path::new(&source_path))?;
+    log::info!("Installed theme pack: {}", pack.manifest.name);
+    Ok(pack)
+}
+
+#[command]
+pub async fn remove_theme_pack(id: String) -> Result<(), String> {
+    log::info!("Removing theme pack: {}", id);
+    ThemeStore::default_location()?.remove(&id)
+}
+
+#[command]
+pub async fn export_theme_pack(id: String, output_path: String) -> Result<String, String> {
+    log::info!("Exporting theme pack {} to {}", id, output_path);
+    ThemeStore::default_location()?.export(&id, Path::new(&output_path))?;
+    Ok(output_path)
+}
+
+/// Return the options with a theme pack's config, CSS and background applied
+#[command]
+pub async fn apply_theme_pack(
+    id: String,
+    options: DiagramOptions,
+) -> Result<DiagramOptions, String> {
+    let pack = ThemeStore::default_location()?.get(&id)?;
+    theme_service::apply_theme(&pack, &options)
+}
+
+/// Render a sample diagram with every available theme
+#[command]
+pub async fn render_theme_previews(
+    code: Option<String>,
+    service: State<'_, RenderServiceState>,
+) -> Result<Vec<ThemePreview>, String> {
+    let packs = ThemeStore::default_location()?.list()?;
+    let code = code.unwrap_or_else(|| theme_service::PREVIEW_SAMPLE.to_string());
+    log::info!("Rendering previews for {} themes", packs.len());
+
+    let mut service = service.lock().unwrap();
+    Ok(theme_service::render_previews(&packs, &code, &mut service))
+}
@@ -0,0 +1,33 @@
+use crate::commands::diagram::RenderServiceState;
+use crate::models::{RenderWorkerSettings, RenderWorkerStatus};
+use tauri::{command, State};
+
+#[command]
+pub async fn get_render_worker_status(
+    service: State<'_, RenderServiceState>,
+) -> Result<RenderWorkerStatus, String> {
+    let mut service = service.lock().unwrap();
+    Ok(service.worker_status())
+}
+
+/// Apply and save new worker settings; a running worker is stopped and
+/// restarted on the next render
+#[command]
+pub async fn configure_render_worker(
+    settings: RenderWorkerSettings,
+    service: State<'_, RenderServiceState>,
+) -> Result<RenderWorkerStatus, String> {
+    log::info!("Configuring render worker: {:?}", settings);
+    let mut service = service.lock().unwrap();
+    service.configure_worker(settings)?;
+    Ok(service.worker_status())
+}
+
+#[command]
+pub async fn restart_render_worker(
+    service: State<'_, RenderServiceState>,
+) -> Result<RenderWorkerStatus, String> {
+    log::info!("Restarting render worker");
+    let mut service = service.lock().unwrap();
+    service.restart_worker()
+}
@@ -1,33 +1,48 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod cli;
 mod commands;
 mod models;
 mod services;
 mod utils;
 
 use commands::{
-    check_mmdc, generate_diagram, generate_diagram_to_file, generate_preview_svg, MmdcServiceState,
-    clear_recent_files, find_mermaid_files_in_directory, get_file_info, get_recent_files,
-    read_mermaid_file, validate_file_path, write_mermaid_file, FileServiceState,
-    get_default_directories, get_environment_variable, get_system_info, greet, 
-    list_environment_variables, open_file_location,
+    apply_theme_pack, check_mmdc, clear_recent_files, compare_file_revisions,
+    configure_render_worker, convert_dot, convert_drawio, convert_plantuml, convert_sql,
+    delete_config_preset, diagnose_mmdc, diff_diagrams, export_dot_diagram, export_drawio_diagram,
+    export_theme_pack, find_mermaid_files_in_directory, generate_diagram, generate_diagram_formats,
+    generate_diagram_to_file, generate_pdf_document, generate_preview_svg, get_default_directories,
+    get_diagram_stats, get_directory_stats, get_environment_variable, get_file_info,
+    get_gantt_schedule, get_mmdc_settings, get_recent_files, get_render_worker_status,
+    get_renderer_settings, get_system_info, greet, import_diagram_file, import_plantuml_directory,
+    install_mmdc, install_theme_pack, list_config_presets, list_environment_variables,
+    list_file_history, list_mmdc_installs, list_renderers, list_theme_packs, load_config_preset,
+    load_file_revision, open_file_location, postprocess_svg, read_mermaid_file, remove_theme_pack,
+    render_text_diagram, render_theme_previews, resolve_mermaid_config, restart_render_worker,
+    save_config_preset, set_active_mmdc_install, set_mmdc_settings, set_renderer_settings,
+    uninstall_mmdc, validate_file_path, validate_mermaid_config, verify_mmdc_install,
+    write_mermaid_file, FileServiceState, RenderServiceState,
 };
-use services::{FileService, MmdcService};
+use services::{FileService, RenderService};
 use std::env;
 
 fn main() {
     env_logger::init();
+
+    if let Some(code) = cli::run(&env::args().skip(1).collect::<Vec<_>>()) {
+        std::process::exit(code);
+    }
     
     log::info!("Starting Mermaid GUI v2.0...");
     log::info!("Platform: {} {}", env::consts::OS, env::consts::ARCH);
-    
-    let mmdc_service = MmdcService::new();
+
+    let render_service = RenderService::new();
     let file_service = FileService::new();
     
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
-        .manage(MmdcServiceState::new(mmdc_service))
+        .manage(RenderServiceState::new(render_service))
         .manage(FileServiceState::new(file_service))
         .invoke_handler(tauri::generate_handler![
             // System commands
@@ -39,9 +54,51 @@ fn main() {
             open_file_location,
             // Diagram commands
             check_mmdc,
+            diagnose_mmdc,
+            get_mmdc_settings,
+            set_mmdc_settings,
             generate_diagram,
             generate_diagram_to_file,
+            generate_diagram_formats,
             generate_preview_svg,
+            postprocess_svg,
+            generate_pdf_document,
+            render_text_diagram,
+            export_dot_diagram,
+            export_drawio_diagram,
+            // Managed mermaid-cli commands
+            list_mmdc_installs,
+            install_mmdc,
+            verify_mmdc_install,
+            set_active_mmdc_install,
+            uninstall_mmdc,
+            // Config commands
+            validate_mermaid_config,
+            resolve_mermaid_config,
+            list_config_presets,
+            load_config_preset,
+            save_config_preset,
+            delete_config_preset,
+            // Theme commands
+            list_theme_packs,
+            install_theme_pack,
+            remove_theme_pack,
+            export_theme_pack,
+            apply_theme_pack,
+            render_theme_previews,
+            // Render worker commands
+            get_render_worker_status,
+            configure_render_worker,
+            restart_render_worker,
+            // Renderer commands
+            list_renderers,
+            get_renderer_settings,
+            set_renderer_settings,
+            // Analysis commands
+            diff_diagrams,
+            get_diagram_stats,
+            get_directory_stats,
+            get_gantt_schedule,
             // File operation commands
             read_mermaid_file,
             write_mermaid_file,
@@ -50,7 +107,17 @@ fn main() {
             validate_file_path,
             get_file_info,
             find_mermaid_files_in_directory,
+            convert_plantuml,
+            convert_dot,
+            convert_drawio,
+            convert_sql,
+            import_diagram_file,
+            import_plantuml_directory,
+            // History commands
+            list_file_history,
+            load_file_revision,
+            compare_file_revisions,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
-}
\ No newline at end of file
+}
@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Built-in mermaid themes
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MermaidTheme {
+    Default,
+    Base,
+    Dark,
+    Forest,
+    Neutral,
+    Null,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SecurityLevel {
+    Strict,
+    Loose,
+    Antiscript,
+    Sandbox,
+}
+
+/// d3 curve used for flowchart edges
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FlowchartCurve {
+    Basis,
+    BumpX,
+    BumpY,
+    Cardinal,
+    CatmullRom,
+    Linear,
+    MonotoneX,
+    MonotoneY,
+    Natural,
+    Step,
+    StepAfter,
+    StepBefore,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// Mermaid configuration as passed to `mermaid.initialize` or an
+/// `%%{init: ...}%%` directive.
+///
+/// Only commonly used settings are typed; any other keys are kept in `extra`
+/// so a config survives a round trip unchanged.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MermaidConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<MermaidTheme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme_variables: Option<ThemeVariables>,
+    /// Extra CSS injected into the diagram's style element
+    #[serde(rename = "themeCSS", skip_serializing_if = "Option::is_none")]
+    pub theme_css: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security_level: Option<SecurityLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html_labels: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flowchart: Option<FlowchartConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<SequenceConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gantt: Option<GanttConfig>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Variables for the `base` theme. Colours are CSS colour strings.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeVariables {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dark_mode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+    /// CSS length such as `16px`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_text_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_border_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secondary_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tertiary_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note_bkg_color: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FlowchartConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub curve: Option<FlowchartCurve>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html_labels: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_spacing: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank_spacing: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diagram_padding: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_max_width: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_renderer: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirror_actors: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_sequence_numbers: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor_margin: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_align: Option<MessageAlign>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrap: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_max_width: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GanttConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bar_height: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bar_gap: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f64>,
+    /// d3 time format for axis labels, e.g. `%Y-%m-%d`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub axis_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_axis: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_max_width: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Where a layer of configuration came from, lowest precedence first
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    Global,
+    Workspace,
+    File,
+}
+
+/// A named configuration saved in the app data directory
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConfigPreset {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub config: MermaidConfig,
+}
+
+/// Result of merging the configuration layers that apply to a file
+#[derive(Debug, Serialize, Clone)]
+pub struct ResolvedConfig {
+    pub config: MermaidConfig,
+    /// Layers that were present, in the order they were applied
+    pub sources: Vec<ConfigSource>,
+    /// Problems found by validation; rendering still works but may differ
+    pub warnings: Vec<String>,
+}
@@ -1,21 +1,131 @@
+use super::{MermaidConfig, RendererBackend, SvgProcessOptions};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DiagramOptions {
     pub format: DiagramFormat,
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub background: String,
     pub theme: Option<String>,
+    /// Raster scale factor, where 1.0 is 96 DPI
+    pub scale: Option<f32>,
+    /// Lossy quality from 1 to 100 for JPEG and AVIF; WebP is lossless only
+    /// and rejects it
+    pub quality: Option<u8>,
+    /// Lossless encoding for AVIF; WebP is always lossless and rejects `false`
+    pub lossless: Option<bool>,
+    /// TIFF compression method
+    pub compression: Option<TiffCompression>,
+    /// Colour space for TIFF and EPS output
+    pub color_space: Option<ColorSpace>,
+    /// Post-processing for SVG and HTML output
+    pub svg_processing: Option<SvgProcessOptions>,
+    /// Size the PDF page to the diagram (`--pdfFit`)
+    pub pdf_fit: Option<bool>,
+    /// Mermaid config file (`--configFile`)
+    pub config_file: Option<String>,
+    /// Mermaid config merged over `config_file`
+    pub mermaid_config: Option<MermaidConfig>,
+    /// Stylesheet for the rendered diagram (`--cssFile`)
+    pub css_file: Option<String>,
+    /// CSS appended after `css_file`
+    pub css: Option<String>,
+    /// Puppeteer launch options file (`--puppeteerConfigFile`)
+    pub puppeteer_config_file: Option<String>,
+    /// Puppeteer options merged over `puppeteer_config_file`,
+    /// e.g. `{"args": ["--no-sandbox"]}` in containers
+    pub puppeteer_config: Option<serde_json::Value>,
+    /// Id of the root `<svg>` element (`--svgId`)
+    pub svg_id: Option<String>,
+    /// Installed or built-in theme pack applied before rendering
+    pub theme_pack: Option<String>,
+    /// Render with this backend only instead of the configured chain
+    pub renderer: Option<RendererBackend>,
+    /// Character set and width limit for text output
+    pub text: Option<TextOptions>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+impl DiagramOptions {
+    /// Options for rendering an SVG at its natural size
+    pub fn svg(theme: Option<String>) -> Self {
+        Self {
+            format: DiagramFormat::Svg,
+            width: None,
+            height: None,
+            background: "transparent".to_string(),
+            theme,
+            scale: None,
+            quality: None,
+            lossless: None,
+            compression: None,
+            color_space: None,
+            svg_processing: None,
+            pdf_fit: None,
+            config_file: None,
+            mermaid_config: None,
+            css_file: None,
+            css: None,
+            puppeteer_config_file: None,
+            puppeteer_config: None,
+            svg_id: None,
+            theme_pack: None,
+            renderer: None,
+            text: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum DiagramFormat {
     Png,
     Svg,
     Pdf,
     Jpg,
+    Webp,
+    Avif,
+    Tiff,
+    Eps,
+    Html,
+    /// Unicode or ASCII box-drawing art, rendered without mermaid
+    Text,
+    /// Graphviz source for flowcharts, rendered without mermaid
+    Dot,
+    /// draw.io XML for flowcharts, laid out by the native renderer
+    Drawio,
+    /// Excalidraw scene for flowcharts and sequence diagrams
+    Excalidraw,
+}
+
+impl DiagramFormat {
+    /// Formats produced by rasterizing the SVG output in-process
+    pub fn is_raster(&self) -> bool {
+        matches!(
+            self,
+            DiagramFormat::Png
+                | DiagramFormat::Jpg
+                | DiagramFormat::Webp
+                | DiagramFormat::Avif
+                | DiagramFormat::Tiff
+        )
+    }
+
+    /// Formats written from the parsed source, without any renderer
+    pub fn is_source(&self) -> bool {
+        matches!(
+            self,
+            DiagramFormat::Text
+                | DiagramFormat::Dot
+                | DiagramFormat::Drawio
+                | DiagramFormat::Excalidraw
+        )
+    }
+
+    /// Formats converted in-process from the SVG that mmdc renders
+    pub fn is_derived_from_svg(&self) -> bool {
+        self.is_raster() || matches!(self, DiagramFormat::Eps | DiagramFormat::Html)
+    }
 }
 
 impl std::fmt::Display for DiagramFormat {
@@ -25,10 +135,46 @@ impl std::fmt::Display for DiagramFormat {
             DiagramFormat::Svg => write!(f, "svg"),
             DiagramFormat::Pdf => write!(f, "pdf"),
             DiagramFormat::Jpg => write!(f, "jpg"),
+            DiagramFormat::Webp => write!(f, "webp"),
+            DiagramFormat::Avif => write!(f, "avif"),
+            DiagramFormat::Tiff => write!(f, "tiff"),
+            DiagramFormat::Eps => write!(f, "eps"),
+            DiagramFormat::Html => write!(f, "html"),
+            // Also the file extension of exports
+            DiagramFormat::Text => write!(f, "txt"),
+            DiagramFormat::Dot => write!(f, "dot"),
+            DiagramFormat::Drawio => write!(f, "drawio"),
+            DiagramFormat::Excalidraw => write!(f, "excalidraw"),
         }
     }
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TiffCompression {
+    None,
+    Lzw,
+    Deflate,
+    Packbits,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    Rgb,
+    Gray,
+    Cmyk,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct TextOptions {
+    /// Plain ASCII instead of Unicode box-drawing characters
+    #[serde(default)]
+    pub ascii: bool,
+    /// Longest allowed line, in characters
+    pub max_width: Option<usize>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct DiagramResult {
     pub success: bool,
@@ -36,6 +182,8 @@ pub struct DiagramResult {
     pub error_message: Option<String>,
     pub generation_time: u64,
     pub file_size: Option<u64>,
+    /// Backend that rendered the diagram
+    pub renderer: Option<RendererBackend>,
 }
 
 impl DiagramResult {
@@ -46,6 +194,7 @@ impl DiagramResult {
             error_message: None,
             generation_time,
             file_size,
+            renderer: None,
         }
     }
 
@@ -56,6 +205,7 @@ impl DiagramResult {
             error_message: Some(error_message),
             generation_time,
             file_size: None,
+            renderer: None,
         }
     }
-}
\ No newline at end of file
+}
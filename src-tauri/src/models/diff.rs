@@ -0,0 +1,86 @@
+use super::DiagramKind;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ElementChange {
+    pub change: ChangeKind,
+    /// Node id, class name, participant id or `from->to` for edges and messages
+    pub key: String,
+    /// Which property changed, e.g. `label`, `shape`, `arrow` or `member`
+    pub field: Option<String>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl ElementChange {
+    pub fn added(key: String, value: Option<String>) -> Self {
+        Self {
+            change: ChangeKind::Added,
+            key,
+            field: None,
+            old_value: None,
+            new_value: value,
+        }
+    }
+
+    pub fn removed(key: String, value: Option<String>) -> Self {
+        Self {
+            change: ChangeKind::Removed,
+            key,
+            field: None,
+            old_value: value,
+            new_value: None,
+        }
+    }
+
+    pub fn changed(
+        key: String,
+        field: &str,
+        old_value: Option<String>,
+        new_value: Option<String>,
+    ) -> Self {
+        Self {
+            change: ChangeKind::Changed,
+            key,
+            field: Some(field.to_string()),
+            old_value,
+            new_value,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DiagramDiff {
+    pub old_kind: DiagramKind,
+    pub new_kind: DiagramKind,
+    pub nodes: Vec<ElementChange>,
+    pub edges: Vec<ElementChange>,
+    pub participants: Vec<ElementChange>,
+    pub messages: Vec<ElementChange>,
+    pub classes: Vec<ElementChange>,
+    pub summary: DiffSummary,
+    /// Combined rendering with additions and removals highlighted, when requested
+    pub highlighted_svg: Option<String>,
+}
+
+impl DiagramDiff {
+    pub fn has_changes(&self) -> bool {
+        self.old_kind != self.new_kind
+            || self.summary.added + self.summary.removed + self.summary.changed > 0
+    }
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+/// Where an mmdc candidate was found
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MmdcSource {
+    /// Path set in the app settings
+    Configured,
+    /// The `MMDC_PATH` environment variable
+    Environment,
+    /// Installed by the app into its data directory
+    Managed,
+    /// A directory on `PATH`
+    Path,
+    Pnpm,
+    Yarn,
+    Volta,
+    Nvm,
+    Asdf,
+    /// A package cached by `npx`
+    Npx,
+    /// A usual install location for the platform
+    KnownLocation,
+    /// The bin directory of `npm root -g`
+    NpmGlobal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MmdcCandidate {
+    pub path: String,
+    pub source: MmdcSource,
+}
+
+/// User settings for finding mmdc, persisted in the app data directory
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MmdcSettings {
+    /// Used before any discovered mmdc when set
+    pub mmdc_path: Option<String>,
+}
+
+/// Result of running one candidate with `--version`
+#[derive(Debug, Serialize, Clone)]
+pub struct MmdcProbe {
+    pub path: String,
+    pub source: MmdcSource,
+    pub exists: bool,
+    pub exit_code: Option<i32>,
+    pub version: Option<String>,
+    pub stderr: String,
+    /// Why the candidate could not be run at all
+    pub error: Option<String>,
+    /// Node the candidate runs with
+    pub node_path: Option<String>,
+    pub node_version: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl MmdcProbe {
+    pub fn works(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Every candidate checked, for troubleshooting a missing or broken mmdc
+#[derive(Debug, Serialize, Clone)]
+pub struct MmdcDiagnosis {
+    pub settings: MmdcSettings,
+    pub mmdc_path_env: Option<String>,
+    /// First working candidate, which the app now uses
+    pub selected: Option<MmdcCandidate>,
+    pub probes: Vec<MmdcProbe>,
+}
@@ -48,4 +48,4 @@ pub struct BatchProcessOptions {
     pub file_pattern: String, // e.g., "*.mmd"
     pub output_format: super::DiagramFormat,
     pub preserve_structure: bool,
-}
\ No newline at end of file
+}
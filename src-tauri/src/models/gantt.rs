@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+/// A gantt chart with every task's dates resolved
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GanttSchedule {
+    pub title: Option<String>,
+    /// The chart's `dateFormat`, used to read its dates
+    pub date_format: String,
+    /// The chart's `excludes` entries, such as `weekends` or `2024-05-01`
+    pub excludes: Vec<String>,
+    /// Earliest task start, as `YYYY-MM-DDTHH:mm:ss`
+    pub start: Option<String>,
+    /// Latest task end, as `YYYY-MM-DDTHH:mm:ss`
+    pub end: Option<String>,
+    pub tasks: Vec<ScheduledTask>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScheduledTask {
+    /// The task's id, or `task1`, `task2`, ... when it has none
+    pub id: String,
+    pub name: String,
+    pub section: Option<String>,
+    pub start: String,
+    /// Exclusive end, as mermaid draws it
+    pub end: String,
+    pub milestone: bool,
+    pub crit: bool,
+    pub active: bool,
+    pub done: bool,
+    /// Ids of the tasks named in `after`
+    pub dependencies: Vec<String>,
+}
@@ -0,0 +1,39 @@
+use super::DiagramDiff;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub short_hash: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Author date as seconds since the Unix epoch
+    pub timestamp: i64,
+    pub summary: String,
+    /// Path of the file at this commit, relative to the repository root
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FileRevision {
+    pub path: String,
+    /// Commit hash, or `None` for the working copy
+    pub revision: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionView {
+    SideBySide,
+    Diff,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RevisionComparison {
+    pub old: FileRevision,
+    pub new: FileRevision,
+    pub old_svg: Option<String>,
+    pub new_svg: Option<String>,
+    pub diff: Option<DiagramDiff>,
+}
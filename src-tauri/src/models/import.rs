@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// A source construct that has no Mermaid equivalent and was dropped or
+/// approximated
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SkippedConstruct {
+    /// 1-based line in the source file
+    pub line: usize,
+    pub source: String,
+    pub reason: String,
+}
+
+/// Mermaid source converted from another diagram language
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImportedDiagram {
+    /// Source file, for batch imports
+    pub path: Option<String>,
+    /// Where the Mermaid file was written, for batch imports
+    pub output_path: Option<String>,
+    pub code: String,
+    pub skipped: Vec<SkippedConstruct>,
+    /// Set instead of the code when the file could not be converted
+    pub error: Option<String>,
+}
+
+impl ImportedDiagram {
+    pub fn new(code: String, skipped: Vec<SkippedConstruct>) -> Self {
+        Self {
+            path: None,
+            output_path: None,
+            code,
+            skipped,
+            error: None,
+        }
+    }
+
+    pub fn error(path: Option<String>, error: String) -> Self {
+        Self {
+            path,
+            error: Some(error),
+            ..Self::new(String::new(), Vec::new())
+        }
+    }
+}
+
+/// Which tables of a SQL schema are converted. Patterns are comma-separated
+/// and may use `*` and `?` wildcards; they match case-insensitively.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct SqlImportOptions {
+    /// Schema (or MySQL database) names to keep
+    pub schemas: Option<String>,
+    /// Table names to keep, either bare or as `schema.table`
+    pub tables: Option<String>,
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+/// Where a managed mermaid-cli install comes from
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InstallSource {
+    Registry {
+        /// Version or dist-tag; `latest` when unset
+        #[serde(default)]
+        version: Option<String>,
+        /// Registry URL overriding the npm configuration
+        #[serde(default)]
+        registry: Option<String>,
+        /// Only use packages already in the app's npm cache
+        #[serde(default)]
+        offline: bool,
+    },
+    /// A package tarball (`.tgz`) on disk
+    Tarball { path: String },
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallStage {
+    Preparing,
+    Installing,
+    Verifying,
+    Finished,
+    Failed,
+}
+
+/// One progress update while installing, sent to the frontend as an event
+#[derive(Debug, Serialize, Clone)]
+pub struct InstallProgress {
+    pub stage: InstallStage,
+    pub message: String,
+}
+
+/// A mermaid-cli version installed in the app data directory
+#[derive(Debug, Serialize, Clone)]
+pub struct ManagedInstall {
+    pub version: String,
+    pub mmdc_path: String,
+    /// Whether mmdc discovery uses this install
+    pub active: bool,
+    /// Seconds since the epoch
+    pub installed_at: Option<u64>,
+}
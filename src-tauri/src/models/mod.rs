@@ -1,6 +1,36 @@
+pub mod config;
 pub mod diagram;
+pub mod diff;
+pub mod discovery;
 pub mod file;
+pub mod gantt;
+pub mod git;
+pub mod import;
+pub mod installer;
+pub mod parsed;
+pub mod pdf;
+pub mod preview;
+pub mod renderer;
+pub mod stats;
+pub mod svg;
+pub mod theme;
+pub mod worker;
 
 // Re-export commonly used types
+pub use config::*;
 pub use diagram::*;
-pub use file::*;
\ No newline at end of file
+pub use diff::*;
+pub use discovery::*;
+pub use file::*;
+pub use gantt::*;
+pub use git::*;
+pub use import::*;
+pub use installer::*;
+pub use parsed::*;
+pub use pdf::*;
+pub use preview::*;
+pub use renderer::*;
+pub use stats::*;
+pub use svg::*;
+pub use theme::*;
+pub use worker::*;
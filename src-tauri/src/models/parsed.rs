@@ -0,0 +1,257 @@
+use serde::{Deserialize, Serialize};
+
+/// Diagram type declared on the first line of the source
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagramKind {
+    Flowchart,
+    Sequence,
+    Class,
+    State,
+    Unknown,
+}
+
+impl std::fmt::Display for DiagramKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagramKind::Flowchart => write!(f, "flowchart"),
+            DiagramKind::Sequence => write!(f, "sequence"),
+            DiagramKind::Class => write!(f, "class"),
+            DiagramKind::State => write!(f, "state"),
+            DiagramKind::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeShape {
+    Rect,
+    Round,
+    Stadium,
+    Subroutine,
+    Cylinder,
+    Circle,
+    DoubleCircle,
+    Asymmetric,
+    Rhombus,
+    Hexagon,
+    Parallelogram,
+    ParallelogramAlt,
+    Trapezoid,
+    TrapezoidAlt,
+}
+
+impl NodeShape {
+    /// Opening and closing delimiters used in flowchart syntax
+    pub fn delimiters(&self) -> (&'static str, &'static str) {
+        match self {
+            NodeShape::Rect => ("[", "]"),
+            NodeShape::Round => ("(", ")"),
+            NodeShape::Stadium => ("([", "])"),
+            NodeShape::Subroutine => ("[[", "]]"),
+            NodeShape::Cylinder => ("[(", ")]"),
+            NodeShape::Circle => ("((", "))"),
+            NodeShape::DoubleCircle => ("(((", ")))"),
+            NodeShape::Asymmetric => (">", "]"),
+            NodeShape::Rhombus => ("{", "}"),
+            NodeShape::Hexagon => ("{{", "}}"),
+            NodeShape::Parallelogram => ("[/", "/]"),
+            NodeShape::ParallelogramAlt => ("[\\", "\\]"),
+            NodeShape::Trapezoid => ("[/", "\\]"),
+            NodeShape::TrapezoidAlt => ("[\\", "/]"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeStroke {
+    Normal,
+    Dotted,
+    Thick,
+    Invisible,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrowHead {
+    None,
+    Arrow,
+    Circle,
+    Cross,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Node {
+    pub id: String,
+    pub label: Option<String>,
+    pub shape: NodeShape,
+    /// Classes from `class` statements and the `:::name` shorthand
+    #[serde(default)]
+    pub classes: Vec<String>,
+    /// CSS declarations from `style` statements
+    #[serde(default)]
+    pub styles: Vec<String>,
+}
+
+impl Node {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            label: None,
+            shape: NodeShape::Rect,
+            classes: Vec::new(),
+            styles: Vec::new(),
+        }
+    }
+
+    /// Label shown in the diagram, falling back to the id
+    pub fn display_label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.id)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub label: Option<String>,
+    /// Arrow exactly as written in the source, e.g. `-->` or `<|--`
+    pub arrow: String,
+    pub stroke: EdgeStroke,
+    pub head: ArrowHead,
+    pub tail: ArrowHead,
+}
+
+/// A `classDef` statement
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClassDef {
+    pub name: String,
+    /// CSS declarations such as `fill:#f9f`
+    pub styles: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Subgraph {
+    pub id: String,
+    pub label: Option<String>,
+    pub parent: Option<String>,
+    pub direction: Option<String>,
+    /// Ids of the nodes declared directly inside this subgraph
+    pub nodes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Participant {
+    pub id: String,
+    pub label: Option<String>,
+    pub is_actor: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Message {
+    pub from: String,
+    pub to: String,
+    pub text: String,
+    pub arrow: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotePlacement {
+    LeftOf,
+    RightOf,
+    Over,
+}
+
+/// A sequence diagram note beside one participant or over one or two
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Note {
+    pub placement: NotePlacement,
+    pub participants: Vec<String>,
+    pub text: String,
+}
+
+/// A sequence diagram statement, kept in source order
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceStep {
+    /// Index into `messages`
+    Message(usize),
+    Note(Note),
+    /// Opens a `loop`, `alt`, `opt`, `par`, `critical`, `break` or `rect`
+    /// block
+    BlockStart {
+        keyword: String,
+        label: String,
+    },
+    /// An `else`, `and` or `option` section of the innermost open block
+    BlockSection {
+        keyword: String,
+        label: String,
+    },
+    BlockEnd,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClassNode {
+    pub name: String,
+    pub annotations: Vec<String>,
+    pub members: Vec<String>,
+}
+
+/// Structural view of a mermaid diagram, independent of its formatting
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParsedDiagram {
+    pub kind: DiagramKind,
+    pub title: Option<String>,
+    /// Accessible title from `accTitle:`
+    pub acc_title: Option<String>,
+    /// Accessible description from `accDescr:` or an `accDescr { ... }` block
+    pub acc_descr: Option<String>,
+    pub direction: Option<String>,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    pub subgraphs: Vec<Subgraph>,
+    #[serde(default)]
+    pub class_defs: Vec<ClassDef>,
+    pub participants: Vec<Participant>,
+    pub messages: Vec<Message>,
+    /// Messages, notes and blocks of a sequence diagram in source order
+    #[serde(default)]
+    pub steps: Vec<SequenceStep>,
+    pub classes: Vec<ClassNode>,
+}
+
+impl ParsedDiagram {
+    pub fn new(kind: DiagramKind) -> Self {
+        Self {
+            kind,
+            title: None,
+            acc_title: None,
+            acc_descr: None,
+            direction: None,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            subgraphs: Vec::new(),
+            class_defs: Vec::new(),
+            participants: Vec::new(),
+            messages: Vec::new(),
+            steps: Vec::new(),
+            classes: Vec::new(),
+        }
+    }
+
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    pub fn participant(&self, id: &str) -> Option<&Participant> {
+        self.participants.iter().find(|p| p.id == id)
+    }
+
+    pub fn class(&self, name: &str) -> Option<&ClassNode> {
+        self.classes.iter().find(|c| c.name == name)
+    }
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+    A3,
+    A4,
+    A5,
+    Letter,
+    Legal,
+    Tabloid,
+}
+
+impl PageSize {
+    /// Portrait width and height in PostScript points
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            PageSize::A3 => (841.89, 1190.55),
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::A5 => (419.53, 595.28),
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::Legal => (612.0, 1008.0),
+            PageSize::Tabloid => (792.0, 1224.0),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PageOrientation {
+    Portrait,
+    Landscape,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PdfDocumentOptions {
+    pub page_size: PageSize,
+    pub orientation: PageOrientation,
+    /// Page margin in points
+    pub margin: f32,
+    /// Document title, shown on the contents page and in the PDF metadata
+    pub title: Option<String>,
+    /// Print the diagram title and file name at the top of each page
+    pub header: bool,
+    pub page_numbers: bool,
+    /// Start with a contents page; bookmarks are always written
+    pub table_of_contents: bool,
+    /// Scale diagrams up as well as down to fill the page
+    pub fit_to_page: bool,
+    pub theme: Option<String>,
+    pub background: String,
+}
+
+impl Default for PdfDocumentOptions {
+    fn default() -> Self {
+        Self {
+            page_size: PageSize::A4,
+            orientation: PageOrientation::Portrait,
+            margin: 36.0,
+            title: None,
+            header: true,
+            page_numbers: true,
+            table_of_contents: true,
+            fit_to_page: true,
+            theme: None,
+            background: "white".to_string(),
+        }
+    }
+}
+
+impl PdfDocumentOptions {
+    /// Page width and height in points after applying the orientation
+    pub fn page_dimensions(&self) -> (f32, f32) {
+        let (width, height) = self.page_size.dimensions();
+        match self.orientation {
+            PageOrientation::Portrait => (width, height),
+            PageOrientation::Landscape => (height, width),
+        }
+    }
+}
+
+/// A rendered diagram to place on its own page
+#[derive(Debug, Clone)]
+pub struct PdfDiagram {
+    pub path: String,
+    pub title: String,
+    /// SVG markup, or the error that prevented rendering it
+    pub svg: Result<String, String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PdfPageEntry {
+    pub path: String,
+    pub title: String,
+    /// 1-based page number, `None` when the diagram was left out
+    pub page: Option<usize>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PdfDocumentResult {
+    pub output_path: String,
+    pub page_count: usize,
+    pub entries: Vec<PdfPageEntry>,
+    pub file_size: u64,
+    pub generation_time: u64,
+}
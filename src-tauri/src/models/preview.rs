@@ -0,0 +1,17 @@
+use serde::Deserialize;
+
+/// How the editor preview is rendered, as sent by the frontend
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PreviewOptions {
+    /// Theme pack id such as `light` or `dark`, or a mermaid theme name
+    pub theme: Option<String>,
+    /// Overrides the theme's background
+    pub background: Option<String>,
+    /// Size of the page mermaid lays the diagram out in
+    pub viewport_width: Option<u32>,
+    pub viewport_height: Option<u32>,
+    /// Zoom factor applied to the diagram's natural size
+    pub scale: Option<f32>,
+    pub font_family: Option<String>,
+}
@@ -0,0 +1,111 @@
+use super::{DiagramFormat, RenderWorkerSettings};
+use serde::{Deserialize, Serialize};
+
+/// An engine that turns mermaid source into SVG or PDF
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RendererBackend {
+    /// One mmdc process per render
+    Mmdc,
+    /// The persistent Node worker
+    Worker,
+    /// A Kroki-compatible HTTP server
+    Kroki,
+    /// Rust implementations of simple diagram types
+    Native,
+}
+
+impl std::fmt::Display for RendererBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RendererBackend::Mmdc => write!(f, "mmdc"),
+            RendererBackend::Worker => write!(f, "worker"),
+            RendererBackend::Kroki => write!(f, "kroki"),
+            RendererBackend::Native => write!(f, "native"),
+        }
+    }
+}
+
+/// How the order of renderers is chosen
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderPolicy {
+    /// Mermaid itself first; native renderers only as a last resort
+    #[default]
+    Quality,
+    /// Native renderers first, then the cheapest mermaid backend
+    Speed,
+    /// The order given in `RendererSettings::chain`
+    Custom,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct KrokiSettings {
+    /// Server base URL such as `http://localhost:8000`; Kroki is skipped
+    /// when unset
+    pub url: Option<String>,
+    pub timeout_ms: u64,
+}
+
+impl Default for KrokiSettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// Renderer selection, persisted in the app data directory
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RendererSettings {
+    pub policy: RenderPolicy,
+    /// Renderers tried in order with the custom policy
+    pub chain: Vec<RendererBackend>,
+    pub worker: RenderWorkerSettings,
+    pub kroki: KrokiSettings,
+}
+
+impl RendererSettings {
+    /// Renderers to try in order; each one that cannot handle a diagram
+    /// falls through to the next
+    pub fn fallback_chain(&self) -> Vec<RendererBackend> {
+        use RendererBackend::*;
+        match self.policy {
+            RenderPolicy::Quality => vec![Worker, Mmdc, Kroki, Native],
+            RenderPolicy::Speed => vec![Native, Worker, Kroki, Mmdc],
+            RenderPolicy::Custom => self.chain.clone(),
+        }
+    }
+}
+
+/// What a renderer produces directly; other formats are converted from its
+/// SVG in-process
+#[derive(Debug, Serialize, Clone)]
+pub struct RendererCapabilities {
+    pub formats: Vec<DiagramFormat>,
+    /// Mermaid diagram types such as `pie` or `flowchart`; `None` for all
+    pub diagram_types: Option<Vec<String>>,
+}
+
+impl RendererCapabilities {
+    pub fn supports(&self, format: &DiagramFormat, diagram_type: Option<&str>) -> bool {
+        self.formats.contains(format)
+            && match (&self.diagram_types, diagram_type) {
+                (None, _) => true,
+                (Some(types), Some(diagram_type)) => types.iter().any(|t| t == diagram_type),
+                (Some(_), None) => false,
+            }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RendererInfo {
+    pub backend: RendererBackend,
+    pub capabilities: RendererCapabilities,
+    pub available: bool,
+    /// Why the renderer is unavailable
+    pub reason: Option<String>,
+}
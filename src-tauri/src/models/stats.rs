@@ -0,0 +1,69 @@
+use super::DiagramKind;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct NodeDegree {
+    pub id: String,
+    pub fan_in: usize,
+    pub fan_out: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DiagramStats {
+    pub path: Option<String>,
+    pub kind: DiagramKind,
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub subgraph_count: usize,
+    pub participant_count: usize,
+    pub message_count: usize,
+    pub class_count: usize,
+    /// Deepest subgraph or composite state nesting, 0 when there is none
+    pub max_nesting_depth: usize,
+    /// `E - N + 2P` for flowcharts and state diagrams
+    pub cyclomatic_complexity: Option<i64>,
+    pub degrees: Vec<NodeDegree>,
+    /// Strongly connected components with more than one element or a
+    /// self-loop. Every element of a group can reach every other, so each
+    /// lies on a cycle, but a group may hold several overlapping cycles.
+    pub cycles: Vec<Vec<String>>,
+    /// Weakly connected components; more than one means the diagram is disconnected
+    pub components: Vec<Vec<String>>,
+    /// Set instead of the metrics when the file could not be read or parsed
+    pub error: Option<String>,
+}
+
+impl DiagramStats {
+    pub fn empty(path: Option<String>, kind: DiagramKind) -> Self {
+        Self {
+            path,
+            kind,
+            node_count: 0,
+            edge_count: 0,
+            subgraph_count: 0,
+            participant_count: 0,
+            message_count: 0,
+            class_count: 0,
+            max_nesting_depth: 0,
+            cyclomatic_complexity: None,
+            degrees: Vec::new(),
+            cycles: Vec::new(),
+            components: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn error(path: Option<String>, error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::empty(path, DiagramKind::Unknown)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsExportFormat {
+    Csv,
+    Json,
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// Post-processing steps applied to SVG produced by mmdc
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SvgProcessOptions {
+    /// Background colour for the root element; `transparent` removes it
+    pub background: Option<String>,
+    /// Drop comments and insignificant whitespace
+    pub minify: bool,
+    /// Remove `<defs>` entries that nothing references
+    pub remove_unused_defs: bool,
+    /// Inline the fonts the diagram uses as `@font-face` data URIs
+    pub embed_fonts: bool,
+    /// Add `<title>`, `<desc>` and `role="img"` from `accTitle`/`accDescr`
+    pub accessibility: bool,
+    /// Remove scripts, event handlers and `javascript:` links
+    pub strip_scripts: bool,
+    /// Size the root element to its viewBox instead of the container width
+    pub fit_content: bool,
+    /// Zoom factor for `fit_content`
+    pub scale: Option<f32>,
+}
+
+impl Default for SvgProcessOptions {
+    fn default() -> Self {
+        Self {
+            background: None,
+            minify: false,
+            remove_unused_defs: true,
+            embed_fonts: false,
+            accessibility: true,
+            strip_scripts: true,
+            fit_content: false,
+            scale: None,
+        }
+    }
+}
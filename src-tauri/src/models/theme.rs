@@ -0,0 +1,52 @@
+use super::MermaidConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeAppearance {
+    #[default]
+    Light,
+    Dark,
+}
+
+/// Metadata from a theme pack's `theme.json`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ThemeManifest {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Whether the theme is meant for light or dark surroundings
+    #[serde(default)]
+    pub appearance: ThemeAppearance,
+    /// Background the theme is designed for, used for previews and exports
+    #[serde(default)]
+    pub background: Option<String>,
+}
+
+/// A theme: mermaid config plus optional CSS and metadata.
+///
+/// On disk a pack is a folder or zip with `theme.json`, `config.json` and an
+/// optional `theme.css`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ThemePack {
+    /// Folder name under the themes directory, derived from the name
+    pub id: String,
+    pub manifest: ThemeManifest,
+    pub config: MermaidConfig,
+    pub css: Option<String>,
+    /// Shipped with the app and cannot be removed
+    pub builtin: bool,
+}
+
+/// Rendered sample diagram for one theme
+#[derive(Debug, Serialize, Clone)]
+pub struct ThemePreview {
+    pub id: String,
+    pub name: String,
+    pub svg: Option<String>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+/// Settings for the long-lived render worker that replaces one mmdc process
+/// per SVG render
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RenderWorkerSettings {
+    pub enabled: bool,
+    /// Node executable; `node` from the PATH when unset
+    pub node_path: Option<String>,
+    /// Restart the worker once Node and Chromium together use more than this
+    pub max_memory_mb: u64,
+    /// Restart the worker after this many renders
+    pub max_renders: u32,
+    pub render_timeout_ms: u64,
+    /// Time allowed for Node and Chromium to start
+    pub startup_timeout_ms: u64,
+    /// Puppeteer launch options, e.g. `{"args": ["--no-sandbox"]}`
+    pub puppeteer_config: Option<serde_json::Value>,
+}
+
+impl Default for RenderWorkerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            node_path: None,
+            max_memory_mb: 768,
+            max_renders: 500,
+            render_timeout_ms: 30_000,
+            startup_timeout_ms: 30_000,
+            puppeteer_config: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct RenderWorkerStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub pid: Option<u32>,
+    /// Renders done by the current worker process
+    pub renders: u32,
+    pub memory_mb: Option<u64>,
+    /// Times the worker was restarted after a crash, timeout or recycling
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
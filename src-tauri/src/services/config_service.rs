@@ -0,0 +1,364 @@
+use crate::models::{ConfigPreset, ConfigSource, MermaidConfig, MermaidTheme, ResolvedConfig};
+use crate::utils::{merge_json, slugify, DefaultDirectories};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Config file picked up from the root of a workspace directory
+pub const WORKSPACE_CONFIG_FILE: &str = "mermaid.config.json";
+
+/// Merge configs in order; later layers override earlier ones key by key
+pub fn merge_configs(layers: &[&MermaidConfig]) -> Result<MermaidConfig, String> {
+    let mut merged = Value::Object(Default::default());
+    for layer in layers {
+        let value = serde_json::to_value(layer)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        merge_json(&mut merged, &value);
+    }
+    serde_json::from_value(merged).map_err(|e| format!("Invalid merged config: {}", e))
+}
+
+/// Merge global, workspace and per-file `%%{init}%%` configuration
+pub fn resolve_config(
+    global: Option<&MermaidConfig>,
+    workspace: Option<&MermaidConfig>,
+    code: &str,
+) -> Result<ResolvedConfig, String> {
+    let file = parse_init_directives(code)?;
+
+    let layers = [
+        (ConfigSource::Global, global),
+        (ConfigSource::Workspace, workspace),
+        (ConfigSource::File, file.as_ref()),
+    ];
+    let present: Vec<_> = layers
+        .iter()
+        .filter_map(|(source, config)| config.map(|config| (*source, config)))
+        .collect();
+
+    let configs: Vec<&MermaidConfig> = present.iter().map(|(_, config)| *config).collect();
+    let config = merge_configs(&configs)?;
+    let warnings = validate_config(&config);
+
+    Ok(ResolvedConfig {
+        config,
+        sources: present.into_iter().map(|(source, _)| source).collect(),
+        warnings,
+    })
+}
+
+/// Check a config for settings mermaid silently ignores or rejects
+pub fn validate_config(config: &MermaidConfig) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if let Some(ref variables) = config.theme_variables {
+        if !matches!(config.theme, Some(MermaidTheme::Base)) {
+            warnings.push("themeVariables only take full effect with the base theme".to_string());
+        }
+
+        let colors = [
+            ("background", &variables.background),
+            ("primaryColor", &variables.primary_color),
+            ("primaryTextColor", &variables.primary_text_color),
+            ("primaryBorderColor", &variables.primary_border_color),
+            ("secondaryColor", &variables.secondary_color),
+            ("tertiaryColor", &variables.tertiary_color),
+            ("lineColor", &variables.line_color),
+            ("textColor", &variables.text_color),
+            ("noteBkgColor", &variables.note_bkg_color),
+        ];
+        let extra_colors = variables
+            .extra
+            .iter()
+            .filter(|(key, _)| key.ends_with("Color") || key.ends_with("Bkg"))
+            .filter_map(|(key, value)| value.as_str().map(|value| (key.as_str(), value)));
+
+        // The theming engine derives shades from the value, which only works for hex colours
+        for (key, value) in colors
+            .iter()
+            .filter_map(|(key, value)| value.as_deref().map(|value| (*key, value)))
+            .chain(extra_colors)
+        {
+            if !is_hex_color(value) {
+                warnings.push(format!(
+                    "themeVariables.{} must be a hex colour, got '{}'",
+                    key, value
+                ));
+            }
+        }
+    }
+
+    let mut positive = |name: &str, value: Option<f64>| {
+        if let Some(value) = value {
+            if !(value.is_finite() && value > 0.0) {
+                warnings.push(format!("{} must be a positive number, got {}", name, value));
+            }
+        }
+    };
+    positive("fontSize", config.font_size);
+    if let Some(ref flowchart) = config.flowchart {
+        positive("flowchart.nodeSpacing", flowchart.node_spacing);
+        positive("flowchart.rankSpacing", flowchart.rank_spacing);
+    }
+    if let Some(ref sequence) = config.sequence {
+        positive("sequence.actorMargin", sequence.actor_margin);
+    }
+    if let Some(ref gantt) = config.gantt {
+        positive("gantt.barHeight", gantt.bar_height);
+        positive("gantt.fontSize", gantt.font_size);
+    }
+
+    warnings
+}
+
+fn is_hex_color(value: &str) -> bool {
+    value
+        .strip_prefix('#')
+        .filter(|hex| matches!(hex.len(), 3 | 4 | 6 | 8))
+        .is_some_and(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Collect `%%{init: ...}%%` directives from mermaid source. Several
+/// directives are merged in order, as mermaid does.
+pub fn parse_init_directives(code: &str) -> Result<Option<MermaidConfig>, String> {
+    init_directive_values(code)?
+        .map(|value| {
+            serde_json::from_value(value).map_err(|e| format!("Invalid init directive: {}", e))
+        })
+        .transpose()
+}
+
+/// The merged `%%{init: ...}%%` directives as untyped JSON, for renderers
+/// that read config keys [`MermaidConfig`] does not model
+pub fn init_directive_values(code: &str) -> Result<Option<Value>, String> {
+    let mut merged: Option<Value> = None;
+    let mut rest = code;
+
+    while let Some(start) = rest.find("%%{") {
+        let after = &rest[start + 3..];
+        let end = after
+            .find("}%%")
+            .ok_or_else(|| "Unterminated %%{ directive".to_string())?;
+        let directive = after[..end].trim();
+        rest = &after[end + 3..];
+
+        let Some((name, body)) = directive.split_once(':') else {
+            continue;
+        };
+        let name = name.trim().trim_matches(|c| c == '"' || c == '\'');
+        if name != "init" && name != "initialize" {
+            continue;
+        }
+
+        let value: Value = serde_json::from_str(&normalize_quotes(body.trim()))
+            .map_err(|e| format!("Invalid init directive: {}", e))?;
+        match merged {
+            Some(ref mut merged) => merge_json(merged, &value),
+            None => merged = Some(value),
+        }
+    }
+
+    Ok(merged)
+}
+
+/// Directives are usually written with single quotes, which JSON does not allow
+fn normalize_quotes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for c in text.chars() {
+        if escaped {
+            out.push(c);
+            escaped = false;
+            continue;
+        }
+        match (quote, c) {
+            (Some(_), '\\') => {
+                out.push(c);
+                escaped = true;
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                out.push('"');
+            }
+            (Some(open), _) if c == open => {
+                quote = None;
+                out.push('"');
+            }
+            (Some('\''), '"') => out.push_str("\\\""),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Read `mermaid.config.json` from a workspace directory, if there is one
+pub fn load_workspace_config(directory: &Path) -> Result<Option<MermaidConfig>, String> {
+    let path = directory.join(WORKSPACE_CONFIG_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Invalid config in {}: {}", path.display(), e))
+}
+
+/// Named config presets stored as JSON files in one directory
+pub struct ConfigPresetStore {
+    directory: PathBuf,
+}
+
+impl ConfigPresetStore {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    /// Presets directory inside the app data directory
+    pub fn default_location() -> Result<Self, String> {
+        let storage = DefaultDirectories::app_storage()
+            .ok_or_else(|| "Could not determine the app data directory".to_string())?;
+        Ok(Self::new(storage.join("presets")))
+    }
+
+    pub fn list(&self) -> Result<Vec<ConfigPreset>, String> {
+        if !self.directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let entries = fs::read_dir(&self.directory)
+            .map_err(|e| format!("Failed to read presets directory: {}", e))?;
+        let mut presets = Vec::new();
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_some_and(|ext| ext == "json") {
+                match read_preset(&path) {
+                    Ok(preset) => presets.push(preset),
+                    Err(e) => log::warn!("Skipping preset {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        presets.sort_by_key(|preset| preset.name.to_lowercase());
+        Ok(presets)
+    }
+
+    pub fn load(&self, name: &str) -> Result<ConfigPreset, String> {
+        let path = self.preset_path(name)?;
+        if !path.exists() {
+            return Err(format!("Preset not found: {}", name));
+        }
+        read_preset(&path)
+    }
+
+    pub fn save(&self, preset: &ConfigPreset) -> Result<PathBuf, String> {
+        let path = self.preset_path(&preset.name)?;
+        fs::create_dir_all(&self.directory)
+            .map_err(|e| format!("Failed to create presets directory: {}", e))?;
+
+        let content = serde_json::to_string_pretty(preset)
+            .map_err(|e| format!("Failed to serialize preset: {}", e))?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write preset: {}", e))?;
+        Ok(path)
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        let path = self.preset_path(name)?;
+        fs::remove_file(&path).map_err(|e| format!("Failed to delete preset {}: {}", name, e))
+    }
+
+    /// File for a preset name, limited to a safe set of characters
+    fn preset_path(&self, name: &str) -> Result<PathBuf, String> {
+        let slug = slugify(name).ok_or_else(|| format!("Invalid preset name: '{}'", name))?;
+        Ok(self.directory.join(format!("{}.json", slug)))
+    }
+}
+
+fn read_preset(path: &Path) -> Result<ConfigPreset, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read preset: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid preset: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FlowchartCurve, SequenceConfig};
+    use crate::utils::temp::TempDir;
+
+    #[test]
+    fn test_resolve_config_layers() {
+        let global: MermaidConfig = serde_json::from_str(
+            r##"{"theme":"base","themeVariables":{"primaryColor":"#004488","lineColor":"gray"},"flowchart":{"curve":"basis","nodeSpacing":40},"deterministicIds":true}"##,
+        )
+        .unwrap();
+        let workspace = MermaidConfig {
+            sequence: Some(SequenceConfig {
+                mirror_actors: Some(false),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let code = "%%{init: {'flowchart': {'curve': 'linear'}, 'themeVariables': {'primaryColor': '#ff0000'}}}%%\nflowchart TD\n    A --> B\n";
+
+        let resolved = resolve_config(Some(&global), Some(&workspace), code).unwrap();
+        assert_eq!(
+            resolved.sources,
+            vec![
+                ConfigSource::Global,
+                ConfigSource::Workspace,
+                ConfigSource::File
+            ]
+        );
+
+        let config = resolved.config;
+        let flowchart = config.flowchart.as_ref().unwrap();
+        assert_eq!(flowchart.curve, Some(FlowchartCurve::Linear));
+        assert_eq!(flowchart.node_spacing, Some(40.0));
+        assert_eq!(config.sequence.unwrap().mirror_actors, Some(false));
+        let variables = config.theme_variables.unwrap();
+        assert_eq!(variables.primary_color.as_deref(), Some("#ff0000"));
+        assert_eq!(config.extra["deterministicIds"], true);
+
+        assert_eq!(resolved.warnings.len(), 1);
+        assert!(resolved.warnings[0].contains("lineColor"));
+    }
+
+    #[test]
+    fn test_directive_quotes() {
+        assert_eq!(
+            normalize_quotes(r#"{'themeCSS': '.a { font: "x" }', "k": "it's"}"#),
+            r#"{"themeCSS": ".a { font: \"x\" }", "k": "it's"}"#
+        );
+        assert!(parse_init_directives("flowchart TD\n    A --> B\n")
+            .unwrap()
+            .is_none());
+        assert!(parse_init_directives("%%{init: {theme: }}%%\ngraph TD").is_err());
+    }
+
+    #[test]
+    fn test_preset_store() {
+        let temp = TempDir::new("mermaid_presets");
+        let directory = temp.path().join("presets");
+        let store = ConfigPresetStore::new(directory.clone());
+        let preset = ConfigPreset {
+            name: "Corporate Theme".to_string(),
+            description: None,
+            config: MermaidConfig {
+                theme: Some(MermaidTheme::Base),
+                ..Default::default()
+            },
+        };
+
+        let path = store.save(&preset).unwrap();
+        assert_eq!(path, directory.join("corporate-theme.json"));
+        assert_eq!(store.load("Corporate Theme").unwrap(), preset);
+        assert_eq!(store.list().unwrap(), vec![preset]);
+        assert!(store.load("../secrets").is_err());
+
+        store.delete("Corporate Theme").unwrap();
+        assert!(store.list().unwrap().is_empty());
+    }
+}
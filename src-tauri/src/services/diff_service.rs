@@ -0,0 +1,765 @@
+use crate::models::{
+    ChangeKind, ClassNode, DiagramDiff, DiagramKind, DiffSummary, Edge, ElementChange, Message,
+    ParsedDiagram, SequenceStep,
+};
+use crate::services::parser_service::{format_message, format_step, write_diagram};
+
+const ADDED_STYLE: &str = "fill:#dafbe1,stroke:#2da44e,stroke-width:2px";
+const REMOVED_STYLE: &str = "fill:#ffebe9,stroke:#cf222e,stroke-width:2px,stroke-dasharray:5 5";
+const CHANGED_STYLE: &str = "fill:#fff8c5,stroke:#bf8700,stroke-width:2px";
+
+const ADDED_LINK: &str = "stroke:#2da44e,stroke-width:2px";
+const REMOVED_LINK: &str = "stroke:#cf222e,stroke-width:2px,stroke-dasharray:5 5";
+const CHANGED_LINK: &str = "stroke:#bf8700,stroke-width:2px";
+
+const ADDED_RECT: &str = "rgba(45, 164, 78, 0.15)";
+const REMOVED_RECT: &str = "rgba(207, 34, 46, 0.15)";
+const CHANGED_RECT: &str = "rgba(191, 135, 0, 0.15)";
+
+/// Appended to the label of a removed class diagram relation
+const REMOVED_LABEL: &str = "(removed)";
+
+/// Compare two parsed diagrams element by element
+pub fn diff_diagrams(old: &ParsedDiagram, new: &ParsedDiagram) -> DiagramDiff {
+    let mut diff = DiagramDiff {
+        old_kind: old.kind,
+        new_kind: new.kind,
+        nodes: diff_nodes(old, new),
+        edges: diff_edges(&old.edges, &new.edges),
+        participants: diff_participants(old, new),
+        messages: diff_messages(&old.messages, &new.messages),
+        classes: diff_classes(&old.classes, &new.classes),
+        summary: DiffSummary::default(),
+        highlighted_svg: None,
+    };
+
+    let all = diff
+        .nodes
+        .iter()
+        .chain(&diff.edges)
+        .chain(&diff.participants)
+        .chain(&diff.messages)
+        .chain(&diff.classes);
+    for change in all {
+        match change.change {
+            ChangeKind::Added => diff.summary.added += 1,
+            ChangeKind::Removed => diff.summary.removed += 1,
+            ChangeKind::Changed => diff.summary.changed += 1,
+        }
+    }
+
+    diff
+}
+
+/// Build mermaid source that shows both versions, styling additions green,
+/// removals red and modifications amber
+pub fn highlight_source(old: &ParsedDiagram, new: &ParsedDiagram) -> Result<String, String> {
+    if old.kind != new.kind {
+        return Err(format!(
+            "Cannot highlight changes between a {} and a {} diagram",
+            old.kind, new.kind
+        ));
+    }
+
+    match new.kind {
+        DiagramKind::Flowchart => Ok(highlight_flowchart(old, new)),
+        DiagramKind::Sequence => Ok(highlight_sequence(old, new)),
+        DiagramKind::Class => Ok(highlight_class(old, new)),
+        kind => Err(format!("Highlighting {} diagrams is not supported", kind)),
+    }
+}
+
+fn edge_key(from: &str, to: &str) -> String {
+    format!("{}->{}", from, to)
+}
+
+fn diff_nodes(old: &ParsedDiagram, new: &ParsedDiagram) -> Vec<ElementChange> {
+    let mut changes = Vec::new();
+
+    for node in &new.nodes {
+        match old.node(&node.id) {
+            None => changes.push(ElementChange::added(
+                node.id.clone(),
+                Some(node.display_label().to_string()),
+            )),
+            Some(previous) => {
+                if previous.label != node.label {
+                    changes.push(ElementChange::changed(
+                        node.id.clone(),
+                        "label",
+                        Some(previous.display_label().to_string()),
+                        Some(node.display_label().to_string()),
+                    ));
+                }
+                if previous.shape != node.shape {
+                    changes.push(ElementChange::changed(
+                        node.id.clone(),
+                        "shape",
+                        Some(format!("{:?}", previous.shape)),
+                        Some(format!("{:?}", node.shape)),
+                    ));
+                }
+            }
+        }
+    }
+
+    for node in &old.nodes {
+        if new.node(&node.id).is_none() {
+            changes.push(ElementChange::removed(
+                node.id.clone(),
+                Some(node.display_label().to_string()),
+            ));
+        }
+    }
+
+    changes
+}
+
+/// Pairing of old and new edges: identical edges first, then edges that
+/// connect the same nodes but differ in label or arrow
+struct EdgeMatch {
+    pairs: Vec<(usize, usize)>,
+    added: Vec<usize>,
+    removed: Vec<usize>,
+}
+
+fn match_edges(old: &[Edge], new: &[Edge]) -> EdgeMatch {
+    let mut old_used = vec![false; old.len()];
+    let mut new_match: Vec<Option<usize>> = vec![None; new.len()];
+
+    for (j, edge) in new.iter().enumerate() {
+        if let Some(i) = (0..old.len()).find(|&i| !old_used[i] && old[i] == *edge) {
+            old_used[i] = true;
+            new_match[j] = Some(i);
+        }
+    }
+
+    for (j, edge) in new.iter().enumerate() {
+        if new_match[j].is_some() {
+            continue;
+        }
+        if let Some(i) = (0..old.len())
+            .find(|&i| !old_used[i] && old[i].from == edge.from && old[i].to == edge.to)
+        {
+            old_used[i] = true;
+            new_match[j] = Some(i);
+        }
+    }
+
+    EdgeMatch {
+        pairs: new_match
+            .iter()
+            .enumerate()
+            .filter_map(|(j, i)| i.map(|i| (i, j)))
+            .collect(),
+        added: (0..new.len()).filter(|&j| new_match[j].is_none()).collect(),
+        removed: (0..old.len()).filter(|&i| !old_used[i]).collect(),
+    }
+}
+
+fn diff_edges(old: &[Edge], new: &[Edge]) -> Vec<ElementChange> {
+    let matched = match_edges(old, new);
+    let mut changes = Vec::new();
+
+    for &j in &matched.added {
+        let edge = &new[j];
+        changes.push(ElementChange::added(
+            edge_key(&edge.from, &edge.to),
+            edge.label.clone(),
+        ));
+    }
+
+    for &(i, j) in &matched.pairs {
+        let (before, after) = (&old[i], &new[j]);
+        let key = edge_key(&after.from, &after.to);
+        if before.label != after.label {
+            changes.push(ElementChange::changed(
+                key.clone(),
+                "label",
+                before.label.clone(),
+                after.label.clone(),
+            ));
+        }
+        if before.arrow != after.arrow {
+            changes.push(ElementChange::changed(
+                key,
+                "arrow",
+                Some(before.arrow.clone()),
+                Some(after.arrow.clone()),
+            ));
+        }
+    }
+
+    for &i in &matched.removed {
+        let edge = &old[i];
+        changes.push(ElementChange::removed(
+            edge_key(&edge.from, &edge.to),
+            edge.label.clone(),
+        ));
+    }
+
+    changes
+}
+
+fn diff_participants(old: &ParsedDiagram, new: &ParsedDiagram) -> Vec<ElementChange> {
+    let mut changes = Vec::new();
+
+    for participant in &new.participants {
+        match old.participant(&participant.id) {
+            None => changes.push(ElementChange::added(
+                participant.id.clone(),
+                participant.label.clone(),
+            )),
+            Some(previous) => {
+                if previous.label != participant.label {
+                    changes.push(ElementChange::changed(
+                        participant.id.clone(),
+                        "label",
+                        previous.label.clone(),
+                        participant.label.clone(),
+                    ));
+                }
+                if previous.is_actor != participant.is_actor {
+                    let kind = |is_actor: bool| if is_actor { "actor" } else { "participant" };
+                    changes.push(ElementChange::changed(
+                        participant.id.clone(),
+                        "type",
+                        Some(kind(previous.is_actor).to_string()),
+                        Some(kind(participant.is_actor).to_string()),
+                    ));
+                }
+            }
+        }
+    }
+
+    for participant in &old.participants {
+        if new.participant(&participant.id).is_none() {
+            changes.push(ElementChange::removed(
+                participant.id.clone(),
+                participant.label.clone(),
+            ));
+        }
+    }
+
+    changes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MessageOp {
+    Same(usize, usize),
+    Changed(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Align two message sequences with a longest common subsequence, then pair
+/// removed and added messages between the same participants as changes
+fn message_ops(old: &[Message], new: &[Message]) -> Vec<MessageOp> {
+    let (n, m) = (old.len(), new.len());
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut removed: Vec<usize> = Vec::new();
+    let mut added: Vec<usize> = Vec::new();
+
+    let flush = |ops: &mut Vec<MessageOp>, removed: &mut Vec<usize>, added: &mut Vec<usize>| {
+        let mut pending_added: Vec<Option<usize>> = added.drain(..).map(Some).collect();
+        for r in removed.drain(..) {
+            let partner = pending_added
+                .iter_mut()
+                .find(|a| a.is_some_and(|a| new[a].from == old[r].from && new[a].to == old[r].to));
+            match partner.and_then(|a| a.take()) {
+                Some(a) => ops.push(MessageOp::Changed(r, a)),
+                None => ops.push(MessageOp::Removed(r)),
+            }
+        }
+        ops.extend(pending_added.into_iter().flatten().map(MessageOp::Added));
+    };
+
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            flush(&mut ops, &mut removed, &mut added);
+            ops.push(MessageOp::Same(i, j));
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lengths[i][j + 1] >= lengths[i + 1][j]) {
+            added.push(j);
+            j += 1;
+        } else {
+            removed.push(i);
+            i += 1;
+        }
+    }
+    flush(&mut ops, &mut removed, &mut added);
+
+    ops
+}
+
+fn message_key(index: usize, message: &Message) -> String {
+    format!("#{} {}", index + 1, edge_key(&message.from, &message.to))
+}
+
+fn diff_messages(old: &[Message], new: &[Message]) -> Vec<ElementChange> {
+    let mut changes = Vec::new();
+
+    for op in message_ops(old, new) {
+        match op {
+            MessageOp::Same(..) => {}
+            MessageOp::Added(j) => changes.push(ElementChange::added(
+                message_key(j, &new[j]),
+                Some(new[j].text.clone()),
+            )),
+            MessageOp::Removed(i) => changes.push(ElementChange::removed(
+                message_key(i, &old[i]),
+                Some(old[i].text.clone()),
+            )),
+            MessageOp::Changed(i, j) => {
+                let key = message_key(j, &new[j]);
+                if old[i].text != new[j].text {
+                    changes.push(ElementChange::changed(
+                        key.clone(),
+                        "label",
+                        Some(old[i].text.clone()),
+                        Some(new[j].text.clone()),
+                    ));
+                }
+                if old[i].arrow != new[j].arrow {
+                    changes.push(ElementChange::changed(
+                        key,
+                        "arrow",
+                        Some(old[i].arrow.clone()),
+                        Some(new[j].arrow.clone()),
+                    ));
+                }
+            }
+        }
+    }
+
+    changes
+}
+
+fn diff_classes(old: &[ClassNode], new: &[ClassNode]) -> Vec<ElementChange> {
+    let mut changes = Vec::new();
+
+    for class in new {
+        let Some(previous) = old.iter().find(|c| c.name == class.name) else {
+            changes.push(ElementChange::added(class.name.clone(), None));
+            continue;
+        };
+
+        let fields = [
+            ("annotation", &previous.annotations, &class.annotations),
+            ("member", &previous.members, &class.members),
+        ];
+        for (field, before, after) in fields {
+            for item in after.iter().filter(|item| !before.contains(item)) {
+                changes.push(ElementChange::changed(
+                    class.name.clone(),
+                    field,
+                    None,
+                    Some(item.clone()),
+                ));
+            }
+            for item in before.iter().filter(|item| !after.contains(item)) {
+                changes.push(ElementChange::changed(
+                    class.name.clone(),
+                    field,
+                    Some(item.clone()),
+                    None,
+                ));
+            }
+        }
+    }
+
+    for class in old {
+        if !new.iter().any(|c| c.name == class.name) {
+            changes.push(ElementChange::removed(class.name.clone(), None));
+        }
+    }
+
+    changes
+}
+
+fn push_class_assignment(out: &mut String, keyword: &str, ids: &[String], class: &str) {
+    if ids.is_empty() {
+        return;
+    }
+    match keyword {
+        "cssClass" => out.push_str(&format!("    cssClass \"{}\" {}\n", ids.join(","), class)),
+        _ => out.push_str(&format!("    {} {} {}\n", keyword, ids.join(","), class)),
+    }
+}
+
+fn push_link_style(out: &mut String, indexes: &[usize], style: &str) {
+    if indexes.is_empty() {
+        return;
+    }
+    let indexes: Vec<String> = indexes.iter().map(|i| i.to_string()).collect();
+    out.push_str(&format!("    linkStyle {} {}\n", indexes.join(","), style));
+}
+
+fn push_class_defs(out: &mut String) {
+    out.push_str(&format!("    classDef added {}\n", ADDED_STYLE));
+    out.push_str(&format!("    classDef removed {}\n", REMOVED_STYLE));
+    out.push_str(&format!("    classDef changed {}\n", CHANGED_STYLE));
+}
+
+fn highlight_flowchart(old: &ParsedDiagram, new: &ParsedDiagram) -> String {
+    let mut combined = new.clone();
+
+    let added_nodes: Vec<String> = new
+        .nodes
+        .iter()
+        .filter(|n| old.node(&n.id).is_none())
+        .map(|n| n.id.clone())
+        .collect();
+    let changed_nodes: Vec<String> = new
+        .nodes
+        .iter()
+        .filter(|n| {
+            old.node(&n.id)
+                .is_some_and(|o| o.label != n.label || o.shape != n.shape)
+        })
+        .map(|n| n.id.clone())
+        .collect();
+
+    let mut removed_nodes = Vec::new();
+    for node in old.nodes.iter().filter(|n| new.node(&n.id).is_none()) {
+        combined.nodes.push(node.clone());
+        removed_nodes.push(node.id.clone());
+
+        // Keep removed nodes inside their subgraph when it still exists
+        let parent = old.subgraphs.iter().find(|s| s.nodes.contains(&node.id));
+        if let Some(parent) = parent {
+            if let Some(subgraph) = combined.subgraphs.iter_mut().find(|s| s.id == parent.id) {
+                subgraph.nodes.push(node.id.clone());
+            }
+        }
+    }
+
+    let matched = match_edges(&old.edges, &new.edges);
+    let changed_edges: Vec<usize> = matched
+        .pairs
+        .iter()
+        .filter(|(i, j)| old.edges[*i] != new.edges[*j])
+        .map(|(_, j)| *j)
+        .collect();
+    let mut removed_edges = Vec::new();
+    for &i in &matched.removed {
+        removed_edges.push(combined.edges.len());
+        combined.edges.push(old.edges[i].clone());
+    }
+
+    let mut out = write_diagram(&combined).unwrap_or_default();
+    push_class_defs(&mut out);
+    push_class_assignment(&mut out, "class", &added_nodes, "added");
+    push_class_assignment(&mut out, "class", &removed_nodes, "removed");
+    push_class_assignment(&mut out, "class", &changed_nodes, "changed");
+    push_link_style(&mut out, &matched.added, ADDED_LINK);
+    push_link_style(&mut out, &removed_edges, REMOVED_LINK);
+    push_link_style(&mut out, &changed_edges, CHANGED_LINK);
+
+    out
+}
+
+fn highlight_class(old: &ParsedDiagram, new: &ParsedDiagram) -> String {
+    let mut combined = new.clone();
+
+    let added: Vec<String> = new
+        .classes
+        .iter()
+        .filter(|c| old.class(&c.name).is_none())
+        .map(|c| c.name.clone())
+        .collect();
+    let changed: Vec<String> = new
+        .classes
+        .iter()
+        .filter(|c| old.class(&c.name).is_some_and(|o| o != *c))
+        .map(|c| c.name.clone())
+        .collect();
+
+    let mut removed = Vec::new();
+    for class in old.classes.iter().filter(|c| new.class(&c.name).is_none()) {
+        combined.classes.push(class.clone());
+        removed.push(class.name.clone());
+    }
+
+    // Class diagrams cannot style relations, so removed ones are marked in
+    // their label instead
+    let matched = match_edges(&old.edges, &new.edges);
+    for &i in &matched.removed {
+        let edge = &old.edges[i];
+        combined.edges.push(Edge {
+            label: Some(match edge.label {
+                Some(ref label) => format!("{} {}", label, REMOVED_LABEL),
+                None => REMOVED_LABEL.to_string(),
+            }),
+            ..edge.clone()
+        });
+    }
+
+    let mut out = write_diagram(&combined).unwrap_or_default();
+    push_class_defs(&mut out);
+    push_class_assignment(&mut out, "cssClass", &added, "added");
+    push_class_assignment(&mut out, "cssClass", &removed, "removed");
+    push_class_assignment(&mut out, "cssClass", &changed, "changed");
+
+    out
+}
+
+fn highlight_sequence(old: &ParsedDiagram, new: &ParsedDiagram) -> String {
+    let mut out = String::from("sequenceDiagram\n");
+
+    let removed_participants = old
+        .participants
+        .iter()
+        .filter(|p| new.participant(&p.id).is_none());
+    for participant in new.participants.iter().chain(removed_participants) {
+        let keyword = if participant.is_actor {
+            "actor"
+        } else {
+            "participant"
+        };
+        match participant.label {
+            Some(ref label) => out.push_str(&format!(
+                "    {} {} as {}\n",
+                keyword, participant.id, label
+            )),
+            None => out.push_str(&format!("    {} {}\n", keyword, participant.id)),
+        }
+    }
+
+    // Removed messages follow the new message they came after, so notes and
+    // blocks keep the new revision's structure. Index 0 holds those removed
+    // before the first message, index `j + 1` those after message `j`.
+    let mut new_ops: Vec<Option<MessageOp>> = vec![None; new.messages.len()];
+    let mut removed_after: Vec<Vec<usize>> = vec![Vec::new(); new.messages.len() + 1];
+    let mut last = 0;
+    for op in message_ops(&old.messages, &new.messages) {
+        match op {
+            MessageOp::Removed(i) => removed_after[last].push(i),
+            MessageOp::Same(_, j) | MessageOp::Changed(_, j) | MessageOp::Added(j) => {
+                new_ops[j] = Some(op);
+                last = j + 1;
+            }
+        }
+    }
+
+    let mut block = HighlightBlock::default();
+    for step in &new.steps {
+        let SequenceStep::Message(j) = *step else {
+            block.close(&mut out);
+            if matches!(step, SequenceStep::BlockEnd) {
+                block.depth = (block.depth - 1).max(1);
+            }
+            let indent = match step {
+                SequenceStep::BlockSection { .. } => block.depth.max(2) - 1,
+                _ => block.depth,
+            };
+            out.push_str(&format!(
+                "{}{}\n",
+                "    ".repeat(indent),
+                format_step(new, step)
+            ));
+            if matches!(step, SequenceStep::BlockStart { .. }) {
+                block.depth += 1;
+            }
+            continue;
+        };
+
+        if j == 0 {
+            for &i in &removed_after[0] {
+                block.push(&mut out, Some(REMOVED_RECT), &old.messages[i]);
+            }
+        }
+        let color = match new_ops[j] {
+            Some(MessageOp::Added(_)) => Some(ADDED_RECT),
+            Some(MessageOp::Changed(..)) => Some(CHANGED_RECT),
+            _ => None,
+        };
+        block.push(&mut out, color, &new.messages[j]);
+        for &i in &removed_after[j + 1] {
+            block.push(&mut out, Some(REMOVED_RECT), &old.messages[i]);
+        }
+    }
+    if new.messages.is_empty() {
+        for &i in &removed_after[0] {
+            block.push(&mut out, Some(REMOVED_RECT), &old.messages[i]);
+        }
+    }
+    block.close(&mut out);
+
+    out
+}
+
+/// Consecutive messages with the same status share one highlighted `rect`
+/// block, nested at the depth of the enclosing sequence blocks
+struct HighlightBlock {
+    color: Option<&'static str>,
+    depth: usize,
+}
+
+impl Default for HighlightBlock {
+    fn default() -> Self {
+        Self {
+            color: None,
+            depth: 1,
+        }
+    }
+}
+
+impl HighlightBlock {
+    fn push(&mut self, out: &mut String, color: Option<&'static str>, message: &Message) {
+        if self.color != color {
+            self.close(out);
+            if let Some(color) = color {
+                out.push_str(&format!("{}rect {}\n", "    ".repeat(self.depth), color));
+            }
+            self.color = color;
+        }
+        let indent = self.depth + usize::from(self.color.is_some());
+        out.push_str(&format!(
+            "{}{}\n",
+            "    ".repeat(indent),
+            format_message(message)
+        ));
+    }
+
+    fn close(&mut self, out: &mut String) {
+        if self.color.take().is_some() {
+            out.push_str(&format!("{}end\n", "    ".repeat(self.depth)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::parse_diagram;
+
+    #[test]
+    fn test_flowchart_diff() {
+        let old = parse_diagram("flowchart TD\n    A[Start] --> B\n    B --> C\n").unwrap();
+        let new = parse_diagram("flowchart TD\n    A[Begin] --> B\n    B --> D\n").unwrap();
+        let diff = diff_diagrams(&old, &new);
+
+        assert_eq!(diff.nodes.len(), 3);
+        assert!(diff
+            .nodes
+            .iter()
+            .any(|c| c.key == "A" && c.field.as_deref() == Some("label")));
+        assert!(diff
+            .nodes
+            .iter()
+            .any(|c| c.key == "D" && c.change == ChangeKind::Added));
+        assert!(diff
+            .nodes
+            .iter()
+            .any(|c| c.key == "C" && c.change == ChangeKind::Removed));
+        assert_eq!(diff.edges.len(), 2);
+        assert_eq!(diff.summary.added, 2);
+        assert_eq!(diff.summary.removed, 2);
+        assert_eq!(diff.summary.changed, 1);
+
+        let source = highlight_source(&old, &new).unwrap();
+        assert!(source.contains("class D added"));
+        assert!(source.contains("class C removed"));
+        assert!(source.contains("linkStyle 2 stroke:#cf222e"));
+    }
+
+    #[test]
+    fn test_sequence_diff() {
+        let old = parse_diagram("sequenceDiagram\n    A->>B: hello\n    B-->>A: bye\n").unwrap();
+        let new = parse_diagram(
+            "sequenceDiagram\n    A->>B: hello\n    B-->>A: goodbye\n    A->>C: ping\n",
+        )
+        .unwrap();
+        let diff = diff_diagrams(&old, &new);
+
+        assert_eq!(diff.participants.len(), 1);
+        assert_eq!(diff.messages.len(), 2);
+        assert_eq!(diff.messages[0].field.as_deref(), Some("label"));
+        assert_eq!(diff.messages[1].change, ChangeKind::Added);
+
+        let source = highlight_source(&old, &new).unwrap();
+        assert!(source.contains(&format!("rect {}", CHANGED_RECT)));
+        assert!(source.contains(&format!("rect {}", ADDED_RECT)));
+    }
+
+    #[test]
+    fn test_sequence_diff_keeps_notes_and_blocks() {
+        let old = parse_diagram(
+            "sequenceDiagram\n    A->>B: login\n    alt valid\n    B-->>A: token\n    B->>B: audit\n    else invalid\n    B-->>A: error\n    end\n",
+        )
+        .unwrap();
+        let new = parse_diagram(
+            "sequenceDiagram\n    A->>B: login\n    Note over A,B: TLS only\n    alt valid\n    B-->>A: token\n    else invalid\n    B-->>A: denied\n    end\n",
+        )
+        .unwrap();
+
+        let source = highlight_source(&old, &new).unwrap();
+        let steps = [
+            "    A->>B: login\n",
+            "    Note over A,B: TLS only\n",
+            "    alt valid\n",
+            "        B-->>A: token\n",
+            &format!(
+                "        rect {}\n            B->>B: audit\n        end\n",
+                REMOVED_RECT
+            ),
+            "    else invalid\n",
+            &format!(
+                "        rect {}\n            B-->>A: denied\n        end\n",
+                CHANGED_RECT
+            ),
+            "    end\n",
+        ]
+        .concat();
+        assert!(source.ends_with(&steps), "{}", source);
+
+        assert_eq!(parse_diagram(&source).unwrap().messages.len(), 4);
+        let rewritten = parse_diagram(&write_diagram(&new).unwrap()).unwrap();
+        assert_eq!(rewritten.steps, new.steps);
+    }
+
+    #[test]
+    fn test_class_diff() {
+        let old = parse_diagram(
+            "classDiagram\n    class User\n    class Session\n    class Audit\n    User --> Session : opens\n    User --> Audit\n",
+        )
+        .unwrap();
+        let new = parse_diagram(
+            "classDiagram\n    class User {\n        +login()\n    }\n    class Session\n    class Token\n    User --> Token\n",
+        )
+        .unwrap();
+        let diff = diff_diagrams(&old, &new);
+        assert_eq!(diff.summary.added, 2);
+        assert_eq!(diff.summary.removed, 3);
+
+        let source = highlight_source(&old, &new).unwrap();
+        assert!(source.contains("cssClass \"Token\" added"));
+        assert!(source.contains("cssClass \"Audit\" removed"));
+        assert!(source.contains("cssClass \"User\" changed"));
+        assert!(source.contains("User --> Token\n"));
+        assert!(source.contains(&format!("User --> Session : opens {}", REMOVED_LABEL)));
+        assert!(source.contains(&format!("User --> Audit : {}", REMOVED_LABEL)));
+    }
+
+    #[test]
+    fn test_identical_diagrams_have_no_changes() {
+        let code =
+            "classDiagram\n    class User {\n        +login()\n    }\n    User --> Session\n";
+        let diagram = parse_diagram(code).unwrap();
+        assert!(!diff_diagrams(&diagram, &diagram).has_changes());
+    }
+}
@@ -0,0 +1,618 @@
+use crate::models::{MmdcCandidate, MmdcDiagnosis, MmdcProbe, MmdcSettings, MmdcSource};
+use crate::services::installer_service::{MermaidCliInstaller, MANAGED_DIR};
+use crate::utils::platform::get_node_binary_name;
+use crate::utils::{
+    ensure_directory_exists, get_mmdc_paths, get_npm_global_path, DefaultDirectories,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Instant, UNIX_EPOCH};
+
+/// Environment variable naming the mmdc executable to use
+pub const MMDC_PATH_ENV: &str = "MMDC_PATH";
+
+const SETTINGS_FILE: &str = "mmdc-settings.json";
+const CACHE_FILE: &str = "mmdc-discovery.json";
+
+#[cfg(target_os = "windows")]
+const MMDC_NAMES: &[&str] = &["mmdc.cmd", "mmdc.exe"];
+#[cfg(not(target_os = "windows"))]
+const MMDC_NAMES: &[&str] = &["mmdc"];
+
+/// A working mmdc and its `--version` output
+#[derive(Debug, Clone)]
+pub struct DiscoveredMmdc {
+    pub candidate: MmdcCandidate,
+    pub version: Option<String>,
+}
+
+/// Inputs a cached discovery depends on; it is discarded when any changes
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct CacheKey {
+    configured: Option<String>,
+    env_override: Option<String>,
+    /// mmdc of the active managed install
+    managed: Option<String>,
+    path_env: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DiscoveryCache {
+    key: CacheKey,
+    candidate: MmdcCandidate,
+    version: Option<String>,
+    /// Modification time of the executable in seconds since the epoch
+    modified: Option<u64>,
+}
+
+/// Finds mmdc and remembers the result across restarts
+pub struct MmdcDiscovery {
+    directory: Option<PathBuf>,
+}
+
+impl MmdcDiscovery {
+    /// Keep settings and the cache in `directory`; without one nothing is
+    /// persisted
+    pub fn new(directory: Option<PathBuf>) -> Self {
+        Self { directory }
+    }
+
+    /// Settings and cache in the app data directory
+    pub fn default_location() -> Self {
+        Self::new(DefaultDirectories::app_storage())
+    }
+
+    pub fn settings(&self) -> MmdcSettings {
+        self.read(SETTINGS_FILE).unwrap_or_default()
+    }
+
+    pub fn save_settings(&self, settings: &MmdcSettings) -> Result<(), String> {
+        self.write(SETTINGS_FILE, settings)?;
+        self.invalidate();
+        Ok(())
+    }
+
+    /// The cached mmdc if it is still valid, otherwise the first candidate
+    /// that runs. `npm root -g` is only asked when nothing else works.
+    pub fn discover(&self) -> Option<DiscoveredMmdc> {
+        let settings = self.settings();
+        let managed = self.managed_mmdc();
+        let key = cache_key(&settings, managed.as_deref());
+
+        if let Some(cache) = self.read::<DiscoveryCache>(CACHE_FILE) {
+            let modified = modified_secs(Path::new(&cache.candidate.path));
+            if cache.key == key && modified.is_some() && modified == cache.modified {
+                log::info!("Using cached mmdc at: {}", cache.candidate.path);
+                return Some(DiscoveredMmdc {
+                    candidate: cache.candidate,
+                    version: cache.version,
+                });
+            }
+            log::info!("Cached mmdc location is out of date");
+        }
+
+        let found = local_candidates(&settings, managed.as_deref())
+            .into_iter()
+            .chain(std::iter::once_with(npm_global_candidates).flatten())
+            .find_map(|candidate| {
+                log::debug!("Trying mmdc path: {}", candidate.path);
+                let probe = probe(&candidate);
+                probe.works().then_some(DiscoveredMmdc {
+                    candidate,
+                    version: probe.version,
+                })
+            });
+
+        match found {
+            Some(ref found) => {
+                log::info!("Found working mmdc at: {}", found.candidate.path);
+                self.store(key, found);
+            }
+            None => self.invalidate(),
+        }
+        found
+    }
+
+    /// Forget the cached location so the next discovery searches again
+    pub fn invalidate(&self) {
+        if let Some(ref directory) = self.directory {
+            let _ = fs::remove_file(directory.join(CACHE_FILE));
+        }
+    }
+
+    /// Every candidate in the order they are tried
+    pub fn candidates(&self) -> Vec<MmdcCandidate> {
+        let settings = self.settings();
+        dedup(
+            local_candidates(&settings, self.managed_mmdc().as_deref())
+                .into_iter()
+                .chain(npm_global_candidates()),
+        )
+    }
+
+    /// Run every candidate and report the outcome; the first working one is
+    /// cached
+    pub fn diagnose(&self) -> MmdcDiagnosis {
+        let settings = self.settings();
+        let probes: Vec<MmdcProbe> = self.candidates().iter().map(probe).collect();
+
+        let selected = probes.iter().find(|probe| probe.works());
+        match selected {
+            Some(probe) => self.store(
+                cache_key(&settings, self.managed_mmdc().as_deref()),
+                &DiscoveredMmdc {
+                    candidate: MmdcCandidate {
+                        path: probe.path.clone(),
+                        source: probe.source,
+                    },
+                    version: probe.version.clone(),
+                },
+            ),
+            None => self.invalidate(),
+        }
+
+        MmdcDiagnosis {
+            selected: selected.map(|probe| MmdcCandidate {
+                path: probe.path.clone(),
+                source: probe.source,
+            }),
+            mmdc_path_env: env::var(MMDC_PATH_ENV).ok(),
+            settings,
+            probes,
+        }
+    }
+
+    /// mmdc of the active install made by the app's installer
+    fn managed_mmdc(&self) -> Option<PathBuf> {
+        let root = self.directory.as_ref()?.join(MANAGED_DIR);
+        MermaidCliInstaller::new(root).active_mmdc()
+    }
+
+    fn store(&self, key: CacheKey, found: &DiscoveredMmdc) {
+        let cache = DiscoveryCache {
+            key,
+            modified: modified_secs(Path::new(&found.candidate.path)),
+            candidate: found.candidate.clone(),
+            version: found.version.clone(),
+        };
+        if let Err(e) = self.write(CACHE_FILE, &cache) {
+            log::warn!("Failed to cache mmdc location: {}", e);
+        }
+    }
+
+    fn read<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let content = fs::read_to_string(self.directory.as_ref()?.join(name)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write<T: Serialize>(&self, name: &str, value: &T) -> Result<(), String> {
+        let directory = self
+            .directory
+            .as_ref()
+            .ok_or_else(|| "Could not determine the app data directory".to_string())?;
+        ensure_directory_exists(directory)?;
+        let content = serde_json::to_string_pretty(value)
+            .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+        fs::write(directory.join(name), content)
+            .map_err(|e| format!("Failed to write {}: {}", name, e))
+    }
+}
+
+/// Run a candidate with `--version`, along with the Node it would use
+pub fn probe(candidate: &MmdcCandidate) -> MmdcProbe {
+    let start = Instant::now();
+    let path = Path::new(&candidate.path);
+    let mut probe = MmdcProbe {
+        path: candidate.path.clone(),
+        source: candidate.source,
+        exists: path.is_file(),
+        exit_code: None,
+        version: None,
+        stderr: String::new(),
+        error: None,
+        node_path: None,
+        node_version: None,
+        duration_ms: 0,
+    };
+    if !probe.exists {
+        probe.error = Some("File not found".to_string());
+        return probe;
+    }
+
+    if let Some(node) = node_for_mmdc(path) {
+        probe.node_version = node_version(&node);
+        probe.node_path = Some(node.to_string_lossy().to_string());
+    }
+
+    match mmdc_command(path).arg("--version").output() {
+        Ok(output) => {
+            probe.exit_code = output.status.code();
+            probe.stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            if output.status.success() {
+                probe.version = Some(String::from_utf8_lossy(&output.stdout).trim().to_string());
+            }
+        }
+        Err(e) => probe.error = Some(format!("Failed to run: {}", e)),
+    }
+    probe.duration_ms = start.elapsed().as_millis() as u64;
+    probe
+}
+
+/// Command running mmdc. Version managers keep `node` next to mmdc, and a
+/// GUI app often lacks their directories on `PATH`, so the mmdc directory
+/// is put first on `PATH` when it holds a Node binary.
+pub fn mmdc_command(mmdc: &Path) -> Command {
+    let mut command = Command::new(mmdc);
+    if let Some(dir) = node_dir(mmdc) {
+        let paths = std::iter::once(dir.to_path_buf()).chain(path_dirs());
+        if let Ok(path) = env::join_paths(paths) {
+            command.env("PATH", path);
+        }
+    }
+    command
+}
+
+/// Node executable mmdc runs with: the one beside it, or the first on `PATH`
+pub fn node_for_mmdc(mmdc: &Path) -> Option<PathBuf> {
+    node_dir(mmdc)
+        .map(|dir| dir.join(get_node_binary_name()))
+        .or_else(|| find_on_path(get_node_binary_name()))
+}
+
+fn node_dir(mmdc: &Path) -> Option<&Path> {
+    mmdc.parent()
+        .filter(|dir| dir.join(get_node_binary_name()).is_file())
+}
+
+fn node_version(node: &Path) -> Option<String> {
+    let output = Command::new(node).arg("--version").output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn cache_key(settings: &MmdcSettings, managed: Option<&Path>) -> CacheKey {
+    CacheKey {
+        configured: settings.mmdc_path.clone(),
+        env_override: env::var(MMDC_PATH_ENV).ok(),
+        managed: managed.map(|path| path.to_string_lossy().to_string()),
+        path_env: env::var("PATH").ok(),
+    }
+}
+
+/// Candidates found without running anything, most specific first. Only
+/// explicit user choices go before the managed install.
+fn local_candidates(settings: &MmdcSettings, managed: Option<&Path>) -> Vec<MmdcCandidate> {
+    let mut found: Vec<(MmdcSource, PathBuf)> = Vec::new();
+
+    let configured = settings.mmdc_path.as_deref().map(str::trim);
+    if let Some(path) = configured.filter(|path| !path.is_empty()) {
+        found.push((MmdcSource::Configured, resolve_command(path)));
+    }
+    if let Ok(path) = env::var(MMDC_PATH_ENV) {
+        if !path.trim().is_empty() {
+            found.push((MmdcSource::Environment, resolve_command(path.trim())));
+        }
+    }
+    if let Some(path) = managed {
+        found.push((MmdcSource::Managed, path.to_path_buf()));
+    }
+
+    let directories = [
+        (MmdcSource::Path, path_dirs().collect()),
+        (MmdcSource::Pnpm, pnpm_dirs()),
+        (MmdcSource::Yarn, yarn_dirs()),
+        (MmdcSource::Volta, volta_dirs()),
+        (MmdcSource::Nvm, nvm_dirs()),
+        (MmdcSource::Asdf, asdf_dirs()),
+        (MmdcSource::Npx, npx_dirs()),
+    ];
+    for (source, dirs) in directories {
+        found.extend(mmdc_in(&dirs).map(|path| (source, path)));
+    }
+
+    found.extend(
+        get_mmdc_paths()
+            .into_iter()
+            .map(|path| (MmdcSource::KnownLocation, PathBuf::from(path))),
+    );
+
+    dedup(found.into_iter().map(|(source, path)| MmdcCandidate {
+        path: path.to_string_lossy().to_string(),
+        source,
+    }))
+}
+
+/// mmdc in the bin directory of `npm root -g`, which takes a process spawn
+fn npm_global_candidates() -> Vec<MmdcCandidate> {
+    let Some(bin) = get_npm_global_path() else {
+        return Vec::new();
+    };
+    MMDC_NAMES
+        .iter()
+        .map(|name| MmdcCandidate {
+            path: bin.join(name).to_string_lossy().to_string(),
+            source: MmdcSource::NpmGlobal,
+        })
+        .collect()
+}
+
+fn dedup(candidates: impl IntoIterator<Item = MmdcCandidate>) -> Vec<MmdcCandidate> {
+    let mut unique: Vec<MmdcCandidate> = Vec::new();
+    for candidate in candidates {
+        if !unique.iter().any(|c| c.path == candidate.path) {
+            unique.push(candidate);
+        }
+    }
+    unique
+}
+
+/// Existing mmdc executables in the given directories
+fn mmdc_in(dirs: &[PathBuf]) -> impl Iterator<Item = PathBuf> + '_ {
+    dirs.iter()
+        .flat_map(|dir| MMDC_NAMES.iter().map(move |name| dir.join(name)))
+        .filter(|path| path.is_file())
+}
+
+/// A bare command name is looked up on `PATH`; paths are used as given
+fn resolve_command(command: &str) -> PathBuf {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.to_path_buf();
+    }
+    find_on_path(command).unwrap_or_else(|| path.to_path_buf())
+}
+
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    path_dirs()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+fn path_dirs() -> impl Iterator<Item = PathBuf> {
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|dir| !dir.as_os_str().is_empty())
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+fn pnpm_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = env_dir("PNPM_HOME").into_iter().collect();
+
+    #[cfg(target_os = "windows")]
+    dirs.extend(env_dir("LOCALAPPDATA").map(|dir| dir.join("pnpm")));
+
+    #[cfg(target_os = "macos")]
+    dirs.extend(DefaultDirectories::home().map(|home| home.join("Library").join("pnpm")));
+
+    #[cfg(target_os = "linux")]
+    dirs.extend(
+        env_dir("XDG_DATA_HOME")
+            .or_else(|| DefaultDirectories::home().map(|home| home.join(".local").join("share")))
+            .map(|dir| dir.join("pnpm")),
+    );
+
+    dirs
+}
+
+fn yarn_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(home) = DefaultDirectories::home() {
+        dirs.push(home.join(".yarn").join("bin"));
+        dirs.push(
+            home.join(".config")
+                .join("yarn")
+                .join("global")
+                .join("node_modules")
+                .join(".bin"),
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    dirs.extend(env_dir("LOCALAPPDATA").map(|dir| dir.join("Yarn").join("bin")));
+
+    dirs
+}
+
+fn volta_dirs() -> Vec<PathBuf> {
+    let mut homes: Vec<PathBuf> = env_dir("VOLTA_HOME").into_iter().collect();
+    homes.extend(DefaultDirectories::home().map(|home| home.join(".volta")));
+
+    #[cfg(target_os = "windows")]
+    homes.extend(env_dir("LOCALAPPDATA").map(|dir| dir.join("Volta")));
+
+    homes.into_iter().map(|home| home.join("bin")).collect()
+}
+
+/// The active nvm Node first, then installed versions newest first
+fn nvm_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = env_dir("NVM_BIN").into_iter().collect();
+
+    #[cfg(target_os = "windows")]
+    {
+        // nvm-windows keeps npm's global binaries in each version folder
+        dirs.extend(env_dir("NVM_SYMLINK"));
+        if let Some(nvm_home) = env_dir("NVM_HOME") {
+            dirs.extend(versions_newest_first(&nvm_home));
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let nvm_dir =
+            env_dir("NVM_DIR").or_else(|| DefaultDirectories::home().map(|home| home.join(".nvm")));
+        if let Some(nvm_dir) = nvm_dir {
+            let versions = versions_newest_first(&nvm_dir.join("versions").join("node"));
+            dirs.extend(versions.into_iter().map(|dir| dir.join("bin")));
+        }
+    }
+
+    dirs
+}
+
+/// asdf shims first, then installed Node versions newest first
+fn asdf_dirs() -> Vec<PathBuf> {
+    let data_dir = env_dir("ASDF_DATA_DIR")
+        .or_else(|| DefaultDirectories::home().map(|home| home.join(".asdf")));
+    let Some(data_dir) = data_dir else {
+        return Vec::new();
+    };
+
+    let mut dirs = vec![data_dir.join("shims")];
+    let versions = versions_newest_first(&data_dir.join("installs").join("nodejs"));
+    dirs.extend(versions.into_iter().map(|dir| dir.join("bin")));
+    dirs
+}
+
+/// Packages `npx` has downloaded, most recently used first
+fn npx_dirs() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let cache = env_dir("LOCALAPPDATA").map(|dir| dir.join("npm-cache"));
+    #[cfg(not(target_os = "windows"))]
+    let cache = DefaultDirectories::home().map(|home| home.join(".npm"));
+
+    let Some(Ok(entries)) = cache.map(|cache| fs::read_dir(cache.join("_npx"))) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<(Option<u64>, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path().join("node_modules").join(".bin"))
+        .filter(|dir| dir.is_dir())
+        .map(|dir| (modified_secs(&dir), dir))
+        .collect();
+    dirs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    dirs.into_iter().map(|(_, dir)| dir).collect()
+}
+
+/// Subdirectories named after versions like `v20.11.1`, newest first
+pub fn versions_newest_first(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut versions: Vec<(Vec<u32>, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let version = name
+                .trim_start_matches('v')
+                .split('.')
+                .map(|part| part.parse().unwrap_or(0))
+                .collect();
+            (version, path)
+        })
+        .collect();
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    versions.into_iter().map(|(_, path)| path).collect()
+}
+
+pub fn modified_secs(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp::TempDir;
+
+    #[test]
+    fn test_versions_newest_first() {
+        let temp = TempDir::new("mermaid_nvm");
+        let root = temp.path();
+        for version in ["v18.19.0", "v20.9.0", "v20.11.1"] {
+            fs::create_dir_all(root.join(version)).unwrap();
+        }
+
+        let names: Vec<String> = versions_newest_first(root)
+            .iter()
+            .map(|dir| dir.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["v20.11.1", "v20.9.0", "v18.19.0"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_configured_mmdc_is_probed_and_cached() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new("mermaid_discovery");
+        let root = temp.path();
+        let bin = root.join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        let write_stub = |name: &str, script: &str| {
+            let path = bin.join(name);
+            fs::write(&path, script).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let mmdc = write_stub("mmdc", "#!/bin/sh\necho 11.4.2\n");
+        let broken = write_stub(
+            "broken-mmdc",
+            "#!/bin/sh\necho 'node: not found' >&2\nexit 127\n",
+        );
+
+        let discovery = MmdcDiscovery::new(Some(root.join("storage")));
+        discovery
+            .save_settings(&MmdcSettings {
+                mmdc_path: Some(mmdc.to_string_lossy().to_string()),
+            })
+            .unwrap();
+
+        let candidates = discovery.candidates();
+        assert_eq!(candidates[0].source, MmdcSource::Configured);
+        let found = discovery.discover().unwrap();
+        assert_eq!(found.candidate.path, mmdc.to_string_lossy());
+        assert_eq!(found.version.as_deref(), Some("11.4.2"));
+
+        // A changed executable is only noticed through its modification time
+        let modified = fs::metadata(&mmdc).unwrap().modified().unwrap();
+        fs::write(&mmdc, "#!/bin/sh\necho 11.5.0\n").unwrap();
+        let file = fs::File::options().write(true).open(&mmdc).unwrap();
+        file.set_modified(modified).unwrap();
+        drop(file);
+        assert_eq!(
+            discovery.discover().unwrap().version.as_deref(),
+            Some("11.4.2")
+        );
+
+        let file = fs::File::options().write(true).open(&mmdc).unwrap();
+        file.set_modified(modified + std::time::Duration::from_secs(10))
+            .unwrap();
+        drop(file);
+        assert_eq!(
+            discovery.discover().unwrap().version.as_deref(),
+            Some("11.5.0")
+        );
+
+        let failed = probe(&MmdcCandidate {
+            path: broken.to_string_lossy().to_string(),
+            source: MmdcSource::Configured,
+        });
+        assert!(!failed.works());
+        assert_eq!(failed.exit_code, Some(127));
+        assert_eq!(failed.stderr, "node: not found");
+
+        let missing = probe(&MmdcCandidate {
+            path: root.join("missing").to_string_lossy().to_string(),
+            source: MmdcSource::KnownLocation,
+        });
+        assert!(!missing.exists);
+    }
+}
@@ -0,0 +1,1283 @@
+use crate::models::{
+    ArrowHead, DiagramKind, Edge, EdgeStroke, ImportedDiagram, Node, NodeShape, ParsedDiagram,
+    SkippedConstruct, Subgraph,
+};
+use crate::services::flowchart_service::{label_lines, representative};
+use crate::services::parser_service::canonical_arrow;
+use crate::services::{parse_diagram, write_diagram};
+use std::collections::{HashMap, HashSet};
+
+/// Flowchart keywords that cannot be used as node ids, in lowercase
+pub(crate) const RESERVED_IDS: [&str; 17] = [
+    "end",
+    "graph",
+    "flowchart",
+    "flowchart-elk",
+    "subgraph",
+    "direction",
+    "style",
+    "class",
+    "classdef",
+    "linkstyle",
+    "default",
+    "interpolate",
+    "click",
+    "call",
+    "href",
+    "acctitle",
+    "accdescr",
+];
+
+const NODE_ATTRIBUTES: [&str; 8] = [
+    "label",
+    "peripheries",
+    "shape",
+    "style",
+    "fillcolor",
+    "color",
+    "fontcolor",
+    "penwidth",
+];
+const EDGE_ATTRIBUTES: [&str; 5] = ["label", "style", "dir", "arrowhead", "arrowtail"];
+
+/// Convert a Graphviz `graph` or `digraph` into a Mermaid flowchart. Nodes
+/// without a `shape` keep Mermaid's default box rather than Graphviz's
+/// ellipse; attributes without a flowchart equivalent are listed in the
+/// result.
+pub fn import_dot(source: &str) -> Result<ImportedDiagram, String> {
+    DotParser::new(source)?.parse()
+}
+
+/// Write a Mermaid flowchart as a Graphviz digraph, for graph-analysis tools
+pub fn export_dot(code: &str) -> Result<String, String> {
+    let diagram = parse_diagram(code)?;
+    if diagram.kind != DiagramKind::Flowchart {
+        return Err("Only flowcharts can be exported to DOT".to_string());
+    }
+
+    let mut out = String::from("digraph {\n");
+    match diagram.direction.as_deref() {
+        None | Some("TD") | Some("TB") => {}
+        Some(direction) => out.push_str(&format!("    rankdir={};\n", direction)),
+    }
+    if let Some(ref title) = diagram.title {
+        out.push_str(&format!(
+            "    label={};\n    labelloc=t;\n",
+            dot_label(title)
+        ));
+    }
+    let to_subgraph = diagram.edges.iter().any(|edge| {
+        diagram
+            .subgraphs
+            .iter()
+            .any(|subgraph| subgraph.id == edge.from || subgraph.id == edge.to)
+    });
+    if to_subgraph {
+        out.push_str("    compound=true;\n");
+    }
+    out.push_str("    node [shape=box];\n");
+
+    for (index, subgraph) in diagram.subgraphs.iter().enumerate() {
+        if subgraph.parent.is_none() {
+            write_cluster(&diagram, index, 1, &mut out);
+        }
+    }
+    for node in &diagram.nodes {
+        let in_subgraph = diagram.subgraphs.iter().any(|s| s.nodes.contains(&node.id));
+        if !in_subgraph {
+            out.push_str(&format!("    {};\n", dot_node(&diagram, node)));
+        }
+    }
+    for edge in &diagram.edges {
+        if let Some(edge) = dot_edge(&diagram, edge) {
+            out.push_str(&format!("    {};\n", edge));
+        }
+    }
+
+    out.push_str("}\n");
+    Ok(out)
+}
+
+// ---------------------------------------------------------------------------
+// Import
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier or numeral
+    Id(String),
+    Quoted(String),
+    /// `<...>` string, without the outer brackets
+    Html(String),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Equals,
+    /// `;` or `,`
+    Separator,
+    Colon,
+    /// `->` or `--`
+    Edge,
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut line = 1;
+    let mut position = 0;
+    let mut line_start = true;
+    let mut concatenate = false;
+
+    while position < chars.len() {
+        let c = chars[position];
+        let next = chars.get(position + 1).copied();
+        if c == '\n' {
+            line += 1;
+            line_start = true;
+            position += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            position += 1;
+            continue;
+        }
+        let at_line_start = std::mem::replace(&mut line_start, false);
+        let start_line = line;
+
+        let token = match c {
+            // Comments and C preprocessor output
+            '#' if at_line_start => {
+                while position < chars.len() && chars[position] != '\n' {
+                    position += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('/') => {
+                while position < chars.len() && chars[position] != '\n' {
+                    position += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('*') => {
+                position += 2;
+                while position < chars.len()
+                    && !(chars[position] == '*' && chars.get(position + 1) == Some(&'/'))
+                {
+                    if chars[position] == '\n' {
+                        line += 1;
+                    }
+                    position += 1;
+                }
+                position += 2;
+                continue;
+            }
+            '+' => {
+                concatenate = true;
+                position += 1;
+                continue;
+            }
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '=' => Token::Equals,
+            ';' | ',' => Token::Separator,
+            ':' => Token::Colon,
+            '-' if matches!(next, Some('>') | Some('-')) => {
+                position += 1;
+                Token::Edge
+            }
+            '"' => {
+                let mut text = String::new();
+                position += 1;
+                loop {
+                    match (chars.get(position), chars.get(position + 1)) {
+                        (None, _) => {
+                            return Err(format!("Unterminated string on line {}", start_line))
+                        }
+                        (Some('"'), _) => break,
+                        (Some('\\'), Some('"')) => {
+                            text.push('"');
+                            position += 1;
+                        }
+                        // Line continuation
+                        (Some('\\'), Some('\n')) => {
+                            line += 1;
+                            position += 1;
+                        }
+                        (Some(&c), _) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            text.push(c);
+                        }
+                    }
+                    position += 1;
+                }
+                if std::mem::take(&mut concatenate) {
+                    if let Some((_, Token::Quoted(previous))) = tokens.last_mut() {
+                        previous.push_str(&text);
+                        position += 1;
+                        continue;
+                    }
+                }
+                Token::Quoted(text)
+            }
+            '<' => {
+                let mut depth = 0;
+                let start = position + 1;
+                loop {
+                    match chars.get(position) {
+                        None => {
+                            return Err(format!("Unterminated HTML string on line {}", start_line))
+                        }
+                        Some('<') => depth += 1,
+                        Some('>') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        Some('\n') => line += 1,
+                        _ => {}
+                    }
+                    position += 1;
+                }
+                Token::Html(chars[start..position].iter().collect())
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || !c.is_ascii() => {
+                let start = position;
+                position += 1;
+                while chars
+                    .get(position)
+                    .is_some_and(|&c| c.is_alphanumeric() || c == '_' || c == '.' || !c.is_ascii())
+                {
+                    position += 1;
+                }
+                tokens.push((
+                    start_line,
+                    Token::Id(chars[start..position].iter().collect()),
+                ));
+                continue;
+            }
+            c => return Err(format!("Unexpected '{}' on line {}", c, line)),
+        };
+        tokens.push((start_line, token));
+        position += 1;
+    }
+    Ok(tokens)
+}
+
+/// Attribute defaults and membership of a graph, subgraph or cluster body
+#[derive(Clone, Default)]
+struct Scope {
+    node_attributes: Vec<(String, String)>,
+    edge_attributes: Vec<(String, String)>,
+    /// Subgraph that nodes mentioned in the scope are placed in
+    cluster: Option<String>,
+    /// Whether this scope is the body of `cluster`
+    is_cluster: bool,
+    /// Nodes mentioned in the scope, the targets of edges to the subgraph
+    nodes: Vec<String>,
+}
+
+struct DotParser<'a> {
+    source_lines: Vec<&'a str>,
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    directed: bool,
+    strict: bool,
+    graph_name: String,
+    diagram: ParsedDiagram,
+    /// DOT node ids mapped to Mermaid ids
+    ids: HashMap<String, String>,
+    /// Mermaid ids used by nodes and subgraphs
+    taken: HashSet<String>,
+    /// Nodes with record shapes, whose labels list fields
+    records: HashSet<String>,
+    scopes: Vec<Scope>,
+    skipped: Vec<SkippedConstruct>,
+}
+
+impl<'a> DotParser<'a> {
+    fn new(source: &'a str) -> Result<Self, String> {
+        Ok(Self {
+            source_lines: source.lines().collect(),
+            tokens: tokenize(source)?,
+            position: 0,
+            directed: true,
+            strict: false,
+            graph_name: String::new(),
+            diagram: ParsedDiagram::new(DiagramKind::Flowchart),
+            ids: HashMap::new(),
+            taken: HashSet::new(),
+            records: HashSet::new(),
+            scopes: vec![Scope::default()],
+            skipped: Vec::new(),
+        })
+    }
+
+    fn parse(mut self) -> Result<ImportedDiagram, String> {
+        if self.keyword(0, "strict") {
+            self.strict = true;
+            self.position += 1;
+        }
+        if self.keyword(0, "digraph") {
+            self.directed = true;
+        } else if self.keyword(0, "graph") {
+            self.directed = false;
+        } else {
+            return Err("Expected a `graph` or `digraph` definition".to_string());
+        }
+        self.position += 1;
+        if self.peek() != Some(&Token::LBrace) {
+            self.graph_name = self.id()?;
+        }
+        self.expect(Token::LBrace, "{")?;
+        self.statements()?;
+        self.expect(Token::RBrace, "}")?;
+        if self.position < self.tokens.len() {
+            let line = self.line();
+            self.skip(line, "Only the first graph of a file is imported");
+        }
+
+        let mut code = write_diagram(&self.diagram)?;
+        if let Some(ref title) = self.diagram.title {
+            code = format!("---\ntitle: {}\n---\n{}", title, code);
+        }
+        self.skipped.sort_by_key(|skipped| skipped.line);
+        Ok(ImportedDiagram::new(code, self.skipped))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn keyword(&self, offset: usize, keyword: &str) -> bool {
+        matches!(
+            self.tokens.get(self.position + offset),
+            Some((_, Token::Id(id))) if id.eq_ignore_ascii_case(keyword)
+        )
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |&(line, _)| line)
+    }
+
+    fn expect(&mut self, token: Token, text: &str) -> Result<(), String> {
+        if self.peek() != Some(&token) {
+            return Err(format!("Expected `{}` on line {}", text, self.line()));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn skip(&mut self, line: usize, reason: &str) {
+        let source = self.source_lines.get(line - 1).copied().unwrap_or("");
+        self.skipped.push(SkippedConstruct {
+            line,
+            source: source.trim().to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    /// An identifier, numeral or string; HTML strings become plain text
+    fn id(&mut self) -> Result<String, String> {
+        let line = self.line();
+        let id = match self.peek() {
+            Some(Token::Id(id)) | Some(Token::Quoted(id)) => id.clone(),
+            Some(Token::Html(html)) => {
+                let text = html_text(html);
+                self.skip(line, "HTML labels are converted to plain text");
+                text
+            }
+            _ => return Err(format!("Expected an identifier on line {}", line)),
+        };
+        self.position += 1;
+        Ok(id)
+    }
+
+    fn statements(&mut self) -> Result<(), String> {
+        loop {
+            match self.peek() {
+                None => return Err("Unexpected end of file, expected `}`".to_string()),
+                Some(Token::RBrace) => return Ok(()),
+                Some(Token::Separator) => self.position += 1,
+                _ => self.statement()?,
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<(), String> {
+        let line = self.line();
+        let is_attribute_statement = ["graph", "node", "edge"]
+            .iter()
+            .any(|keyword| self.keyword(0, keyword))
+            && self.tokens.get(self.position + 1).map(|(_, t)| t) == Some(&Token::LBracket);
+
+        if is_attribute_statement {
+            let keyword = self.id()?.to_ascii_lowercase();
+            let attributes = self.attributes()?;
+            let scope = self.scopes.last_mut().expect("root scope");
+            match keyword.as_str() {
+                "node" => {
+                    scope.node_attributes.extend(attributes.iter().cloned());
+                    self.report_node_attributes(line, &attributes);
+                }
+                "edge" => {
+                    scope.edge_attributes.extend(attributes.iter().cloned());
+                    self.report_edge_attributes(line, &attributes);
+                }
+                _ => self.graph_attributes(line, &attributes),
+            }
+            return Ok(());
+        }
+
+        if self.keyword(0, "subgraph") || self.peek() == Some(&Token::LBrace) {
+            let nodes = self.subgraph()?;
+            if self.peek() == Some(&Token::Edge) {
+                self.edges(line, nodes)?;
+            }
+            return Ok(());
+        }
+
+        let id = self.id()?;
+        if self.peek() == Some(&Token::Equals) {
+            self.position += 1;
+            let value = self.id()?;
+            self.graph_attributes(line, &[(id, value)]);
+            return Ok(());
+        }
+        self.port(line)?;
+        let node = self.mention(&id);
+        if self.peek() == Some(&Token::Edge) {
+            return self.edges(line, vec![node]);
+        }
+        if self.peek() == Some(&Token::LBracket) {
+            let attributes = self.attributes()?;
+            self.report_node_attributes(line, &attributes);
+            self.apply_node_attributes(&node, &id, &attributes);
+        }
+        Ok(())
+    }
+
+    /// Skip a `:port:compass` suffix
+    fn port(&mut self, line: usize) -> Result<(), String> {
+        let mut has_port = false;
+        while self.peek() == Some(&Token::Colon) {
+            self.position += 1;
+            self.id()?;
+            has_port = true;
+        }
+        if has_port {
+            self.skip(line, "Ports are dropped; edges connect the whole node");
+        }
+        Ok(())
+    }
+
+    fn attributes(&mut self) -> Result<Vec<(String, String)>, String> {
+        let mut attributes = Vec::new();
+        while self.peek() == Some(&Token::LBracket) {
+            self.position += 1;
+            loop {
+                match self.peek() {
+                    Some(Token::RBracket) => break,
+                    Some(Token::Separator) => self.position += 1,
+                    _ => {
+                        let name = self.id()?;
+                        let value = match self.peek() {
+                            Some(Token::Equals) => {
+                                self.position += 1;
+                                self.id()?
+                            }
+                            _ => "true".to_string(),
+                        };
+                        attributes.push((name, value));
+                    }
+                }
+            }
+            self.expect(Token::RBracket, "]")?;
+        }
+        Ok(attributes)
+    }
+
+    fn subgraph(&mut self) -> Result<Vec<String>, String> {
+        let line = self.line();
+        let mut name = None;
+        if self.keyword(0, "subgraph") {
+            self.position += 1;
+            if self.peek() != Some(&Token::LBrace) {
+                name = Some(self.id()?);
+            }
+        }
+        if self.peek() != Some(&Token::LBrace) {
+            self.skip(
+                line,
+                "References to subgraphs defined elsewhere are dropped",
+            );
+            return Ok(Vec::new());
+        }
+        self.position += 1;
+
+        let parent = self.scopes.last().cloned().unwrap_or_default();
+        let mut scope = Scope {
+            nodes: Vec::new(),
+            is_cluster: false,
+            ..parent
+        };
+        // Only clusters are drawn as boxes; other subgraphs group attributes
+        if let Some(name) = name.filter(|name| name.starts_with("cluster")) {
+            let id = self.unique_id(&name);
+            self.diagram.subgraphs.push(Subgraph {
+                id: id.clone(),
+                label: None,
+                parent: scope.cluster.clone(),
+                direction: None,
+                nodes: Vec::new(),
+            });
+            scope.cluster = Some(id);
+            scope.is_cluster = true;
+        }
+        self.scopes.push(scope);
+        self.statements()?;
+        self.expect(Token::RBrace, "}")?;
+        let scope = self.scopes.pop().unwrap_or_default();
+        Ok(scope.nodes)
+    }
+
+    fn edges(&mut self, line: usize, first: Vec<String>) -> Result<(), String> {
+        let mut operands = vec![first];
+        while self.peek() == Some(&Token::Edge) {
+            self.position += 1;
+            if self.keyword(0, "subgraph") || self.peek() == Some(&Token::LBrace) {
+                operands.push(self.subgraph()?);
+            } else {
+                let id = self.id()?;
+                self.port(line)?;
+                operands.push(vec![self.mention(&id)]);
+            }
+        }
+        let mut attributes = self
+            .scopes
+            .last()
+            .cloned()
+            .unwrap_or_default()
+            .edge_attributes;
+        if self.peek() == Some(&Token::LBracket) {
+            let explicit = self.attributes()?;
+            self.report_edge_attributes(line, &explicit);
+            attributes.extend(explicit);
+        }
+
+        for pair in operands.windows(2) {
+            for from in &pair[0] {
+                for to in &pair[1] {
+                    self.add_edge(from, to, &attributes);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, attributes: &[(String, String)]) {
+        let mut label = None;
+        let mut stroke = EdgeStroke::Normal;
+        let mut direction = if self.directed { "forward" } else { "none" }.to_string();
+        let mut head = ArrowHead::Arrow;
+        let mut tail = ArrowHead::Arrow;
+        for (name, value) in attributes {
+            match name.as_str() {
+                "label" => label = Some(dot_text(value, "")),
+                "style" => {
+                    for style in value.split(',').map(str::trim) {
+                        stroke = match style {
+                            "dashed" | "dotted" => EdgeStroke::Dotted,
+                            "bold" => EdgeStroke::Thick,
+                            "invis" => EdgeStroke::Invisible,
+                            "solid" => EdgeStroke::Normal,
+                            _ => stroke,
+                        }
+                    }
+                }
+                "dir" => direction = value.clone(),
+                "arrowhead" => head = arrow_head(value).0,
+                "arrowtail" => tail = arrow_head(value).0,
+                _ => {}
+            }
+        }
+        let (head, tail) = match direction.as_str() {
+            "both" => (head, tail),
+            "back" => (ArrowHead::None, tail),
+            "none" => (ArrowHead::None, ArrowHead::None),
+            _ => (head, ArrowHead::None),
+        };
+        // Mermaid has no arrows that only point backwards
+        let (from, to, head, tail) = match (head, tail) {
+            (ArrowHead::None, ArrowHead::None) => (from, to, head, tail),
+            (ArrowHead::None, tail) => (to, from, tail, ArrowHead::None),
+            _ => (from, to, head, tail),
+        };
+
+        if self.strict
+            && self
+                .diagram
+                .edges
+                .iter()
+                .any(|edge| edge.from == from && edge.to == to)
+        {
+            return;
+        }
+        self.diagram.edges.push(Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            label: label.filter(|label| !label.is_empty()),
+            arrow: canonical_arrow(stroke, head, tail),
+            stroke,
+            head,
+            tail,
+        });
+    }
+
+    fn graph_attributes(&mut self, line: usize, attributes: &[(String, String)]) {
+        let scope = self.scopes.last().cloned().unwrap_or_default();
+        let is_root = self.scopes.len() == 1;
+        let mut dropped = Vec::new();
+        for (name, value) in attributes {
+            match name.as_str() {
+                "rankdir" if is_root => {
+                    self.diagram.direction = match value.to_ascii_uppercase().as_str() {
+                        "LR" => Some("LR".to_string()),
+                        "RL" => Some("RL".to_string()),
+                        "BT" => Some("BT".to_string()),
+                        _ => Some("TD".to_string()),
+                    }
+                }
+                "label" if is_root => {
+                    self.diagram.title =
+                        Some(dot_text(value, &self.graph_name).replace("<br/>", " "))
+                }
+                "label" if scope.is_cluster => {
+                    let label = dot_text(value, "");
+                    let id = scope.cluster.clone().unwrap_or_default();
+                    if let Some(subgraph) = self.diagram.subgraphs.iter_mut().find(|s| s.id == id) {
+                        subgraph.label = Some(label);
+                    }
+                }
+                "rank" => self.skip(line, "Rank constraints are dropped"),
+                // Title placement, and clipping at clusters that export needs
+                "labelloc" | "labeljust" | "compound" if is_root => {}
+                _ => dropped.push(name.as_str()),
+            }
+        }
+        if !dropped.is_empty() {
+            let reason = format!(
+                "Unsupported graph attributes dropped: {}",
+                dropped.join(", ")
+            );
+            self.skip(line, &reason);
+        }
+    }
+
+    fn report_node_attributes(&mut self, line: usize, attributes: &[(String, String)]) {
+        let dropped: Vec<&str> = attributes
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| !NODE_ATTRIBUTES.contains(name))
+            .collect();
+        if !dropped.is_empty() {
+            let reason = format!(
+                "Unsupported node attributes dropped: {}",
+                dropped.join(", ")
+            );
+            self.skip(line, &reason);
+        }
+        for (name, value) in attributes {
+            let approximate = match name.as_str() {
+                "shape" => node_shape(value).is_none(),
+                "fillcolor" | "color" | "fontcolor" => css_color(value).is_none(),
+                _ => false,
+            };
+            if approximate {
+                let reason = format!("{} `{}` has no Mermaid equivalent", name, value);
+                self.skip(line, &reason);
+            }
+        }
+    }
+
+    fn report_edge_attributes(&mut self, line: usize, attributes: &[(String, String)]) {
+        let dropped: Vec<&str> = attributes
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| !EDGE_ATTRIBUTES.contains(name))
+            .collect();
+        if !dropped.is_empty() {
+            let reason = format!(
+                "Unsupported edge attributes dropped: {}",
+                dropped.join(", ")
+            );
+            self.skip(line, &reason);
+        }
+        for (name, value) in attributes {
+            if matches!(name.as_str(), "arrowhead" | "arrowtail") && !arrow_head(value).1 {
+                let reason = format!("`{}` arrows are drawn as plain arrows", value);
+                self.skip(line, &reason);
+            }
+        }
+    }
+
+    /// The Mermaid id of a DOT node, creating it with the current defaults
+    /// on first use
+    fn mention(&mut self, dot_id: &str) -> String {
+        let id = match self.ids.get(dot_id) {
+            Some(id) => id.clone(),
+            None => {
+                let id = self.unique_id(dot_id);
+                self.ids.insert(dot_id.to_string(), id.clone());
+                let mut node = Node::new(&id);
+                if id != dot_id {
+                    node.label = Some(dot_id.to_string());
+                }
+                self.diagram.nodes.push(node);
+                let defaults = self
+                    .scopes
+                    .last()
+                    .cloned()
+                    .unwrap_or_default()
+                    .node_attributes;
+                self.apply_node_attributes(&id, dot_id, &defaults);
+                id
+            }
+        };
+
+        // A node belongs to the first cluster it is mentioned in
+        let cluster = self.scopes.last().and_then(|scope| scope.cluster.clone());
+        let placed = self.diagram.subgraphs.iter().any(|s| s.nodes.contains(&id));
+        if let (Some(cluster), false) = (cluster, placed) {
+            if let Some(subgraph) = self.diagram.subgraphs.iter_mut().find(|s| s.id == cluster) {
+                subgraph.nodes.push(id.clone());
+            }
+        }
+        for scope in &mut self.scopes {
+            if !scope.nodes.contains(&id) {
+                scope.nodes.push(id.clone());
+            }
+        }
+        id
+    }
+
+    fn apply_node_attributes(&mut self, id: &str, dot_id: &str, attributes: &[(String, String)]) {
+        let shape = attributes.iter().rev().find(|(name, _)| name == "shape");
+        if let Some((_, shape)) = shape {
+            match matches!(shape.as_str(), "record" | "Mrecord") {
+                true => self.records.insert(id.to_string()),
+                false => self.records.remove(id),
+            };
+        }
+        let is_record = self.records.contains(id);
+        let Some(node) = self.diagram.nodes.iter_mut().find(|node| node.id == id) else {
+            return;
+        };
+        let mut filled = false;
+        let mut color = None;
+        for (name, value) in attributes {
+            match name.as_str() {
+                "label" => {
+                    let label = dot_text(value, dot_id);
+                    node.label = (label != node.id).then_some(label);
+                }
+                "shape" => {
+                    node.shape = node_shape(value).unwrap_or(NodeShape::Rect);
+                    if value == "Mrecord" {
+                        node.shape = NodeShape::Round;
+                    }
+                }
+                "style" => {
+                    for style in value.split(',').map(str::trim) {
+                        match style {
+                            "filled" => filled = true,
+                            "rounded" if node.shape == NodeShape::Rect => {
+                                node.shape = NodeShape::Round
+                            }
+                            "dashed" | "dotted" => {
+                                set_style(&mut node.styles, "stroke-dasharray", "5 5")
+                            }
+                            "bold" => set_style(&mut node.styles, "stroke-width", "2px"),
+                            _ => {}
+                        }
+                    }
+                }
+                "fillcolor" => {
+                    if let Some(fill) = css_color(value) {
+                        set_style(&mut node.styles, "fill", &fill);
+                    }
+                }
+                "color" => {
+                    if let Some(stroke) = css_color(value) {
+                        set_style(&mut node.styles, "stroke", &stroke);
+                        color = Some(stroke);
+                    }
+                }
+                "fontcolor" => {
+                    if let Some(text) = css_color(value) {
+                        set_style(&mut node.styles, "color", &text);
+                    }
+                }
+                "peripheries" if value == "2" && node.shape == NodeShape::Rect => {
+                    node.shape = NodeShape::Subroutine
+                }
+                "penwidth" if value.parse::<f64>().is_ok() => {
+                    set_style(&mut node.styles, "stroke-width", &format!("{}px", value));
+                }
+                _ => {}
+            }
+        }
+        if is_record {
+            node.label = node.label.as_deref().map(record_text);
+        }
+        // Filled nodes without a fill colour use the outline colour
+        if filled && !node.styles.iter().any(|style| style.starts_with("fill:")) {
+            let fill = color.unwrap_or_else(|| "lightgrey".to_string());
+            set_style(&mut node.styles, "fill", &fill);
+        }
+    }
+
+    fn unique_id(&mut self, name: &str) -> String {
+        let mut base: String = name
+            .chars()
+            .map(|c| match c.is_alphanumeric() || c == '_' {
+                true => c,
+                false => '_',
+            })
+            .collect();
+        if base.is_empty() || RESERVED_IDS.contains(&base.to_ascii_lowercase().as_str()) {
+            base.push('_');
+        }
+        let mut id = base.clone();
+        let mut counter = 2;
+        while self.taken.contains(&id) {
+            id = format!("{}_{}", base, counter);
+            counter += 1;
+        }
+        self.taken.insert(id.clone());
+        id
+    }
+}
+
+fn set_style(styles: &mut Vec<String>, property: &str, value: &str) {
+    let prefix = format!("{}:", property);
+    styles.retain(|style| !style.starts_with(&prefix));
+    styles.push(format!("{}{}", prefix, value));
+}
+
+/// Flowchart shape for a Graphviz shape, `None` when it is only approximated
+fn node_shape(shape: &str) -> Option<NodeShape> {
+    Some(match shape {
+        "box" | "rect" | "rectangle" | "square" => NodeShape::Rect,
+        "ellipse" | "oval" => NodeShape::Stadium,
+        "circle" => NodeShape::Circle,
+        "doublecircle" => NodeShape::DoubleCircle,
+        "diamond" => NodeShape::Rhombus,
+        "hexagon" => NodeShape::Hexagon,
+        "parallelogram" => NodeShape::Parallelogram,
+        "trapezium" => NodeShape::Trapezoid,
+        "invtrapezium" => NodeShape::TrapezoidAlt,
+        "cylinder" => NodeShape::Cylinder,
+        "cds" => NodeShape::Asymmetric,
+        "record" | "Mrecord" => NodeShape::Rect,
+        _ => return None,
+    })
+}
+
+/// Flowchart head for a Graphviz arrow type, and whether it is exact
+fn arrow_head(arrow: &str) -> (ArrowHead, bool) {
+    match arrow {
+        "none" => (ArrowHead::None, true),
+        "normal" | "vee" | "open" => (ArrowHead::Arrow, true),
+        "dot" | "odot" => (ArrowHead::Circle, true),
+        "tee" => (ArrowHead::Cross, true),
+        _ => (ArrowHead::Arrow, false),
+    }
+}
+
+/// CSS colour for a Graphviz colour; HSV triples and colour schemes have
+/// none
+fn css_color(color: &str) -> Option<String> {
+    let color = color.trim();
+    let is_hex = color.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    });
+    let is_name = !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic());
+    (is_hex || is_name).then(|| color.to_string())
+}
+
+/// Label text with Graphviz escapes resolved and line breaks as `<br/>`
+fn dot_text(text: &str, name: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('l') | Some('r') if chars.peek().is_some() => out.push_str("<br/>"),
+            Some('n') | Some('l') | Some('r') => {}
+            Some('N') | Some('G') => out.push_str(name),
+            Some('E') | Some('T') | Some('H') => {}
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Record fields such as `{<in> a|b}` flattened into one label
+fn record_text(label: &str) -> String {
+    let mut out = String::new();
+    let mut in_port = false;
+    for c in label.chars() {
+        match c {
+            '<' if !in_port => in_port = true,
+            '>' if in_port => in_port = false,
+            _ if in_port => {}
+            '{' | '}' => {}
+            '|' => out.push_str(" | "),
+            _ => out.push(c),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// HTML label text, keeping line breaks
+pub(crate) fn html_text(html: &str) -> String {
+    let mut out = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('>')
+            .map_or(rest.len(), |end| start + end + 1);
+        if rest[start..end].to_ascii_lowercase().starts_with("<br") {
+            out.push_str("<br/>");
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Export
+// ---------------------------------------------------------------------------
+
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A quoted DOT label, with `<br>` tags as line breaks
+fn dot_label(label: &str) -> String {
+    let label = label.trim_matches('`').replace("#quot;", "\"");
+    let lines: Vec<String> = label_lines(&label)
+        .iter()
+        .map(|line| line.replace('\\', "\\\\").replace('"', "\\\""))
+        .collect();
+    format!("\"{}\"", lines.join("\\n"))
+}
+
+fn write_cluster(diagram: &ParsedDiagram, index: usize, depth: usize, out: &mut String) {
+    let subgraph = &diagram.subgraphs[index];
+    let indent = "    ".repeat(depth);
+    out.push_str(&format!(
+        "{}subgraph {} {{\n",
+        indent,
+        dot_string(&format!("cluster_{}", subgraph.id))
+    ));
+    let label = subgraph.label.as_deref().unwrap_or(&subgraph.id);
+    out.push_str(&format!("{}    label={};\n", indent, dot_label(label)));
+    for id in &subgraph.nodes {
+        if let Some(node) = diagram.node(id) {
+            out.push_str(&format!("{}    {};\n", indent, dot_node(diagram, node)));
+        }
+    }
+    for (child, _) in diagram
+        .subgraphs
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.parent.as_deref() == Some(&subgraph.id))
+    {
+        write_cluster(diagram, child, depth + 1, out);
+    }
+    out.push_str(&format!("{}}}\n", indent));
+}
+
+fn dot_node(diagram: &ParsedDiagram, node: &Node) -> String {
+    let mut attributes = Vec::new();
+    if let Some(ref label) = node.label {
+        attributes.push(format!("label={}", dot_label(label)));
+    }
+    let mut styles = Vec::new();
+    let shape = match node.shape {
+        NodeShape::Rect => None,
+        NodeShape::Round => {
+            styles.push("rounded");
+            None
+        }
+        NodeShape::Stadium => Some("oval"),
+        NodeShape::Subroutine => {
+            attributes.push("peripheries=2".to_string());
+            None
+        }
+        NodeShape::Cylinder => Some("cylinder"),
+        NodeShape::Circle => Some("circle"),
+        NodeShape::DoubleCircle => Some("doublecircle"),
+        NodeShape::Asymmetric => Some("cds"),
+        NodeShape::Rhombus => Some("diamond"),
+        NodeShape::Hexagon => Some("hexagon"),
+        NodeShape::Parallelogram | NodeShape::ParallelogramAlt => Some("parallelogram"),
+        NodeShape::Trapezoid => Some("trapezium"),
+        NodeShape::TrapezoidAlt => Some("invtrapezium"),
+    };
+    if let Some(shape) = shape {
+        attributes.push(format!("shape={}", shape));
+    }
+
+    // classDef styles first, so that `style` statements override them
+    let css = diagram
+        .class_defs
+        .iter()
+        .filter(|class_def| node.classes.contains(&class_def.name))
+        .flat_map(|class_def| class_def.styles.iter())
+        .chain(node.styles.iter());
+    let mut properties: Vec<(&str, String)> = Vec::new();
+    for declaration in css {
+        if let Some((property, value)) = declaration.split_once(':') {
+            let value = value.trim();
+            let attribute = match property.trim() {
+                "fill" => {
+                    if !styles.contains(&"filled") {
+                        styles.push("filled");
+                    }
+                    "fillcolor"
+                }
+                "stroke" => "color",
+                "color" => "fontcolor",
+                "stroke-width" => "penwidth",
+                "stroke-dasharray" => {
+                    if !styles.contains(&"dashed") {
+                        styles.push("dashed");
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            let value = value.trim_end_matches("px").to_string();
+            properties.retain(|(name, _)| *name != attribute);
+            properties.push((attribute, value));
+        }
+    }
+    if !styles.is_empty() {
+        attributes.push(format!("style={}", dot_string(&styles.join(","))));
+    }
+    for (name, value) in properties {
+        attributes.push(format!("{}={}", name, dot_string(&value)));
+    }
+
+    match attributes.is_empty() {
+        true => dot_string(&node.id),
+        false => format!("{} [{}]", dot_string(&node.id), attributes.join(", ")),
+    }
+}
+
+/// The node an edge attaches to, and the cluster it should clip at when the
+/// Mermaid edge points at a whole subgraph
+fn edge_end(diagram: &ParsedDiagram, id: &str) -> Option<(String, Option<String>)> {
+    match diagram.subgraphs.iter().position(|s| s.id == id) {
+        Some(index) => representative(diagram, index).map(|node| {
+            (
+                diagram.nodes[node].id.clone(),
+                Some(format!("cluster_{}", id)),
+            )
+        }),
+        None => Some((id.to_string(), None)),
+    }
+}
+
+fn dot_edge(diagram: &ParsedDiagram, edge: &Edge) -> Option<String> {
+    let (from, from_cluster) = edge_end(diagram, &edge.from)?;
+    let (to, to_cluster) = edge_end(diagram, &edge.to)?;
+
+    let mut attributes = Vec::new();
+    if let Some(ref label) = edge.label {
+        attributes.push(format!("label={}", dot_label(label)));
+    }
+    match edge.stroke {
+        EdgeStroke::Normal => {}
+        EdgeStroke::Dotted => attributes.push("style=dotted".to_string()),
+        EdgeStroke::Thick => attributes.push("penwidth=2".to_string()),
+        EdgeStroke::Invisible => attributes.push("style=invis".to_string()),
+    }
+    // Graphviz has no cross head; a tee is the closest blocked end
+    let arrow = |head: ArrowHead| match head {
+        ArrowHead::None => "none",
+        ArrowHead::Arrow => "normal",
+        ArrowHead::Circle => "dot",
+        ArrowHead::Cross => "tee",
+    };
+    match (edge.tail, edge.head) {
+        _ if edge.stroke == EdgeStroke::Invisible => {}
+        (ArrowHead::None, ArrowHead::Arrow) => {}
+        (ArrowHead::None, head) => attributes.push(format!("arrowhead={}", arrow(head))),
+        (tail, head) => {
+            attributes.push("dir=both".to_string());
+            attributes.push(format!("arrowtail={}", arrow(tail)));
+            if head != ArrowHead::Arrow {
+                attributes.push(format!("arrowhead={}", arrow(head)));
+            }
+        }
+    }
+    if let Some(cluster) = from_cluster {
+        attributes.push(format!("ltail={}", dot_string(&cluster)));
+    }
+    if let Some(cluster) = to_cluster {
+        attributes.push(format!("lhead={}", dot_string(&cluster)));
+    }
+
+    let edge = format!("{} -> {}", dot_string(&from), dot_string(&to));
+    Some(match attributes.is_empty() {
+        true => edge,
+        false => format!("{} [{}]", edge, attributes.join(", ")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_dot() {
+        let imported = import_dot(
+            r##"// generated
+digraph deps {
+    rankdir=LR; label="Build graph"; splines=ortho;
+    node [shape=box, fontname="Helvetica"];
+    subgraph cluster_core {
+        label = "Core";
+        parser [label="Parser\nstage"];
+        "end" [shape=cylinder, style=filled, fillcolor="#eeeeee"];
+    }
+    app:out -> parser -> "end" [label="feeds"];
+    app -> {util cli} [style=dashed];
+    cli -> app [dir=back, arrowtail=diamond];
+}"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            imported.code,
+            [
+                "---",
+                "title: Build graph",
+                "---",
+                "flowchart LR",
+                "    subgraph cluster_core[\"Core\"]",
+                "        parser[\"Parser<br/>stage\"]",
+                "        end_[(\"end\")]",
+                "    end",
+                "    app",
+                "    util",
+                "    cli",
+                "    app -->|feeds| parser",
+                "    parser -->|feeds| end_",
+                "    app -.-> util",
+                "    app -.-> cli",
+                "    app --> cli",
+                "    style end_ fill:#eeeeee",
+                "",
+            ]
+            .join("\n")
+        );
+        let reasons: Vec<(usize, &str)> = imported
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.line, skipped.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (3, "Unsupported graph attributes dropped: splines"),
+                (4, "Unsupported node attributes dropped: fontname"),
+                (10, "Ports are dropped; edges connect the whole node"),
+                (12, "`diamond` arrows are drawn as plain arrows"),
+            ]
+        );
+        assert!(parse_diagram(&imported.code).is_ok());
+
+        let undirected =
+            import_dot("strict graph { a -- b; a -- b; b -- c [dir=forward] }").unwrap();
+        assert_eq!(
+            undirected.code,
+            "flowchart TD\n    a\n    b\n    c\n    a --- b\n    b --> c\n"
+        );
+        assert!(import_dot("flowchart TD\n    A --> B").is_err());
+
+        let keywords = import_dot("digraph { click -> classDef; linkStyle -> direction }").unwrap();
+        assert_eq!(
+            keywords.code,
+            "flowchart TD\n    click_[\"click\"]\n    classDef_[\"classDef\"]\n    linkStyle_[\"linkStyle\"]\n    direction_[\"direction\"]\n    click_ --> classDef_\n    linkStyle_ --> direction_\n"
+        );
+        assert_eq!(parse_diagram(&keywords.code).unwrap().edges.len(), 2);
+    }
+
+    #[test]
+    fn test_export_dot() {
+        let dot = export_dot(
+            r##"flowchart LR
+    subgraph api["API layer"]
+        A(["Gateway"]) -->|"routes"| B{"Auth?"}
+    end
+    B -.->|no| C["Say #quot;no#quot;"]
+    B ==> api
+    C --o D
+    style C fill:#f96,stroke-width:2px
+"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            dot,
+            r##"digraph {
+    rankdir=LR;
+    compound=true;
+    node [shape=box];
+    subgraph "cluster_api" {
+        label="API layer";
+        "A" [label="Gateway", shape=oval];
+        "B" [label="Auth?", shape=diamond];
+    }
+    "C" [label="Say \"no\"", style="filled", fillcolor="#f96", penwidth="2"];
+    "D";
+    "A" -> "B" [label="routes"];
+    "B" -> "C" [label="no", style=dotted];
+    "B" -> "A" [penwidth=2, lhead="cluster_api"];
+    "C" -> "D" [arrowhead=dot];
+}
+"##
+        );
+        assert!(export_dot("sequenceDiagram\n    A->>B: hi").is_err());
+
+        // Round trip through the importer keeps the structure
+        let imported = import_dot(&dot).unwrap();
+        let original = parse_diagram(&imported.code).unwrap();
+        assert_eq!(original.nodes.len(), 4);
+        assert_eq!(original.subgraphs.len(), 1);
+    }
+}
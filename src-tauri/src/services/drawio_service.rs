@@ -0,0 +1,944 @@
+use crate::models::{
+    ArrowHead, DiagramFormat, DiagramKind, DiagramOptions, Edge, EdgeStroke, ImportedDiagram, Node,
+    NodeShape, ParsedDiagram, SkippedConstruct, Subgraph,
+};
+use crate::services::dot_service::{html_text, RESERVED_IDS};
+use crate::services::flowchart_service::{
+    label_lines, layout_flowchart, node_paint, FlowchartLayout, FONT_SIZE, TITLE_FONT_SIZE,
+};
+use crate::services::layout_service::Point;
+use crate::services::native_service::{escape_xml, native_config, num, ThemeVariables};
+use crate::services::parser_service::canonical_arrow;
+use crate::services::renderer_service::{RenderError, RenderJob};
+use crate::services::{parse_diagram, write_diagram};
+use resvg::usvg::roxmltree;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
+/// Cell id prefixes of exported diagrams, dropped again on import
+const NODE_PREFIX: &str = "node-";
+const SUBGRAPH_PREFIX: &str = "subgraph-";
+
+/// Write a Mermaid flowchart as a draw.io (diagrams.net) file, with nodes,
+/// subgraphs and edge bends where the native renderer places them. Themes
+/// without a native renderer fall back to the default theme's colours.
+pub fn export_drawio(code: &str, options: &DiagramOptions) -> Result<String, String> {
+    if parse_diagram(code)?.kind != DiagramKind::Flowchart {
+        return Err("Only flowcharts can be exported to draw.io".to_string());
+    }
+    let job = RenderJob {
+        code,
+        diagram_type: Some("flowchart"),
+        format: DiagramFormat::Svg,
+        options,
+        extra_config: None,
+    };
+    let config = native_config(&job)?;
+    let theme = ThemeVariables::from_config(&config)
+        .or_else(|_| ThemeVariables::from_config(&json!({})))?;
+    let placed = layout_flowchart(code, &config, &theme.font_family).map_err(|e| match e {
+        RenderError::Unsupported(message)
+        | RenderError::Unavailable(message)
+        | RenderError::Failed(message) => message,
+    })?;
+
+    let diagram = &placed.diagram;
+    let font = format!(
+        "fontFamily={};fontSize={};",
+        font_name(&theme.font_family),
+        num(FONT_SIZE)
+    );
+    let origin = Point {
+        x: placed.padding,
+        y: placed.padding + placed.title_height,
+    };
+    let mut cells = vec![
+        r#"<mxCell id="0"/>"#.to_string(),
+        r#"<mxCell id="1" parent="0"/>"#.to_string(),
+    ];
+
+    if let Some(ref title) = diagram.title {
+        let style = format!(
+            "text;html=1;align=center;verticalAlign=middle;fontFamily={};fontSize={};fontColor={};",
+            font_name(&theme.font_family),
+            num(TITLE_FONT_SIZE),
+            theme.title_color
+        );
+        let width = placed.layout.width + 2.0 * placed.padding;
+        cells.push(vertex(
+            "title",
+            "1",
+            &html_label(title),
+            &style,
+            (0.0, placed.padding, width, TITLE_FONT_SIZE),
+        ));
+    }
+
+    // Subgraphs as containers, outer ones first; draw.io positions the
+    // children of a container relative to it
+    for index in placed.cluster_order() {
+        let Some(rect) = placed.layout.clusters[index] else {
+            continue;
+        };
+        let subgraph = &diagram.subgraphs[index];
+        let parent = subgraph
+            .parent
+            .as_ref()
+            .and_then(|p| diagram.subgraphs.iter().position(|s| s.id == *p));
+        let (parent_id, offset) = container(&placed, parent, origin);
+        let style = format!(
+            "rounded=0;whiteSpace=wrap;html=1;container=1;collapsible=0;verticalAlign=top;fillColor={};strokeColor={};fontColor={};{}",
+            theme.cluster_bkg, theme.cluster_border, theme.title_color, font
+        );
+        cells.push(vertex(
+            &format!("{}{}", SUBGRAPH_PREFIX, subgraph.id),
+            &parent_id,
+            &html_label(subgraph.label.as_deref().unwrap_or(&subgraph.id)),
+            &style,
+            (
+                rect.x - offset.x,
+                rect.y - offset.y,
+                rect.width,
+                rect.height,
+            ),
+        ));
+    }
+
+    for (index, node) in diagram.nodes.iter().enumerate() {
+        let cluster = diagram
+            .subgraphs
+            .iter()
+            .rposition(|s| s.nodes.contains(&node.id));
+        let (parent_id, offset) = container(&placed, cluster, origin);
+        let centre = placed.layout.nodes[index];
+        let (width, height) = placed.node_size(index);
+        cells.push(vertex(
+            &format!("{}{}", NODE_PREFIX, node.id),
+            &parent_id,
+            &html_label(node.display_label()),
+            &format!("{}{}", node_style(diagram, node, &theme), font),
+            (
+                centre.x - width / 2.0 - offset.x,
+                centre.y - height / 2.0 - offset.y,
+                width,
+                height,
+            ),
+        ));
+    }
+
+    for (index, points, _) in placed.edge_paths() {
+        let edge = &diagram.edges[index];
+        let end = |id: &str| match diagram.subgraphs.iter().any(|s| s.id == id) {
+            true => format!("{}{}", SUBGRAPH_PREFIX, id),
+            false => format!("{}{}", NODE_PREFIX, id),
+        };
+        let mut cell = format!(
+            r#"<mxCell id="edge-{}" value="{}" style="{}{}" edge="1" parent="1" source="{}" target="{}"><mxGeometry relative="1" as="geometry">"#,
+            index,
+            escape_xml(&edge.label.as_deref().map(html_label).unwrap_or_default()),
+            edge_style(edge, &theme),
+            font,
+            escape_xml(&end(&edge.from)),
+            escape_xml(&end(&edge.to))
+        );
+        // Bends only; draw.io clips the ends at the shapes itself
+        if points.len() > 2 {
+            cell.push_str(r#"<Array as="points">"#);
+            for point in &points[1..points.len() - 1] {
+                cell.push_str(&format!(
+                    r#"<mxPoint x="{}" y="{}"/>"#,
+                    num(point.x + origin.x),
+                    num(point.y + origin.y)
+                ));
+            }
+            cell.push_str("</Array>");
+        }
+        cell.push_str("</mxGeometry></mxCell>");
+        cells.push(cell);
+    }
+
+    let mut out = String::from("<mxfile type=\"device\">\n");
+    out.push_str(&format!(
+        "  <diagram id=\"mermaid\" name=\"{}\">\n",
+        escape_xml(diagram.title.as_deref().unwrap_or("Page-1"))
+    ));
+    out.push_str(
+        "    <mxGraphModel grid=\"1\" gridSize=\"10\" guides=\"1\" connect=\"1\" arrows=\"1\" fold=\"1\" page=\"0\" math=\"0\" shadow=\"0\">\n      <root>\n",
+    );
+    for cell in cells {
+        out.push_str(&format!("        {}\n", cell));
+    }
+    out.push_str("      </root>\n    </mxGraphModel>\n  </diagram>\n</mxfile>\n");
+    Ok(out)
+}
+
+/// Convert the first page of an uncompressed draw.io file into a Mermaid
+/// flowchart. Shapes become nodes, containers and groups become subgraphs
+/// and connectors become edges; anything else is listed in the result.
+pub fn import_drawio(source: &str) -> Result<ImportedDiagram, String> {
+    let document =
+        roxmltree::Document::parse(source).map_err(|e| format!("Invalid draw.io file: {}", e))?;
+    DrawioImport::new(&document).import()
+}
+
+// ---------------------------------------------------------------------------
+// Export
+// ---------------------------------------------------------------------------
+
+/// First family of a CSS font stack; draw.io takes a single name
+fn font_name(font_family: &str) -> String {
+    font_family
+        .split(',')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .to_string()
+}
+
+/// An HTML cell value, with `<br>` tags as line breaks
+fn html_label(label: &str) -> String {
+    let label = label.trim_matches('`').replace("#quot;", "\"");
+    label_lines(&label)
+        .iter()
+        .map(|line| escape_xml(line))
+        .collect::<Vec<_>>()
+        .join("<br>")
+}
+
+fn vertex(
+    id: &str,
+    parent: &str,
+    value: &str,
+    style: &str,
+    geometry: (f64, f64, f64, f64),
+) -> String {
+    let (x, y, width, height) = geometry;
+    format!(
+        r#"<mxCell id="{}" value="{}" style="{}" vertex="1" parent="{}"><mxGeometry x="{}" y="{}" width="{}" height="{}" as="geometry"/></mxCell>"#,
+        escape_xml(id),
+        escape_xml(value),
+        escape_xml(style),
+        escape_xml(parent),
+        num(x),
+        num(y),
+        num(width),
+        num(height)
+    )
+}
+
+/// The cell a shape belongs to and where that cell's own coordinates start
+fn container(placed: &FlowchartLayout, cluster: Option<usize>, origin: Point) -> (String, Point) {
+    match cluster.and_then(|c| placed.layout.clusters[c].map(|rect| (c, rect))) {
+        Some((cluster, rect)) => (
+            format!(
+                "{}{}",
+                SUBGRAPH_PREFIX, placed.diagram.subgraphs[cluster].id
+            ),
+            Point {
+                x: rect.x - origin.x,
+                y: rect.y - origin.y,
+            },
+        ),
+        None => (
+            "1".to_string(),
+            Point {
+                x: -origin.x,
+                y: -origin.y,
+            },
+        ),
+    }
+}
+
+fn node_style(diagram: &ParsedDiagram, node: &Node, theme: &ThemeVariables) -> String {
+    let shape = match node.shape {
+        NodeShape::Rect => "rounded=0;",
+        NodeShape::Round => "rounded=1;",
+        NodeShape::Stadium => "rounded=1;arcSize=50;",
+        NodeShape::Subroutine => "shape=process;backgroundOutline=1;",
+        NodeShape::Cylinder => "shape=cylinder3;boundedLbl=1;backgroundOutline=1;size=8;",
+        NodeShape::Circle => "ellipse;",
+        NodeShape::DoubleCircle => "ellipse;shape=doubleEllipse;",
+        NodeShape::Asymmetric => "shape=step;perimeter=stepPerimeter;fixedSize=1;size=12;",
+        NodeShape::Rhombus => "rhombus;",
+        NodeShape::Hexagon => "shape=hexagon;perimeter=hexagonPerimeter2;fixedSize=1;size=12;",
+        NodeShape::Parallelogram => {
+            "shape=parallelogram;perimeter=parallelogramPerimeter;fixedSize=1;size=12;"
+        }
+        NodeShape::ParallelogramAlt => {
+            "shape=parallelogram;perimeter=parallelogramPerimeter;fixedSize=1;size=12;flipH=1;"
+        }
+        NodeShape::Trapezoid => "shape=trapezoid;perimeter=trapezoidPerimeter;fixedSize=1;size=12;",
+        NodeShape::TrapezoidAlt => {
+            "shape=trapezoid;perimeter=trapezoidPerimeter;fixedSize=1;size=12;flipV=1;"
+        }
+    };
+
+    let paint = node_paint(diagram, node, theme);
+    let mut properties = vec![
+        ("fillColor", paint.fill),
+        ("strokeColor", paint.stroke),
+        ("fontColor", paint.text),
+    ];
+    if let Some(width) = paint.stroke_width {
+        properties.push(("strokeWidth", num(width)));
+    }
+    if paint.dashed {
+        properties.push(("dashed", "1".to_string()));
+    }
+    if paint.bold {
+        properties.push(("fontStyle", "1".to_string()));
+    }
+
+    let mut style = format!("{}whiteSpace=wrap;html=1;", shape);
+    for (key, value) in properties {
+        // Style values cannot hold the separators
+        if !value.contains([';', '=']) {
+            style.push_str(&format!("{}={};", key, value));
+        }
+    }
+    style
+}
+
+fn edge_style(edge: &Edge, theme: &ThemeVariables) -> String {
+    let arrow = |head: ArrowHead| match head {
+        ArrowHead::None => "none",
+        ArrowHead::Arrow => "classic",
+        ArrowHead::Circle => "oval",
+        ArrowHead::Cross => "cross",
+    };
+    let mut style = String::from("edgeStyle=none;curved=1;html=1;");
+    match edge.stroke {
+        EdgeStroke::Invisible => {
+            style.push_str("strokeColor=none;endArrow=none;startArrow=none;");
+            return style;
+        }
+        EdgeStroke::Dotted => style.push_str("dashed=1;strokeWidth=2;"),
+        EdgeStroke::Thick => style.push_str("strokeWidth=3.5;"),
+        EdgeStroke::Normal => style.push_str("strokeWidth=2;"),
+    }
+    style.push_str(&format!(
+        "endArrow={};endFill=1;startArrow={};startFill=1;strokeColor={};fontColor={};labelBackgroundColor={};",
+        arrow(edge.head),
+        arrow(edge.tail),
+        theme.line_color,
+        theme.text_color,
+        theme.edge_label_background
+    ));
+    style
+}
+
+// ---------------------------------------------------------------------------
+// Import
+// ---------------------------------------------------------------------------
+
+/// An `mxCell`, with the id and label of any `object` wrapping it
+struct Cell<'a> {
+    element: roxmltree::Node<'a, 'a>,
+    id: &'a str,
+    value: &'a str,
+    style: &'a str,
+    parent: Option<&'a str>,
+    vertex: bool,
+    edge: bool,
+    source: Option<&'a str>,
+    target: Option<&'a str>,
+    /// x, y, width and height, relative to the parent cell
+    geometry: (f64, f64, f64, f64),
+}
+
+impl<'a> Cell<'a> {
+    fn new(element: roxmltree::Node<'a, 'a>) -> Option<Self> {
+        let (cell, id, value) = match element.tag_name().name() {
+            "mxCell" => (
+                element,
+                element.attribute("id")?,
+                element.attribute("value"),
+            ),
+            "object" | "UserObject" => (
+                element.children().find(|n| n.has_tag_name("mxCell"))?,
+                element.attribute("id")?,
+                element.attribute("label"),
+            ),
+            _ => return None,
+        };
+        let geometry = cell
+            .children()
+            .find(|n| n.has_tag_name("mxGeometry"))
+            .map(|g| {
+                let number = |name| {
+                    g.attribute(name)
+                        .and_then(|v: &str| v.parse::<f64>().ok())
+                        .unwrap_or(0.0)
+                };
+                (number("x"), number("y"), number("width"), number("height"))
+            })
+            .unwrap_or_default();
+        Some(Self {
+            element,
+            id,
+            value: value.unwrap_or_default(),
+            style: cell.attribute("style").unwrap_or_default(),
+            parent: cell.attribute("parent"),
+            vertex: cell.attribute("vertex") == Some("1"),
+            edge: cell.attribute("edge") == Some("1"),
+            source: cell.attribute("source"),
+            target: cell.attribute("target"),
+            geometry,
+        })
+    }
+
+    /// The style's base name, e.g. `ellipse`, and its `key=value` pairs
+    fn style(&self) -> (Option<&'a str>, HashMap<&'a str, &'a str>) {
+        let mut base = None;
+        let mut pairs = HashMap::new();
+        for part in self.style.split(';').filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some((key, value)) => {
+                    pairs.insert(key, value);
+                }
+                None if base.is_none() => base = Some(part),
+                None => {}
+            }
+        }
+        (base, pairs)
+    }
+
+    /// The label as plain text, with line breaks as `<br/>`
+    fn text(&self) -> String {
+        let html = self.style().1.get("html") == Some(&"1");
+        let text = match html {
+            true => html_text(&self.value.replace("&nbsp;", " ").replace("<div>", "<br>")),
+            false => self.value.replace('\n', "<br/>"),
+        };
+        text.split("<br/>")
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("<br/>")
+    }
+}
+
+struct DrawioImport<'a> {
+    document: &'a roxmltree::Document<'a>,
+    diagram: ParsedDiagram,
+    skipped: Vec<SkippedConstruct>,
+    taken: HashSet<String>,
+}
+
+impl<'a> DrawioImport<'a> {
+    fn new(document: &'a roxmltree::Document<'a>) -> Self {
+        Self {
+            document,
+            diagram: ParsedDiagram::new(DiagramKind::Flowchart),
+            skipped: Vec::new(),
+            taken: HashSet::new(),
+        }
+    }
+
+    fn import(mut self) -> Result<ImportedDiagram, String> {
+        let root = self.document.root_element();
+        let model = match root.tag_name().name() {
+            "mxGraphModel" => root,
+            "mxfile" => {
+                let mut pages = root.children().filter(|n| n.has_tag_name("diagram"));
+                let page = pages.next().ok_or("The draw.io file has no pages")?;
+                for extra in pages {
+                    self.skip(extra, "Only the first page of a file is imported");
+                }
+                self.diagram.title = page
+                    .attribute("name")
+                    .filter(|name| !is_default_page_name(name))
+                    .map(str::to_string);
+                match page.children().find(|n| n.has_tag_name("mxGraphModel")) {
+                    Some(model) => model,
+                    None if page.text().is_some_and(|text| !text.trim().is_empty()) => {
+                        return Err("Compressed draw.io files are not supported; uncheck \
+                            Compressed under File > Properties in draw.io and save again"
+                            .to_string())
+                    }
+                    None => return Err("The first draw.io page is empty".to_string()),
+                }
+            }
+            other => {
+                return Err(format!(
+                    "Not a draw.io file: unexpected <{}> element",
+                    other
+                ))
+            }
+        };
+        let cells: Vec<Cell> = model
+            .children()
+            .find(|n| n.has_tag_name("root"))
+            .ok_or("The draw.io diagram has no cells")?
+            .children()
+            .filter_map(Cell::new)
+            .collect();
+        let by_id: HashMap<&str, usize> =
+            cells.iter().enumerate().map(|(i, c)| (c.id, i)).collect();
+
+        // Shapes and connectors sit in layers, which are not drawn
+        let parent_of = |cell: &Cell| -> Option<usize> {
+            let parent = cell.parent.and_then(|p| by_id.get(p).copied())?;
+            (cells[parent].vertex || cells[parent].edge).then_some(parent)
+        };
+        let is_text = |cell: &Cell| cell.style().0 == Some("text");
+        let edge_labels: HashSet<usize> = cells
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.vertex && parent_of(c).is_some_and(|p| cells[p].edge))
+            .map(|(i, _)| i)
+            .collect();
+        let containers: HashSet<usize> = cells
+            .iter()
+            .enumerate()
+            .filter(|&(i, cell)| {
+                let (base, style) = cell.style();
+                cell.vertex
+                    && !edge_labels.contains(&i)
+                    && (matches!(base, Some("swimlane") | Some("group"))
+                        || style.get("container") == Some(&"1")
+                        || cells.iter().enumerate().any(|(j, child)| {
+                            child.vertex && !edge_labels.contains(&j) && parent_of(child) == Some(i)
+                        }))
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        // Mermaid ids for every shape and container, in document order
+        let mut ids: HashMap<usize, String> = HashMap::new();
+        for (index, cell) in cells.iter().enumerate() {
+            if !cell.vertex || edge_labels.contains(&index) {
+                continue;
+            }
+            if is_text(cell) && !containers.contains(&index) {
+                let consumed = self.diagram.title.as_deref() == Some(cell.text().as_str());
+                if !consumed {
+                    self.skip(
+                        cell.element,
+                        "Free-standing text has no flowchart equivalent",
+                    );
+                }
+                continue;
+            }
+            let id = self.unique_id(cell.id);
+            ids.insert(index, id);
+        }
+
+        for (index, cell) in cells.iter().enumerate() {
+            let Some(id) = ids.get(&index) else {
+                continue;
+            };
+            let parent = parent_of(cell).and_then(|p| ids.get(&p)).cloned();
+            let text = cell.text();
+            if containers.contains(&index) {
+                self.diagram.subgraphs.push(Subgraph {
+                    id: id.clone(),
+                    label: (!text.is_empty()).then_some(text),
+                    parent,
+                    direction: None,
+                    nodes: Vec::new(),
+                });
+            } else {
+                let node = self.node(cell, id, text);
+                self.diagram.nodes.push(node);
+            }
+        }
+        // Containers can come after the shapes inside them, so members are
+        // added once every subgraph exists
+        for (index, cell) in cells.iter().enumerate() {
+            let (Some(id), Some(parent)) =
+                (ids.get(&index), parent_of(cell).and_then(|p| ids.get(&p)))
+            else {
+                continue;
+            };
+            if let Some(subgraph) = self.diagram.subgraphs.iter_mut().find(|s| s.id == *parent) {
+                if !containers.contains(&index) {
+                    subgraph.nodes.push(id.clone());
+                }
+            }
+        }
+
+        for (index, cell) in cells.iter().enumerate().filter(|(_, c)| c.edge) {
+            let end = |end: Option<&str>| end.and_then(|e| by_id.get(e)).and_then(|e| ids.get(e));
+            let (Some(from), Some(to)) = (end(cell.source), end(cell.target)) else {
+                self.skip(
+                    cell.element,
+                    "Connectors must join two shapes to be imported",
+                );
+                continue;
+            };
+            let mut labels: Vec<String> = std::iter::once(cell.text())
+                .chain(
+                    cells
+                        .iter()
+                        .enumerate()
+                        .filter(|&(i, _)| edge_labels.contains(&i))
+                        .filter(|(_, label)| parent_of(label) == Some(index))
+                        .map(|(_, label)| label.text()),
+                )
+                .filter(|label| !label.is_empty())
+                .collect();
+            if labels.len() > 1 {
+                self.skip(cell.element, "Only the first label of a connector is kept");
+            }
+            let edge = edge(
+                cell,
+                from,
+                to,
+                (!labels.is_empty()).then(|| labels.remove(0)),
+            );
+            self.diagram.edges.push(edge);
+        }
+
+        self.diagram.direction = Some(direction(&cells, &by_id, &ids).to_string());
+        let mut code = write_diagram(&self.diagram)?;
+        if let Some(ref title) = self.diagram.title {
+            code = format!("---\ntitle: {}\n---\n{}", title, code);
+        }
+        self.skipped.sort_by_key(|skipped| skipped.line);
+        Ok(ImportedDiagram::new(code, self.skipped))
+    }
+
+    fn node(&mut self, cell: &Cell, id: &str, text: String) -> Node {
+        let (base, style) = cell.style();
+        let mut node = Node::new(id);
+        // Unlabelled shapes would otherwise show their generated id
+        node.label = Some(match text.is_empty() {
+            true => " ".to_string(),
+            false => text,
+        });
+        let (_, _, width, height) = cell.geometry;
+        match node_shape(base, &style, width, height) {
+            Some(shape) => node.shape = shape,
+            None => self.skip(
+                cell.element,
+                &format!(
+                    "The {} shape is imported as a rectangle",
+                    style.get("shape").copied().or(base).unwrap_or_default()
+                ),
+            ),
+        }
+
+        // Colours matching mermaid's default theme are left to the theme
+        let defaults = ThemeVariables::from_config(&json!({})).ok();
+        let properties = [
+            (
+                "fillColor",
+                "fill",
+                defaults.as_ref().map(|t| t.main_bkg.clone()),
+            ),
+            (
+                "strokeColor",
+                "stroke",
+                defaults.as_ref().map(|t| t.node_border.clone()),
+            ),
+            (
+                "fontColor",
+                "color",
+                defaults.as_ref().map(|t| t.node_text_color.clone()),
+            ),
+        ];
+        for (key, property, default) in properties {
+            let Some(&value) = style.get(key) else {
+                continue;
+            };
+            let is_default = default.is_some_and(|d| d.eq_ignore_ascii_case(value));
+            if value != "default" && !is_default {
+                node.styles.push(format!("{}:{}", property, value));
+            }
+        }
+        if let Some(width) = style
+            .get("strokeWidth")
+            .filter(|w| w.parse::<f64>().is_ok())
+        {
+            node.styles.push(format!("stroke-width:{}px", width));
+        }
+        if style.get("dashed") == Some(&"1") {
+            node.styles.push("stroke-dasharray:5 5".to_string());
+        }
+        node
+    }
+
+    fn skip(&mut self, element: roxmltree::Node, reason: &str) {
+        let line = self.document.text_pos_at(element.range().start).row as usize;
+        self.skipped.push(SkippedConstruct {
+            line,
+            source: self
+                .document
+                .input_text()
+                .lines()
+                .nth(line - 1)
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    fn unique_id(&mut self, cell_id: &str) -> String {
+        let name = cell_id
+            .strip_prefix(NODE_PREFIX)
+            .or_else(|| cell_id.strip_prefix(SUBGRAPH_PREFIX))
+            .unwrap_or(cell_id);
+        let mut base: String = name
+            .chars()
+            .map(|c| match c.is_alphanumeric() || c == '_' {
+                true => c,
+                false => '_',
+            })
+            .collect();
+        if base.is_empty() || RESERVED_IDS.contains(&base.to_ascii_lowercase().as_str()) {
+            base.push('_');
+        }
+        let mut id = base.clone();
+        let mut counter = 2;
+        while self.taken.contains(&id) {
+            id = format!("{}_{}", base, counter);
+            counter += 1;
+        }
+        self.taken.insert(id.clone());
+        id
+    }
+}
+
+/// Names draw.io gives new pages, e.g. `Page-1`
+fn is_default_page_name(name: &str) -> bool {
+    name.strip_prefix("Page-")
+        .or_else(|| name.strip_prefix("Page "))
+        .is_some_and(|number| number.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Flowchart shape for a draw.io style, `None` when it is only approximated
+fn node_shape(
+    base: Option<&str>,
+    style: &HashMap<&str, &str>,
+    width: f64,
+    height: f64,
+) -> Option<NodeShape> {
+    let flag = |key: &str| style.get(key) == Some(&"1");
+    Some(match style.get("shape").copied().or(base) {
+        None | Some("rectangle") | Some("label") => match (flag("rounded"), style.get("arcSize")) {
+            (true, Some(&"50")) => NodeShape::Stadium,
+            (true, _) => NodeShape::Round,
+            (false, _) => NodeShape::Rect,
+        },
+        Some("ellipse") if (width - height).abs() < 1.0 => NodeShape::Circle,
+        Some("ellipse") => NodeShape::Stadium,
+        Some("doubleEllipse") => NodeShape::DoubleCircle,
+        Some("rhombus") => NodeShape::Rhombus,
+        Some("hexagon") => NodeShape::Hexagon,
+        Some("parallelogram") if flag("flipH") => NodeShape::ParallelogramAlt,
+        Some("parallelogram") => NodeShape::Parallelogram,
+        Some("trapezoid") if flag("flipV") => NodeShape::TrapezoidAlt,
+        Some("trapezoid") => NodeShape::Trapezoid,
+        Some("cylinder") | Some("cylinder3") | Some("datastore") => NodeShape::Cylinder,
+        Some("process") => NodeShape::Subroutine,
+        Some("step") => NodeShape::Asymmetric,
+        _ => return None,
+    })
+}
+
+fn edge(cell: &Cell, from: &str, to: &str, label: Option<String>) -> Edge {
+    let (_, style) = cell.style();
+    let head = |key: &str, default: &str| match style.get(key).copied().unwrap_or(default) {
+        "none" | "" => ArrowHead::None,
+        "oval" | "dot" | "circle" | "circlePlus" => ArrowHead::Circle,
+        "cross" => ArrowHead::Cross,
+        _ => ArrowHead::Arrow,
+    };
+    let width = style
+        .get("strokeWidth")
+        .and_then(|w| w.parse::<f64>().ok())
+        .unwrap_or(1.0);
+    let stroke = if style.get("strokeColor") == Some(&"none") {
+        EdgeStroke::Invisible
+    } else if style.get("dashed") == Some(&"1") {
+        EdgeStroke::Dotted
+    } else if width >= 3.0 {
+        EdgeStroke::Thick
+    } else {
+        EdgeStroke::Normal
+    };
+    let (head, tail) = (head("endArrow", "classic"), head("startArrow", "none"));
+    Edge {
+        from: from.to_string(),
+        to: to.to_string(),
+        label,
+        arrow: canonical_arrow(stroke, head, tail),
+        stroke,
+        head,
+        tail,
+    }
+}
+
+/// The direction most connectors point in, from the shapes' positions
+fn direction(
+    cells: &[Cell],
+    by_id: &HashMap<&str, usize>,
+    ids: &HashMap<usize, String>,
+) -> &'static str {
+    let centre = |index: usize| {
+        let mut cell = &cells[index];
+        let (x, y, width, height) = cell.geometry;
+        let mut point = (x + width / 2.0, y + height / 2.0);
+        while let Some(parent) = cell.parent.and_then(|p| by_id.get(p)) {
+            cell = &cells[*parent];
+            point = (point.0 + cell.geometry.0, point.1 + cell.geometry.1);
+        }
+        point
+    };
+    // Total distance along each axis, and net distance for the sense
+    let (mut across, mut down) = (0.0, 0.0);
+    let (mut dx, mut dy) = (0.0, 0.0);
+    for cell in cells.iter().filter(|c| c.edge) {
+        let end = |end: Option<&str>| {
+            end.and_then(|e| by_id.get(e))
+                .filter(|e| ids.contains_key(e))
+        };
+        if let (Some(&from), Some(&to)) = (end(cell.source), end(cell.target)) {
+            let ((x1, y1), (x2, y2)) = (centre(from), centre(to));
+            across += f64::abs(x2 - x1);
+            down += f64::abs(y2 - y1);
+            dx += x2 - x1;
+            dy += y2 - y1;
+        }
+    }
+    match across > down {
+        true if dx < 0.0 => "RL",
+        true => "LR",
+        false if dy < 0.0 => "BT",
+        false => "TD",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_drawio() {
+        let imported = import_drawio(
+            r##"<mxfile host="app.diagrams.net">
+  <diagram id="p1" name="Checkout">
+    <mxGraphModel>
+      <root>
+        <mxCell id="0"/>
+        <mxCell id="1" parent="0"/>
+        <mxCell id="Xk3-aB-1" value="&lt;div&gt;Cart&lt;/div&gt;&lt;div&gt;page&amp;nbsp;&lt;/div&gt;" style="rounded=1;whiteSpace=wrap;html=1;fillColor=#dae8fc;" vertex="1" parent="1">
+          <mxGeometry x="100" y="20" width="120" height="60" as="geometry"/>
+        </mxCell>
+        <object label="Pay" id="end">
+          <mxCell style="rhombus;html=1;" vertex="1" parent="1">
+            <mxGeometry x="300" y="20" width="80" height="80" as="geometry"/>
+          </mxCell>
+        </object>
+        <mxCell id="grp" value="Backend" style="swimlane;" vertex="1" parent="1">
+          <mxGeometry x="500" y="0" width="200" height="120" as="geometry"/>
+        </mxCell>
+        <mxCell id="db" value="Orders" style="shape=cylinder3;html=1;" vertex="1" parent="grp">
+          <mxGeometry x="20" y="40" width="60" height="80" as="geometry"/>
+        </mxCell>
+        <mxCell id="e1" style="edgeStyle=orthogonalEdgeStyle;html=1;" edge="1" parent="1" source="Xk3-aB-1" target="end">
+          <mxGeometry relative="1" as="geometry"/>
+        </mxCell>
+        <mxCell id="e1-label" value="checkout" style="edgeLabel;html=1;" vertex="1" connectable="0" parent="e1">
+          <mxGeometry relative="1" as="geometry"/>
+        </mxCell>
+        <mxCell id="e2" value="store" style="dashed=1;endArrow=oval;" edge="1" parent="1" source="end" target="db">
+          <mxGeometry relative="1" as="geometry"/>
+        </mxCell>
+        <mxCell id="e3" edge="1" parent="1" source="db">
+          <mxGeometry relative="1" as="geometry"/>
+        </mxCell>
+        <mxCell id="note" value="Draft" style="text;html=1;" vertex="1" parent="1">
+          <mxGeometry x="100" y="200" width="60" height="30" as="geometry"/>
+        </mxCell>
+      </root>
+    </mxGraphModel>
+  </diagram>
+</mxfile>"##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            imported.code,
+            [
+                "---",
+                "title: Checkout",
+                "---",
+                "flowchart LR",
+                "    subgraph grp[\"Backend\"]",
+                "        db[(\"Orders\")]",
+                "    end",
+                "    Xk3_aB_1(\"Cart<br/>page\")",
+                "    end_{\"Pay\"}",
+                "    Xk3_aB_1 -->|checkout| end_",
+                "    end_ -.-o|store| db",
+                "    style Xk3_aB_1 fill:#dae8fc",
+                "",
+            ]
+            .join("\n")
+        );
+        let lines: Vec<usize> = imported.skipped.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![30, 33]);
+
+        let compressed =
+            r#"<mxfile><diagram id="a" name="Page-1">7VtRc6M2EP41nmkf</diagram></mxfile>"#;
+        assert!(import_drawio(compressed)
+            .unwrap_err()
+            .contains("Compressed"));
+    }
+
+    #[test]
+    fn test_export_drawio() {
+        let code = r#"flowchart LR
+    subgraph api["API layer"]
+        A(["Gateway"]) -->|routes| B{"Auth?"}
+    end
+    B -.->|no| C[("Users")]
+    C --o A
+    style C fill:#f96
+"#;
+        let xml = export_drawio(code, &DiagramOptions::svg(None)).unwrap();
+        let document = roxmltree::Document::parse(&xml).unwrap();
+        let cell = |id: &str| {
+            document
+                .descendants()
+                .find(|n| n.attribute("id") == Some(id))
+                .unwrap()
+        };
+
+        // Nodes inside a subgraph are placed relative to its container
+        let gateway = cell("node-A");
+        assert_eq!(gateway.attribute("parent"), Some("subgraph-api"));
+        let geometry = gateway.first_element_child().unwrap();
+        let x: f64 = geometry.attribute("x").unwrap().parse().unwrap();
+        assert!(x > 0.0 && x < 50.0);
+        let users = cell("node-C");
+        assert!(users
+            .attribute("style")
+            .unwrap()
+            .starts_with("shape=cylinder3;"));
+        assert!(users
+            .attribute("style")
+            .unwrap()
+            .contains("fillColor=#f96;"));
+        let edge = cell("edge-1");
+        assert_eq!(edge.attribute("value"), Some("no"));
+        assert!(edge.attribute("style").unwrap().contains("dashed=1;"));
+
+        // Importing the export gives back the same flowchart
+        let imported = import_drawio(&xml).unwrap();
+        assert!(imported.skipped.is_empty());
+        let original = parse_diagram(code).unwrap();
+        let round_trip = parse_diagram(&imported.code).unwrap();
+        assert_eq!(round_trip.direction.as_deref(), Some("LR"));
+        assert_eq!(round_trip.nodes, original.nodes);
+        assert_eq!(round_trip.edges, original.edges);
+        assert_eq!(round_trip.subgraphs, original.subgraphs);
+        assert!(
+            export_drawio("sequenceDiagram\n    A->>B: hi", &DiagramOptions::svg(None)).is_err()
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::models::{ColorSpace, DiagramOptions};
+use crate::services::raster_service::{parse_background, parse_svg, to_cmyk, to_gray};
+use crate::services::vector_service::{draw_tree, Segment, VectorSink, POINTS_PER_PIXEL};
+use resvg::tiny_skia::{Point, Transform};
+use resvg::usvg::{FillRule, LineCap, LineJoin, Stroke};
+use std::fmt::Write;
+
+/// Convert SVG markup into a vector Encapsulated PostScript document.
+///
+/// Gradients and patterns are approximated by their first colour, and
+/// opacity is blended against white since PostScript has no transparency.
+pub fn svg_to_eps(svg: &str, options: &DiagramOptions) -> Result<Vec<u8>, String> {
+    let tree = parse_svg(svg)?;
+    let color_space = options.color_space.unwrap_or(ColorSpace::Rgb);
+    let scale = options.scale.filter(|s| *s > 0.0).unwrap_or(1.0) * POINTS_PER_PIXEL;
+
+    let size = tree.size();
+    let width = size.width() * scale;
+    let height = size.height() * scale;
+
+    let mut out = String::new();
+    let _ = writeln!(out, "%!PS-Adobe-3.0 EPSF-3.0");
+    let _ = writeln!(
+        out,
+        "%%BoundingBox: 0 0 {} {}",
+        width.ceil() as u32,
+        height.ceil() as u32
+    );
+    let _ = writeln!(
+        out,
+        "%%HiResBoundingBox: 0 0 {} {}",
+        num(width),
+        num(height)
+    );
+    let _ = writeln!(out, "%%Creator: Mermaid GUI");
+    let _ = writeln!(out, "%%LanguageLevel: 2");
+    let _ = writeln!(out, "%%Pages: 1");
+    let _ = writeln!(out, "%%EndComments");
+    let _ = writeln!(out, "gsave");
+
+    if let Some(color) = parse_background(&options.background)? {
+        let rgb = blend_with_white(
+            [
+                (color.red() * 255.0).round() as u8,
+                (color.green() * 255.0).round() as u8,
+                (color.blue() * 255.0).round() as u8,
+            ],
+            color.alpha(),
+        );
+        out.push_str(&set_color(rgb, color_space));
+        let _ = writeln!(out, "0 0 {} {} rectfill", num(width), num(height));
+    }
+
+    // SVG grows downwards from the top-left corner, PostScript upwards from the bottom-left
+    let _ = writeln!(out, "0 {} translate", num(height));
+    let _ = writeln!(out, "{} {} scale", num(scale), num(-scale));
+
+    draw_tree(
+        &mut PostScriptSink {
+            out: &mut out,
+            color_space,
+        },
+        tree.root(),
+    );
+
+    let _ = writeln!(out, "grestore");
+    let _ = writeln!(out, "showpage");
+    let _ = writeln!(out, "%%EOF");
+    Ok(out.into_bytes())
+}
+
+/// Writes paths as PostScript operators
+struct PostScriptSink<'a> {
+    out: &'a mut String,
+    color_space: ColorSpace,
+}
+
+impl VectorSink for PostScriptSink<'_> {
+    fn begin_path(&mut self, transform: Transform) {
+        let Transform {
+            sx,
+            ky,
+            kx,
+            sy,
+            tx,
+            ty,
+        } = transform;
+        let _ = writeln!(self.out, "gsave");
+        let _ = writeln!(
+            self.out,
+            "[{} {} {} {} {} {}] concat",
+            num(sx),
+            num(ky),
+            num(kx),
+            num(sy),
+            num(tx),
+            num(ty)
+        );
+    }
+
+    fn fill(&mut self, outline: &[Segment], rgb: [u8; 3], opacity: f32, rule: FillRule) {
+        self.out
+            .push_str(&set_color(blend_with_white(rgb, opacity), self.color_space));
+        write_outline(self.out, outline);
+        let _ = writeln!(
+            self.out,
+            "{}",
+            match rule {
+                FillRule::NonZero => "fill",
+                FillRule::EvenOdd => "eofill",
+            }
+        );
+    }
+
+    fn stroke(&mut self, outline: &[Segment], rgb: [u8; 3], opacity: f32, stroke: &Stroke) {
+        self.out
+            .push_str(&set_color(blend_with_white(rgb, opacity), self.color_space));
+        let cap = match stroke.linecap() {
+            LineCap::Butt => 0,
+            LineCap::Round => 1,
+            LineCap::Square => 2,
+        };
+        let join = match stroke.linejoin() {
+            LineJoin::Miter | LineJoin::MiterClip => 0,
+            LineJoin::Round => 1,
+            LineJoin::Bevel => 2,
+        };
+        let _ = writeln!(
+            self.out,
+            "{} setlinewidth {} setlinecap {} setlinejoin {} setmiterlimit",
+            num(stroke.width().get()),
+            cap,
+            join,
+            num(stroke.miterlimit().get())
+        );
+        let dashes = stroke
+            .dasharray()
+            .map(|d| d.iter().map(|v| num(*v)).collect::<Vec<_>>().join(" "))
+            .unwrap_or_default();
+        let _ = writeln!(
+            self.out,
+            "[{}] {} setdash",
+            dashes,
+            num(stroke.dashoffset())
+        );
+        write_outline(self.out, outline);
+        let _ = writeln!(self.out, "stroke");
+    }
+
+    fn end_path(&mut self) {
+        let _ = writeln!(self.out, "grestore");
+    }
+}
+
+/// PostScript path construction operators for an outline
+fn write_outline(out: &mut String, outline: &[Segment]) {
+    out.push_str("newpath\n");
+    for segment in outline {
+        match *segment {
+            Segment::MoveTo(p) => {
+                let _ = writeln!(out, "{} {} moveto", num(p.x), num(p.y));
+            }
+            Segment::LineTo(p) => {
+                let _ = writeln!(out, "{} {} lineto", num(p.x), num(p.y));
+            }
+            Segment::CubicTo(c1, c2, p) => write_curve(out, c1, c2, p),
+            Segment::Close => out.push_str("closepath\n"),
+        }
+    }
+}
+
+fn write_curve(out: &mut String, c1: Point, c2: Point, p: Point) {
+    let _ = writeln!(
+        out,
+        "{} {} {} {} {} {} curveto",
+        num(c1.x),
+        num(c1.y),
+        num(c2.x),
+        num(c2.y),
+        num(p.x),
+        num(p.y)
+    );
+}
+
+fn blend_with_white(rgb: [u8; 3], opacity: f32) -> [u8; 3] {
+    let opacity = opacity.clamp(0.0, 1.0);
+    rgb.map(|channel| (channel as f32 * opacity + 255.0 * (1.0 - opacity)).round() as u8)
+}
+
+fn set_color([red, green, blue]: [u8; 3], color_space: ColorSpace) -> String {
+    let unit = |value: u8| num(value as f32 / 255.0);
+    match color_space {
+        ColorSpace::Rgb => format!("{} {} {} setrgbcolor\n", unit(red), unit(green), unit(blue)),
+        ColorSpace::Gray => format!("{} setgray\n", unit(to_gray(red, green, blue))),
+        ColorSpace::Cmyk => {
+            let [c, m, y, k] = to_cmyk(red, green, blue);
+            format!(
+                "{} {} {} {} setcmykcolor\n",
+                unit(c),
+                unit(m),
+                unit(y),
+                unit(k)
+            )
+        }
+    }
+}
+
+/// Format a number compactly, with at most three decimals
+fn num(value: f32) -> String {
+    let text = format!("{:.3}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "" | "-0" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DiagramFormat;
+
+    #[test]
+    fn test_svg_to_eps() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20" viewBox="0 0 40 20"><rect x="0" y="0" width="20" height="20" fill="#ff0000" stroke="#000000" stroke-dasharray="2 1"/></svg>"##;
+        let options = DiagramOptions {
+            format: DiagramFormat::Eps,
+            background: "white".to_string(),
+            color_space: Some(ColorSpace::Cmyk),
+            ..DiagramOptions::svg(None)
+        };
+
+        let eps = String::from_utf8(svg_to_eps(svg, &options).unwrap()).unwrap();
+        assert!(eps.starts_with("%!PS-Adobe-3.0 EPSF-3.0\n"));
+        assert!(eps.contains("%%BoundingBox: 0 0 30 15\n"));
+        assert!(eps.contains("0 0 0 0 setcmykcolor\n0 0 30 15 rectfill"));
+        assert!(eps.contains("0 1 1 0 setcmykcolor"));
+        assert!(eps.contains("[2 1] 0 setdash"));
+        assert!(eps.trim_end().ends_with("%%EOF"));
+    }
+
+    #[test]
+    fn test_number_formatting() {
+        assert_eq!(num(1.0), "1");
+        assert_eq!(num(0.75), "0.75");
+        assert_eq!(num(-0.0001), "0");
+        assert_eq!(num(2.0 / 3.0), "0.667");
+    }
+}
@@ -0,0 +1,776 @@
+use crate::models::{
+    ArrowHead, DiagramFormat, DiagramKind, DiagramOptions, EdgeStroke, NodeShape, ParsedDiagram,
+};
+use crate::services::flowchart_service::{
+    label_lines, layout_flowchart, node_paint, FONT_SIZE, TITLE_FONT_SIZE,
+};
+use crate::services::layout_service::Point;
+use crate::services::mmdc_service::mmdc_background;
+use crate::services::native_service::{native_config, text_width, ThemeVariables};
+use crate::services::parse_diagram;
+use crate::services::renderer_service::{RenderError, RenderJob};
+use serde_json::{json, Value};
+
+/// Excalidraw's Helvetica; its default hand-drawn font is much wider than
+/// the text was measured with
+const FONT_FAMILY: u32 = 2;
+/// Excalidraw's line height, as a multiple of the font size
+const LINE_HEIGHT: f64 = 1.25;
+
+/// Sizes from mermaid's sequence renderer
+const ACTOR_FONT_SIZE: f64 = 14.0;
+const WRAP_PADDING: f64 = 10.0;
+const BOX_MARGIN: f64 = 10.0;
+/// Width and height of a message from a participant to itself
+const SELF_MESSAGE_SIZE: (f64, f64) = (40.0, 20.0);
+const LIFELINE_COLOR: &str = "#808080";
+
+/// Export a flowchart or sequence diagram as an Excalidraw scene. Flowcharts
+/// keep the native layout, with arrows bound to the shapes they join so that
+/// they follow them when moved; shapes Excalidraw lacks are drawn as
+/// rectangles. Sequence messages run between lifelines, which Excalidraw
+/// cannot bind arrows to.
+pub fn export_excalidraw(code: &str, options: &DiagramOptions) -> Result<String, String> {
+    let diagram = parse_diagram(code)?;
+    let job = RenderJob {
+        code,
+        diagram_type: None,
+        format: DiagramFormat::Svg,
+        options,
+        extra_config: None,
+    };
+    let config = native_config(&job)?;
+    // Themes without a native renderer keep the default theme's colours
+    let theme = ThemeVariables::from_config(&config)
+        .or_else(|_| ThemeVariables::from_config(&json!({})))?;
+
+    let mut scene = Scene {
+        elements: Vec::new(),
+        font: theme.font_family.clone(),
+    };
+    match diagram.kind {
+        DiagramKind::Flowchart => flowchart(&mut scene, code, &config, &theme)?,
+        DiagramKind::Sequence => sequence(&mut scene, &diagram, &config, &theme),
+        _ => {
+            return Err(
+                "Only flowcharts and sequence diagrams can be exported to Excalidraw".to_string(),
+            )
+        }
+    }
+
+    let scene = json!({
+        "type": "excalidraw",
+        "version": 2,
+        "source": "mermaid-gui",
+        "elements": scene.elements,
+        "appState": {
+            "viewBackgroundColor": mmdc_background(options).unwrap_or("#ffffff"),
+            "gridSize": null,
+        },
+        "files": {},
+    });
+    serde_json::to_string_pretty(&scene)
+        .map_err(|e| format!("Failed to serialize Excalidraw scene: {}", e))
+}
+
+fn flowchart(
+    scene: &mut Scene,
+    code: &str,
+    config: &Value,
+    theme: &ThemeVariables,
+) -> Result<(), String> {
+    let placed = layout_flowchart(code, config, &theme.font_family).map_err(|e| match e {
+        RenderError::Unsupported(message)
+        | RenderError::Unavailable(message)
+        | RenderError::Failed(message) => message,
+    })?;
+    let diagram = &placed.diagram;
+    let origin = Point {
+        x: placed.padding,
+        y: placed.padding + placed.title_height,
+    };
+    let at = |point: Point| Point {
+        x: point.x + origin.x,
+        y: point.y + origin.y,
+    };
+
+    if let Some(ref title) = diagram.title {
+        let center = Point {
+            x: placed.layout.width / 2.0 + placed.padding,
+            y: placed.padding + TITLE_FONT_SIZE / 2.0,
+        };
+        scene.text(
+            "title".to_string(),
+            &label_lines(title),
+            center,
+            TITLE_FONT_SIZE,
+            &theme.title_color,
+            None,
+        );
+    }
+
+    // Subgraphs first, so that their nodes are drawn on top
+    for cluster in placed.cluster_order() {
+        let Some(rect) = placed.layout.clusters[cluster] else {
+            continue;
+        };
+        let subgraph = &diagram.subgraphs[cluster];
+        let id = format!("subgraph-{}", subgraph.id);
+        let mut element = scene.element(
+            "rectangle",
+            id.clone(),
+            (
+                rect.x + origin.x,
+                rect.y + origin.y,
+                rect.width,
+                rect.height,
+            ),
+        );
+        element["strokeColor"] = json!(theme.cluster_border);
+        element["backgroundColor"] = json!(theme.cluster_bkg);
+        scene.elements.push(element);
+
+        let lines = label_lines(subgraph.label.as_deref().unwrap_or(&subgraph.id));
+        let height = lines.len() as f64 * FONT_SIZE * LINE_HEIGHT;
+        let center = Point {
+            x: rect.x + origin.x + rect.width / 2.0,
+            y: rect.y + origin.y + placed.options.cluster_padding + height / 2.0,
+        };
+        let title = scene.text(
+            format!("{}-label", id),
+            &lines,
+            center,
+            FONT_SIZE,
+            &theme.title_color,
+            Some(&id),
+        );
+        scene.elements[title]["verticalAlign"] = json!("top");
+    }
+
+    for (index, node) in diagram.nodes.iter().enumerate() {
+        let id = format!("node-{}", node.id);
+        let center = at(placed.layout.nodes[index]);
+        let (width, height) = placed.node_size(index);
+        let kind = match node.shape {
+            NodeShape::Circle | NodeShape::DoubleCircle => "ellipse",
+            NodeShape::Rhombus => "diamond",
+            _ => "rectangle",
+        };
+        let paint = node_paint(diagram, node, theme);
+        let mut element = scene.element(
+            kind,
+            id.clone(),
+            (
+                center.x - width / 2.0,
+                center.y - height / 2.0,
+                width,
+                height,
+            ),
+        );
+        element["strokeColor"] = json!(paint.stroke);
+        element["backgroundColor"] = json!(paint.fill);
+        if let Some(width) = paint.stroke_width {
+            element["strokeWidth"] = json!(width);
+        }
+        if paint.dashed {
+            element["strokeStyle"] = json!("dashed");
+        }
+        if matches!(
+            node.shape,
+            NodeShape::Round | NodeShape::Stadium | NodeShape::Cylinder
+        ) {
+            element["roundness"] = json!({ "type": 3 });
+        }
+        scene.elements.push(element);
+        scene.text(
+            format!("{}-label", id),
+            &label_lines(node.display_label()),
+            center,
+            FONT_SIZE,
+            &paint.text,
+            Some(&id),
+        );
+    }
+
+    for (index, points, label) in placed.edge_paths() {
+        let edge = &diagram.edges[index];
+        if edge.stroke == EdgeStroke::Invisible {
+            continue;
+        }
+        let head = |head: ArrowHead| match head {
+            ArrowHead::None => None,
+            ArrowHead::Arrow => Some("triangle"),
+            ArrowHead::Circle => Some("dot"),
+            ArrowHead::Cross => Some("bar"),
+        };
+        let id = format!("edge-{}", index);
+        let points: Vec<Point> = points.into_iter().map(at).collect();
+        let arrow = scene.line(
+            "arrow",
+            id.clone(),
+            &points,
+            &theme.line_color,
+            (head(edge.tail), head(edge.head)),
+        );
+        let element = &mut scene.elements[arrow];
+        match edge.stroke {
+            EdgeStroke::Thick => element["strokeWidth"] = json!(4),
+            EdgeStroke::Dotted => element["strokeStyle"] = json!("dashed"),
+            _ => {}
+        }
+        let end = |id: &str| match diagram.subgraphs.iter().any(|s| s.id == id) {
+            true => format!("subgraph-{}", id),
+            false => format!("node-{}", id),
+        };
+        scene.bind(arrow, &end(&edge.from), &end(&edge.to));
+
+        if let Some(ref text) = edge.label {
+            scene.text(
+                format!("{}-label", id),
+                &label_lines(text),
+                at(label),
+                FONT_SIZE,
+                &theme.text_color,
+                Some(&id),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn sequence(scene: &mut Scene, diagram: &ParsedDiagram, config: &Value, theme: &ThemeVariables) {
+    let sequence = &config["sequence"];
+    let number = |name: &str, default: f64| sequence[name].as_f64().unwrap_or(default);
+    let box_width = number("width", 150.0);
+    let box_height = number("height", 65.0);
+    let actor_margin = number("actorMargin", 50.0);
+    let message_margin = number("messageMargin", 35.0);
+    let margin_x = number("diagramMarginX", 50.0);
+    let title_height = match diagram.title {
+        Some(_) => TITLE_FONT_SIZE * LINE_HEIGHT + BOX_MARGIN,
+        None => 0.0,
+    };
+    let margin_y = number("diagramMarginY", 10.0) + title_height;
+    let mirror = sequence["mirrorActors"].as_bool().unwrap_or(true);
+
+    let participants = &diagram.participants;
+    let labels: Vec<Vec<String>> = participants
+        .iter()
+        .map(|p| label_lines(p.label.as_deref().unwrap_or(&p.id)))
+        .collect();
+    let widths: Vec<f64> = labels
+        .iter()
+        .map(|lines| (scene.width(lines, ACTOR_FONT_SIZE) + 2.0 * WRAP_PADDING).max(box_width))
+        .collect();
+    let position = |id: &str| participants.iter().position(|p| p.id == id);
+    let messages: Vec<(usize, usize, Vec<String>, &str)> = diagram
+        .messages
+        .iter()
+        .filter_map(|message| {
+            let lines = match message.text.trim() {
+                "" => Vec::new(),
+                text => label_lines(text),
+            };
+            Some((
+                position(&message.from)?,
+                position(&message.to)?,
+                lines,
+                message.arrow.as_str(),
+            ))
+        })
+        .collect();
+
+    // Lifelines far enough apart for the boxes, then for the messages
+    let mut centers = vec![0.0; participants.len()];
+    for index in 0..participants.len() {
+        centers[index] = match index {
+            0 => margin_x + widths[0] / 2.0,
+            _ => centers[index - 1] + widths[index - 1] / 2.0 + actor_margin + widths[index] / 2.0,
+        };
+    }
+    for (from, to, lines, _) in &messages {
+        let text = scene.width(lines, FONT_SIZE) + 2.0 * WRAP_PADDING;
+        let (low, high, needed) = match from == to {
+            true => (
+                *from,
+                from + 1,
+                text.max(SELF_MESSAGE_SIZE.0) + WRAP_PADDING,
+            ),
+            false => (*from.min(to), *from.max(to), text),
+        };
+        if high >= centers.len() {
+            continue;
+        }
+        let shortfall = centers[low] + needed - centers[high];
+        if shortfall > 0.0 {
+            for center in &mut centers[high..] {
+                *center += shortfall;
+            }
+        }
+    }
+
+    // Messages top to bottom, each label above its arrow
+    let mut y = margin_y + box_height;
+    let mut rows = Vec::new();
+    for (from, to, lines, _) in &messages {
+        let label_height = lines.len() as f64 * FONT_SIZE * LINE_HEIGHT;
+        let top = y + BOX_MARGIN;
+        let arrow = top + label_height + BOX_MARGIN / 2.0;
+        rows.push((top, arrow));
+        y = match from == to {
+            true => arrow + SELF_MESSAGE_SIZE.1,
+            false => arrow,
+        };
+    }
+    let footer = y + message_margin;
+
+    for (index, participant) in participants.iter().enumerate() {
+        let center = centers[index];
+        let bottom = footer + if mirror { 0.0 } else { BOX_MARGIN };
+        let lifeline = scene.line(
+            "line",
+            format!("lifeline-{}", participant.id),
+            &[
+                Point {
+                    x: center,
+                    y: margin_y + box_height,
+                },
+                Point {
+                    x: center,
+                    y: bottom,
+                },
+            ],
+            LIFELINE_COLOR,
+            (None, None),
+        );
+        scene.elements[lifeline]["strokeStyle"] = json!("dashed");
+
+        let tops = match mirror {
+            true => vec![("top", margin_y), ("bottom", footer)],
+            false => vec![("top", margin_y)],
+        };
+        for (place, top) in tops {
+            let id = format!("participant-{}-{}", participant.id, place);
+            match participant.is_actor {
+                true => scene.actor(id, center, top, box_height, &labels[index], theme),
+                false => {
+                    let width = widths[index];
+                    let mut element = scene.element(
+                        "rectangle",
+                        id.clone(),
+                        (center - width / 2.0, top, width, box_height),
+                    );
+                    element["strokeColor"] = json!(theme.node_border);
+                    element["backgroundColor"] = json!(theme.main_bkg);
+                    scene.elements.push(element);
+                    scene.text(
+                        format!("{}-label", id),
+                        &labels[index],
+                        Point {
+                            x: center,
+                            y: top + box_height / 2.0,
+                        },
+                        ACTOR_FONT_SIZE,
+                        &theme.node_text_color,
+                        Some(&id),
+                    );
+                }
+            }
+        }
+    }
+
+    for (index, ((from, to, lines, arrow), (top, y))) in messages.iter().zip(rows).enumerate() {
+        let (start, end) = (centers[*from], centers[*to]);
+        let points = match from == to {
+            true => {
+                let (width, height) = SELF_MESSAGE_SIZE;
+                vec![
+                    Point { x: start, y },
+                    Point {
+                        x: start + width,
+                        y,
+                    },
+                    Point {
+                        x: start + width,
+                        y: y + height,
+                    },
+                    Point {
+                        x: start,
+                        y: y + height,
+                    },
+                ]
+            }
+            false => vec![Point { x: start, y }, Point { x: end, y }],
+        };
+        let head = if arrow.ends_with(">>") {
+            Some("triangle")
+        } else if arrow.ends_with(')') {
+            Some("arrow")
+        } else if arrow.ends_with('x') {
+            Some("bar")
+        } else {
+            None
+        };
+        let tail = arrow.starts_with("<<").then_some("triangle");
+        let id = format!("message-{}", index);
+        let element = scene.line(
+            "arrow",
+            id.clone(),
+            &points,
+            &theme.text_color,
+            (tail, head),
+        );
+        if arrow.contains("--") {
+            scene.elements[element]["strokeStyle"] = json!("dashed");
+        }
+
+        if !lines.is_empty() {
+            let label_height = lines.len() as f64 * FONT_SIZE * LINE_HEIGHT;
+            let x = match from == to {
+                true => start + SELF_MESSAGE_SIZE.0 / 2.0,
+                false => (start + end) / 2.0,
+            };
+            scene.text(
+                format!("{}-label", id),
+                lines,
+                Point {
+                    x,
+                    y: top + label_height / 2.0,
+                },
+                FONT_SIZE,
+                &theme.text_color,
+                None,
+            );
+        }
+    }
+
+    if let Some(ref title) = diagram.title {
+        scene.text(
+            "title".to_string(),
+            &label_lines(title),
+            Point {
+                x: (centers.first().unwrap_or(&0.0) + centers.last().unwrap_or(&0.0)) / 2.0,
+                y: margin_y - title_height / 2.0 - BOX_MARGIN / 2.0,
+            },
+            TITLE_FONT_SIZE,
+            &theme.title_color,
+            None,
+        );
+    }
+}
+
+/// Excalidraw elements, in drawing order
+struct Scene {
+    elements: Vec<Value>,
+    /// Font the text is measured with
+    font: String,
+}
+
+impl Scene {
+    /// Properties every element has; the seeds only need to be stable
+    fn element(
+        &self,
+        kind: &str,
+        id: String,
+        (x, y, width, height): (f64, f64, f64, f64),
+    ) -> Value {
+        let seed = self.elements.len() as u64 * 7919 + 1;
+        json!({
+            "id": id,
+            "type": kind,
+            "x": round(x),
+            "y": round(y),
+            "width": round(width),
+            "height": round(height),
+            "angle": 0,
+            "strokeColor": "#1e1e1e",
+            "backgroundColor": "transparent",
+            "fillStyle": "solid",
+            "strokeWidth": 1,
+            "strokeStyle": "solid",
+            "roughness": 0,
+            "opacity": 100,
+            "groupIds": [],
+            "frameId": null,
+            "roundness": null,
+            "seed": seed,
+            "version": 1,
+            "versionNonce": seed,
+            "isDeleted": false,
+            "boundElements": null,
+            "updated": 1,
+            "link": null,
+            "locked": false,
+        })
+    }
+
+    fn width(&self, lines: &[String], font_size: f64) -> f64 {
+        lines
+            .iter()
+            .map(|line| text_width(line, font_size, &self.font))
+            .fold(0.0, f64::max)
+    }
+
+    /// Centred text, inside `container` when given; returns its index
+    fn text(
+        &mut self,
+        id: String,
+        lines: &[String],
+        center: Point,
+        font_size: f64,
+        color: &str,
+        container: Option<&str>,
+    ) -> usize {
+        let width = self.width(lines, font_size);
+        let height = lines.len() as f64 * font_size * LINE_HEIGHT;
+        let mut text = self.element(
+            "text",
+            id.clone(),
+            (
+                center.x - width / 2.0,
+                center.y - height / 2.0,
+                width,
+                height,
+            ),
+        );
+        text["strokeColor"] = json!(color);
+        text["text"] = json!(lines.join("\n"));
+        text["originalText"] = json!(lines.join("\n"));
+        text["fontSize"] = json!(font_size);
+        text["fontFamily"] = json!(FONT_FAMILY);
+        text["textAlign"] = json!("center");
+        text["verticalAlign"] = json!("middle");
+        text["containerId"] = json!(container);
+        text["lineHeight"] = json!(LINE_HEIGHT);
+        text["autoResize"] = json!(true);
+        if let Some(container) = container {
+            self.add_bound(container, &id, "text");
+        }
+        self.elements.push(text);
+        self.elements.len() - 1
+    }
+
+    /// A line or arrow through the points, with the given start and end
+    /// arrowheads; returns its index
+    fn line(
+        &mut self,
+        kind: &str,
+        id: String,
+        points: &[Point],
+        color: &str,
+        (start, end): (Option<&str>, Option<&str>),
+    ) -> usize {
+        let first = points[0];
+        let relative: Vec<[f64; 2]> = points
+            .iter()
+            .map(|p| [round(p.x - first.x), round(p.y - first.y)])
+            .collect();
+        let extent = |axis: fn(&[f64; 2]) -> f64| {
+            let values = relative.iter().map(axis);
+            values.clone().fold(f64::MIN, f64::max) - values.fold(f64::MAX, f64::min)
+        };
+        let size = (extent(|p| p[0]), extent(|p| p[1]));
+        let mut line = self.element(kind, id, (first.x, first.y, size.0, size.1));
+        line["strokeColor"] = json!(color);
+        line["strokeWidth"] = json!(2);
+        line["points"] = json!(relative);
+        line["lastCommittedPoint"] = Value::Null;
+        line["startBinding"] = Value::Null;
+        line["endBinding"] = Value::Null;
+        line["startArrowhead"] = json!(start);
+        line["endArrowhead"] = json!(end);
+        if points.len() > 2 {
+            line["roundness"] = json!({ "type": 2 });
+        }
+        if kind == "arrow" {
+            line["elbowed"] = json!(false);
+        }
+        self.elements.push(line);
+        self.elements.len() - 1
+    }
+
+    /// Bind both ends of an arrow, so that it follows the shapes
+    fn bind(&mut self, arrow: usize, start: &str, end: &str) {
+        let id = self.elements[arrow]["id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        for (side, shape) in [("startBinding", start), ("endBinding", end)] {
+            self.elements[arrow][side] = json!({ "elementId": shape, "focus": 0, "gap": 1 });
+            self.add_bound(shape, &id, "arrow");
+        }
+    }
+
+    fn add_bound(&mut self, element: &str, id: &str, kind: &str) {
+        let Some(element) = self.elements.iter_mut().find(|e| e["id"] == element) else {
+            return;
+        };
+        let bound = json!({ "id": id, "type": kind });
+        match element["boundElements"].as_array_mut() {
+            Some(elements) if elements.contains(&bound) => {}
+            Some(elements) => elements.push(bound),
+            None => element["boundElements"] = json!([bound]),
+        }
+    }
+
+    /// A stick figure with its name beneath, grouped so it moves as one
+    fn actor(
+        &mut self,
+        id: String,
+        center: f64,
+        top: f64,
+        height: f64,
+        label: &[String],
+        theme: &ThemeVariables,
+    ) {
+        let head = height * 0.25;
+        let body = top + head;
+        let figure = height * 0.5;
+        let point = |x: f64, y: f64| Point {
+            x: center + x,
+            y: body + y,
+        };
+        let first = self.elements.len();
+
+        let mut element = self.element(
+            "ellipse",
+            format!("{}-head", id),
+            (center - head / 2.0, top, head, head),
+        );
+        element["strokeColor"] = json!(theme.node_border);
+        element["backgroundColor"] = json!(theme.main_bkg);
+        self.elements.push(element);
+        let strokes = [
+            ("body", vec![point(0.0, 0.0), point(0.0, figure * 0.45)]),
+            (
+                "arms",
+                vec![point(-18.0, figure * 0.15), point(18.0, figure * 0.15)],
+            ),
+            (
+                "legs",
+                vec![
+                    point(-15.0, figure * 0.75),
+                    point(0.0, figure * 0.45),
+                    point(15.0, figure * 0.75),
+                ],
+            ),
+        ];
+        for (part, points) in strokes {
+            let line = self.line(
+                "line",
+                format!("{}-{}", id, part),
+                &points,
+                &theme.node_border,
+                (None, None),
+            );
+            self.elements[line]["roundness"] = Value::Null;
+        }
+        let label_height = label.len() as f64 * ACTOR_FONT_SIZE * LINE_HEIGHT;
+        self.text(
+            format!("{}-label", id),
+            label,
+            Point {
+                x: center,
+                y: top + height - label_height / 2.0,
+            },
+            ACTOR_FONT_SIZE,
+            &theme.node_text_color,
+            None,
+        );
+        for element in &mut self.elements[first..] {
+            element["groupIds"] = json!([id]);
+        }
+    }
+}
+
+/// Coordinates to two decimals, which is as precise as Excalidraw draws
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(code: &str) -> Vec<Value> {
+        let scene: Value =
+            serde_json::from_str(&export_excalidraw(code, &DiagramOptions::svg(None)).unwrap())
+                .unwrap();
+        assert_eq!(scene["type"], "excalidraw");
+        scene["elements"].as_array().unwrap().clone()
+    }
+
+    fn find<'a>(elements: &'a [Value], id: &str) -> &'a Value {
+        elements.iter().find(|e| e["id"] == id).unwrap()
+    }
+
+    #[test]
+    fn test_export_flowchart() {
+        let elements = export(
+            "flowchart LR\n    subgraph S [Checkout]\n        A([Start]) -->|pay| B{Paid?}\n    end\n    B -.-> C((Done))\n    A ~~~ C",
+        );
+        let start = find(&elements, "node-A");
+        assert_eq!(start["type"], "rectangle");
+        assert_eq!(start["roundness"]["type"], 3);
+        assert_eq!(find(&elements, "node-B")["type"], "diamond");
+        assert_eq!(find(&elements, "node-C")["type"], "ellipse");
+        let label = find(&elements, "node-A-label");
+        assert_eq!(label["text"], "Start");
+        assert_eq!(label["containerId"], "node-A");
+
+        // Arrows start and end on the shapes they join, and carry their labels
+        let edge = find(&elements, "edge-0");
+        assert_eq!(edge["startBinding"]["elementId"], "node-A");
+        assert_eq!(edge["endBinding"]["elementId"], "node-B");
+        assert_eq!(edge["endArrowhead"], "triangle");
+        assert_eq!(find(&elements, "edge-0-label")["containerId"], "edge-0");
+        let bound = start["boundElements"].as_array().unwrap();
+        assert!(bound.iter().any(|b| b["id"] == "edge-0"));
+        assert!(start["x"].as_f64().unwrap() < edge["x"].as_f64().unwrap());
+        assert_eq!(find(&elements, "edge-1")["strokeStyle"], "dashed");
+        assert!(elements.iter().all(|e| e["id"] != "edge-2"));
+
+        // Subgraphs come before, and so beneath, their nodes
+        let position = |id: &str| elements.iter().position(|e| e["id"] == id).unwrap();
+        assert!(position("subgraph-S") < position("node-A"));
+        assert_eq!(find(&elements, "subgraph-S-label")["text"], "Checkout");
+
+        assert!(export_excalidraw("pie\n    \"A\" : 1", &DiagramOptions::svg(None)).is_err());
+    }
+
+    #[test]
+    fn test_export_sequence() {
+        let elements = export(
+            "sequenceDiagram\n    actor U as User\n    participant S as Server\n    U->>S: a request with a long label\n    S-->>U: ok\n    S->>S: retry",
+        );
+        let user = find(&elements, "lifeline-U")["x"].as_f64().unwrap();
+        let server = find(&elements, "lifeline-S")["x"].as_f64().unwrap();
+        let label = find(&elements, "message-0-label");
+        assert!(server - user > label["width"].as_f64().unwrap());
+        assert_eq!(find(&elements, "participant-S-top-label")["text"], "Server");
+        assert_eq!(
+            find(&elements, "participant-U-bottom-label")["text"],
+            "User"
+        );
+        assert_eq!(
+            find(&elements, "participant-U-top-head")["groupIds"][0],
+            "participant-U-top"
+        );
+
+        let request = find(&elements, "message-0");
+        assert_eq!(request["x"].as_f64().unwrap(), user);
+        assert_eq!(request["points"][1][0].as_f64().unwrap(), server - user);
+        assert_eq!(request["endArrowhead"], "triangle");
+        let reply = find(&elements, "message-1");
+        assert_eq!(reply["strokeStyle"], "dashed");
+        assert!(reply["y"].as_f64().unwrap() > request["y"].as_f64().unwrap());
+        assert!(label["y"].as_f64().unwrap() < request["y"].as_f64().unwrap());
+        assert_eq!(
+            find(&elements, "message-2")["points"]
+                .as_array()
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+}
@@ -1,8 +1,18 @@
-use crate::models::{FileOperationResult, MermaidFile, RecentFile};
+use crate::models::{
+    FileOperationResult, ImportedDiagram, MermaidFile, RecentFile, SqlImportOptions,
+};
+use crate::services::dot_service::import_dot;
+use crate::services::drawio_service::import_drawio;
+use crate::services::plantuml_service::import_plantuml;
+use crate::services::sql_service::import_sql;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+const MERMAID_EXTENSIONS: [&str; 2] = ["mmd", "mermaid"];
+const PLANTUML_EXTENSIONS: [&str; 5] = ["puml", "plantuml", "pu", "iuml", "wsd"];
+
 pub struct FileService {
     recent_files: Vec<RecentFile>,
 }
@@ -123,8 +133,132 @@ impl FileService {
         directory: P,
         recursive: bool,
     ) -> Result<Vec<PathBuf>, String> {
+        self.find_files(directory.as_ref(), recursive, &MERMAID_EXTENSIONS)
+    }
+
+    /// Find PlantUML files in a directory
+    pub fn find_plantuml_files<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        recursive: bool,
+    ) -> Result<Vec<PathBuf>, String> {
+        self.find_files(directory.as_ref(), recursive, &PLANTUML_EXTENSIONS)
+    }
+
+    /// Convert every PlantUML file in a directory to a `.mmd` file, written
+    /// beside it or under `output_directory` with the same relative path.
+    /// Files that fail are reported in their result rather than stopping the
+    /// batch.
+    pub fn import_plantuml_directory<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        recursive: bool,
+        output_directory: Option<&Path>,
+        overwrite: bool,
+    ) -> Result<Vec<ImportedDiagram>, String> {
         let directory = directory.as_ref();
-        
+        let mut files = self.find_plantuml_files(directory, recursive)?;
+        files.sort();
+
+        let outputs: Vec<PathBuf> = files
+            .iter()
+            .map(|path| {
+                match output_directory {
+                    Some(output_directory) => {
+                        output_directory.join(path.strip_prefix(directory).unwrap_or(path))
+                    }
+                    None => path.clone(),
+                }
+                .with_extension("mmd")
+            })
+            .collect();
+        // Files such as `a.puml` and `a.pu` would both become `a.mmd`, so
+        // those keep their own extension as `a.puml.mmd` and `a.pu.mmd`
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for output in &outputs {
+            *counts
+                .entry(output.to_string_lossy().to_lowercase())
+                .or_default() += 1;
+        }
+
+        let results = files
+            .into_iter()
+            .zip(outputs)
+            .map(|(path, output)| {
+                let output = match counts[&output.to_string_lossy().to_lowercase()] {
+                    1 => output,
+                    _ => {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        output.with_file_name(format!("{}.mmd", name))
+                    }
+                };
+
+                let path_str = Some(path.to_string_lossy().to_string());
+                match self.import_plantuml_file(&path, &output, overwrite) {
+                    Ok(imported) => ImportedDiagram {
+                        path: path_str,
+                        output_path: Some(output.to_string_lossy().to_string()),
+                        ..imported
+                    },
+                    Err(e) => ImportedDiagram::error(path_str, e),
+                }
+            })
+            .collect();
+        Ok(results)
+    }
+
+    /// Convert a PlantUML, DOT, draw.io or SQL file to Mermaid, picking the
+    /// importer from the file extension. Anything unrecognised is read as
+    /// PlantUML.
+    pub fn import_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        sql_options: &SqlImportOptions,
+    ) -> Result<ImportedDiagram, String> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let imported = match extension.as_str() {
+            "sql" => import_sql(&source, sql_options)?,
+            "dot" | "gv" => import_dot(&source)?,
+            "drawio" | "xml" => import_drawio(&source)?,
+            _ => import_plantuml(&source)?,
+        };
+        Ok(ImportedDiagram {
+            path: Some(path.to_string_lossy().to_string()),
+            ..imported
+        })
+    }
+
+    fn import_plantuml_file(
+        &self,
+        path: &Path,
+        output: &Path,
+        overwrite: bool,
+    ) -> Result<ImportedDiagram, String> {
+        if output.exists() && !overwrite {
+            return Err(format!("{} already exists", output.display()));
+        }
+        let source = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let imported = import_plantuml(&source)?;
+
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        fs::write(output, &imported.code).map_err(|e| format!("Failed to write file: {}", e))?;
+        Ok(imported)
+    }
+
+    fn find_files(
+        &self,
+        directory: &Path,
+        recursive: bool,
+        extensions: &[&str],
+    ) -> Result<Vec<PathBuf>, String> {
         if !directory.exists() {
             return Err(format!("Directory does not exist: {}", directory.display()));
         }
@@ -133,17 +267,18 @@ impl FileService {
             return Err("Path is not a directory".to_string());
         }
 
-        let mut mermaid_files = Vec::new();
-        
-        self.scan_directory(directory, recursive, &mut mermaid_files)?;
-        
-        Ok(mermaid_files)
+        let mut files = Vec::new();
+
+        self.scan_directory(directory, recursive, extensions, &mut files)?;
+
+        Ok(files)
     }
 
     fn scan_directory(
         &self,
         directory: &Path,
         recursive: bool,
+        extensions: &[&str],
         results: &mut Vec<PathBuf>,
     ) -> Result<(), String> {
         let entries = fs::read_dir(directory)
@@ -155,12 +290,12 @@ impl FileService {
 
             if path.is_file() {
                 if let Some(extension) = path.extension() {
-                    if extension == "mmd" || extension == "mermaid" {
+                    if extensions.iter().any(|&known| extension == known) {
                         results.push(path);
                     }
                 }
             } else if path.is_dir() && recursive {
-                self.scan_directory(&path, recursive, results)?;
+                self.scan_directory(&path, recursive, extensions, results)?;
             }
         }
 
@@ -198,4 +333,70 @@ impl Default for FileService {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp::TempDir;
+
+    #[test]
+    fn test_import_plantuml_directory() {
+        let temp = TempDir::new("mermaid_plantuml");
+        let root = temp.path();
+        let source = root.join("source");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        let diagram = "@startuml\nAlice -> Bob: hello\n@enduml\n";
+        for name in ["a.puml", "a.pu", "b.wsd", "nested/c.plantuml"] {
+            fs::write(source.join(name), diagram).unwrap();
+        }
+        fs::write(source.join("broken.iuml"), "not plantuml").unwrap();
+
+        let service = FileService::new();
+        let output = root.join("output");
+        let results = service
+            .import_plantuml_directory(&source, true, Some(&output), false)
+            .unwrap();
+        let written: Vec<(String, bool)> = results
+            .iter()
+            .map(|result| {
+                let path = Path::new(result.path.as_deref().unwrap());
+                let name = path.strip_prefix(&source).unwrap().to_string_lossy();
+                (name.replace('\\', "/"), result.error.is_none())
+            })
+            .collect();
+        assert_eq!(
+            written,
+            [
+                ("a.pu".to_string(), true),
+                ("a.puml".to_string(), true),
+                ("b.wsd".to_string(), true),
+                ("broken.iuml".to_string(), false),
+                ("nested/c.plantuml".to_string(), true),
+            ]
+        );
+        for name in ["a.pu.mmd", "a.puml.mmd", "b.mmd", "nested/c.mmd"] {
+            let code = fs::read_to_string(output.join(name)).unwrap();
+            assert!(code.starts_with("sequenceDiagram"), "{}", code);
+        }
+        assert!(!output.join("a.mmd").exists());
+
+        // Existing files are only replaced when asked to
+        let again = service
+            .import_plantuml_directory(&source, true, Some(&output), false)
+            .unwrap();
+        assert!(again[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("already exists"));
+        assert!(service
+            .import_plantuml_directory(&source, true, Some(&output), true)
+            .unwrap()
+            .iter()
+            .all(
+                |result| result.path.as_deref().unwrap().ends_with("broken.iuml")
+                    || result.error.is_none()
+            ));
+    }
+}
@@ -0,0 +1,1083 @@
+use crate::models::{ArrowHead, DiagramKind, EdgeStroke, Node, NodeShape, ParsedDiagram};
+use crate::services::layout_service::{
+    layout, Direction, Layout, LayoutCluster, LayoutEdge, LayoutNode, LayoutOptions, Point,
+};
+use crate::services::native_service::{escape_xml, num, text_width, ThemeVariables};
+use crate::services::parser_service::parse_diagram;
+use crate::services::renderer_service::RenderError;
+use serde_json::Value;
+
+/// Sizes from mermaid's flowchart renderer
+pub(crate) const FONT_SIZE: f64 = 16.0;
+const LINE_HEIGHT: f64 = 24.0;
+const NODE_PADDING: f64 = 15.0;
+const DIAGRAM_PADDING: f64 = 8.0;
+pub(crate) const TITLE_FONT_SIZE: f64 = 18.0;
+/// How far a self-loop reaches out of its node
+const LOOP_SIZE: f64 = 30.0;
+
+/// A flowchart with every node, cluster and edge placed the way the SVG
+/// renderer draws it
+pub(crate) struct FlowchartLayout {
+    pub(crate) diagram: ParsedDiagram,
+    pub(crate) options: LayoutOptions,
+    shapes: Vec<Shape>,
+    clusters: Vec<LayoutCluster>,
+    edges: Vec<PlacedEdge>,
+    pub(crate) layout: Layout,
+    /// Space around the layout
+    pub(crate) padding: f64,
+    /// Space above the layout for the diagram title
+    pub(crate) title_height: f64,
+}
+
+/// An edge between two laid out nodes; subgraph endpoints are attached to
+/// one of their nodes and keep the cluster index
+struct PlacedEdge {
+    /// Index into the diagram's edges
+    edge: usize,
+    from: (usize, Option<usize>),
+    to: (usize, Option<usize>),
+    label: Option<Vec<String>>,
+    label_size: Option<(f64, f64)>,
+}
+
+impl FlowchartLayout {
+    /// Width and height of a node's outline, without room for self-loops
+    pub(crate) fn node_size(&self, node: usize) -> (f64, f64) {
+        (self.shapes[node].width, self.shapes[node].height)
+    }
+
+    /// Clusters that have nodes, outermost first
+    pub(crate) fn cluster_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.clusters.len())
+            .filter(|&cluster| self.layout.clusters[cluster].is_some())
+            .collect();
+        order.sort_by_key(|&cluster| depth(&self.clusters, cluster));
+        order
+    }
+
+    /// Drawn edges as the diagram edge index, their path clipped at the
+    /// shapes they join and the centre of their label. Self-loops leave the
+    /// node's right side and come back.
+    pub(crate) fn edge_paths(&self) -> impl Iterator<Item = (usize, Vec<Point>, Point)> + '_ {
+        self.edges
+            .iter()
+            .zip(&self.layout.edges)
+            .map(|(placed, route)| {
+                if route.points.is_empty() {
+                    let (center, shape) = (
+                        self.layout.nodes[placed.from.0],
+                        &self.shapes[placed.from.0],
+                    );
+                    let right = center.x + shape.width / 2.0;
+                    let points = vec![
+                        Point {
+                            x: right,
+                            y: center.y - shape.height / 4.0,
+                        },
+                        Point {
+                            x: right + LOOP_SIZE,
+                            y: center.y,
+                        },
+                        Point {
+                            x: right,
+                            y: center.y + shape.height / 4.0,
+                        },
+                    ];
+                    let (_, label) = self_loop(center, shape, placed.label_size);
+                    return (placed.edge, points, label);
+                }
+                let mut points = route.points.clone();
+                let last = points.len() - 1;
+                points[0] = clip(
+                    &self.layout,
+                    &self.shapes,
+                    placed.from,
+                    points[0],
+                    points[1],
+                );
+                points[last] = clip(
+                    &self.layout,
+                    &self.shapes,
+                    placed.to,
+                    points[last],
+                    points[last - 1],
+                );
+                let label = route.label.unwrap_or_else(|| midpoint(&points));
+                (placed.edge, points, label)
+            })
+    }
+}
+
+/// Lay out a flowchart with a layered layout similar to mermaid's dagre
+/// layout. Only the classic syntax is supported; the `@{ ... }` shape and
+/// edge metadata is left to mermaid.
+pub(crate) fn layout_flowchart(
+    code: &str,
+    config: &Value,
+    font: &str,
+) -> Result<FlowchartLayout, RenderError> {
+    if code.contains("@{") {
+        return Err(RenderError::Unsupported(
+            "Flowchart shape and edge metadata has no native renderer".to_string(),
+        ));
+    }
+    let diagram = parse_diagram(code).map_err(RenderError::Failed)?;
+    if diagram.kind != DiagramKind::Flowchart {
+        return Err(RenderError::Unsupported(format!(
+            "{} diagrams are not flowcharts",
+            diagram.kind
+        )));
+    }
+
+    let flowchart = &config["flowchart"];
+    let options = LayoutOptions {
+        direction: Direction::parse(diagram.direction.as_deref()),
+        node_spacing: flowchart["nodeSpacing"].as_f64().unwrap_or(50.0),
+        rank_spacing: flowchart["rankSpacing"].as_f64().unwrap_or(50.0),
+        ..LayoutOptions::default()
+    };
+
+    // Nodes, with room on both sides for any self-loop
+    let shapes: Vec<Shape> = diagram
+        .nodes
+        .iter()
+        .map(|node| Shape::new(node, font))
+        .collect();
+    let mut layout_nodes: Vec<LayoutNode> = shapes
+        .iter()
+        .map(|shape| LayoutNode {
+            width: shape.width,
+            height: shape.height,
+            cluster: None,
+        })
+        .collect();
+    for (index, node) in diagram.nodes.iter().enumerate() {
+        layout_nodes[index].cluster = diagram
+            .subgraphs
+            .iter()
+            .rposition(|s| s.nodes.contains(&node.id));
+    }
+
+    let clusters: Vec<LayoutCluster> = diagram
+        .subgraphs
+        .iter()
+        .map(|subgraph| {
+            let title = subgraph.label.as_deref().unwrap_or(&subgraph.id);
+            let lines = label_lines(title);
+            LayoutCluster {
+                parent: subgraph
+                    .parent
+                    .as_ref()
+                    .and_then(|p| diagram.subgraphs.iter().position(|s| s.id == *p)),
+                title_width: lines_width(&lines, font),
+                title_height: lines.len() as f64 * LINE_HEIGHT,
+            }
+        })
+        .collect();
+
+    // Edges, with subgraph endpoints attached to one of their nodes
+    let endpoint = |id: &str| edge_endpoint(&diagram, id);
+    let mut edges = Vec::new();
+    let mut layout_edges = Vec::new();
+    for (index, edge) in diagram.edges.iter().enumerate() {
+        let (Some(from), Some(to)) = (endpoint(&edge.from), endpoint(&edge.to)) else {
+            continue;
+        };
+        let label = edge.label.as_deref().map(label_lines);
+        let label_size = label
+            .as_ref()
+            .map(|lines| (lines_width(lines, font), lines.len() as f64 * LINE_HEIGHT));
+        if from.0 == to.0 && from.1.is_none() && to.1.is_none() {
+            let reach = LOOP_SIZE + label_size.map(|(w, _)| w).unwrap_or(0.0);
+            layout_nodes[from.0].width = layout_nodes[from.0]
+                .width
+                .max(shapes[from.0].width + 2.0 * reach);
+        }
+        layout_edges.push(LayoutEdge {
+            from: from.0,
+            to: to.0,
+            label: label_size,
+        });
+        edges.push(PlacedEdge {
+            edge: index,
+            from,
+            to,
+            label,
+            label_size,
+        });
+    }
+
+    let layout = layout(&layout_nodes, &layout_edges, &clusters, &options);
+    let padding = flowchart["diagramPadding"]
+        .as_f64()
+        .unwrap_or(DIAGRAM_PADDING);
+    let title_height = match diagram.title {
+        Some(_) => TITLE_FONT_SIZE + 2.0 * padding,
+        None => 0.0,
+    };
+    Ok(FlowchartLayout {
+        diagram,
+        options,
+        shapes,
+        clusters,
+        edges,
+        layout,
+        padding,
+        title_height,
+    })
+}
+
+/// Render a flowchart to SVG, laid out by [`layout_flowchart`]
+pub fn render_flowchart(
+    code: &str,
+    config: &Value,
+    theme: &ThemeVariables,
+    svg_id: &str,
+) -> Result<String, RenderError> {
+    let FlowchartLayout {
+        diagram,
+        options,
+        shapes,
+        clusters,
+        edges,
+        layout,
+        padding,
+        title_height,
+    } = layout_flowchart(code, config, &theme.font_family)?;
+
+    let width = layout.width + 2.0 * padding;
+    let height = layout.height + 2.0 * padding + title_height;
+    let id = escape_xml(svg_id);
+    let mut svg = format!(
+        r#"<svg id="{id}" width="100%" xmlns="http://www.w3.org/2000/svg" class="flowchart" style="max-width: {width}px;" viewBox="0 0 {width} {height}" role="graphics-document document" aria-roledescription="flowchart-v2">"#,
+        id = id,
+        width = num(width),
+        height = num(height)
+    );
+    svg.push_str(&style(&id, theme));
+    svg.push_str(&markers(&id));
+    if let Some(ref title) = diagram.title {
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" class="flowchartTitleText">{}</text>"#,
+            num(width / 2.0),
+            num(padding + TITLE_FONT_SIZE / 2.0),
+            escape_xml(title)
+        ));
+    }
+    svg.push_str(&format!(
+        r#"<g transform="translate({},{})">"#,
+        num(padding),
+        num(padding + title_height)
+    ));
+
+    // Clusters, outermost first so inner ones are drawn on top
+    svg.push_str(r#"<g class="clusters">"#);
+    let mut order: Vec<usize> = (0..clusters.len()).collect();
+    order.sort_by_key(|&c| depth(&clusters, c));
+    for cluster in order {
+        let Some(rect) = layout.clusters[cluster] else {
+            continue;
+        };
+        let subgraph = &diagram.subgraphs[cluster];
+        let title = subgraph.label.as_deref().unwrap_or(&subgraph.id);
+        svg.push_str(&format!(
+            r#"<g class="cluster" id="{}"><rect x="{}" y="{}" width="{}" height="{}"/>{}</g>"#,
+            escape_xml(&subgraph.id),
+            num(rect.x),
+            num(rect.y),
+            num(rect.width),
+            num(rect.height),
+            text(
+                &label_lines(title),
+                Point {
+                    x: rect.x + rect.width / 2.0,
+                    y: rect.y + options.cluster_padding + clusters[cluster].title_height / 2.0,
+                },
+                "cluster-label"
+            )
+        ));
+    }
+    svg.push_str("</g>");
+
+    // Edges, then their labels on top
+    svg.push_str(r#"<g class="edgePaths">"#);
+    let mut labels = String::new();
+    for (index, placed) in edges.iter().enumerate() {
+        let edge = &diagram.edges[placed.edge];
+        let (from, to) = (&placed.from, &placed.to);
+        let (label, label_size) = (&placed.label, &placed.label_size);
+        if edge.stroke == EdgeStroke::Invisible {
+            continue;
+        }
+        let route = &layout.edges[index];
+        let (path, label_position) = if route.points.is_empty() {
+            self_loop(layout.nodes[from.0], &shapes[from.0], *label_size)
+        } else {
+            let mut points = route.points.clone();
+            let last = points.len() - 1;
+            points[0] = clip(&layout, &shapes, *from, points[0], points[1]);
+            points[last] = clip(&layout, &shapes, *to, points[last], points[last - 1]);
+            (
+                basis_path(&points),
+                route.label.unwrap_or_else(|| midpoint(&points)),
+            )
+        };
+
+        let thickness = match edge.stroke {
+            EdgeStroke::Thick => "thick",
+            _ => "normal",
+        };
+        let pattern = match edge.stroke {
+            EdgeStroke::Dotted => "dotted",
+            _ => "solid",
+        };
+        svg.push_str(&format!(
+            r#"<path d="{}" id="{}-L-{}-{}-{}" class="flowchart-link edge-thickness-{} edge-pattern-{}"{}{}/>"#,
+            path,
+            id,
+            escape_xml(&edge.from),
+            escape_xml(&edge.to),
+            index,
+            thickness,
+            pattern,
+            marker(&id, edge.tail, "Start", "start"),
+            marker(&id, edge.head, "End", "end"),
+        ));
+
+        if let (Some(lines), Some((label_width, label_height))) = (label, label_size) {
+            labels.push_str(&format!(
+                r#"<g class="edgeLabel" transform="translate({},{})"><rect class="background" x="{}" y="{}" width="{}" height="{}"/>{}</g>"#,
+                num(label_position.x),
+                num(label_position.y),
+                num(-label_width / 2.0),
+                num(-label_height / 2.0),
+                num(*label_width),
+                num(*label_height),
+                text(lines, Point { x: 0.0, y: 0.0 }, "label")
+            ));
+        }
+    }
+    svg.push_str("</g>");
+    svg.push_str(r#"<g class="edgeLabels">"#);
+    svg.push_str(&labels);
+    svg.push_str("</g>");
+
+    // Nodes, with classDef and style declarations inlined
+    svg.push_str(r#"<g class="nodes">"#);
+    for (index, (node, shape)) in diagram.nodes.iter().zip(&shapes).enumerate() {
+        let (shape_style, text_style) = node_styles(&diagram, node);
+        let position = layout.nodes[index];
+        let classes: String = node
+            .classes
+            .iter()
+            .map(|c| format!(" {}", escape_xml(c)))
+            .collect();
+        svg.push_str(&format!(
+            r#"<g class="node default{}" id="{}-flowchart-{}-{}" transform="translate({},{})">{}{}</g>"#,
+            classes,
+            id,
+            escape_xml(&node.id),
+            index,
+            num(position.x),
+            num(position.y),
+            shape.svg(&shape_style),
+            text_with_style(&shape.lines, &text_style)
+        ));
+    }
+    svg.push_str("</g></g></svg>");
+    Ok(svg)
+}
+
+/// A node's label and outline, centred on the origin
+struct Shape {
+    kind: NodeShape,
+    lines: Vec<String>,
+    width: f64,
+    height: f64,
+}
+
+impl Shape {
+    /// Sizes follow mermaid's shapes: the label with padding, widened for
+    /// slanted or rounded sides
+    fn new(node: &Node, font: &str) -> Self {
+        let lines = label_lines(node.display_label());
+        let width = lines_width(&lines, font) + 2.0 * NODE_PADDING;
+        let height = lines.len() as f64 * LINE_HEIGHT + 2.0 * NODE_PADDING;
+        let (width, height) = match node.shape {
+            NodeShape::Stadium => (width + height / 2.0, height),
+            NodeShape::Subroutine => (width + 16.0, height),
+            NodeShape::Cylinder => {
+                let rx = width / 2.0;
+                (width, height + rx / (2.5 + width / 50.0))
+            }
+            NodeShape::Circle => (width.max(height), width.max(height)),
+            NodeShape::DoubleCircle => (width.max(height) + 10.0, width.max(height) + 10.0),
+            NodeShape::Rhombus => (width + height, width + height),
+            NodeShape::Hexagon => (width + height / 2.0, height),
+            NodeShape::Asymmetric => (width + height / 2.0, height),
+            NodeShape::Parallelogram
+            | NodeShape::ParallelogramAlt
+            | NodeShape::Trapezoid
+            | NodeShape::TrapezoidAlt => (width + height / 3.0, height),
+            NodeShape::Rect | NodeShape::Round => (width, height),
+        };
+        Self {
+            kind: node.shape,
+            lines,
+            width,
+            height,
+        }
+    }
+
+    /// Corners of polygonal shapes
+    fn polygon(&self) -> Option<Vec<Point>> {
+        let (w, h) = (self.width / 2.0, self.height / 2.0);
+        let slant = self.height / 3.0;
+        let point = |x: f64, y: f64| Point { x, y };
+        let points = match self.kind {
+            NodeShape::Rhombus => {
+                vec![point(0.0, -h), point(w, 0.0), point(0.0, h), point(-w, 0.0)]
+            }
+            NodeShape::Hexagon => {
+                let m = self.height / 4.0;
+                vec![
+                    point(-w + m, -h),
+                    point(w - m, -h),
+                    point(w, 0.0),
+                    point(w - m, h),
+                    point(-w + m, h),
+                    point(-w, 0.0),
+                ]
+            }
+            NodeShape::Asymmetric => vec![
+                point(-w, -h),
+                point(w, -h),
+                point(w, h),
+                point(-w, h),
+                point(-w + h, 0.0),
+            ],
+            NodeShape::Parallelogram => {
+                vec![
+                    point(-w + slant, -h),
+                    point(w, -h),
+                    point(w - slant, h),
+                    point(-w, h),
+                ]
+            }
+            NodeShape::ParallelogramAlt => {
+                vec![
+                    point(-w, -h),
+                    point(w - slant, -h),
+                    point(w, h),
+                    point(-w + slant, h),
+                ]
+            }
+            NodeShape::Trapezoid => {
+                vec![
+                    point(-w + slant / 2.0, -h),
+                    point(w - slant / 2.0, -h),
+                    point(w, h),
+                    point(-w, h),
+                ]
+            }
+            NodeShape::TrapezoidAlt => {
+                vec![
+                    point(-w, -h),
+                    point(w, -h),
+                    point(w - slant / 2.0, h),
+                    point(-w + slant / 2.0, h),
+                ]
+            }
+            _ => return None,
+        };
+        Some(points)
+    }
+
+    fn svg(&self, style: &str) -> String {
+        let (w, h) = (self.width / 2.0, self.height / 2.0);
+        let style = match style {
+            "" => String::new(),
+            style => format!(r#" style="{}""#, escape_xml(style)),
+        };
+        let rect = |radius: f64| {
+            format!(
+                r#"<rect rx="{r}" ry="{r}" x="{}" y="{}" width="{}" height="{}"{}/>"#,
+                num(-w),
+                num(-h),
+                num(self.width),
+                num(self.height),
+                style,
+                r = num(radius)
+            )
+        };
+        match self.kind {
+            NodeShape::Rect => rect(0.0),
+            NodeShape::Round => rect(5.0),
+            NodeShape::Stadium => rect(h),
+            NodeShape::Subroutine => format!(
+                r#"{}<path d="M{x},{top}V{bottom}M{x2},{top}V{bottom}"{}/>"#,
+                rect(0.0),
+                style,
+                x = num(-w + 8.0),
+                x2 = num(w - 8.0),
+                top = num(-h),
+                bottom = num(h)
+            ),
+            NodeShape::Cylinder => {
+                let rx = w;
+                let ry = rx / (2.5 + self.width / 50.0);
+                let body = self.height - 2.0 * ry;
+                format!(
+                    r#"<path d="M{x},{y}a{rx},{ry},0,0,0,{d},0a{rx},{ry},0,0,0,{nd},0l0,{body}a{rx},{ry},0,0,0,{d},0l0,-{body}"{}/>"#,
+                    style,
+                    x = num(-w),
+                    y = num(-h + ry),
+                    rx = num(rx),
+                    ry = num(ry),
+                    d = num(2.0 * rx),
+                    nd = num(-2.0 * rx),
+                    body = num(body)
+                )
+            }
+            NodeShape::Circle => format!(r#"<circle r="{}"{}/>"#, num(w), style),
+            NodeShape::DoubleCircle => format!(
+                r#"<circle r="{}"{s}/><circle r="{}"{s}/>"#,
+                num(w),
+                num(w - 5.0),
+                s = style
+            ),
+            _ => {
+                let points: Vec<String> = self
+                    .polygon()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|p| format!("{},{}", num(p.x), num(p.y)))
+                    .collect();
+                format!(r#"<polygon points="{}"{}/>"#, points.join(" "), style)
+            }
+        }
+    }
+
+    /// Where the line from the centre towards `toward` leaves the shape,
+    /// relative to the centre
+    fn boundary(&self, toward: Point) -> Point {
+        let (w, h) = (self.width / 2.0, self.height / 2.0);
+        if toward.x == 0.0 && toward.y == 0.0 {
+            return toward;
+        }
+        match self.kind {
+            NodeShape::Circle | NodeShape::DoubleCircle => {
+                let length = toward.x.hypot(toward.y);
+                Point {
+                    x: toward.x / length * w,
+                    y: toward.y / length * w,
+                }
+            }
+            _ => {
+                // Extend the direction past the outline before crossing it
+                let scale = (w + h) / toward.x.hypot(toward.y);
+                let far = Point {
+                    x: toward.x * scale,
+                    y: toward.y * scale,
+                };
+                let polygon = self.polygon().unwrap_or_else(|| rect_corners(w, h));
+                polygon_boundary(&polygon, Point { x: 0.0, y: 0.0 }, far)
+            }
+        }
+    }
+}
+
+fn rect_corners(w: f64, h: f64) -> Vec<Point> {
+    vec![
+        Point { x: -w, y: -h },
+        Point { x: w, y: -h },
+        Point { x: w, y: h },
+        Point { x: -w, y: h },
+    ]
+}
+
+/// First point where the ray from `inside` towards `toward` crosses the
+/// polygon's outline, or `toward` itself when it is within the polygon
+fn polygon_boundary(polygon: &[Point], inside: Point, toward: Point) -> Point {
+    let (dx, dy) = (toward.x - inside.x, toward.y - inside.y);
+    let mut nearest = 1.0_f64;
+    for (index, a) in polygon.iter().enumerate() {
+        let b = polygon[(index + 1) % polygon.len()];
+        let (ex, ey) = (b.x - a.x, b.y - a.y);
+        let denominator = dx * ey - dy * ex;
+        if denominator.abs() < 1e-9 {
+            continue;
+        }
+        let t = ((a.x - inside.x) * ey - (a.y - inside.y) * ex) / denominator;
+        let s = ((a.x - inside.x) * dy - (a.y - inside.y) * dx) / denominator;
+        if t > 0.0 && (0.0..=1.0).contains(&s) {
+            nearest = nearest.min(t);
+        }
+    }
+    Point {
+        x: inside.x + dx * nearest,
+        y: inside.y + dy * nearest,
+    }
+}
+
+/// Move an edge's end from the centre of its node, or from inside its
+/// subgraph, to the outline
+fn clip(
+    layout: &Layout,
+    shapes: &[Shape],
+    (node, cluster): (usize, Option<usize>),
+    end: Point,
+    toward: Point,
+) -> Point {
+    if let Some(rect) = cluster.and_then(|c| layout.clusters[c]) {
+        let corners = rect_corners(rect.width / 2.0, rect.height / 2.0);
+        let center = rect.center();
+        let corners: Vec<Point> = corners
+            .iter()
+            .map(|p| Point {
+                x: p.x + center.x,
+                y: p.y + center.y,
+            })
+            .collect();
+        return polygon_boundary(&corners, end, toward);
+    }
+    let offset = shapes[node].boundary(Point {
+        x: toward.x - end.x,
+        y: toward.y - end.y,
+    });
+    Point {
+        x: end.x + offset.x,
+        y: end.y + offset.y,
+    }
+}
+
+/// A loop out of the node's right side and back, and its label position
+fn self_loop(center: Point, shape: &Shape, label: Option<(f64, f64)>) -> (String, Point) {
+    let right = center.x + shape.width / 2.0;
+    let (top, bottom) = (center.y - shape.height / 4.0, center.y + shape.height / 4.0);
+    let path = format!(
+        "M{},{}C{x},{},{x},{},{},{}",
+        num(right),
+        num(top),
+        num(top - LOOP_SIZE / 2.0),
+        num(bottom + LOOP_SIZE / 2.0),
+        num(right),
+        num(bottom),
+        x = num(right + LOOP_SIZE * 1.2)
+    );
+    let label_x = right + LOOP_SIZE + label.map(|(w, _)| w / 2.0).unwrap_or(0.0);
+    (
+        path,
+        Point {
+            x: label_x,
+            y: center.y,
+        },
+    )
+}
+
+/// A smooth path through the points, like d3's `curveBasis`
+fn basis_path(points: &[Point]) -> String {
+    let mut path = format!("M{},{}", num(points[0].x), num(points[0].y));
+    if points.len() == 2 {
+        path.push_str(&format!("L{},{}", num(points[1].x), num(points[1].y)));
+    }
+    if points.len() <= 2 {
+        return path;
+    }
+
+    let curve = |path: &mut String, p0: Point, p1: Point, p: Point| {
+        path.push_str(&format!(
+            "C{},{},{},{},{},{}",
+            num((2.0 * p0.x + p1.x) / 3.0),
+            num((2.0 * p0.y + p1.y) / 3.0),
+            num((p0.x + 2.0 * p1.x) / 3.0),
+            num((p0.y + 2.0 * p1.y) / 3.0),
+            num((p0.x + 4.0 * p1.x + p.x) / 6.0),
+            num((p0.y + 4.0 * p1.y + p.y) / 6.0)
+        ));
+    };
+    let (mut p0, mut p1) = (points[0], points[1]);
+    path.push_str(&format!(
+        "L{},{}",
+        num((5.0 * p0.x + p1.x) / 6.0),
+        num((5.0 * p0.y + p1.y) / 6.0)
+    ));
+    for &point in &points[2..] {
+        curve(&mut path, p0, p1, point);
+        (p0, p1) = (p1, point);
+    }
+    curve(&mut path, p0, p1, p1);
+    path.push_str(&format!("L{},{}", num(p1.x), num(p1.y)));
+    path
+}
+
+fn midpoint(points: &[Point]) -> Point {
+    let (a, b) = (points[0], points[points.len() - 1]);
+    Point {
+        x: (a.x + b.x) / 2.0,
+        y: (a.y + b.y) / 2.0,
+    }
+}
+
+/// The node an edge endpoint attaches to, and the subgraph it stands for
+/// when the edge points at a whole subgraph
+pub(crate) fn edge_endpoint(diagram: &ParsedDiagram, id: &str) -> Option<(usize, Option<usize>)> {
+    if let Some(index) = diagram.nodes.iter().position(|n| n.id == id) {
+        return Some((index, None));
+    }
+    let cluster = diagram.subgraphs.iter().position(|s| s.id == id)?;
+    representative(diagram, cluster).map(|node| (node, Some(cluster)))
+}
+
+/// The first node inside a subgraph or its nested subgraphs. Subgraphs are
+/// walked by index and visited once each, so a subgraph nested in another
+/// with the same id cannot count as its own child.
+pub(crate) fn representative(diagram: &ParsedDiagram, cluster: usize) -> Option<usize> {
+    let parents: Vec<Option<usize>> = diagram
+        .subgraphs
+        .iter()
+        .map(|subgraph| {
+            subgraph
+                .parent
+                .as_ref()
+                .and_then(|p| diagram.subgraphs.iter().position(|s| s.id == *p))
+        })
+        .collect();
+    let mut visited = vec![false; diagram.subgraphs.len()];
+    let mut pending = vec![cluster];
+
+    while let Some(current) = pending.pop() {
+        if std::mem::replace(&mut visited[current], true) {
+            continue;
+        }
+        let node = diagram.subgraphs[current]
+            .nodes
+            .iter()
+            .find_map(|id| diagram.nodes.iter().position(|n| n.id == *id));
+        if node.is_some() {
+            return node;
+        }
+        // Children go on the stack last first, so the first is tried first
+        pending.extend(
+            (0..parents.len())
+                .rev()
+                .filter(|&child| parents[child] == Some(current)),
+        );
+    }
+    None
+}
+
+fn depth(clusters: &[LayoutCluster], cluster: usize) -> usize {
+    let mut depth = 0;
+    let mut current = clusters[cluster].parent;
+    while let Some(parent) = current {
+        depth += 1;
+        if depth > clusters.len() {
+            break;
+        }
+        current = clusters[parent].parent;
+    }
+    depth
+}
+
+/// Label text split on `<br>` tags
+pub(crate) fn label_lines(label: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut rest = label;
+    while let Some(start) = rest.to_ascii_lowercase().find("<br") {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        lines.push(rest[..start].trim().to_string());
+        rest = &rest[start + end + 1..];
+    }
+    lines.push(rest.trim().to_string());
+    lines
+}
+
+fn lines_width(lines: &[String], font: &str) -> f64 {
+    lines
+        .iter()
+        .map(|line| text_width(line, FONT_SIZE, font))
+        .fold(0.0, f64::max)
+}
+
+fn text(lines: &[String], center: Point, class: &str) -> String {
+    format!(
+        r#"<g class="{}" transform="translate({},{})">{}</g>"#,
+        class,
+        num(center.x),
+        num(center.y),
+        text_with_style(lines, "")
+    )
+}
+
+/// Lines of text centred on the origin
+fn text_with_style(lines: &[String], style: &str) -> String {
+    let style = match style {
+        "" => String::new(),
+        style => format!(r#" style="{}""#, escape_xml(style)),
+    };
+    let first = -(lines.len() as f64 - 1.0) / 2.0 * LINE_HEIGHT;
+    let spans: String = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            format!(
+                r#"<tspan x="0" y="{}">{}</tspan>"#,
+                num(first + index as f64 * LINE_HEIGHT),
+                escape_xml(line)
+            )
+        })
+        .collect();
+    format!(
+        r#"<text text-anchor="middle" dominant-baseline="central"{}>{}</text>"#,
+        style, spans
+    )
+}
+
+/// Colours and outline of a node as drawn, for formats that style shapes
+/// with properties rather than CSS
+pub(crate) struct NodePaint {
+    pub(crate) fill: String,
+    pub(crate) stroke: String,
+    pub(crate) text: String,
+    /// In pixels, when set by a style
+    pub(crate) stroke_width: Option<f64>,
+    pub(crate) dashed: bool,
+    pub(crate) bold: bool,
+}
+
+/// The theme's node colours, overridden by classDef and style statements
+pub(crate) fn node_paint(
+    diagram: &ParsedDiagram,
+    node: &Node,
+    theme: &ThemeVariables,
+) -> NodePaint {
+    let mut paint = NodePaint {
+        fill: theme.main_bkg.clone(),
+        stroke: theme.node_border.clone(),
+        text: theme.node_text_color.clone(),
+        stroke_width: None,
+        dashed: false,
+        bold: false,
+    };
+    let (shape, text) = node_styles(diagram, node);
+    for declaration in shape.split(';') {
+        match declaration.split_once(':') {
+            Some(("fill", value)) => paint.fill = value.to_string(),
+            Some(("stroke", value)) => paint.stroke = value.to_string(),
+            Some(("stroke-width", value)) => {
+                paint.stroke_width = value.trim_end_matches("px").parse().ok()
+            }
+            Some(("stroke-dasharray", _)) => paint.dashed = true,
+            _ => {}
+        }
+    }
+    for declaration in text.split(';') {
+        match declaration.split_once(':') {
+            Some(("fill", value)) => paint.text = value.to_string(),
+            Some(("font-weight", "bold")) => paint.bold = true,
+            _ => {}
+        }
+    }
+    paint
+}
+
+/// Declarations from the `default` class, the node's classes and its
+/// `style` statements, split into those for the shape and for the label
+fn node_styles(diagram: &ParsedDiagram, node: &Node) -> (String, String) {
+    let declarations = diagram
+        .class_defs
+        .iter()
+        .filter(|c| c.name == "default")
+        .chain(
+            node.classes
+                .iter()
+                .flat_map(|class| diagram.class_defs.iter().filter(move |c| c.name == *class)),
+        )
+        .flat_map(|c| c.styles.iter())
+        .chain(node.styles.iter());
+
+    let mut shape = Vec::new();
+    let mut text = Vec::new();
+    for declaration in declarations {
+        let Some((property, value)) = declaration.split_once(':') else {
+            continue;
+        };
+        let (property, value) = (property.trim(), value.trim());
+        match property {
+            "color" => text.push(format!("fill:{}", value)),
+            property if property.starts_with("font") || property == "text-decoration" => {
+                text.push(format!("{}:{}", property, value))
+            }
+            property => shape.push(format!("{}:{}", property, value)),
+        }
+    }
+    (shape.join(";"), text.join(";"))
+}
+
+fn marker(id: &str, head: ArrowHead, suffix: &str, position: &str) -> String {
+    let kind = match head {
+        ArrowHead::None => return String::new(),
+        ArrowHead::Arrow => "point",
+        ArrowHead::Circle => "circle",
+        ArrowHead::Cross => "cross",
+    };
+    format!(
+        r#" marker-{}="url(#{}_flowchart-{}{})""#,
+        position, id, kind, suffix
+    )
+}
+
+fn markers(id: &str) -> String {
+    let marker = |name: &str, ref_x: u8, class: &str, shape: &str| {
+        format!(
+            r#"<marker id="{id}_flowchart-{name}" class="marker {class}" viewBox="0 0 10 10" refX="{ref_x}" refY="5" markerUnits="userSpaceOnUse" markerWidth="8" markerHeight="8" orient="auto">{shape}</marker>"#,
+            id = id,
+            name = name,
+            class = class,
+            ref_x = ref_x,
+            shape = shape
+        )
+    };
+    let circle =
+        r#"<circle cx="5" cy="5" r="5" style="stroke-width: 1; stroke-dasharray: 1, 0;"/>"#;
+    let cross = r#"<path d="M1,1L9,9M9,1L1,9" style="stroke-width: 2; stroke-dasharray: 1, 0;"/>"#;
+    format!(
+        "<defs>{}{}{}{}{}{}</defs>",
+        marker(
+            "pointEnd",
+            10,
+            "flowchart",
+            r#"<path d="M0,0L10,5L0,10z" style="stroke-width: 1; stroke-dasharray: 1, 0;"/>"#
+        ),
+        marker(
+            "pointStart",
+            0,
+            "flowchart",
+            r#"<path d="M0,5L10,10L10,0z" style="stroke-width: 1; stroke-dasharray: 1, 0;"/>"#
+        ),
+        marker("circleEnd", 10, "flowchart", circle),
+        marker("circleStart", 0, "flowchart", circle),
+        marker("crossEnd", 10, "cross flowchart", cross),
+        marker("crossStart", 0, "cross flowchart", cross),
+    )
+}
+
+fn style(id: &str, theme: &ThemeVariables) -> String {
+    format!(
+        "<style>#{id}{{font-family:{font};font-size:{size}px;fill:{text};}}\
+         #{id} .node rect,#{id} .node circle,#{id} .node polygon,#{id} .node path{{fill:{bkg};stroke:{border};stroke-width:1px;}}\
+         #{id} .node text{{fill:{node_text};}}\
+         #{id} .flowchart-link{{stroke:{line};fill:none;}}\
+         #{id} .edge-thickness-normal{{stroke-width:2px;}}\
+         #{id} .edge-thickness-thick{{stroke-width:3.5px;}}\
+         #{id} .edge-pattern-dotted{{stroke-dasharray:3;}}\
+         #{id} .marker{{fill:{line};stroke:{line};}}\
+         #{id} .marker.cross{{stroke:{line};}}\
+         #{id} .edgeLabel rect{{opacity:0.5;fill:{label_bkg};}}\
+         #{id} .edgeLabel text{{fill:{text};}}\
+         #{id} .cluster rect{{fill:{cluster_bkg};stroke:{cluster_border};stroke-width:1px;}}\
+         #{id} .cluster text{{fill:{title};}}\
+         #{id} .flowchartTitleText{{text-anchor:middle;font-size:{title_size}px;fill:{text};}}</style>",
+        id = id,
+        font = escape_xml(&theme.font_family),
+        size = num(FONT_SIZE),
+        text = escape_xml(&theme.text_color),
+        bkg = escape_xml(&theme.main_bkg),
+        border = escape_xml(&theme.node_border),
+        node_text = escape_xml(&theme.node_text_color),
+        line = escape_xml(&theme.line_color),
+        label_bkg = escape_xml(&theme.edge_label_background),
+        cluster_bkg = escape_xml(&theme.cluster_bkg),
+        cluster_border = escape_xml(&theme.cluster_border),
+        title = escape_xml(&theme.title_color),
+        title_size = num(TITLE_FONT_SIZE),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::raster_service::parse_svg;
+    use serde_json::json;
+
+    const FLOW: &str = r#"flowchart LR
+    classDef warn fill:#f96,color:#fff
+    A[Start] --> B{Is it?}
+    B -- Yes --> C([Done<br>now]):::warn
+    B -.-> D[(Store)]
+    D ==> A
+    D --> D
+    subgraph Group [The group]
+        E((E)) --x F>Flag]
+    end
+    C --> Group
+    style E stroke-width:4px
+"#;
+
+    #[test]
+    fn test_render_flowchart() {
+        let theme = ThemeVariables::from_config(&json!({})).unwrap();
+        let svg = render_flowchart(FLOW, &json!({}), &theme, "flow").unwrap();
+        parse_svg(&svg).unwrap();
+
+        assert!(svg.contains(r#"aria-roledescription="flowchart-v2""#));
+        assert!(svg.contains(r#"class="node default warn""#));
+        assert!(svg.contains(r#"style="fill:#f96""#));
+        assert!(svg.contains(
+            r#"<text text-anchor="middle" dominant-baseline="central" style="fill:#fff">"#
+        ));
+        assert!(svg.contains(r#"<circle r="#) && svg.contains("stroke-width:4px"));
+        assert!(svg.contains(">now</tspan>"));
+        assert!(svg.contains("edge-pattern-dotted"));
+        assert!(svg.contains("edge-thickness-thick"));
+        assert!(svg.contains(r#"marker-end="url(#flow_flowchart-crossEnd)""#));
+        assert!(svg.contains(r#"<g class="cluster" id="Group">"#));
+        assert!(svg.contains(">The group</tspan>"));
+        assert!(svg.contains(">Yes</tspan>"));
+        assert_eq!(svg.matches("class=\"flowchart-link").count(), 7);
+
+        assert!(matches!(
+            render_flowchart("flowchart TD\n  A@{ shape: rect }", &json!({}), &theme, "x"),
+            Err(RenderError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_subgraph_nested_in_its_namesake() {
+        let theme = ThemeVariables::from_config(&json!({})).unwrap();
+        let code = "flowchart TD\n subgraph A\n subgraph A\n end\n end\n B --> A";
+        let svg = render_flowchart(code, &json!({}), &theme, "flow").unwrap();
+        parse_svg(&svg).unwrap();
+        // Neither subgraph has a node to attach the edge to
+        assert_eq!(svg.matches("class=\"flowchart-link").count(), 0);
+    }
+
+    #[test]
+    fn test_shape_geometry() {
+        let diamond = Shape {
+            kind: NodeShape::Rhombus,
+            lines: vec![],
+            width: 100.0,
+            height: 100.0,
+        };
+        let edge = diamond.boundary(Point { x: 0.0, y: 200.0 });
+        assert_eq!((edge.x, edge.y), (0.0, 50.0));
+        let corner = diamond.boundary(Point { x: 10.0, y: 10.0 });
+        assert!((corner.x - 25.0).abs() < 1e-9 && (corner.y - 25.0).abs() < 1e-9);
+
+        assert_eq!(label_lines("a<br/>b<BR>c"), vec!["a", "b", "c"]);
+        assert_eq!(
+            basis_path(&[
+                Point { x: 0.0, y: 0.0 },
+                Point { x: 0.0, y: 60.0 },
+                Point { x: 60.0, y: 60.0 }
+            ]),
+            "M0,0L0,10C0,20,0,40,10,50C20,60,40,60,50,60L60,60"
+        );
+    }
+}
@@ -0,0 +1,1085 @@
+use crate::models::{GanttSchedule, ScheduledTask};
+use crate::services::native_service::{escape_xml, num, text_width, ThemeVariables};
+use crate::services::parser_service::{
+    is_comment_or_blank, strip_accessibility, strip_front_matter,
+};
+use crate::services::renderer_service::RenderError;
+use crate::utils::date::{
+    add_months, format_date, now, parse_date, start_of_day, strftime, to_iso, weekday, DateTime,
+    MAX_TIMESTAMP, MS_PER_DAY, MS_PER_HOUR, MS_PER_MINUTE, MS_PER_SECOND, MS_PER_WEEK, WEEKDAYS,
+};
+use serde_json::Value;
+
+/// Layout defaults from mermaid's gantt config
+const WIDTH: f64 = 800.0;
+const BAR_HEIGHT: f64 = 20.0;
+const BAR_GAP: f64 = 4.0;
+const TOP_PADDING: f64 = 50.0;
+const SIDE_PADDING: f64 = 75.0;
+const GRID_LINE_START_PADDING: f64 = 35.0;
+const FONT_SIZE: f64 = 11.0;
+const TITLE_TOP_MARGIN: f64 = 25.0;
+const SECTION_STYLES: usize = 4;
+const DEFAULT_DATE_FORMAT: &str = "YYYY-MM-DD";
+const DEFAULT_AXIS_FORMAT: &str = "%Y-%m-%d";
+/// More grid lines than this fall back to the automatic tick interval
+const MAX_TICKS: i64 = 10_000;
+/// Longest span, in days, that is walked day by day to find excluded days
+const MAX_EXCLUDED_SPAN: i64 = 100_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GanttChart {
+    pub title: Option<String>,
+    pub acc_title: Option<String>,
+    pub acc_descr: Option<String>,
+    pub date_format: String,
+    pub axis_format: Option<String>,
+    /// Fixed grid spacing such as `1week`
+    pub tick_interval: Option<String>,
+    /// Lowercased `excludes` entries: `weekends`, weekday names or dates
+    pub excludes: Vec<String>,
+    /// Lowercased `includes` entries, which win over `excludes`
+    pub includes: Vec<String>,
+    /// ISO weekday the two-day weekend starts on: 5 for Friday, 6 for Saturday
+    pub weekend_start: u32,
+    /// Weekday weekly ticks fall on, 0 for Sunday
+    pub week_start: u32,
+    pub inclusive_end_dates: bool,
+    /// `off`, or CSS for the line marking today
+    pub today_marker: Option<String>,
+    pub tasks: Vec<GanttTask>,
+}
+
+/// A task with its dates resolved, as naive millisecond timestamps
+#[derive(Debug, Clone, PartialEq)]
+pub struct GanttTask {
+    pub id: String,
+    pub name: String,
+    pub section: Option<String>,
+    pub start: i64,
+    pub end: i64,
+    /// Where the bar stops; before `end` when the task ends on excluded days
+    pub render_end: i64,
+    pub milestone: bool,
+    pub crit: bool,
+    pub active: bool,
+    pub done: bool,
+    pub dependencies: Vec<String>,
+}
+
+/// A task as written, before its dates are resolved
+struct RawTask {
+    id: String,
+    name: String,
+    section: Option<String>,
+    tags: [bool; 4],
+    /// None to start where the previous task ends
+    start: Option<String>,
+    end: String,
+}
+
+const TAGS: [&str; 4] = ["milestone", "crit", "active", "done"];
+
+/// Parse a mermaid `gantt` chart and resolve the dates of its tasks
+pub fn parse_gantt(code: &str) -> Result<GanttChart, String> {
+    let (title, lines) = strip_front_matter(code);
+    let (acc_title, acc_descr, lines) = strip_accessibility(lines);
+    let mut lines = lines.into_iter().filter(|line| !is_comment_or_blank(line));
+
+    let header = lines.next().ok_or("Empty mermaid code provided")?.trim();
+    if header != "gantt" {
+        return Err(format!("Not a gantt chart: {}", header));
+    }
+
+    let mut chart = GanttChart {
+        title,
+        acc_title,
+        acc_descr,
+        date_format: DEFAULT_DATE_FORMAT.to_string(),
+        axis_format: None,
+        tick_interval: None,
+        excludes: Vec::new(),
+        includes: Vec::new(),
+        weekend_start: 6,
+        week_start: 0,
+        inclusive_end_dates: false,
+        today_marker: None,
+        tasks: Vec::new(),
+    };
+    let mut section = None;
+    let mut raw_tasks = Vec::new();
+    let mut generated_ids = 0;
+
+    for line in lines {
+        let line = line.trim().trim_end_matches(';').trim();
+        if let Some(value) = statement(line, "dateFormat") {
+            chart.date_format = value.to_string();
+        } else if let Some(value) = statement(line, "axisFormat") {
+            chart.axis_format = Some(value.to_string());
+        } else if let Some(value) = statement(line, "tickInterval") {
+            chart.tick_interval = Some(value.to_string());
+        } else if let Some(value) = statement(line, "excludes") {
+            chart.excludes = date_list(value);
+        } else if let Some(value) = statement(line, "includes") {
+            chart.includes = date_list(value);
+        } else if let Some(value) = statement(line, "todayMarker") {
+            chart.today_marker = Some(value.to_string());
+        } else if let Some(value) = statement(line, "weekend") {
+            chart.weekend_start = match value {
+                "friday" => 5,
+                "saturday" => 6,
+                other => return Err(format!("Invalid weekend start: {}", other)),
+            };
+        } else if let Some(value) = statement(line, "weekday") {
+            chart.week_start = WEEKDAYS
+                .iter()
+                .position(|day| day.eq_ignore_ascii_case(value))
+                .ok_or_else(|| format!("Invalid weekday: {}", value))?
+                as u32;
+        } else if let Some(value) = statement(line, "title") {
+            chart.title = Some(value.to_string());
+        } else if let Some(value) = statement(line, "section") {
+            section = Some(value.to_string());
+        } else if line == "inclusiveEndDates" {
+            chart.inclusive_end_dates = true;
+        } else if line == "topAxis"
+            || statement(line, "displayMode").is_some()
+            || statement(line, "click").is_some()
+        {
+            // Presentation and interaction only
+        } else if let Some((name, data)) = line.split_once(':') {
+            raw_tasks.push(parse_task(
+                name.trim(),
+                data,
+                section.clone(),
+                &mut generated_ids,
+            )?);
+        } else {
+            return Err(format!("Invalid gantt statement: {}", line));
+        }
+    }
+
+    // Durations could never be finished when no day counts
+    if (0..7).all(|day| is_excluded_weekday(&chart, day)) {
+        return Err("The excludes leave no working days in the week".to_string());
+    }
+    chart.tasks = resolve_tasks(&chart, &raw_tasks)?;
+    Ok(chart)
+}
+
+/// The value of a `keyword value` statement
+fn statement<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let value = line.strip_prefix(keyword)?;
+    value.starts_with(char::is_whitespace).then(|| value.trim())
+}
+
+fn date_list(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// `Name : [tags,] [id,] [start,] end` like mermaid: one item is an end
+/// after the previous task, two are a start and end, three add an id
+fn parse_task(
+    name: &str,
+    data: &str,
+    section: Option<String>,
+    generated_ids: &mut usize,
+) -> Result<RawTask, String> {
+    let mut items: Vec<&str> = data.split(',').map(str::trim).collect();
+    let mut tags = [false; 4];
+    while let Some(index) = items
+        .first()
+        .and_then(|item| TAGS.iter().position(|tag| tag == item))
+    {
+        tags[index] = true;
+        items.remove(0);
+    }
+
+    let mut generated_id = || {
+        *generated_ids += 1;
+        format!("task{}", generated_ids)
+    };
+    let (id, start, end) = match items[..] {
+        [end] => (generated_id(), None, end),
+        [start, end] => (generated_id(), Some(start), end),
+        [id, start, end] => (id.to_string(), Some(start), end),
+        _ => {
+            return Err(format!(
+                "Invalid task data for \"{}\": {}",
+                name,
+                data.trim()
+            ))
+        }
+    };
+    Ok(RawTask {
+        id,
+        name: name.to_string(),
+        section,
+        tags,
+        start: start.map(str::to_string),
+        end: end.to_string(),
+    })
+}
+
+/// Resolve start and end dates, repeating while tasks wait on `after` and
+/// `until` references to tasks further down
+fn resolve_tasks(chart: &GanttChart, raw_tasks: &[RawTask]) -> Result<Vec<GanttTask>, String> {
+    let mut resolved: Vec<Option<GanttTask>> = vec![None; raw_tasks.len()];
+    loop {
+        let mut progress = false;
+        for index in 0..raw_tasks.len() {
+            if resolved[index].is_none() {
+                resolved[index] = resolve_task(chart, raw_tasks, &resolved, index)?;
+                progress |= resolved[index].is_some();
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+
+    raw_tasks
+        .iter()
+        .zip(resolved)
+        .map(|(raw, task)| {
+            task.ok_or_else(|| format!("Circular dependency involving task \"{}\"", raw.id))
+        })
+        .collect()
+}
+
+/// The task's dates, or None while a task it refers to is unresolved
+fn resolve_task(
+    chart: &GanttChart,
+    raw_tasks: &[RawTask],
+    resolved: &[Option<GanttTask>],
+    index: usize,
+) -> Result<Option<GanttTask>, String> {
+    let raw = &raw_tasks[index];
+    let referenced = |ids: &str| -> Result<Option<Vec<&GanttTask>>, String> {
+        let mut tasks = Vec::new();
+        for id in ids.split_whitespace() {
+            let position = raw_tasks
+                .iter()
+                .position(|task| task.id == id)
+                .ok_or_else(|| format!("Task \"{}\" refers to unknown task \"{}\"", raw.id, id))?;
+            match &resolved[position] {
+                Some(task) => tasks.push(task),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(tasks))
+    };
+    let mut dependencies = Vec::new();
+
+    let start = match raw.start.as_deref() {
+        None if index == 0 => {
+            return Err(format!(
+                "The first task \"{}\" needs a start date",
+                raw.name
+            ))
+        }
+        None => match &resolved[index - 1] {
+            Some(previous) => previous.end,
+            None => return Ok(None),
+        },
+        Some(text) => match text.strip_prefix("after ") {
+            Some(ids) => {
+                let Some(tasks) = referenced(ids)? else {
+                    return Ok(None);
+                };
+                dependencies = ids.split_whitespace().map(str::to_string).collect();
+                tasks.iter().map(|task| task.end).max().unwrap_or_default()
+            }
+            None => read_date(text, &chart.date_format).ok_or_else(|| {
+                format!("Invalid start date \"{}\" for task \"{}\"", text, raw.name)
+            })?,
+        },
+    };
+
+    let mut manual_end = false;
+    let end = if let Some(ids) = raw.end.strip_prefix("until ") {
+        let Some(tasks) = referenced(ids)? else {
+            return Ok(None);
+        };
+        tasks.iter().map(|task| task.start).min().unwrap_or(start)
+    } else if let Some(date) = read_date(&raw.end, &chart.date_format) {
+        manual_end = true;
+        date + if chart.inclusive_end_dates {
+            MS_PER_DAY
+        } else {
+            0
+        }
+    } else {
+        add_duration(start, &raw.end).ok_or_else(|| {
+            format!(
+                "Invalid end date or duration \"{}\" for task \"{}\"",
+                raw.end, raw.name
+            )
+        })?
+    };
+
+    let (end, render_end) = match manual_end || chart.excludes.is_empty() {
+        true => (end, end),
+        false => skip_excluded_days(chart, start, end).ok_or_else(|| {
+            format!(
+                "Task \"{}\" is too long to skip excluded days; it may span at most {} days",
+                raw.name, MAX_EXCLUDED_SPAN
+            )
+        })?,
+    };
+    let [milestone, crit, active, done] = raw.tags;
+    Ok(Some(GanttTask {
+        id: raw.id.clone(),
+        name: raw.name.clone(),
+        section: raw.section.clone(),
+        start,
+        end,
+        render_end,
+        milestone,
+        crit,
+        active,
+        done,
+        dependencies,
+    }))
+}
+
+/// A date in the chart's format, or plain `YYYY-MM-DD`
+fn read_date(text: &str, date_format: &str) -> Option<i64> {
+    parse_date(text, date_format)
+        .or_else(|| parse_date(text, DEFAULT_DATE_FORMAT))
+        .filter(|date| date.abs() <= MAX_TIMESTAMP)
+}
+
+/// `start` plus a duration such as `3d`, `12h` or `1.5w`, with day.js's
+/// rounding of fractional days and months; None past the range of dates
+fn add_duration(start: i64, duration: &str) -> Option<i64> {
+    let split = duration.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let value: f64 = duration[..split].parse().ok()?;
+    // Larger counts are out of range in every unit
+    if value > MAX_TIMESTAMP as f64 {
+        return None;
+    }
+    let days = |days: f64| (days.round() as i64).checked_mul(MS_PER_DAY);
+    let end = match &duration[split..] {
+        "ms" => start.checked_add(value as i64),
+        "s" => start.checked_add((value * MS_PER_SECOND as f64) as i64),
+        "m" => start.checked_add((value * MS_PER_MINUTE as f64) as i64),
+        "h" => start.checked_add((value * MS_PER_HOUR as f64) as i64),
+        "d" => days(value).and_then(|ms| start.checked_add(ms)),
+        "w" => days(value * 7.0).and_then(|ms| start.checked_add(ms)),
+        // More months than that are out of range, and overflow add_months
+        "M" | "y" if value > 12.0 * 300_000.0 => None,
+        "M" => Some(add_months(start, value.trunc() as i64)),
+        "y" => Some(add_months(start, value.trunc() as i64 * 12)),
+        _ => None,
+    };
+    end.filter(|end| end.abs() <= MAX_TIMESTAMP)
+}
+
+/// Push the end back by one day for every excluded day the task spans,
+/// the way mermaid does; the bar itself stops after the last working day.
+/// None when the task spans more than `MAX_EXCLUDED_SPAN` days.
+fn skip_excluded_days(chart: &GanttChart, start: i64, mut end: i64) -> Option<(i64, i64)> {
+    let mut day = start + MS_PER_DAY;
+    let mut excluded = false;
+    let mut render_end = end;
+    while day <= end {
+        if day - start > MAX_EXCLUDED_SPAN * MS_PER_DAY {
+            return None;
+        }
+        if !excluded {
+            render_end = end;
+        }
+        excluded = is_excluded(chart, day);
+        if excluded {
+            end += MS_PER_DAY;
+        }
+        day += MS_PER_DAY;
+    }
+    Some((end, render_end))
+}
+
+fn is_excluded(chart: &GanttChart, timestamp: i64) -> bool {
+    let formatted = format_date(timestamp, &chart.date_format).to_lowercase();
+    let date_only = format_date(timestamp, DEFAULT_DATE_FORMAT);
+    let listed = |entries: &[String]| entries.contains(&formatted) || entries.contains(&date_only);
+    if listed(&chart.includes) {
+        return false;
+    }
+    is_excluded_weekday(chart, weekday(timestamp)) || listed(&chart.excludes)
+}
+
+/// Whether `excludes` names the day of the week, 0 for Sunday, or it is
+/// part of the weekend
+fn is_excluded_weekday(chart: &GanttChart, day: u32) -> bool {
+    let iso_weekday = match day {
+        0 => 7,
+        day => day,
+    };
+    (chart.excludes.iter().any(|entry| entry == "weekends")
+        && (iso_weekday == chart.weekend_start || iso_weekday == chart.weekend_start + 1))
+        || chart
+            .excludes
+            .contains(&WEEKDAYS[day as usize].to_lowercase())
+}
+
+/// The resolved schedule of a gantt chart, for reporting
+pub fn schedule(code: &str) -> Result<GanttSchedule, String> {
+    let chart = parse_gantt(code)?;
+    Ok(GanttSchedule {
+        start: chart.tasks.iter().map(|task| task.start).min().map(to_iso),
+        end: chart.tasks.iter().map(|task| task.end).max().map(to_iso),
+        tasks: chart
+            .tasks
+            .iter()
+            .map(|task| ScheduledTask {
+                id: task.id.clone(),
+                name: task.name.clone(),
+                section: task.section.clone(),
+                start: to_iso(task.start),
+                end: to_iso(task.end),
+                milestone: task.milestone,
+                crit: task.crit,
+                active: task.active,
+                done: task.done,
+                dependencies: task.dependencies.clone(),
+            })
+            .collect(),
+        title: chart.title,
+        date_format: chart.date_format,
+        excludes: chart.excludes,
+    })
+}
+
+/// Maps timestamps to x positions, like d3's `scaleTime().rangeRound()`
+struct TimeScale {
+    start: i64,
+    end: i64,
+    width: f64,
+}
+
+impl TimeScale {
+    fn x(&self, timestamp: i64) -> f64 {
+        if self.end == self.start {
+            return (self.width / 2.0).round();
+        }
+        ((timestamp - self.start) as f64 / (self.end - self.start) as f64 * self.width).round()
+    }
+}
+
+/// Render a gantt chart to SVG the way mermaid lays it out: one row per
+/// task shaded by section, section titles on the left and a date axis at
+/// the bottom
+pub fn render_gantt(
+    code: &str,
+    config: &Value,
+    theme: &ThemeVariables,
+    svg_id: &str,
+) -> Result<String, RenderError> {
+    let chart = parse_gantt(code).map_err(RenderError::Failed)?;
+    let gantt = &config["gantt"];
+    let setting = |name: &str, default: f64| gantt[name].as_f64().unwrap_or(default);
+    let width = setting("useWidth", WIDTH);
+    let bar_height = setting("barHeight", BAR_HEIGHT);
+    let gap = bar_height + setting("barGap", BAR_GAP);
+    let top_padding = setting("topPadding", TOP_PADDING);
+    let left_padding = setting("leftPadding", SIDE_PADDING);
+    let right_padding = setting("rightPadding", SIDE_PADDING);
+    let grid_start = setting("gridLineStartPadding", GRID_LINE_START_PADDING);
+    let font_size = setting("fontSize", FONT_SIZE);
+    let section_font_size = setting("sectionFontSize", FONT_SIZE);
+    let title_top_margin = setting("titleTopMargin", TITLE_TOP_MARGIN);
+    let height = chart.tasks.len() as f64 * gap + 2.0 * top_padding;
+
+    let scale = TimeScale {
+        start: chart
+            .tasks
+            .iter()
+            .map(|task| task.start)
+            .min()
+            .unwrap_or_default(),
+        end: chart
+            .tasks
+            .iter()
+            .map(|task| task.end)
+            .max()
+            .unwrap_or_default(),
+        width: width - left_padding - right_padding,
+    };
+    let mut sections: Vec<Option<&str>> = Vec::new();
+    for task in &chart.tasks {
+        if !sections.contains(&task.section.as_deref()) {
+            sections.push(task.section.as_deref());
+        }
+    }
+    let section_number = |task: &GanttTask| {
+        sections
+            .iter()
+            .position(|section| *section == task.section.as_deref())
+            .unwrap_or_default()
+            % SECTION_STYLES
+    };
+
+    let id = escape_xml(svg_id);
+    let mut svg = format!(
+        r#"<svg id="{id}" width="100%" xmlns="http://www.w3.org/2000/svg" class="gantt" style="max-width: {width}px;" viewBox="0 0 {width} {height}" role="graphics-document document" aria-roledescription="gantt">"#,
+        id = id,
+        width = num(width),
+        height = num(height)
+    );
+    svg.push_str(&style(&id, theme));
+
+    // Grid
+    let axis_format = chart
+        .axis_format
+        .as_deref()
+        .or(gantt["axisFormat"].as_str())
+        .unwrap_or(DEFAULT_AXIS_FORMAT);
+    let tick_interval = chart
+        .tick_interval
+        .as_deref()
+        .or(gantt["tickInterval"].as_str());
+    svg.push_str(&format!(
+        r#"<g class="grid" transform="translate({},{})">"#,
+        num(left_padding),
+        num(height - 50.0)
+    ));
+    for tick in ticks(scale.start, scale.end, tick_interval, chart.week_start) {
+        svg.push_str(&format!(
+            r#"<g class="tick" transform="translate({},0)"><line y2="{}"/><text y="3" dy="1em" font-size="10">{}</text></g>"#,
+            num(scale.x(tick) + 0.5),
+            num(-(height - top_padding - grid_start)),
+            escape_xml(&strftime(tick, axis_format))
+        ));
+    }
+    svg.push_str("</g>");
+
+    // Section rows
+    for (index, task) in chart.tasks.iter().enumerate() {
+        svg.push_str(&format!(
+            r#"<rect x="0" y="{}" width="{}" height="{}" class="section section{}"/>"#,
+            num(index as f64 * gap + top_padding - 2.0),
+            num(width - right_padding / 2.0),
+            num(gap),
+            section_number(task)
+        ));
+    }
+
+    for (start, end) in excluded_ranges(&chart, scale.start, scale.end) {
+        svg.push_str(&format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" class="exclude-range"/>"#,
+            num(scale.x(start) + left_padding),
+            num(grid_start),
+            num(scale.x(end) - scale.x(start)),
+            num(height - top_padding - grid_start)
+        ));
+    }
+
+    // Bars and their labels
+    for (index, task) in chart.tasks.iter().enumerate() {
+        let number = section_number(task);
+        let y = index as f64 * gap + top_padding;
+        let mut start_x = scale.x(task.start);
+        let mut end_x = scale.x(task.render_end);
+        if task.milestone {
+            start_x += (scale.x(task.end) - scale.x(task.start)) / 2.0 - bar_height / 2.0;
+            end_x = start_x + bar_height;
+        }
+
+        let state = match (task.active, task.done, task.crit) {
+            (true, _, true) => "activeCrit",
+            (true, _, false) => "active",
+            (false, true, true) => "doneCrit",
+            (false, true, false) => "done",
+            (false, false, true) => "crit",
+            (false, false, false) => "task",
+        };
+        let milestone = if task.milestone { " milestone" } else { "" };
+        let transform = if task.milestone {
+            let (cx, cy) = (
+                start_x + left_padding + bar_height / 2.0,
+                y + bar_height / 2.0,
+            );
+            format!(
+                r#" transform="rotate(45,{cx},{cy}) translate({cx},{cy}) scale(0.8) translate({},{})""#,
+                num(-cx),
+                num(-cy),
+                cx = num(cx),
+                cy = num(cy)
+            )
+        } else {
+            String::new()
+        };
+        svg.push_str(&format!(
+            r#"<rect id="{}" rx="3" ry="3" x="{}" y="{}" width="{}" height="{}"{} class="task{} {}{}"/>"#,
+            escape_xml(&task.id),
+            num(start_x + left_padding),
+            num(y),
+            num(end_x - start_x),
+            num(bar_height),
+            transform,
+            milestone,
+            state,
+            number
+        ));
+
+        let text_class = match state {
+            "task" => String::new(),
+            state => format!(" {}Text{}", state, number),
+        };
+        let milestone_text = if task.milestone { " milestoneText" } else { "" };
+        let label_width = text_width(&task.name, font_size, &theme.font_family);
+        let (x, class) = if label_width <= end_x - start_x {
+            let x = (start_x + end_x) / 2.0 + left_padding;
+            (x, format!("taskText taskText{}", number))
+        } else if end_x + label_width + 1.5 * left_padding > width {
+            let x = start_x + left_padding - 5.0;
+            (x, format!("taskTextOutsideLeft taskTextOutside{}", number))
+        } else {
+            let x = end_x + left_padding + 5.0;
+            (x, format!("taskTextOutsideRight taskTextOutside{}", number))
+        };
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" font-size="{}" class="{}{}{}">{}</text>"#,
+            num(x),
+            num(y + bar_height / 2.0 + (font_size / 2.0 - 2.0)),
+            num(font_size),
+            class,
+            text_class,
+            milestone_text,
+            escape_xml(&task.name)
+        ));
+    }
+
+    // Section titles, centred on their rows
+    let mut row = 0;
+    for (number, section) in sections.iter().enumerate() {
+        let count = chart
+            .tasks
+            .iter()
+            .filter(|task| task.section.as_deref() == *section)
+            .count();
+        if let Some(section) = section {
+            svg.push_str(&format!(
+                r#"<text x="10" y="{}" font-size="{}" dominant-baseline="central" class="sectionTitle sectionTitle{}">{}</text>"#,
+                num((row as f64 + count as f64 / 2.0) * gap + top_padding),
+                num(section_font_size),
+                number % SECTION_STYLES,
+                escape_xml(section)
+            ));
+        }
+        row += count;
+    }
+
+    if chart.today_marker.as_deref() != Some("off") {
+        let today = now();
+        if (scale.start..=scale.end).contains(&today) {
+            let x = num(scale.x(today) + left_padding);
+            let style = match &chart.today_marker {
+                Some(css) => format!(r#" style="{}""#, escape_xml(css)),
+                None => String::new(),
+            };
+            svg.push_str(&format!(
+                r#"<line x1="{x}" y1="{}" x2="{x}" y2="{}" class="today"{}/>"#,
+                num(title_top_margin),
+                num(height - title_top_margin),
+                style,
+                x = x
+            ));
+        }
+    }
+
+    if let Some(ref title) = chart.title {
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" class="titleText">{}</text>"#,
+            num(width / 2.0),
+            num(title_top_margin),
+            escape_xml(title)
+        ));
+    }
+
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+/// Runs of excluded days between the first start and the last end, each
+/// as a start and an exclusive end; none for charts too long to walk
+fn excluded_ranges(chart: &GanttChart, start: i64, end: i64) -> Vec<(i64, i64)> {
+    if chart.excludes.is_empty() || end - start > MAX_EXCLUDED_SPAN * MS_PER_DAY {
+        return Vec::new();
+    }
+    let mut ranges: Vec<(i64, i64)> = Vec::new();
+    let mut day = start;
+    let mut previous_excluded = false;
+    while day <= end {
+        let excluded = is_excluded(chart, day);
+        match ranges.last_mut() {
+            Some(range) if excluded && previous_excluded => range.1 = day + MS_PER_DAY,
+            _ if excluded => ranges.push((day, day + MS_PER_DAY)),
+            _ => {}
+        }
+        previous_excluded = excluded;
+        day += MS_PER_DAY;
+    }
+    ranges
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TickUnit {
+    Millisecond,
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl TickUnit {
+    /// Approximate length, used to pick an interval
+    fn duration(self) -> i64 {
+        match self {
+            TickUnit::Millisecond => 1,
+            TickUnit::Second => MS_PER_SECOND,
+            TickUnit::Minute => MS_PER_MINUTE,
+            TickUnit::Hour => MS_PER_HOUR,
+            TickUnit::Day => MS_PER_DAY,
+            TickUnit::Week => MS_PER_WEEK,
+            TickUnit::Month => 30 * MS_PER_DAY,
+            TickUnit::Year => 365 * MS_PER_DAY,
+        }
+    }
+}
+
+/// The intervals d3's time scale chooses ticks from
+const TICK_INTERVALS: &[(TickUnit, i64)] = &[
+    (TickUnit::Second, 1),
+    (TickUnit::Second, 5),
+    (TickUnit::Second, 15),
+    (TickUnit::Second, 30),
+    (TickUnit::Minute, 1),
+    (TickUnit::Minute, 5),
+    (TickUnit::Minute, 15),
+    (TickUnit::Minute, 30),
+    (TickUnit::Hour, 1),
+    (TickUnit::Hour, 3),
+    (TickUnit::Hour, 6),
+    (TickUnit::Hour, 12),
+    (TickUnit::Day, 1),
+    (TickUnit::Day, 2),
+    (TickUnit::Week, 1),
+    (TickUnit::Month, 1),
+    (TickUnit::Month, 3),
+    (TickUnit::Year, 1),
+];
+
+/// Grid positions between `start` and `end`: every `tickInterval` such as
+/// `2day` when one is set, otherwise about ten at d3's choice of interval
+fn ticks(start: i64, end: i64, tick_interval: Option<&str>, week_start: u32) -> Vec<i64> {
+    let fixed = tick_interval.and_then(|interval| {
+        let split = interval.find(|c: char| !c.is_ascii_digit())?;
+        let step: i64 = interval[..split].parse().ok().filter(|step| *step > 0)?;
+        let unit = match &interval[split..] {
+            "millisecond" => TickUnit::Millisecond,
+            "second" => TickUnit::Second,
+            "minute" => TickUnit::Minute,
+            "hour" => TickUnit::Hour,
+            "day" => TickUnit::Day,
+            "week" => TickUnit::Week,
+            "month" => TickUnit::Month,
+            _ => return None,
+        };
+        ((end - start) / (unit.duration() * step) <= MAX_TICKS).then_some((unit, step))
+    });
+    let (unit, step) = fixed.unwrap_or_else(|| automatic_interval(start, end));
+    aligned_ticks(start, end, unit, step, week_start)
+}
+
+/// d3's `timeTicks` interval for about ten ticks
+fn automatic_interval(start: i64, end: i64) -> (TickUnit, i64) {
+    let target = (end - start) as f64 / 10.0;
+    let index = TICK_INTERVALS
+        .iter()
+        .position(|(unit, step)| (unit.duration() * step) as f64 > target)
+        .unwrap_or(TICK_INTERVALS.len());
+    if index == TICK_INTERVALS.len() {
+        let year = TickUnit::Year.duration() as f64;
+        return (TickUnit::Year, tick_step(target / year).max(1.0) as i64);
+    }
+    if index == 0 {
+        return (TickUnit::Millisecond, tick_step(target).max(1.0) as i64);
+    }
+    let (before, after) = (TICK_INTERVALS[index - 1], TICK_INTERVALS[index]);
+    let duration = |(unit, step): (TickUnit, i64)| (unit.duration() * step) as f64;
+    if target / duration(before) < duration(after) / target {
+        before
+    } else {
+        after
+    }
+}
+
+/// A round step of 1, 2 or 5 times a power of ten near `step`
+fn tick_step(step: f64) -> f64 {
+    if step <= 0.0 {
+        return 1.0;
+    }
+    let power = 10f64.powf(step.log10().floor());
+    let error = step / power;
+    power
+        * match error {
+            e if e >= 50f64.sqrt() => 10.0,
+            e if e >= 10f64.sqrt() => 5.0,
+            e if e >= 2f64.sqrt() => 2.0,
+            _ => 1.0,
+        }
+}
+
+/// Boundaries of `unit` within the range whose field is a multiple of
+/// `step`, like d3's `interval.every(step).range()`
+fn aligned_ticks(start: i64, end: i64, unit: TickUnit, step: i64, week_start: u32) -> Vec<i64> {
+    let date = DateTime::from_timestamp(start);
+    let mut tick = match unit {
+        TickUnit::Month => DateTime::date(date.year, date.month, 1).timestamp(),
+        TickUnit::Year => DateTime::date(date.year, 1, 1).timestamp(),
+        TickUnit::Day | TickUnit::Week => start_of_day(start),
+        _ => start - start.rem_euclid(unit.duration()),
+    };
+    let next = |tick: i64| match unit {
+        TickUnit::Week => tick + MS_PER_DAY,
+        TickUnit::Month => add_months(tick, 1),
+        TickUnit::Year => add_months(tick, 12),
+        _ => tick + unit.duration(),
+    };
+
+    let mut ticks = Vec::new();
+    while tick <= end {
+        let date = DateTime::from_timestamp(tick);
+        let field = match unit {
+            TickUnit::Millisecond => date.millisecond as i64,
+            TickUnit::Second => date.second as i64,
+            TickUnit::Minute => date.minute as i64,
+            TickUnit::Hour => date.hour as i64,
+            TickUnit::Day => date.day as i64 - 1,
+            TickUnit::Week if weekday(tick) != week_start => -1,
+            TickUnit::Week => tick.div_euclid(MS_PER_WEEK),
+            TickUnit::Month => date.month as i64 - 1,
+            TickUnit::Year => date.year,
+        };
+        if tick >= start && field >= 0 && field % step == 0 {
+            ticks.push(tick);
+        }
+        tick = next(tick);
+    }
+    ticks
+}
+
+fn style(id: &str, theme: &ThemeVariables) -> String {
+    let font = escape_xml(&theme.font_family);
+    let numbered = |class: &str| {
+        (0..SECTION_STYLES)
+            .map(|number| format!("#{} .{}{}", id, class, number))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let dark = escape_xml(&theme.task_text_dark_color);
+    let state_text: Vec<String> = [
+        "activeText",
+        "doneText",
+        "critText",
+        "activeCritText",
+        "doneCritText",
+    ]
+    .iter()
+    .map(|class| numbered(class))
+    .collect();
+    format!(
+        "<style>#{id}{{font-family:{font};font-size:16px;fill:{text};}}\
+         #{id} .section{{stroke:none;opacity:0.2;}}\
+         #{id} .section0{{fill:{section};}}\
+         #{id} .section2{{fill:{section2};}}\
+         #{id} .section1,#{id} .section3{{fill:{alt_section};opacity:0.2;}}\
+         #{id} .sectionTitle{{text-anchor:start;font-family:{font};}}\
+         {section_titles}{{fill:{title};}}\
+         #{id} .grid .tick line{{stroke:{grid};opacity:0.8;shape-rendering:crispEdges;}}\
+         #{id} .grid .tick text{{font-family:{font};fill:{text};text-anchor:middle;}}\
+         #{id} .exclude-range{{fill:{exclude};}}\
+         #{id} .today{{fill:none;stroke:{today};stroke-width:2px;}}\
+         #{id} .task{{stroke-width:2;}}\
+         #{id} .taskText{{text-anchor:middle;font-family:{font};}}\
+         #{id} .taskTextOutsideRight{{fill:{dark};text-anchor:start;font-family:{font};}}\
+         #{id} .taskTextOutsideLeft{{fill:{dark};text-anchor:end;font-family:{font};}}\
+         {tasks}{{fill:{task_bkg};stroke:{task_border};}}\
+         {task_texts}{{fill:{task_text};}}\
+         {outside_texts}{{fill:{outside};}}\
+         {active}{{fill:{active_bkg};stroke:{active_border};}}\
+         {done}{{stroke:{done_border};fill:{done_bkg};stroke-width:2;}}\
+         {crit}{{stroke:{crit_border};fill:{crit_bkg};stroke-width:2;}}\
+         {active_crit}{{stroke:{crit_border};fill:{active_bkg};stroke-width:2;}}\
+         {done_crit}{{stroke:{crit_border};fill:{done_bkg};stroke-width:2;}}\
+         {state_text}{{fill:{dark};}}\
+         #{id} .milestoneText{{font-style:italic;}}\
+         #{id} .titleText{{text-anchor:middle;font-size:18px;fill:{title};font-family:{font};}}</style>",
+        id = id,
+        font = font,
+        text = escape_xml(&theme.text_color),
+        section = escape_xml(&theme.section_bkg_color),
+        section2 = escape_xml(&theme.section_bkg_color2),
+        alt_section = escape_xml(&theme.alt_section_bkg_color),
+        section_titles = numbered("sectionTitle"),
+        title = escape_xml(&theme.title_color),
+        grid = escape_xml(&theme.grid_color),
+        exclude = escape_xml(&theme.exclude_bkg_color),
+        today = escape_xml(&theme.today_line_color),
+        dark = dark,
+        tasks = numbered("task"),
+        task_bkg = escape_xml(&theme.task_bkg_color),
+        task_border = escape_xml(&theme.task_border_color),
+        task_texts = numbered("taskText"),
+        task_text = escape_xml(&theme.task_text_color),
+        outside_texts = numbered("taskTextOutside"),
+        outside = escape_xml(&theme.task_text_outside_color),
+        active = numbered("active"),
+        active_bkg = escape_xml(&theme.active_task_bkg_color),
+        active_border = escape_xml(&theme.active_task_border_color),
+        done = numbered("done"),
+        done_border = escape_xml(&theme.done_task_border_color),
+        done_bkg = escape_xml(&theme.done_task_bkg_color),
+        crit = numbered("crit"),
+        crit_border = escape_xml(&theme.crit_border_color),
+        crit_bkg = escape_xml(&theme.crit_bkg_color),
+        active_crit = numbered("activeCrit"),
+        done_crit = numbered("doneCrit"),
+        state_text = state_text.join(","),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::raster_service::parse_svg;
+    use serde_json::json;
+
+    const RELEASE: &str = r#"gantt
+    title Release plan
+    dateFormat YYYY-MM-DD
+    axisFormat %d %b
+    excludes weekends
+    todayMarker off
+    section Build
+    Design        :done, des, 2024-03-01, 3d
+    Implement     :active, crit, impl, after des, 5d
+    Review        :rev, after impl qa, 1d
+    section Ship
+    QA            :qa, 2024-03-04, until launch
+    Launch        :milestone, launch, 2024-03-15, 0d
+    Retrospective :2d
+"#;
+
+    #[test]
+    fn test_schedule() {
+        let plan = schedule(RELEASE).unwrap();
+        assert_eq!(plan.title.as_deref(), Some("Release plan"));
+        assert_eq!(plan.excludes, vec!["weekends"]);
+        let dates: Vec<(&str, &str, &str)> = plan
+            .tasks
+            .iter()
+            .map(|task| (task.id.as_str(), &task.start[..10], &task.end[..10]))
+            .collect();
+        assert_eq!(
+            dates,
+            vec![
+                // Friday plus three days skips the weekend
+                ("des", "2024-03-01", "2024-03-06"),
+                ("impl", "2024-03-06", "2024-03-13"),
+                ("rev", "2024-03-19", "2024-03-20"),
+                ("qa", "2024-03-04", "2024-03-19"),
+                ("launch", "2024-03-15", "2024-03-15"),
+                ("task1", "2024-03-15", "2024-03-19"),
+            ]
+        );
+        let implement = &plan.tasks[1];
+        assert!(implement.active && implement.crit && !implement.done);
+        assert_eq!(implement.section.as_deref(), Some("Build"));
+        assert_eq!(plan.tasks[2].dependencies, vec!["impl", "qa"]);
+        assert!(plan.tasks[4].milestone);
+        assert_eq!(plan.start.as_deref(), Some("2024-03-01T00:00:00"));
+        assert_eq!(plan.end.as_deref(), Some("2024-03-20T00:00:00"));
+
+        let hours =
+            schedule("gantt\ndateFormat HH:mm\ninclusiveEndDates\nA :a, 09:00, 90m\nB :10:30")
+                .unwrap();
+        assert_eq!(hours.tasks[0].end, "1970-01-01T10:30:00");
+        assert_eq!(hours.tasks[1].start, "1970-01-01T10:30:00");
+
+        assert!(schedule("gantt\nA :a, after b, 1d\nB :b, after a, 1d")
+            .unwrap_err()
+            .contains("Circular"));
+        assert!(schedule("gantt\nA :a, after nope, 1d")
+            .unwrap_err()
+            .contains("nope"));
+        assert!(schedule("gantt\nA :1d").unwrap_err().contains("start date"));
+        assert!(schedule("gantt\nA :a, 2024-03-01, soon").is_err());
+    }
+
+    #[test]
+    fn test_schedule_limits() {
+        // Nothing could be scheduled when every day is excluded
+        let every_day = "gantt\nexcludes saturday,sunday,monday,tuesday,wednesday,thursday,friday\nA :a, 2024-01-01, 3d";
+        assert!(schedule(every_day).unwrap_err().contains("no working days"));
+        let weekends = "gantt\nexcludes weekends,monday,tuesday,wednesday,thursday,friday\nA :a, 2024-01-01, 3d";
+        assert!(schedule(weekends).is_err());
+
+        // Skipping excluded days is bounded
+        assert!(
+            schedule("gantt\nexcludes weekends\nA :a, 2024-01-01, 9999999d")
+                .unwrap_err()
+                .contains("too long")
+        );
+        let years = schedule("gantt\nexcludes weekends\nA :a, 2024-01-01, 100y").unwrap();
+        assert_eq!(years.tasks[0].start, "2024-01-01T00:00:00");
+
+        // Durations and dates past the range of dates are invalid, not overflows
+        for duration in [
+            "9999999999d",
+            "999999999999999999d",
+            "99999999999999999999w",
+            "999999999999y",
+        ] {
+            let code = format!("gantt\nA :a, 2024-01-01, {}", duration);
+            assert!(
+                schedule(&code).unwrap_err().contains("Invalid"),
+                "{}",
+                duration
+            );
+        }
+        assert!(schedule("gantt\ndateFormat X\nA :a, 99999999999999999, 1d")
+            .unwrap_err()
+            .contains("Invalid start date"));
+    }
+
+    #[test]
+    fn test_render_gantt() {
+        let theme = ThemeVariables::from_config(&json!({})).unwrap();
+        let svg = render_gantt(RELEASE, &json!({}), &theme, "plan").unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 800 244""#), "{}", svg);
+        assert!(svg.contains(r#"class="task activeCrit0""#));
+        assert!(svg.contains(r#"class="task milestone task1""#));
+        assert!(svg.contains(r#"class="sectionTitle sectionTitle1">Ship</text>"#));
+        assert!(svg.contains(">01 Mar</text>"));
+        assert!(svg.contains(r#"class="exclude-range""#));
+        assert!(svg.contains("Release plan"));
+        assert!(!svg.contains(r#"class="today""#));
+        assert!(parse_svg(&svg).is_ok());
+
+        assert_eq!(automatic_interval(0, 30 * MS_PER_DAY), (TickUnit::Day, 2));
+        assert_eq!(
+            ticks(0, 3 * MS_PER_DAY, Some("1day"), 0),
+            vec![0, MS_PER_DAY, 2 * MS_PER_DAY, 3 * MS_PER_DAY]
+        );
+    }
+}
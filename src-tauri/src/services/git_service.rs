@@ -0,0 +1,305 @@
+use crate::models::CommitInfo;
+use std::path::Path;
+use std::process::Command;
+
+const FIELD_SEPARATOR: char = '\u{1f}';
+const RECORD_SEPARATOR: char = '\u{1e}';
+
+pub struct GitService {
+    git_path: String,
+}
+
+impl GitService {
+    pub fn new() -> Self {
+        Self {
+            git_path: "git".to_string(),
+        }
+    }
+
+    /// List the commits that touched a file, newest first, following renames
+    pub fn file_history<P: AsRef<Path>>(
+        &self,
+        path: P,
+        limit: Option<usize>,
+    ) -> Result<Vec<CommitInfo>, String> {
+        let (directory, file_name) = split_path(path.as_ref())?;
+
+        let format = format!(
+            "--format={}%H{}%h{}%an{}%ae{}%at{}%s",
+            RECORD_SEPARATOR,
+            FIELD_SEPARATOR,
+            FIELD_SEPARATOR,
+            FIELD_SEPARATOR,
+            FIELD_SEPARATOR,
+            FIELD_SEPARATOR
+        );
+        let limit = limit.map(|n| format!("-n{}", n));
+
+        let mut args = vec!["log", "--follow", "--name-only", format.as_str()];
+        if let Some(ref limit) = limit {
+            args.push(limit);
+        }
+        args.push("--");
+        args.push(&file_name);
+
+        let output = self.run(directory, &args)?;
+        Ok(output
+            .split(RECORD_SEPARATOR)
+            .filter_map(parse_commit_record)
+            .collect())
+    }
+
+    /// Read the content of a file as it was at the given revision
+    pub fn read_file_at_revision<P: AsRef<Path>>(
+        &self,
+        path: P,
+        revision: &str,
+    ) -> Result<String, String> {
+        let (directory, file_name) = split_path(path.as_ref())?;
+        let commit = self.resolve_revision(directory, revision)?;
+
+        let object = match self.path_at_commit(directory, &commit, &file_name)? {
+            Some(repo_path) => format!("{}:{}", commit, repo_path),
+            None => format!("{}:./{}", commit, file_name),
+        };
+
+        self.run(directory, &["show", &object])
+    }
+
+    /// Resolve a revision such as `HEAD~2` or a short hash to a full commit hash
+    pub fn resolve_revision<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        revision: &str,
+    ) -> Result<String, String> {
+        // A leading dash would be read as an option rather than a revision
+        if revision.is_empty() || revision.starts_with('-') {
+            return Err(format!("Unknown revision: {}", revision));
+        }
+
+        let spec = format!("{}^{{commit}}", revision);
+        self.run(
+            directory.as_ref(),
+            &["rev-parse", "--verify", "--quiet", &spec],
+        )
+        .map(|hash| hash.trim().to_string())
+        .map_err(|_| format!("Unknown revision: {}", revision))
+    }
+
+    /// Find the repository path a file had at a commit. The file is looked up
+    /// under its current name first; otherwise the renames made since that
+    /// commit are walked back to the name it had then.
+    fn path_at_commit(
+        &self,
+        directory: &Path,
+        commit: &str,
+        file_name: &str,
+    ) -> Result<Option<String>, String> {
+        let current = self.run(
+            directory,
+            &[
+                "ls-tree",
+                "--full-name",
+                "--name-only",
+                commit,
+                "--",
+                file_name,
+            ],
+        )?;
+        if let Some(path) = current.lines().next().filter(|l| !l.is_empty()) {
+            return Ok(Some(path.to_string()));
+        }
+
+        // Renames are listed newest first, so the last one holds the name
+        // the file had at the commit
+        let range = format!("{}..HEAD", commit);
+        let renames = self.run(
+            directory,
+            &[
+                "log",
+                "--follow",
+                "--name-status",
+                "--format=",
+                &range,
+                "--",
+                file_name,
+            ],
+        )?;
+        Ok(renames
+            .lines()
+            .rfind(|line| line.starts_with('R'))
+            .and_then(|line| line.split('\t').nth(1))
+            .map(|path| path.to_string()))
+    }
+
+    fn run(&self, directory: &Path, args: &[&str]) -> Result<String, String> {
+        let output = Command::new(&self.git_path)
+            .arg("-C")
+            .arg(directory)
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run git: {}", e))?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr);
+            Err(format!("git error: {}", error.trim()))
+        }
+    }
+}
+
+impl Default for GitService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split a file path into the directory git runs in and the file name
+fn split_path(path: &Path) -> Result<(&Path, String), String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Not a file path: {}", path.display()))?
+        .to_string_lossy()
+        .to_string();
+
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    if !directory.is_dir() {
+        return Err(format!("Directory does not exist: {}", directory.display()));
+    }
+
+    Ok((directory, file_name))
+}
+
+fn parse_commit_record(record: &str) -> Option<CommitInfo> {
+    let mut lines = record.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next()?;
+    let fields: Vec<&str> = header.split(FIELD_SEPARATOR).collect();
+    if fields.len() < 6 {
+        return None;
+    }
+
+    Some(CommitInfo {
+        hash: fields[0].to_string(),
+        short_hash: fields[1].to_string(),
+        author_name: fields[2].to_string(),
+        author_email: fields[3].to_string(),
+        timestamp: fields[4].parse().unwrap_or_default(),
+        summary: fields[5..].join(&FIELD_SEPARATOR.to_string()),
+        path: lines.next().unwrap_or_default().trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp::TempDir;
+    use std::fs;
+
+    fn git(directory: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(directory)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .expect("git must be installed to run these tests")
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn temp_repository(name: &str) -> TempDir {
+        let directory = TempDir::new(&format!("mermaid_git_{}", name));
+        git(directory.path(), &["init", "-q"]);
+        directory
+    }
+
+    #[test]
+    fn test_history_and_revisions() {
+        let temp = temp_repository("history");
+        let repository = temp.path();
+        let file = repository.join("flow.mmd");
+
+        fs::write(&file, "flowchart TD\n    A --> B\n").unwrap();
+        git(repository, &["add", "flow.mmd"]);
+        git(repository, &["commit", "-q", "-m", "Add flow"]);
+
+        fs::write(&file, "flowchart TD\n    A --> C\n").unwrap();
+        git(repository, &["commit", "-q", "-am", "Change target"]);
+
+        let service = GitService::new();
+        let history = service.file_history(&file, None).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].summary, "Change target");
+        assert_eq!(history[1].path, "flow.mmd");
+
+        let first = service
+            .read_file_at_revision(&file, &history[1].hash)
+            .unwrap();
+        assert!(first.contains("A --> B"));
+        let previous = service.read_file_at_revision(&file, "HEAD~1").unwrap();
+        assert_eq!(first, previous);
+    }
+
+    #[test]
+    fn test_history_follows_renames() {
+        let temp = temp_repository("rename");
+        let repository = temp.path();
+        fs::write(
+            repository.join("old.mmd"),
+            "sequenceDiagram\n    A->>B: hi\n",
+        )
+        .unwrap();
+        git(repository, &["add", "old.mmd"]);
+        git(repository, &["commit", "-q", "-m", "Add diagram"]);
+        git(repository, &["mv", "old.mmd", "new.mmd"]);
+        git(repository, &["commit", "-q", "-m", "Rename diagram"]);
+
+        let service = GitService::new();
+        let file = repository.join("new.mmd");
+        let history = service.file_history(&file, None).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].path, "old.mmd");
+
+        let content = service
+            .read_file_at_revision(&file, &history[1].short_hash)
+            .unwrap();
+        assert!(content.contains("A->>B: hi"));
+        assert!(service
+            .read_file_at_revision(&file, "does-not-exist")
+            .is_err());
+        assert!(service
+            .read_file_at_revision(&file, "--output=/tmp/x")
+            .is_err());
+    }
+
+    #[test]
+    fn test_read_at_merge_commit() {
+        let temp = temp_repository("merge");
+        let repository = temp.path();
+        let file = repository.join("flow.mmd");
+        fs::write(&file, "flowchart TD\n    A --> B\n").unwrap();
+        git(repository, &["add", "flow.mmd"]);
+        git(repository, &["commit", "-q", "-m", "Add flow"]);
+
+        git(repository, &["checkout", "-q", "-b", "side"]);
+        fs::write(repository.join("other.txt"), "side\n").unwrap();
+        git(repository, &["add", "other.txt"]);
+        git(repository, &["commit", "-q", "-m", "Side change"]);
+        git(repository, &["checkout", "-q", "-"]);
+        fs::write(&file, "flowchart TD\n    A --> C\n").unwrap();
+        git(repository, &["commit", "-q", "-am", "Change target"]);
+        git(
+            repository,
+            &["merge", "-q", "--no-ff", "-m", "Merge side", "side"],
+        );
+
+        let service = GitService::new();
+        let content = service.read_file_at_revision(&file, "HEAD").unwrap();
+        assert_eq!(content, "flowchart TD\n    A --> C\n");
+    }
+}
@@ -0,0 +1,271 @@
+use crate::models::DiagramOptions;
+use crate::services::{parse_diagram, svg_service};
+
+const DEFAULT_TITLE: &str = "Mermaid diagram";
+
+const STYLE: &str = r#"
+:root { --page: #f5f5f7; --panel: #ffffff; --text: #1f2328; --border: #d0d7de; --accent: #0969da; }
+:root[data-theme="dark"] { --page: #0f0f17; --panel: #1b1b26; --text: #e6e6ef; --border: #34344a; --accent: #58a6ff; }
+* { box-sizing: border-box; }
+html, body { margin: 0; height: 100%; }
+body { display: flex; flex-direction: column; background: var(--page); color: var(--text);
+  font: 14px/1.4 -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; }
+header { display: flex; align-items: center; gap: 8px; padding: 8px 12px;
+  background: var(--panel); border-bottom: 1px solid var(--border); }
+header h1 { flex: 1; margin: 0; font-size: 15px; font-weight: 600; }
+button { padding: 4px 10px; border: 1px solid var(--border); border-radius: 6px;
+  background: var(--panel); color: var(--text); font: inherit; cursor: pointer; }
+button:hover { border-color: var(--accent); }
+#viewport { position: relative; flex: 1; overflow: hidden; cursor: grab; touch-action: none; }
+#viewport.dragging { cursor: grabbing; }
+#canvas { position: absolute; top: 0; left: 0; transform-origin: 0 0; padding: 24px; }
+#canvas svg { display: block; max-width: none !important; height: auto; }
+:root[data-theme="dark"] #canvas svg { filter: invert(0.9) hue-rotate(180deg); }
+#source { display: none; max-height: 40%; margin: 0; padding: 12px; overflow: auto;
+  background: var(--panel); border-top: 1px solid var(--border);
+  font: 13px/1.5 ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; white-space: pre; }
+body.show-source #source { display: block; }
+"#;
+
+const SCRIPT: &str = r#"
+(function () {
+  var root = document.documentElement;
+  var viewport = document.getElementById('viewport');
+  var canvas = document.getElementById('canvas');
+  var source = document.getElementById('source');
+  var state = { x: 0, y: 0, scale: 1 };
+
+  function apply() {
+    canvas.style.transform = 'translate(' + state.x + 'px,' + state.y + 'px) scale(' + state.scale + ')';
+  }
+
+  function zoomAt(factor, cx, cy) {
+    var scale = Math.min(20, Math.max(0.1, state.scale * factor));
+    state.x = cx - (cx - state.x) * (scale / state.scale);
+    state.y = cy - (cy - state.y) * (scale / state.scale);
+    state.scale = scale;
+    apply();
+  }
+
+  function fit() {
+    var width = canvas.scrollWidth, height = canvas.scrollHeight;
+    var bounds = viewport.getBoundingClientRect();
+    state.scale = Math.min(1, bounds.width / width, bounds.height / height) || 1;
+    state.x = (bounds.width - width * state.scale) / 2;
+    state.y = Math.max(0, (bounds.height - height * state.scale) / 2);
+    apply();
+  }
+
+  viewport.addEventListener('wheel', function (event) {
+    event.preventDefault();
+    var bounds = viewport.getBoundingClientRect();
+    zoomAt(event.deltaY < 0 ? 1.1 : 1 / 1.1, event.clientX - bounds.left, event.clientY - bounds.top);
+  }, { passive: false });
+
+  // Small pointer movements still count as clicks so diagram links keep working
+  var drag = null;
+  viewport.addEventListener('pointerdown', function (event) {
+    drag = { x: event.clientX, y: event.clientY, originX: state.x, originY: state.y, moved: false };
+  });
+  window.addEventListener('pointermove', function (event) {
+    if (!drag) return;
+    var dx = event.clientX - drag.x, dy = event.clientY - drag.y;
+    if (!drag.moved && Math.abs(dx) + Math.abs(dy) < 4) return;
+    drag.moved = true;
+    viewport.classList.add('dragging');
+    state.x = drag.originX + dx;
+    state.y = drag.originY + dy;
+    apply();
+  });
+  window.addEventListener('pointerup', function () {
+    viewport.classList.remove('dragging');
+    setTimeout(function () { drag = null; });
+  });
+  viewport.addEventListener('click', function (event) {
+    if (drag && drag.moved) {
+      event.preventDefault();
+      event.stopPropagation();
+    }
+  }, true);
+
+  function center() {
+    var bounds = viewport.getBoundingClientRect();
+    return [bounds.width / 2, bounds.height / 2];
+  }
+
+  document.getElementById('zoom-in').onclick = function () { zoomAt(1.25, center()[0], center()[1]); };
+  document.getElementById('zoom-out').onclick = function () { zoomAt(0.8, center()[0], center()[1]); };
+  document.getElementById('zoom-fit').onclick = fit;
+
+  document.getElementById('theme').onclick = function () {
+    root.setAttribute('data-theme', root.getAttribute('data-theme') === 'dark' ? 'light' : 'dark');
+  };
+  if (window.matchMedia && window.matchMedia('(prefers-color-scheme: dark)').matches) {
+    root.setAttribute('data-theme', 'dark');
+  }
+
+  document.getElementById('toggle-source').onclick = function () {
+    document.body.classList.toggle('show-source');
+  };
+
+  var copyButton = document.getElementById('copy');
+  function copied() {
+    copyButton.textContent = 'Copied';
+    setTimeout(function () { copyButton.textContent = 'Copy source'; }, 1500);
+  }
+  copyButton.onclick = function () {
+    var text = source.textContent;
+    if (navigator.clipboard && window.isSecureContext) {
+      navigator.clipboard.writeText(text).then(copied);
+      return;
+    }
+    // Fallback for pages opened from the file system
+    var area = document.createElement('textarea');
+    area.value = text;
+    document.body.appendChild(area);
+    area.select();
+    document.execCommand('copy');
+    document.body.removeChild(area);
+    copied();
+  };
+
+  window.addEventListener('resize', fit);
+  fit();
+})();
+"#;
+
+/// Build a self-contained HTML page around a rendered SVG.
+///
+/// The page embeds the diagram source and has pan/zoom, a light/dark toggle
+/// and a copy button, without loading anything from the network.
+pub fn svg_to_html(svg: &str, code: &str, options: &DiagramOptions) -> Result<Vec<u8>, String> {
+    let svg = sanitize_svg(svg)?;
+
+    let title = parse_diagram(code)
+        .ok()
+        .and_then(|diagram| diagram.title)
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_TITLE.to_string());
+
+    let background = options.background.trim();
+    let stage_style = if background.is_empty() || background.eq_ignore_ascii_case("transparent") {
+        String::new()
+    } else {
+        format!(
+            "\n#canvas {{ background: {}; }}",
+            escape_html(background).replace([';', '{', '}'], "")
+        )
+    };
+
+    let mut html =
+        String::with_capacity(svg.len() + code.len() + STYLE.len() + SCRIPT.len() + 1024);
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\" data-theme=\"light\">\n<head>\n");
+    html.push_str("<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    html.push_str("<meta name=\"generator\" content=\"Mermaid GUI\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape_html(&title)));
+    html.push_str("<style>");
+    html.push_str(STYLE);
+    html.push_str(&stage_style);
+    html.push_str("</style>\n</head>\n<body>\n<header>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", escape_html(&title)));
+    html.push_str("<button id=\"zoom-out\" title=\"Zoom out\">&minus;</button>\n");
+    html.push_str("<button id=\"zoom-in\" title=\"Zoom in\">+</button>\n");
+    html.push_str("<button id=\"zoom-fit\" title=\"Fit to window\">Fit</button>\n");
+    html.push_str("<button id=\"theme\" title=\"Toggle dark mode\">Dark/Light</button>\n");
+    html.push_str("<button id=\"toggle-source\">Source</button>\n");
+    html.push_str("<button id=\"copy\">Copy source</button>\n");
+    html.push_str("</header>\n<div id=\"viewport\"><div id=\"canvas\">\n");
+    html.push_str(&svg);
+    html.push_str("\n</div></div>\n<pre id=\"source\"><code>");
+    html.push_str(&escape_html(code));
+    html.push_str("</code></pre>\n<script>");
+    html.push_str(SCRIPT);
+    html.push_str("</script>\n</body>\n</html>\n");
+
+    Ok(html.into_bytes())
+}
+
+/// Prepare mmdc SVG output for inlining: drop the XML prolog, scripts and
+/// stylesheet imports, and open diagram links outside the page
+fn sanitize_svg(svg: &str) -> Result<String, String> {
+    let mut svg = fix_root_size(&svg_service::sanitize_svg(svg)?);
+    if !svg.contains("target=") {
+        svg = svg.replace("<a ", "<a target=\"_blank\" rel=\"noopener\" ");
+    }
+    Ok(svg)
+}
+
+/// mmdc sizes the root element with `width="100%"`, which collapses inside
+/// the pan/zoom canvas; give it the natural size from its viewBox instead
+fn fix_root_size(svg: &str) -> String {
+    let Some(tag_end) = svg.find('>') else {
+        return svg.to_string();
+    };
+    let (root, rest) = svg.split_at(tag_end);
+
+    let size = root
+        .split("viewBox=\"")
+        .nth(1)
+        .and_then(|v| v.split('"').next())
+        .map(|v| {
+            v.split([' ', ','])
+                .filter(|n| !n.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|v| v.len() == 4)
+        .map(|v| (v[2].to_string(), v[3].to_string()));
+
+    match size {
+        Some((width, height)) if root.contains("width=\"100%\"") => {
+            let root = root.replacen(
+                "width=\"100%\"",
+                &format!("width=\"{}\" height=\"{}\"", width, height),
+                1,
+            );
+            format!("{}{}", root, rest)
+        }
+        _ => svg.to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DiagramFormat;
+
+    #[test]
+    fn test_svg_to_html() {
+        let svg = r#"<?xml version="1.0"?><svg width="100%" xmlns="http://www.w3.org/2000/svg" viewBox="0 0 120.5 80"><style>@import url("https://fonts.example.com/font.css");.node{fill:red}</style><script>alert(1)</script><a href="https://example.com"><rect onclick="alert(2)"/></a><foreignObject><div xmlns="http://www.w3.org/1999/xhtml">A<br>B</div></foreignObject></svg>"#;
+        let code = "---\ntitle: Deploy <flow>\n---\nflowchart TD\n    A --> B\n    click A href \"https://example.com\"\n";
+        let options = DiagramOptions {
+            format: DiagramFormat::Html,
+            ..DiagramOptions::svg(None)
+        };
+
+        let html = String::from_utf8(svg_to_html(svg, code, &options).unwrap()).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Deploy &lt;flow&gt;</title>"));
+        assert!(html.contains("A --&gt; B"));
+        assert!(html
+            .contains("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"120.5\" height=\"80\""));
+        assert!(
+            html.contains("<a target=\"_blank\" rel=\"noopener\" href=\"https://example.com\">")
+        );
+        assert!(html.contains(".node{fill:red}"));
+        assert!(!html.contains("@import"));
+        assert!(!html.contains("alert"));
+        assert!(html.contains("A<br/>B"));
+        assert!(!html.contains("<?xml"));
+        assert!(!html.contains("src="));
+
+        assert!(svg_to_html("<html><svg/></html>", code, &options).is_err());
+    }
+}
@@ -0,0 +1,497 @@
+use crate::models::{InstallProgress, InstallSource, InstallStage, ManagedInstall};
+use crate::services::discovery_service::{mmdc_command, modified_secs, versions_newest_first};
+use crate::utils::platform::get_npm_binary_name;
+use crate::utils::{ensure_directory_exists, DefaultDirectories, TempFile};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+
+pub const MERMAID_CLI_PACKAGE: &str = "@mermaid-js/mermaid-cli";
+
+/// Directory under the app storage holding managed installs
+pub const MANAGED_DIR: &str = "mermaid-cli";
+
+const ACTIVE_FILE: &str = "active.json";
+
+/// npm output lines kept for the error message of a failed install
+const ERROR_LINES: usize = 20;
+
+#[cfg(target_os = "windows")]
+const MMDC_BIN: &str = "mmdc.cmd";
+#[cfg(not(target_os = "windows"))]
+const MMDC_BIN: &str = "mmdc";
+
+#[derive(Serialize, Deserialize)]
+struct ActiveInstall {
+    version: String,
+}
+
+/// Installs mermaid-cli with npm into private prefixes, one per version:
+/// `versions/<version>/node_modules/.bin/mmdc`. Downloads are kept in a
+/// private npm cache so later installs can run offline.
+pub struct MermaidCliInstaller {
+    root: PathBuf,
+    npm: PathBuf,
+}
+
+impl MermaidCliInstaller {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            npm: PathBuf::from(get_npm_binary_name()),
+        }
+    }
+
+    /// Use another npm executable
+    #[cfg(test)]
+    pub fn with_npm(mut self, npm: PathBuf) -> Self {
+        self.npm = npm;
+        self
+    }
+
+    /// Installs inside the app data directory
+    pub fn default_location() -> Result<Self, String> {
+        let storage = DefaultDirectories::app_storage()
+            .ok_or_else(|| "Could not determine the app data directory".to_string())?;
+        Ok(Self::new(storage.join(MANAGED_DIR)))
+    }
+
+    /// Installed versions, newest first
+    pub fn list(&self) -> Result<Vec<ManagedInstall>, String> {
+        let active = self.active_version();
+        Ok(versions_newest_first(&self.root.join("versions"))
+            .into_iter()
+            .filter_map(|dir| {
+                let version = dir.file_name()?.to_string_lossy().to_string();
+                let mmdc = mmdc_in(&dir);
+                mmdc.is_file().then(|| ManagedInstall {
+                    active: active.as_deref() == Some(version.as_str()),
+                    mmdc_path: mmdc.to_string_lossy().to_string(),
+                    installed_at: modified_secs(&dir),
+                    version,
+                })
+            })
+            .collect())
+    }
+
+    pub fn active_version(&self) -> Option<String> {
+        let content = fs::read_to_string(self.root.join(ACTIVE_FILE)).ok()?;
+        serde_json::from_str::<ActiveInstall>(&content)
+            .ok()
+            .map(|active| active.version)
+    }
+
+    /// mmdc of the active install, if it is still there
+    pub fn active_mmdc(&self) -> Option<PathBuf> {
+        let version = self.active_version()?;
+        let mmdc = mmdc_in(&self.version_dir(&version).ok()?);
+        mmdc.is_file().then_some(mmdc)
+    }
+
+    pub fn set_active(&self, version: &str) -> Result<ManagedInstall, String> {
+        let install = self
+            .list()?
+            .into_iter()
+            .find(|install| install.version == version)
+            .ok_or_else(|| format!("mermaid-cli {} is not installed", version))?;
+
+        let content = serde_json::to_string(&ActiveInstall {
+            version: version.to_string(),
+        })
+        .map_err(|e| format!("Failed to serialize active version: {}", e))?;
+        fs::write(self.root.join(ACTIVE_FILE), content)
+            .map_err(|e| format!("Failed to save active version: {}", e))?;
+
+        log::info!("Using managed mermaid-cli {}", version);
+        Ok(ManagedInstall {
+            active: true,
+            ..install
+        })
+    }
+
+    /// Remove an installed version; the active one falls back to the newest
+    /// remaining version
+    pub fn uninstall(&self, version: &str) -> Result<(), String> {
+        let dir = self.version_dir(version)?;
+        if !dir.exists() {
+            return Err(format!("mermaid-cli {} is not installed", version));
+        }
+        fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Failed to remove mermaid-cli {}: {}", version, e))?;
+        log::info!("Uninstalled managed mermaid-cli {}", version);
+
+        if self.active_version().as_deref() == Some(version) {
+            match self.list()?.first() {
+                Some(newest) => {
+                    self.set_active(&newest.version)?;
+                }
+                None => {
+                    let _ = fs::remove_file(self.root.join(ACTIVE_FILE));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Install mermaid-cli, verify it and make it the active version
+    pub fn install(
+        &self,
+        source: &InstallSource,
+        mut progress: impl FnMut(InstallProgress),
+    ) -> Result<ManagedInstall, String> {
+        let result = self.install_with(source, &mut progress);
+        match result {
+            Ok(ref install) => report(
+                &mut progress,
+                InstallStage::Finished,
+                format!("Installed mermaid-cli {}", install.version),
+            ),
+            Err(ref e) => report(&mut progress, InstallStage::Failed, e.clone()),
+        }
+        result
+    }
+
+    fn install_with(
+        &self,
+        source: &InstallSource,
+        progress: &mut dyn FnMut(InstallProgress),
+    ) -> Result<ManagedInstall, String> {
+        report(
+            progress,
+            InstallStage::Preparing,
+            "Preparing install directory",
+        );
+        let staging = self.root.join(format!(".staging-{}", std::process::id()));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)
+                .map_err(|e| format!("Failed to clear {}: {}", staging.display(), e))?;
+        }
+        ensure_directory_exists(&staging)?;
+
+        let result = self
+            .run_npm(source, &staging, progress)
+            .and_then(|_| installed_version(&staging))
+            .and_then(|version| {
+                let dir = self.version_dir(&version)?;
+                if dir.exists() {
+                    fs::remove_dir_all(&dir)
+                        .map_err(|e| format!("Failed to replace mermaid-cli {}: {}", version, e))?;
+                }
+                ensure_directory_exists(&self.root.join("versions"))?;
+                fs::rename(&staging, &dir)
+                    .map_err(|e| format!("Failed to move install into place: {}", e))?;
+                Ok(version)
+            });
+        if staging.exists() {
+            let _ = fs::remove_dir_all(&staging);
+        }
+        let version = result?;
+
+        report(
+            progress,
+            InstallStage::Verifying,
+            format!("Rendering a test diagram with mermaid-cli {}", version),
+        );
+        if let Err(e) = self.verify(&version) {
+            let _ = fs::remove_dir_all(self.version_dir(&version)?);
+            return Err(e);
+        }
+
+        self.set_active(&version)
+    }
+
+    /// Check that an installed version runs and can render a diagram
+    pub fn verify(&self, version: &str) -> Result<String, String> {
+        let mmdc = mmdc_in(&self.version_dir(version)?);
+        if !mmdc.is_file() {
+            return Err(format!("mermaid-cli {} is not installed", version));
+        }
+
+        let output = mmdc_command(&mmdc)
+            .arg("--version")
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", mmdc.display(), e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "mmdc --version failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let reported = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        let input = TempFile::with_contents("mermaid_verify", "mmd", b"graph TD\n  A --> B\n")?;
+        let svg = TempFile::reserve("mermaid_verify", "svg")?;
+        let output = mmdc_command(&mmdc)
+            .arg("-i")
+            .arg(input.path())
+            .arg("-o")
+            .arg(svg.path())
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", mmdc.display(), e))?;
+        let rendered = fs::read_to_string(svg.path()).unwrap_or_default();
+        if !output.status.success() || !rendered.contains("<svg") {
+            return Err(format!(
+                "mermaid-cli {} could not render a test diagram: {}",
+                version,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(reported)
+    }
+
+    /// Run `npm install` into `prefix`, reporting each line npm prints
+    fn run_npm(
+        &self,
+        source: &InstallSource,
+        prefix: &Path,
+        progress: &mut dyn FnMut(InstallProgress),
+    ) -> Result<(), String> {
+        let mut args: Vec<OsString> = vec![
+            "install".into(),
+            "--prefix".into(),
+            prefix.into(),
+            "--cache".into(),
+            self.root.join("cache").into(),
+            "--no-audit".into(),
+            "--no-fund".into(),
+            "--loglevel=http".into(),
+        ];
+        let package = match source {
+            InstallSource::Registry {
+                version,
+                registry,
+                offline,
+            } => {
+                if let Some(registry) = registry.as_deref().filter(|r| !r.trim().is_empty()) {
+                    args.push("--registry".into());
+                    args.push(registry.trim().into());
+                }
+                if *offline {
+                    args.push("--offline".into());
+                }
+                let version = version.as_deref().map(str::trim).filter(|v| !v.is_empty());
+                format!("{}@{}", MERMAID_CLI_PACKAGE, version.unwrap_or("latest")).into()
+            }
+            InstallSource::Tarball { path } => {
+                let path = Path::new(path);
+                if !path.is_file() {
+                    return Err(format!("Tarball not found: {}", path.display()));
+                }
+                // A relative path would resolve against the prefix
+                fs::canonicalize(path)
+                    .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?
+                    .into_os_string()
+            }
+        };
+        report(
+            progress,
+            InstallStage::Installing,
+            format!("npm install {}", package.to_string_lossy()),
+        );
+        args.push(package);
+
+        let mut child = Command::new(&self.npm)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run npm ({}): {}", self.npm.display(), e))?;
+
+        let (sender, lines) = mpsc::channel();
+        let readers: Vec<_> = [
+            child
+                .stdout
+                .take()
+                .map(|out| Box::new(out) as Box<dyn Read + Send>),
+            child
+                .stderr
+                .take()
+                .map(|err| Box::new(err) as Box<dyn Read + Send>),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|stream| {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+        drop(sender);
+
+        let mut recent = VecDeque::with_capacity(ERROR_LINES);
+        for line in lines {
+            let line = line.trim().to_string();
+            if line.is_empty() {
+                continue;
+            }
+            if recent.len() == ERROR_LINES {
+                recent.pop_front();
+            }
+            recent.push_back(line.clone());
+            report(progress, InstallStage::Installing, line);
+        }
+        for reader in readers {
+            let _ = reader.join();
+        }
+
+        let status = child
+            .wait()
+            .map_err(|e| format!("Failed to wait for npm: {}", e))?;
+        if !status.success() {
+            return Err(format!(
+                "npm install failed ({}):\n{}",
+                status,
+                Vec::from(recent).join("\n")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Directory of one version; rejects names that are not plain versions
+    fn version_dir(&self, version: &str) -> Result<PathBuf, String> {
+        let valid = !version.is_empty()
+            && !version.starts_with('.')
+            && version
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | '_'));
+        if !valid {
+            return Err(format!("Invalid mermaid-cli version: {}", version));
+        }
+        Ok(self.root.join("versions").join(version))
+    }
+}
+
+fn report(
+    progress: &mut dyn FnMut(InstallProgress),
+    stage: InstallStage,
+    message: impl Into<String>,
+) {
+    progress(InstallProgress {
+        stage,
+        message: message.into(),
+    });
+}
+
+fn mmdc_in(prefix: &Path) -> PathBuf {
+    prefix.join("node_modules").join(".bin").join(MMDC_BIN)
+}
+
+/// Version from the installed package's `package.json`
+fn installed_version(prefix: &Path) -> Result<String, String> {
+    let manifest = prefix
+        .join("node_modules")
+        .join("@mermaid-js")
+        .join("mermaid-cli")
+        .join("package.json");
+    let content = fs::read_to_string(&manifest)
+        .map_err(|_| format!("npm did not install {}", MERMAID_CLI_PACKAGE))?;
+    let manifest: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid {}: {}", manifest.display(), e))?;
+    manifest["version"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("{} has no version", MERMAID_CLI_PACKAGE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp::TempDir;
+
+    /// Fake npm that "installs" a mermaid-cli whose mmdc prints the
+    /// requested version and writes a minimal SVG
+    #[cfg(unix)]
+    const STUB_NPM: &str = r#"#!/bin/sh
+prefix=""
+spec=""
+while [ $# -gt 0 ]; do
+  case "$1" in
+    --prefix) prefix="$2"; shift ;;
+    --cache|--registry) shift ;;
+    install|--*) ;;
+    *) spec="$1" ;;
+  esac
+  shift
+done
+echo "npm http fetch GET 200 https://registry.npmjs.org/$spec" >&2
+case "$spec" in
+  *fail*) echo "npm error 404 Not Found" >&2; exit 1 ;;
+esac
+version="${spec##*@}"
+pkg="$prefix/node_modules/@mermaid-js/mermaid-cli"
+mkdir -p "$pkg" "$prefix/node_modules/.bin"
+printf '{"name":"@mermaid-js/mermaid-cli","version":"%s"}\n' "$version" > "$pkg/package.json"
+cat > "$prefix/node_modules/.bin/mmdc" <<EOF
+#!/bin/sh
+if [ "\$1" = "--version" ]; then echo $version; exit 0; fi
+while [ \$# -gt 0 ]; do
+  if [ "\$1" = "-o" ]; then echo '<svg xmlns="http://www.w3.org/2000/svg"/>' > "\$2"; fi
+  shift
+done
+EOF
+chmod +x "$prefix/node_modules/.bin/mmdc"
+"#;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_switch_and_uninstall() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new("mermaid_installer");
+        let root = temp.path();
+        let npm = root.join("npm");
+        fs::write(&npm, STUB_NPM).unwrap();
+        fs::set_permissions(&npm, fs::Permissions::from_mode(0o755)).unwrap();
+        let installer = MermaidCliInstaller::new(root.join("managed")).with_npm(npm);
+
+        let registry = |version: &str| InstallSource::Registry {
+            version: Some(version.to_string()),
+            registry: None,
+            offline: false,
+        };
+        let mut stages = Vec::new();
+        let install = installer
+            .install(&registry("10.9.1"), |p| stages.push(p.stage))
+            .unwrap();
+        assert!(install.active);
+        assert_eq!(stages.first(), Some(&InstallStage::Preparing));
+        assert!(stages.contains(&InstallStage::Verifying));
+        assert_eq!(stages.last(), Some(&InstallStage::Finished));
+
+        installer.install(&registry("11.4.2"), |_| {}).unwrap();
+        let versions: Vec<String> = installer
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|i| i.version)
+            .collect();
+        assert_eq!(versions, ["11.4.2", "10.9.1"]);
+        assert!(installer
+            .active_mmdc()
+            .unwrap()
+            .ends_with("versions/11.4.2/node_modules/.bin/mmdc"));
+
+        installer.set_active("10.9.1").unwrap();
+        assert_eq!(installer.verify("10.9.1").unwrap(), "10.9.1");
+        installer.uninstall("10.9.1").unwrap();
+        assert_eq!(installer.active_version().as_deref(), Some("11.4.2"));
+
+        let error = installer.install(&registry("fail"), |_| {}).unwrap_err();
+        assert!(error.contains("404 Not Found"));
+        assert_eq!(installer.list().unwrap().len(), 1);
+        assert!(installer.set_active("../../etc").is_err());
+    }
+}
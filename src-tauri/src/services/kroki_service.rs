@@ -0,0 +1,193 @@
+use crate::models::{DiagramFormat, KrokiSettings, RendererBackend, RendererCapabilities};
+use crate::services::mmdc_service::mermaid_config;
+use crate::services::renderer_service::{apply_mmdc_styles, RenderError, RenderJob, Renderer};
+use crate::utils::http;
+use std::time::Duration;
+
+/// SVG renderer using a Kroki server, e.g. a self-hosted `yuzutech/kroki`
+pub struct KrokiRenderer {
+    settings: KrokiSettings,
+}
+
+impl KrokiRenderer {
+    pub fn new(settings: KrokiSettings) -> Self {
+        Self { settings }
+    }
+
+    fn url(&self) -> Result<String, String> {
+        self.settings
+            .url
+            .as_deref()
+            .map(|url| url.trim().trim_end_matches('/'))
+            .filter(|url| !url.is_empty())
+            .map(|url| format!("{}/mermaid/svg", url))
+            .ok_or_else(|| "No Kroki server configured".to_string())
+    }
+}
+
+impl Renderer for KrokiRenderer {
+    fn backend(&self) -> RendererBackend {
+        RendererBackend::Kroki
+    }
+
+    fn capabilities(&self) -> RendererCapabilities {
+        RendererCapabilities {
+            formats: vec![DiagramFormat::Svg],
+            diagram_types: None,
+        }
+    }
+
+    fn availability(&mut self) -> Result<(), String> {
+        self.url().map(|_| ())
+    }
+
+    fn render(&mut self, job: &RenderJob) -> Result<Vec<u8>, RenderError> {
+        let url = self.url().map_err(RenderError::Unavailable)?;
+
+        // Kroki has no config option, so it goes into an init directive
+        let config = mermaid_config(job.options, job.extra_config).map_err(RenderError::Failed)?;
+        let code = match config.as_object() {
+            Some(object) if !object.is_empty() => with_init_directive(job.code, &config),
+            _ => job.code.to_string(),
+        };
+
+        let timeout = Duration::from_millis(self.settings.timeout_ms);
+        let response = http::post(&url, "text/plain", code.as_bytes(), timeout)
+            .map_err(RenderError::Unavailable)?;
+        if response.status >= 500 {
+            return Err(RenderError::Unavailable(format!(
+                "Kroki server error {}: {}",
+                response.status,
+                response.text().trim()
+            )));
+        }
+        if !response.is_success() {
+            return Err(RenderError::Failed(format!(
+                "Kroki error: {}",
+                response.text().trim()
+            )));
+        }
+
+        let svg = apply_mmdc_styles(&response.text(), job).map_err(RenderError::Failed)?;
+        Ok(svg.into_bytes())
+    }
+}
+
+/// Add an `%%{init}%%` directive to mermaid source. Mermaid only reads
+/// front matter from the first line, so the directive goes after it.
+fn with_init_directive(code: &str, config: &serde_json::Value) -> String {
+    let mut front_matter_end = 0;
+    let mut lines = code.split_inclusive('\n');
+    if let Some(first) = lines.next().filter(|line| line.trim_end() == "---") {
+        let mut offset = first.len();
+        for line in lines {
+            offset += line.len();
+            if line.trim_end() == "---" {
+                front_matter_end = offset;
+                break;
+            }
+        }
+    }
+
+    let (front_matter, body) = code.split_at(front_matter_end);
+    let separator = if front_matter.is_empty() || front_matter.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    format!(
+        "{}{}%%{{init: {}}}%%\n{}",
+        front_matter, separator, config, body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DiagramOptions;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_render_with_local_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            // The request body is the diagram, ending the same way every time
+            while !request.ends_with(b"A --> B") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            let body = r#"<svg xmlns="http://www.w3.org/2000/svg"><g/></svg>"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: image/svg+xml\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let mut renderer = KrokiRenderer::new(KrokiSettings {
+            url: Some(format!("http://127.0.0.1:{}/", port)),
+            ..KrokiSettings::default()
+        });
+        let options = DiagramOptions {
+            css: Some(".node { fill: red; }".to_string()),
+            ..DiagramOptions::svg(Some("dark".to_string()))
+        };
+        let job = RenderJob {
+            code: "graph TD\n  A --> B",
+            diagram_type: Some("flowchart"),
+            format: DiagramFormat::Svg,
+            options: &options,
+            extra_config: None,
+        };
+        let svg = String::from_utf8(renderer.render(&job).unwrap()).unwrap();
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /mermaid/svg HTTP/1.1"));
+        assert!(request.contains("%%{init: {\"theme\":\"dark\"}}%%\ngraph TD"));
+        assert!(svg.contains("background-color: white"), "{}", svg);
+        assert!(
+            svg.contains(".node { fill: red; }]]></style><g/>"),
+            "{}",
+            svg
+        );
+
+        let mut unconfigured = KrokiRenderer::new(KrokiSettings::default());
+        assert!(matches!(
+            unconfigured.render(&job),
+            Err(RenderError::Unavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_init_directive_follows_front_matter() {
+        let config = serde_json::json!({ "theme": "dark" });
+        assert_eq!(
+            with_init_directive("graph TD\n  A --> B", &config),
+            "%%{init: {\"theme\":\"dark\"}}%%\ngraph TD\n  A --> B"
+        );
+        assert_eq!(
+            with_init_directive("---\ntitle: Flow\n---\ngraph TD\n  A --> B", &config),
+            "---\ntitle: Flow\n---\n%%{init: {\"theme\":\"dark\"}}%%\ngraph TD\n  A --> B"
+        );
+        assert_eq!(
+            with_init_directive("---\ntitle: Flow\n---", &config),
+            "---\ntitle: Flow\n---\n%%{init: {\"theme\":\"dark\"}}%%\n"
+        );
+        // An unclosed block is not front matter
+        assert_eq!(
+            with_init_directive("---\ngraph TD", &config),
+            "%%{init: {\"theme\":\"dark\"}}%%\n---\ngraph TD"
+        );
+    }
+}
@@ -0,0 +1,1157 @@
+//! Sugiyama-style layered graph layout: cycle removal, layer assignment,
+//! crossing minimization and coordinate assignment, in the spirit of the
+//! dagre layout mermaid uses for flowcharts.
+//!
+//! Internally the layout works in rank space, where ranks run along the
+//! y axis and the order within a rank along the x axis; the result is
+//! transformed to the requested direction at the end.
+
+/// Direction ranks flow in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    TopBottom,
+    BottomTop,
+    LeftRight,
+    RightLeft,
+}
+
+impl Direction {
+    /// Parse a flowchart direction such as `TD` or `LR`, defaulting to
+    /// top to bottom
+    pub fn parse(direction: Option<&str>) -> Self {
+        match direction.map(str::trim) {
+            Some("BT") => Direction::BottomTop,
+            Some("LR") => Direction::LeftRight,
+            Some("RL") => Direction::RightLeft,
+            _ => Direction::TopBottom,
+        }
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        matches!(self, Direction::LeftRight | Direction::RightLeft)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutNode {
+    pub width: f64,
+    pub height: f64,
+    /// Innermost cluster containing the node
+    pub cluster: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutEdge {
+    pub from: usize,
+    pub to: usize,
+    /// Width and height of the edge label, if any
+    pub label: Option<(f64, f64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutCluster {
+    pub parent: Option<usize>,
+    pub title_width: f64,
+    pub title_height: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutOptions {
+    pub direction: Direction,
+    /// Space between neighbouring nodes of a rank
+    pub node_spacing: f64,
+    /// Space between ranks
+    pub rank_spacing: f64,
+    /// Space between edges passing through a rank
+    pub edge_spacing: f64,
+    /// Space between a cluster's border and its contents
+    pub cluster_padding: f64,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            direction: Direction::TopBottom,
+            node_spacing: 50.0,
+            rank_spacing: 50.0,
+            edge_spacing: 20.0,
+            cluster_padding: 8.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn center(&self) -> Point {
+        Point {
+            x: self.x + self.width / 2.0,
+            y: self.y + self.height / 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EdgeRoute {
+    /// From the source's centre through any bends to the target's centre;
+    /// empty for self-loops
+    pub points: Vec<Point>,
+    /// Centre of the edge label
+    pub label: Option<Point>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Layout {
+    /// Node centres
+    pub nodes: Vec<Point>,
+    pub edges: Vec<EdgeRoute>,
+    /// Cluster bounds; `None` for clusters without any nodes
+    pub clusters: Vec<Option<Rect>>,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Number of crossing minimization sweeps, alternating down and up
+const ORDER_SWEEPS: usize = 24;
+/// Number of coordinate assignment rounds, each a down and an up sweep
+const POSITION_ROUNDS: usize = 8;
+/// Number of rounds lining up the sides of clusters
+const BORDER_ROUNDS: usize = 12;
+
+/// A node, a dummy vertex on an edge spanning several ranks, or a dummy
+/// marking the side of a cluster on one rank
+struct Vertex {
+    /// Size across ranks and along them
+    cross: f64,
+    length: f64,
+    rank: usize,
+    /// Clusters containing the vertex, outermost first
+    clusters: Vec<usize>,
+    dummy: bool,
+    border: Option<Border>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Border {
+    Left,
+    Right,
+}
+
+/// An edge split into segments between adjacent ranks
+struct Chain {
+    vertices: Vec<usize>,
+    reversed: bool,
+    label: Option<usize>,
+}
+
+/// The side vertices of a cluster on each rank it spans, and how far
+/// apart they need to be for the title
+#[derive(Clone, Default)]
+struct ClusterSides {
+    ranks: Vec<(usize, usize)>,
+    min_width: f64,
+}
+
+/// Cluster margins in rank space: before and after along the cross axis,
+/// then before and after along the rank axis
+type Margins = [f64; 4];
+
+pub fn layout(
+    nodes: &[LayoutNode],
+    edges: &[LayoutEdge],
+    clusters: &[LayoutCluster],
+    options: &LayoutOptions,
+) -> Layout {
+    if nodes.is_empty() {
+        return Layout {
+            nodes: Vec::new(),
+            edges: vec![EdgeRoute::default(); edges.len()],
+            clusters: vec![None; clusters.len()],
+            width: 0.0,
+            height: 0.0,
+        };
+    }
+
+    let horizontal = options.direction.is_horizontal();
+    let size = |width: f64, height: f64| {
+        if horizontal {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    };
+    let margins: Vec<Margins> = clusters
+        .iter()
+        .map(|cluster| cluster_margins(cluster, options))
+        .collect();
+
+    // Labels sit on a rank of their own, so every edge spans two ranks
+    // at half the spacing, as in dagre
+    let labelled = edges.iter().any(|e| e.label.is_some());
+    let (min_length, rank_spacing) = if labelled {
+        (2, options.rank_spacing / 2.0)
+    } else {
+        (1, options.rank_spacing)
+    };
+
+    let graph: Vec<(usize, usize, usize)> = edges
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.from != e.to && e.from < nodes.len() && e.to < nodes.len())
+        .map(|(index, e)| (index, e.from, e.to))
+        .collect();
+    let reversed = acyclic(nodes.len(), &graph);
+    let oriented: Vec<(usize, usize)> = graph
+        .iter()
+        .zip(&reversed)
+        .map(|(&(_, from, to), &reversed)| if reversed { (to, from) } else { (from, to) })
+        .collect();
+    let ranks = assign_ranks(nodes.len(), &oriented, min_length);
+
+    // Vertices: the nodes, then dummies for edges spanning several ranks
+    let mut vertices: Vec<Vertex> = nodes
+        .iter()
+        .zip(&ranks)
+        .map(|(node, &rank)| {
+            let (cross, length) = size(node.width, node.height);
+            Vertex {
+                cross,
+                length,
+                rank,
+                clusters: cluster_chain(clusters, node.cluster),
+                dummy: false,
+                border: None,
+            }
+        })
+        .collect();
+    let mut chains: Vec<Option<Chain>> = (0..edges.len()).map(|_| None).collect();
+    for (&(index, _, _), (&(from, to), &reversed)) in
+        graph.iter().zip(oriented.iter().zip(&reversed))
+    {
+        let common: Vec<usize> = vertices[from]
+            .clusters
+            .iter()
+            .zip(&vertices[to].clusters)
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| *a)
+            .collect();
+        let (first, last) = (ranks[from], ranks[to]);
+        let label_rank = first + (last - first) / 2;
+        let mut chain = Chain {
+            vertices: vec![from],
+            reversed,
+            label: None,
+        };
+        for rank in first + 1..last {
+            let (cross, length) = match edges[index].label {
+                Some((width, height)) if rank == label_rank => size(width, height),
+                _ => (0.0, 0.0),
+            };
+            if edges[index].label.is_some() && rank == label_rank {
+                chain.label = Some(vertices.len());
+            }
+            chain.vertices.push(vertices.len());
+            vertices.push(Vertex {
+                cross,
+                length,
+                rank,
+                clusters: common.clone(),
+                dummy: true,
+                border: None,
+            });
+        }
+        chain.vertices.push(to);
+        chains[index] = Some(chain);
+    }
+
+    // Both sides of each cluster on every rank it spans, so that nothing
+    // outside it ends up within its bounds
+    let mut first_rank = vec![usize::MAX; clusters.len()];
+    let mut last_rank = vec![0; clusters.len()];
+    for vertex in &vertices {
+        for &cluster in &vertex.clusters {
+            first_rank[cluster] = first_rank[cluster].min(vertex.rank);
+            last_rank[cluster] = last_rank[cluster].max(vertex.rank);
+        }
+    }
+    let mut sides: Vec<ClusterSides> = vec![ClusterSides::default(); clusters.len()];
+    for (cluster, sides) in sides.iter_mut().enumerate() {
+        if first_rank[cluster] == usize::MAX {
+            continue;
+        }
+        if !horizontal {
+            sides.min_width = clusters[cluster].title_width + 2.0 * options.cluster_padding;
+        }
+        for rank in first_rank[cluster]..=last_rank[cluster] {
+            sides.ranks.push((vertices.len(), vertices.len() + 1));
+            for border in [Border::Left, Border::Right] {
+                vertices.push(Vertex {
+                    cross: 0.0,
+                    length: 0.0,
+                    rank,
+                    clusters: cluster_chain(clusters, Some(cluster)),
+                    dummy: true,
+                    border: Some(border),
+                });
+            }
+        }
+    }
+
+    // Links between adjacent ranks, weighted to keep long edges straight
+    let links = chains
+        .iter()
+        .flatten()
+        .flat_map(|chain| chain.vertices.windows(2).map(|pair| (pair[0], pair[1])));
+    let mut predecessors: Vec<Vec<(usize, f64)>> = vec![Vec::new(); vertices.len()];
+    let mut successors: Vec<Vec<(usize, f64)>> = vec![Vec::new(); vertices.len()];
+    for (from, to) in links {
+        let weight = match (vertices[from].dummy, vertices[to].dummy) {
+            (true, true) => 8.0,
+            (false, false) => 1.0,
+            _ => 2.0,
+        };
+        successors[from].push((to, weight));
+        predecessors[to].push((from, weight));
+    }
+
+    let layers = order_layers(&vertices, &predecessors, &successors);
+    let cross = assign_cross(
+        &vertices,
+        &layers,
+        &predecessors,
+        &successors,
+        &sides,
+        &margins,
+        options,
+    );
+
+    // Rank positions, leaving room for the clusters starting and ending on
+    // each rank
+    let mut along = vec![0.0; layers.len()];
+    let mut position = 0.0;
+    for (rank, layer) in layers.iter().enumerate() {
+        let extent = |margin: usize, edge: &[usize]| {
+            layer
+                .iter()
+                .map(|&v| {
+                    vertices[v]
+                        .clusters
+                        .iter()
+                        .filter(|&&c| edge[c] == rank)
+                        .map(|&c| margins[c][margin])
+                        .sum::<f64>()
+                })
+                .fold(0.0, f64::max)
+        };
+        let length = layer
+            .iter()
+            .map(|&v| vertices[v].length)
+            .fold(0.0, f64::max);
+        if rank > 0 {
+            position += rank_spacing;
+        }
+        position += extent(2, &first_rank) + length / 2.0;
+        along[rank] = position;
+        position += length / 2.0 + extent(3, &last_rank);
+    }
+    let rank_extent = position;
+
+    // Cluster bounds, innermost first
+    let mut bounds: Vec<Option<Rect>> = vec![None; clusters.len()];
+    let mut by_depth: Vec<usize> = (0..clusters.len()).collect();
+    let depth = |c: usize| cluster_chain(clusters, Some(c)).len();
+    by_depth.sort_by_key(|&c| std::cmp::Reverse(depth(c)));
+    for &cluster in &by_depth {
+        let mut rect: Option<Rect> = None;
+        for (index, vertex) in vertices.iter().enumerate() {
+            if vertex.clusters.last() == Some(&cluster) {
+                let member = Rect {
+                    x: cross[index] - vertex.cross / 2.0,
+                    y: along[vertex.rank] - vertex.length / 2.0,
+                    width: vertex.cross,
+                    height: vertex.length,
+                };
+                rect = Some(union(rect, member));
+            }
+        }
+        for (child, cluster_bounds) in bounds.iter().enumerate() {
+            if clusters[child].parent == Some(cluster) && child != cluster {
+                if let Some(child_rect) = cluster_bounds {
+                    rect = Some(union(rect, *child_rect));
+                }
+            }
+        }
+        // The sides already include the margins across ranks
+        bounds[cluster] = rect.map(|rect| {
+            let [_, _, top, bottom] = margins[cluster];
+            let mut rect = Rect {
+                y: rect.y - top,
+                height: rect.height + top + bottom,
+                ..rect
+            };
+            // Long enough for the title when it runs along the ranks
+            let title = clusters[cluster].title_width + 2.0 * options.cluster_padding;
+            if horizontal && rect.height < title {
+                rect.y -= (title - rect.height) / 2.0;
+                rect.height = title;
+            }
+            rect
+        });
+    }
+
+    // Shift everything to start at zero
+    let min_cross = vertices
+        .iter()
+        .zip(&cross)
+        .map(|(vertex, x)| x - vertex.cross / 2.0)
+        .chain(bounds.iter().flatten().map(|rect| rect.x))
+        .fold(f64::INFINITY, f64::min);
+    let min_along = bounds.iter().flatten().map(|r| r.y).fold(0.0, f64::min);
+    let cross_extent = vertices
+        .iter()
+        .zip(&cross)
+        .map(|(vertex, x)| x + vertex.cross / 2.0)
+        .chain(bounds.iter().flatten().map(|rect| rect.x + rect.width))
+        .fold(f64::NEG_INFINITY, f64::max)
+        - min_cross;
+    let rank_extent = bounds
+        .iter()
+        .flatten()
+        .map(|rect| rect.y + rect.height)
+        .fold(rank_extent, f64::max)
+        - min_along;
+
+    let direction = options.direction;
+    let point = |x: f64, y: f64| {
+        let (x, y) = (x - min_cross, y - min_along);
+        match direction {
+            Direction::TopBottom => Point { x, y },
+            Direction::BottomTop => Point {
+                x,
+                y: rank_extent - y,
+            },
+            Direction::LeftRight => Point { x: y, y: x },
+            Direction::RightLeft => Point {
+                x: rank_extent - y,
+                y: x,
+            },
+        }
+    };
+    let vertex_point = |v: usize| point(cross[v], along[vertices[v].rank]);
+
+    let edges = chains
+        .iter()
+        .map(|chain| match chain {
+            Some(chain) => {
+                let mut points: Vec<Point> =
+                    chain.vertices.iter().map(|&v| vertex_point(v)).collect();
+                if chain.reversed {
+                    points.reverse();
+                }
+                EdgeRoute {
+                    points,
+                    label: chain.label.map(vertex_point),
+                }
+            }
+            None => EdgeRoute::default(),
+        })
+        .collect();
+    let clusters = bounds
+        .iter()
+        .map(|rect| {
+            rect.map(|rect| {
+                let a = point(rect.x, rect.y);
+                let b = point(rect.x + rect.width, rect.y + rect.height);
+                Rect {
+                    x: a.x.min(b.x),
+                    y: a.y.min(b.y),
+                    width: (a.x - b.x).abs(),
+                    height: (a.y - b.y).abs(),
+                }
+            })
+        })
+        .collect();
+    let (width, height) = size(cross_extent, rank_extent);
+
+    Layout {
+        nodes: (0..nodes.len()).map(vertex_point).collect(),
+        edges,
+        clusters,
+        width,
+        height,
+    }
+}
+
+/// The title goes on the side that ends up at the top of the diagram
+fn cluster_margins(cluster: &LayoutCluster, options: &LayoutOptions) -> Margins {
+    let padding = options.cluster_padding;
+    let title = padding + cluster.title_height;
+    match options.direction {
+        Direction::TopBottom => [padding, padding, title, padding],
+        Direction::BottomTop => [padding, padding, padding, title],
+        Direction::LeftRight | Direction::RightLeft => [title, padding, padding, padding],
+    }
+}
+
+/// A cluster and its ancestors, outermost first
+fn cluster_chain(clusters: &[LayoutCluster], cluster: Option<usize>) -> Vec<usize> {
+    let mut chain = Vec::new();
+    let mut current = cluster.filter(|&c| c < clusters.len());
+    while let Some(cluster) = current {
+        if chain.contains(&cluster) {
+            break;
+        }
+        chain.push(cluster);
+        current = clusters[cluster].parent.filter(|&c| c < clusters.len());
+    }
+    chain.reverse();
+    chain
+}
+
+fn union(rect: Option<Rect>, other: Rect) -> Rect {
+    match rect {
+        None => other,
+        Some(rect) => {
+            let x = rect.x.min(other.x);
+            let y = rect.y.min(other.y);
+            Rect {
+                x,
+                y,
+                width: (rect.x + rect.width).max(other.x + other.width) - x,
+                height: (rect.y + rect.height).max(other.y + other.height) - y,
+            }
+        }
+    }
+}
+
+/// Edges to reverse to break cycles: the back edges of a depth-first
+/// search in node order
+fn acyclic(count: usize, edges: &[(usize, usize, usize)]) -> Vec<bool> {
+    let mut outgoing: Vec<Vec<(usize, usize)>> = vec![Vec::new(); count];
+    for (index, &(_, from, to)) in edges.iter().enumerate() {
+        outgoing[from].push((index, to));
+    }
+
+    let mut reversed = vec![false; edges.len()];
+    // 0: unvisited, 1: on the stack, 2: done
+    let mut state = vec![0u8; count];
+    for start in 0..count {
+        if state[start] != 0 {
+            continue;
+        }
+        state[start] = 1;
+        let mut stack = vec![(start, 0)];
+        while let Some(&(node, next)) = stack.last() {
+            match outgoing[node].get(next) {
+                Some(&(edge, to)) => {
+                    if let Some(top) = stack.last_mut() {
+                        top.1 += 1;
+                    }
+                    match state[to] {
+                        0 => {
+                            state[to] = 1;
+                            stack.push((to, 0));
+                        }
+                        1 => reversed[edge] = true,
+                        _ => {}
+                    }
+                }
+                None => {
+                    state[node] = 2;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    reversed
+}
+
+/// Longest-path ranking, with sources pulled down next to their successors
+fn assign_ranks(count: usize, edges: &[(usize, usize)], min_length: usize) -> Vec<usize> {
+    let mut incoming = vec![0; count];
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for &(from, to) in edges {
+        incoming[to] += 1;
+        outgoing[from].push(to);
+    }
+
+    let mut order = Vec::with_capacity(count);
+    let mut remaining = incoming.clone();
+    let mut ready: Vec<usize> = (0..count).rev().filter(|&n| remaining[n] == 0).collect();
+    while let Some(node) = ready.pop() {
+        order.push(node);
+        for &to in outgoing[node].iter().rev() {
+            remaining[to] -= 1;
+            if remaining[to] == 0 {
+                ready.push(to);
+            }
+        }
+    }
+
+    let mut ranks = vec![0; count];
+    for &node in &order {
+        for &to in &outgoing[node] {
+            ranks[to] = ranks[to].max(ranks[node] + min_length);
+        }
+    }
+    for &node in order.iter().rev() {
+        if incoming[node] == 0 {
+            if let Some(rank) = outgoing[node].iter().map(|&to| ranks[to]).min() {
+                ranks[node] = rank.saturating_sub(min_length);
+            }
+        }
+    }
+    let lowest = ranks.iter().copied().min().unwrap_or(0);
+    ranks.iter().map(|rank| rank - lowest).collect()
+}
+
+/// Order the vertices within each rank: a depth-first initial order, then
+/// barycenter sweeps keeping the order with the fewest crossings. Members
+/// of a cluster always stay next to each other.
+fn order_layers(
+    vertices: &[Vertex],
+    predecessors: &[Vec<(usize, f64)>],
+    successors: &[Vec<(usize, f64)>],
+) -> Vec<Vec<usize>> {
+    let rank_count = vertices.iter().map(|v| v.rank + 1).max().unwrap_or(0);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); rank_count];
+
+    let mut starts: Vec<usize> = (0..vertices.len()).collect();
+    starts.sort_by_key(|&v| vertices[v].rank);
+    let mut visited = vec![false; vertices.len()];
+    for start in starts {
+        let mut stack = vec![start];
+        while let Some(vertex) = stack.pop() {
+            if visited[vertex] {
+                continue;
+            }
+            visited[vertex] = true;
+            layers[vertices[vertex].rank].push(vertex);
+            stack.extend(successors[vertex].iter().rev().map(|&(to, _)| to));
+        }
+    }
+    for layer in &mut layers {
+        let items = layer
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| (v, i as f64))
+            .collect();
+        *layer = group_clusters(items, 0, vertices);
+    }
+
+    let mut best = layers.clone();
+    let mut best_crossings = crossings(&layers, successors, vertices.len());
+    for sweep in 0..ORDER_SWEEPS {
+        let down = sweep % 2 == 0;
+        let ranks: Vec<usize> = if down {
+            (1..rank_count).collect()
+        } else {
+            (0..rank_count.saturating_sub(1)).rev().collect()
+        };
+        for rank in ranks {
+            let fixed = if down { rank - 1 } else { rank + 1 };
+            let mut position = vec![0.0; vertices.len()];
+            let fixed_count = layers[fixed].len() as f64;
+            for (index, &vertex) in layers[fixed].iter().enumerate() {
+                position[vertex] = (index as f64 + 0.5) / fixed_count;
+            }
+
+            let count = layers[rank].len() as f64;
+            let items = layers[rank]
+                .iter()
+                .enumerate()
+                .map(|(index, &vertex)| {
+                    let neighbours = if down {
+                        &predecessors[vertex]
+                    } else {
+                        &successors[vertex]
+                    };
+                    let barycenter = if neighbours.is_empty() {
+                        (index as f64 + 0.5) / count
+                    } else {
+                        neighbours.iter().map(|&(n, _)| position[n]).sum::<f64>()
+                            / neighbours.len() as f64
+                    };
+                    (vertex, barycenter)
+                })
+                .collect();
+            layers[rank] = group_clusters(items, 0, vertices);
+        }
+
+        let count = crossings(&layers, successors, vertices.len());
+        if count < best_crossings {
+            best_crossings = count;
+            best = layers.clone();
+        }
+    }
+    best
+}
+
+/// Vertices of a rank with their barycenters
+type Barycenters = Vec<(usize, f64)>;
+
+/// Sort a rank by barycenter, keeping each cluster contiguous and placed
+/// by the average barycenter of its members
+fn group_clusters(items: Barycenters, depth: usize, vertices: &[Vertex]) -> Vec<usize> {
+    // Clusters at this depth, or single vertices directly in the parent
+    let mut groups: Vec<(Option<usize>, Barycenters)> = Vec::new();
+    for item in items {
+        match vertices[item.0].clusters.get(depth).copied() {
+            Some(cluster) => match groups.iter_mut().find(|(key, _)| *key == Some(cluster)) {
+                Some((_, members)) => members.push(item),
+                None => groups.push((Some(cluster), vec![item])),
+            },
+            None => groups.push((None, vec![item])),
+        }
+    }
+
+    let mut sorted: Vec<(f64, Vec<usize>)> = groups
+        .into_iter()
+        .map(|(key, members)| {
+            let barycenter = members.iter().map(|(_, b)| b).sum::<f64>() / members.len() as f64;
+            let order = match key {
+                Some(_) => {
+                    // The cluster's sides go around its contents
+                    let order = group_clusters(members, depth + 1, vertices);
+                    let side = |v: &usize, border| {
+                        vertices[*v].border == Some(border)
+                            && vertices[*v].clusters.len() == depth + 1
+                    };
+                    let (left, rest): (Vec<usize>, Vec<usize>) =
+                        order.into_iter().partition(|v| side(v, Border::Left));
+                    let (right, rest): (Vec<usize>, Vec<usize>) =
+                        rest.into_iter().partition(|v| side(v, Border::Right));
+                    left.into_iter().chain(rest).chain(right).collect()
+                }
+                None => members.iter().map(|(v, _)| *v).collect(),
+            };
+            (barycenter, order)
+        })
+        .collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    sorted.into_iter().flat_map(|(_, order)| order).collect()
+}
+
+fn crossings(layers: &[Vec<usize>], successors: &[Vec<(usize, f64)>], count: usize) -> usize {
+    let mut position = vec![0; count];
+    for layer in layers {
+        for (index, &vertex) in layer.iter().enumerate() {
+            position[vertex] = index;
+        }
+    }
+
+    let mut total = 0;
+    for layer in layers {
+        let segments: Vec<(usize, usize)> = layer
+            .iter()
+            .flat_map(|&v| successors[v].iter().map(move |&(to, _)| (v, to)))
+            .map(|(from, to)| (position[from], position[to]))
+            .collect();
+        for (i, a) in segments.iter().enumerate() {
+            for b in &segments[i + 1..] {
+                if (a.0 < b.0 && a.1 > b.1) || (a.0 > b.0 && a.1 < b.1) {
+                    total += 1;
+                }
+            }
+        }
+    }
+    total
+}
+
+/// Positions across the ranks: each vertex is pulled towards the weighted
+/// average of its neighbours, and each rank is then placed as close to
+/// those targets as the spacing allows
+fn assign_cross(
+    vertices: &[Vertex],
+    layers: &[Vec<usize>],
+    predecessors: &[Vec<(usize, f64)>],
+    successors: &[Vec<(usize, f64)>],
+    sides: &[ClusterSides],
+    margins: &[Margins],
+    options: &LayoutOptions,
+) -> Vec<f64> {
+    // Half the space each vertex wants next to it; a cluster's sides keep
+    // its margin towards the inside instead
+    let half_spacing = |vertex: &Vertex| match (vertex.dummy, vertex.border) {
+        (true, None) => options.edge_spacing / 2.0,
+        _ => options.node_spacing / 2.0,
+    };
+    let separation = |a: usize, b: usize| {
+        let (a, b) = (&vertices[a], &vertices[b]);
+        let spacing = match (a.border, b.border) {
+            (Some(Border::Left), _) => a.clusters.last().map_or(0.0, |&c| margins[c][0]),
+            (_, Some(Border::Right)) => b.clusters.last().map_or(0.0, |&c| margins[c][1]),
+            _ => half_spacing(a) + half_spacing(b),
+        };
+        (a.cross + b.cross) / 2.0 + spacing
+    };
+    let offsets: Vec<Vec<f64>> = layers
+        .iter()
+        .map(|layer| {
+            let mut offset = 0.0;
+            let mut offsets = Vec::with_capacity(layer.len());
+            for (index, &vertex) in layer.iter().enumerate() {
+                if index > 0 {
+                    offset += separation(layer[index - 1], vertex);
+                }
+                offsets.push(offset);
+            }
+            offsets
+        })
+        .collect();
+
+    let mut cross = vec![0.0; vertices.len()];
+    for (layer, offsets) in layers.iter().zip(&offsets) {
+        let middle = offsets.last().copied().unwrap_or(0.0) / 2.0;
+        for (&vertex, offset) in layer.iter().zip(offsets) {
+            cross[vertex] = offset - middle;
+        }
+    }
+
+    let place = |cross: &mut Vec<f64>, rank: usize, up: bool, down: bool| {
+        let layer = &layers[rank];
+        let targets: Vec<(f64, f64)> = layer
+            .iter()
+            .enumerate()
+            .map(|(index, &vertex)| {
+                // The sides of a cluster hug its contents
+                match vertices[vertex].border {
+                    Some(Border::Left) if index + 1 < layer.len() => {
+                        let next = layer[index + 1];
+                        return (cross[next] - separation(vertex, next), 1.0);
+                    }
+                    Some(Border::Right) if index > 0 => {
+                        let previous = layer[index - 1];
+                        return (cross[previous] + separation(previous, vertex), 1.0);
+                    }
+                    _ => {}
+                }
+                let neighbours = predecessors[vertex]
+                    .iter()
+                    .filter(|_| up)
+                    .chain(successors[vertex].iter().filter(|_| down));
+                let (sum, weight) = neighbours.fold((0.0, 0.0), |(sum, weight), &(n, w)| {
+                    (sum + cross[n] * w, weight + w)
+                });
+                if weight > 0.0 {
+                    (sum / weight, weight)
+                } else {
+                    (cross[vertex], 0.1)
+                }
+            })
+            .collect();
+        let placed = place_in_order(&targets, &offsets[rank]);
+        for (&vertex, x) in layer.iter().zip(placed) {
+            cross[vertex] = x;
+        }
+    };
+    for _ in 0..POSITION_ROUNDS {
+        for rank in 1..layers.len() {
+            place(&mut cross, rank, true, false);
+        }
+        for rank in (0..layers.len().saturating_sub(1)).rev() {
+            place(&mut cross, rank, false, true);
+        }
+    }
+    for rank in 0..layers.len() {
+        place(&mut cross, rank, true, true);
+    }
+
+    // Line up the sides of each cluster at its widest, or wider for its
+    // title, and widen the clusters around it to match, innermost first
+    let mut nesting: Vec<usize> = (0..sides.len())
+        .filter(|&c| !sides[c].ranks.is_empty())
+        .collect();
+    let chain = |cluster: usize| &vertices[sides[cluster].ranks[0].0].clusters;
+    nesting.sort_by_key(|&c| std::cmp::Reverse(chain(c).len()));
+    for _ in 0..BORDER_ROUNDS {
+        let mut extents: Vec<(f64, f64)> = sides
+            .iter()
+            .map(|cluster| {
+                cluster.ranks.iter().fold(
+                    (f64::INFINITY, f64::NEG_INFINITY),
+                    |(left, right), &(left_side, right_side)| {
+                        (left.min(cross[left_side]), right.max(cross[right_side]))
+                    },
+                )
+            })
+            .collect();
+        let mut targets: Vec<Option<f64>> = vec![None; vertices.len()];
+        for &cluster in &nesting {
+            let (mut left, mut right) = extents[cluster];
+            let missing = sides[cluster].min_width - (right - left);
+            if missing > 0.0 {
+                left -= missing / 2.0;
+                right += missing / 2.0;
+            }
+            for &(left_side, right_side) in &sides[cluster].ranks {
+                targets[left_side] = Some(left);
+                targets[right_side] = Some(right);
+            }
+            if let Some(&parent) = chain(cluster).iter().rev().nth(1) {
+                let extent = &mut extents[parent];
+                extent.0 = extent.0.min(left - margins[parent][0]);
+                extent.1 = extent.1.max(right + margins[parent][1]);
+            }
+        }
+        for (layer, offsets) in layers.iter().zip(&offsets) {
+            let goals: Vec<(f64, f64)> = layer
+                .iter()
+                .map(|&vertex| match targets[vertex] {
+                    Some(target) => (target, 1e6),
+                    None => (cross[vertex], 1.0),
+                })
+                .collect();
+            for (&vertex, x) in layer.iter().zip(place_in_order(&goals, offsets)) {
+                cross[vertex] = x;
+            }
+        }
+    }
+
+    // Nothing pulls unconnected parts of the graph together, so slide each
+    // one towards its left neighbours, leftmost first. Clusters move as a
+    // whole.
+    let mut links: Vec<(usize, usize)> = successors
+        .iter()
+        .enumerate()
+        .flat_map(|(from, links)| links.iter().map(move |&(to, _)| (from, to)))
+        .collect();
+    for (vertex, data) in vertices.iter().enumerate() {
+        if let Some(&(left, _)) = data.clusters.first().and_then(|&c| sides[c].ranks.first()) {
+            links.push((vertex, left));
+        }
+    }
+    let component = components(vertices.len(), &links);
+    let mut left_of: Vec<Option<usize>> = vec![None; vertices.len()];
+    for layer in layers {
+        for pair in layer.windows(2) {
+            left_of[pair[1]] = Some(pair[0]);
+        }
+    }
+    let mut order: Vec<usize> = (0..vertices.len()).collect();
+    order.sort_by(|&a, &b| cross[a].total_cmp(&cross[b]));
+    let mut done = vec![false; vertices.len()];
+    for vertex in order {
+        let id = component[vertex];
+        if done[id] {
+            continue;
+        }
+        done[id] = true;
+        let members: Vec<usize> = (0..vertices.len())
+            .filter(|&v| component[v] == id)
+            .collect();
+        let slack = members
+            .iter()
+            .filter_map(|&v| {
+                let left = left_of[v].filter(|&left| component[left] != id)?;
+                Some(cross[v] - cross[left] - separation(left, v))
+            })
+            .fold(f64::INFINITY, f64::min);
+        if slack.is_finite() && slack > 0.0 {
+            for &v in &members {
+                cross[v] -= slack;
+            }
+        }
+    }
+    cross
+}
+
+/// Connected component of each vertex, numbered from zero
+fn components(count: usize, links: &[(usize, usize)]) -> Vec<usize> {
+    let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for &(from, to) in links {
+        neighbours[from].push(to);
+        neighbours[to].push(from);
+    }
+
+    let mut component = vec![usize::MAX; count];
+    let mut next = 0;
+    for start in 0..count {
+        if component[start] != usize::MAX {
+            continue;
+        }
+        let mut stack = vec![start];
+        component[start] = next;
+        while let Some(vertex) = stack.pop() {
+            for &neighbour in &neighbours[vertex] {
+                if component[neighbour] == usize::MAX {
+                    component[neighbour] = next;
+                    stack.push(neighbour);
+                }
+            }
+        }
+        next += 1;
+    }
+    component
+}
+
+/// Positions closest to the weighted targets, in the given order and at
+/// least the given offsets apart: isotonic regression by pool adjacent
+/// violators
+fn place_in_order(targets: &[(f64, f64)], offsets: &[f64]) -> Vec<f64> {
+    // Blocks of (weighted sum, weight, length)
+    let mut blocks: Vec<(f64, f64, usize)> = Vec::new();
+    for (&(target, weight), offset) in targets.iter().zip(offsets) {
+        blocks.push(((target - offset) * weight, weight, 1));
+        while blocks.len() > 1 {
+            let (sum, weight, length) = blocks[blocks.len() - 1];
+            let (previous_sum, previous_weight, previous_length) = blocks[blocks.len() - 2];
+            if previous_sum / previous_weight <= sum / weight {
+                break;
+            }
+            blocks.pop();
+            if let Some(last) = blocks.last_mut() {
+                *last = (
+                    previous_sum + sum,
+                    previous_weight + weight,
+                    previous_length + length,
+                );
+            }
+        }
+    }
+
+    let mut positions = Vec::with_capacity(targets.len());
+    for (sum, weight, length) in blocks {
+        for _ in 0..length {
+            positions.push(sum / weight + offsets[positions.len()]);
+        }
+    }
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(cluster: Option<usize>) -> LayoutNode {
+        LayoutNode {
+            width: 60.0,
+            height: 40.0,
+            cluster,
+        }
+    }
+
+    fn edge(from: usize, to: usize) -> LayoutEdge {
+        LayoutEdge {
+            from,
+            to,
+            label: None,
+        }
+    }
+
+    fn overlaps(layout: &Layout, width: f64, height: f64) -> bool {
+        layout.nodes.iter().enumerate().any(|(i, a)| {
+            layout.nodes[i + 1..]
+                .iter()
+                .any(|b| (a.x - b.x).abs() < width && (a.y - b.y).abs() < height)
+        })
+    }
+
+    #[test]
+    fn test_layout_directions() {
+        let nodes = vec![node(None), node(None), node(None)];
+        let edges = vec![edge(0, 1), edge(1, 2)];
+        let mut options = LayoutOptions::default();
+
+        let layout = layout(&nodes, &edges, &[], &options);
+        assert!(layout.nodes[0].y < layout.nodes[1].y && layout.nodes[1].y < layout.nodes[2].y);
+        assert_eq!(layout.nodes[1].y - layout.nodes[0].y, 90.0);
+        assert_eq!((layout.width, layout.height), (60.0, 220.0));
+
+        options.direction = Direction::BottomTop;
+        let bottom_top = super::layout(&nodes, &edges, &[], &options);
+        assert!(bottom_top.nodes[0].y > bottom_top.nodes[2].y);
+
+        options.direction = Direction::LeftRight;
+        let left_right = super::layout(&nodes, &edges, &[], &options);
+        assert!(left_right.nodes[0].x < left_right.nodes[2].x);
+        assert_eq!((left_right.width, left_right.height), (280.0, 40.0));
+
+        options.direction = Direction::RightLeft;
+        let right_left = super::layout(&nodes, &edges, &[], &options);
+        assert!(right_left.nodes[0].x > right_left.nodes[2].x);
+    }
+
+    #[test]
+    fn test_layout_cycles_labels_and_crossings() {
+        // A -> B, A -> C, B -> D, C -> D, D -> A with a labelled edge
+        let nodes = vec![node(None), node(None), node(None), node(None)];
+        let mut edges = vec![edge(0, 1), edge(0, 2), edge(1, 3), edge(2, 3), edge(3, 0)];
+        edges[0].label = Some((40.0, 24.0));
+        let layout = layout(&nodes, &edges, &[], &LayoutOptions::default());
+
+        assert!(!overlaps(&layout, 60.0, 40.0));
+        assert!(layout.nodes[0].y < layout.nodes[1].y);
+        assert_eq!(layout.nodes[1].y, layout.nodes[2].y);
+        // The back edge runs from D to A, around the other nodes
+        let back = &layout.edges[4].points;
+        assert_eq!(back.first(), Some(&layout.nodes[3]));
+        assert_eq!(back.last(), Some(&layout.nodes[0]));
+        assert!(back.len() > 2);
+
+        let label = layout.edges[0].label.unwrap();
+        assert!(label.y > layout.nodes[0].y && label.y < layout.nodes[1].y);
+
+        // Two unconnected chains placed side by side without crossing
+        let nodes = vec![node(None), node(None), node(None), node(None)];
+        let edges = vec![edge(0, 3), edge(1, 2)];
+        let layout = super::layout(&nodes, &edges, &[], &LayoutOptions::default());
+        assert_eq!(
+            layout.nodes[0].x < layout.nodes[1].x,
+            layout.nodes[3].x < layout.nodes[2].x
+        );
+        assert_eq!((layout.width, layout.height), (170.0, 130.0));
+
+        let empty = super::layout(&[], &edges, &[], &LayoutOptions::default());
+        assert_eq!((empty.width, empty.edges.len()), (0.0, 2));
+    }
+
+    #[test]
+    fn test_layout_clusters() {
+        // Cluster 0 holds B and C, and cluster 1 inside it holds C only
+        let nodes = vec![node(None), node(Some(0)), node(Some(1)), node(None)];
+        let edges = vec![edge(0, 1), edge(0, 3), edge(0, 2)];
+        let clusters = vec![
+            LayoutCluster {
+                parent: None,
+                title_width: 40.0,
+                title_height: 24.0,
+            },
+            LayoutCluster {
+                parent: Some(0),
+                title_width: 120.0,
+                title_height: 24.0,
+            },
+        ];
+        let layout = layout(&nodes, &edges, &clusters, &LayoutOptions::default());
+        assert!(!overlaps(&layout, 60.0, 40.0));
+
+        let outer = layout.clusters[0].unwrap();
+        let inner = layout.clusters[1].unwrap();
+        let contains = |rect: Rect, point: Point| {
+            point.x > rect.x
+                && point.x < rect.x + rect.width
+                && point.y > rect.y
+                && point.y < rect.y + rect.height
+        };
+        assert!(contains(outer, layout.nodes[1]) && contains(outer, layout.nodes[2]));
+        assert!(contains(inner, layout.nodes[2]) && !contains(inner, layout.nodes[1]));
+        assert!(!contains(outer, layout.nodes[0]) && !contains(outer, layout.nodes[3]));
+        assert!(contains(outer, inner.center()));
+        assert!(inner.width > 135.9, "{:?}", inner);
+        // Room for the title above the contents
+        assert!(layout.nodes[2].y - 20.0 - inner.y >= 32.0);
+    }
+}
@@ -1,21 +1,97 @@
-use crate::models::{DiagramOptions, DiagramResult};
-use crate::utils::path::get_mmdc_paths;
+use crate::models::{
+    DiagramFormat, DiagramOptions, MmdcDiagnosis, MmdcSettings, RendererBackend,
+    RendererCapabilities,
+};
+use crate::services::discovery_service::{mmdc_command, MmdcDiscovery};
+use crate::services::renderer_service::{RenderError, RenderJob, Renderer};
+use crate::utils::{merge_json, TempFile};
+use serde_json::{json, Value};
+use std::ffi::OsString;
 use std::io::Write;
-use std::process::Command;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MmdcVersion(pub u32, pub u32, pub u32);
+
+impl MmdcVersion {
+    /// Find the first `major.minor.patch` number in `mmdc --version` output
+    pub fn parse(text: &str) -> Option<Self> {
+        text.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .find_map(|token| {
+                let mut parts = token.split('.').map(|p| p.parse::<u32>());
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch))) => {
+                        Some(Self(major, minor, patch))
+                    }
+                    _ => None,
+                }
+            })
+    }
+}
+
+impl std::fmt::Display for MmdcVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// An optional mmdc flag and the first release that accepts it
+struct MmdcFlag {
+    name: &'static str,
+    since: MmdcVersion,
+}
+
+const OUTPUT_FORMAT: MmdcFlag = MmdcFlag {
+    name: "--outputFormat",
+    since: MmdcVersion(9, 2, 0),
+};
+const SCALE: MmdcFlag = MmdcFlag {
+    name: "--scale",
+    since: MmdcVersion(8, 4, 0),
+};
+const PDF_FIT: MmdcFlag = MmdcFlag {
+    name: "--pdfFit",
+    since: MmdcVersion(8, 6, 0),
+};
+const CONFIG_FILE: MmdcFlag = MmdcFlag {
+    name: "--configFile",
+    since: MmdcVersion(8, 0, 0),
+};
+const CSS_FILE: MmdcFlag = MmdcFlag {
+    name: "--cssFile",
+    since: MmdcVersion(8, 0, 0),
+};
+const PUPPETEER_CONFIG_FILE: MmdcFlag = MmdcFlag {
+    name: "--puppeteerConfigFile",
+    since: MmdcVersion(8, 0, 0),
+};
+const SVG_ID: MmdcFlag = MmdcFlag {
+    name: "--svgId",
+    since: MmdcVersion(9, 2, 0),
+};
 
 pub struct MmdcService {
     mmdc_path: Option<String>,
+    mmdc_version: Option<MmdcVersion>,
+    discovery: MmdcDiscovery,
 }
 
 impl MmdcService {
     pub fn new() -> Self {
-        Self { mmdc_path: None }
+        Self {
+            mmdc_path: None,
+            mmdc_version: None,
+            discovery: MmdcDiscovery::default_location(),
+        }
     }
 
-    /// Find and cache the mmdc executable path
+    /// Find and cache the mmdc executable path and version
     pub fn initialize(&mut self) -> Result<(), String> {
-        self.mmdc_path = self.find_mmdc_executable();
+        let found = self.discovery.discover();
+        self.set_mmdc(
+            found.as_ref().map(|found| found.candidate.path.clone()),
+            found.and_then(|found| found.version),
+        );
         if self.mmdc_path.is_some() {
             Ok(())
         } else {
@@ -23,6 +99,15 @@ impl MmdcService {
         }
     }
 
+    /// Path and version of mmdc, searching for it on first use
+    pub fn locate(&mut self) -> Result<(String, Option<MmdcVersion>), String> {
+        if self.mmdc_path.is_none() {
+            self.initialize()?;
+        }
+        let path = self.mmdc_path.clone().ok_or("mmdc not available")?;
+        Ok((path, self.mmdc_version))
+    }
+
     /// Check if mmdc is available and return version info
     pub fn check_availability(&mut self) -> Result<String, String> {
         if self.mmdc_path.is_none() {
@@ -30,7 +115,7 @@ impl MmdcService {
         }
 
         if let Some(ref path) = self.mmdc_path {
-            match Command::new(path).arg("--version").output() {
+            match mmdc_command(Path::new(path)).arg("--version").output() {
                 Ok(output) => {
                     if output.status.success() {
                         let version = String::from_utf8_lossy(&output.stdout);
@@ -43,7 +128,12 @@ impl MmdcService {
                 Err(e) => Err(format!("❌ mmdc execution error: {}", e)),
             }
         } else {
-            let attempted_paths = get_mmdc_paths();
+            let attempted_paths: Vec<String> = self
+                .discovery
+                .candidates()
+                .into_iter()
+                .map(|candidate| candidate.path)
+                .collect();
             Err(format!(
                 "❌ mmdc not found. Attempted paths:\n{}",
                 attempted_paths.join("\n")
@@ -51,136 +141,219 @@ impl MmdcService {
         }
     }
 
-    /// Generate diagram from mermaid code
-    pub fn generate_diagram(
+    /// Run mmdc once, writing `options.format` to `output_path`
+    fn run_mmdc(
         &mut self,
         code: &str,
         options: &DiagramOptions,
-        output_path: &std::path::Path,
-    ) -> DiagramResult {
-        let start_time = Instant::now();
+        output_path: &Path,
+        extra_config: Option<&Value>,
+    ) -> Result<(), String> {
+        let (mmdc_path, _) = self.locate()?;
 
-        // Ensure mmdc is available
-        if self.mmdc_path.is_none() {
-            if let Err(e) = self.initialize() {
-                return DiagramResult::error(e, start_time.elapsed().as_millis() as u64);
-            }
-        }
+        // Temporary config and CSS files live until mmdc has finished
+        let (args, _temp_files) = self.mmdc_args(options, output_path, extra_config)?;
+        let mut cmd = mmdc_command(Path::new(&mmdc_path));
+        cmd.args(&args);
 
-        let mmdc_path = match &self.mmdc_path {
-            Some(path) => path,
-            None => {
-                return DiagramResult::error(
-                    "mmdc not available".to_string(),
-                    start_time.elapsed().as_millis() as u64,
-                );
+        // Execute command
+        let mut child = match cmd
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                // mmdc moved or was uninstalled; search again next time
+                self.discovery.invalidate();
+                self.mmdc_path = None;
+                return Err(format!("Failed to start mmdc: {}", e));
             }
         };
 
-        // Build command
-        let mut cmd = Command::new(mmdc_path);
-        cmd.arg("-i").arg("-").arg("-o").arg(output_path);
+        // Write mermaid code to stdin, which is closed when dropped
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(code.as_bytes())
+                .map_err(|e| format!("Failed to write to stdin: {}", e))?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Command execution error: {}", e))?;
+        if !output.status.success() {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("mmdc error: {}", error_msg));
+        }
+        if !output_path.exists() {
+            return Err("File was not created".to_string());
+        }
+        Ok(())
+    }
+
+    /// Build the mmdc command line. Files for inline config and CSS are
+    /// returned alongside and removed when dropped.
+    fn mmdc_args(
+        &self,
+        options: &DiagramOptions,
+        output_path: &Path,
+        extra_config: Option<&Value>,
+    ) -> Result<(Vec<OsString>, Vec<TempFile>), String> {
+        let mut args: Vec<OsString> =
+            vec!["-i".into(), "-".into(), "-o".into(), output_path.into()];
+        let mut temp_files = Vec::new();
 
-        // Add format parameter
-        cmd.arg("-f").arg(options.format.to_string());
+        // Older releases infer the format from the output file extension
+        if self.supports(&OUTPUT_FORMAT) {
+            args.push(OUTPUT_FORMAT.name.into());
+            args.push(options.format.to_string().into());
+        }
 
-        // Add optional parameters
         if let Some(width) = options.width {
             if width > 0 {
-                cmd.arg("-w").arg(width.to_string());
+                args.push("-w".into());
+                args.push(width.to_string().into());
             }
         }
 
         if let Some(height) = options.height {
             if height > 0 {
-                cmd.arg("-H").arg(height.to_string());
+                args.push("-H".into());
+                args.push(height.to_string().into());
             }
         }
 
-        if !options.background.trim().is_empty() && options.background != "transparent" {
-            cmd.arg("-b").arg(&options.background);
+        if let Some(background) = mmdc_background(options) {
+            args.push("-b".into());
+            args.push(background.into());
         }
 
         if let Some(ref theme) = options.theme {
-            cmd.arg("-t").arg(theme);
+            args.push("-t".into());
+            args.push(theme.into());
         }
 
-        // Execute command
-        match cmd
-            .stdin(std::process::Stdio::piped())
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()
+        if let Some(scale) = options.scale.filter(|s| *s > 0.0) {
+            if self.supports(&SCALE) {
+                args.push(SCALE.name.into());
+                args.push(scale.to_string().into());
+            }
+        }
+
+        if options.pdf_fit == Some(true)
+            && matches!(options.format, DiagramFormat::Pdf)
+            && self.supports(&PDF_FIT)
         {
-            Ok(mut child) => {
-                // Write mermaid code to stdin
-                if let Some(stdin) = child.stdin.as_mut() {
-                    if let Err(e) = stdin.write_all(code.as_bytes()) {
-                        return DiagramResult::error(
-                            format!("Failed to write to stdin: {}", e),
-                            start_time.elapsed().as_millis() as u64,
-                        );
-                    }
-                    let _ = stdin; // Close stdin
-                }
+            args.push(PDF_FIT.name.into());
+        }
 
-                match child.wait_with_output() {
-                    Ok(output) => {
-                        let generation_time = start_time.elapsed().as_millis() as u64;
-
-                        if output.status.success() {
-                            // Check if file exists and get size
-                            if output_path.exists() {
-                                let file_size = std::fs::metadata(output_path)
-                                    .ok()
-                                    .map(|meta| meta.len());
-
-                                DiagramResult::success(
-                                    output_path.to_string_lossy().to_string(),
-                                    generation_time,
-                                    file_size,
-                                )
-                            } else {
-                                DiagramResult::error(
-                                    "File was not created".to_string(),
-                                    generation_time,
-                                )
-                            }
-                        } else {
-                            let error_msg = String::from_utf8_lossy(&output.stderr);
-                            DiagramResult::error(
-                                format!("mmdc error: {}", error_msg),
-                                generation_time,
-                            )
-                        }
-                    }
-                    Err(e) => DiagramResult::error(
-                        format!("Command execution error: {}", e),
-                        start_time.elapsed().as_millis() as u64,
-                    ),
+        let mermaid_config = mermaid_config_value(options)?;
+        let configs = [mermaid_config.as_ref(), extra_config];
+        if (options.config_file.is_some() || configs.iter().any(Option::is_some))
+            && self.supports(&CONFIG_FILE)
+        {
+            let path = json_file(
+                options.config_file.as_deref(),
+                &configs,
+                "mermaid_config",
+                &mut temp_files,
+            )?;
+            args.push(CONFIG_FILE.name.into());
+            args.push(path.into());
+        }
+
+        if (options.css_file.is_some() || options.css.is_some()) && self.supports(&CSS_FILE) {
+            let path = match (&options.css_file, &options.css) {
+                (Some(file), None) => PathBuf::from(file),
+                _ => {
+                    let content = read_css(options)?.unwrap_or_default();
+                    let temp = TempFile::with_contents("mermaid_css", "css", content.as_bytes())?;
+                    let path = temp.path().to_path_buf();
+                    temp_files.push(temp);
+                    path
                 }
+            };
+            args.push(CSS_FILE.name.into());
+            args.push(path.into());
+        }
+
+        if (options.puppeteer_config_file.is_some() || options.puppeteer_config.is_some())
+            && self.supports(&PUPPETEER_CONFIG_FILE)
+        {
+            let path = json_file(
+                options.puppeteer_config_file.as_deref(),
+                &[options.puppeteer_config.as_ref()],
+                "mermaid_puppeteer",
+                &mut temp_files,
+            )?;
+            args.push(PUPPETEER_CONFIG_FILE.name.into());
+            args.push(path.into());
+        }
+
+        if let Some(ref svg_id) = options.svg_id {
+            if self.supports(&SVG_ID) {
+                args.push(SVG_ID.name.into());
+                args.push(svg_id.into());
             }
-            Err(e) => DiagramResult::error(
-                format!("Failed to start mmdc: {}", e),
-                start_time.elapsed().as_millis() as u64,
-            ),
         }
-    }
 
-    fn find_mmdc_executable(&self) -> Option<String> {
-        let paths = get_mmdc_paths();
+        Ok((args, temp_files))
+    }
 
-        for path in paths {
-            log::debug!("Trying mmdc path: {}", path);
-            if let Ok(output) = Command::new(&path).arg("--version").output() {
-                if output.status.success() {
-                    log::info!("Found working mmdc at: {}", path);
-                    return Some(path);
-                }
+    /// Whether the installed mmdc accepts a flag; unknown versions are trusted
+    fn supports(&self, flag: &MmdcFlag) -> bool {
+        match self.mmdc_version {
+            Some(version) if version < flag.since => {
+                log::warn!(
+                    "mmdc {} does not support {} (needs {}), ignoring it",
+                    version,
+                    flag.name,
+                    flag.since
+                );
+                false
             }
+            _ => true,
         }
+    }
+
+    pub fn settings(&self) -> MmdcSettings {
+        self.discovery.settings()
+    }
+
+    /// Save new discovery settings and look for mmdc again
+    pub fn configure(&mut self, settings: MmdcSettings) -> Result<(), String> {
+        self.discovery.save_settings(&settings)?;
+        self.initialize()
+    }
+
+    /// Search for mmdc again, ignoring the cached location
+    pub fn rediscover(&mut self) -> Result<(), String> {
+        self.discovery.invalidate();
+        self.initialize()
+    }
 
-        None
+    /// Try every mmdc candidate and switch to the first working one
+    pub fn diagnose(&mut self) -> MmdcDiagnosis {
+        let diagnosis = self.discovery.diagnose();
+        let version = diagnosis
+            .probes
+            .iter()
+            .find(|probe| probe.works())
+            .and_then(|probe| probe.version.clone());
+        self.set_mmdc(
+            diagnosis
+                .selected
+                .as_ref()
+                .map(|selected| selected.path.clone()),
+            version,
+        );
+        diagnosis
+    }
+
+    fn set_mmdc(&mut self, path: Option<String>, version: Option<String>) {
+        self.mmdc_path = path;
+        self.mmdc_version = version.as_deref().and_then(MmdcVersion::parse);
     }
 }
 
@@ -188,4 +361,221 @@ impl Default for MmdcService {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+impl Renderer for MmdcService {
+    fn backend(&self) -> RendererBackend {
+        RendererBackend::Mmdc
+    }
+
+    fn capabilities(&self) -> RendererCapabilities {
+        RendererCapabilities {
+            formats: vec![DiagramFormat::Svg, DiagramFormat::Pdf],
+            diagram_types: None,
+        }
+    }
+
+    fn availability(&mut self) -> Result<(), String> {
+        self.locate().map(|_| ())
+    }
+
+    fn render(&mut self, job: &RenderJob) -> Result<Vec<u8>, RenderError> {
+        self.locate().map_err(RenderError::Unavailable)?;
+
+        let output_file = TempFile::reserve("mermaid_mmdc", &job.format.to_string())
+            .map_err(RenderError::Failed)?;
+        let options = DiagramOptions {
+            format: job.format.clone(),
+            ..job.options.clone()
+        };
+        match self.run_mmdc(job.code, &options, output_file.path(), job.extra_config) {
+            Ok(()) => std::fs::read(output_file.path())
+                .map_err(|e| RenderError::Failed(format!("Failed to read mmdc output: {}", e))),
+            // mmdc could not be started at all
+            Err(e) if self.mmdc_path.is_none() => Err(RenderError::Unavailable(e)),
+            Err(e) => Err(RenderError::Failed(e)),
+        }
+    }
+}
+
+/// Background passed to mmdc with `-b`; mmdc itself defaults to white
+pub fn mmdc_background(options: &DiagramOptions) -> Option<&str> {
+    let background = options.background.trim();
+    (!background.is_empty() && background != "transparent").then_some(background)
+}
+
+fn mermaid_config_value(options: &DiagramOptions) -> Result<Option<Value>, String> {
+    options
+        .mermaid_config
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| format!("Failed to serialize mermaid config: {}", e))
+}
+
+/// Contents of `css_file` followed by the inline `css`
+pub fn read_css(options: &DiagramOptions) -> Result<Option<String>, String> {
+    if options.css_file.is_none() && options.css.is_none() {
+        return Ok(None);
+    }
+
+    let mut content = match options.css_file {
+        Some(ref file) => std::fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read CSS file {}: {}", file, e))?,
+        None => String::new(),
+    };
+    if let Some(ref css) = options.css {
+        content.push('\n');
+        content.push_str(css);
+    }
+    Ok(Some(content))
+}
+
+/// The mermaid config mmdc would render with: the theme option, overridden
+/// by the config file, the inline config and `extra_config` in that order
+pub fn mermaid_config(
+    options: &DiagramOptions,
+    extra_config: Option<&Value>,
+) -> Result<Value, String> {
+    let mut config = json!({});
+    if let Some(ref theme) = options.theme {
+        config["theme"] = json!(theme);
+    }
+    let mermaid_config = mermaid_config_value(options)?;
+    let overlays = [mermaid_config.as_ref(), extra_config];
+    merge_json(
+        &mut config,
+        &merge_json_sources(options.config_file.as_deref(), &overlays)?,
+    );
+    Ok(config)
+}
+
+/// Use a JSON config file as-is, or merge the overlays over it into a
+/// temporary file
+fn json_file(
+    file: Option<&str>,
+    overlays: &[Option<&Value>],
+    prefix: &str,
+    temp_files: &mut Vec<TempFile>,
+) -> Result<PathBuf, String> {
+    if let (Some(file), true) = (file, overlays.iter().all(Option::is_none)) {
+        return Ok(PathBuf::from(file));
+    }
+
+    let config = merge_json_sources(file, overlays)?;
+    let content =
+        serde_json::to_string(&config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let temp = TempFile::with_contents(prefix, "json", content.as_bytes())?;
+    let path = temp.path().to_path_buf();
+    temp_files.push(temp);
+    Ok(path)
+}
+
+/// A JSON config file, if any, with the overlays merged over it in order
+fn merge_json_sources(file: Option<&str>, overlays: &[Option<&Value>]) -> Result<Value, String> {
+    let mut config = match file {
+        Some(file) => {
+            let content = std::fs::read_to_string(file)
+                .map_err(|e| format!("Failed to read config file {}: {}", file, e))?;
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid JSON in config file {}: {}", file, e))?
+        }
+        None => json!({}),
+    };
+    for overlay in overlays.iter().flatten() {
+        merge_json(&mut config, overlay);
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FlowchartConfig, FlowchartCurve, MermaidConfig};
+
+    fn service(version: MmdcVersion) -> MmdcService {
+        MmdcService {
+            mmdc_version: Some(version),
+            ..MmdcService::new()
+        }
+    }
+
+    fn has_flag(args: &[OsString], flag: &str) -> bool {
+        args.iter().any(|arg| arg == flag)
+    }
+
+    #[test]
+    fn test_version_parsing() {
+        assert_eq!(MmdcVersion::parse("11.4.2\n"), Some(MmdcVersion(11, 4, 2)));
+        assert_eq!(
+            MmdcVersion::parse("@mermaid-js/mermaid-cli v10.9.1"),
+            Some(MmdcVersion(10, 9, 1))
+        );
+        assert_eq!(MmdcVersion::parse("unknown"), None);
+        assert!(MmdcVersion(8, 14, 0) < MmdcVersion(9, 2, 0));
+    }
+
+    #[test]
+    fn test_args_respect_version_and_merge_config() {
+        let config_file = TempFile::with_contents(
+            "mermaid_test_config",
+            "json",
+            br#"{"theme":"forest","flowchart":{"curve":"basis","htmlLabels":true}}"#,
+        )
+        .unwrap();
+        let options = DiagramOptions {
+            format: DiagramFormat::Pdf,
+            scale: Some(2.0),
+            pdf_fit: Some(true),
+            config_file: Some(config_file.path().to_string_lossy().to_string()),
+            mermaid_config: Some(MermaidConfig {
+                flowchart: Some(FlowchartConfig {
+                    curve: Some(FlowchartCurve::Linear),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            css: Some(".node rect { rx: 4px; }".to_string()),
+            svg_id: Some("diagram".to_string()),
+            ..DiagramOptions::svg(None)
+        };
+
+        let modern = service(MmdcVersion(11, 4, 2));
+        let (args, temp_files) = modern
+            .mmdc_args(
+                &options,
+                Path::new("out.pdf"),
+                Some(&json!({ "flowchart": { "htmlLabels": false } })),
+            )
+            .unwrap();
+        for flag in [
+            "--outputFormat",
+            "--scale",
+            "--pdfFit",
+            "--configFile",
+            "--cssFile",
+            "--svgId",
+        ] {
+            assert!(has_flag(&args, flag), "missing {}", flag);
+        }
+        assert_eq!(temp_files.len(), 2);
+
+        let config_index = args.iter().position(|a| a == "--configFile").unwrap();
+        let merged: Value =
+            serde_json::from_str(&std::fs::read_to_string(&args[config_index + 1]).unwrap())
+                .unwrap();
+        assert_eq!(merged["theme"], "forest");
+        assert_eq!(merged["flowchart"]["curve"], "linear");
+        assert_eq!(merged["flowchart"]["htmlLabels"], false);
+
+        let temp_paths: Vec<PathBuf> = temp_files.iter().map(|t| t.path().to_path_buf()).collect();
+        drop(temp_files);
+        assert!(temp_paths.iter().all(|p| !p.exists()));
+
+        let old = service(MmdcVersion(8, 9, 0));
+        let (args, _) = old.mmdc_args(&options, Path::new("out.pdf"), None).unwrap();
+        assert!(!has_flag(&args, "--outputFormat"));
+        assert!(!has_flag(&args, "--svgId"));
+        assert!(has_flag(&args, "--pdfFit"));
+    }
+}
@@ -1,6 +1,37 @@
-pub mod mmdc_service;
+pub mod config_service;
+pub mod diff_service;
+pub mod discovery_service;
+pub mod dot_service;
+pub mod drawio_service;
+pub mod eps_service;
+pub mod excalidraw_service;
 pub mod file_service;
+pub mod flowchart_service;
+pub mod gantt_service;
+pub mod git_service;
+pub mod html_service;
+pub mod installer_service;
+pub mod kroki_service;
+pub mod layout_service;
+pub mod mmdc_service;
+pub mod native_service;
+pub mod parser_service;
+pub mod pdf_service;
+pub mod pie_service;
+pub mod plantuml_service;
+pub mod raster_service;
+pub mod renderer_service;
+pub mod sql_service;
+pub mod stats_service;
+pub mod svg_service;
+pub mod text_service;
+pub mod theme_service;
+pub mod vector_service;
+pub mod worker_service;
 
 // Re-export services
+pub use file_service::FileService;
+pub use git_service::GitService;
 pub use mmdc_service::MmdcService;
-pub use file_service::FileService;
\ No newline at end of file
+pub use parser_service::{parse_diagram, write_diagram};
+pub use renderer_service::RenderService;
@@ -0,0 +1,321 @@
+use crate::models::{DiagramFormat, RendererBackend, RendererCapabilities};
+use crate::services::config_service::init_directive_values;
+use crate::services::flowchart_service;
+use crate::services::gantt_service;
+use crate::services::mmdc_service::mermaid_config;
+use crate::services::pie_service;
+use crate::services::raster_service::parse_svg;
+use crate::services::renderer_service::{apply_mmdc_styles, RenderError, RenderJob, Renderer};
+use crate::utils::merge_json;
+use serde_json::Value;
+
+/// Diagram types the native renderer draws without mermaid
+pub const NATIVE_DIAGRAM_TYPES: &[&str] = &["pie", "flowchart", "gantt"];
+
+/// Mermaid's default font stack
+pub const FONT_FAMILY: &str = "\"trebuchet ms\", verdana, arial, sans-serif";
+
+/// Renders simple diagram types in Rust, with no Node or browser involved
+pub struct NativeRenderer;
+
+impl Renderer for NativeRenderer {
+    fn backend(&self) -> RendererBackend {
+        RendererBackend::Native
+    }
+
+    fn capabilities(&self) -> RendererCapabilities {
+        RendererCapabilities {
+            formats: vec![DiagramFormat::Svg],
+            diagram_types: Some(NATIVE_DIAGRAM_TYPES.iter().map(|t| t.to_string()).collect()),
+        }
+    }
+
+    fn render(&mut self, job: &RenderJob) -> Result<Vec<u8>, RenderError> {
+        let config = native_config(job).map_err(RenderError::Failed)?;
+        let theme = ThemeVariables::from_config(&config).map_err(RenderError::Unsupported)?;
+        let svg_id = job.options.svg_id.as_deref().unwrap_or("my-svg");
+
+        let svg = match job.diagram_type {
+            Some("pie") => pie_service::render_pie(job.code, &config, &theme, svg_id)?,
+            Some("flowchart") => {
+                flowchart_service::render_flowchart(job.code, &config, &theme, svg_id)?
+            }
+            Some("gantt") => gantt_service::render_gantt(job.code, &config, &theme, svg_id)?,
+            other => {
+                return Err(RenderError::Unsupported(format!(
+                    "{} diagrams have no native renderer",
+                    other.unwrap_or("unknown")
+                )))
+            }
+        };
+        let svg = apply_mmdc_styles(&svg, job).map_err(RenderError::Failed)?;
+        Ok(svg.into_bytes())
+    }
+}
+
+/// Theme colours and fonts, as mermaid's `themeVariables` would resolve them
+#[derive(Debug, Clone)]
+pub struct ThemeVariables {
+    pub font_family: String,
+    pub text_color: String,
+    pub pie_colors: [String; 12],
+    pub pie_title_text_color: String,
+    pub pie_section_text_color: String,
+    pub pie_legend_text_color: String,
+    pub pie_stroke_color: String,
+    pub pie_outer_stroke_color: String,
+    pub pie_opacity: String,
+    pub main_bkg: String,
+    pub node_border: String,
+    pub node_text_color: String,
+    pub line_color: String,
+    pub cluster_bkg: String,
+    pub cluster_border: String,
+    pub title_color: String,
+    pub edge_label_background: String,
+    pub section_bkg_color: String,
+    pub alt_section_bkg_color: String,
+    pub section_bkg_color2: String,
+    pub exclude_bkg_color: String,
+    pub task_bkg_color: String,
+    pub task_border_color: String,
+    pub task_text_color: String,
+    pub task_text_outside_color: String,
+    pub task_text_dark_color: String,
+    pub active_task_bkg_color: String,
+    pub active_task_border_color: String,
+    pub done_task_bkg_color: String,
+    pub done_task_border_color: String,
+    pub crit_bkg_color: String,
+    pub crit_border_color: String,
+    pub grid_color: String,
+    pub today_line_color: String,
+}
+
+impl ThemeVariables {
+    /// Variables of the `default` or `dark` theme with the config's
+    /// `themeVariables` applied; other themes are not supported natively
+    pub fn from_config(config: &Value) -> Result<Self, String> {
+        let mut theme = match config["theme"].as_str().unwrap_or("default") {
+            "default" | "null" => Self::default_theme(),
+            "dark" => Self::dark_theme(),
+            other => return Err(format!("The {} theme has no native renderer", other)),
+        };
+
+        let variables = &config["themeVariables"];
+        let set = |target: &mut String, name: &str| {
+            if let Some(value) = variables[name].as_str() {
+                *target = value.to_string();
+            }
+        };
+        set(&mut theme.font_family, "fontFamily");
+        set(&mut theme.text_color, "textColor");
+        for (index, color) in theme.pie_colors.iter_mut().enumerate() {
+            set(color, &format!("pie{}", index + 1));
+        }
+        set(&mut theme.pie_title_text_color, "pieTitleTextColor");
+        set(&mut theme.pie_section_text_color, "pieSectionTextColor");
+        set(&mut theme.pie_legend_text_color, "pieLegendTextColor");
+        set(&mut theme.pie_stroke_color, "pieStrokeColor");
+        set(&mut theme.pie_outer_stroke_color, "pieOuterStrokeColor");
+        set(&mut theme.pie_opacity, "pieOpacity");
+        // Derived from the primary colours unless set themselves
+        set(&mut theme.main_bkg, "primaryColor");
+        set(&mut theme.node_border, "primaryBorderColor");
+        set(&mut theme.node_text_color, "primaryTextColor");
+        set(&mut theme.main_bkg, "mainBkg");
+        set(&mut theme.node_border, "nodeBorder");
+        set(&mut theme.node_text_color, "nodeTextColor");
+        set(&mut theme.line_color, "lineColor");
+        set(&mut theme.cluster_bkg, "clusterBkg");
+        set(&mut theme.cluster_border, "clusterBorder");
+        set(&mut theme.title_color, "titleColor");
+        set(&mut theme.edge_label_background, "edgeLabelBackground");
+        set(&mut theme.section_bkg_color, "sectionBkgColor");
+        set(&mut theme.alt_section_bkg_color, "altSectionBkgColor");
+        set(&mut theme.section_bkg_color2, "sectionBkgColor2");
+        set(&mut theme.exclude_bkg_color, "excludeBkgColor");
+        set(&mut theme.task_bkg_color, "taskBkgColor");
+        set(&mut theme.task_border_color, "taskBorderColor");
+        set(&mut theme.task_text_color, "taskTextColor");
+        set(&mut theme.task_text_outside_color, "taskTextOutsideColor");
+        set(&mut theme.task_text_dark_color, "taskTextDarkColor");
+        set(&mut theme.active_task_bkg_color, "activeTaskBkgColor");
+        set(&mut theme.active_task_border_color, "activeTaskBorderColor");
+        set(&mut theme.done_task_bkg_color, "doneTaskBkgColor");
+        set(&mut theme.done_task_border_color, "doneTaskBorderColor");
+        set(&mut theme.crit_bkg_color, "critBkgColor");
+        set(&mut theme.crit_border_color, "critBorderColor");
+        set(&mut theme.grid_color, "gridColor");
+        set(&mut theme.today_line_color, "todayLineColor");
+        Ok(theme)
+    }
+
+    /// Mermaid's default theme. The pie colours are its primary, secondary
+    /// and tertiary colours with the theme's hue and lightness adjustments.
+    fn default_theme() -> Self {
+        Self {
+            font_family: FONT_FAMILY.to_string(),
+            text_color: "#333".to_string(),
+            pie_colors: [
+                "#ececff", "#ffffde", "#b5ff20", "#b9b9ff", "#ffff45", "#d7ff86", "#ff86ff",
+                "#20ffff", "#ff2020", "#ff20ff", "#20ff90", "#ff5353",
+            ]
+            .map(String::from),
+            pie_title_text_color: "black".to_string(),
+            pie_section_text_color: "#333".to_string(),
+            pie_legend_text_color: "black".to_string(),
+            pie_stroke_color: "black".to_string(),
+            pie_outer_stroke_color: "black".to_string(),
+            pie_opacity: "0.7".to_string(),
+            main_bkg: "#ECECFF".to_string(),
+            node_border: "#9370DB".to_string(),
+            node_text_color: "#333".to_string(),
+            line_color: "#333333".to_string(),
+            cluster_bkg: "#ffffde".to_string(),
+            cluster_border: "#aaaa33".to_string(),
+            title_color: "#333".to_string(),
+            edge_label_background: "#e8e8e8".to_string(),
+            section_bkg_color: "rgba(102, 102, 255, 0.49)".to_string(),
+            alt_section_bkg_color: "white".to_string(),
+            section_bkg_color2: "#fff400".to_string(),
+            exclude_bkg_color: "#eeeeee".to_string(),
+            task_bkg_color: "#8a90dd".to_string(),
+            task_border_color: "#534fbc".to_string(),
+            task_text_color: "white".to_string(),
+            task_text_outside_color: "black".to_string(),
+            task_text_dark_color: "black".to_string(),
+            active_task_bkg_color: "#bfc7ff".to_string(),
+            active_task_border_color: "#534fbc".to_string(),
+            done_task_bkg_color: "lightgrey".to_string(),
+            done_task_border_color: "grey".to_string(),
+            crit_bkg_color: "red".to_string(),
+            crit_border_color: "#ff8888".to_string(),
+            grid_color: "lightgrey".to_string(),
+            today_line_color: "red".to_string(),
+        }
+    }
+
+    /// Mermaid's dark theme. Its pie colours are the dark primary colour and
+    /// grey variations of it.
+    fn dark_theme() -> Self {
+        Self {
+            font_family: FONT_FAMILY.to_string(),
+            text_color: "#ccc".to_string(),
+            pie_colors: [
+                "#1f2020", "#474949", "#878584", "#383a3a", "#939696", "#545251", "#515154",
+                "#848784", "#878487", "#848487", "#868784", "#6e6a6e",
+            ]
+            .map(String::from),
+            pie_title_text_color: "lightgrey".to_string(),
+            pie_section_text_color: "#ccc".to_string(),
+            pie_legend_text_color: "lightgrey".to_string(),
+            pie_stroke_color: "black".to_string(),
+            pie_outer_stroke_color: "black".to_string(),
+            pie_opacity: "0.7".to_string(),
+            main_bkg: "#1f2020".to_string(),
+            node_border: "#ccc".to_string(),
+            node_text_color: "#ccc".to_string(),
+            line_color: "lightgrey".to_string(),
+            cluster_bkg: "#474949".to_string(),
+            cluster_border: "rgba(255, 255, 255, 0.25)".to_string(),
+            title_color: "#F9FFFE".to_string(),
+            edge_label_background: "#585858".to_string(),
+            section_bkg_color: "#b4ad76".to_string(),
+            alt_section_bkg_color: "white".to_string(),
+            section_bkg_color2: "#EAE8D9".to_string(),
+            exclude_bkg_color: "#9f9758".to_string(),
+            task_bkg_color: "#1f2020".to_string(),
+            task_border_color: "#ffffff".to_string(),
+            task_text_color: "#e2dcd6".to_string(),
+            task_text_outside_color: "lightgrey".to_string(),
+            task_text_dark_color: "#e2dcd6".to_string(),
+            active_task_bkg_color: "#1f2020".to_string(),
+            active_task_border_color: "#ffffff".to_string(),
+            done_task_bkg_color: "lightgrey".to_string(),
+            done_task_border_color: "lightgrey".to_string(),
+            crit_bkg_color: "#E83737".to_string(),
+            crit_border_color: "#E83737".to_string(),
+            grid_color: "lightgrey".to_string(),
+            today_line_color: "#DB5757".to_string(),
+        }
+    }
+}
+
+/// The mermaid config for a job: the options' config with any
+/// `%%{init: ...}%%` directives in the source merged over it
+pub fn native_config(job: &RenderJob) -> Result<Value, String> {
+    let mut config = mermaid_config(job.options, job.extra_config)?;
+    if let Some(overlay) = init_directive_values(job.code)? {
+        merge_json(&mut config, &overlay);
+    }
+    Ok(config)
+}
+
+/// Width of `text` as rendered in `font_family`, using the fonts available
+/// for rasterization; a per-character estimate when none match
+pub fn text_width(text: &str, font_size: f64, font_family: &str) -> f64 {
+    if text.trim().is_empty() {
+        return 0.0;
+    }
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"><text font-family="{}" font-size="{}">{}</text></svg>"#,
+        escape_xml(font_family),
+        font_size,
+        escape_xml(text)
+    );
+    let measured = parse_svg(&svg)
+        .map(|tree| tree.root().abs_bounding_box().width() as f64)
+        .unwrap_or_default();
+    if measured > 0.0 {
+        measured
+    } else {
+        text.chars().count() as f64 * font_size * 0.55
+    }
+}
+
+/// Escape text for use in SVG content and attribute values
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Format a coordinate without trailing zeros, like d3 does
+pub fn num(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DiagramOptions;
+    use serde_json::json;
+
+    #[test]
+    fn test_theme_from_config_and_directive() {
+        let options = DiagramOptions::svg(Some("default".to_string()));
+        let job = RenderJob {
+            code: "%%{init: {'theme': 'dark', 'themeVariables': {'pie1': '#ff0000'}}}%%\n%%{init: {\n  'themeVariables': {'fontFamily': \"Gill Sans, 'Trebuchet MS'\"}\n}}%%\npie\n",
+            diagram_type: Some("pie"),
+            format: DiagramFormat::Svg,
+            options: &options,
+            extra_config: None,
+        };
+        let config = native_config(&job).unwrap();
+        let theme = ThemeVariables::from_config(&config).unwrap();
+        assert_eq!(theme.pie_colors[0], "#ff0000");
+        assert_eq!(theme.pie_colors[1], "#474949");
+        assert_eq!(theme.font_family, "Gill Sans, 'Trebuchet MS'");
+
+        assert!(ThemeVariables::from_config(&json!({"theme": "forest"})).is_err());
+        assert_eq!(num(12.5000), "12.5");
+        assert_eq!(num(-0.0001), "0");
+    }
+}
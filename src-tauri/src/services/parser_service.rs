@@ -0,0 +1,1510 @@
+use crate::models::{
+    ArrowHead, ClassDef, ClassNode, DiagramKind, Edge, EdgeStroke, Message, Node, NodeShape, Note,
+    NotePlacement, ParsedDiagram, Participant, SequenceStep, Subgraph,
+};
+
+/// Parse mermaid source into its structural elements.
+///
+/// Only flowchart, sequence, class and state diagrams are understood; any
+/// other diagram type is returned with `DiagramKind::Unknown` and no elements.
+pub fn parse_diagram(code: &str) -> Result<ParsedDiagram, String> {
+    let (title, lines) = strip_front_matter(code);
+    let (acc_title, acc_descr, lines) = strip_accessibility(lines);
+
+    let mut lines = lines.into_iter().filter(|line| !is_comment_or_blank(line));
+    let header = lines
+        .next()
+        .ok_or_else(|| "Empty mermaid code provided".to_string())?;
+
+    let mut header_parts = header.split_whitespace();
+    let keyword = header_parts.next().unwrap_or_default();
+    let direction = header_parts
+        .next()
+        .map(|d| d.trim_end_matches(';').to_string());
+    let body: Vec<&str> = lines.collect();
+
+    let mut diagram = match keyword {
+        "flowchart" | "flowchart-elk" | "graph" => {
+            let mut diagram = parse_flowchart(&body);
+            diagram.direction = direction.or_else(|| Some("TD".to_string()));
+            diagram
+        }
+        "sequenceDiagram" => parse_sequence(&body),
+        "classDiagram" | "classDiagram-v2" => parse_class(&body),
+        "stateDiagram" | "stateDiagram-v2" => parse_state(&body),
+        _ => ParsedDiagram::new(DiagramKind::Unknown),
+    };
+
+    if diagram.title.is_none() {
+        diagram.title = title;
+    }
+    diagram.acc_title = acc_title;
+    diagram.acc_descr = acc_descr;
+
+    Ok(diagram)
+}
+
+/// Serialize a parsed diagram back into mermaid source
+pub fn write_diagram(diagram: &ParsedDiagram) -> Result<String, String> {
+    match diagram.kind {
+        DiagramKind::Flowchart => Ok(write_flowchart(diagram)),
+        DiagramKind::Sequence => Ok(write_sequence(diagram)),
+        DiagramKind::Class => Ok(write_class(diagram)),
+        kind => Err(format!("Writing {} diagrams is not supported", kind)),
+    }
+}
+
+/// Split off a `---` front matter block, returning its title and the remaining lines
+pub(crate) fn strip_front_matter(code: &str) -> (Option<String>, Vec<&str>) {
+    let lines: Vec<&str> = code.lines().collect();
+    let first = lines.iter().position(|l| !l.trim().is_empty());
+
+    if let Some(start) = first {
+        if lines[start].trim() == "---" {
+            if let Some(len) = lines[start + 1..].iter().position(|l| l.trim() == "---") {
+                let end = start + 1 + len;
+                let title = lines[start + 1..end]
+                    .iter()
+                    .find_map(|l| l.trim().strip_prefix("title:"))
+                    .map(|t| unquote(t.trim()).to_string());
+                return (title, lines[end + 1..].to_vec());
+            }
+        }
+    }
+
+    (None, lines)
+}
+
+/// Take `accTitle` and `accDescr` statements out of the body, including
+/// multi-line `accDescr { ... }` blocks
+pub(crate) fn strip_accessibility(lines: Vec<&str>) -> (Option<String>, Option<String>, Vec<&str>) {
+    let mut acc_title = None;
+    let mut acc_descr = None;
+    let mut body = Vec::with_capacity(lines.len());
+    let mut block: Option<Vec<&str>> = None;
+
+    for line in lines {
+        let trimmed = line.trim();
+
+        if let Some(ref mut block_lines) = block {
+            match trimmed.strip_suffix('}') {
+                Some(last) => {
+                    block_lines.push(last.trim());
+                    let text = block_lines
+                        .iter()
+                        .filter(|l| !l.is_empty())
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    acc_descr = Some(text);
+                    block = None;
+                }
+                None => block_lines.push(trimmed),
+            }
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("accTitle") {
+            if let Some(text) = rest.trim_start().strip_prefix(':') {
+                acc_title = Some(text.trim().to_string());
+                continue;
+            }
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("accDescr") {
+            let rest = rest.trim_start();
+            if let Some(text) = rest.strip_prefix(':') {
+                acc_descr = Some(text.trim().to_string());
+                continue;
+            }
+            if let Some(text) = rest.strip_prefix('{') {
+                match text.trim().strip_suffix('}') {
+                    Some(single) => acc_descr = Some(single.trim().to_string()),
+                    None => block = Some(vec![text.trim()]),
+                }
+                continue;
+            }
+        }
+
+        body.push(line);
+    }
+
+    (acc_title, acc_descr, body)
+}
+
+pub(crate) fn is_comment_or_blank(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with("%%")
+}
+
+pub(crate) fn unquote(text: &str) -> &str {
+    let text = text.trim();
+    if text.len() >= 2
+        && ((text.starts_with('"') && text.ends_with('"'))
+            || (text.starts_with('`') && text.ends_with('`')))
+    {
+        &text[1..text.len() - 1]
+    } else {
+        text
+    }
+}
+
+/// Split a line on `;` separators that are not inside quotes or brackets
+fn split_statements(line: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+    let mut in_quotes = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '[' | '(' | '{' if !in_quotes => depth += 1,
+            ']' | ')' | '}' if !in_quotes => depth -= 1,
+            ';' if !in_quotes && depth <= 0 => {
+                statements.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    statements.push(current);
+
+    statements
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn first_word(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Flowchart
+// ---------------------------------------------------------------------------
+
+struct FlowchartBuilder {
+    diagram: ParsedDiagram,
+    subgraph_stack: Vec<String>,
+}
+
+impl FlowchartBuilder {
+    fn add_node(&mut self, id: &str, label: Option<String>, shape: Option<NodeShape>) -> usize {
+        let index = match self.diagram.nodes.iter().position(|n| n.id == id) {
+            Some(index) => index,
+            None => {
+                self.diagram.nodes.push(Node::new(id));
+                if let Some(parent) = self.subgraph_stack.last() {
+                    let parent = parent.clone();
+                    if let Some(subgraph) =
+                        self.diagram.subgraphs.iter_mut().find(|s| s.id == parent)
+                    {
+                        subgraph.nodes.push(id.to_string());
+                    }
+                }
+                self.diagram.nodes.len() - 1
+            }
+        };
+
+        let node = &mut self.diagram.nodes[index];
+        if label.is_some() {
+            node.label = label;
+        }
+        if let Some(shape) = shape {
+            node.shape = shape;
+        }
+        index
+    }
+
+    fn node_mut(&mut self, id: &str) -> &mut Node {
+        let index = self.add_node(id, None, None);
+        &mut self.diagram.nodes[index]
+    }
+
+    fn add_class(&mut self, id: &str, class: &str) {
+        let node = self.node_mut(id);
+        if !node.classes.iter().any(|c| c == class) {
+            node.classes.push(class.to_string());
+        }
+    }
+
+    /// `classDef`, `class` and `style` statements
+    fn styling(&mut self, keyword: &str, rest: &str) {
+        let rest = rest.trim().trim_end_matches(';');
+        let (target, styles) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let declarations: Vec<String> = styles
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        match keyword {
+            "classDef" => {
+                for name in target.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                    self.diagram.class_defs.push(ClassDef {
+                        name: name.to_string(),
+                        styles: declarations.clone(),
+                    });
+                }
+            }
+            "class" => {
+                let class = styles.trim();
+                if !class.is_empty() {
+                    for id in target.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                        self.add_class(id, class);
+                    }
+                }
+            }
+            _ => self.node_mut(target).styles.extend(declarations),
+        }
+    }
+
+    fn open_subgraph(&mut self, rest: &str) {
+        let rest = rest.trim();
+        let (id, label) = match rest.find('[') {
+            Some(open) if rest.ends_with(']') => (
+                rest[..open].trim().to_string(),
+                Some(unquote(&rest[open + 1..rest.len() - 1]).to_string()),
+            ),
+            _ => {
+                let title = unquote(rest).to_string();
+                if title.contains(char::is_whitespace) || rest.starts_with('"') {
+                    (title.clone(), Some(title))
+                } else {
+                    (title, None)
+                }
+            }
+        };
+
+        let id = if id.is_empty() {
+            format!("subgraph{}", self.diagram.subgraphs.len())
+        } else {
+            id
+        };
+
+        self.diagram.subgraphs.push(Subgraph {
+            id: id.clone(),
+            label,
+            parent: self.subgraph_stack.last().cloned(),
+            direction: None,
+            nodes: Vec::new(),
+        });
+        self.subgraph_stack.push(id);
+    }
+
+    fn statement(&mut self, statement: &str) {
+        match first_word(statement) {
+            "subgraph" => self.open_subgraph(&statement["subgraph".len()..]),
+            "end" => {
+                self.subgraph_stack.pop();
+            }
+            "direction" => {
+                let direction = statement["direction".len()..].trim().to_string();
+                match self.subgraph_stack.last() {
+                    Some(current) => {
+                        let current = current.clone();
+                        if let Some(subgraph) =
+                            self.diagram.subgraphs.iter_mut().find(|s| s.id == current)
+                        {
+                            subgraph.direction = Some(direction);
+                        }
+                    }
+                    None => self.diagram.direction = Some(direction),
+                }
+            }
+            keyword @ ("classDef" | "class" | "style") => {
+                self.styling(keyword, &statement[keyword.len()..])
+            }
+            "linkStyle" | "click" | "accTitle:" | "accDescr:" | "accDescr" | "title" => {}
+            _ => self.chain(statement),
+        }
+    }
+
+    /// Parse `A --> B & C -- text --> D` style statements
+    fn chain(&mut self, statement: &str) {
+        let chars: Vec<char> = statement.chars().collect();
+        let mut pos = 0;
+
+        let Some((mut previous, next)) = self.node_group(&chars, pos) else {
+            return;
+        };
+        pos = next;
+
+        while let Some((link, next)) = parse_link(&chars, pos) {
+            let Some((targets, next)) = self.node_group(&chars, next) else {
+                break;
+            };
+            pos = next;
+
+            for from in &previous {
+                for to in &targets {
+                    self.diagram.edges.push(Edge {
+                        from: from.clone(),
+                        to: to.clone(),
+                        label: link.label.clone(),
+                        arrow: link.arrow.clone(),
+                        stroke: link.stroke,
+                        head: link.head,
+                        tail: link.tail,
+                    });
+                }
+            }
+            previous = targets;
+        }
+    }
+
+    fn node_group(&mut self, chars: &[char], mut pos: usize) -> Option<(Vec<String>, usize)> {
+        let mut ids = Vec::new();
+        loop {
+            let (id, label, shape, class, next) = parse_node(chars, skip_whitespace(chars, pos))?;
+            self.add_node(&id, label, shape);
+            if let Some(class) = class {
+                self.add_class(&id, &class);
+            }
+            ids.push(id);
+            pos = skip_whitespace(chars, next);
+            if chars.get(pos) == Some(&'&') {
+                pos += 1;
+            } else {
+                return Some((ids, pos));
+            }
+        }
+    }
+
+    fn finish(mut self) -> ParsedDiagram {
+        // Subgraph ids used as edge endpoints are not nodes of their own
+        let subgraph_ids: Vec<String> = self
+            .diagram
+            .subgraphs
+            .iter()
+            .map(|s| s.id.clone())
+            .collect();
+        self.diagram.nodes.retain(|n| !subgraph_ids.contains(&n.id));
+        for subgraph in &mut self.diagram.subgraphs {
+            subgraph.nodes.retain(|n| !subgraph_ids.contains(n));
+        }
+        self.diagram
+    }
+}
+
+fn parse_flowchart(lines: &[&str]) -> ParsedDiagram {
+    let mut builder = FlowchartBuilder {
+        diagram: ParsedDiagram::new(DiagramKind::Flowchart),
+        subgraph_stack: Vec::new(),
+    };
+
+    for line in lines {
+        for statement in split_statements(line) {
+            builder.statement(&statement);
+        }
+    }
+
+    builder.finish()
+}
+
+struct Link {
+    arrow: String,
+    label: Option<String>,
+    stroke: EdgeStroke,
+    head: ArrowHead,
+    tail: ArrowHead,
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || !c.is_ascii()
+}
+
+fn is_link_char(c: char) -> bool {
+    matches!(c, '-' | '=' | '.' | '~')
+}
+
+fn skip_whitespace(chars: &[char], mut pos: usize) -> usize {
+    while pos < chars.len() && chars[pos].is_whitespace() {
+        pos += 1;
+    }
+    pos
+}
+
+fn starts_with_at(chars: &[char], pos: usize, pattern: &str) -> bool {
+    pattern
+        .chars()
+        .enumerate()
+        .all(|(offset, p)| chars.get(pos + offset) == Some(&p))
+}
+
+fn find_at(chars: &[char], from: usize, pattern: &str) -> Option<usize> {
+    (from..chars.len()).find(|&i| starts_with_at(chars, i, pattern))
+}
+
+/// Id, label, shape and `:::` class of a node reference, and the position
+/// after it
+type NodeRef = (
+    String,
+    Option<String>,
+    Option<NodeShape>,
+    Option<String>,
+    usize,
+);
+
+/// Parse a node reference with an optional shape, e.g. `A`, `B[Label]` or `C{{"Hex"}}`
+fn parse_node(chars: &[char], pos: usize) -> Option<NodeRef> {
+    let start = pos;
+    let mut pos = pos;
+    while pos < chars.len() {
+        let c = chars[pos];
+        let next = chars.get(pos + 1).copied().unwrap_or(' ');
+        if is_id_char(c) || (c == '-' && is_id_char(next)) {
+            pos += 1;
+        } else {
+            break;
+        }
+    }
+
+    if pos == start {
+        return None;
+    }
+    let id: String = chars[start..pos].iter().collect();
+
+    const OPENERS: [(&str, &[(&str, NodeShape)]); 12] = [
+        ("(((", &[(")))", NodeShape::DoubleCircle)]),
+        ("((", &[("))", NodeShape::Circle)]),
+        ("([", &[("])", NodeShape::Stadium)]),
+        ("(", &[(")", NodeShape::Round)]),
+        ("[[", &[("]]", NodeShape::Subroutine)]),
+        ("[(", &[(")]", NodeShape::Cylinder)]),
+        (
+            "[/",
+            &[
+                ("/]", NodeShape::Parallelogram),
+                ("\\]", NodeShape::Trapezoid),
+            ],
+        ),
+        (
+            "[\\",
+            &[
+                ("\\]", NodeShape::ParallelogramAlt),
+                ("/]", NodeShape::TrapezoidAlt),
+            ],
+        ),
+        ("[", &[("]", NodeShape::Rect)]),
+        ("{{", &[("}}", NodeShape::Hexagon)]),
+        ("{", &[("}", NodeShape::Rhombus)]),
+        (">", &[("]", NodeShape::Asymmetric)]),
+    ];
+
+    let mut label = None;
+    let mut shape = None;
+
+    if let Some((open, closers)) = OPENERS
+        .iter()
+        .find(|(open, _)| starts_with_at(chars, pos, open))
+    {
+        let content_start = pos + open.chars().count();
+        // Quoted labels may contain the closing delimiter
+        let search_from = if chars.get(skip_whitespace(chars, content_start)) == Some(&'"') {
+            let quote = skip_whitespace(chars, content_start);
+            find_at(chars, quote + 1, "\"")
+                .map(|q| q + 1)
+                .unwrap_or(content_start)
+        } else {
+            content_start
+        };
+
+        let closing = closers
+            .iter()
+            .filter_map(|(close, shape)| {
+                find_at(chars, search_from, close).map(|i| (i, *close, *shape))
+            })
+            .min_by_key(|(i, _, _)| *i);
+
+        if let Some((end, close, found_shape)) = closing {
+            let text: String = chars[content_start..end].iter().collect();
+            label = Some(unquote(&text).to_string());
+            shape = Some(found_shape);
+            pos = end + close.chars().count();
+        }
+    }
+
+    // `:::className` shorthand
+    let mut class = None;
+    if starts_with_at(chars, pos, ":::") {
+        pos += 3;
+        let class_start = pos;
+        while pos < chars.len() && (is_id_char(chars[pos]) || chars[pos] == '-') {
+            pos += 1;
+        }
+        class = Some(chars[class_start..pos].iter().collect::<String>()).filter(|c| !c.is_empty());
+    }
+
+    Some((id, label, shape, class, pos))
+}
+
+fn head_at(chars: &[char], pos: usize) -> Option<ArrowHead> {
+    let after = chars.get(pos + 1).copied().unwrap_or(' ');
+    match chars.get(pos) {
+        Some('>') => Some(ArrowHead::Arrow),
+        Some('o') if !is_id_char(after) => Some(ArrowHead::Circle),
+        Some('x') if !is_id_char(after) => Some(ArrowHead::Cross),
+        _ => None,
+    }
+}
+
+fn stroke_of(body: &str) -> EdgeStroke {
+    if body.contains('~') {
+        EdgeStroke::Invisible
+    } else if body.contains('=') {
+        EdgeStroke::Thick
+    } else if body.contains('.') {
+        EdgeStroke::Dotted
+    } else {
+        EdgeStroke::Normal
+    }
+}
+
+/// Build the shortest arrow that mermaid draws with the given style
+pub fn canonical_arrow(stroke: EdgeStroke, head: ArrowHead, tail: ArrowHead) -> String {
+    let body = match (stroke, head) {
+        (EdgeStroke::Invisible, _) => return "~~~".to_string(),
+        (EdgeStroke::Normal, ArrowHead::None) => "---",
+        (EdgeStroke::Normal, _) => "--",
+        (EdgeStroke::Dotted, _) => "-.-",
+        (EdgeStroke::Thick, ArrowHead::None) => "===",
+        (EdgeStroke::Thick, _) => "==",
+    };
+    let marker = |head: ArrowHead, arrow: &'static str| match head {
+        ArrowHead::None => "",
+        ArrowHead::Arrow => arrow,
+        ArrowHead::Circle => "o",
+        ArrowHead::Cross => "x",
+    };
+    format!("{}{}{}", marker(tail, "<"), body, marker(head, ">"))
+}
+
+/// Parse a link such as `-->`, `-.->|label|`, `-- label -->` or `<==>`
+fn parse_link(chars: &[char], pos: usize) -> Option<(Link, usize)> {
+    let mut pos = skip_whitespace(chars, pos);
+    let start = pos;
+
+    let mut tail = ArrowHead::None;
+    match chars.get(pos) {
+        Some('<') => {
+            tail = ArrowHead::Arrow;
+            pos += 1;
+        }
+        Some('o') | Some('x')
+            if chars.get(pos + 1).is_some_and(|c| is_link_char(*c))
+                && chars.get(pos + 2).is_some_and(|c| is_link_char(*c)) =>
+        {
+            tail = if chars[pos] == 'o' {
+                ArrowHead::Circle
+            } else {
+                ArrowHead::Cross
+            };
+            pos += 1;
+        }
+        _ => {}
+    }
+
+    let body_start = pos;
+    while pos < chars.len() && is_link_char(chars[pos]) {
+        pos += 1;
+    }
+    let body: String = chars[body_start..pos].iter().collect();
+    if body.len() < 2 {
+        return None;
+    }
+
+    let mut stroke = stroke_of(&body);
+    let mut head = head_at(chars, pos);
+    let mut label = None;
+    let arrow;
+
+    if head.is_none() && body.len() == 2 && body != "~~" {
+        // `-- text -->` form: the label sits between an opening and a closing arrow
+        let text_start = pos;
+        let mut i = pos;
+        let mut closing = None;
+        while i < chars.len() {
+            if is_link_char(chars[i]) {
+                let run_start = i;
+                while i < chars.len() && is_link_char(chars[i]) {
+                    i += 1;
+                }
+                if i - run_start >= 2 {
+                    closing = Some((run_start, i));
+                    break;
+                }
+            } else {
+                i += 1;
+            }
+        }
+        let (run_start, run_end) = closing?;
+        label = Some(unquote(&chars[text_start..run_start].iter().collect::<String>()).to_string());
+        stroke = stroke_of(&body);
+        head = head_at(chars, run_end);
+        pos = run_end + usize::from(head.is_some());
+        arrow = canonical_arrow(stroke, head.unwrap_or(ArrowHead::None), tail);
+    } else {
+        pos += usize::from(head.is_some());
+        arrow = chars[start..pos].iter().collect();
+    }
+
+    // `-->|label|` form
+    let after = skip_whitespace(chars, pos);
+    if chars.get(after) == Some(&'|') {
+        if let Some(end) = find_at(chars, after + 1, "|") {
+            label = Some(unquote(&chars[after + 1..end].iter().collect::<String>()).to_string());
+            pos = end + 1;
+        }
+    }
+
+    Some((
+        Link {
+            arrow,
+            label: label.filter(|l| !l.is_empty()),
+            stroke,
+            head: head.unwrap_or(ArrowHead::None),
+            tail,
+        },
+        pos,
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Sequence diagram
+// ---------------------------------------------------------------------------
+
+const SEQUENCE_ARROWS: [&str; 10] = [
+    "<<-->>", "<<->>", "-->>", "->>", "-->", "->", "--x", "-x", "--)", "-)",
+];
+
+const SEQUENCE_KEYWORDS: [&str; 20] = [
+    "loop",
+    "alt",
+    "else",
+    "opt",
+    "par",
+    "and",
+    "critical",
+    "option",
+    "break",
+    "rect",
+    "end",
+    "activate",
+    "deactivate",
+    "autonumber",
+    "box",
+    "destroy",
+    "link",
+    "links",
+    "Note",
+    "note",
+];
+
+fn add_participant(diagram: &mut ParsedDiagram, id: &str, label: Option<String>, is_actor: bool) {
+    if let Some(existing) = diagram.participants.iter_mut().find(|p| p.id == id) {
+        if label.is_some() {
+            existing.label = label;
+        }
+        existing.is_actor |= is_actor;
+    } else {
+        diagram.participants.push(Participant {
+            id: id.to_string(),
+            label,
+            is_actor,
+        });
+    }
+}
+
+/// Parse `right of A: text` or `over A,B: text` following `Note`
+fn parse_note(rest: &str) -> Option<Note> {
+    let (target, text) = rest.split_once(':')?;
+    let target = target.trim();
+    let (placement, ids) = [
+        ("left of ", NotePlacement::LeftOf),
+        ("right of ", NotePlacement::RightOf),
+        ("over ", NotePlacement::Over),
+    ]
+    .into_iter()
+    .find_map(|(prefix, placement)| {
+        let head = target.get(..prefix.len())?;
+        head.eq_ignore_ascii_case(prefix)
+            .then(|| (placement, &target[prefix.len()..]))
+    })?;
+    let participants: Vec<String> = ids
+        .split(',')
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect();
+    if participants.is_empty() {
+        return None;
+    }
+    Some(Note {
+        placement,
+        participants,
+        text: text.trim().to_string(),
+    })
+}
+
+fn parse_sequence(lines: &[&str]) -> ParsedDiagram {
+    let mut diagram = ParsedDiagram::new(DiagramKind::Sequence);
+    // Whether each open `end`-terminated statement is a participant `box`
+    let mut open_boxes = Vec::new();
+
+    for line in lines {
+        let mut line = line.trim();
+        if let Some(rest) = line.strip_prefix("create ") {
+            line = rest.trim();
+        }
+
+        let keyword = first_word(line);
+        if keyword == "participant" || keyword == "actor" {
+            let rest = line[keyword.len()..].trim();
+            let (id, label) = match rest.split_once(" as ") {
+                Some((id, label)) => (id.trim(), Some(label.trim().to_string())),
+                None => (rest, None),
+            };
+            add_participant(&mut diagram, id, label, keyword == "actor");
+            continue;
+        }
+
+        if keyword == "title" || keyword == "title:" {
+            diagram.title = Some(line[keyword.len()..].trim().to_string());
+            continue;
+        }
+
+        let rest = line[keyword.len()..].trim();
+        match keyword {
+            "loop" | "alt" | "opt" | "par" | "critical" | "break" | "rect" => {
+                open_boxes.push(false);
+                diagram.steps.push(SequenceStep::BlockStart {
+                    keyword: keyword.to_string(),
+                    label: rest.to_string(),
+                });
+                continue;
+            }
+            "box" => {
+                open_boxes.push(true);
+                continue;
+            }
+            "else" | "and" | "option" => {
+                diagram.steps.push(SequenceStep::BlockSection {
+                    keyword: keyword.to_string(),
+                    label: rest.to_string(),
+                });
+                continue;
+            }
+            "end" => {
+                if open_boxes.pop() == Some(false) {
+                    diagram.steps.push(SequenceStep::BlockEnd);
+                }
+                continue;
+            }
+            "Note" | "note" => {
+                if let Some(note) = parse_note(rest) {
+                    for id in &note.participants {
+                        add_participant(&mut diagram, id, None, false);
+                    }
+                    diagram.steps.push(SequenceStep::Note(note));
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        if SEQUENCE_KEYWORDS.contains(&keyword)
+            || line.starts_with("accTitle")
+            || line.starts_with("accDescr")
+        {
+            continue;
+        }
+
+        let (head, text) = match line.split_once(':') {
+            Some((head, text)) => (head, text.trim()),
+            None => (line, ""),
+        };
+
+        let arrow = SEQUENCE_ARROWS
+            .iter()
+            .filter_map(|arrow| head.find(arrow).map(|i| (i, *arrow)))
+            .min_by(|a, b| a.0.cmp(&b.0).then(b.1.len().cmp(&a.1.len())));
+
+        if let Some((index, arrow)) = arrow {
+            let from = head[..index].trim();
+            let to = head[index + arrow.len()..]
+                .trim()
+                .trim_start_matches(['+', '-'])
+                .trim();
+            if from.is_empty() || to.is_empty() {
+                continue;
+            }
+
+            add_participant(&mut diagram, from, None, false);
+            add_participant(&mut diagram, to, None, false);
+            diagram
+                .steps
+                .push(SequenceStep::Message(diagram.messages.len()));
+            diagram.messages.push(Message {
+                from: from.to_string(),
+                to: to.to_string(),
+                text: text.to_string(),
+                arrow: arrow.to_string(),
+            });
+        }
+    }
+
+    diagram
+}
+
+// ---------------------------------------------------------------------------
+// Class diagram
+// ---------------------------------------------------------------------------
+
+const CLASS_RELATIONS: [&str; 16] = [
+    "<|--", "--|>", "<|..", "..|>", "*--", "--*", "o--", "--o", "<-->", "-->", "<--", "<..", "..>",
+    "--", "..", "()--",
+];
+
+fn class_name(raw: &str) -> String {
+    let raw = raw.trim();
+    let raw = raw.split(":::").next().unwrap_or(raw);
+    let raw = raw.split('[').next().unwrap_or(raw);
+    raw.trim().trim_matches('`').to_string()
+}
+
+fn class_entry<'a>(diagram: &'a mut ParsedDiagram, name: &str) -> &'a mut ClassNode {
+    let index = match diagram.classes.iter().position(|c| c.name == name) {
+        Some(index) => index,
+        None => {
+            diagram.classes.push(ClassNode {
+                name: name.to_string(),
+                annotations: Vec::new(),
+                members: Vec::new(),
+            });
+            diagram.classes.len() - 1
+        }
+    };
+    &mut diagram.classes[index]
+}
+
+fn strip_cardinality(side: &str) -> &str {
+    let side = side.trim();
+    if let Some(quoted) = side.strip_prefix('"') {
+        quoted
+            .split_once('"')
+            .map(|(_, rest)| rest.trim())
+            .unwrap_or(side)
+    } else if let Some(quoted) = side.strip_suffix('"') {
+        quoted
+            .rsplit_once('"')
+            .map(|(rest, _)| rest.trim())
+            .unwrap_or(side)
+    } else {
+        side
+    }
+}
+
+fn parse_class_relation(diagram: &mut ParsedDiagram, line: &str) -> bool {
+    let (relation, label) = match line.split_once(" : ").or_else(|| line.split_once(':')) {
+        Some((relation, label)) => (relation, Some(label.trim().to_string())),
+        None => (line, None),
+    };
+
+    let found = CLASS_RELATIONS
+        .iter()
+        .filter_map(|arrow| relation.find(arrow).map(|i| (i, *arrow)))
+        .min_by(|a, b| a.0.cmp(&b.0).then(b.1.len().cmp(&a.1.len())));
+
+    let Some((index, arrow)) = found else {
+        return false;
+    };
+
+    let from = class_name(strip_cardinality(&relation[..index]));
+    let to = class_name(strip_cardinality(&relation[index + arrow.len()..]));
+    if from.is_empty()
+        || to.is_empty()
+        || from.contains(char::is_whitespace)
+        || to.contains(char::is_whitespace)
+    {
+        return false;
+    }
+
+    class_entry(diagram, &from);
+    class_entry(diagram, &to);
+
+    let head = if arrow.ends_with('>') || arrow.ends_with('*') || arrow.ends_with('o') {
+        ArrowHead::Arrow
+    } else {
+        ArrowHead::None
+    };
+    let tail = if arrow.starts_with('<') || arrow.starts_with('*') || arrow.starts_with('o') {
+        ArrowHead::Arrow
+    } else {
+        ArrowHead::None
+    };
+
+    diagram.edges.push(Edge {
+        from,
+        to,
+        label: label.filter(|l| !l.is_empty()),
+        arrow: arrow.to_string(),
+        stroke: if arrow.contains("..") {
+            EdgeStroke::Dotted
+        } else {
+            EdgeStroke::Normal
+        },
+        head,
+        tail,
+    });
+    true
+}
+
+fn parse_class(lines: &[&str]) -> ParsedDiagram {
+    let mut diagram = ParsedDiagram::new(DiagramKind::Class);
+    let mut open_class: Option<String> = None;
+
+    for line in lines {
+        let line = line.trim();
+
+        if let Some(ref name) = open_class {
+            if line == "}" {
+                open_class = None;
+            } else if let Some(annotation) =
+                line.strip_prefix("<<").and_then(|l| l.strip_suffix(">>"))
+            {
+                class_entry(&mut diagram, name)
+                    .annotations
+                    .push(annotation.to_string());
+            } else {
+                class_entry(&mut diagram, name)
+                    .members
+                    .push(line.to_string());
+            }
+            continue;
+        }
+
+        let keyword = first_word(line);
+        match keyword {
+            "class" => {
+                let rest = line["class".len()..].trim();
+                let (declaration, has_body) = match rest.strip_suffix('{') {
+                    Some(declaration) => (declaration, true),
+                    None => (rest, false),
+                };
+                let name = class_name(declaration.split('~').next().unwrap_or(declaration));
+                class_entry(&mut diagram, &name);
+                if has_body {
+                    open_class = Some(name);
+                }
+            }
+            "direction" => diagram.direction = Some(line["direction".len()..].trim().to_string()),
+            "note" | "classDef" | "cssClass" | "style" | "click" | "callback" | "link"
+            | "title" | "accTitle:" | "accDescr:" | "namespace" | "}" => {}
+            _ if line.starts_with("<<") => {
+                if let Some((annotation, name)) = line[2..].split_once(">>") {
+                    class_entry(&mut diagram, &class_name(name))
+                        .annotations
+                        .push(annotation.to_string());
+                }
+            }
+            _ => {
+                if !parse_class_relation(&mut diagram, line) {
+                    // `ClassName : +member` adds a member without a body block
+                    if let Some((name, member)) = line.split_once(':') {
+                        let name = class_name(name);
+                        if !name.is_empty() && !name.contains(char::is_whitespace) {
+                            class_entry(&mut diagram, &name)
+                                .members
+                                .push(member.trim().to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    diagram
+}
+
+// ---------------------------------------------------------------------------
+// State diagram
+// ---------------------------------------------------------------------------
+
+fn parse_state(lines: &[&str]) -> ParsedDiagram {
+    let mut builder = FlowchartBuilder {
+        diagram: ParsedDiagram::new(DiagramKind::State),
+        subgraph_stack: Vec::new(),
+    };
+    let mut in_note = false;
+
+    for line in lines {
+        let line = line.trim();
+
+        if in_note {
+            in_note = line != "end note";
+            continue;
+        }
+
+        let keyword = first_word(line);
+        match keyword {
+            "note" => in_note = !line.contains(':'),
+            "direction" => {
+                builder.diagram.direction = Some(line["direction".len()..].trim().to_string())
+            }
+            "}" => {
+                builder.subgraph_stack.pop();
+            }
+            "classDef" | "class" | "style" | "title" | "accTitle:" | "accDescr:" | "--" => {}
+            "state" => {
+                let rest = line["state".len()..].trim();
+                if let Some(composite) = rest.strip_suffix('{') {
+                    let composite = composite.trim();
+                    let (id, label) = match composite.split_once(" as ") {
+                        Some((label, id)) => (id.trim(), Some(unquote(label).to_string())),
+                        None => (composite, None),
+                    };
+                    builder.diagram.subgraphs.push(Subgraph {
+                        id: id.to_string(),
+                        label,
+                        parent: builder.subgraph_stack.last().cloned(),
+                        direction: None,
+                        nodes: Vec::new(),
+                    });
+                    builder.subgraph_stack.push(id.to_string());
+                } else if let Some((label, id)) = rest.split_once(" as ") {
+                    builder.add_node(id.trim(), Some(unquote(label).to_string()), None);
+                } else if let Some((id, stereotype)) = rest.split_once("<<") {
+                    let shape = if stereotype.starts_with("choice") {
+                        NodeShape::Rhombus
+                    } else {
+                        NodeShape::Rect
+                    };
+                    builder.add_node(id.trim(), None, Some(shape));
+                } else {
+                    builder.add_node(rest, None, None);
+                }
+            }
+            _ => {
+                let (transition, label) = match line.split_once(':') {
+                    Some((transition, label)) => (transition, Some(label.trim().to_string())),
+                    None => (line, None),
+                };
+
+                if let Some((from, to)) = transition.split_once("-->") {
+                    let scope = builder.subgraph_stack.last().cloned();
+                    let from = state_id(from.trim(), "start", scope.as_deref());
+                    let to = state_id(to.trim(), "end", scope.as_deref());
+                    for id in [&from, &to] {
+                        let pseudo = id.ends_with("[*]start") || id.ends_with("[*]end");
+                        builder.add_node(
+                            id,
+                            pseudo.then(|| "[*]".to_string()),
+                            pseudo.then_some(NodeShape::Circle),
+                        );
+                    }
+                    builder.diagram.edges.push(Edge {
+                        from,
+                        to,
+                        label: label.filter(|l| !l.is_empty()),
+                        arrow: "-->".to_string(),
+                        stroke: EdgeStroke::Normal,
+                        head: ArrowHead::Arrow,
+                        tail: ArrowHead::None,
+                    });
+                } else if let Some(label) = label {
+                    // `StateId : description`
+                    builder.add_node(transition.trim(), Some(label), None);
+                }
+            }
+        }
+    }
+
+    builder.finish()
+}
+
+/// Start and end pseudo-states are distinct nodes per composite scope
+fn state_id(raw: &str, role: &str, scope: Option<&str>) -> String {
+    if raw == "[*]" {
+        match scope {
+            Some(scope) => format!("{}.[*]{}", scope, role),
+            None => format!("[*]{}", role),
+        }
+    } else {
+        raw.to_string()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Writers
+// ---------------------------------------------------------------------------
+
+fn escape_label(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+/// Format a flowchart node declaration such as `A["Label"]`
+pub fn format_node(node: &Node) -> String {
+    match node.label {
+        Some(ref label) => {
+            let (open, close) = node.shape.delimiters();
+            format!("{}{}\"{}\"{}", node.id, open, escape_label(label), close)
+        }
+        None if node.shape != NodeShape::Rect => {
+            let (open, close) = node.shape.delimiters();
+            format!("{}{}\"{}\"{}", node.id, open, escape_label(&node.id), close)
+        }
+        None => node.id.clone(),
+    }
+}
+
+/// Format a flowchart edge such as `A -->|label| B`
+pub fn format_edge(edge: &Edge) -> String {
+    match edge.label {
+        Some(ref label) => format!(
+            "{} {}|{}| {}",
+            edge.from,
+            edge.arrow,
+            escape_label(label).replace('|', "#124;"),
+            edge.to
+        ),
+        None => format!("{} {} {}", edge.from, edge.arrow, edge.to),
+    }
+}
+
+fn write_subgraph(diagram: &ParsedDiagram, subgraph: &Subgraph, depth: usize, out: &mut String) {
+    let indent = "    ".repeat(depth);
+    match subgraph.label {
+        Some(ref label) => out.push_str(&format!(
+            "{}subgraph {}[\"{}\"]\n",
+            indent,
+            subgraph.id,
+            escape_label(label)
+        )),
+        None => out.push_str(&format!("{}subgraph {}\n", indent, subgraph.id)),
+    }
+    if let Some(ref direction) = subgraph.direction {
+        out.push_str(&format!("{}    direction {}\n", indent, direction));
+    }
+    for id in &subgraph.nodes {
+        if let Some(node) = diagram.node(id) {
+            out.push_str(&format!("{}    {}\n", indent, format_node(node)));
+        }
+    }
+    for child in diagram
+        .subgraphs
+        .iter()
+        .filter(|s| s.parent.as_deref() == Some(&subgraph.id))
+    {
+        write_subgraph(diagram, child, depth + 1, out);
+    }
+    out.push_str(&format!("{}end\n", indent));
+}
+
+fn write_flowchart(diagram: &ParsedDiagram) -> String {
+    let mut out = format!(
+        "flowchart {}\n",
+        diagram.direction.as_deref().unwrap_or("TD")
+    );
+
+    for subgraph in diagram.subgraphs.iter().filter(|s| s.parent.is_none()) {
+        write_subgraph(diagram, subgraph, 1, &mut out);
+    }
+
+    for node in &diagram.nodes {
+        let in_subgraph = diagram.subgraphs.iter().any(|s| s.nodes.contains(&node.id));
+        if !in_subgraph {
+            out.push_str(&format!("    {}\n", format_node(node)));
+        }
+    }
+
+    for edge in &diagram.edges {
+        out.push_str(&format!("    {}\n", format_edge(edge)));
+    }
+
+    for class_def in &diagram.class_defs {
+        out.push_str(&format!(
+            "    classDef {} {}\n",
+            class_def.name,
+            class_def.styles.join(",")
+        ));
+    }
+    for node in &diagram.nodes {
+        for class in &node.classes {
+            out.push_str(&format!("    class {} {}\n", node.id, class));
+        }
+        if !node.styles.is_empty() {
+            out.push_str(&format!(
+                "    style {} {}\n",
+                node.id,
+                node.styles.join(",")
+            ));
+        }
+    }
+
+    out
+}
+
+fn write_sequence(diagram: &ParsedDiagram) -> String {
+    let mut out = String::from("sequenceDiagram\n");
+
+    for participant in &diagram.participants {
+        let keyword = if participant.is_actor {
+            "actor"
+        } else {
+            "participant"
+        };
+        match participant.label {
+            Some(ref label) => out.push_str(&format!(
+                "    {} {} as {}\n",
+                keyword, participant.id, label
+            )),
+            None => out.push_str(&format!("    {} {}\n", keyword, participant.id)),
+        }
+    }
+
+    let mut depth = 1;
+    for step in &diagram.steps {
+        if matches!(step, SequenceStep::BlockEnd) {
+            depth = (depth - 1).max(1);
+        }
+        let indent = match step {
+            SequenceStep::BlockSection { .. } => depth.max(2) - 1,
+            _ => depth,
+        };
+        out.push_str(&format!(
+            "{}{}\n",
+            "    ".repeat(indent),
+            format_step(diagram, step)
+        ));
+        if matches!(step, SequenceStep::BlockStart { .. }) {
+            depth += 1;
+        }
+    }
+
+    out
+}
+
+/// Format a sequence statement such as a message, `Note over A,B: text`,
+/// a block opener like `alt ok`, a section like `else` or `end`
+pub fn format_step(diagram: &ParsedDiagram, step: &SequenceStep) -> String {
+    match step {
+        SequenceStep::Message(index) => format_message(&diagram.messages[*index]),
+        SequenceStep::Note(note) => {
+            let placement = match note.placement {
+                NotePlacement::LeftOf => "left of",
+                NotePlacement::RightOf => "right of",
+                NotePlacement::Over => "over",
+            };
+            format!(
+                "Note {} {}: {}",
+                placement,
+                note.participants.join(","),
+                note.text
+            )
+        }
+        SequenceStep::BlockStart { keyword, label }
+        | SequenceStep::BlockSection { keyword, label } => {
+            format!("{} {}", keyword, label).trim_end().to_string()
+        }
+        SequenceStep::BlockEnd => "end".to_string(),
+    }
+}
+
+/// Format a sequence message such as `A->>B: text`
+pub fn format_message(message: &Message) -> String {
+    format!(
+        "{}{}{}: {}",
+        message.from, message.arrow, message.to, message.text
+    )
+}
+
+fn write_class(diagram: &ParsedDiagram) -> String {
+    let mut out = String::from("classDiagram\n");
+
+    if let Some(ref direction) = diagram.direction {
+        out.push_str(&format!("    direction {}\n", direction));
+    }
+
+    for class in &diagram.classes {
+        if class.members.is_empty() && class.annotations.is_empty() {
+            out.push_str(&format!("    class {}\n", class.name));
+            continue;
+        }
+        out.push_str(&format!("    class {} {{\n", class.name));
+        for annotation in &class.annotations {
+            out.push_str(&format!("        <<{}>>\n", annotation));
+        }
+        for member in &class.members {
+            out.push_str(&format!("        {}\n", member));
+        }
+        out.push_str("    }\n");
+    }
+
+    for edge in &diagram.edges {
+        match edge.label {
+            Some(ref label) => out.push_str(&format!(
+                "    {} {} {} : {}\n",
+                edge.from, edge.arrow, edge.to, label
+            )),
+            None => out.push_str(&format!("    {} {} {}\n", edge.from, edge.arrow, edge.to)),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flowchart_nodes_and_edges() {
+        let diagram = parse_diagram(
+            "flowchart LR\n    A[Start] --> B{Valid?}\n    B -- Yes --> C([Done])\n    B -->|No| A\n",
+        )
+        .unwrap();
+
+        assert_eq!(diagram.kind, DiagramKind::Flowchart);
+        assert_eq!(diagram.direction.as_deref(), Some("LR"));
+        assert_eq!(diagram.nodes.len(), 3);
+        assert_eq!(diagram.node("B").unwrap().shape, NodeShape::Rhombus);
+        assert_eq!(diagram.node("C").unwrap().shape, NodeShape::Stadium);
+        assert_eq!(diagram.edges.len(), 3);
+        assert_eq!(diagram.edges[1].label.as_deref(), Some("Yes"));
+        assert_eq!(diagram.edges[1].arrow, "-->");
+        assert_eq!(diagram.edges[2].label.as_deref(), Some("No"));
+    }
+
+    #[test]
+    fn test_flowchart_chains_and_subgraphs() {
+        let diagram = parse_diagram(
+            "graph TD\n  subgraph one [First]\n    a1 & a2 -.-> b1\n  end\n  b1 ==> c((Circle)) --- d\n",
+        )
+        .unwrap();
+
+        assert_eq!(diagram.edges.len(), 4);
+        assert_eq!(diagram.edges[0].stroke, EdgeStroke::Dotted);
+        assert_eq!(diagram.edges[2].stroke, EdgeStroke::Thick);
+        assert_eq!(diagram.edges[3].head, ArrowHead::None);
+        assert_eq!(diagram.subgraphs[0].label.as_deref(), Some("First"));
+        assert_eq!(diagram.subgraphs[0].nodes, vec!["a1", "a2", "b1"]);
+        assert_eq!(diagram.node("c").unwrap().shape, NodeShape::Circle);
+    }
+
+    #[test]
+    fn test_sequence_messages() {
+        let diagram = parse_diagram(
+            "sequenceDiagram\n    participant API Gateway\n    actor U as User\n    U->>+API Gateway: GET /data\n    alt ok\n    API Gateway-->>U: 200\n    end\n",
+        )
+        .unwrap();
+
+        assert_eq!(diagram.participants.len(), 2);
+        assert!(diagram.participant("U").unwrap().is_actor);
+        assert_eq!(diagram.messages.len(), 2);
+        assert_eq!(diagram.messages[0].to, "API Gateway");
+        assert_eq!(diagram.messages[1].arrow, "-->>");
+        assert_eq!(
+            diagram.steps,
+            [
+                SequenceStep::Message(0),
+                SequenceStep::BlockStart {
+                    keyword: "alt".to_string(),
+                    label: "ok".to_string(),
+                },
+                SequenceStep::Message(1),
+                SequenceStep::BlockEnd,
+            ]
+        );
+
+        let notes = parse_diagram(
+            "sequenceDiagram\n    box Team\n    participant A\n    end\n    Note over A,B: shared\n    note left of A : aside\n",
+        )
+        .unwrap();
+        assert_eq!(notes.participants.len(), 2);
+        assert_eq!(
+            notes.steps,
+            [
+                SequenceStep::Note(Note {
+                    placement: NotePlacement::Over,
+                    participants: vec!["A".to_string(), "B".to_string()],
+                    text: "shared".to_string(),
+                }),
+                SequenceStep::Note(Note {
+                    placement: NotePlacement::LeftOf,
+                    participants: vec!["A".to_string()],
+                    text: "aside".to_string(),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_class_members_and_relations() {
+        let diagram = parse_diagram(
+            "classDiagram\n    class User {\n        <<entity>>\n        +String name\n        +login()\n    }\n    Order : +float total\n    User \"1\" --> \"*\" Order : places\n    Animal <|-- Dog\n",
+        )
+        .unwrap();
+
+        let user = diagram.class("User").unwrap();
+        assert_eq!(user.annotations, vec!["entity"]);
+        assert_eq!(user.members.len(), 2);
+        assert_eq!(
+            diagram.class("Order").unwrap().members,
+            vec!["+float total"]
+        );
+        assert_eq!(diagram.edges.len(), 2);
+        assert_eq!(diagram.edges[0].label.as_deref(), Some("places"));
+        assert_eq!(diagram.edges[1].arrow, "<|--");
+    }
+
+    #[test]
+    fn test_flowchart_round_trip() {
+        let source = "flowchart LR\n    A[Start] --> B{Check}\n    B -->|yes| C\n";
+        let diagram = parse_diagram(source).unwrap();
+        let reparsed = parse_diagram(&write_diagram(&diagram).unwrap()).unwrap();
+
+        assert_eq!(diagram.nodes, reparsed.nodes);
+        assert_eq!(diagram.edges, reparsed.edges);
+    }
+
+    #[test]
+    fn test_flowchart_styling() {
+        let source = "flowchart TD\n    classDef warn,alert fill:#f96,stroke:#333\n    A:::warn --> B\n    class B,C alert\n    style A stroke-width:4px,color:#fff\n";
+        let diagram = parse_diagram(source).unwrap();
+
+        assert_eq!(diagram.class_defs.len(), 2);
+        assert_eq!(
+            diagram.class_defs[1].styles,
+            vec!["fill:#f96", "stroke:#333"]
+        );
+        assert_eq!(diagram.node("A").unwrap().classes, vec!["warn"]);
+        assert_eq!(
+            diagram.node("A").unwrap().styles,
+            vec!["stroke-width:4px", "color:#fff"]
+        );
+        assert_eq!(diagram.node("C").unwrap().classes, vec!["alert"]);
+        assert_eq!(diagram.edges.len(), 1);
+
+        let reparsed = parse_diagram(&write_diagram(&diagram).unwrap()).unwrap();
+        assert_eq!(diagram.nodes, reparsed.nodes);
+        assert_eq!(diagram.class_defs, reparsed.class_defs);
+    }
+
+    #[test]
+    fn test_accessibility_statements() {
+        let diagram = parse_diagram(
+            "flowchart TD\n    accTitle: Login flow\n    accDescr {\n        User signs in\n        and is redirected\n    }\n    A --> B\n",
+        )
+        .unwrap();
+
+        assert_eq!(diagram.acc_title.as_deref(), Some("Login flow"));
+        assert_eq!(
+            diagram.acc_descr.as_deref(),
+            Some("User signs in\nand is redirected")
+        );
+        assert_eq!(diagram.nodes.len(), 2);
+    }
+}
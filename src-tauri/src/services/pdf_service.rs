@@ -0,0 +1,579 @@
+use crate::models::{PdfDiagram, PdfDocumentOptions, PdfPageEntry};
+use crate::services::raster_service::{parse_background, parse_svg};
+use crate::services::vector_service::{draw_tree, Segment, VectorSink, POINTS_PER_PIXEL};
+use pdf_writer::types::{ActionType, AnnotationType, LineCapStyle, LineJoinStyle, PageMode};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use resvg::tiny_skia::Transform;
+use resvg::usvg::{FillRule, LineCap, LineJoin, Stroke};
+use std::collections::BTreeSet;
+
+const FONT: Name = Name(b"F1");
+const BOLD_FONT: Name = Name(b"F2");
+const HEADER_SIZE: f32 = 9.0;
+const HEADING_SIZE: f32 = 18.0;
+const ENTRY_SIZE: f32 = 11.0;
+const LINE_HEIGHT: f32 = 18.0;
+/// Space reserved above and below the diagram for the header and page number
+const BAND_HEIGHT: f32 = 24.0;
+
+/// Lay out rendered diagrams as a multi-page PDF, one diagram per page,
+/// with optional contents pages, headers and page numbers. Every diagram
+/// also gets a bookmark.
+///
+/// Diagrams that failed to render are left out and reported in the entries.
+pub fn build_pdf_document(
+    diagrams: &[PdfDiagram],
+    options: &PdfDocumentOptions,
+) -> Result<(Vec<u8>, Vec<PdfPageEntry>), String> {
+    let (page_width, page_height) = options.page_dimensions();
+    let margin = options.margin.max(0.0);
+    if page_width <= 2.0 * margin || page_height <= 2.0 * margin {
+        return Err("Page margins leave no room for the diagram".to_string());
+    }
+
+    let mut entries = Vec::with_capacity(diagrams.len());
+    let mut pages = Vec::new();
+    for diagram in diagrams {
+        let tree = diagram
+            .svg
+            .as_ref()
+            .map_err(String::clone)
+            .and_then(|svg| parse_svg(svg));
+        match tree {
+            Ok(tree) => {
+                pages.push((diagram, tree));
+                entries.push(PdfPageEntry {
+                    path: diagram.path.clone(),
+                    title: diagram.title.clone(),
+                    page: None,
+                    error: None,
+                });
+            }
+            Err(e) => entries.push(PdfPageEntry {
+                path: diagram.path.clone(),
+                title: diagram.title.clone(),
+                page: None,
+                error: Some(e),
+            }),
+        }
+    }
+
+    if pages.is_empty() {
+        return Err("None of the diagrams could be rendered".to_string());
+    }
+
+    // Contents pages come first, so their count shifts every diagram page
+    let entries_per_page = (((page_height - 2.0 * margin - 2.0 * LINE_HEIGHT - BAND_HEIGHT)
+        / LINE_HEIGHT)
+        .floor() as usize)
+        .max(1);
+    let contents_pages = if options.table_of_contents {
+        pages.len().div_ceil(entries_per_page)
+    } else {
+        0
+    };
+    let page_count = contents_pages + pages.len();
+
+    let mut next_ref = Ref::new(1);
+    let catalog_id = next_ref.bump();
+    let tree_id = next_ref.bump();
+    let outline_id = next_ref.bump();
+    let info_id = next_ref.bump();
+    let font_id = next_ref.bump();
+    let bold_font_id = next_ref.bump();
+    let page_ids: Vec<Ref> = (0..page_count).map(|_| next_ref.bump()).collect();
+    let content_ids: Vec<Ref> = (0..page_count).map(|_| next_ref.bump()).collect();
+    let bookmark_ids: Vec<Ref> = (0..pages.len()).map(|_| next_ref.bump()).collect();
+
+    let mut pdf = Pdf::new();
+    let mut catalog = pdf.catalog(catalog_id);
+    catalog
+        .pages(tree_id)
+        .outlines(outline_id)
+        .page_mode(PageMode::UseOutlines);
+    catalog.finish();
+
+    pdf.pages(tree_id)
+        .kids(page_ids.iter().copied())
+        .count(page_count as i32);
+
+    let mut info = pdf.document_info(info_id);
+    if let Some(ref title) = options.title {
+        info.title(TextStr(title));
+    }
+    info.creator(TextStr("Mermaid GUI"));
+    info.finish();
+
+    pdf.type1_font(font_id)
+        .base_font(Name(b"Helvetica"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+    pdf.type1_font(bold_font_id)
+        .base_font(Name(b"Helvetica-Bold"))
+        .encoding_predefined(Name(b"WinAnsiEncoding"));
+
+    let background = parse_background(&options.background)?;
+    // Opacity states are shared by every page that uses them
+    let mut alpha_states = Vec::new();
+
+    for index in 0..page_count {
+        let mut content = Content::new();
+        let mut links = Vec::new();
+        let mut page_states = BTreeSet::new();
+
+        if index < contents_pages {
+            let chunk = index * entries_per_page..((index + 1) * entries_per_page).min(pages.len());
+            let mut y = page_height - margin - HEADING_SIZE;
+
+            if index == 0 {
+                let heading = options.title.as_deref().unwrap_or("Contents");
+                write_text(&mut content, BOLD_FONT, HEADING_SIZE, margin, y, heading);
+            }
+            y -= 2.0 * LINE_HEIGHT;
+
+            for number in chunk {
+                let (diagram, _) = &pages[number];
+                let target = contents_pages + number;
+                let page_label = (target + 1).to_string();
+                let label_width = text_width(&page_label, ENTRY_SIZE);
+                let available = page_width - 2.0 * margin - label_width - ENTRY_SIZE;
+                let title = truncate(&diagram.title, ENTRY_SIZE, available);
+
+                write_text(&mut content, FONT, ENTRY_SIZE, margin, y, &title);
+                write_text(
+                    &mut content,
+                    FONT,
+                    ENTRY_SIZE,
+                    page_width - margin - label_width,
+                    y,
+                    &page_label,
+                );
+                links.push((
+                    Rect::new(margin, y - 4.0, page_width - margin, y + ENTRY_SIZE),
+                    page_ids[target],
+                ));
+                y -= LINE_HEIGHT;
+            }
+        } else {
+            let (diagram, tree) = &pages[index - contents_pages];
+
+            let header_band = if options.header { BAND_HEIGHT } else { 0.0 };
+            let footer_band = if options.page_numbers {
+                BAND_HEIGHT
+            } else {
+                0.0
+            };
+            let (top, bottom) = (page_height - margin - header_band, margin + footer_band);
+            let (area_width, area_height) = (page_width - 2.0 * margin, top - bottom);
+
+            if options.header {
+                let file_name = std::path::Path::new(&diagram.path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let name_width = text_width(&file_name, HEADER_SIZE);
+                let y = page_height - margin - HEADER_SIZE;
+                let title = truncate(
+                    &diagram.title,
+                    HEADER_SIZE,
+                    area_width - name_width - HEADER_SIZE * 2.0,
+                );
+                write_text(&mut content, BOLD_FONT, HEADER_SIZE, margin, y, &title);
+                write_text(
+                    &mut content,
+                    FONT,
+                    HEADER_SIZE,
+                    page_width - margin - name_width,
+                    y,
+                    &file_name,
+                );
+                content
+                    .set_stroke_gray(0.75)
+                    .set_line_width(0.5)
+                    .move_to(margin, y - 6.0)
+                    .line_to(page_width - margin, y - 6.0)
+                    .stroke();
+            }
+
+            let size = tree.size();
+            let (natural_width, natural_height) = (
+                size.width() * POINTS_PER_PIXEL,
+                size.height() * POINTS_PER_PIXEL,
+            );
+            let mut scale = (area_width / natural_width).min(area_height / natural_height);
+            if !options.fit_to_page {
+                scale = scale.min(1.0);
+            }
+            let (width, height) = (natural_width * scale, natural_height * scale);
+            let x = margin + (area_width - width) / 2.0;
+            let y = bottom + (area_height - height) / 2.0;
+
+            let mut sink = PdfSink {
+                content: &mut content,
+                alpha_states: &mut alpha_states,
+                page_states: &mut page_states,
+                next_ref: &mut next_ref,
+            };
+
+            if let Some(color) = background {
+                sink.content.save_state();
+                sink.set_alpha(false, color.alpha());
+                sink.content
+                    .set_fill_rgb(color.red(), color.green(), color.blue())
+                    .rect(x, y, width, height)
+                    .fill_nonzero();
+                sink.content.restore_state();
+            }
+
+            // SVG grows downwards from the top-left corner, PDF upwards from the bottom-left
+            let pixel_scale = scale * POINTS_PER_PIXEL;
+            sink.content.save_state();
+            sink.content
+                .transform([pixel_scale, 0.0, 0.0, -pixel_scale, x, y + height]);
+            draw_tree(&mut sink, tree.root());
+            sink.content.restore_state();
+        }
+
+        if options.page_numbers {
+            let label = format!("{} / {}", index + 1, page_count);
+            let x = (page_width - text_width(&label, HEADER_SIZE)) / 2.0;
+            content.set_fill_gray(0.0);
+            write_text(&mut content, FONT, HEADER_SIZE, x, margin, &label);
+        }
+
+        let mut page = pdf.page(page_ids[index]);
+        page.media_box(Rect::new(0.0, 0.0, page_width, page_height))
+            .parent(tree_id)
+            .contents(content_ids[index]);
+        let mut resources = page.resources();
+        resources
+            .fonts()
+            .pair(FONT, font_id)
+            .pair(BOLD_FONT, bold_font_id);
+        if !page_states.is_empty() {
+            let mut states = resources.ext_g_states();
+            for &number in &page_states {
+                let (_, id) = alpha_states[number];
+                states.pair(Name(alpha_state_name(number).as_bytes()), id);
+            }
+        }
+        resources.finish();
+
+        if !links.is_empty() {
+            let mut annotations = page.annotations();
+            for (rect, target) in links {
+                let mut annotation = annotations.push();
+                annotation
+                    .subtype(AnnotationType::Link)
+                    .rect(rect)
+                    .border(0.0, 0.0, 0.0, None);
+                annotation
+                    .action()
+                    .action_type(ActionType::GoTo)
+                    .destination()
+                    .page(target)
+                    .xyz(0.0, page_height, None);
+            }
+        }
+        page.finish();
+
+        pdf.stream(content_ids[index], &content.finish());
+    }
+
+    for &((stroking, alpha), id) in &alpha_states {
+        let alpha = alpha as f32 / 1000.0;
+        let mut state = pdf.ext_graphics(id);
+        if stroking {
+            state.stroking_alpha(alpha);
+        } else {
+            state.non_stroking_alpha(alpha);
+        }
+    }
+
+    // One bookmark per diagram
+    pdf.outline(outline_id)
+        .first(bookmark_ids[0])
+        .last(bookmark_ids[bookmark_ids.len() - 1])
+        .count(bookmark_ids.len() as i32);
+
+    for (number, (diagram, _)) in pages.iter().enumerate() {
+        let mut item = pdf.outline_item(bookmark_ids[number]);
+        item.title(TextStr(&diagram.title)).parent(outline_id);
+        if number > 0 {
+            item.prev(bookmark_ids[number - 1]);
+        }
+        if number + 1 < bookmark_ids.len() {
+            item.next(bookmark_ids[number + 1]);
+        }
+        item.dest()
+            .page(page_ids[contents_pages + number])
+            .xyz(0.0, page_height, None);
+    }
+
+    let mut page = contents_pages;
+    for entry in entries.iter_mut().filter(|e| e.error.is_none()) {
+        page += 1;
+        entry.page = Some(page);
+    }
+
+    Ok((pdf.finish(), entries))
+}
+
+/// Draws paths into a page's content stream, with opacity applied through
+/// `/ca` and `/CA` graphics states
+struct PdfSink<'a> {
+    content: &'a mut Content,
+    /// Opacity in thousandths for fills or strokes, and the state's object
+    alpha_states: &'a mut Vec<((bool, u16), Ref)>,
+    /// Indices into `alpha_states` used on the current page
+    page_states: &'a mut BTreeSet<usize>,
+    next_ref: &'a mut Ref,
+}
+
+impl PdfSink<'_> {
+    /// Opacity is reset by the `restore_state` that ends each path, so
+    /// opaque paint needs no state
+    fn set_alpha(&mut self, stroking: bool, opacity: f32) {
+        let alpha = (opacity.clamp(0.0, 1.0) * 1000.0).round() as u16;
+        if alpha >= 1000 {
+            return;
+        }
+
+        let key = (stroking, alpha);
+        let number = match self.alpha_states.iter().position(|(k, _)| *k == key) {
+            Some(number) => number,
+            None => {
+                self.alpha_states.push((key, self.next_ref.bump()));
+                self.alpha_states.len() - 1
+            }
+        };
+        self.page_states.insert(number);
+        self.content
+            .set_parameters(Name(alpha_state_name(number).as_bytes()));
+    }
+}
+
+impl VectorSink for PdfSink<'_> {
+    fn begin_path(&mut self, transform: Transform) {
+        let Transform {
+            sx,
+            ky,
+            kx,
+            sy,
+            tx,
+            ty,
+        } = transform;
+        self.content.save_state();
+        self.content.transform([sx, ky, kx, sy, tx, ty]);
+    }
+
+    fn fill(
+        &mut self,
+        outline: &[Segment],
+        [red, green, blue]: [u8; 3],
+        opacity: f32,
+        rule: FillRule,
+    ) {
+        self.set_alpha(false, opacity);
+        self.content
+            .set_fill_rgb(unit(red), unit(green), unit(blue));
+        write_outline(self.content, outline);
+        match rule {
+            FillRule::NonZero => self.content.fill_nonzero(),
+            FillRule::EvenOdd => self.content.fill_even_odd(),
+        };
+    }
+
+    fn stroke(
+        &mut self,
+        outline: &[Segment],
+        [red, green, blue]: [u8; 3],
+        opacity: f32,
+        stroke: &Stroke,
+    ) {
+        self.set_alpha(true, opacity);
+        self.content
+            .set_stroke_rgb(unit(red), unit(green), unit(blue))
+            .set_line_width(stroke.width().get())
+            .set_line_cap(match stroke.linecap() {
+                LineCap::Butt => LineCapStyle::ButtCap,
+                LineCap::Round => LineCapStyle::RoundCap,
+                LineCap::Square => LineCapStyle::ProjectingSquareCap,
+            })
+            .set_line_join(match stroke.linejoin() {
+                LineJoin::Miter | LineJoin::MiterClip => LineJoinStyle::MiterJoin,
+                LineJoin::Round => LineJoinStyle::RoundJoin,
+                LineJoin::Bevel => LineJoinStyle::BevelJoin,
+            })
+            .set_miter_limit(stroke.miterlimit().get())
+            .set_dash_pattern(
+                stroke.dasharray().unwrap_or_default().iter().copied(),
+                stroke.dashoffset(),
+            );
+        write_outline(self.content, outline);
+        self.content.stroke();
+    }
+
+    fn end_path(&mut self) {
+        self.content.restore_state();
+    }
+}
+
+fn write_outline(content: &mut Content, outline: &[Segment]) {
+    for segment in outline {
+        match *segment {
+            Segment::MoveTo(p) => {
+                content.move_to(p.x, p.y);
+            }
+            Segment::LineTo(p) => {
+                content.line_to(p.x, p.y);
+            }
+            Segment::CubicTo(c1, c2, p) => {
+                content.cubic_to(c1.x, c1.y, c2.x, c2.y, p.x, p.y);
+            }
+            Segment::Close => {
+                content.close_path();
+            }
+        }
+    }
+}
+
+fn alpha_state_name(number: usize) -> String {
+    format!("GS{}", number)
+}
+
+fn unit(value: u8) -> f32 {
+    value as f32 / 255.0
+}
+
+fn write_text(content: &mut Content, font: Name, size: f32, x: f32, y: f32, text: &str) {
+    content
+        .begin_text()
+        .set_font(font, size)
+        .next_line(x, y)
+        .show(Str(&win_ansi(text)))
+        .end_text();
+}
+
+/// Encode text for the standard fonts; characters outside WinAnsi become `?`
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '\u{2018}' => 0x91,
+            '\u{2019}' => 0x92,
+            '\u{201c}' => 0x93,
+            '\u{201d}' => 0x94,
+            '\u{2022}' => 0x95,
+            '\u{2013}' => 0x96,
+            '\u{2014}' => 0x97,
+            '\u{2026}' => 0x85,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Approximate Helvetica advance width; digits are exact so page numbers align
+fn text_width(text: &str, size: f32) -> f32 {
+    let em: f32 = text
+        .chars()
+        .map(|c| match c {
+            '0'..='9' => 0.556,
+            ' ' | '.' | ',' | '/' | ':' | ';' | 'i' | 'j' | 'l' | '\'' | '|' => 0.278,
+            'f' | 't' | 'r' | '-' | '(' | ')' => 0.333,
+            'm' | 'w' | 'M' | 'W' => 0.86,
+            'A'..='Z' => 0.667,
+            _ => 0.556,
+        })
+        .sum();
+    em * size
+}
+
+fn truncate(text: &str, size: f32, max_width: f32) -> String {
+    if text_width(text, size) <= max_width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    for c in text.chars() {
+        if text_width(&truncated, size) + text_width(&format!("{}\u{2026}", c), size) > max_width {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push('\u{2026}');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PageOrientation;
+
+    const SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="0 0 200 100"><rect x="10" y="10" width="80" height="40" fill="#ececff" stroke="#9370db"/><path d="M90 30 Q 120 0 150 30" fill="none" stroke="#333"/></svg>"##;
+
+    fn diagram(path: &str, svg: Result<&str, &str>) -> PdfDiagram {
+        PdfDiagram {
+            path: path.to_string(),
+            title: path.trim_end_matches(".mmd").to_string(),
+            svg: svg.map(str::to_string).map_err(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_build_pdf_document() {
+        let diagrams = [
+            diagram("docs/login.mmd", Ok(SVG)),
+            diagram("docs/broken.mmd", Err("Parse error on line 2")),
+            diagram("docs/checkout.mmd", Ok(SVG)),
+        ];
+        let options = PdfDocumentOptions {
+            orientation: PageOrientation::Landscape,
+            title: Some("Design document".to_string()),
+            ..PdfDocumentOptions::default()
+        };
+
+        let (pdf, entries) = build_pdf_document(&diagrams, &options).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert_eq!(text.matches("/Type /Page\n").count(), 3);
+        assert!(text.contains("/MediaBox [0 0 841.89 595.28]"));
+        assert!(text.contains("/Title (Design document)"));
+        assert!(text.contains("/Title (docs/checkout)"));
+        assert!(text.contains("/S /GoTo"));
+
+        let pages: Vec<Option<usize>> = entries.iter().map(|e| e.page).collect();
+        assert_eq!(pages, [Some(2), None, Some(3)]);
+        assert_eq!(entries[1].error.as_deref(), Some("Parse error on line 2"));
+    }
+
+    #[test]
+    fn test_opacity_uses_graphics_states() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20"><rect width="20" height="20" fill="#ff0000" fill-opacity="0.5" stroke="#000000"/><rect x="20" width="20" height="20" fill="#0000ff" opacity="0.5"/></svg>"##;
+        let options = PdfDocumentOptions {
+            background: "#000000".to_string(),
+            table_of_contents: false,
+            ..PdfDocumentOptions::default()
+        };
+
+        let (pdf, _) = build_pdf_document(&[diagram("a.mmd", Ok(svg))], &options).unwrap();
+        let text = String::from_utf8_lossy(&pdf);
+
+        // Both half-transparent fills share one state instead of being
+        // blended with white
+        assert_eq!(text.matches("/ca 0.5").count(), 1);
+        assert!(!text.contains("/CA"));
+        assert!(text.contains("/ExtGState <<\n      /GS0"));
+        assert!(text.contains("/GS0 gs\n1 0 0 rg"));
+        assert!(text.contains("/GS0 gs\n0 0 1 rg"));
+    }
+
+    #[test]
+    fn test_text_helpers() {
+        assert_eq!(win_ansi("Café – 1"), b"Caf\xe9 \x96 1");
+        assert_eq!(text_width("10", 10.0), 11.12);
+        let title = truncate("A very long diagram title", 10.0, 50.0);
+        assert!(title.ends_with('\u{2026}'));
+        assert!(text_width(&title, 10.0) <= 50.0);
+    }
+}
@@ -0,0 +1,340 @@
+use crate::services::native_service::{escape_xml, num, text_width, ThemeVariables};
+use crate::services::parser_service::{
+    is_comment_or_blank, strip_accessibility, strip_front_matter,
+};
+use crate::services::renderer_service::RenderError;
+use serde_json::Value;
+use std::f64::consts::PI;
+
+/// Layout constants from mermaid's pie renderer
+const HEIGHT: f64 = 450.0;
+const PIE_WIDTH: f64 = HEIGHT;
+const MARGIN: f64 = 40.0;
+const LEGEND_RECT_SIZE: f64 = 18.0;
+const LEGEND_SPACING: f64 = 4.0;
+const OUTER_STROKE_WIDTH: f64 = 2.0;
+const TITLE_FONT_SIZE: f64 = 25.0;
+const LEGEND_FONT_SIZE: f64 = 17.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PieChart {
+    pub title: Option<String>,
+    pub acc_title: Option<String>,
+    pub acc_descr: Option<String>,
+    /// Add the values to the legend labels
+    pub show_data: bool,
+    /// Labels and values in source order
+    pub sections: Vec<(String, f64)>,
+}
+
+/// Parse a mermaid `pie` diagram
+pub fn parse_pie(code: &str) -> Result<PieChart, String> {
+    let (title, lines) = strip_front_matter(code);
+    let (acc_title, acc_descr, lines) = strip_accessibility(lines);
+    let mut lines = lines.into_iter().filter(|line| !is_comment_or_blank(line));
+
+    let header = lines.next().ok_or("Empty mermaid code provided")?.trim();
+    let rest = header
+        .strip_prefix("pie")
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        .ok_or_else(|| format!("Not a pie chart: {}", header))?
+        .trim();
+    let (show_data, rest) = match rest.strip_prefix("showData") {
+        Some(rest) => (true, rest.trim()),
+        None => (false, rest),
+    };
+
+    let mut chart = PieChart {
+        title,
+        acc_title,
+        acc_descr,
+        show_data,
+        sections: Vec::new(),
+    };
+    if let Some(title) = title_statement(rest) {
+        chart.title = Some(title);
+    } else if !rest.is_empty() {
+        return Err(format!("Unexpected text after pie: {}", rest));
+    }
+
+    for line in lines {
+        let line = line.trim().trim_end_matches(';');
+        if let Some(title) = title_statement(line) {
+            chart.title = Some(title);
+            continue;
+        }
+
+        let (label, value) =
+            parse_section(line).ok_or_else(|| format!("Invalid pie chart section: {}", line))?;
+        if value < 0.0 {
+            return Err(format!(
+                "Negative value {} for \"{}\"; pie chart values must be positive",
+                value, label
+            ));
+        }
+        // Like mermaid, the first section with a label wins
+        if !chart
+            .sections
+            .iter()
+            .any(|(existing, _)| *existing == label)
+        {
+            chart.sections.push((label, value));
+        }
+    }
+
+    Ok(chart)
+}
+
+fn title_statement(line: &str) -> Option<String> {
+    let title = line.strip_prefix("title")?;
+    (title.is_empty() || title.starts_with(char::is_whitespace)).then(|| title.trim().to_string())
+}
+
+/// `"label" : value`
+fn parse_section(line: &str) -> Option<(String, f64)> {
+    let rest = line.strip_prefix('"')?;
+    let (label, rest) = rest.split_once('"')?;
+    let value = rest.trim_start().strip_prefix(':')?.trim();
+    Some((label.to_string(), value.parse().ok()?))
+}
+
+/// Render a pie chart to SVG the way mermaid lays it out: the pie on the
+/// left, percentages on the slices and a legend to the right
+pub fn render_pie(
+    code: &str,
+    config: &Value,
+    theme: &ThemeVariables,
+    svg_id: &str,
+) -> Result<String, RenderError> {
+    let chart = parse_pie(code).map_err(RenderError::Failed)?;
+    let text_position = config["pie"]["textPosition"].as_f64().unwrap_or(0.75);
+    let radius = PIE_WIDTH.min(HEIGHT) / 2.0 - MARGIN;
+    let total: f64 = chart.sections.iter().map(|(_, value)| value).sum();
+
+    let legend_labels: Vec<String> = chart
+        .sections
+        .iter()
+        .map(|(label, value)| match chart.show_data {
+            true => format!("{} [{}]", label, value),
+            false => label.clone(),
+        })
+        .collect();
+    let longest_label = legend_labels
+        .iter()
+        .map(|label| text_width(label, LEGEND_FONT_SIZE, &theme.font_family))
+        .fold(0.0, f64::max);
+    let total_width = PIE_WIDTH + MARGIN + LEGEND_RECT_SIZE + LEGEND_SPACING + longest_label;
+
+    let id = escape_xml(svg_id);
+    let mut svg = format!(
+        r#"<svg id="{id}" width="100%" xmlns="http://www.w3.org/2000/svg" class="pie" style="max-width: {width}px;" viewBox="0 0 {width} {height}" role="graphics-document document" aria-roledescription="pie">"#,
+        id = id,
+        width = num(total_width),
+        height = num(HEIGHT)
+    );
+    svg.push_str(&style(&id, theme));
+    svg.push_str(&format!(
+        r#"<g transform="translate({},{})">"#,
+        num(PIE_WIDTH / 2.0),
+        num(HEIGHT / 2.0)
+    ));
+    svg.push_str(&format!(
+        r#"<circle cx="0" cy="0" r="{}" class="pieOuterCircle"/>"#,
+        num(radius + OUTER_STROKE_WIDTH / 2.0)
+    ));
+
+    let color = |index: usize| &theme.pie_colors[index % theme.pie_colors.len()];
+    let arcs = pie_angles(&chart.sections);
+    for (index, (start, end)) in arcs.iter().enumerate() {
+        if end > start {
+            svg.push_str(&format!(
+                r#"<path d="{}" fill="{}" class="pieCircle"/>"#,
+                arc_path(radius, *start, *end),
+                escape_xml(color(index))
+            ));
+        }
+    }
+    for ((start, end), (_, value)) in arcs.iter().zip(&chart.sections) {
+        let percent = if total > 0.0 {
+            value / total * 100.0
+        } else {
+            0.0
+        };
+        let middle = (start + end) / 2.0;
+        let label_radius = radius * text_position;
+        svg.push_str(&format!(
+            r#"<text transform="translate({},{})" class="slice" style="text-anchor: middle;">{}%</text>"#,
+            num(label_radius * middle.sin()),
+            num(-label_radius * middle.cos()),
+            percent.round()
+        ));
+    }
+
+    if let Some(ref title) = chart.title {
+        svg.push_str(&format!(
+            r#"<text x="0" y="{}" class="pieTitleText">{}</text>"#,
+            num(-(HEIGHT - 50.0) / 2.0),
+            escape_xml(title)
+        ));
+    }
+
+    let row_height = LEGEND_RECT_SIZE + LEGEND_SPACING;
+    let offset = row_height * chart.sections.len() as f64 / 2.0;
+    for (index, label) in legend_labels.iter().enumerate() {
+        let fill = escape_xml(color(index));
+        svg.push_str(&format!(
+            r#"<g class="legend" transform="translate({},{})"><rect width="{size}" height="{size}" style="fill: {fill}; stroke: {fill};"/><text x="22" y="14">{}</text></g>"#,
+            num(12.0 * LEGEND_RECT_SIZE),
+            num(index as f64 * row_height - offset),
+            escape_xml(label),
+            size = num(LEGEND_RECT_SIZE),
+            fill = fill
+        ));
+    }
+
+    svg.push_str("</g></svg>");
+    Ok(svg)
+}
+
+fn style(id: &str, theme: &ThemeVariables) -> String {
+    let font = escape_xml(&theme.font_family);
+    format!(
+        "<style>#{id}{{font-family:{font};font-size:16px;fill:{text};}}\
+         #{id} .pieCircle{{stroke:{stroke};stroke-width:2px;opacity:{opacity};}}\
+         #{id} .pieOuterCircle{{stroke:{outer};stroke-width:{outer_width}px;fill:none;}}\
+         #{id} .pieTitleText{{text-anchor:middle;font-size:{title_size}px;fill:{title};font-family:{font};}}\
+         #{id} .slice{{font-family:{font};fill:{section};font-size:17px;}}\
+         #{id} .legend text{{fill:{legend};font-family:{font};font-size:{legend_size}px;}}</style>",
+        id = id,
+        font = font,
+        text = escape_xml(&theme.text_color),
+        stroke = escape_xml(&theme.pie_stroke_color),
+        opacity = escape_xml(&theme.pie_opacity),
+        outer = escape_xml(&theme.pie_outer_stroke_color),
+        outer_width = num(OUTER_STROKE_WIDTH),
+        title_size = num(TITLE_FONT_SIZE),
+        title = escape_xml(&theme.pie_title_text_color),
+        section = escape_xml(&theme.pie_section_text_color),
+        legend = escape_xml(&theme.pie_legend_text_color),
+        legend_size = num(LEGEND_FONT_SIZE),
+    )
+}
+
+/// Start and end angle of each section, in source order. Like d3's pie
+/// layout the largest section starts at twelve o'clock and the others
+/// follow clockwise by decreasing value.
+fn pie_angles(sections: &[(String, f64)]) -> Vec<(f64, f64)> {
+    let total: f64 = sections.iter().map(|(_, value)| value).sum();
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by(|a, b| sections[*b].1.total_cmp(&sections[*a].1));
+
+    let mut angles = vec![(0.0, 0.0); sections.len()];
+    let mut angle = 0.0;
+    for index in order {
+        let span = if total > 0.0 {
+            sections[index].1 / total * 2.0 * PI
+        } else {
+            0.0
+        };
+        angles[index] = (angle, angle + span);
+        angle += span;
+    }
+    angles
+}
+
+/// Path of a pie slice between two angles measured clockwise from twelve
+/// o'clock
+fn arc_path(radius: f64, start: f64, end: f64) -> String {
+    let point = |angle: f64| (radius * angle.sin(), -radius * angle.cos());
+    let (x0, y0) = point(start);
+
+    // A full circle needs two arcs; one arc to the same point draws nothing
+    if end - start >= 2.0 * PI - 1e-9 {
+        let (x1, y1) = point(start + PI);
+        return format!(
+            "M{},{}A{r},{r},0,1,1,{},{}A{r},{r},0,1,1,{},{}Z",
+            num(x0),
+            num(y0),
+            num(x1),
+            num(y1),
+            num(x0),
+            num(y0),
+            r = num(radius)
+        );
+    }
+
+    let (x1, y1) = point(end);
+    let large_arc = u8::from(end - start > PI);
+    format!(
+        "M{},{}A{r},{r},0,{},1,{},{}L0,0Z",
+        num(x0),
+        num(y0),
+        large_arc,
+        num(x1),
+        num(y1),
+        r = num(radius)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::raster_service::parse_svg;
+    use serde_json::json;
+
+    const PETS: &str = r#"%%{init: {"theme": "dark"}}%%
+pie showData
+    title Pets adopted by volunteers
+    accTitle: Pets
+    "Dogs" : 386
+    "Cats" : 85.5
+    "Rats" : 15
+    "Dogs" : 1
+"#;
+
+    #[test]
+    fn test_parse_pie() {
+        let chart = parse_pie(PETS).unwrap();
+        assert!(chart.show_data);
+        assert_eq!(chart.title.as_deref(), Some("Pets adopted by volunteers"));
+        assert_eq!(chart.acc_title.as_deref(), Some("Pets"));
+        assert_eq!(
+            chart.sections,
+            vec![
+                ("Dogs".to_string(), 386.0),
+                ("Cats".to_string(), 85.5),
+                ("Rats".to_string(), 15.0)
+            ]
+        );
+
+        let inline = parse_pie("pie title Fruit\n\"Apples: red\" : 3").unwrap();
+        assert_eq!(inline.title.as_deref(), Some("Fruit"));
+        assert_eq!(inline.sections[0].0, "Apples: red");
+
+        assert!(parse_pie("pie\n\"A\" : -1")
+            .unwrap_err()
+            .contains("positive"));
+        assert!(parse_pie("pie\nA : 1").is_err());
+        assert!(parse_pie("pies\n").is_err());
+    }
+
+    #[test]
+    fn test_render_pie() {
+        let theme = ThemeVariables::from_config(&json!({})).unwrap();
+        let svg = render_pie(PETS, &json!({}), &theme, "pets").unwrap();
+        assert!(svg.contains(r#"<text x="22" y="14">Cats [85.5]</text>"#));
+        assert!(svg.contains(">79%</text>"));
+        assert!(svg.contains("Pets adopted by volunteers"));
+        // The largest slice starts at twelve o'clock and spans over half
+        assert!(
+            svg.contains(r#"<path d="M0,-185A185,185,0,1,1,"#),
+            "{}",
+            svg
+        );
+        assert!(parse_svg(&svg).is_ok());
+
+        let single = render_pie("pie\n\"Only\" : 1", &json!({}), &theme, "one").unwrap();
+        assert!(single.contains("A185,185,0,1,1,0,185A185,185,0,1,1,0,-185Z"));
+        assert!(single.contains(">100%</text>"));
+    }
+}
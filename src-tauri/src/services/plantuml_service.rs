@@ -0,0 +1,1691 @@
+use crate::models::{ImportedDiagram, SkippedConstruct};
+use std::collections::HashMap;
+
+/// Sequence participant kinds; Mermaid draws only actors differently
+const PARTICIPANT_KINDS: [&str; 8] = [
+    "participant",
+    "actor",
+    "boundary",
+    "control",
+    "entity",
+    "database",
+    "collections",
+    "queue",
+];
+
+/// Class-like declarations and the annotation Mermaid shows for them
+const CLASS_KINDS: [(&str, Option<&str>); 13] = [
+    ("class", None),
+    ("interface", Some("interface")),
+    ("abstract", Some("abstract")),
+    ("enum", Some("enumeration")),
+    ("annotation", Some("annotation")),
+    ("entity", Some("entity")),
+    ("struct", Some("struct")),
+    ("protocol", Some("protocol")),
+    ("exception", Some("exception")),
+    ("metaclass", Some("metaclass")),
+    ("stereotype", Some("stereotype")),
+    ("record", Some("record")),
+    ("dataclass", Some("dataclass")),
+];
+
+/// Containers that group classes, all imported as Mermaid namespaces
+const PACKAGE_KINDS: [&str; 7] = [
+    "package",
+    "namespace",
+    "folder",
+    "frame",
+    "node",
+    "rectangle",
+    "cloud",
+];
+
+/// Statements that only affect styling, layout or page furniture
+const IGNORED_DIRECTIVES: [&str; 13] = [
+    "skinparam",
+    "hide",
+    "show",
+    "remove",
+    "restore",
+    "scale",
+    "header",
+    "footer",
+    "caption",
+    "newpage",
+    "mainframe",
+    "set",
+    "allowmixing",
+];
+
+/// Convert the first `@startuml` block of a PlantUML file into Mermaid
+/// source. Sequence and class diagrams are supported; constructs without a
+/// Mermaid equivalent are listed in the result instead of failing the import.
+pub fn import_plantuml(source: &str) -> Result<ImportedDiagram, String> {
+    let mut out = Output::default();
+    let lines = diagram_lines(source, &mut out)?;
+
+    let is_class = lines.iter().any(|(_, line)| is_class_statement(line));
+    let is_sequence = lines.iter().any(|(_, line)| is_sequence_statement(line));
+    let code = if is_class {
+        ClassConverter::new(out).convert(&lines)
+    } else if is_sequence {
+        SequenceConverter::new(out).convert(&lines)
+    } else {
+        return Err("Only PlantUML sequence and class diagrams can be imported".to_string());
+    };
+    Ok(code)
+}
+
+/// Mermaid lines being written, and the constructs left behind
+#[derive(Default)]
+struct Output {
+    lines: Vec<String>,
+    skipped: Vec<SkippedConstruct>,
+    depth: usize,
+}
+
+impl Output {
+    fn push(&mut self, text: impl AsRef<str>) {
+        let indent = "    ".repeat(self.depth + 1);
+        self.lines.push(format!("{}{}", indent, text.as_ref()));
+    }
+
+    fn skip(&mut self, line: usize, source: &str, reason: &str) {
+        self.skipped.push(SkippedConstruct {
+            line,
+            source: source.trim().to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    /// A `'` comment, kept as a Mermaid comment
+    fn comment(&mut self, line: &str) -> bool {
+        match line.strip_prefix('\'') {
+            Some(text) => {
+                self.push(format!("%% {}", text.trim()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Directives with no Mermaid equivalent, including `{ ... }` blocks
+    fn directive(&mut self, lines: &[(usize, String)], index: &mut usize) -> bool {
+        let (number, ref line) = lines[*index - 1];
+        let line = line.trim();
+        // Keywords are case-insensitive
+        let keyword = first_word(line).to_ascii_lowercase();
+        let keyword = keyword.as_str();
+        if line.starts_with('!') {
+            self.skip(number, line, "Preprocessor directives are not converted");
+            return true;
+        }
+        if keyword == "legend" {
+            block_until(lines, index, |l| l == "endlegend" || l == "end legend");
+            self.skip(number, line, "Legends have no Mermaid equivalent");
+            return true;
+        }
+        if !IGNORED_DIRECTIVES.contains(&keyword) {
+            return false;
+        }
+        if line.ends_with('{') {
+            block_until(lines, index, |l| l == "}");
+        }
+        self.skip(
+            number,
+            line,
+            "Styling and layout directives are not converted",
+        );
+        true
+    }
+
+    fn finish(self, header: &str) -> String {
+        let mut code = String::from(header);
+        code.push('\n');
+        for line in &self.lines {
+            code.push_str(line);
+            code.push('\n');
+        }
+        code
+    }
+}
+
+/// Non-empty lines of the first diagram, numbered from 1, without block
+/// comments
+fn diagram_lines(source: &str, out: &mut Output) -> Result<Vec<(usize, String)>, String> {
+    let has_start = source
+        .lines()
+        .any(|line| line.trim_start().starts_with("@start"));
+    let mut started = !has_start;
+    let mut in_comment = false;
+    let mut lines = Vec::new();
+
+    for (index, raw) in source.lines().enumerate() {
+        let number = index + 1;
+        let mut line = raw.trim().to_string();
+
+        // Block comments may span lines and sit within a line
+        let mut kept = String::new();
+        let mut rest = line.as_str();
+        loop {
+            if in_comment {
+                match rest.find("'/") {
+                    Some(end) => {
+                        rest = &rest[end + 2..];
+                        in_comment = false;
+                    }
+                    None => break,
+                }
+            } else {
+                match rest.find("/'") {
+                    Some(start) => {
+                        kept.push_str(&rest[..start]);
+                        rest = &rest[start + 2..];
+                        in_comment = true;
+                    }
+                    None => {
+                        kept.push_str(rest);
+                        break;
+                    }
+                }
+            }
+        }
+        line = kept.trim().to_string();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(kind) = line.strip_prefix("@start") {
+            if !started && !lines.is_empty() {
+                out.skip(
+                    number,
+                    &line,
+                    "Only the first diagram of a file is imported",
+                );
+                break;
+            }
+            if !first_word(kind).eq_ignore_ascii_case("uml") {
+                return Err(format!(
+                    "@start{} diagrams cannot be imported; only sequence and class diagrams can",
+                    first_word(kind)
+                ));
+            }
+            started = true;
+            continue;
+        }
+        if line.starts_with("@end") {
+            started = false;
+            continue;
+        }
+        if started {
+            lines.push((number, line));
+        }
+    }
+    Ok(lines)
+}
+
+/// Lines up to the one that closes a multi-line block, moving `index` past it
+fn block_until<'a>(
+    lines: &'a [(usize, String)],
+    index: &mut usize,
+    is_end: impl Fn(&str) -> bool,
+) -> Vec<&'a str> {
+    let mut block = Vec::new();
+    while *index < lines.len() {
+        let line = lines[*index].1.trim();
+        *index += 1;
+        if is_end(line) {
+            break;
+        }
+        block.push(line);
+    }
+    block
+}
+
+fn first_word(line: &str) -> &str {
+    line.split(|c: char| c.is_whitespace() || c == ':')
+        .next()
+        .unwrap_or("")
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '@' | '$')
+}
+
+/// Split a possibly quoted name off the front of `text`
+fn split_name(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    if let Some(quoted) = text.strip_prefix('"') {
+        let end = quoted.find('"')?;
+        return Some((&text[..end + 2], &text[end + 2..]));
+    }
+    let end = text
+        .char_indices()
+        .find(|&(_, c)| !is_name_char(c))
+        .map_or(text.len(), |(i, _)| i);
+    match end {
+        0 => None,
+        _ => Some((&text[..end], &text[end..])),
+    }
+}
+
+fn unquote(name: &str) -> &str {
+    name.trim()
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+        .unwrap_or(name.trim())
+}
+
+/// A Mermaid identifier for a PlantUML name
+fn mermaid_id(name: &str) -> String {
+    let id: String = name
+        .chars()
+        .map(|c| match c.is_alphanumeric() || c == '_' {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    match id.trim_matches('_').is_empty() {
+        true => "_".to_string(),
+        false => id,
+    }
+}
+
+/// Text safe inside a Mermaid statement, with PlantUML `\n` breaks
+fn mermaid_text(text: &str) -> String {
+    text.trim().replace("\\n", "<br/>").replace(';', "#59;")
+}
+
+/// Remove `[#red]`-style arrow decorations, returning whether any were present
+fn strip_brackets(arrow: &str) -> (String, Option<String>) {
+    let mut plain = String::new();
+    let mut style = None;
+    let mut rest = arrow;
+    while let Some(start) = rest.find('[') {
+        plain.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(']')
+            .map_or(rest.len(), |end| start + end);
+        style = Some(rest[start + 1..end].to_string());
+        rest = rest.get(end + 1..).unwrap_or("");
+    }
+    plain.push_str(rest);
+    (plain, style)
+}
+
+/// Remove trailing `#color` and `<<stereotype>>` decorations, returning the
+/// rest and whether there were any
+fn strip_decorations(text: &str) -> (&str, bool) {
+    let mut text = text.trim();
+    let mut found = false;
+    loop {
+        if let Some(start) = text
+            .rfind(" #")
+            .or_else(|| text.starts_with('#').then_some(0))
+        {
+            if !text[start..].trim()[1..].contains(char::is_whitespace) {
+                text = text[..start].trim_end();
+                found = true;
+                continue;
+            }
+        }
+        if text.ends_with(">>") {
+            if let Some(start) = text.rfind("<<") {
+                text = text[..start].trim_end();
+                found = true;
+                continue;
+            }
+        }
+        return (text, found);
+    }
+}
+
+fn is_sequence_statement(line: &str) -> bool {
+    let keyword = first_word(line).to_ascii_lowercase();
+    let keyword = keyword.as_str();
+    PARTICIPANT_KINDS.contains(&keyword)
+        || matches!(
+            keyword,
+            "activate" | "deactivate" | "autonumber" | "alt" | "loop" | "opt" | "par" | "box"
+        )
+        || parse_message(line).is_some()
+}
+
+fn is_class_statement(line: &str) -> bool {
+    let keyword = first_word(line).to_ascii_lowercase();
+    let keyword = keyword.as_str();
+    (CLASS_KINDS.iter().any(|&(kind, _)| kind == keyword) && keyword != "entity")
+        || (keyword == "entity" && line.trim_end().ends_with('{'))
+        || (PACKAGE_KINDS.contains(&keyword) && keyword != "node")
+        || [
+            "<|--", "--|>", "<|..", "..|>", "*--", "--*", "o--", "--o", "..>", "<..",
+        ]
+        .iter()
+        .any(|arrow| line.contains(arrow))
+}
+
+// ---------------------------------------------------------------------------
+// Sequence diagrams
+// ---------------------------------------------------------------------------
+
+/// A PlantUML sequence message, split into its parts
+#[derive(Debug, PartialEq)]
+struct PlantMessage<'a> {
+    from: &'a str,
+    arrow: &'a str,
+    to: &'a str,
+    /// Activation shorthand such as `++` or `--`
+    lifeline: &'a str,
+    text: &'a str,
+}
+
+fn parse_message(line: &str) -> Option<PlantMessage<'_>> {
+    let line = line.trim();
+    let (from, rest) = match line.chars().next()? {
+        '[' | '?' => line.split_at(1),
+        _ => split_name(line)?,
+    };
+    let rest_trimmed = rest.trim_start();
+    let chars: Vec<(usize, char)> = rest_trimmed.char_indices().collect();
+    let mut end = 0;
+    let mut position = 0;
+    while position < chars.len() {
+        let c = chars[position].1;
+        let next = chars.get(position + 1).map(|&(_, c)| c);
+        let taken = match c {
+            '<' | '>' | '-' | '\\' | '/' => true,
+            '[' if end > 0 => {
+                // Decorations such as `-[#red]>`
+                while position + 1 < chars.len() && chars[position].1 != ']' {
+                    position += 1;
+                }
+                true
+            }
+            'x' | 'o' => next.is_none_or(|c| c.is_whitespace() || c == '<' || c == '-'),
+            _ => false,
+        };
+        if !taken {
+            break;
+        }
+        position += 1;
+        end = chars.get(position).map_or(rest_trimmed.len(), |&(i, _)| i);
+    }
+    let arrow = &rest_trimmed[..end];
+    if !arrow.contains('-') {
+        return None;
+    }
+
+    let after = rest_trimmed[end..].trim_start();
+    let (to, after) = match after.chars().next()? {
+        ']' | '?' => after.split_at(1),
+        _ => split_name(after)?,
+    };
+    let after = after.trim_start();
+    let lifeline_end = after
+        .find(|c: char| !matches!(c, '+' | '-' | '*' | '!' | ' '))
+        .unwrap_or(after.len());
+    let lifeline = after[..lifeline_end].trim();
+    let after = after[lifeline_end..].trim();
+    let text = match after.strip_prefix(':') {
+        Some(text) => text.trim(),
+        None if after.is_empty() => "",
+        None => return None,
+    };
+
+    Some(PlantMessage {
+        from,
+        arrow,
+        to,
+        lifeline,
+        text,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Head {
+    None,
+    Normal,
+    Thin,
+    Cross,
+    /// Half and circle heads, drawn as normal ones
+    Approximate,
+}
+
+fn head_of(part: &str) -> Head {
+    match part {
+        "" => Head::None,
+        _ if part.contains('x') => Head::Cross,
+        ">>" | "<<" => Head::Thin,
+        ">" | "<" => Head::Normal,
+        _ => Head::Approximate,
+    }
+}
+
+struct SequenceConverter {
+    out: Output,
+    /// PlantUML names and aliases, mapped to Mermaid participant ids
+    names: HashMap<String, String>,
+    /// Participant ids in order of appearance
+    order: Vec<String>,
+    /// Open `alt`, `loop`, `box` and similar blocks
+    groups: Vec<String>,
+    last_message: Option<(String, String)>,
+    title: Option<String>,
+}
+
+impl SequenceConverter {
+    fn new(out: Output) -> Self {
+        Self {
+            out,
+            names: HashMap::new(),
+            order: Vec::new(),
+            groups: Vec::new(),
+            last_message: None,
+            title: None,
+        }
+    }
+
+    fn convert(mut self, lines: &[(usize, String)]) -> ImportedDiagram {
+        let mut index = 0;
+        while index < lines.len() {
+            let (number, ref line) = lines[index];
+            index += 1;
+            let line = line.as_str();
+            if self.out.comment(line) || self.out.directive(lines, &mut index) {
+                continue;
+            }
+            self.statement(number, line, lines, &mut index);
+        }
+
+        while self.groups.pop().is_some() {
+            self.out.depth = self.out.depth.saturating_sub(1);
+            self.out.push("end");
+        }
+        if let Some(title) = self.title.take() {
+            self.out.lines.insert(0, format!("    title {}", title));
+        }
+        let mut skipped = std::mem::take(&mut self.out.skipped);
+        skipped.sort_by_key(|skipped| skipped.line);
+        ImportedDiagram::new(self.out.finish("sequenceDiagram"), skipped)
+    }
+
+    fn statement(
+        &mut self,
+        number: usize,
+        line: &str,
+        lines: &[(usize, String)],
+        index: &mut usize,
+    ) {
+        let keyword = first_word(line).to_ascii_lowercase();
+        let keyword = keyword.as_str();
+        let rest = line[keyword.len()..].trim();
+
+        match keyword {
+            _ if PARTICIPANT_KINDS.contains(&keyword) => {
+                self.declare(number, line, "", keyword, rest)
+            }
+            "create" => {
+                let kind = first_word(rest);
+                match PARTICIPANT_KINDS.contains(&kind) {
+                    true => self.declare(number, line, "create ", kind, rest[kind.len()..].trim()),
+                    false => self.declare(number, line, "create ", "participant", rest),
+                }
+            }
+            "title" => {
+                self.title = Some(match rest.is_empty() {
+                    true => block_until(lines, index, |l| l == "end title" || l == "endtitle")
+                        .join("<br/>"),
+                    false => rest.to_string(),
+                })
+            }
+            "autonumber" => {
+                match first_word(rest) {
+                    "stop" | "resume" | "inc" => {
+                        self.out.skip(
+                            number,
+                            line,
+                            "Pausing or resuming numbering has no Mermaid equivalent",
+                        );
+                        return;
+                    }
+                    "" => {}
+                    _ => {
+                        self.out
+                            .skip(number, line, "Numbering start, step and format are dropped")
+                    }
+                }
+                self.out.push("autonumber");
+            }
+            "activate" | "deactivate" | "destroy" => {
+                let (name, decorated) = strip_decorations(rest);
+                if decorated {
+                    self.out
+                        .skip(number, line, "Activation colours are dropped");
+                }
+                let id = self.reference(name);
+                self.out.push(format!("{} {}", keyword, id));
+            }
+            "note" | "hnote" | "rnote" => self.note(number, line, rest, lines, index),
+            "alt" | "opt" | "loop" | "par" | "break" | "critical" => {
+                self.out
+                    .push(format!("{} {}", keyword, mermaid_text(rest)).trim_end());
+                self.open(keyword);
+            }
+            "group" => {
+                self.out.push("rect rgba(128, 128, 128, 0.1)");
+                self.open("group");
+                if !rest.is_empty() {
+                    self.out.skip(
+                        number,
+                        line,
+                        "Group titles are dropped; the group is shown as a shaded area",
+                    );
+                }
+            }
+            "else" => {
+                let keyword = match self.groups.last().map(String::as_str) {
+                    Some("alt") => "else",
+                    Some("par") => "and",
+                    Some("critical") => "option",
+                    _ => {
+                        self.out.skip(
+                            number,
+                            line,
+                            "`else` only converts inside alt, par and critical groups",
+                        );
+                        return;
+                    }
+                };
+                self.out.depth -= 1;
+                self.out
+                    .push(format!("{} {}", keyword, mermaid_text(rest)).trim_end());
+                self.out.depth += 1;
+            }
+            "box" => {
+                let (title, color) = match rest.rfind('#') {
+                    Some(start) if !rest[start..].contains(char::is_whitespace) => {
+                        (&rest[..start], &rest[start..])
+                    }
+                    _ => (rest, ""),
+                };
+                let color = match color.strip_prefix('#') {
+                    Some(hex) if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                        color.to_string()
+                    }
+                    Some(name) if !name.is_empty() => name.to_string(),
+                    _ => "transparent".to_string(),
+                };
+                self.out
+                    .push(format!("box {} {}", color, mermaid_text(unquote(title))).trim_end());
+                self.open("box");
+            }
+            "end" => {
+                if self.groups.pop().is_none() {
+                    self.out.skip(number, line, "`end` without an open group");
+                    return;
+                }
+                self.out.depth -= 1;
+                self.out.push("end");
+            }
+            "ref" => {
+                if !rest.contains(':') {
+                    block_until(lines, index, |l| l == "end ref" || l == "endref");
+                }
+                self.out.skip(
+                    number,
+                    line,
+                    "References to other diagrams have no Mermaid equivalent",
+                );
+            }
+            "return" => self.out.skip(
+                number,
+                line,
+                "`return` needs the matching call; add the reply message by hand",
+            ),
+            _ if line.starts_with("==") => {
+                let text = line.trim_matches('=').trim();
+                match (self.order.first(), self.order.last()) {
+                    (Some(first), Some(last)) => {
+                        let over = match first == last {
+                            true => first.clone(),
+                            false => format!("{},{}", first, last),
+                        };
+                        self.out
+                            .push(format!("Note over {}: {}", over, mermaid_text(text)));
+                        self.out.skip(
+                            number,
+                            line,
+                            "Dividers are shown as a note over all participants",
+                        );
+                    }
+                    _ => self
+                        .out
+                        .skip(number, line, "Dividers have no Mermaid equivalent"),
+                }
+            }
+            _ if line.starts_with("...") => {
+                self.out
+                    .skip(number, line, "Delays have no Mermaid equivalent")
+            }
+            _ if line.starts_with("|||") || (line.starts_with("||") && line.ends_with("||")) => {
+                self.out
+                    .skip(number, line, "Spacing has no Mermaid equivalent")
+            }
+            _ => match parse_message(line) {
+                Some(message) => self.message(number, line, &message),
+                None => self
+                    .out
+                    .skip(number, line, "Not a recognised sequence diagram statement"),
+            },
+        }
+    }
+
+    fn open(&mut self, group: &str) {
+        self.groups.push(group.to_string());
+        self.out.depth += 1;
+    }
+
+    /// The Mermaid id for a name, declaring the participant on first use
+    /// when its name is not a valid id
+    fn reference(&mut self, name: &str) -> String {
+        self.reference_with(name, "")
+    }
+
+    fn reference_with(&mut self, name: &str, prefix: &str) -> String {
+        let display = unquote(name);
+        if let Some(id) = self.names.get(display) {
+            return id.clone();
+        }
+        let id = self.unique_id(display);
+        self.names.insert(display.to_string(), id.clone());
+        self.order.push(id.clone());
+        if id != display {
+            self.out.push(format!(
+                "{}participant {} as {}",
+                prefix,
+                id,
+                mermaid_text(display)
+            ));
+        } else if !prefix.is_empty() {
+            self.out.push(format!("{}participant {}", prefix, id));
+        }
+        id
+    }
+
+    fn unique_id(&self, name: &str) -> String {
+        let base = mermaid_id(name);
+        let mut id = base.clone();
+        let mut counter = 2;
+        while self.order.contains(&id) {
+            id = format!("{}_{}", base, counter);
+            counter += 1;
+        }
+        id
+    }
+
+    fn declare(&mut self, number: usize, line: &str, prefix: &str, kind: &str, rest: &str) {
+        let (rest, decorated) = strip_decorations(rest);
+        let rest = match rest.find(" order ") {
+            Some(start) => &rest[..start],
+            None => rest,
+        };
+        if decorated {
+            self.out.skip(
+                number,
+                line,
+                "Participant colours and stereotypes are dropped",
+            );
+        }
+        if !matches!(kind, "participant" | "actor") {
+            self.out.skip(
+                number,
+                line,
+                &format!("{} participants are shown as plain participants", kind),
+            );
+        }
+
+        let Some((first, after)) = split_name(rest) else {
+            self.out.skip(number, line, "Participant without a name");
+            return;
+        };
+        let (display, alias) = match after.trim().strip_prefix("as ") {
+            Some(second) => {
+                let second = second.trim();
+                match (first.starts_with('"'), second.starts_with('"')) {
+                    (false, true) => (unquote(second), first),
+                    _ => (unquote(first), second),
+                }
+            }
+            None => (unquote(first), unquote(first)),
+        };
+
+        let id = match self.names.get(alias) {
+            Some(id) => id.clone(),
+            None => {
+                let id = self.unique_id(alias);
+                self.order.push(id.clone());
+                id
+            }
+        };
+        self.names.insert(alias.to_string(), id.clone());
+        self.names.insert(display.to_string(), id.clone());
+        let keyword = if kind == "actor" {
+            "actor"
+        } else {
+            "participant"
+        };
+        match display == id {
+            true => self.out.push(format!("{}{} {}", prefix, keyword, id)),
+            false => self.out.push(format!(
+                "{}{} {} as {}",
+                prefix,
+                keyword,
+                id,
+                mermaid_text(display)
+            )),
+        }
+    }
+
+    fn message(&mut self, number: usize, line: &str, message: &PlantMessage) {
+        let outside = ["[", "]", "?"];
+        if outside.contains(&message.from) || outside.contains(&message.to) {
+            self.out.skip(
+                number,
+                line,
+                "Messages from or to outside the diagram have no Mermaid equivalent",
+            );
+            return;
+        }
+
+        let (arrow, style) = strip_brackets(message.arrow);
+        if style.is_some() {
+            self.out
+                .skip(number, line, "Arrow colours and styles are dropped");
+        }
+        let dotted = arrow.contains("--");
+        let first = arrow.find('-').unwrap_or(0);
+        let last = arrow.rfind('-').unwrap_or(0);
+        let mut left = head_of(&arrow[..first]);
+        let mut right = head_of(&arrow[last + 1..]);
+        let (mut from, mut to) = (message.from, message.to);
+        if left != Head::None && right == Head::None {
+            std::mem::swap(&mut from, &mut to);
+            std::mem::swap(&mut left, &mut right);
+        }
+        if left == Head::Approximate || right == Head::Approximate {
+            self.out.skip(
+                number,
+                line,
+                "Half and circle arrow heads are drawn as normal heads",
+            );
+        }
+
+        let line_style = if dotted { "--" } else { "-" };
+        let arrow = match (left, right) {
+            (Head::None, Head::None) => line_style.to_string(),
+            (Head::None, Head::Normal | Head::Approximate) => format!("{}>>", line_style),
+            (Head::None, Head::Thin) => format!("{})", line_style),
+            (Head::None, Head::Cross) => format!("{}x", line_style),
+            _ => format!("<<{}>>", line_style),
+        };
+        let arrow = match arrow.as_str() {
+            "-" => "->".to_string(),
+            "--" => "-->".to_string(),
+            _ => arrow,
+        };
+
+        let from_id = self.reference(from);
+        let created = message.lifeline.contains("**");
+        let to_id = match created {
+            true if !self.names.contains_key(unquote(to)) => self.reference_with(to, "create "),
+            true => {
+                self.out.skip(
+                    number,
+                    line,
+                    "Participants can only be created before their first use",
+                );
+                self.reference(to)
+            }
+            false => self.reference(to),
+        };
+        if message.lifeline.contains("!!") {
+            self.out.push(format!("destroy {}", to_id));
+        }
+        let activation = match (
+            message.lifeline.contains("++"),
+            message.lifeline.contains("--"),
+        ) {
+            (true, _) => "+",
+            (false, true) => "-",
+            (false, false) => "",
+        };
+        let text = mermaid_text(message.text);
+        self.out
+            .push(format!("{}{}{}{}: {}", from_id, arrow, activation, to_id, text).trim_end());
+        if activation == "+" && message.lifeline.contains("--") {
+            self.out.push(format!("deactivate {}", from_id));
+        }
+        self.last_message = Some((from_id, to_id));
+    }
+
+    fn note(
+        &mut self,
+        number: usize,
+        line: &str,
+        rest: &str,
+        lines: &[(usize, String)],
+        index: &mut usize,
+    ) {
+        let (head, text) = match rest.split_once(':') {
+            Some((head, text)) => (head.trim(), Some(text.trim().to_string())),
+            None => (rest, None),
+        };
+        let text = text.unwrap_or_else(|| {
+            block_until(lines, index, |l| {
+                let l = l.replace(' ', "");
+                l == "endnote" || l == "endhnote" || l == "endrnote"
+            })
+            .join("\\n")
+        });
+        let (head, decorated) = strip_decorations(head);
+        if decorated {
+            self.out.skip(number, line, "Note colours are dropped");
+        }
+
+        let position = first_word(head);
+        let targets: Vec<&str> = head[position.len()..]
+            .trim()
+            .trim_start_matches("of ")
+            .split(',')
+            .map(str::trim)
+            .filter(|target| !target.is_empty())
+            .collect();
+        let targets: Vec<String> = match (position, targets.is_empty(), &self.last_message) {
+            ("across", _, _) => match (self.order.first(), self.order.last()) {
+                (Some(first), Some(last)) => vec![first.clone(), last.clone()],
+                _ => Vec::new(),
+            },
+            ("left", true, Some((from, _))) => vec![from.clone()],
+            ("right", true, Some((_, to))) => vec![to.clone()],
+            ("over", true, Some((from, to))) => vec![from.clone(), to.clone()],
+            _ => targets
+                .iter()
+                .map(|target| self.reference(target))
+                .collect(),
+        };
+        let position = match position {
+            "left" => "left of",
+            "right" => "right of",
+            "over" | "across" => "over",
+            _ => {
+                self.out
+                    .skip(number, line, "Not a recognised note position");
+                return;
+            }
+        };
+        if targets.is_empty() {
+            self.out
+                .skip(number, line, "Notes need a participant to attach to");
+            return;
+        }
+        let targets = match position {
+            "over" if targets.len() > 1 => {
+                format!("{},{}", targets[0], targets[targets.len() - 1])
+            }
+            _ => targets[0].clone(),
+        };
+        self.out.push(format!(
+            "Note {} {}: {}",
+            position,
+            targets,
+            mermaid_text(&text)
+        ));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Class diagrams
+// ---------------------------------------------------------------------------
+
+/// A PlantUML class relation, split into its parts
+#[derive(Debug, PartialEq)]
+struct PlantRelation<'a> {
+    left: &'a str,
+    left_label: Option<&'a str>,
+    arrow: &'a str,
+    right_label: Option<&'a str>,
+    right: &'a str,
+    label: &'a str,
+}
+
+fn split_quoted(text: &str) -> (Option<&str>, &str) {
+    let text = text.trim_start();
+    match text.strip_prefix('"').and_then(|rest| rest.find('"')) {
+        Some(end) => (Some(&text[..end + 2]), &text[end + 2..]),
+        None => (None, text),
+    }
+}
+
+fn parse_relation(line: &str) -> Option<PlantRelation<'_>> {
+    let (left, rest) = split_name(line)?;
+    let (left_label, rest) = split_quoted(rest);
+    let rest = rest.trim_start();
+    let run_end = rest
+        .find(|c: char| c.is_whitespace() || c == '"')
+        .unwrap_or(rest.len());
+    let run = &rest[..run_end];
+    let last_line = run.rfind(['-', '.'])?;
+    let first_line = run.find(['-', '.'])?;
+    // The heads that may follow the line; anything after them is the
+    // right-hand name written without a space
+    let heads = ["|>", ">", "*", "o", "#", "x", "}", "{", "+", "^", ""];
+    let tail = &run[last_line + 1..];
+    let head = heads
+        .iter()
+        .find(|head| tail.starts_with(*head))
+        .copied()
+        .unwrap_or("");
+    let arrow_end = last_line + 1 + head.len();
+    if run[..first_line].contains(|c: char| c.is_alphanumeric() && c != 'o' && c != 'x') {
+        return None;
+    }
+    let arrow = &run[..arrow_end];
+    let rest = &rest[arrow_end..];
+
+    let (right_label, rest) = split_quoted(rest);
+    let (right, rest) = split_name(rest)?;
+    let rest = rest.trim();
+    let label = match rest.strip_prefix(':') {
+        Some(label) => label.trim(),
+        None if rest.is_empty() => "",
+        None => return None,
+    };
+    Some(PlantRelation {
+        left,
+        left_label,
+        arrow,
+        right_label,
+        right,
+        label,
+    })
+}
+
+/// Mermaid relation arrow for a PlantUML one, with the reason for anything
+/// lost. `None` when the relation only exists for layout.
+fn class_arrow(arrow: &str) -> Option<(String, Option<&'static str>)> {
+    let (plain, style) = strip_brackets(arrow);
+    if style
+        .as_deref()
+        .is_some_and(|style| style.contains("hidden"))
+    {
+        return None;
+    }
+    let mut lost = style.map(|_| "Relation colours and styles are dropped");
+
+    let first = plain.find(['-', '.'])?;
+    let last = plain.rfind(['-', '.'])?;
+    let dotted = plain[first..=last].contains('.');
+    let left = match &plain[..first] {
+        "<|" | "^" => "<|",
+        "*" => "*",
+        "o" => "o",
+        "<" => "<",
+        "" => "",
+        _ => {
+            lost = Some("Relation heads without a Mermaid equivalent are dropped");
+            ""
+        }
+    };
+    let right = match &plain[last + 1..] {
+        "|>" | "^" => "|>",
+        "*" => "*",
+        "o" => "o",
+        ">" => ">",
+        "" => "",
+        _ => {
+            lost = Some("Relation heads without a Mermaid equivalent are dropped");
+            ""
+        }
+    };
+    let line = if dotted { ".." } else { "--" };
+    Some((format!("{}{}{}", left, line, right), lost))
+}
+
+/// Mermaid member syntax for a PlantUML attribute or method
+fn class_member(member: &str) -> Option<String> {
+    let mut member = member.trim().to_string();
+    if member.is_empty()
+        || member
+            .chars()
+            .all(|c| matches!(c, '-' | '.' | '=' | '_' | ' '))
+    {
+        return None;
+    }
+    // Section separators with a title, e.g. `-- accessors --`
+    for separator in ["--", "..", "==", "__"] {
+        if member.starts_with(separator) && member.ends_with(separator) && member.len() > 4 {
+            return None;
+        }
+    }
+
+    let mut suffix = "";
+    for (modifier, marker) in [
+        ("{static}", "$"),
+        ("{classifier}", "$"),
+        ("{abstract}", "*"),
+        ("{field}", ""),
+        ("{method}", ""),
+    ] {
+        if member.contains(modifier) {
+            member = member.replace(modifier, "").trim().to_string();
+            if !marker.is_empty() {
+                suffix = marker;
+            }
+        }
+    }
+
+    let (visibility, body) = match member.chars().next() {
+        Some(c @ ('+' | '-' | '#' | '~')) => (c.to_string(), member[1..].trim().to_string()),
+        _ => (String::new(), member.clone()),
+    };
+    let generics = |text: &str| text.replace(['<', '>'], "~");
+
+    let converted = match body.find('(') {
+        Some(open) => {
+            let close = body.rfind(')').unwrap_or(body.len() - 1);
+            let signature = &body[..=close.max(open)];
+            let returns = body[close.max(open) + 1..]
+                .trim()
+                .trim_start_matches(':')
+                .trim();
+            match returns.is_empty() {
+                true => generics(signature),
+                false => format!("{} {}", generics(signature), generics(returns)),
+            }
+        }
+        None => match body.split_once(':') {
+            Some((name, kind)) => format!("{} {}", generics(kind.trim()), name.trim()),
+            None => generics(&body),
+        },
+    };
+    Some(format!("{}{}{}", visibility, converted, suffix))
+}
+
+struct ClassConverter {
+    out: Output,
+    /// PlantUML names and aliases, mapped to Mermaid class ids
+    names: HashMap<String, String>,
+    /// Floating note aliases, whose links are dropped
+    notes: Vec<String>,
+    /// Open `{` blocks: namespaces, flattened nested packages and
+    /// `together` groups
+    blocks: Vec<&'static str>,
+    /// Statements that Mermaid does not allow inside a namespace, written
+    /// once it closes
+    deferred: Vec<String>,
+    title: Option<String>,
+}
+
+impl ClassConverter {
+    fn new(out: Output) -> Self {
+        Self {
+            out,
+            names: HashMap::new(),
+            notes: Vec::new(),
+            blocks: Vec::new(),
+            deferred: Vec::new(),
+            title: None,
+        }
+    }
+
+    fn convert(mut self, lines: &[(usize, String)]) -> ImportedDiagram {
+        let mut index = 0;
+        while index < lines.len() {
+            let (number, ref line) = lines[index];
+            index += 1;
+            let line = line.as_str();
+            if self.out.comment(line) || self.out.directive(lines, &mut index) {
+                continue;
+            }
+            self.statement(number, line, lines, &mut index);
+        }
+        while !self.blocks.is_empty() {
+            self.close();
+        }
+
+        let header = match self.title.take() {
+            Some(title) => format!("---\ntitle: {}\n---\nclassDiagram", title),
+            None => "classDiagram".to_string(),
+        };
+        let mut skipped = std::mem::take(&mut self.out.skipped);
+        skipped.sort_by_key(|skipped| skipped.line);
+        ImportedDiagram::new(self.out.finish(&header), skipped)
+    }
+
+    fn in_namespace(&self) -> bool {
+        self.blocks.contains(&"namespace")
+    }
+
+    /// Write a statement that may not appear inside a namespace
+    fn outside(&mut self, text: String) {
+        match self.in_namespace() {
+            true => self.deferred.push(text),
+            false => self.out.push(text),
+        }
+    }
+
+    fn close(&mut self) {
+        match self.blocks.pop() {
+            Some("namespace") => {
+                self.out.depth -= 1;
+                self.out.push("}");
+                if !self.in_namespace() {
+                    for statement in std::mem::take(&mut self.deferred) {
+                        self.out.push(statement);
+                    }
+                }
+            }
+            Some(_) => {}
+            None => {}
+        }
+    }
+
+    fn statement(
+        &mut self,
+        number: usize,
+        line: &str,
+        lines: &[(usize, String)],
+        index: &mut usize,
+    ) {
+        let keyword = first_word(line).to_ascii_lowercase();
+        let keyword = keyword.as_str();
+        let rest = line[keyword.len()..].trim();
+        let class_kind = CLASS_KINDS.iter().find(|&&(kind, _)| kind == keyword);
+
+        match keyword {
+            "title" => self.title = Some(rest.to_string()),
+            "left" if line == "left to right direction" => self.out.push("direction LR"),
+            "top" if line == "top to bottom direction" => self.out.push("direction TB"),
+            "}" => self.close(),
+            "together" => {
+                self.blocks.push("together");
+                self.out.skip(
+                    number,
+                    line,
+                    "Layout groups are dropped; their classes are kept",
+                );
+            }
+            _ if PACKAGE_KINDS.contains(&keyword) => self.package(number, line, rest),
+            "note" => self.note(number, line, rest, lines, index),
+            _ if class_kind.is_some() && parse_relation(line).is_none() => {
+                let (kind, annotation) = *class_kind.unwrap_or(&("class", None));
+                // `abstract class X` and `abstract X` are the same
+                let rest = match kind {
+                    "abstract" => rest.strip_prefix("class ").unwrap_or(rest).trim(),
+                    _ => rest,
+                };
+                self.class(number, line, rest, annotation, lines, index);
+            }
+            _ => {
+                if let Some(relation) = parse_relation(line) {
+                    self.relation(number, line, &relation);
+                } else if let Some((name, member)) = line.split_once(':') {
+                    let id = self.reference(name);
+                    if let Some(member) = class_member(member) {
+                        self.outside(format!("{} : {}", id, member));
+                    }
+                } else {
+                    self.out
+                        .skip(number, line, "Not a recognised class diagram statement");
+                }
+            }
+        }
+    }
+
+    fn package(&mut self, number: usize, line: &str, rest: &str) {
+        let Some(head) = rest.strip_suffix('{') else {
+            self.out
+                .skip(number, line, "Packages without braces are not converted");
+            return;
+        };
+        if self.in_namespace() {
+            self.blocks.push("flattened");
+            self.out.skip(
+                number,
+                line,
+                "Nested packages are merged into the outer namespace",
+            );
+            return;
+        }
+        let (head, decorated) = strip_decorations(head);
+        if decorated {
+            self.out
+                .skip(number, line, "Package colours and stereotypes are dropped");
+        }
+        let name = match head.split_once(" as ") {
+            Some((_, alias)) => alias,
+            None => head,
+        };
+        let name: String = unquote(name)
+            .chars()
+            .map(|c| match c.is_alphanumeric() || c == '_' || c == '.' {
+                true => c,
+                false => '_',
+            })
+            .collect();
+        self.out.push(format!("namespace {} {{", name));
+        self.out.depth += 1;
+        self.blocks.push("namespace");
+    }
+
+    /// The Mermaid id for a class name, declaring the class on first use when
+    /// its name is not a valid id
+    fn reference(&mut self, name: &str) -> String {
+        let display = unquote(name);
+        let display = display.split('<').next().unwrap_or(display).trim();
+        if let Some(id) = self.names.get(display) {
+            return id.clone();
+        }
+        let id = mermaid_id(display);
+        self.names.insert(display.to_string(), id.clone());
+        if id != display {
+            self.out
+                .push(format!("class {}[\"{}\"]", id, display.replace('"', "'")));
+        }
+        id
+    }
+
+    fn class(
+        &mut self,
+        number: usize,
+        line: &str,
+        rest: &str,
+        annotation: Option<&str>,
+        lines: &[(usize, String)],
+        index: &mut usize,
+    ) {
+        let Some((name, mut after)) = split_name(rest) else {
+            self.out.skip(number, line, "Class without a name");
+            return;
+        };
+
+        let mut generic = None;
+        if let Some(open) = after
+            .strip_prefix('<')
+            .filter(|text| !text.starts_with('<'))
+        {
+            if let Some(close) = open.find('>') {
+                generic = Some(&open[..close]);
+                after = &open[close + 1..];
+            }
+        }
+
+        let mut label = None;
+        let mut id_name = unquote(name).to_string();
+        let mut annotations: Vec<String> = annotation.map(str::to_string).into_iter().collect();
+        let mut parents = Vec::new();
+        let mut body = None;
+        let mut after = after.trim();
+        while !after.is_empty() {
+            if let Some(alias) = after.strip_prefix("as ") {
+                let (alias, next) = split_name(alias).unwrap_or((alias, ""));
+                match alias.starts_with('"') {
+                    true => label = Some(unquote(alias).to_string()),
+                    false => {
+                        label = Some(unquote(name).to_string());
+                        id_name = alias.to_string();
+                    }
+                }
+                after = next.trim();
+            } else if let Some(stereotype) = after.strip_prefix("<<") {
+                let end = stereotype.find(">>").unwrap_or(stereotype.len());
+                annotations.push(stereotype[..end].trim().to_string());
+                after = stereotype.get(end + 2..).unwrap_or("").trim();
+            } else if after.starts_with('#') {
+                self.out.skip(number, line, "Class colours are dropped");
+                let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                after = after[end..].trim();
+            } else if let Some(open) = after.strip_prefix('{') {
+                body = Some(match open.find('}') {
+                    Some(close) => vec![open[..close].trim()],
+                    None => block_until(lines, index, |l| l.starts_with('}')),
+                });
+                after = "";
+            } else if let Some(list) = after
+                .strip_prefix("extends ")
+                .map(|list| ("<|--", list))
+                .or_else(|| after.strip_prefix("implements ").map(|list| ("<|..", list)))
+            {
+                let (arrow, list) = list;
+                let end = [" implements ", " extends "]
+                    .iter()
+                    .filter_map(|keyword| list.find(keyword))
+                    .chain(list.find(['{', '#', '<']))
+                    .min()
+                    .unwrap_or(list.len());
+                for parent in list[..end]
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|p| !p.is_empty())
+                {
+                    parents.push((arrow, parent.to_string()));
+                }
+                after = list[end..].trim();
+            } else {
+                self.out.skip(
+                    number,
+                    line,
+                    "Unrecognised class declaration details are dropped",
+                );
+                break;
+            }
+        }
+
+        let id = mermaid_id(&id_name);
+        if label.is_none() && id != id_name {
+            label = Some(id_name.clone());
+        }
+        self.names.insert(id_name.clone(), id.clone());
+        if let Some(label) = &label {
+            self.names.insert(label.clone(), id.clone());
+        }
+
+        let mut header = format!("class {}", id);
+        if let Some(generic) = generic {
+            header.push_str(&format!("~{}~", generic));
+        }
+        if let Some(label) = &label {
+            header.push_str(&format!("[\"{}\"]", label.replace('"', "'")));
+        }
+        let members: Vec<String> = body
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(class_member)
+            .collect();
+        if members.is_empty() && annotations.is_empty() {
+            self.out.push(header);
+        } else {
+            self.out.push(format!("{} {{", header));
+            for annotation in &annotations {
+                self.out.push(format!("    <<{}>>", annotation));
+            }
+            for member in &members {
+                self.out.push(format!("    {}", member));
+            }
+            self.out.push("}");
+        }
+
+        for (arrow, parent) in parents {
+            let parent = self.reference(&parent);
+            self.outside(format!("{} {} {}", parent, arrow, id));
+        }
+    }
+
+    fn relation(&mut self, number: usize, line: &str, relation: &PlantRelation) {
+        if self
+            .notes
+            .iter()
+            .any(|note| note == relation.left || note == relation.right)
+        {
+            self.out
+                .skip(number, line, "Links between notes and classes are dropped");
+            return;
+        }
+        let Some((arrow, lost)) = class_arrow(relation.arrow) else {
+            self.out
+                .skip(number, line, "Hidden layout relations are dropped");
+            return;
+        };
+        if let Some(reason) = lost {
+            self.out.skip(number, line, reason);
+        }
+
+        let left = self.reference(relation.left);
+        let right = self.reference(relation.right);
+        let mut statement = left;
+        if let Some(cardinality) = relation.left_label {
+            statement.push_str(&format!(" {}", cardinality));
+        }
+        statement.push_str(&format!(" {} ", arrow));
+        if let Some(cardinality) = relation.right_label {
+            statement.push_str(&format!("{} ", cardinality));
+        }
+        statement.push_str(&right);
+        // Reading direction markers such as `: owns >` have no equivalent
+        let label = relation
+            .label
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .trim();
+        if !label.is_empty() {
+            statement.push_str(&format!(" : {}", mermaid_text(label)));
+        }
+        self.outside(statement);
+    }
+
+    fn note(
+        &mut self,
+        number: usize,
+        line: &str,
+        rest: &str,
+        lines: &[(usize, String)],
+        index: &mut usize,
+    ) {
+        let is_end = |l: &str| l.replace(' ', "") == "endnote";
+        if rest.starts_with("on link") {
+            if !rest.contains(':') {
+                block_until(lines, index, is_end);
+            }
+            self.out.skip(
+                number,
+                line,
+                "Notes on relations have no Mermaid equivalent",
+            );
+            return;
+        }
+
+        // Floating notes: `note "text" as N1` or a block ending in `as N1`
+        let (head, text) = match rest.split_once(':') {
+            Some((head, text)) => (head.trim(), Some(text.trim().to_string())),
+            None => (rest, None),
+        };
+        let (quoted, after) = split_quoted(head);
+        if let Some(quoted) = quoted {
+            if let Some(alias) = after.trim().strip_prefix("as ") {
+                self.notes.push(alias.trim().to_string());
+            }
+            self.outside(format!("note \"{}\"", note_text(unquote(quoted))));
+            return;
+        }
+        let text = text.unwrap_or_else(|| block_until(lines, index, is_end).join("\\n"));
+        if let Some(alias) = head.strip_prefix("as ") {
+            self.notes.push(alias.trim().to_string());
+            self.outside(format!("note \"{}\"", note_text(&text)));
+            return;
+        }
+
+        let (head, decorated) = strip_decorations(head);
+        if decorated {
+            self.out.skip(number, line, "Note colours are dropped");
+        }
+        let target = head
+            .split_once(" of ")
+            .map(|(_, target)| target.trim())
+            .filter(|target| !target.is_empty());
+        match target {
+            Some(target) => {
+                let id = self.reference(target);
+                self.outside(format!("note for {} \"{}\"", id, note_text(&text)));
+            }
+            None => self
+                .out
+                .skip(number, line, "Notes need a class to attach to"),
+        }
+    }
+}
+
+/// Note text for a double-quoted Mermaid string
+fn note_text(text: &str) -> String {
+    text.trim().replace('"', "'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_sequence() {
+        let imported = import_plantuml(
+            r#"@startuml
+title Checkout
+actor Customer as C
+participant "Payment\nService" as Pay #LightBlue
+database Orders
+' the happy path
+C -> Pay ++ : pay(order)
+Pay -> Orders : save
+Orders --> Pay
+alt card declined
+  Pay -->> C : retry
+else ok
+  Pay --> C -- : receipt
+end
+note right of Pay : idempotent
+note over C, Orders
+  spans
+  everyone
+end note
+== Later ==
+C <- Pay : <b>newsletter</b>
+[-> C : wake up
+@enduml"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            imported.code,
+            [
+                "sequenceDiagram",
+                "    title Checkout",
+                "    actor C as Customer",
+                "    participant Pay as Payment<br/>Service",
+                "    participant Orders",
+                "    %% the happy path",
+                "    C->>+Pay: pay(order)",
+                "    Pay->>Orders: save",
+                "    Orders-->>Pay:",
+                "    alt card declined",
+                "        Pay--)C: retry",
+                "    else ok",
+                "        Pay-->>-C: receipt",
+                "    end",
+                "    Note right of Pay: idempotent",
+                "    Note over C,Orders: spans<br/>everyone",
+                "    Note over C,Orders: Later",
+                "    Pay->>C: <b>newsletter</b>",
+                "",
+            ]
+            .join("\n")
+        );
+        let reasons: Vec<(usize, &str)> = imported
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.line, skipped.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (4, "Participant colours and stereotypes are dropped"),
+                (5, "database participants are shown as plain participants"),
+                (20, "Dividers are shown as a note over all participants"),
+                (
+                    22,
+                    "Messages from or to outside the diagram have no Mermaid equivalent"
+                ),
+            ]
+        );
+        assert!(crate::services::parse_diagram(&imported.code).is_ok());
+    }
+
+    #[test]
+    fn test_import_class() {
+        let imported = import_plantuml(
+            r#"@startuml
+left to right direction
+package shop {
+  abstract class Product <<Entity>> {
+    -id : UUID
+    +{abstract} price() : Money
+    --
+    {static} +create(name: String) : Product
+  }
+  interface Sellable
+  class Book extends Product implements Sellable
+}
+enum Status {
+  OPEN
+  CLOSED
+}
+class "Order Line" as Line
+Line "1" *-- "many" Book : contains >
+Book -[hidden]- Status
+Book -up-> Status
+Book : +isbn : String
+note left of Book : paperback\nonly
+skinparam classAttributeIconSize 0
+@enduml"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            imported.code,
+            [
+                "classDiagram",
+                "    direction LR",
+                "    namespace shop {",
+                "        class Product {",
+                "            <<abstract>>",
+                "            <<Entity>>",
+                "            -UUID id",
+                "            +price() Money*",
+                "            +create(name: String) Product$",
+                "        }",
+                "        class Sellable {",
+                "            <<interface>>",
+                "        }",
+                "        class Book",
+                "    }",
+                "    Product <|-- Book",
+                "    Sellable <|.. Book",
+                "    class Status {",
+                "        <<enumeration>>",
+                "        OPEN",
+                "        CLOSED",
+                "    }",
+                "    class Line[\"Order Line\"]",
+                "    Line \"1\" *-- \"many\" Book : contains",
+                "    Book --> Status",
+                "    Book : +String isbn",
+                "    note for Book \"paperback\\nonly\"",
+                "",
+            ]
+            .join("\n")
+        );
+        let reasons: Vec<(usize, &str)> = imported
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.line, skipped.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (19, "Hidden layout relations are dropped"),
+                (23, "Styling and layout directives are not converted"),
+            ]
+        );
+
+        assert!(import_plantuml("@startmindmap\n* root\n@endmindmap").is_err());
+        assert!(import_plantuml("@startuml\nstart\n:step;\nstop\n@enduml").is_err());
+    }
+}
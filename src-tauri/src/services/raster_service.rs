@@ -0,0 +1,338 @@
+use crate::models::{ColorSpace, DiagramFormat, DiagramOptions, TiffCompression};
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+use resvg::tiny_skia::{Color, Pixmap, Transform};
+use resvg::usvg::{fontdb, Options, Tree};
+use std::io::Cursor;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use tiff::encoder::{colortype, Compression, DeflateLevel, Rational, TiffEncoder};
+use tiff::tags::ResolutionUnit;
+
+const DEFAULT_QUALITY: u8 = 90;
+/// rav1e speed from 1 (slowest) to 10; 6 keeps exports under a second or two
+const AVIF_SPEED: u8 = 6;
+/// Upper bound on either side of the output image, to keep memory in check
+const MAX_DIMENSION: f32 = 16384.0;
+
+/// System fonts are loaded once; scanning them takes noticeably long
+pub(crate) fn font_database() -> Arc<fontdb::Database> {
+    static FONTS: OnceLock<Arc<fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut database = fontdb::Database::new();
+            database.load_system_fonts();
+            database.set_sans_serif_family("Arial");
+            log::info!("Loaded {} font faces for rasterization", database.len());
+            Arc::new(database)
+        })
+        .clone()
+}
+
+/// Parse a CSS colour such as `white`, `#0f0f23` or `rgba(0,0,0,0.5)`.
+/// Returns `None` for `transparent` and empty strings.
+pub fn parse_background(background: &str) -> Result<Option<Color>, String> {
+    let background = background.trim();
+    if background.is_empty() || background.eq_ignore_ascii_case("transparent") {
+        return Ok(None);
+    }
+
+    let color = svgtypes::Color::from_str(background)
+        .map_err(|_| format!("Invalid background colour: {}", background))?;
+    Ok(Some(Color::from_rgba8(
+        color.red,
+        color.green,
+        color.blue,
+        color.alpha,
+    )))
+}
+
+/// Parse SVG markup with the shared system font database
+pub fn parse_svg(svg: &str) -> Result<Tree, String> {
+    let usvg_options = Options {
+        fontdb: font_database(),
+        ..Options::default()
+    };
+    Tree::from_str(svg, &usvg_options).map_err(|e| format!("Failed to parse SVG: {}", e))
+}
+
+/// Rasterize SVG markup into an image pixmap, returned with the scale it was
+/// drawn at after `width` and the size limit are applied
+pub fn render_pixmap(svg: &str, options: &DiagramOptions) -> Result<(Pixmap, f32), String> {
+    let tree = parse_svg(svg)?;
+
+    let size = tree.size();
+    let scale = match (options.scale, options.width) {
+        (Some(scale), _) if scale > 0.0 => scale,
+        (_, Some(width)) if width > 0 => width as f32 / size.width(),
+        _ => 1.0,
+    };
+    let scale = scale
+        .min(MAX_DIMENSION / size.width())
+        .min(MAX_DIMENSION / size.height());
+
+    let width = (size.width() * scale).ceil().max(1.0) as u32;
+    let height = (size.height() * scale).ceil().max(1.0) as u32;
+    let mut pixmap = Pixmap::new(width, height)
+        .ok_or_else(|| format!("Cannot allocate a {}x{} image", width, height))?;
+
+    if let Some(color) = parse_background(&options.background)? {
+        pixmap.fill(color);
+    }
+
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    Ok((pixmap, scale))
+}
+
+/// Rasterize SVG markup and encode it in the requested image format
+pub fn rasterize_svg(svg: &str, options: &DiagramOptions) -> Result<Vec<u8>, String> {
+    let (pixmap, scale) = render_pixmap(svg, options)?;
+    encode_pixmap(&pixmap, scale, options)
+}
+
+/// Straight (non-premultiplied) RGBA pixels of a pixmap
+fn rgba_pixels(pixmap: &Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+/// RGB pixels composited over white, for formats without an alpha channel
+fn rgb_pixels(pixmap: &Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            // Premultiplied channels only need the uncovered remainder added
+            let white = 255 - pixel.alpha();
+            [
+                pixel.red().saturating_add(white),
+                pixel.green().saturating_add(white),
+                pixel.blue().saturating_add(white),
+            ]
+        })
+        .collect()
+}
+
+/// Luminance of an RGB colour (ITU-R BT.601 weights)
+pub fn to_gray(red: u8, green: u8, blue: u8) -> u8 {
+    (0.299 * red as f32 + 0.587 * green as f32 + 0.114 * blue as f32).round() as u8
+}
+
+/// Naive device CMYK conversion, without an ICC profile
+pub fn to_cmyk(red: u8, green: u8, blue: u8) -> [u8; 4] {
+    let (r, g, b) = (
+        red as f32 / 255.0,
+        green as f32 / 255.0,
+        blue as f32 / 255.0,
+    );
+    let key = 1.0 - r.max(g).max(b);
+    if key >= 1.0 {
+        return [0, 0, 0, 255];
+    }
+    let channel = |value: f32| ((1.0 - value - key) / (1.0 - key) * 255.0).round() as u8;
+    [
+        channel(r),
+        channel(g),
+        channel(b),
+        (key * 255.0).round() as u8,
+    ]
+}
+
+/// Encode a pixmap drawn at `scale` in the requested image format
+pub fn encode_pixmap(
+    pixmap: &Pixmap,
+    scale: f32,
+    options: &DiagramOptions,
+) -> Result<Vec<u8>, String> {
+    let (width, height) = (pixmap.width(), pixmap.height());
+    let format = &options.format;
+    let lossless = options.lossless.unwrap_or(false);
+    let mut buffer = Vec::new();
+
+    let result = match format {
+        DiagramFormat::Png => PngEncoder::new(&mut buffer).write_image(
+            &rgba_pixels(pixmap),
+            width,
+            height,
+            ExtendedColorType::Rgba8,
+        ),
+        DiagramFormat::Jpg => {
+            let quality = options.quality.unwrap_or(DEFAULT_QUALITY).clamp(1, 100);
+            JpegEncoder::new_with_quality(&mut buffer, quality).write_image(
+                &rgb_pixels(pixmap),
+                width,
+                height,
+                ExtendedColorType::Rgb8,
+            )
+        }
+        DiagramFormat::Avif => {
+            // ravif has no true lossless mode; maximum quality is the closest match
+            let quality = if lossless {
+                100
+            } else {
+                options.quality.unwrap_or(DEFAULT_QUALITY).clamp(1, 100)
+            };
+            AvifEncoder::new_with_speed_quality(&mut buffer, AVIF_SPEED, quality).write_image(
+                &rgba_pixels(pixmap),
+                width,
+                height,
+                ExtendedColorType::Rgba8,
+            )
+        }
+        // The pure-Rust WebP encoder is lossless only, which suits flat diagram colours
+        DiagramFormat::Webp => {
+            if options.quality.is_some() || options.lossless == Some(false) {
+                return Err(
+                    "WebP export is lossless only; leave quality unset for WebP".to_string()
+                );
+            }
+            WebPEncoder::new_lossless(&mut buffer).write_image(
+                &rgba_pixels(pixmap),
+                width,
+                height,
+                ExtendedColorType::Rgba8,
+            )
+        }
+        DiagramFormat::Tiff => return encode_tiff(pixmap, scale, options),
+        other => return Err(format!("{} is not a raster format", other)),
+    };
+
+    result.map_err(|e| format!("Failed to encode {}: {}", format, e))?;
+    Ok(buffer)
+}
+
+fn encode_tiff(pixmap: &Pixmap, scale: f32, options: &DiagramOptions) -> Result<Vec<u8>, String> {
+    let compression = match options.compression.unwrap_or(TiffCompression::Lzw) {
+        TiffCompression::None => Compression::Uncompressed,
+        TiffCompression::Lzw => Compression::Lzw,
+        TiffCompression::Deflate => Compression::Deflate(DeflateLevel::Balanced),
+        TiffCompression::Packbits => Compression::Packbits,
+    };
+    // Record the effective resolution so print layouts get the intended size
+    let dpi = (96.0 * scale).round().max(1.0) as u32;
+
+    let (width, height) = (pixmap.width(), pixmap.height());
+    let mut cursor = Cursor::new(Vec::new());
+    let mut encoder = TiffEncoder::new(&mut cursor)
+        .map_err(|e| format!("Failed to create TIFF encoder: {}", e))?
+        .with_compression(compression);
+
+    fn write<C: colortype::ColorType<Inner = u8>>(
+        encoder: &mut TiffEncoder<&mut Cursor<Vec<u8>>>,
+        width: u32,
+        height: u32,
+        dpi: u32,
+        data: &[u8],
+    ) -> tiff::TiffResult<()> {
+        let mut image = encoder.new_image::<C>(width, height)?;
+        image.resolution(ResolutionUnit::Inch, Rational { n: dpi, d: 1 });
+        image.write_data(data)
+    }
+
+    let result = match options.color_space.unwrap_or(ColorSpace::Rgb) {
+        ColorSpace::Rgb => {
+            write::<colortype::RGBA8>(&mut encoder, width, height, dpi, &rgba_pixels(pixmap))
+        }
+        ColorSpace::Gray => {
+            let gray: Vec<u8> = rgb_pixels(pixmap)
+                .chunks_exact(3)
+                .map(|p| to_gray(p[0], p[1], p[2]))
+                .collect();
+            write::<colortype::Gray8>(&mut encoder, width, height, dpi, &gray)
+        }
+        ColorSpace::Cmyk => {
+            let cmyk: Vec<u8> = rgb_pixels(pixmap)
+                .chunks_exact(3)
+                .flat_map(|p| to_cmyk(p[0], p[1], p[2]))
+                .collect();
+            write::<colortype::CMYK8>(&mut encoder, width, height, dpi, &cmyk)
+        }
+    };
+
+    result.map_err(|e| format!("Failed to encode tiff: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20" viewBox="0 0 40 20"><rect x="0" y="0" width="20" height="20" fill="#ff0000"/></svg>"##;
+
+    fn options(format: DiagramFormat, background: &str) -> DiagramOptions {
+        DiagramOptions {
+            format,
+            background: background.to_string(),
+            ..DiagramOptions::svg(None)
+        }
+    }
+
+    #[test]
+    fn test_scale_and_background() {
+        let mut options = options(DiagramFormat::Png, "#0000ff");
+        options.scale = Some(2.0);
+        let (pixmap, scale) = render_pixmap(SVG, &options).unwrap();
+        assert_eq!(scale, 2.0);
+
+        assert_eq!((pixmap.width(), pixmap.height()), (80, 40));
+        let left = pixmap.pixel(5, 5).unwrap();
+        let right = pixmap.pixel(75, 5).unwrap();
+        assert_eq!((left.red(), left.blue()), (255, 0));
+        assert_eq!((right.red(), right.blue(), right.alpha()), (0, 255, 255));
+    }
+
+    #[test]
+    fn test_encoders() {
+        let png = rasterize_svg(SVG, &options(DiagramFormat::Png, "transparent")).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+
+        let jpg = rasterize_svg(SVG, &options(DiagramFormat::Jpg, "transparent")).unwrap();
+        assert!(jpg.starts_with(&[0xff, 0xd8]));
+
+        let mut webp_options = options(DiagramFormat::Webp, "white");
+        let webp = rasterize_svg(SVG, &webp_options).unwrap();
+        assert_eq!(&webp[8..12], b"WEBP");
+        webp_options.quality = Some(80);
+        assert!(rasterize_svg(SVG, &webp_options).is_err());
+
+        let mut tiff_options = options(DiagramFormat::Tiff, "white");
+        tiff_options.color_space = Some(ColorSpace::Cmyk);
+        tiff_options.compression = Some(TiffCompression::Deflate);
+        let tiff = rasterize_svg(SVG, &tiff_options).unwrap();
+        assert!(tiff.starts_with(b"II*\0") || tiff.starts_with(b"MM\0*"));
+
+        // The DPI follows the scale a requested width implies
+        tiff_options.width = Some(80);
+        let tiff = rasterize_svg(SVG, &tiff_options).unwrap();
+        let mut decoder = tiff::decoder::Decoder::new(Cursor::new(tiff)).unwrap();
+        assert_eq!(
+            decoder
+                .get_tag_u32_vec(tiff::tags::Tag::XResolution)
+                .unwrap(),
+            [192, 1]
+        );
+
+        assert!(parse_background("not-a-colour").is_err());
+    }
+
+    #[test]
+    fn test_color_conversion() {
+        assert_eq!(to_cmyk(255, 255, 255), [0, 0, 0, 0]);
+        assert_eq!(to_cmyk(0, 0, 0), [0, 0, 0, 255]);
+        assert_eq!(to_cmyk(255, 0, 0), [0, 255, 255, 0]);
+        assert_eq!(to_gray(255, 255, 255), 255);
+    }
+}
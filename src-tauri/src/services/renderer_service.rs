@@ -0,0 +1,586 @@
+use crate::models::{
+    DiagramFormat, DiagramOptions, DiagramResult, RenderWorkerSettings, RenderWorkerStatus,
+    RendererBackend, RendererCapabilities, RendererInfo, RendererSettings, SvgProcessOptions,
+};
+use crate::services::kroki_service::KrokiRenderer;
+use crate::services::mmdc_service::{mmdc_background, read_css};
+use crate::services::native_service::NativeRenderer;
+use crate::services::worker_service::WorkerRenderer;
+use crate::services::MmdcService;
+use crate::services::{
+    dot_service, drawio_service, eps_service, excalidraw_service, html_service, raster_service,
+    svg_service, text_service, theme_service,
+};
+use crate::utils::{ensure_directory_exists, DefaultDirectories, TempFile};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+const SETTINGS_FILE: &str = "renderer-settings.json";
+
+/// Mermaid config for SVGs that get rasterized in-process: resvg cannot draw
+/// HTML labels inside `<foreignObject>`, so labels must be plain SVG text
+fn raster_config() -> Value {
+    json!({
+        "htmlLabels": false,
+        "flowchart": { "htmlLabels": false },
+        "class": { "htmlLabels": false }
+    })
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    /// The renderer does not handle this diagram; try the next one
+    Unsupported(String),
+    /// The renderer cannot run right now; try the next one
+    Unavailable(String),
+    /// The diagram itself failed to render
+    Failed(String),
+}
+
+/// One diagram to render in a format the renderer produces directly
+pub struct RenderJob<'a> {
+    pub code: &'a str,
+    /// Mermaid diagram type from `diagram_type`
+    pub diagram_type: Option<&'a str>,
+    /// `Svg` or `Pdf`
+    pub format: DiagramFormat,
+    pub options: &'a DiagramOptions,
+    /// Mermaid config merged over the options' own
+    pub extra_config: Option<&'a Value>,
+}
+
+pub trait Renderer {
+    fn backend(&self) -> RendererBackend;
+
+    fn capabilities(&self) -> RendererCapabilities;
+
+    /// Whether the renderer can currently be used, without rendering
+    fn availability(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Render the job to file contents in `job.format`
+    fn render(&mut self, job: &RenderJob) -> Result<Vec<u8>, RenderError>;
+}
+
+/// Mermaid diagram type from the first statement of the source, e.g.
+/// `flowchart` for `graph LR` or `sequence` for `sequenceDiagram`
+pub fn diagram_type(code: &str) -> Option<String> {
+    let mut lines = code.lines().map(str::trim);
+    let mut line = lines.next()?;
+
+    // Skip YAML front matter
+    if line == "---" {
+        lines.find(|line| *line == "---")?;
+        line = lines.next()?;
+    }
+
+    std::iter::once(line)
+        .chain(lines)
+        .find(|line| !line.is_empty() && !line.starts_with("%%"))
+        .and_then(|line| line.split_whitespace().next())
+        .map(|keyword| {
+            let keyword = keyword.trim_end_matches(':').to_ascii_lowercase();
+            let keyword = keyword
+                .trim_end_matches("-v2")
+                .trim_end_matches("-beta")
+                .trim_end_matches("diagram");
+            match keyword {
+                "graph" | "flowchart-elk" => "flowchart".to_string(),
+                keyword => keyword.to_string(),
+            }
+        })
+}
+
+/// Renders diagrams through the configured chain of renderers and converts
+/// their SVG into the other export formats
+pub struct RenderService {
+    settings: RendererSettings,
+    /// Where settings are saved; `None` keeps them in memory only
+    settings_path: Option<PathBuf>,
+    mmdc: MmdcService,
+    worker: WorkerRenderer,
+    kroki: KrokiRenderer,
+    native: NativeRenderer,
+}
+
+impl RenderService {
+    /// Renderers configured from the settings saved in the app data directory
+    pub fn new() -> Self {
+        let settings_path = DefaultDirectories::app_storage().map(|dir| dir.join(SETTINGS_FILE));
+        let settings = settings_path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| match serde_json::from_str(&content) {
+                Ok(settings) => Some(settings),
+                Err(e) => {
+                    log::warn!("Ignoring invalid renderer settings: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self::with_settings(settings, settings_path)
+    }
+
+    pub fn with_settings(settings: RendererSettings, settings_path: Option<PathBuf>) -> Self {
+        Self {
+            mmdc: MmdcService::new(),
+            worker: WorkerRenderer::new(settings.worker.clone()),
+            kroki: KrokiRenderer::new(settings.kroki.clone()),
+            native: NativeRenderer,
+            settings,
+            settings_path,
+        }
+    }
+
+    pub fn settings(&self) -> &RendererSettings {
+        &self.settings
+    }
+
+    /// Apply and save new renderer settings
+    pub fn configure(&mut self, settings: RendererSettings) -> Result<(), String> {
+        if let Some(ref path) = self.settings_path {
+            if let Some(parent) = path.parent() {
+                ensure_directory_exists(parent)?;
+            }
+            let content = serde_json::to_string_pretty(&settings)
+                .map_err(|e| format!("Failed to serialize renderer settings: {}", e))?;
+            std::fs::write(path, content)
+                .map_err(|e| format!("Failed to save renderer settings: {}", e))?;
+        }
+
+        self.worker.configure(settings.worker.clone());
+        self.kroki = KrokiRenderer::new(settings.kroki.clone());
+        self.settings = settings;
+        Ok(())
+    }
+
+    /// The one-shot mmdc renderer, which also owns mmdc discovery
+    pub fn mmdc(&mut self) -> &mut MmdcService {
+        &mut self.mmdc
+    }
+
+    pub fn configure_worker(&mut self, worker: RenderWorkerSettings) -> Result<(), String> {
+        self.configure(RendererSettings {
+            worker,
+            ..self.settings.clone()
+        })
+    }
+
+    pub fn worker_status(&mut self) -> RenderWorkerStatus {
+        self.worker.status()
+    }
+
+    /// Start the worker now, or restart it if it is running
+    pub fn restart_worker(&mut self) -> Result<RenderWorkerStatus, String> {
+        if !self.settings.worker.enabled {
+            return Err("The render worker is disabled".to_string());
+        }
+        self.mmdc.locate()?;
+        self.sync_worker();
+        self.worker.restart()
+    }
+
+    /// Every renderer with its capabilities, in fallback order first
+    pub fn renderers(&mut self) -> Vec<RendererInfo> {
+        let mut backends = self.settings.fallback_chain();
+        for backend in [
+            RendererBackend::Worker,
+            RendererBackend::Mmdc,
+            RendererBackend::Kroki,
+            RendererBackend::Native,
+        ] {
+            if !backends.contains(&backend) {
+                backends.push(backend);
+            }
+        }
+
+        backends
+            .into_iter()
+            .map(|backend| {
+                let renderer = self.renderer(backend);
+                let availability = renderer.availability();
+                RendererInfo {
+                    backend: renderer.backend(),
+                    capabilities: renderer.capabilities(),
+                    available: availability.is_ok(),
+                    reason: availability.err(),
+                }
+            })
+            .collect()
+    }
+
+    /// Render with the first renderer in the chain that can handle the
+    /// diagram, returning the content and the renderer used
+    pub fn render(
+        &mut self,
+        code: &str,
+        options: &DiagramOptions,
+        format: DiagramFormat,
+        extra_config: Option<&Value>,
+    ) -> Result<(Vec<u8>, RendererBackend), String> {
+        let diagram_type = diagram_type(code);
+        let job = RenderJob {
+            code,
+            diagram_type: diagram_type.as_deref(),
+            format,
+            options,
+            extra_config,
+        };
+
+        let chain = match options.renderer {
+            Some(backend) => vec![backend],
+            None => self.settings.fallback_chain(),
+        };
+        let mut skipped = Vec::new();
+        for requested in chain {
+            let renderer = self.renderer(requested);
+            let backend = renderer.backend();
+            if !renderer
+                .capabilities()
+                .supports(&job.format, job.diagram_type)
+            {
+                skipped.push(format!(
+                    "{}: cannot render {} diagrams to {}",
+                    backend,
+                    job.diagram_type.unwrap_or("unknown"),
+                    job.format
+                ));
+                continue;
+            }
+
+            match renderer.render(&job) {
+                Ok(content) => {
+                    log::debug!("Rendered {} with {}", job.format, backend);
+                    return Ok((content, backend));
+                }
+                Err(RenderError::Failed(e)) => return Err(e),
+                Err(RenderError::Unsupported(e)) | Err(RenderError::Unavailable(e)) => {
+                    log::info!("Skipping {} renderer: {}", backend, e);
+                    skipped.push(format!("{}: {}", backend, e));
+                }
+            }
+        }
+
+        Err(format!(
+            "No renderer could render the diagram:\n{}",
+            skipped.join("\n")
+        ))
+    }
+
+    /// Generate diagram from mermaid code
+    pub fn generate_diagram(
+        &mut self,
+        code: &str,
+        options: &DiagramOptions,
+        output_path: &Path,
+    ) -> DiagramResult {
+        let start_time = Instant::now();
+        let options = &match theme_service::resolve_theme_pack(options) {
+            Ok(options) => options,
+            Err(e) => return DiagramResult::error(e, 0),
+        };
+        if options.format.is_source() {
+            return write_output(render_source_output(code, options), output_path, start_time);
+        }
+        let post_processed_svg =
+            matches!(options.format, DiagramFormat::Svg) && options.svg_processing.is_some();
+        if options.format.is_derived_from_svg() || post_processed_svg {
+            return self
+                .generate_formats(
+                    code,
+                    options,
+                    &[(options.format.clone(), output_path.to_path_buf())],
+                )
+                .remove(0);
+        }
+
+        let rendered = self.render(code, options, options.format.clone(), None);
+        write_output(rendered, output_path, start_time)
+    }
+
+    /// Generate several formats from as few renders as possible. Raster and
+    /// EPS formats share one SVG with plain text labels, while SVG and HTML
+    /// share one that keeps mermaid's HTML labels; only PDF needs its own
+    /// render, and source formats such as DOT are written without one.
+    pub fn generate_formats(
+        &mut self,
+        code: &str,
+        options: &DiagramOptions,
+        outputs: &[(DiagramFormat, PathBuf)],
+    ) -> Vec<DiagramResult> {
+        let start_time = Instant::now();
+
+        let options = &match theme_service::resolve_theme_pack(options) {
+            Ok(options) => options,
+            Err(e) => {
+                return outputs
+                    .iter()
+                    .map(|_| DiagramResult::error(e.clone(), 0))
+                    .collect()
+            }
+        };
+
+        let is_markup =
+            |format: &DiagramFormat| matches!(format, DiagramFormat::Svg | DiagramFormat::Html);
+        let needs_raster_svg = outputs.iter().any(|(format, _)| {
+            !matches!(format, DiagramFormat::Pdf) && !format.is_source() && !is_markup(format)
+        });
+        let raster_svg = if needs_raster_svg {
+            Some(self.render_svg_with(code, options, Some(&raster_config())))
+        } else {
+            None
+        };
+        let markup_svg = if outputs.iter().any(|(format, _)| is_markup(format)) {
+            Some(self.render_svg_with(code, options, None))
+        } else {
+            None
+        };
+
+        outputs
+            .iter()
+            .map(|(format, output_path)| {
+                let format_options = DiagramOptions {
+                    format: format.clone(),
+                    ..options.clone()
+                };
+
+                if matches!(format, DiagramFormat::Pdf) {
+                    let rendered = self.render(code, &format_options, DiagramFormat::Pdf, None);
+                    return write_output(rendered, output_path, start_time);
+                }
+                if format.is_source() {
+                    let rendered = render_source_output(code, &format_options);
+                    return write_output(rendered, output_path, start_time);
+                }
+
+                // Post-processing applies to the SVG and HTML outputs only
+                let svg = if is_markup(format) {
+                    &markup_svg
+                } else {
+                    &raster_svg
+                };
+                let content = match svg {
+                    Some(Ok((svg, backend))) => {
+                        let svg = match options.svg_processing {
+                            Some(ref processing) if is_markup(format) => {
+                                svg_service::process_svg(svg, code, processing)
+                            }
+                            _ => Ok(svg.clone()),
+                        };
+                        svg.and_then(|svg| match format {
+                            DiagramFormat::Svg => Ok(svg.into_bytes()),
+                            DiagramFormat::Eps => eps_service::svg_to_eps(&svg, &format_options),
+                            DiagramFormat::Html => {
+                                html_service::svg_to_html(&svg, code, &format_options)
+                            }
+                            _ => raster_service::rasterize_svg(&svg, &format_options),
+                        })
+                        .map(|content| (content, *backend))
+                    }
+                    Some(Err(e)) => Err(e.clone()),
+                    None => Err("SVG was not rendered".to_string()),
+                };
+                write_output(content, output_path, start_time)
+            })
+            .collect()
+    }
+
+    /// Render mermaid code to SVG markup through a temporary file
+    pub fn render_svg(&mut self, code: &str, options: &DiagramOptions) -> Result<String, String> {
+        let output_file = TempFile::reserve("mermaid_render", "svg")?;
+
+        let result = self.generate_diagram(code, options, output_file.path());
+        if !result.success {
+            return Err(result.error_message.unwrap_or("Unknown error".to_string()));
+        }
+
+        std::fs::read_to_string(output_file.path())
+            .map_err(|e| format!("Failed to read SVG: {}", e))
+    }
+
+    /// Render an SVG with plain SVG text labels, suitable for in-process
+    /// rasterization and vector conversion
+    pub fn render_raster_svg(
+        &mut self,
+        code: &str,
+        options: &DiagramOptions,
+    ) -> Result<String, String> {
+        self.render_svg_with(code, options, Some(&raster_config()))
+            .map(|(svg, _)| svg)
+    }
+
+    fn render_svg_with(
+        &mut self,
+        code: &str,
+        options: &DiagramOptions,
+        extra_config: Option<&Value>,
+    ) -> Result<(String, RendererBackend), String> {
+        let svg_options = DiagramOptions {
+            format: DiagramFormat::Svg,
+            ..options.clone()
+        };
+        let (content, backend) =
+            self.render(code, &svg_options, DiagramFormat::Svg, extra_config)?;
+        let svg = String::from_utf8(content).map_err(|e| format!("Invalid SVG: {}", e))?;
+        Ok((svg, backend))
+    }
+
+    fn renderer(&mut self, backend: RendererBackend) -> &mut dyn Renderer {
+        match backend {
+            RendererBackend::Mmdc => &mut self.mmdc,
+            RendererBackend::Worker => {
+                self.sync_worker();
+                &mut self.worker
+            }
+            RendererBackend::Kroki => &mut self.kroki,
+            RendererBackend::Native => &mut self.native,
+        }
+    }
+
+    /// Point the worker at the mmdc install currently in use
+    fn sync_worker(&mut self) {
+        if self.settings.worker.enabled {
+            let located = self.mmdc.locate().ok();
+            self.worker.use_mmdc(located);
+        }
+    }
+}
+
+impl Default for RenderService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Apply the background and CSS that mmdc adds to the SVG mermaid renders,
+/// for renderers that produce the bare SVG
+pub fn apply_mmdc_styles(svg: &str, job: &RenderJob) -> Result<String, String> {
+    let processing = SvgProcessOptions {
+        background: Some(mmdc_background(job.options).unwrap_or("white").to_string()),
+        remove_unused_defs: false,
+        accessibility: false,
+        strip_scripts: false,
+        ..SvgProcessOptions::default()
+    };
+    let mut svg = svg_service::process_svg(svg, job.code, &processing)?;
+
+    if let Some(css) = read_css(job.options)? {
+        let root_end = svg
+            .find("<svg")
+            .and_then(|start| svg[start..].find('>').map(|end| start + end + 1))
+            .ok_or("SVG has no root element")?;
+        let style = format!(
+            "<style><![CDATA[{}]]></style>",
+            css.replace("]]>", "]]]]><![CDATA[>")
+        );
+        svg.insert_str(root_end, &style);
+    }
+    Ok(svg)
+}
+
+/// Text art, DOT, draw.io and Excalidraw scenes are written in Rust from the parsed source, without any
+/// of the renderer backends
+fn render_source_output(
+    code: &str,
+    options: &DiagramOptions,
+) -> Result<(Vec<u8>, RendererBackend), String> {
+    let source = match options.format {
+        DiagramFormat::Dot => dot_service::export_dot(code),
+        DiagramFormat::Drawio => drawio_service::export_drawio(code, options),
+        DiagramFormat::Excalidraw => excalidraw_service::export_excalidraw(code, options),
+        _ => text_service::render_text(code, &options.text.clone().unwrap_or_default()),
+    };
+    source.map(|source| (source.into_bytes(), RendererBackend::Native))
+}
+
+fn write_output(
+    content: Result<(Vec<u8>, RendererBackend), String>,
+    output_path: &Path,
+    start_time: Instant,
+) -> DiagramResult {
+    let generation_time = start_time.elapsed().as_millis() as u64;
+    let written = content.and_then(|(content, backend)| {
+        std::fs::write(output_path, &content)
+            .map(|_| (content.len() as u64, backend))
+            .map_err(|e| format!("Failed to write {}: {}", output_path.display(), e))
+    });
+    match written {
+        Ok((file_size, backend)) => DiagramResult {
+            renderer: Some(backend),
+            ..DiagramResult::success(
+                output_path.to_string_lossy().to_string(),
+                generation_time,
+                Some(file_size),
+            )
+        },
+        Err(e) => DiagramResult::error(e, generation_time),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::RenderPolicy;
+
+    #[test]
+    fn test_diagram_type() {
+        assert_eq!(
+            diagram_type("graph LR\n  A --> B").as_deref(),
+            Some("flowchart")
+        );
+        assert_eq!(
+            diagram_type("---\ntitle: Flow\n---\n%%{init: {}}%%\n\nsequenceDiagram\n").as_deref(),
+            Some("sequence")
+        );
+        assert_eq!(diagram_type("stateDiagram-v2").as_deref(), Some("state"));
+        assert_eq!(diagram_type("pie showData").as_deref(), Some("pie"));
+        assert_eq!(diagram_type("xychart-beta").as_deref(), Some("xychart"));
+        assert_eq!(diagram_type("  \n%% comment only"), None);
+    }
+
+    #[test]
+    fn test_fallback_chain() {
+        let settings = RendererSettings {
+            policy: RenderPolicy::Custom,
+            chain: vec![RendererBackend::Kroki],
+            ..RendererSettings::default()
+        };
+        let mut service = RenderService::with_settings(settings, None);
+
+        // Kroki without a server is skipped and there is nothing after it
+        let error = service
+            .render(
+                "graph TD\n  A --> B",
+                &DiagramOptions::svg(None),
+                DiagramFormat::Svg,
+                None,
+            )
+            .unwrap_err();
+        assert!(
+            error.contains("kroki: No Kroki server configured"),
+            "{}",
+            error
+        );
+
+        // A format the renderer cannot produce is skipped without trying it
+        let options = DiagramOptions {
+            renderer: Some(RendererBackend::Native),
+            ..DiagramOptions::svg(None)
+        };
+        let error = service
+            .render("graph TD\n  A --> B", &options, DiagramFormat::Pdf, None)
+            .unwrap_err();
+        assert!(
+            error.contains("native: cannot render flowchart diagrams to pdf"),
+            "{}",
+            error
+        );
+
+        let infos = service.renderers();
+        assert_eq!(infos[0].backend, RendererBackend::Kroki);
+        assert!(!infos[0].available);
+        assert_eq!(infos.len(), 4);
+    }
+}
@@ -0,0 +1,1091 @@
+use crate::models::{ImportedDiagram, SkippedConstruct, SqlImportOptions};
+
+/// Schema of unqualified table names until a `USE` or `SET search_path`
+const DEFAULT_SCHEMA: &str = "public";
+
+/// Words that end a column's type and start its constraints
+const COLUMN_KEYWORDS: [&str; 17] = [
+    "constraint",
+    "not",
+    "null",
+    "primary",
+    "unique",
+    "references",
+    "default",
+    "check",
+    "collate",
+    "generated",
+    "auto_increment",
+    "autoincrement",
+    "comment",
+    "on",
+    "as",
+    "identity",
+    "charset",
+];
+
+/// Convert the tables of a PostgreSQL, MySQL or SQLite schema into a Mermaid
+/// entity relationship diagram. Foreign keys become relationships: a nullable
+/// key makes the referenced row optional, and a key that is also unique
+/// allows at most one referencing row. Views, triggers, data and other
+/// statements are ignored.
+pub fn import_sql(source: &str, options: &SqlImportOptions) -> Result<ImportedDiagram, String> {
+    let mut parser = SchemaParser {
+        source_lines: source.lines().collect(),
+        tables: Vec::new(),
+        default_schema: DEFAULT_SCHEMA.to_string(),
+        skipped: Vec::new(),
+    };
+    let tokens = tokenize(source)?;
+    for statement in tokens.split(|(_, token)| *token == Token::Semicolon) {
+        if !statement.is_empty() {
+            parser.statement(Statement {
+                tokens: statement,
+                position: 0,
+            });
+        }
+    }
+    if parser.tables.is_empty() {
+        return Err("No CREATE TABLE statements were found".to_string());
+    }
+
+    let kept: Vec<usize> = (0..parser.tables.len())
+        .filter(|&index| parser.tables[index].matches(options))
+        .collect();
+    if kept.is_empty() {
+        return Err("No tables match the schema and table filters".to_string());
+    }
+    let code = parser.write(&kept);
+    Ok(ImportedDiagram::new(code, parser.skipped))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Keyword, unquoted identifier or number
+    Word(String),
+    /// `"identifier"`, `` `identifier` `` or `[identifier]`
+    Quoted(String),
+    /// `'string'` or `$$string$$`
+    Str(String),
+    LParen,
+    RParen,
+    Comma,
+    Dot,
+    Semicolon,
+    Other(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut position = 0;
+
+    // Text up to `end`, counting lines; `None` when it is never closed
+    let until = |position: &mut usize, line: &mut usize, end: &[char], doubled: bool| {
+        let mut text = String::new();
+        loop {
+            let c = *chars.get(*position)?;
+            if chars[*position..].starts_with(end) {
+                // A doubled quote stands for the quote itself
+                if doubled && chars.get(*position + 1) == Some(&end[0]) {
+                    text.push(c);
+                    *position += 2;
+                    continue;
+                }
+                *position += end.len();
+                return Some(text);
+            }
+            if c == '\\' && doubled && end == ['\''] {
+                // MySQL escapes quotes with backslashes
+                if let Some(&escaped) = chars.get(*position + 1) {
+                    text.push(escaped);
+                    *position += 2;
+                    continue;
+                }
+            }
+            if c == '\n' {
+                *line += 1;
+            }
+            text.push(c);
+            *position += 1;
+        }
+    };
+
+    while position < chars.len() {
+        let c = chars[position];
+        let next = chars.get(position + 1).copied();
+        let start_line = line;
+        let unterminated = || format!("Unterminated {} on line {}", c, start_line);
+        let token = match c {
+            '\n' => {
+                line += 1;
+                position += 1;
+                continue;
+            }
+            c if c.is_whitespace() => {
+                position += 1;
+                continue;
+            }
+            // Comments, including MySQL's `#` and `/*! ... */`
+            '-' if next == Some('-') => {
+                while position < chars.len() && chars[position] != '\n' {
+                    position += 1;
+                }
+                continue;
+            }
+            '#' => {
+                while position < chars.len() && chars[position] != '\n' {
+                    position += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('*') => {
+                position += 2;
+                until(&mut position, &mut line, &['*', '/'], false)
+                    .ok_or_else(|| format!("Unterminated comment on line {}", start_line))?;
+                continue;
+            }
+            '\'' => {
+                position += 1;
+                Token::Str(until(&mut position, &mut line, &['\''], true).ok_or_else(unterminated)?)
+            }
+            '"' | '`' => {
+                position += 1;
+                Token::Quoted(until(&mut position, &mut line, &[c], true).ok_or_else(unterminated)?)
+            }
+            // SQL Server style identifiers, but not PostgreSQL's `int[]`
+            '[' if chars[position + 1..]
+                .iter()
+                .take_while(|&&c| c != ']' && c != '\n')
+                .any(|c| !c.is_ascii_digit())
+                && chars[position + 1..]
+                    .iter()
+                    .take_while(|&&c| c != '\n')
+                    .any(|&c| c == ']') =>
+            {
+                position += 1;
+                Token::Quoted(
+                    until(&mut position, &mut line, &[']'], false).ok_or_else(unterminated)?,
+                )
+            }
+            // PostgreSQL dollar quoting, as in function bodies
+            '$' if chars[position + 1..]
+                .iter()
+                .take_while(|&&c| c.is_alphanumeric() || c == '_' || c == '$')
+                .any(|&c| c == '$') =>
+            {
+                let length = chars[position + 1..]
+                    .iter()
+                    .position(|&c| c == '$')
+                    .unwrap_or(0);
+                let tag: Vec<char> = chars[position..position + length + 2].to_vec();
+                position += tag.len();
+                Token::Str(until(&mut position, &mut line, &tag, false).ok_or_else(unterminated)?)
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let start = position;
+                while chars
+                    .get(position)
+                    .is_some_and(|&c| c.is_alphanumeric() || c == '_' || c == '$')
+                {
+                    position += 1;
+                }
+                tokens.push((
+                    start_line,
+                    Token::Word(chars[start..position].iter().collect()),
+                ));
+                continue;
+            }
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            '.' => Token::Dot,
+            ';' => Token::Semicolon,
+            c => Token::Other(c),
+        };
+        if !matches!(token, Token::Str(_) | Token::Quoted(_)) {
+            position += 1;
+        }
+        tokens.push((start_line, token));
+    }
+    Ok(tokens)
+}
+
+/// The tokens of one statement, or of one part of a statement
+struct Statement<'t> {
+    tokens: &'t [(usize, Token)],
+    position: usize,
+}
+
+impl<'t> Statement<'t> {
+    fn peek(&self) -> Option<&'t Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&'t Token> {
+        self.tokens
+            .get(self.position + offset)
+            .map(|(_, token)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |&(line, _)| line)
+    }
+
+    fn keyword_at(&self, offset: usize, keyword: &str) -> bool {
+        matches!(self.peek_at(offset), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Consume the keywords if they all come next
+    fn eat(&mut self, keywords: &[&str]) -> bool {
+        let found = keywords
+            .iter()
+            .enumerate()
+            .all(|(offset, keyword)| self.keyword_at(offset, keyword));
+        if found {
+            self.position += keywords.len();
+        }
+        found
+    }
+
+    fn eat_token(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn identifier(&mut self) -> Option<String> {
+        let identifier = match self.peek()? {
+            Token::Word(word) | Token::Quoted(word) => word.clone(),
+            _ => return None,
+        };
+        self.position += 1;
+        Some(identifier)
+    }
+
+    /// A name with its qualifiers, e.g. `["public", "orders"]`
+    fn name(&mut self) -> Option<Vec<String>> {
+        let mut parts = vec![self.identifier()?];
+        while self.peek() == Some(&Token::Dot) {
+            self.position += 1;
+            parts.push(self.identifier()?);
+        }
+        Some(parts)
+    }
+
+    /// Tokens up to the parenthesis closing the one at the current position
+    fn group(&mut self) -> &'t [(usize, Token)] {
+        let start = self.position + 1;
+        let mut depth = 0;
+        while let Some(token) = self.peek() {
+            self.position += 1;
+            match token {
+                Token::LParen => depth += 1,
+                Token::RParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return &self.tokens[start..self.position - 1];
+                    }
+                }
+                _ => {}
+            }
+        }
+        &self.tokens[start.min(self.tokens.len())..]
+    }
+
+    /// A parenthesized list of plain column names; `None` when an entry is an
+    /// expression
+    fn columns(&mut self) -> Option<Vec<String>> {
+        if self.peek() != Some(&Token::LParen) {
+            return None;
+        }
+        let mut columns = Vec::new();
+        for mut entry in split(self.group()) {
+            columns.push(entry.identifier()?);
+            // Sort orders, collations and MySQL prefix lengths
+            while let Some(token) = entry.peek() {
+                match token {
+                    Token::LParen => {
+                        let length = entry.group();
+                        if !length.iter().all(|(_, t)| matches!(t, Token::Word(w) if w.chars().all(|c| c.is_ascii_digit()))) {
+                            return None;
+                        }
+                    }
+                    Token::Word(_) | Token::Quoted(_) => entry.position += 1,
+                    _ => return None,
+                }
+            }
+        }
+        Some(columns)
+    }
+
+    /// The remaining tokens, split at top-level commas
+    fn rest(&self) -> Vec<Statement<'t>> {
+        split(&self.tokens[self.position..])
+    }
+}
+
+/// Split at commas outside parentheses
+fn split(tokens: &[(usize, Token)]) -> Vec<Statement<'_>> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, (_, token)) in tokens.iter().enumerate() {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            Token::Comma if depth == 0 => {
+                parts.push(&tokens[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&tokens[start..]);
+    parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .map(|tokens| Statement {
+            tokens,
+            position: 0,
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Table {
+    schema: String,
+    name: String,
+    columns: Vec<Column>,
+    primary_key: Vec<String>,
+    /// Column sets of unique constraints and unique indexes
+    unique: Vec<Vec<String>>,
+    foreign_keys: Vec<ForeignKey>,
+}
+
+struct Column {
+    name: String,
+    data_type: String,
+    not_null: bool,
+    comment: Option<String>,
+}
+
+struct ForeignKey {
+    line: usize,
+    columns: Vec<String>,
+    schema: String,
+    table: String,
+}
+
+enum Constraint {
+    PrimaryKey(Vec<String>),
+    Unique(Vec<String>),
+    ForeignKey(ForeignKey),
+    /// Checks, exclusions and plain indexes
+    Other,
+}
+
+impl Table {
+    fn column(&mut self, name: &str) -> Option<&mut Column> {
+        self.columns
+            .iter_mut()
+            .find(|column| column.name.eq_ignore_ascii_case(name))
+    }
+
+    fn has_column(&self, name: &str) -> bool {
+        self.columns
+            .iter()
+            .any(|column| column.name.eq_ignore_ascii_case(name))
+    }
+
+    fn add(&mut self, constraint: Constraint) {
+        match constraint {
+            Constraint::PrimaryKey(columns) => self.primary_key = columns,
+            Constraint::Unique(columns) => self.unique.push(columns),
+            Constraint::ForeignKey(key) => self.foreign_keys.push(key),
+            Constraint::Other => {}
+        }
+    }
+
+    fn matches(&self, options: &SqlImportOptions) -> bool {
+        let schema = options
+            .schemas
+            .as_deref()
+            .filter(|patterns| !patterns.trim().is_empty())
+            .is_none_or(|patterns| matches_any(patterns, &self.schema));
+        let table = options
+            .tables
+            .as_deref()
+            .filter(|patterns| !patterns.trim().is_empty())
+            .is_none_or(|patterns| {
+                matches_any(patterns, &self.name)
+                    || matches_any(patterns, &format!("{}.{}", self.schema, self.name))
+            });
+        schema && table
+    }
+
+    /// Whether the columns are exactly those of the primary key or of a
+    /// unique constraint
+    fn is_unique(&self, columns: &[String]) -> bool {
+        std::iter::once(&self.primary_key)
+            .chain(&self.unique)
+            .any(|key| !key.is_empty() && same_columns(key, columns))
+    }
+
+    fn in_primary_key(&self, column: &str) -> bool {
+        contains(&self.primary_key, column)
+    }
+
+    fn is_not_null(&self, column: &str) -> bool {
+        self.in_primary_key(column)
+            || self
+                .columns
+                .iter()
+                .any(|c| c.name.eq_ignore_ascii_case(column) && c.not_null)
+    }
+}
+
+fn contains(columns: &[String], column: &str) -> bool {
+    columns.iter().any(|c| c.eq_ignore_ascii_case(column))
+}
+
+fn same_columns(a: &[String], b: &[String]) -> bool {
+    a.len() == b.len() && a.iter().all(|column| contains(b, column))
+}
+
+struct SchemaParser<'a> {
+    source_lines: Vec<&'a str>,
+    tables: Vec<Table>,
+    /// Schema of unqualified names, from `USE` or `SET search_path`
+    default_schema: String,
+    skipped: Vec<SkippedConstruct>,
+}
+
+impl SchemaParser<'_> {
+    fn statement(&mut self, mut statement: Statement) {
+        if statement.eat(&["create"]) {
+            statement.eat(&["or", "replace"]);
+            let unique = statement.eat(&["unique"]);
+            if unique || statement.keyword_at(0, "index") {
+                if unique {
+                    self.unique_index(statement);
+                }
+                return;
+            }
+            for modifier in ["global", "local", "temp", "temporary", "unlogged"] {
+                statement.eat(&[modifier]);
+            }
+            if statement.eat(&["virtual", "table"]) {
+                self.skip(statement.line(), "Virtual tables are not converted");
+            } else if statement.eat(&["table"]) {
+                self.create_table(statement);
+            }
+        } else if statement.eat(&["alter", "table"]) {
+            self.alter_table(statement);
+        } else if statement.eat(&["comment", "on", "column"]) {
+            self.column_comment(statement);
+        } else if statement.eat(&["use"]) {
+            if let Some(schema) = statement.identifier() {
+                self.default_schema = schema;
+            }
+        } else if statement.eat(&["set"]) {
+            statement.eat(&["session"]);
+            statement.eat(&["local"]);
+            if statement.eat(&["search_path"]) {
+                statement.eat(&["to"]);
+                statement.eat_token(&Token::Other('='));
+                if let Some(Token::Word(schema) | Token::Quoted(schema) | Token::Str(schema)) =
+                    statement.peek()
+                {
+                    self.default_schema = schema.clone();
+                }
+            }
+        }
+    }
+
+    fn create_table(&mut self, mut statement: Statement) {
+        statement.eat(&["if", "not", "exists"]);
+        let line = statement.line();
+        let Some(name) = statement.name() else {
+            self.skip(line, "The table name could not be read");
+            return;
+        };
+        if statement.peek() != Some(&Token::LParen) {
+            self.skip(
+                line,
+                "Tables created from a query or from another table are not converted",
+            );
+            return;
+        }
+        let (schema, name) = self.qualify(name);
+        let mut table = Table {
+            schema,
+            name,
+            ..Table::default()
+        };
+        for mut item in split(statement.group()) {
+            if item.eat(&["like"]) {
+                self.skip(
+                    item.line(),
+                    "Columns copied from another table are not converted",
+                );
+            } else if let Some(constraint) = self.constraint(&mut item) {
+                table.add(constraint);
+            } else if !self.column(&mut item, &mut table) {
+                self.skip(item.line(), "The column definition could not be read");
+            }
+        }
+
+        // A table created again replaces the earlier one
+        self.tables
+            .retain(|t| !(t.schema == table.schema && t.name.eq_ignore_ascii_case(&table.name)));
+        self.tables.push(table);
+    }
+
+    fn alter_table(&mut self, mut statement: Statement) {
+        statement.eat(&["if", "exists"]);
+        statement.eat(&["only"]);
+        let line = statement.line();
+        let Some(name) = statement.name() else {
+            return;
+        };
+        let Some(index) = self.find(name) else {
+            // Owners and defaults of sequences and views are set the same way
+            if statement
+                .rest()
+                .iter()
+                .any(|action| action.keyword_at(0, "add"))
+            {
+                self.skip(line, "The altered table is not defined");
+            }
+            return;
+        };
+        for mut action in statement.rest() {
+            if action.eat(&["add"]) {
+                if let Some(constraint) = self.constraint(&mut action) {
+                    self.tables[index].add(constraint);
+                } else {
+                    action.eat(&["column"]);
+                    action.eat(&["if", "not", "exists"]);
+                    let mut table = std::mem::take(&mut self.tables[index]);
+                    if !self.column(&mut action, &mut table) {
+                        self.skip(action.line(), "The column definition could not be read");
+                    }
+                    self.tables[index] = table;
+                }
+            } else if action.eat(&["alter"]) {
+                action.eat(&["column"]);
+                let Some(name) = action.identifier() else {
+                    continue;
+                };
+                let not_null = if action.eat(&["set", "not", "null"]) {
+                    true
+                } else if action.eat(&["drop", "not", "null"]) {
+                    false
+                } else {
+                    continue;
+                };
+                if let Some(column) = self.tables[index].column(&name) {
+                    column.not_null = not_null;
+                }
+            }
+        }
+    }
+
+    fn unique_index(&mut self, mut statement: Statement) {
+        if !statement.eat(&["index"]) {
+            return;
+        }
+        statement.eat(&["concurrently"]);
+        statement.eat(&["if", "not", "exists"]);
+        if !statement.keyword_at(0, "on") {
+            statement.name();
+        }
+        if !statement.eat(&["on"]) {
+            return;
+        }
+        statement.eat(&["only"]);
+        let Some(table) = statement.name().and_then(|name| self.find(name)) else {
+            return;
+        };
+        if statement.eat(&["using"]) {
+            statement.identifier();
+        }
+        // Partial indexes only make some rows unique
+        if let Some(columns) = statement.columns() {
+            let partial = statement.tokens[statement.position..].iter().any(
+                |(_, token)| matches!(token, Token::Word(w) if w.eq_ignore_ascii_case("where")),
+            );
+            if !partial {
+                self.tables[table].unique.push(columns);
+            }
+        }
+    }
+
+    fn column_comment(&mut self, mut statement: Statement) {
+        let Some(mut name) = statement.name().filter(|name| name.len() >= 2) else {
+            return;
+        };
+        let column = name.pop().unwrap_or_default();
+        if !statement.eat(&["is"]) {
+            return;
+        }
+        let Some(Token::Str(comment)) = statement.peek() else {
+            return;
+        };
+        if let Some(column) = self
+            .find(name)
+            .and_then(|table| self.tables[table].column(&column))
+        {
+            column.comment = Some(comment.clone());
+        }
+    }
+
+    /// A table constraint, or `None` when the item defines a column
+    fn constraint(&self, item: &mut Statement) -> Option<Constraint> {
+        let start = item.position;
+        let named = item.eat(&["constraint"]) && item.identifier().is_some();
+        if item.eat(&["primary", "key"]) {
+            return Some(
+                item.columns()
+                    .map_or(Constraint::Other, Constraint::PrimaryKey),
+            );
+        }
+        if item.eat(&["unique"]) {
+            let _ = item.eat(&["key"]) || item.eat(&["index"]);
+            if item.peek_at(1) == Some(&Token::LParen) {
+                item.identifier();
+            }
+            return Some(match item.columns() {
+                Some(columns) => Constraint::Unique(columns),
+                None => Constraint::Other,
+            });
+        }
+        if item.eat(&["foreign", "key"]) {
+            if item.peek() != Some(&Token::LParen) {
+                item.identifier();
+            }
+            let line = item.line();
+            let key = item.columns().and_then(|columns| {
+                item.eat(&["references"]).then_some(())?;
+                let (schema, table) = self.qualify(item.name()?);
+                Some(ForeignKey {
+                    line,
+                    columns,
+                    schema,
+                    table,
+                })
+            });
+            return Some(key.map_or(Constraint::Other, Constraint::ForeignKey));
+        }
+        let index = ["key", "index", "fulltext", "spatial"]
+            .iter()
+            .any(|keyword| item.keyword_at(0, keyword))
+            && (item.peek_at(1) == Some(&Token::LParen)
+                || item.peek_at(2) == Some(&Token::LParen)
+                || item.keyword_at(1, "key")
+                || item.keyword_at(1, "index"));
+        let check = ["check", "exclude"]
+            .iter()
+            .any(|keyword| item.keyword_at(0, keyword));
+        if named || index || check {
+            return Some(Constraint::Other);
+        }
+        item.position = start;
+        None
+    }
+
+    /// Add a column definition to the table; false when it cannot be read
+    fn column(&self, item: &mut Statement, table: &mut Table) -> bool {
+        let Some(name) = item.identifier() else {
+            return false;
+        };
+        let start = item.position;
+        while let Some(token) = item.peek() {
+            let keyword = COLUMN_KEYWORDS
+                .iter()
+                .any(|keyword| item.keyword_at(0, keyword));
+            if keyword || (item.keyword_at(0, "character") && item.keyword_at(1, "set")) {
+                break;
+            }
+            match token {
+                Token::LParen => {
+                    item.group();
+                }
+                _ => item.position += 1,
+            }
+        }
+        let mut column = Column {
+            name: name.clone(),
+            data_type: type_name(&item.tokens[start..item.position]),
+            not_null: false,
+            comment: None,
+        };
+
+        while let Some(token) = item.peek() {
+            if item.eat(&["not", "null"]) {
+                column.not_null = true;
+            } else if item.eat(&["primary", "key"]) {
+                table.primary_key = vec![name.clone()];
+            } else if item.eat(&["unique"]) {
+                table.unique.push(vec![name.clone()]);
+            } else if item.keyword_at(0, "references") {
+                let line = item.line();
+                item.position += 1;
+                if let Some(reference) = item.name() {
+                    let (schema, target) = self.qualify(reference);
+                    table.foreign_keys.push(ForeignKey {
+                        line,
+                        columns: vec![name.clone()],
+                        schema,
+                        table: target,
+                    });
+                }
+            } else if item.eat(&["comment"]) {
+                if let Some(Token::Str(comment)) = item.peek() {
+                    column.comment = Some(comment.clone());
+                    item.position += 1;
+                }
+            } else if *token == Token::LParen {
+                // Checks, defaults and generation expressions
+                item.group();
+            } else {
+                item.position += 1;
+            }
+        }
+
+        table
+            .columns
+            .retain(|c| !c.name.eq_ignore_ascii_case(&name));
+        table.columns.push(column);
+        true
+    }
+
+    /// Schema and name of a possibly qualified table name
+    fn qualify(&self, mut name: Vec<String>) -> (String, String) {
+        let table = name.pop().unwrap_or_default();
+        let schema = name.pop().unwrap_or_else(|| self.default_schema.clone());
+        (schema, table)
+    }
+
+    fn find(&self, name: Vec<String>) -> Option<usize> {
+        let (schema, name) = self.qualify(name);
+        self.find_qualified(&schema, &name)
+    }
+
+    fn find_qualified(&self, schema: &str, name: &str) -> Option<usize> {
+        self.tables.iter().position(|table| {
+            table.schema.eq_ignore_ascii_case(schema) && table.name.eq_ignore_ascii_case(name)
+        })
+    }
+
+    fn skip(&mut self, line: usize, reason: &str) {
+        let source = self.source_lines.get(line - 1).copied().unwrap_or("");
+        self.skipped.push(SkippedConstruct {
+            line,
+            source: source.trim().to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    /// The kept tables as an `erDiagram`, with a relationship for every
+    /// foreign key between them
+    fn write(&mut self, kept: &[usize]) -> String {
+        // Entity names only carry the schema when several are shown
+        let qualified = kept
+            .iter()
+            .any(|&index| self.tables[index].schema != self.tables[kept[0]].schema);
+        let entity = |table: &Table| match qualified {
+            true => identifier(&format!("{}_{}", table.schema, table.name)),
+            false => identifier(&table.name),
+        };
+
+        let mut out = String::from("erDiagram\n");
+        for &index in kept {
+            let table = &self.tables[index];
+            if table.columns.is_empty() {
+                out.push_str(&format!("    {}\n", entity(table)));
+                continue;
+            }
+            out.push_str(&format!("    {} {{\n", entity(table)));
+            for column in &table.columns {
+                let mut keys = Vec::new();
+                if table.in_primary_key(&column.name) {
+                    keys.push("PK");
+                }
+                if table
+                    .foreign_keys
+                    .iter()
+                    .any(|key| contains(&key.columns, &column.name))
+                {
+                    keys.push("FK");
+                }
+                if !table.in_primary_key(&column.name)
+                    && table.unique.iter().any(|key| contains(key, &column.name))
+                {
+                    keys.push("UK");
+                }
+                let mut attribute = format!("{} {}", column.data_type, identifier(&column.name));
+                if !keys.is_empty() {
+                    attribute.push_str(&format!(" {}", keys.join(", ")));
+                }
+                if let Some(ref comment) = column.comment {
+                    attribute.push_str(&format!(" \"{}\"", comment.replace('"', "'")));
+                }
+                out.push_str(&format!("        {}\n", attribute.trim_end()));
+            }
+            out.push_str("    }\n");
+        }
+
+        let mut undefined = Vec::new();
+        for &index in kept {
+            let child = &self.tables[index];
+            for key in &child.foreign_keys {
+                let Some(parent) = self.find_qualified(&key.schema, &key.table) else {
+                    undefined.push(key.line);
+                    continue;
+                };
+                if !kept.contains(&parent)
+                    || !key.columns.iter().all(|column| child.has_column(column))
+                {
+                    continue;
+                }
+                // How many parents a row has, and how many rows a parent has
+                let parent_side = match key.columns.iter().all(|c| child.is_not_null(c)) {
+                    true => "||",
+                    false => "|o",
+                };
+                let child_side = match child.is_unique(&key.columns) {
+                    true => "o|",
+                    false => "o{",
+                };
+                // Keys within the primary key make the relationship identifying
+                let line = match key.columns.iter().all(|c| child.in_primary_key(c)) {
+                    true => "--",
+                    false => "..",
+                };
+                out.push_str(&format!(
+                    "    {} {}{}{} {} : \"{}\"\n",
+                    entity(&self.tables[parent]),
+                    parent_side,
+                    line,
+                    child_side,
+                    entity(child),
+                    key.columns.join(", ").replace('"', "'")
+                ));
+            }
+        }
+        for line in undefined {
+            self.skip(line, "The referenced table is not defined");
+        }
+        out
+    }
+}
+
+/// A column type as a single Mermaid word, e.g. `character_varying(20)`.
+/// Argument lists other than numbers, such as enum values, are dropped.
+fn type_name(tokens: &[(usize, Token)]) -> String {
+    let mut out = String::new();
+    let mut position = 0;
+    while let Some((_, token)) = tokens.get(position) {
+        position += 1;
+        match token {
+            Token::Word(word) | Token::Quoted(word) => {
+                if out.ends_with(|c: char| c.is_alphanumeric() || c == ')') {
+                    out.push('_');
+                }
+                out.push_str(word);
+            }
+            // Schema-qualified types keep only their name
+            Token::Dot => out.clear(),
+            Token::LParen => {
+                let mut group = Statement {
+                    tokens,
+                    position: position - 1,
+                };
+                let arguments = group.group();
+                position = group.position;
+                let numbers: Option<Vec<&str>> = split(arguments)
+                    .iter()
+                    .map(|argument| match argument.tokens {
+                        [(_, Token::Word(word))] => Some(word.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                if let Some(numbers) = numbers {
+                    out.push_str(&format!("({})", numbers.join("_")));
+                }
+            }
+            Token::Other('[') => out.push('['),
+            Token::Other(']') => out.push(']'),
+            _ => {}
+        }
+    }
+    let out: String = out
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || "_-()[]".contains(c) {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect();
+    match out.chars().next() {
+        None => "any".to_string(),
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => out,
+        Some(_) => format!("_{}", out),
+    }
+}
+
+/// A name Mermaid accepts without quotes
+fn identifier(name: &str) -> String {
+    let id: String = name
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect();
+    match id.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => id,
+        _ => format!("_{}", id),
+    }
+}
+
+/// Whether the text matches one of the comma-separated wildcard patterns
+fn matches_any(patterns: &str, text: &str) -> bool {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    patterns
+        .split(',')
+        .map(|pattern| pattern.trim().to_lowercase())
+        .filter(|pattern| !pattern.is_empty())
+        .any(|pattern| wildcard(&pattern.chars().collect::<Vec<_>>(), &text))
+}
+
+fn wildcard(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| wildcard(rest, &text[skip..])),
+        Some((&c, rest)) => text
+            .split_first()
+            .is_some_and(|(&t, text)| (c == '?' || c == t) && wildcard(rest, text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_postgres_dump() {
+        let imported = import_sql(
+            r#"SET search_path = sales;
+CREATE TABLE customers (
+    id integer NOT NULL,
+    email character varying(255) NOT NULL,
+    created_at timestamp with time zone DEFAULT now()
+);
+CREATE TABLE orders (
+    id bigint PRIMARY KEY,
+    customer_id integer REFERENCES customers,
+    total numeric(10,2) CHECK (total >= 0)
+);
+CREATE TABLE order_items (
+    order_id bigint NOT NULL REFERENCES orders(id),
+    line integer NOT NULL,
+    PRIMARY KEY (order_id, line)
+);
+CREATE TABLE profiles (customer_id integer NOT NULL, bio text);
+CREATE TABLE totals AS SELECT 1;
+COMMENT ON COLUMN sales.customers.email IS 'Login address';
+ALTER TABLE ONLY sales.customers ADD CONSTRAINT customers_pkey PRIMARY KEY (id);
+ALTER TABLE ONLY sales.profiles
+    ADD CONSTRAINT profiles_fk FOREIGN KEY (customer_id) REFERENCES sales.customers(id);
+ALTER TABLE ONLY sales.orders ADD CONSTRAINT fk FOREIGN KEY (id) REFERENCES stock(id);
+CREATE UNIQUE INDEX profiles_customer ON sales.profiles USING btree (customer_id);
+"#,
+            &SqlImportOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            imported.code,
+            r#"erDiagram
+    customers {
+        integer id PK
+        character_varying(255) email "Login address"
+        timestamp_with_time_zone created_at
+    }
+    orders {
+        bigint id PK, FK
+        integer customer_id FK
+        numeric(10_2) total
+    }
+    order_items {
+        bigint order_id PK, FK
+        integer line PK
+    }
+    profiles {
+        integer customer_id FK, UK
+        text bio
+    }
+    customers |o..o{ orders : "customer_id"
+    orders ||--o{ order_items : "order_id"
+    customers ||..o| profiles : "customer_id"
+"#
+        );
+        let skipped: Vec<usize> = imported.skipped.iter().map(|s| s.line).collect();
+        assert_eq!(skipped, vec![18, 23]);
+    }
+
+    #[test]
+    fn test_import_with_filters() {
+        let source = r#"
+CREATE TABLE `shop`.`users` (
+  `id` int unsigned NOT NULL AUTO_INCREMENT,
+  `email` varchar(191) CHARACTER SET utf8mb4 NOT NULL COMMENT 'Login',
+  `role` enum('admin','member') DEFAULT 'member',
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `users_email_unique` (`email`),
+  KEY `users_role_index` (`role`)
+) ENGINE=InnoDB;
+CREATE TABLE `shop`.`posts` (
+  `id` bigint NOT NULL,
+  `user_id` int unsigned NOT NULL,
+  CONSTRAINT `posts_user` FOREIGN KEY (`user_id`) REFERENCES `shop`.`users` (`id`)
+);
+CREATE TABLE `audit`.`logins` (`user_id` int unsigned REFERENCES `shop`.`users`);
+"#;
+        let import = |schemas: Option<&str>, tables: Option<&str>| {
+            let options = SqlImportOptions {
+                schemas: schemas.map(String::from),
+                tables: tables.map(String::from),
+            };
+            import_sql(source, &options).map(|imported| imported.code)
+        };
+
+        // Several schemas qualify the entity names
+        let all = import(None, Some(" ")).unwrap();
+        assert!(all.contains("    shop_users {\n        int_unsigned id PK\n"));
+        assert!(all.contains("        varchar(191) email UK \"Login\"\n        enum role\n"));
+        assert!(all.contains("    shop_users ||..o{ shop_posts : \"user_id\"\n"));
+        assert!(all.contains("    shop_users |o..o{ audit_logins : \"user_id\"\n"));
+
+        let shop = import(Some("SHOP"), None).unwrap();
+        assert!(shop.contains("    users ||..o{ posts : \"user_id\"\n"));
+        assert!(!shop.contains("logins"));
+        let users = import(None, Some("audit.*, us?rs")).unwrap();
+        assert!(users.contains("    shop_users {") && users.contains("    audit_logins {"));
+        assert!(!users.contains("posts"));
+        assert!(import(Some("public"), None).is_err());
+        assert!(import_sql("SELECT 1;", &SqlImportOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::models::{DiagramKind, DiagramStats, NodeDegree, ParsedDiagram, StatsExportFormat};
+use crate::services::parse_diagram;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Compute statistics for a mermaid file on disk
+pub fn file_stats<P: AsRef<Path>>(path: P) -> DiagramStats {
+    let path = path.as_ref();
+    let display = Some(path.to_string_lossy().to_string());
+
+    match std::fs::read_to_string(path) {
+        Ok(code) => match parse_diagram(&code) {
+            Ok(diagram) => DiagramStats {
+                path: display,
+                ..diagram_stats(&diagram)
+            },
+            Err(e) => DiagramStats::error(display, e),
+        },
+        Err(e) => DiagramStats::error(display, format!("Failed to read file: {}", e)),
+    }
+}
+
+/// Compute size, structure and complexity metrics for a parsed diagram
+pub fn diagram_stats(diagram: &ParsedDiagram) -> DiagramStats {
+    let mut stats = DiagramStats::empty(None, diagram.kind);
+    stats.node_count = diagram.nodes.len();
+    stats.edge_count = diagram.edges.len();
+    stats.subgraph_count = diagram.subgraphs.len();
+    stats.participant_count = diagram.participants.len();
+    stats.message_count = diagram.messages.len();
+    stats.class_count = diagram.classes.len();
+    stats.max_nesting_depth = nesting_depth(diagram);
+
+    let graph = Graph::from_diagram(diagram);
+    stats.degrees = graph.degrees();
+    stats.cycles = graph.cyclic_components();
+    stats.components = graph.components();
+
+    if matches!(diagram.kind, DiagramKind::Flowchart | DiagramKind::State) {
+        stats.cyclomatic_complexity = Some(
+            graph.edge_count() as i64 - graph.ids.len() as i64 + 2 * stats.components.len() as i64,
+        );
+    }
+
+    stats
+}
+
+/// Serialize a set of statistics for export
+pub fn export_stats(stats: &[DiagramStats], format: StatsExportFormat) -> Result<String, String> {
+    match format {
+        StatsExportFormat::Json => serde_json::to_string_pretty(stats)
+            .map_err(|e| format!("Failed to serialize statistics: {}", e)),
+        StatsExportFormat::Csv => Ok(stats_to_csv(stats)),
+    }
+}
+
+fn nesting_depth(diagram: &ParsedDiagram) -> usize {
+    diagram
+        .subgraphs
+        .iter()
+        .map(|subgraph| {
+            let mut depth = 1;
+            let mut parent = subgraph.parent.as_deref();
+            while let Some(id) = parent {
+                depth += 1;
+                parent = diagram
+                    .subgraphs
+                    .iter()
+                    .find(|s| s.id == id)
+                    .and_then(|s| s.parent.as_deref());
+                if depth > diagram.subgraphs.len() {
+                    break;
+                }
+            }
+            depth
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn stats_to_csv(stats: &[DiagramStats]) -> String {
+    let mut out = String::from(
+        "path,kind,nodes,edges,subgraphs,participants,messages,classes,max_nesting_depth,\
+         cyclomatic_complexity,max_fan_in,max_fan_out,cycles,components,error\n",
+    );
+
+    for entry in stats {
+        let max_fan_in = entry.degrees.iter().map(|d| d.fan_in).max().unwrap_or(0);
+        let max_fan_out = entry.degrees.iter().map(|d| d.fan_out).max().unwrap_or(0);
+        let row = [
+            csv_field(entry.path.as_deref().unwrap_or_default()),
+            entry.kind.to_string(),
+            entry.node_count.to_string(),
+            entry.edge_count.to_string(),
+            entry.subgraph_count.to_string(),
+            entry.participant_count.to_string(),
+            entry.message_count.to_string(),
+            entry.class_count.to_string(),
+            entry.max_nesting_depth.to_string(),
+            entry
+                .cyclomatic_complexity
+                .map(|c| c.to_string())
+                .unwrap_or_default(),
+            max_fan_in.to_string(),
+            max_fan_out.to_string(),
+            entry.cycles.len().to_string(),
+            entry.components.len().to_string(),
+            csv_field(entry.error.as_deref().unwrap_or_default()),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Directed graph over the elements of a diagram
+struct Graph {
+    ids: Vec<String>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl Graph {
+    fn from_diagram(diagram: &ParsedDiagram) -> Self {
+        let ids: Vec<String> = match diagram.kind {
+            DiagramKind::Sequence => diagram.participants.iter().map(|p| p.id.clone()).collect(),
+            DiagramKind::Class => diagram.classes.iter().map(|c| c.name.clone()).collect(),
+            _ => diagram.nodes.iter().map(|n| n.id.clone()).collect(),
+        };
+        let index: HashMap<&str, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.as_str(), i))
+            .collect();
+
+        let pairs: Vec<(&str, &str)> = match diagram.kind {
+            DiagramKind::Sequence => diagram
+                .messages
+                .iter()
+                .map(|m| (m.from.as_str(), m.to.as_str()))
+                .collect(),
+            _ => diagram
+                .edges
+                .iter()
+                .map(|e| (e.from.as_str(), e.to.as_str()))
+                .collect(),
+        };
+
+        let edges = pairs
+            .into_iter()
+            .filter_map(|(from, to)| Some((*index.get(from)?, *index.get(to)?)))
+            .collect();
+
+        Self { ids, edges }
+    }
+
+    fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn degrees(&self) -> Vec<NodeDegree> {
+        let mut degrees: Vec<NodeDegree> = self
+            .ids
+            .iter()
+            .map(|id| NodeDegree {
+                id: id.clone(),
+                fan_in: 0,
+                fan_out: 0,
+            })
+            .collect();
+
+        for &(from, to) in &self.edges {
+            degrees[from].fan_out += 1;
+            degrees[to].fan_in += 1;
+        }
+
+        degrees
+    }
+
+    fn successors(&self) -> Vec<Vec<usize>> {
+        let mut successors = vec![Vec::new(); self.ids.len()];
+        for &(from, to) in &self.edges {
+            if !successors[from].contains(&to) {
+                successors[from].push(to);
+            }
+        }
+        successors
+    }
+
+    /// Strongly connected components that contain a cycle, from Tarjan's
+    /// algorithm. It walks the graph with an explicit stack, so long chains
+    /// of nodes cannot overflow the call stack.
+    fn cyclic_components(&self) -> Vec<Vec<String>> {
+        struct State {
+            index: usize,
+            indexes: Vec<Option<usize>>,
+            lowlinks: Vec<usize>,
+            on_stack: Vec<bool>,
+            stack: Vec<usize>,
+        }
+
+        impl State {
+            fn enter(&mut self, node: usize) {
+                self.indexes[node] = Some(self.index);
+                self.lowlinks[node] = self.index;
+                self.index += 1;
+                self.stack.push(node);
+                self.on_stack[node] = true;
+            }
+        }
+
+        let successors = self.successors();
+        let count = self.ids.len();
+        let mut state = State {
+            index: 0,
+            indexes: vec![None; count],
+            lowlinks: vec![0; count],
+            on_stack: vec![false; count],
+            stack: Vec::new(),
+        };
+        let mut components = Vec::new();
+
+        for start in 0..count {
+            if state.indexes[start].is_some() {
+                continue;
+            }
+            state.enter(start);
+            // Nodes being visited, with how many of their successors are done
+            let mut path = vec![(start, 0)];
+            while let Some((node, visited)) = path.last_mut() {
+                let node = *node;
+                if let Some(&next) = successors[node].get(*visited) {
+                    *visited += 1;
+                    match state.indexes[next] {
+                        None => {
+                            state.enter(next);
+                            path.push((next, 0));
+                        }
+                        Some(index) if state.on_stack[next] => {
+                            state.lowlinks[node] = state.lowlinks[node].min(index);
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                path.pop();
+                if let Some(&(parent, _)) = path.last() {
+                    state.lowlinks[parent] = state.lowlinks[parent].min(state.lowlinks[node]);
+                }
+                if Some(state.lowlinks[node]) == state.indexes[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = state.stack.pop() {
+                        state.on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+
+        components
+            .into_iter()
+            .filter(|c| c.len() > 1 || successors[c[0]].contains(&c[0]))
+            .map(|mut c| {
+                c.sort_unstable();
+                c.into_iter().map(|i| self.ids[i].clone()).collect()
+            })
+            .collect()
+    }
+
+    fn components(&self) -> Vec<Vec<String>> {
+        let mut parent: Vec<usize> = (0..self.ids.len()).collect();
+
+        fn root(parent: &mut [usize], mut node: usize) -> usize {
+            while parent[node] != node {
+                parent[node] = parent[parent[node]];
+                node = parent[node];
+            }
+            node
+        }
+
+        for &(from, to) in &self.edges {
+            let (a, b) = (root(&mut parent, from), root(&mut parent, to));
+            if a != b {
+                parent[a] = b;
+            }
+        }
+
+        // Groups keep the order their first node appears in
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+        for node in 0..self.ids.len() {
+            let group = root(&mut parent, node);
+            let index = *group_of_root.entry(group).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[index].push(self.ids[node].clone());
+        }
+
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flowchart_metrics() {
+        let diagram = parse_diagram(
+            "flowchart TD\n  subgraph outer\n    subgraph inner\n      A\n    end\n  end\n  A --> B{Check}\n  B -->|retry| A\n  B --> C\n  D --> E\n",
+        )
+        .unwrap();
+        let stats = diagram_stats(&diagram);
+
+        assert_eq!(stats.node_count, 5);
+        assert_eq!(stats.edge_count, 4);
+        assert_eq!(stats.max_nesting_depth, 2);
+        assert_eq!(stats.cycles, vec![vec!["A".to_string(), "B".to_string()]]);
+        assert_eq!(stats.components.len(), 2);
+        // 4 edges - 5 nodes + 2 * 2 components
+        assert_eq!(stats.cyclomatic_complexity, Some(3));
+
+        let b = stats.degrees.iter().find(|d| d.id == "B").unwrap();
+        assert_eq!((b.fan_in, b.fan_out), (1, 2));
+    }
+
+    #[test]
+    fn test_cycles_on_deep_chain() {
+        // A chain far deeper than a recursive walk could follow
+        let count = 200_000;
+        let chain = Graph {
+            ids: (0..count).map(|i| format!("N{}", i)).collect(),
+            edges: (0..count)
+                .map(|i| (i, (i + 1) % count))
+                .chain([(5, 5)])
+                .collect(),
+        };
+        let components = chain.cyclic_components();
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), count);
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let diagram = parse_diagram("sequenceDiagram\n    A->>B: hi\n    B->>A: hello\n").unwrap();
+        let mut stats = diagram_stats(&diagram);
+        stats.path = Some("a,b.mmd".to_string());
+
+        let csv = export_stats(&[stats], StatsExportFormat::Csv).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("\"a,b.mmd\",sequence,0,0,0,2,2,0,0,,1,1,1,1,"));
+    }
+}
@@ -0,0 +1,773 @@
+use crate::models::SvgProcessOptions;
+use crate::services::parse_diagram;
+use crate::services::raster_service::font_database;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use resvg::usvg::fontdb::{Database, Family, Query, Weight, ID};
+use resvg::usvg::roxmltree;
+use std::collections::HashSet;
+
+const GENERIC_FAMILIES: [&str; 5] = ["serif", "sans-serif", "monospace", "cursive", "fantasy"];
+
+/// Post-process SVG markup from mmdc: background, scripts, accessibility
+/// metadata, unused definitions, embedded fonts and minification.
+///
+/// `code` is the mermaid source the SVG was rendered from; it supplies the
+/// `accTitle` and `accDescr` text. HTML `<br>` tags from older mmdc releases
+/// are closed before parsing. Markup that is still not well-formed XML is an
+/// error when scripts are to be stripped, and is otherwise returned as-is.
+pub fn process_svg(svg: &str, code: &str, options: &SvgProcessOptions) -> Result<String, String> {
+    let normalized = svg.replace("<br>", "<br/>");
+    let document = match parse_document(&normalized) {
+        Ok(document) => document,
+        Err(e) if options.strip_scripts => {
+            return Err(format!("Failed to parse SVG: {}", e));
+        }
+        Err(e) => {
+            log::warn!("Skipping SVG post-processing, failed to parse SVG: {}", e);
+            return Ok(svg.to_string());
+        }
+    };
+
+    let mut root = XmlElement::from_node(document.root_element(), &[]);
+    if root.local_name() != "svg" {
+        return Err(format!(
+            "Expected an <svg> root element, found <{}>",
+            root.name
+        ));
+    }
+
+    if options.strip_scripts {
+        strip_scripts(&mut root);
+    }
+
+    if let Some(ref background) = options.background {
+        set_background(&mut root, background);
+    }
+
+    if options.fit_content {
+        fit_content(&mut root, options.scale.filter(|s| *s > 0.0).unwrap_or(1.0));
+    }
+
+    if options.accessibility {
+        add_accessibility(&mut root, code);
+    }
+
+    if options.remove_unused_defs {
+        remove_unused_defs(&mut root);
+    }
+
+    if options.embed_fonts {
+        embed_fonts(&mut root, &font_database());
+    }
+
+    if options.minify {
+        minify(&mut root, false);
+    }
+
+    let mut out = String::with_capacity(svg.len());
+    root.write(&mut out);
+    Ok(out)
+}
+
+/// Remove scripts, event handlers, `javascript:` links and stylesheet
+/// `@import` rules from SVG markup, for inlining it into a web page. The
+/// XML prolog is dropped, and HTML `<br>` tags from older mmdc releases are
+/// closed so that the markup parses.
+pub fn sanitize_svg(svg: &str) -> Result<String, String> {
+    let svg = svg.replace("<br>", "<br/>");
+    let document = parse_document(&svg).map_err(|e| format!("Failed to parse SVG: {}", e))?;
+
+    let mut root = XmlElement::from_node(document.root_element(), &[]);
+    if root.local_name() != "svg" {
+        return Err(format!(
+            "Expected an <svg> root element, found <{}>",
+            root.name
+        ));
+    }
+    strip_scripts(&mut root);
+    strip_imports(&mut root);
+
+    let mut out = String::with_capacity(svg.len());
+    root.write(&mut out);
+    Ok(out)
+}
+
+fn parse_document(svg: &str) -> Result<roxmltree::Document<'_>, roxmltree::Error> {
+    roxmltree::Document::parse_with_options(
+        svg,
+        roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..roxmltree::ParsingOptions::default()
+        },
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Document model
+// ---------------------------------------------------------------------------
+
+enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+    Comment(String),
+}
+
+/// Mutable element tree; roxmltree itself is read-only
+struct XmlElement {
+    /// Qualified name, e.g. `svg` or `xlink:href`
+    name: String,
+    /// Namespace declarations introduced on this element
+    namespaces: Vec<(String, String)>,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+}
+
+impl XmlElement {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            namespaces: Vec::new(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn from_node(node: roxmltree::Node, parent_scope: &[(Option<&str>, &str)]) -> Self {
+        let scope: Vec<(Option<&str>, &str)> = node
+            .namespaces()
+            .map(|ns| (ns.name(), ns.uri()))
+            .filter(|(prefix, _)| *prefix != Some("xml"))
+            .collect();
+
+        let namespaces = scope
+            .iter()
+            .filter(|ns| !parent_scope.contains(ns))
+            .map(|(prefix, uri)| match prefix {
+                Some(prefix) => (format!("xmlns:{}", prefix), uri.to_string()),
+                None => ("xmlns".to_string(), uri.to_string()),
+            })
+            .collect();
+
+        let qualify = |namespace: Option<&str>, name: &str| match namespace
+            .and_then(|uri| node.lookup_prefix(uri))
+        {
+            Some(prefix) if !prefix.is_empty() => format!("{}:{}", prefix, name),
+            _ => name.to_string(),
+        };
+
+        let attributes = node
+            .attributes()
+            .map(|a| (qualify(a.namespace(), a.name()), a.value().to_string()))
+            .collect();
+
+        let children = node
+            .children()
+            .filter_map(|child| match child.node_type() {
+                roxmltree::NodeType::Element => {
+                    Some(XmlNode::Element(XmlElement::from_node(child, &scope)))
+                }
+                roxmltree::NodeType::Text => child.text().map(|t| XmlNode::Text(t.to_string())),
+                roxmltree::NodeType::Comment => {
+                    child.text().map(|t| XmlNode::Comment(t.to_string()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            name: qualify(node.tag_name().namespace(), node.tag_name().name()),
+            namespaces,
+            attributes,
+            children,
+        }
+    }
+
+    fn local_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or_default()
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn set_attr(&mut self, name: &str, value: String) {
+        match self.attributes.iter_mut().find(|(key, _)| key == name) {
+            Some(attribute) => attribute.1 = value,
+            None => self.attributes.push((name.to_string(), value)),
+        }
+    }
+
+    fn remove_attr(&mut self, name: &str) {
+        self.attributes.retain(|(key, _)| key != name);
+    }
+
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .filter_map(|child| match child {
+                XmlNode::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+        self.children.iter().filter_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    fn elements_mut(&mut self) -> impl Iterator<Item = &mut XmlElement> {
+        self.children.iter_mut().filter_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.name);
+        for (name, value) in self.namespaces.iter().chain(&self.attributes) {
+            out.push(' ');
+            out.push_str(name);
+            out.push_str("=\"");
+            out.push_str(&escape(value, true));
+            out.push('"');
+        }
+
+        if self.children.is_empty() {
+            out.push_str("/>");
+            return;
+        }
+
+        out.push('>');
+        for child in &self.children {
+            match child {
+                XmlNode::Element(element) => element.write(out),
+                XmlNode::Text(text) => out.push_str(&escape(text, false)),
+                XmlNode::Comment(text) => {
+                    out.push_str("<!--");
+                    out.push_str(text);
+                    out.push_str("-->");
+                }
+            }
+        }
+        out.push_str("</");
+        out.push_str(&self.name);
+        out.push('>');
+    }
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    if attribute {
+        escaped = escaped.replace('"', "&quot;");
+    }
+    escaped
+}
+
+// ---------------------------------------------------------------------------
+// Processing steps
+// ---------------------------------------------------------------------------
+
+fn strip_scripts(element: &mut XmlElement) {
+    element.children.retain(|child| {
+        !matches!(child, XmlNode::Element(e) if e.local_name().eq_ignore_ascii_case("script"))
+    });
+
+    element.attributes.retain(|(name, value)| {
+        let local = name.rsplit(':').next().unwrap_or_default();
+        let is_handler = local.len() > 2 && local[..2].eq_ignore_ascii_case("on");
+        let is_script_link = local == "href"
+            && value
+                .trim_start()
+                .to_ascii_lowercase()
+                .starts_with("javascript:");
+        !is_handler && !is_script_link
+    });
+
+    for child in element.elements_mut() {
+        strip_scripts(child);
+    }
+}
+
+/// Remove `@import` rules from stylesheets, which would load from the network
+fn strip_imports(element: &mut XmlElement) {
+    if element.local_name() == "style" {
+        for child in &mut element.children {
+            let XmlNode::Text(css) = child else {
+                continue;
+            };
+            while let Some(start) = css.find("@import") {
+                let end = css[start..]
+                    .find(';')
+                    .map_or(css.len(), |end| start + end + 1);
+                css.replace_range(start..end, "");
+            }
+        }
+    }
+
+    for child in element.elements_mut() {
+        strip_imports(child);
+    }
+}
+
+/// Set or remove one declaration in an inline `style` attribute
+fn set_style_property(element: &mut XmlElement, property: &str, value: Option<&str>) {
+    let mut declarations: Vec<String> = element
+        .attr("style")
+        .unwrap_or_default()
+        .split(';')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .filter(|d| {
+            d.split(':')
+                .next()
+                .is_none_or(|name| !name.trim().eq_ignore_ascii_case(property))
+        })
+        .map(str::to_string)
+        .collect();
+
+    if let Some(value) = value {
+        declarations.push(format!("{}: {}", property, value));
+    }
+
+    if declarations.is_empty() {
+        element.remove_attr("style");
+    } else {
+        element.set_attr("style", format!("{};", declarations.join("; ")));
+    }
+}
+
+fn set_background(root: &mut XmlElement, background: &str) {
+    let background = background.trim();
+    let value = if background.is_empty() || background.eq_ignore_ascii_case("transparent") {
+        None
+    } else {
+        Some(background)
+    };
+    set_style_property(root, "background-color", value);
+}
+
+/// Replace mmdc's `width="100%"` and `max-width` with the diagram's own size
+fn fit_content(root: &mut XmlElement, scale: f32) {
+    let view_box: Vec<f32> = root
+        .attr("viewBox")
+        .unwrap_or_default()
+        .split([' ', ','])
+        .filter(|n| !n.is_empty())
+        .filter_map(|n| n.parse().ok())
+        .collect();
+    let [_, _, width, height] = view_box[..] else {
+        return;
+    };
+
+    let length = |value: f32| {
+        let text = format!("{:.2}", value * scale);
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    };
+    root.set_attr("width", length(width));
+    root.set_attr("height", length(height));
+    set_style_property(root, "max-width", None);
+}
+
+fn add_accessibility(root: &mut XmlElement, code: &str) {
+    let diagram = parse_diagram(code).ok();
+    let title = diagram
+        .as_ref()
+        .and_then(|d| d.acc_title.clone().or_else(|| d.title.clone()));
+    let description = diagram.and_then(|d| d.acc_descr);
+
+    let id = root.attr("id").unwrap_or("diagram").to_string();
+    root.set_attr("role", "img".to_string());
+
+    // Mermaid adds its own title and desc for accTitle/accDescr; replace them
+    root.children.retain(
+        |child| !matches!(child, XmlNode::Element(e) if matches!(e.local_name(), "title" | "desc")),
+    );
+    root.remove_attr("aria-labelledby");
+    root.remove_attr("aria-describedby");
+
+    if let Some(description) = description {
+        let desc_id = format!("{}-desc", id);
+        let mut desc = XmlElement::new("desc");
+        desc.set_attr("id", desc_id.clone());
+        desc.children.push(XmlNode::Text(description));
+        root.children.insert(0, XmlNode::Element(desc));
+        root.set_attr("aria-describedby", desc_id);
+    }
+
+    if let Some(title) = title {
+        let title_id = format!("{}-title", id);
+        let mut element = XmlElement::new("title");
+        element.set_attr("id", title_id.clone());
+        element.children.push(XmlNode::Text(title));
+        root.children.insert(0, XmlNode::Element(element));
+        root.set_attr("aria-labelledby", title_id);
+    }
+}
+
+/// Ids referenced through `url(#id)` or `href="#id"` anywhere in the tree
+fn collect_references(element: &XmlElement, references: &mut HashSet<String>) {
+    for (name, value) in &element.attributes {
+        if name.ends_with("href") {
+            if let Some(id) = value.trim().strip_prefix('#') {
+                references.insert(id.to_string());
+            }
+        }
+        collect_url_references(value, references);
+    }
+
+    if element.local_name() == "style" {
+        collect_url_references(&element.text(), references);
+    }
+
+    for child in element.elements() {
+        collect_references(child, references);
+    }
+}
+
+fn collect_url_references(text: &str, references: &mut HashSet<String>) {
+    for part in text.split("url(").skip(1) {
+        let id = part.trim_start_matches(['\'', '"', ' ']);
+        if let Some(id) = id.strip_prefix('#') {
+            let end = id.find([')', '\'', '"', ' ']).unwrap_or(id.len());
+            references.insert(id[..end].to_string());
+        }
+    }
+}
+
+fn remove_unused_defs(root: &mut XmlElement) {
+    fn prune(element: &mut XmlElement, references: &HashSet<String>) -> usize {
+        let mut removed = 0;
+        if element.local_name() == "defs" {
+            let before = element.children.len();
+            element.children.retain(|child| match child {
+                XmlNode::Element(def) => def.attr("id").is_none_or(|id| references.contains(id)),
+                _ => true,
+            });
+            removed += before - element.children.len();
+        }
+        for child in element.elements_mut() {
+            removed += prune(child, references);
+        }
+        removed
+    }
+
+    // Removing a definition can orphan the ones it referenced, so repeat
+    loop {
+        let mut references = HashSet::new();
+        collect_references(root, &mut references);
+        if prune(root, &references) == 0 {
+            break;
+        }
+    }
+}
+
+/// `font-family` and `font-weight` values from attributes, inline styles and stylesheets
+fn collect_font_declarations(element: &XmlElement, families: &mut Vec<String>, bold: &mut bool) {
+    let mut record = |property: &str, value: &str| match property {
+        "font-family" => {
+            let value = value.trim().to_string();
+            if !value.is_empty() && !families.contains(&value) {
+                families.push(value);
+            }
+        }
+        "font-weight" => {
+            let value = value.trim();
+            if value.starts_with("bold") || value.parse::<u16>().is_ok_and(|w| w >= 600) {
+                *bold = true;
+            }
+        }
+        _ => {}
+    };
+
+    for (name, value) in &element.attributes {
+        match name.as_str() {
+            "font-family" | "font-weight" => record(name, value),
+            "style" => css_declarations(value, &mut record),
+            _ => {}
+        }
+    }
+
+    if element.local_name() == "style" {
+        css_declarations(&element.text(), &mut record);
+    }
+
+    for child in element.elements() {
+        collect_font_declarations(child, families, bold);
+    }
+}
+
+fn css_declarations(css: &str, record: &mut impl FnMut(&str, &str)) {
+    for declaration in css.split([';', '{', '}']) {
+        if let Some((property, value)) = declaration.split_once(':') {
+            let value = value.trim().trim_end_matches("!important").trim();
+            record(property.trim(), value);
+        }
+    }
+}
+
+/// Family names of a CSS `font-family` value, without quotes
+fn split_families(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|f| f.trim().trim_matches(['"', '\'']).trim().to_string())
+        .filter(|f| !f.is_empty())
+        .collect()
+}
+
+/// Face of the first family in a CSS family list that is installed
+fn resolve_face(database: &Database, families: &[String], weight: u16) -> Option<ID> {
+    // fontdb matches names case-sensitively, CSS does not
+    let installed: Vec<String> = families
+        .iter()
+        .filter(|f| !GENERIC_FAMILIES.contains(&f.to_ascii_lowercase().as_str()))
+        .filter_map(|family| {
+            database
+                .faces()
+                .flat_map(|face| face.families.iter())
+                .find(|(name, _)| name.eq_ignore_ascii_case(family))
+                .map(|(name, _)| name.clone())
+        })
+        .collect();
+
+    let mut query_families: Vec<Family> = installed.iter().map(|f| Family::Name(f)).collect();
+    for family in families {
+        match family.to_ascii_lowercase().as_str() {
+            "serif" => query_families.push(Family::Serif),
+            "sans-serif" => query_families.push(Family::SansSerif),
+            "monospace" => query_families.push(Family::Monospace),
+            "cursive" => query_families.push(Family::Cursive),
+            "fantasy" => query_families.push(Family::Fantasy),
+            _ => {}
+        }
+    }
+    query_families.push(Family::SansSerif);
+    // Same as the renderer: with none of them installed, any face is better than none
+    let fallback = database
+        .faces()
+        .find_map(|face| face.families.first())
+        .map(|(name, _)| name.clone());
+    if let Some(ref fallback) = fallback {
+        query_families.push(Family::Name(fallback));
+    }
+
+    database.query(&Query {
+        families: &query_families,
+        weight: Weight(weight),
+        ..Query::default()
+    })
+}
+
+fn font_format(data: &[u8]) -> Option<(&'static str, &'static str)> {
+    match data.get(..4)? {
+        b"OTTO" => Some(("font/otf", "opentype")),
+        [0, 1, 0, 0] | b"true" => Some(("font/ttf", "truetype")),
+        b"wOFF" => Some(("font/woff", "woff")),
+        b"wOF2" => Some(("font/woff2", "woff2")),
+        _ => None,
+    }
+}
+
+/// Inline the faces used by the diagram as `@font-face` rules.
+///
+/// Each rule is registered under the first family named in the diagram's
+/// CSS, so other machines use exactly the face this one rendered with.
+fn embed_fonts(root: &mut XmlElement, database: &Database) {
+    let mut declarations = Vec::new();
+    let mut bold = false;
+    collect_font_declarations(root, &mut declarations, &mut bold);
+
+    let weights: &[u16] = if bold { &[400, 700] } else { &[400] };
+    let mut embedded = HashSet::new();
+    let mut css = String::new();
+
+    for declaration in &declarations {
+        let families = split_families(declaration);
+        let Some(alias) = families
+            .iter()
+            .find(|f| !GENERIC_FAMILIES.contains(&f.to_ascii_lowercase().as_str()))
+        else {
+            continue;
+        };
+
+        for &weight in weights {
+            if !embedded.insert((alias.to_ascii_lowercase(), weight)) {
+                continue;
+            }
+            let Some(id) = resolve_face(database, &families, weight) else {
+                continue;
+            };
+
+            // Faces inside font collections cannot be referenced from a data URI
+            let data = database
+                .with_face_data(id, |data, index| (index == 0).then(|| data.to_vec()))
+                .flatten();
+            let Some((data, (mime, format))) =
+                data.and_then(|data| font_format(&data).map(|format| (data, format)))
+            else {
+                log::warn!("Cannot embed font for \"{}\"", alias);
+                continue;
+            };
+
+            css.push_str(&format!(
+                "@font-face{{font-family:\"{}\";font-weight:{};src:url(data:{};base64,{}) format(\"{}\")}}",
+                alias.replace('"', ""),
+                weight,
+                mime,
+                BASE64.encode(&data),
+                format
+            ));
+        }
+    }
+
+    if css.is_empty() {
+        return;
+    }
+
+    let mut style = XmlElement::new("style");
+    style.children.push(XmlNode::Text(css));
+    let position = root
+        .children
+        .iter()
+        .position(|child| {
+            !matches!(child, XmlNode::Element(e) if matches!(e.local_name(), "title" | "desc"))
+        })
+        .unwrap_or(root.children.len());
+    root.children.insert(position, XmlNode::Element(style));
+}
+
+/// Drop comments, insignificant whitespace and empty groups
+fn minify(element: &mut XmlElement, preserve_space: bool) {
+    let preserve_space = preserve_space
+        || matches!(
+            element.local_name(),
+            "text" | "tspan" | "textPath" | "foreignObject"
+        )
+        || element.attr("xml:space") == Some("preserve");
+    let is_style = element.local_name() == "style";
+
+    element.children.retain_mut(|child| match child {
+        XmlNode::Comment(_) => false,
+        XmlNode::Text(text) if is_style => {
+            *text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            !text.is_empty()
+        }
+        XmlNode::Text(text) => preserve_space || !text.trim().is_empty(),
+        XmlNode::Element(child) => {
+            minify(child, preserve_space);
+            !(matches!(child.local_name(), "g" | "defs")
+                && child.attributes.is_empty()
+                && child.children.is_empty())
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = r##"<svg id="my-svg" width="100%" xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" style="max-width: 100px; background-color: white;" onload="alert(1)">
+  <!-- generated -->
+  <style>#my-svg .node rect { fill: url(#used); }</style>
+  <defs><linearGradient id="used"/><marker id="orphan"><path xlink:href="#inner"/></marker><path id="inner"/></defs>
+  <script>alert(2)</script>
+  <g>
+    <a xlink:href="javascript:alert(3)"><text x="0"> A &amp; B </text></a>
+    <foreignObject><div xmlns="http://www.w3.org/1999/xhtml"><span>Label</span></div></foreignObject>
+  </g>
+  <g></g>
+</svg>"##;
+
+    #[test]
+    fn test_process_svg() {
+        let options = SvgProcessOptions {
+            background: Some("#0f0f23".to_string()),
+            minify: true,
+            ..SvgProcessOptions::default()
+        };
+        let code =
+            "flowchart TD\n    accTitle: Checkout\n    accDescr: Steps to pay\n    A --> B\n";
+
+        let svg = process_svg(SVG, code, &options).unwrap();
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" id="my-svg""#));
+        assert!(svg.contains(r#"style="max-width: 100px; background-color: #0f0f23;""#));
+        assert!(svg.contains(r#"role="img""#));
+        assert!(svg.contains(r#"aria-labelledby="my-svg-title""#));
+        assert!(svg.contains(r#"><title id="my-svg-title">Checkout</title><desc id="my-svg-desc">Steps to pay</desc><style>"#));
+        assert!(svg.contains(r#"<defs><linearGradient id="used"/></defs>"#));
+        assert!(svg.contains(r#"<text x="0"> A &amp; B </text>"#));
+        assert!(
+            svg.contains(r#"<div xmlns="http://www.w3.org/1999/xhtml"><span>Label</span></div>"#)
+        );
+        assert!(!svg.contains("alert"));
+        assert!(!svg.contains("<!--"));
+        assert!(!svg.contains("<g></g>") && !svg.contains("<g/>"));
+        assert!(!svg.contains('\n'));
+
+        // The output is well-formed and survives a second pass unchanged
+        assert_eq!(process_svg(&svg, code, &options).unwrap(), svg);
+    }
+
+    #[test]
+    fn test_fit_content() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="100%" viewBox="-8 -8 316.5 120" style="max-width: 316.5px;"><g/></svg>"#;
+        let options = SvgProcessOptions {
+            fit_content: true,
+            scale: Some(2.0),
+            ..SvgProcessOptions::default()
+        };
+
+        let svg = process_svg(svg, "flowchart TD", &options).unwrap();
+        assert!(svg.contains(r#"width="633" viewBox="-8 -8 316.5 120" height="240""#));
+        assert!(!svg.contains("max-width"));
+
+        // Older mmdc writes HTML line breaks that are not valid XML
+        let html = r#"<svg xmlns="http://www.w3.org/2000/svg"><foreignObject><div>A<br>B</div><script>alert(1)</script></foreignObject></svg>"#;
+        let svg = process_svg(html, "flowchart TD", &options).unwrap();
+        assert!(svg.contains("<div>A<br/>B</div>"));
+        assert!(!svg.contains("alert"));
+
+        // Scripts cannot be stripped from markup that does not parse
+        let broken =
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><g><script>alert(1)</script></svg>"#;
+        assert!(process_svg(broken, "flowchart TD", &options).is_err());
+        let options = SvgProcessOptions {
+            strip_scripts: false,
+            ..options
+        };
+        assert_eq!(
+            process_svg(broken, "flowchart TD", &options).unwrap(),
+            broken
+        );
+    }
+
+    #[test]
+    fn test_font_declarations() {
+        let document = roxmltree::Document::parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><style>.label { font-family: "trebuchet ms", verdana, arial, sans-serif; font-weight: bold }</style><text font-family="monospace">x</text></svg>"#,
+        )
+        .unwrap();
+        let root = XmlElement::from_node(document.root_element(), &[]);
+
+        let mut families = Vec::new();
+        let mut bold = false;
+        collect_font_declarations(&root, &mut families, &mut bold);
+
+        assert!(bold);
+        assert_eq!(
+            split_families(&families[0]),
+            ["trebuchet ms", "verdana", "arial", "sans-serif"]
+        );
+        assert_eq!(families[1], "monospace");
+    }
+}
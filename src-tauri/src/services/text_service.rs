@@ -0,0 +1,1337 @@
+use crate::models::{
+    ArrowHead, DiagramKind, EdgeStroke, NodeShape, NotePlacement, ParsedDiagram, SequenceStep,
+    TextOptions,
+};
+use crate::services::flowchart_service::{edge_endpoint, label_lines};
+use crate::services::layout_service::{
+    layout, Direction, LayoutCluster, LayoutEdge, LayoutNode, LayoutOptions, Point,
+};
+use crate::services::parser_service::parse_diagram;
+use std::collections::HashMap;
+
+/// Sides a line leaves a cell through
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+/// Longest label line, tried widest first until the diagram fits the
+/// width limit
+const WRAP_WIDTHS: [Option<usize>; 4] = [None, Some(24), Some(16), Some(10)];
+
+/// Render a flowchart or sequence diagram as Unicode box-drawing art, or
+/// plain ASCII, wrapping labels when needed to stay within the width limit
+pub fn render_text(code: &str, options: &TextOptions) -> Result<String, String> {
+    let diagram = parse_diagram(code)?;
+    let render = match diagram.kind {
+        DiagramKind::Flowchart => flowchart_text,
+        DiagramKind::Sequence => sequence_text,
+        _ => {
+            return Err("Only flowcharts and sequence diagrams can be rendered as text".to_string())
+        }
+    };
+
+    let mut narrowest = usize::MAX;
+    for wrap in WRAP_WIDTHS {
+        let text = render(&diagram, wrap, options.ascii);
+        let width = text.lines().map(text_width).max().unwrap_or(0) as usize;
+        match options.max_width {
+            Some(max_width) if width > max_width => narrowest = narrowest.min(width),
+            _ => return Ok(text),
+        }
+    }
+    Err(format!(
+        "The diagram needs at least {} columns, more than the limit of {}",
+        narrowest,
+        options.max_width.unwrap_or_default()
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stroke {
+    Light,
+    Dotted,
+    Heavy,
+}
+
+impl From<EdgeStroke> for Stroke {
+    fn from(stroke: EdgeStroke) -> Self {
+        match stroke {
+            EdgeStroke::Dotted => Stroke::Dotted,
+            EdgeStroke::Thick => Stroke::Heavy,
+            EdgeStroke::Normal | EdgeStroke::Invisible => Stroke::Light,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Cell {
+    Char(char),
+    /// Line sides, joined into the matching box-drawing character
+    Line(u8, Stroke),
+    /// Second column of a wide character
+    Covered,
+}
+
+/// A sparse character grid; lines drawn across each other join up
+#[derive(Default)]
+struct Canvas {
+    cells: HashMap<(i64, i64), Cell>,
+    /// Zero-width characters such as combining accents, written after the
+    /// cell they follow
+    marks: HashMap<(i64, i64), String>,
+}
+
+impl Canvas {
+    fn put(&mut self, x: i64, y: i64, ch: char) {
+        self.cells.insert((x, y), Cell::Char(ch));
+    }
+
+    fn text(&mut self, x: i64, y: i64, text: &str) {
+        let mut column = x;
+        for ch in text.chars() {
+            match char_width(ch) {
+                0 => self.marks.entry((column - 1, y)).or_default().push(ch),
+                width => {
+                    self.put(column, y, ch);
+                    if width == 2 {
+                        self.cells.insert((column + 1, y), Cell::Covered);
+                    }
+                    column += width;
+                }
+            }
+        }
+    }
+
+    /// Empty the cells within a box, including its border
+    fn clear(&mut self, rect: CellRect) {
+        self.cells.retain(|&(x, y), _| {
+            x < rect.left || x > rect.right || y < rect.top || y > rect.bottom
+        });
+    }
+
+    /// Add line sides to a cell, keeping any text and the first stroke
+    fn mark(&mut self, x: i64, y: i64, sides: u8, stroke: Stroke) {
+        match self.cells.get_mut(&(x, y)) {
+            Some(Cell::Line(existing, _)) => *existing |= sides,
+            Some(Cell::Char(_) | Cell::Covered) => {}
+            None => {
+                self.cells.insert((x, y), Cell::Line(sides, stroke));
+            }
+        }
+    }
+
+    /// A horizontal or vertical line between two cells
+    fn segment(&mut self, from: (i64, i64), to: (i64, i64), stroke: Stroke) {
+        if from == to {
+            return;
+        }
+        let step = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+        let (forward, backward) = match step {
+            (1, _) => (RIGHT, LEFT),
+            (-1, _) => (LEFT, RIGHT),
+            (_, 1) => (DOWN, UP),
+            _ => (UP, DOWN),
+        };
+        let (mut x, mut y) = from;
+        self.mark(x, y, forward, stroke);
+        while (x, y) != to {
+            x += step.0;
+            y += step.1;
+            let sides = if (x, y) == to {
+                backward
+            } else {
+                forward | backward
+            };
+            self.mark(x, y, sides, stroke);
+        }
+    }
+
+    fn polyline(&mut self, points: &[(i64, i64)], stroke: Stroke) {
+        for pair in points.windows(2) {
+            self.segment(pair[0], pair[1], stroke);
+        }
+    }
+
+    fn rect(&mut self, rect: CellRect) {
+        let CellRect {
+            left,
+            top,
+            right,
+            bottom,
+        } = rect;
+        self.polyline(
+            &[
+                (left, top),
+                (right, top),
+                (right, bottom),
+                (left, bottom),
+                (left, top),
+            ],
+            Stroke::Light,
+        );
+    }
+
+    fn render(&self, ascii: bool) -> String {
+        let Some(&(first_x, first_y)) = self.cells.keys().next() else {
+            return String::new();
+        };
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (first_x, first_x, first_y, first_y);
+        for &(x, y) in self.cells.keys() {
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+        }
+
+        let mut text = String::new();
+        for y in min_y..=max_y {
+            let mut line = String::new();
+            for x in min_x..=max_x {
+                match self.cells.get(&(x, y)) {
+                    None => line.push(' '),
+                    Some(Cell::Char(ch)) => line.push(*ch),
+                    Some(Cell::Line(sides, stroke)) => line.push(line_char(*sides, *stroke, ascii)),
+                    Some(Cell::Covered) => {}
+                }
+                if let Some(marks) = self.marks.get(&(x, y)) {
+                    line.push_str(marks);
+                }
+            }
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+fn line_char(sides: u8, stroke: Stroke, ascii: bool) -> char {
+    let vertical = sides & (LEFT | RIGHT) == 0;
+    let horizontal = sides & (UP | DOWN) == 0;
+    if ascii {
+        return match (vertical, horizontal, stroke) {
+            (true, _, Stroke::Dotted) => ':',
+            (true, _, _) => '|',
+            (_, true, Stroke::Dotted) => '.',
+            (_, true, Stroke::Heavy) => '=',
+            (_, true, _) => '-',
+            _ => '+',
+        };
+    }
+    match (vertical, horizontal, stroke) {
+        (true, _, Stroke::Dotted) => '┆',
+        (true, _, Stroke::Heavy) => '┃',
+        (true, _, _) => '│',
+        (_, true, Stroke::Dotted) => '┄',
+        (_, true, Stroke::Heavy) => '━',
+        (_, true, _) => '─',
+        _ => match sides {
+            s if s == DOWN | RIGHT => '┌',
+            s if s == DOWN | LEFT => '┐',
+            s if s == UP | RIGHT => '└',
+            s if s == UP | LEFT => '┘',
+            s if s == UP | DOWN | RIGHT => '├',
+            s if s == UP | DOWN | LEFT => '┤',
+            s if s == DOWN | LEFT | RIGHT => '┬',
+            s if s == UP | LEFT | RIGHT => '┴',
+            _ => '┼',
+        },
+    }
+}
+
+/// The marker at the end of a line moving towards `motion`
+fn head_char(head: ArrowHead, motion: u8, ascii: bool) -> Option<char> {
+    let arrow = match (motion, ascii) {
+        (DOWN, false) => '▼',
+        (UP, false) => '▲',
+        (RIGHT, false) => '▶',
+        (LEFT, false) => '◀',
+        (DOWN, true) => 'v',
+        (UP, true) => '^',
+        (RIGHT, true) => '>',
+        _ => '<',
+    };
+    match head {
+        ArrowHead::None => None,
+        ArrowHead::Arrow => Some(arrow),
+        ArrowHead::Circle => Some('o'),
+        ArrowHead::Cross => Some('x'),
+    }
+}
+
+/// Inclusive cell bounds of a box
+#[derive(Debug, Clone, Copy)]
+struct CellRect {
+    left: i64,
+    top: i64,
+    right: i64,
+    bottom: i64,
+}
+
+impl CellRect {
+    fn center(&self) -> (i64, i64) {
+        ((self.left + self.right) / 2, (self.top + self.bottom) / 2)
+    }
+}
+
+fn cell(point: Point) -> (i64, i64) {
+    (point.x.round() as i64, point.y.round() as i64)
+}
+
+/// Terminal columns taken by a character: none for combining marks and
+/// other zero-width characters, two for East Asian wide characters and
+/// emoji, one otherwise
+fn char_width(ch: char) -> i64 {
+    match ch as u32 {
+        0x0300..=0x036F
+        | 0x0483..=0x0489
+        | 0x0591..=0x05BD
+        | 0x0610..=0x061A
+        | 0x064B..=0x065F
+        | 0x0E31
+        | 0x0E34..=0x0E3A
+        | 0x0E47..=0x0E4E
+        | 0x1AB0..=0x1AFF
+        | 0x1DC0..=0x1DFF
+        | 0x200B..=0x200F
+        | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F
+        | 0xFEFF
+        | 0xE0100..=0xE01EF => 0,
+        0x1100..=0x115F
+        | 0x231A..=0x231B
+        | 0x2329..=0x232A
+        | 0x23E9..=0x23EC
+        | 0x23F0
+        | 0x23F3
+        | 0x25FD..=0x25FE
+        | 0x2614..=0x2615
+        | 0x2648..=0x2653
+        | 0x267F
+        | 0x2693
+        | 0x26A1
+        | 0x26AA..=0x26AB
+        | 0x26BD..=0x26BE
+        | 0x26C4..=0x26C5
+        | 0x26CE
+        | 0x26D4
+        | 0x26EA
+        | 0x26F2..=0x26F3
+        | 0x26F5
+        | 0x26FA
+        | 0x26FD
+        | 0x2705
+        | 0x270A..=0x270B
+        | 0x2728
+        | 0x274C
+        | 0x274E
+        | 0x2753..=0x2755
+        | 0x2757
+        | 0x2795..=0x2797
+        | 0x27B0
+        | 0x27BF
+        | 0x2B1B..=0x2B1C
+        | 0x2B50
+        | 0x2B55
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xA960..=0xA97F
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE10..=0xFE19
+        | 0xFE30..=0xFE6F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x16FE0..=0x16FE4
+        | 0x17000..=0x18CFF
+        | 0x1B000..=0x1B2FF
+        | 0x1F004
+        | 0x1F0CF
+        | 0x1F18E
+        | 0x1F191..=0x1F19A
+        | 0x1F200..=0x1F251
+        | 0x1F300..=0x1F320
+        | 0x1F32D..=0x1F335
+        | 0x1F337..=0x1F37C
+        | 0x1F37E..=0x1F393
+        | 0x1F3A0..=0x1F3CA
+        | 0x1F3CF..=0x1F3D3
+        | 0x1F3E0..=0x1F3F0
+        | 0x1F3F4
+        | 0x1F3F8..=0x1F43E
+        | 0x1F440
+        | 0x1F442..=0x1F4FC
+        | 0x1F4FF..=0x1F53D
+        | 0x1F54B..=0x1F54E
+        | 0x1F550..=0x1F567
+        | 0x1F57A
+        | 0x1F595..=0x1F596
+        | 0x1F5A4
+        | 0x1F5FB..=0x1F64F
+        | 0x1F680..=0x1F6C5
+        | 0x1F6CC
+        | 0x1F6D0..=0x1F6D2
+        | 0x1F6D5..=0x1F6D7
+        | 0x1F6DC..=0x1F6DF
+        | 0x1F6EB..=0x1F6EC
+        | 0x1F6F4..=0x1F6FC
+        | 0x1F7E0..=0x1F7EB
+        | 0x1F7F0
+        | 0x1F90C..=0x1F93A
+        | 0x1F93C..=0x1F945
+        | 0x1F947..=0x1F9FF
+        | 0x1FA70..=0x1FAFF
+        | 0x20000..=0x2FFFD
+        | 0x30000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Terminal columns taken by a line of text
+fn text_width(text: &str) -> i64 {
+    text.chars().map(char_width).sum()
+}
+
+/// Label lines split on `<br>`, then word-wrapped to `wrap` characters
+fn wrap_lines(label: &str, wrap: Option<usize>) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in label_lines(label) {
+        let Some(wrap) = wrap else {
+            lines.push(line);
+            continue;
+        };
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            if !current.is_empty() && text_width(&current) + 1 + text_width(word) > wrap as i64 {
+                lines.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        lines.push(current);
+    }
+    lines
+}
+
+/// Prepend the diagram title, centred over the drawing
+fn with_title(title: Option<&str>, drawing: String) -> String {
+    let Some(title) = title else {
+        return drawing;
+    };
+    let width = drawing.lines().map(text_width).max().unwrap_or(0);
+    let indent = (width - text_width(title)).max(0) / 2;
+    format!("{}{}\n\n{}", " ".repeat(indent as usize), title, drawing)
+}
+
+fn flowchart_text(diagram: &ParsedDiagram, wrap: Option<usize>, ascii: bool) -> String {
+    let direction = Direction::parse(diagram.direction.as_deref());
+    let vertical = !direction.is_horizontal();
+    let has_labels = diagram.edges.iter().any(|edge| edge.label.is_some());
+    // Enough room between ranks for an edge to bend, or for its label
+    let options = LayoutOptions {
+        direction,
+        node_spacing: if vertical { 2.0 } else { 1.0 },
+        rank_spacing: match (vertical, has_labels) {
+            (true, false) => 3.0,
+            (true, true) => 5.0,
+            (false, false) => 5.0,
+            (false, true) => 6.0,
+        },
+        edge_spacing: if vertical { 2.0 } else { 1.0 },
+        cluster_padding: 2.0,
+    };
+
+    // Side by side ranks meet boxes on their left and right, so leave room
+    // there for several edges
+    let padding = if vertical { 0 } else { 1 };
+    let labels: Vec<Vec<String>> = diagram
+        .nodes
+        .iter()
+        .map(|node| wrap_lines(node.display_label(), wrap))
+        .collect();
+    let sizes: Vec<(i64, i64)> = labels
+        .iter()
+        .map(|lines| {
+            let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+            (width + 4, lines.len() as i64 + 2 + 2 * padding)
+        })
+        .collect();
+    let mut layout_nodes: Vec<LayoutNode> = diagram
+        .nodes
+        .iter()
+        .zip(&sizes)
+        .map(|(node, &(width, height))| LayoutNode {
+            width: width as f64,
+            height: height as f64,
+            cluster: diagram
+                .subgraphs
+                .iter()
+                .rposition(|s| s.nodes.contains(&node.id)),
+        })
+        .collect();
+    let clusters: Vec<LayoutCluster> = diagram
+        .subgraphs
+        .iter()
+        .map(|subgraph| LayoutCluster {
+            parent: subgraph
+                .parent
+                .as_ref()
+                .and_then(|p| diagram.subgraphs.iter().position(|s| s.id == *p)),
+            title_width: text_width(subgraph.label.as_deref().unwrap_or(&subgraph.id)) as f64 + 2.0,
+            // The title sits on the top border
+            title_height: 0.0,
+        })
+        .collect();
+
+    // Edges, with subgraph endpoints attached to one of their nodes
+    let endpoint = |id: &str| edge_endpoint(diagram, id).map(|(node, _)| node);
+    let mut edges = Vec::new();
+    let mut layout_edges = Vec::new();
+    for edge in &diagram.edges {
+        let (Some(from), Some(to)) = (endpoint(&edge.from), endpoint(&edge.to)) else {
+            continue;
+        };
+        let label = edge.label.as_deref().map(|label| wrap_lines(label, wrap));
+        let label_size = label.as_ref().map(|lines| {
+            let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+            (width as f64, lines.len() as f64)
+        });
+        if from == to {
+            // Room for the loop and its label beside the box, away from
+            // the sides other edges attach to
+            let node = &mut layout_nodes[from];
+            match (vertical, label_size) {
+                (true, _) => node.width += 3.0 + label_size.map_or(0.0, |(width, _)| width + 2.0),
+                (false, None) => node.height += 2.0,
+                (false, Some((width, height))) => {
+                    node.height += 2.0 + height;
+                    node.width = node.width.max(width + 2.0);
+                }
+            }
+        }
+        layout_edges.push(LayoutEdge {
+            from,
+            to,
+            label: label_size,
+        });
+        edges.push((edge, from, to, label));
+    }
+
+    let layout = layout(&layout_nodes, &layout_edges, &clusters, &options);
+    let mut canvas = Canvas::default();
+
+    for (rect, subgraph) in layout.clusters.iter().zip(&diagram.subgraphs) {
+        let Some(rect) = rect else {
+            continue;
+        };
+        let bounds = CellRect {
+            left: rect.x.round() as i64,
+            top: rect.y.round() as i64,
+            right: (rect.x + rect.width).round() as i64 - 1,
+            bottom: (rect.y + rect.height).round() as i64 - 1,
+        };
+        canvas.rect(bounds);
+        let title = subgraph.label.as_deref().unwrap_or(&subgraph.id);
+        canvas.text(bounds.left + 2, bounds.top, &format!(" {} ", title));
+    }
+
+    let boxes: Vec<CellRect> = layout
+        .nodes
+        .iter()
+        .zip(&layout_nodes)
+        .zip(&sizes)
+        .map(|((center, layout_node), &(width, height))| {
+            let left = (center.x - layout_node.width / 2.0).round() as i64;
+            let top = (center.y - layout_node.height / 2.0).round() as i64;
+            CellRect {
+                left,
+                top,
+                right: left + width - 1,
+                bottom: top + height - 1,
+            }
+        })
+        .collect();
+    for ((node, lines), rect) in diagram.nodes.iter().zip(&labels).zip(&boxes) {
+        canvas.rect(*rect);
+        let corners = match (node.shape, ascii) {
+            (NodeShape::Round | NodeShape::Stadium | NodeShape::Circle, false)
+            | (NodeShape::DoubleCircle, false) => Some(['╭', '╮', '╰', '╯']),
+            (NodeShape::Round | NodeShape::Stadium | NodeShape::Circle, true)
+            | (NodeShape::DoubleCircle, true) => Some(['.', '.', '\'', '\'']),
+            (NodeShape::Rhombus | NodeShape::Hexagon, _) => Some(['/', '\\', '\\', '/']),
+            _ => None,
+        };
+        if let Some([top_left, top_right, bottom_left, bottom_right]) = corners {
+            canvas.put(rect.left, rect.top, top_left);
+            canvas.put(rect.right, rect.top, top_right);
+            canvas.put(rect.left, rect.bottom, bottom_left);
+            canvas.put(rect.right, rect.bottom, bottom_right);
+        }
+        let inner = rect.right - rect.left - 1;
+        for (row, line) in lines.iter().enumerate() {
+            let x = rect.left + 1 + (inner - text_width(line)) / 2;
+            canvas.text(x, rect.top + 1 + padding + row as i64, line);
+        }
+    }
+
+    // Edges meeting a box on the same side each get a cell of their own
+    let bends: Vec<Vec<(i64, i64)>> = layout
+        .edges
+        .iter()
+        .map(|route| match route.points.len() {
+            0 | 1 => Vec::new(),
+            count => route.points[1..count - 1]
+                .iter()
+                .map(|p| cell(*p))
+                .collect(),
+        })
+        .collect();
+    let cross = |(x, y): (i64, i64)| if vertical { x } else { y };
+    // Wanted cell, edge index and which end of the edge, per box side
+    type Ports = Vec<(i64, usize, usize)>;
+    let mut sides: HashMap<(usize, u8), Ports> = HashMap::new();
+    for (index, ((edge, from, to, _), route)) in edges.iter().zip(&layout.edges).enumerate() {
+        if from == to || edge.stroke == EdgeStroke::Invisible || route.points.len() < 2 {
+            continue;
+        }
+        let (points, out, into) = connect(
+            boxes[*from],
+            boxes[*to],
+            &bends[index],
+            vertical,
+            [None, None],
+        );
+        let end = points[points.len() - 1];
+        sides
+            .entry((*from, out))
+            .or_default()
+            .push((cross(points[0]), index, 0));
+        sides
+            .entry((*to, opposite(into)))
+            .or_default()
+            .push((cross(end), index, 1));
+    }
+    let mut ports = vec![[None, None]; edges.len()];
+    for ((node, _), mut wanted) in sides {
+        let rect = boxes[node];
+        let (low, high) = match vertical {
+            true => (rect.left + 1, rect.right - 1),
+            false => (rect.top + 1, rect.bottom - 1),
+        };
+        wanted.sort();
+        let mut values: Vec<i64> = wanted.iter().map(|&(value, _, _)| value).collect();
+        spread(&mut values, low, high);
+        for (value, (_, index, end)) in values.into_iter().zip(wanted) {
+            ports[index][end] = Some(value);
+        }
+    }
+
+    // Lines first so that they join the boxes, then markers and labels
+    let mut markers = Vec::new();
+    let mut edge_labels = Vec::new();
+    for (index, ((edge, from, to, label), route)) in edges.iter().zip(&layout.edges).enumerate() {
+        if edge.stroke == EdgeStroke::Invisible {
+            continue;
+        }
+        let stroke = Stroke::from(edge.stroke);
+        if from == to {
+            let rect = boxes[*from];
+            let (_, middle) = rect.center();
+            // Out of the top right corner and back into the right side, or
+            // around the bottom when edges attach to the sides
+            let (points, motion, label_at) = match vertical {
+                true => (
+                    vec![
+                        (rect.right - 1, rect.top),
+                        (rect.right - 1, rect.top - 1),
+                        (rect.right + 2, rect.top - 1),
+                        (rect.right + 2, middle),
+                        (rect.right + 1, middle),
+                    ],
+                    LEFT,
+                    (rect.right + 4, middle),
+                ),
+                false => (
+                    vec![
+                        (rect.right - 1, rect.bottom),
+                        (rect.right - 1, rect.bottom + 2),
+                        (rect.left + 1, rect.bottom + 2),
+                        (rect.left + 1, rect.bottom + 1),
+                    ],
+                    UP,
+                    (rect.left + 1, rect.bottom + 3),
+                ),
+            };
+            canvas.polyline(&points, stroke);
+            let (head_x, head_y) = points[points.len() - 1];
+            if let Some(head) = head_char(edge.head, motion, ascii) {
+                markers.push((head_x, head_y, head));
+            }
+            if let Some(lines) = label {
+                for (row, line) in lines.iter().enumerate() {
+                    edge_labels.push((label_at.0, label_at.1 + row as i64, line.clone()));
+                }
+            }
+            continue;
+        }
+        if route.points.len() < 2 {
+            continue;
+        }
+
+        let (points, out, into) = connect(
+            boxes[*from],
+            boxes[*to],
+            &bends[index],
+            vertical,
+            ports[index],
+        );
+        canvas.polyline(&points, stroke);
+        let (end_x, end_y) = points[points.len() - 1];
+        if let Some(head) = head_char(edge.head, into, ascii) {
+            markers.push((end_x, end_y, head));
+        }
+        let (start_x, start_y) = points[0];
+        let tail_cell = match out {
+            UP => (start_x, start_y - 1),
+            DOWN => (start_x, start_y + 1),
+            LEFT => (start_x - 1, start_y),
+            _ => (start_x + 1, start_y),
+        };
+        if let Some(tail) = head_char(edge.tail, opposite(out), ascii) {
+            markers.push((tail_cell.0, tail_cell.1, tail));
+        }
+
+        if let (Some(lines), Some(position)) = (label, route.label) {
+            let (mut x, mut y) = cell(position);
+            // Centre the label on the line, which may sit a cell off the
+            // label's position after straightening
+            for pair in points.windows(2) {
+                let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                if vertical
+                    && x1 == x2
+                    && (x1 - x).abs() <= 1
+                    && (y1.min(y2)..=y1.max(y2)).contains(&y)
+                {
+                    x = x1;
+                } else if !vertical
+                    && y1 == y2
+                    && (y1 - y).abs() <= 1
+                    && (x1.min(x2)..=x1.max(x2)).contains(&x)
+                {
+                    y = y1;
+                }
+            }
+            let top = y - (lines.len() as i64 - 1) / 2;
+            for (row, line) in lines.iter().enumerate() {
+                edge_labels.push((x - text_width(line) / 2, top + row as i64, line.clone()));
+            }
+        }
+    }
+    for (x, y, marker) in markers {
+        canvas.put(x, y, marker);
+    }
+    for (x, y, text) in edge_labels {
+        canvas.text(x, y, &text);
+    }
+
+    with_title(diagram.title.as_deref(), canvas.render(ascii))
+}
+
+/// Orthogonal route from the side of `from` facing the first bend to just
+/// outside the side of `to` facing the last, bending halfway between ranks.
+/// Also returns the directions the route leaves and arrives in.
+fn connect(
+    from: CellRect,
+    to: CellRect,
+    bends: &[(i64, i64)],
+    vertical: bool,
+    ports: [Option<i64>; 2],
+) -> (Vec<(i64, i64)>, u8, u8) {
+    // Work along the ranks (main) and across them (cross)
+    let flip = |(x, y): (i64, i64)| if vertical { (y, x) } else { (x, y) };
+    let span = |rect: CellRect| match vertical {
+        true => (rect.top, rect.bottom, rect.left, rect.right),
+        false => (rect.left, rect.right, rect.top, rect.bottom),
+    };
+    let inside = |value: i64, low: i64, high: i64| match low + 2 <= high {
+        true => value.clamp(low + 1, high - 1),
+        false => (low + high) / 2,
+    };
+    let (from_low, from_high, from_cross_low, from_cross_high) = span(from);
+    let (to_low, to_high, to_cross_low, to_cross_high) = span(to);
+    let from_center = flip(from.center());
+    let to_center = flip(to.center());
+    // Bends a cell off the line before them come from rounding; keep the
+    // line straight through them
+    let snap = |value: i64, target: i64| match (value - target).abs() <= 1 {
+        true => target,
+        false => value,
+    };
+
+    let next = bends.first().map_or(to_center, |&point| flip(point));
+    let leaves_forward = next.0 >= from_center.0;
+    let start = (
+        if leaves_forward { from_high } else { from_low },
+        ports[0].unwrap_or_else(|| {
+            inside(snap(next.1, from_center.1), from_cross_low, from_cross_high)
+        }),
+    );
+    let mut current = start;
+    let mut stops = Vec::new();
+    for &bend in bends {
+        let (main, cross) = flip(bend);
+        current = (main, snap(cross, current.1));
+        stops.push(current);
+    }
+    let arrives_forward = current.0 <= to_center.0;
+    let end = (
+        if arrives_forward {
+            to_low - 1
+        } else {
+            to_high + 1
+        },
+        ports[1]
+            .unwrap_or_else(|| inside(snap(current.1, to_center.1), to_cross_low, to_cross_high)),
+    );
+    stops.push(end);
+    for index in (0..stops.len() - 1).rev() {
+        stops[index].1 = snap(stops[index].1, stops[index + 1].1);
+    }
+    let mut start = start;
+    if ports[0].is_none() && (from_cross_low + 1..from_cross_high).contains(&stops[0].1) {
+        start.1 = snap(start.1, stops[0].1);
+    }
+
+    // Each step bends halfway, but never on the rows next to the boxes so
+    // that lines leave and arrive straight
+    let mut points = vec![start];
+    let mut current = start;
+    for (index, &stop) in stops.iter().enumerate() {
+        let distance = stop.0 - current.0;
+        if distance != 0 && stop.1 != current.1 {
+            let step = distance.signum();
+            let middle = match (distance.abs() >= 2, index + 1 == stops.len()) {
+                (true, _) => (current.0 + (distance + step) / 2).clamp(
+                    (current.0 + step).min(stop.0 - step),
+                    (current.0 + step).max(stop.0 - step),
+                ),
+                (false, true) => current.0,
+                (false, false) => stop.0,
+            };
+            points.push((middle, current.1));
+            points.push((middle, stop.1));
+        }
+        points.push(stop);
+        current = stop;
+    }
+
+    let motion = |forward: bool| match (vertical, forward) {
+        (true, true) => DOWN,
+        (true, false) => UP,
+        (false, true) => RIGHT,
+        (false, false) => LEFT,
+    };
+    (
+        points.into_iter().map(flip).collect(),
+        motion(leaves_forward),
+        motion(arrives_forward),
+    )
+}
+
+fn opposite(direction: u8) -> u8 {
+    match direction {
+        UP => DOWN,
+        DOWN => UP,
+        LEFT => RIGHT,
+        _ => LEFT,
+    }
+}
+
+/// Move apart positions that share a cell, keeping their order and staying
+/// within `low..=high` while there is room
+fn spread(values: &mut [i64], low: i64, high: i64) {
+    for index in 1..values.len() {
+        values[index] = values[index].max(values[index - 1] + 1);
+    }
+    for index in (0..values.len()).rev() {
+        let limit = values.get(index + 1).map_or(high, |next| next - 1);
+        values[index] = values[index].min(limit).max(low);
+    }
+}
+
+/// Line style and end markers of a sequence message arrow such as `-->>`
+fn message_style(arrow: &str) -> (Stroke, ArrowHead, ArrowHead) {
+    let stroke = if arrow.contains("--") {
+        Stroke::Dotted
+    } else {
+        Stroke::Light
+    };
+    let head = if arrow.ends_with('x') {
+        ArrowHead::Cross
+    } else if arrow.ends_with(">>") || arrow.ends_with(')') {
+        ArrowHead::Arrow
+    } else {
+        ArrowHead::None
+    };
+    let tail = if arrow.starts_with("<<") {
+        ArrowHead::Arrow
+    } else {
+        ArrowHead::None
+    };
+    (stroke, head, tail)
+}
+
+/// A row group of a sequence drawing, in source order
+enum SequenceItem<'a> {
+    Message {
+        from: usize,
+        to: usize,
+        lines: Vec<String>,
+        arrow: &'a str,
+    },
+    /// A note spanning the participants `low..=high`
+    Note {
+        low: usize,
+        high: usize,
+        placement: NotePlacement,
+        lines: Vec<String>,
+    },
+    BlockStart(String),
+    BlockSection(String),
+    BlockEnd,
+}
+
+/// Frame heading such as `loop [every minute]`
+fn block_title(keyword: &str, label: &str) -> String {
+    match label.trim() {
+        "" => format!(" {} ", keyword),
+        label => format!(" {} [{}] ", keyword, label),
+    }
+}
+
+fn sequence_text(diagram: &ParsedDiagram, wrap: Option<usize>, ascii: bool) -> String {
+    let participants = &diagram.participants;
+    let labels: Vec<&str> = participants
+        .iter()
+        .map(|p| p.label.as_deref().unwrap_or(&p.id))
+        .collect();
+    let widths: Vec<i64> = labels.iter().map(|label| text_width(label) + 4).collect();
+    let position = |id: &str| participants.iter().position(|p| p.id == id);
+    let text_lines = |text: &str| match text.trim() {
+        "" => Vec::new(),
+        text => wrap_lines(text, wrap),
+    };
+    let items: Vec<SequenceItem> = diagram
+        .steps
+        .iter()
+        .filter_map(|step| match step {
+            SequenceStep::Message(index) => {
+                let message = diagram.messages.get(*index)?;
+                Some(SequenceItem::Message {
+                    from: position(&message.from)?,
+                    to: position(&message.to)?,
+                    lines: text_lines(&message.text),
+                    arrow: message.arrow.as_str(),
+                })
+            }
+            SequenceStep::Note(note) => {
+                let indices = note
+                    .participants
+                    .iter()
+                    .map(|id| position(id))
+                    .collect::<Option<Vec<usize>>>()?;
+                Some(SequenceItem::Note {
+                    low: *indices.iter().min()?,
+                    high: *indices.iter().max()?,
+                    placement: note.placement,
+                    lines: text_lines(&note.text),
+                })
+            }
+            SequenceStep::BlockStart { keyword, label } => {
+                Some(SequenceItem::BlockStart(block_title(keyword, label)))
+            }
+            SequenceStep::BlockSection { keyword, label } => {
+                Some(SequenceItem::BlockSection(block_title(keyword, label)))
+            }
+            SequenceStep::BlockEnd => Some(SequenceItem::BlockEnd),
+        })
+        .collect();
+    let widest = |lines: &[String]| lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+
+    // Lifelines far enough apart for the boxes, then for the messages and
+    // notes between them
+    let mut centers = vec![0; participants.len()];
+    for index in 0..participants.len() {
+        centers[index] = match index {
+            0 => widths[0] / 2,
+            _ => centers[index - 1] + (widths[index - 1] + 1) / 2 + widths[index] / 2 + 2,
+        };
+    }
+    let mut gaps = Vec::new();
+    for item in &items {
+        match item {
+            SequenceItem::Message {
+                from, to, lines, ..
+            } => gaps.push(match from == to {
+                true => (*from, from + 1, widest(lines) + 6),
+                false => (*from.min(to), *from.max(to), widest(lines) + 4),
+            }),
+            SequenceItem::Note {
+                low,
+                high,
+                placement,
+                lines,
+            } => {
+                let width = widest(lines) + 4;
+                match placement {
+                    NotePlacement::LeftOf if *low > 0 => gaps.push((low - 1, *low, width + 2)),
+                    NotePlacement::LeftOf => {}
+                    NotePlacement::RightOf => gaps.push((*high, high + 1, width + 2)),
+                    NotePlacement::Over if low == high => {
+                        if *low > 0 {
+                            gaps.push((low - 1, *low, width / 2 + 2));
+                        }
+                        gaps.push((*low, low + 1, width - width / 2 + 1));
+                    }
+                    NotePlacement::Over => gaps.push((*low, *high, width - 5)),
+                }
+            }
+            _ => {}
+        }
+    }
+    for (low, high, needed) in gaps {
+        if high >= centers.len() {
+            continue;
+        }
+        let shortfall = centers[low] + needed - centers[high];
+        if shortfall > 0 {
+            for center in &mut centers[high..] {
+                *center += shortfall;
+            }
+        }
+    }
+
+    // Rows: the participant boxes take three, each message its label lines
+    // and an arrow, or a loop back to the same lifeline, each note its box
+    // and each block boundary a line of the block's frame
+    let mut rows = Vec::new();
+    let mut notes = Vec::new();
+    let mut open_blocks = Vec::new();
+    // Top and bottom rows, heading and nesting depth of each block
+    let mut frames = Vec::new();
+    // Row, heading and nesting depth of each block section
+    let mut sections = Vec::new();
+    let mut depth = 0;
+    let mut y = 4;
+    for item in &items {
+        rows.push(y);
+        y += match item {
+            SequenceItem::Message {
+                from, to, lines, ..
+            } => match from == to {
+                true => (lines.len() as i64 + 1).max(3) + 1,
+                false => lines.len() as i64 + 2,
+            },
+            SequenceItem::Note {
+                low,
+                high,
+                placement,
+                lines,
+            } => {
+                let width = widest(lines) + 4;
+                let (left, right) = match placement {
+                    NotePlacement::LeftOf => (centers[*low] - width, centers[*low] - 1),
+                    NotePlacement::RightOf => (centers[*high] + 1, centers[*high] + width),
+                    NotePlacement::Over if low == high => {
+                        let left = centers[*low] - width / 2;
+                        (left, left + width - 1)
+                    }
+                    NotePlacement::Over => {
+                        let left = centers[*low] - 2;
+                        (left, (centers[*high] + 2).max(left + width - 1))
+                    }
+                };
+                let rect = CellRect {
+                    left,
+                    top: y,
+                    right,
+                    bottom: y + lines.len() as i64 + 1,
+                };
+                notes.push((rect, lines));
+                lines.len() as i64 + 3
+            }
+            SequenceItem::BlockStart(title) => {
+                open_blocks.push((y, title));
+                depth = depth.max(open_blocks.len());
+                1
+            }
+            SequenceItem::BlockSection(title) => {
+                if !open_blocks.is_empty() {
+                    sections.push((y, title, open_blocks.len() - 1));
+                }
+                1
+            }
+            SequenceItem::BlockEnd => {
+                if let Some((top, title)) = open_blocks.pop() {
+                    frames.push((top, y, title, open_blocks.len()));
+                }
+                2
+            }
+        };
+    }
+    while let Some((top, title)) = open_blocks.pop() {
+        frames.push((top, y, title, open_blocks.len()));
+        y += 2;
+    }
+    let footer = y;
+
+    // Frames sit outside everything else, the outermost widest
+    let mut min_x = 0;
+    let mut max_x = 0;
+    for (&center, &width) in centers.iter().zip(&widths) {
+        min_x = min_x.min(center - width / 2);
+        max_x = max_x.max(center - width / 2 + width - 1);
+    }
+    for (rect, _) in &notes {
+        min_x = min_x.min(rect.left);
+        max_x = max_x.max(rect.right);
+    }
+    for item in &items {
+        if let SequenceItem::Message {
+            from, to, lines, ..
+        } = item
+        {
+            if from == to {
+                max_x = max_x.max(centers[*from] + 4 + widest(lines));
+            }
+        }
+    }
+    let frame_bounds = |level: usize| {
+        let margin = 2 * (depth - level) as i64;
+        (min_x - margin, max_x + margin)
+    };
+
+    let mut canvas = Canvas::default();
+    let mut texts = Vec::new();
+    let mut markers = Vec::new();
+    for ((participant, label), (&center, &width)) in participants
+        .iter()
+        .zip(&labels)
+        .zip(centers.iter().zip(&widths))
+    {
+        for top in [0, footer] {
+            let rect = CellRect {
+                left: center - width / 2,
+                top,
+                right: center - width / 2 + width - 1,
+                bottom: top + 2,
+            };
+            canvas.rect(rect);
+            if participant.is_actor {
+                let corners = if ascii {
+                    ['.', '.', '\'', '\'']
+                } else {
+                    ['╭', '╮', '╰', '╯']
+                };
+                canvas.put(rect.left, rect.top, corners[0]);
+                canvas.put(rect.right, rect.top, corners[1]);
+                canvas.put(rect.left, rect.bottom, corners[2]);
+                canvas.put(rect.right, rect.bottom, corners[3]);
+            }
+            texts.push((rect.left + 2, top + 1, label.to_string()));
+        }
+        canvas.segment((center, 2), (center, footer), Stroke::Light);
+    }
+
+    // Notes hide the lifelines behind them
+    for (rect, lines) in &notes {
+        canvas.clear(*rect);
+        canvas.rect(*rect);
+        for &center in &centers {
+            if center > rect.left && center < rect.right {
+                canvas.mark(center, rect.top, UP, Stroke::Light);
+                canvas.mark(center, rect.bottom, DOWN, Stroke::Light);
+            }
+        }
+        for (row, line) in lines.iter().enumerate() {
+            texts.push((rect.left + 2, rect.top + 1 + row as i64, line.clone()));
+        }
+    }
+    for &(top, bottom, title, level) in &frames {
+        let (left, right) = frame_bounds(level);
+        canvas.rect(CellRect {
+            left,
+            top,
+            right,
+            bottom,
+        });
+        texts.push((left + 1, top, title.clone()));
+    }
+    for &(row, title, level) in &sections {
+        let (left, right) = frame_bounds(level);
+        canvas.segment((left, row), (right, row), Stroke::Dotted);
+        texts.push((left + 1, row, title.clone()));
+    }
+
+    for (item, &top) in items.iter().zip(&rows) {
+        let SequenceItem::Message {
+            from,
+            to,
+            lines,
+            arrow,
+        } = item
+        else {
+            continue;
+        };
+        let (stroke, head, tail) = message_style(arrow);
+        let center = centers[*from];
+        if from == to {
+            let height = (lines.len() as i64 + 1).max(3);
+            let bottom = top + height - 1;
+            canvas.polyline(
+                &[
+                    (center, top),
+                    (center + 3, top),
+                    (center + 3, bottom),
+                    (center + 1, bottom),
+                ],
+                stroke,
+            );
+            if let Some(head) = head_char(head, LEFT, ascii) {
+                markers.push((center + 1, bottom, head));
+            }
+            for (row, line) in lines.iter().enumerate() {
+                texts.push((center + 5, top + 1 + row as i64, line.clone()));
+            }
+            continue;
+        }
+
+        let target = centers[*to];
+        let step = (target - center).signum();
+        let y = top + lines.len() as i64;
+        for (row, line) in lines.iter().enumerate() {
+            let x = (center + target) / 2 - text_width(line) / 2;
+            texts.push((x, top + row as i64, line.clone()));
+        }
+        canvas.segment((center, y), (target - step, y), stroke);
+        let (forward, backward) = if step > 0 {
+            (RIGHT, LEFT)
+        } else {
+            (LEFT, RIGHT)
+        };
+        if let Some(head) = head_char(head, forward, ascii) {
+            markers.push((target - step, y, head));
+        }
+        if let Some(tail) = head_char(tail, backward, ascii) {
+            markers.push((center + step, y, tail));
+        }
+    }
+    for (x, y, marker) in markers {
+        canvas.put(x, y, marker);
+    }
+    for (x, y, text) in texts {
+        canvas.text(x, y, &text);
+    }
+
+    with_title(diagram.title.as_deref(), canvas.render(ascii))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(code: &str, ascii: bool, max_width: Option<usize>) -> Result<String, String> {
+        render_text(code, &TextOptions { ascii, max_width })
+    }
+
+    #[test]
+    fn test_flowchart_text() {
+        let drawing = text(
+            "flowchart TD\n  A[Start] --> B{Ready?}\n  B -->|yes| C(Ship)",
+            false,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            drawing,
+            [
+                " ┌───────┐",
+                " │ Start │",
+                " └──┬────┘",
+                "    │",
+                "    │",
+                "    │",
+                "    │",
+                "    ▼",
+                "/────────\\",
+                "│ Ready? │",
+                "\\───┬────/",
+                "    │",
+                "    │",
+                "    │",
+                "   yes",
+                "    │",
+                "    ▼",
+                " ╭──────╮",
+                " │ Ship │",
+                " ╰──────╯",
+                "",
+            ]
+            .join("\n")
+        );
+
+        let ascii = text("graph LR\n  A -.-> B\n  B --> A", true, None).unwrap();
+        assert!(ascii.is_ascii(), "{}", ascii);
+        assert!(ascii.contains("| A +....>| B |"), "{}", ascii);
+        assert!(ascii.contains("|   |<----+   |"), "{}", ascii);
+
+        let nested = "flowchart TD\n subgraph A\n subgraph A\n end\n end\n B --> A";
+        assert!(text(nested, false, None).is_ok());
+    }
+
+    #[test]
+    fn test_sequence_text_and_width_limit() {
+        let code = "sequenceDiagram\n  actor U as User\n  participant S as Server\n  U->>S: log in with a password\n  S-->>U: token\n  S->>S: audit";
+        let drawing = text(code, false, None).unwrap();
+        assert!(drawing.contains("│ User │"), "{}", drawing);
+        assert!(drawing.contains("log in with a password"));
+        assert!(drawing.contains("─▶│"));
+        assert!(drawing.contains("│◀┄"));
+        assert!(drawing.contains("audit"));
+        assert!(drawing.starts_with("╭"));
+
+        let narrow = text(code, true, Some(30)).unwrap();
+        assert!(
+            narrow.lines().all(|line| line.chars().count() <= 30),
+            "{}",
+            narrow
+        );
+        assert!(narrow.contains("password"));
+        assert!(text(code, true, Some(12)).unwrap_err().contains("columns"));
+        assert!(text("classDiagram\n  A <|-- B", false, None).is_err());
+
+        // Blocks frame their messages, notes hide the lifelines behind them
+        // and wide characters take two columns
+        let blocks = text(
+            "sequenceDiagram\n  A->>数据: 查询\n  loop retry\n  A->>数据: ping\n  Note over A,数据: 🎉 done\n  end",
+            false,
+            None,
+        )
+        .unwrap();
+        assert!(blocks.contains("│ 数据 │"), "{}", blocks);
+        assert!(blocks.contains("┌ loop [retry] ─"), "{}", blocks);
+        assert!(blocks.contains("│ 🎉 done    │"), "{}", blocks);
+        let widths: Vec<i64> = blocks
+            .lines()
+            .filter(|line| line.starts_with('│') || line.starts_with('└'))
+            .map(|line| text_width(line.trim_end()))
+            .collect();
+        assert!(widths.iter().all(|&width| width == widths[0]), "{}", blocks);
+    }
+}
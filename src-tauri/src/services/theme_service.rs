@@ -0,0 +1,463 @@
+use crate::models::{
+    DiagramOptions, MermaidConfig, MermaidTheme, SvgProcessOptions, ThemeAppearance, ThemeManifest,
+    ThemePack, ThemePreview,
+};
+use crate::services::config_service::{merge_configs, validate_config};
+use crate::services::RenderService;
+use crate::utils::{slugify, DefaultDirectories};
+use std::fs;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+const MANIFEST_FILE: &str = "theme.json";
+const CONFIG_FILE: &str = "config.json";
+const CSS_FILE: &str = "theme.css";
+
+/// Pack files are small text files; anything larger is refused
+const MAX_PACK_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Diagram rendered for theme previews
+pub const PREVIEW_SAMPLE: &str = include_str!("../../../docs/examples/flowchart-example.mmd");
+
+/// Themes shipped with the app, matching the app's light and dark modes
+pub fn builtin_themes() -> Vec<ThemePack> {
+    let builtin = |id: &str, name: &str, theme, appearance, background: &str| ThemePack {
+        id: id.to_string(),
+        manifest: ThemeManifest {
+            name: name.to_string(),
+            description: None,
+            author: None,
+            version: None,
+            appearance,
+            background: Some(background.to_string()),
+        },
+        config: MermaidConfig {
+            theme: Some(theme),
+            ..Default::default()
+        },
+        css: None,
+        builtin: true,
+    };
+
+    vec![
+        builtin(
+            "light",
+            "Light",
+            MermaidTheme::Default,
+            ThemeAppearance::Light,
+            "#ffffff",
+        ),
+        builtin(
+            "dark",
+            "Dark",
+            MermaidTheme::Dark,
+            ThemeAppearance::Dark,
+            "#0f0f23",
+        ),
+    ]
+}
+
+/// Render options with a theme pack applied. The pack's config and CSS go
+/// underneath the options' own, and its background is used when the options
+/// leave the background empty.
+pub fn apply_theme(pack: &ThemePack, options: &DiagramOptions) -> Result<DiagramOptions, String> {
+    let mermaid_config = match options.mermaid_config {
+        Some(ref config) => merge_configs(&[&pack.config, config])?,
+        None => pack.config.clone(),
+    };
+
+    let css = match (&pack.css, &options.css) {
+        (Some(theme_css), Some(css)) => Some(format!("{}\n{}", theme_css, css)),
+        (theme_css, css) => css.clone().or_else(|| theme_css.clone()),
+    };
+
+    let background = match pack.manifest.background {
+        Some(ref background) if options.background.trim().is_empty() => background.clone(),
+        _ => options.background.clone(),
+    };
+
+    Ok(DiagramOptions {
+        mermaid_config: Some(mermaid_config),
+        css,
+        background,
+        theme_pack: None,
+        ..options.clone()
+    })
+}
+
+/// Apply the theme pack named in the options, if any
+pub fn resolve_theme_pack(options: &DiagramOptions) -> Result<DiagramOptions, String> {
+    match options.theme_pack {
+        Some(ref id) => apply_theme(&ThemeStore::default_location()?.get(id)?, options),
+        None => Ok(options.clone()),
+    }
+}
+
+/// Render `code` once per theme
+pub fn render_previews(
+    packs: &[ThemePack],
+    code: &str,
+    service: &mut RenderService,
+) -> Vec<ThemePreview> {
+    packs
+        .iter()
+        .map(|pack| {
+            let svg = apply_theme(pack, &DiagramOptions::svg(None)).and_then(|options| {
+                let options = DiagramOptions {
+                    svg_processing: Some(SvgProcessOptions {
+                        background: Some(options.background.clone()),
+                        ..SvgProcessOptions::default()
+                    }),
+                    ..options
+                };
+                service.render_svg(code, &options)
+            });
+
+            ThemePreview {
+                id: pack.id.clone(),
+                name: pack.manifest.name.clone(),
+                error: svg.as_ref().err().cloned(),
+                svg: svg.ok(),
+            }
+        })
+        .collect()
+}
+
+/// Raw contents of a pack's files
+struct PackFiles {
+    manifest: String,
+    config: String,
+    css: Option<String>,
+}
+
+impl PackFiles {
+    fn from_folder(directory: &Path) -> Result<Self, String> {
+        let read = |name: &str| -> Result<Option<String>, String> {
+            let path = directory.join(name);
+            if !path.is_file() {
+                return Ok(None);
+            }
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or_default();
+            if size > MAX_PACK_FILE_SIZE {
+                return Err(format!("{} is too large", name));
+            }
+            fs::read_to_string(&path)
+                .map(Some)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+        };
+
+        Ok(Self {
+            manifest: read(MANIFEST_FILE)?
+                .ok_or_else(|| format!("{} is missing", MANIFEST_FILE))?,
+            config: read(CONFIG_FILE)?.ok_or_else(|| format!("{} is missing", CONFIG_FILE))?,
+            css: read(CSS_FILE)?,
+        })
+    }
+
+    /// Read a zipped pack. The files may sit at the root of the archive or
+    /// in a single top-level folder; nothing else in the archive is touched.
+    fn from_zip(path: &Path) -> Result<Self, String> {
+        let file = fs::File::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut archive =
+            ZipArchive::new(file).map_err(|e| format!("Invalid theme pack archive: {}", e))?;
+
+        let manifest_name = archive
+            .file_names()
+            .filter(|name| *name == MANIFEST_FILE || name.ends_with(&format!("/{}", MANIFEST_FILE)))
+            .min_by_key(|name| name.matches('/').count())
+            .map(str::to_string)
+            .ok_or_else(|| format!("{} is missing", MANIFEST_FILE))?;
+        let prefix = &manifest_name[..manifest_name.len() - MANIFEST_FILE.len()];
+
+        let mut read = |name: &str| -> Result<Option<String>, String> {
+            let mut entry = match archive.by_name(&format!("{}{}", prefix, name)) {
+                Ok(entry) => entry,
+                Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+                Err(e) => return Err(format!("Failed to read {}: {}", name, e)),
+            };
+            if entry.size() > MAX_PACK_FILE_SIZE {
+                return Err(format!("{} is too large", name));
+            }
+            let mut content = String::new();
+            entry
+                .by_ref()
+                .take(MAX_PACK_FILE_SIZE)
+                .read_to_string(&mut content)
+                .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+            Ok(Some(content))
+        };
+
+        Ok(Self {
+            manifest: read(MANIFEST_FILE)?
+                .ok_or_else(|| format!("{} is missing", MANIFEST_FILE))?,
+            config: read(CONFIG_FILE)?.ok_or_else(|| format!("{} is missing", CONFIG_FILE))?,
+            css: read(CSS_FILE)?,
+        })
+    }
+
+    fn into_pack(self) -> Result<ThemePack, String> {
+        let manifest: ThemeManifest = serde_json::from_str(&self.manifest)
+            .map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?;
+        let config: MermaidConfig = serde_json::from_str(&self.config)
+            .map_err(|e| format!("Invalid {}: {}", CONFIG_FILE, e))?;
+        let id = slugify(&manifest.name)
+            .ok_or_else(|| format!("Invalid theme name: '{}'", manifest.name))?;
+
+        for warning in validate_config(&config) {
+            log::warn!("Theme {}: {}", manifest.name, warning);
+        }
+
+        Ok(ThemePack {
+            id,
+            manifest,
+            config,
+            css: self.css.filter(|css| !css.trim().is_empty()),
+            builtin: false,
+        })
+    }
+}
+
+/// Installed theme packs, one folder per pack
+pub struct ThemeStore {
+    directory: PathBuf,
+}
+
+impl ThemeStore {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    /// Themes directory inside the app data directory
+    pub fn default_location() -> Result<Self, String> {
+        let storage = DefaultDirectories::app_storage()
+            .ok_or_else(|| "Could not determine the app data directory".to_string())?;
+        Ok(Self::new(storage.join("themes")))
+    }
+
+    /// Built-in themes followed by installed ones sorted by name
+    pub fn list(&self) -> Result<Vec<ThemePack>, String> {
+        let mut installed = Vec::new();
+        if self.directory.exists() {
+            let entries = fs::read_dir(&self.directory)
+                .map_err(|e| format!("Failed to read themes directory: {}", e))?;
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.is_dir() {
+                    match self.read_installed(&path) {
+                        Ok(pack) => installed.push(pack),
+                        Err(e) => log::warn!("Skipping theme {}: {}", path.display(), e),
+                    }
+                }
+            }
+        }
+        installed.sort_by_key(|pack| pack.manifest.name.to_lowercase());
+
+        let mut packs = builtin_themes();
+        packs.extend(installed);
+        Ok(packs)
+    }
+
+    pub fn get(&self, id: &str) -> Result<ThemePack, String> {
+        if let Some(pack) = builtin_themes().into_iter().find(|pack| pack.id == id) {
+            return Ok(pack);
+        }
+        let path = self.pack_path(id)?;
+        if !path.is_dir() {
+            return Err(format!("Theme not found: {}", id));
+        }
+        self.read_installed(&path)
+    }
+
+    /// Install a pack from a folder or `.zip`, replacing an installed pack
+    /// with the same name
+    pub fn install(&self, source: &Path) -> Result<ThemePack, String> {
+        let files = if source.is_dir() {
+            PackFiles::from_folder(source)?
+        } else if source
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+        {
+            PackFiles::from_zip(source)?
+        } else {
+            return Err(format!(
+                "Theme packs must be a folder or a .zip file: {}",
+                source.display()
+            ));
+        };
+
+        let pack = files.into_pack()?;
+        if builtin_themes().iter().any(|builtin| builtin.id == pack.id) {
+            return Err(format!(
+                "'{}' is the name of a built-in theme",
+                pack.manifest.name
+            ));
+        }
+
+        let target = self.directory.join(&pack.id);
+        if target.exists() {
+            fs::remove_dir_all(&target)
+                .map_err(|e| format!("Failed to replace installed theme: {}", e))?;
+        }
+        fs::create_dir_all(&target)
+            .map_err(|e| format!("Failed to create theme directory: {}", e))?;
+
+        let write = |name: &str, content: &str| {
+            fs::write(target.join(name), content)
+                .map_err(|e| format!("Failed to write {}: {}", name, e))
+        };
+        write(MANIFEST_FILE, &to_json(&pack.manifest)?)?;
+        write(CONFIG_FILE, &to_json(&pack.config)?)?;
+        if let Some(ref css) = pack.css {
+            write(CSS_FILE, css)?;
+        }
+
+        Ok(pack)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        if builtin_themes().iter().any(|pack| pack.id == id) {
+            return Err(format!("Built-in theme '{}' cannot be removed", id));
+        }
+        let path = self.pack_path(id)?;
+        if !path.is_dir() {
+            return Err(format!("Theme not found: {}", id));
+        }
+        fs::remove_dir_all(&path).map_err(|e| format!("Failed to remove theme {}: {}", id, e))
+    }
+
+    /// Write a pack as a zip that `install` accepts
+    pub fn export(&self, id: &str, output_path: &Path) -> Result<(), String> {
+        let pack = self.get(id)?;
+        let file = fs::File::create(output_path)
+            .map_err(|e| format!("Failed to create {}: {}", output_path.display(), e))?;
+        write_zip(&pack, file)
+    }
+
+    fn read_installed(&self, path: &Path) -> Result<ThemePack, String> {
+        let mut pack = PackFiles::from_folder(path)?.into_pack()?;
+        // The folder name is authoritative, in case the manifest was edited by hand
+        if let Some(name) = path.file_name() {
+            pack.id = name.to_string_lossy().to_string();
+        }
+        Ok(pack)
+    }
+
+    fn pack_path(&self, id: &str) -> Result<PathBuf, String> {
+        match slugify(id) {
+            Some(slug) if slug == id => Ok(self.directory.join(slug)),
+            _ => Err(format!("Invalid theme id: '{}'", id)),
+        }
+    }
+}
+
+fn write_zip<W: Write + Seek>(pack: &ThemePack, writer: W) -> Result<(), String> {
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default();
+
+    let mut files = vec![
+        (MANIFEST_FILE, to_json(&pack.manifest)?),
+        (CONFIG_FILE, to_json(&pack.config)?),
+    ];
+    if let Some(ref css) = pack.css {
+        files.push((CSS_FILE, css.clone()));
+    }
+
+    for (name, content) in files {
+        zip.start_file(name, options)
+            .and_then(|_| zip.write_all(content.as_bytes()).map_err(Into::into))
+            .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish theme archive: {}", e))?;
+    Ok(())
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize theme: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp::TempDir;
+
+    #[test]
+    fn test_install_export_remove() {
+        let temp = TempDir::new("mermaid_themes");
+        let root = temp.path();
+        let store = ThemeStore::new(root.join("installed"));
+
+        let source = root.join("corporate");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(
+            source.join(MANIFEST_FILE),
+            r##"{"name": "Corporate Blue", "appearance": "dark", "background": "#001122"}"##,
+        )
+        .unwrap();
+        fs::write(
+            source.join(CONFIG_FILE),
+            r##"{"theme": "base", "themeVariables": {"primaryColor": "#0055aa"}}"##,
+        )
+        .unwrap();
+        fs::write(source.join(CSS_FILE), ".node rect { rx: 6px; }").unwrap();
+
+        let pack = store.install(&source).unwrap();
+        assert_eq!(pack.id, "corporate-blue");
+        assert_eq!(store.get("corporate-blue").unwrap(), pack);
+
+        // Round trip through a zip with the files inside a folder
+        let archive = root.join("corporate.zip");
+        store.export("corporate-blue", &archive).unwrap();
+        store.remove("corporate-blue").unwrap();
+        assert!(store.get("corporate-blue").is_err());
+
+        let nested = root.join("nested.zip");
+        let mut zip = ZipWriter::new(fs::File::create(&nested).unwrap());
+        let mut exported = ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+        for i in 0..exported.len() {
+            let mut entry = exported.by_index(i).unwrap();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            zip.start_file(
+                format!("corporate/{}", entry.name()),
+                SimpleFileOptions::default(),
+            )
+            .unwrap();
+            zip.write_all(&content).unwrap();
+        }
+        zip.finish().unwrap();
+
+        assert_eq!(store.install(&nested).unwrap(), pack);
+        let ids: Vec<String> = store.list().unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec!["light", "dark", "corporate-blue"]);
+
+        assert!(store.remove("dark").is_err());
+        assert!(store.get("../installed").is_err());
+    }
+
+    #[test]
+    fn test_apply_theme() {
+        let mut pack = builtin_themes().remove(1);
+        pack.css = Some(".label { font-weight: bold; }".to_string());
+
+        let options = DiagramOptions {
+            background: String::new(),
+            css: Some(".edge { stroke-width: 2px; }".to_string()),
+            theme_pack: Some("dark".to_string()),
+            ..DiagramOptions::svg(None)
+        };
+        let applied = apply_theme(&pack, &options).unwrap();
+        assert_eq!(applied.background, "#0f0f23");
+        assert_eq!(
+            applied.mermaid_config.unwrap().theme,
+            Some(MermaidTheme::Dark)
+        );
+        assert_eq!(
+            applied.css.as_deref(),
+            Some(".label { font-weight: bold; }\n.edge { stroke-width: 2px; }")
+        );
+        assert!(applied.theme_pack.is_none());
+    }
+}
@@ -0,0 +1,179 @@
+use resvg::tiny_skia::{PathSegment, Point, Transform};
+use resvg::usvg::{FillRule, Group, Node, Paint, Path, Stroke};
+
+/// PostScript points per CSS pixel (72 / 96)
+pub(crate) const POINTS_PER_PIXEL: f32 = 0.75;
+
+/// Path construction step with quadratic curves already raised to cubics,
+/// since PDF and PostScript only have cubic Béziers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Segment {
+    MoveTo(Point),
+    LineTo(Point),
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+/// Receives the painted paths of an SVG tree, in document order. Every path
+/// is bracketed by `begin_path` and `end_path`, which save and restore the
+/// graphics state.
+pub(crate) trait VectorSink {
+    /// Start a path drawn in the coordinate system `transform` maps from
+    fn begin_path(&mut self, transform: Transform);
+    /// Fill the outline with a solid colour at the given opacity
+    fn fill(&mut self, outline: &[Segment], rgb: [u8; 3], opacity: f32, rule: FillRule);
+    /// Stroke the outline with a solid colour at the given opacity
+    fn stroke(&mut self, outline: &[Segment], rgb: [u8; 3], opacity: f32, stroke: &Stroke);
+    fn end_path(&mut self);
+}
+
+/// Walk a usvg tree and send its paths to the sink. Group opacity is folded
+/// into the opacity of each fill and stroke, gradients are approximated by
+/// their first colour and text arrives as outlines.
+pub(crate) fn draw_tree(sink: &mut impl VectorSink, root: &Group) {
+    draw_group(sink, root, 1.0);
+}
+
+fn draw_group(sink: &mut impl VectorSink, group: &Group, opacity: f32) {
+    let opacity = opacity * group.opacity().get();
+
+    for node in group.children() {
+        match node {
+            Node::Group(child) => draw_group(sink, child, opacity),
+            Node::Path(path) => draw_path(sink, path, opacity),
+            // Text is converted to outlines by usvg
+            Node::Text(text) => draw_group(sink, text.flattened(), opacity),
+            Node::Image(_) => log::warn!("Embedded images are not supported in vector export"),
+        }
+    }
+}
+
+fn draw_path(sink: &mut impl VectorSink, path: &Path, opacity: f32) {
+    if !path.is_visible() || (path.fill().is_none() && path.stroke().is_none()) {
+        return;
+    }
+
+    sink.begin_path(path.abs_transform());
+    let outline = path_outline(path);
+
+    if let Some(fill) = path.fill() {
+        if let Some(rgb) = paint_color(fill.paint()) {
+            sink.fill(&outline, rgb, opacity * fill.opacity().get(), fill.rule());
+        }
+    }
+
+    if let Some(stroke) = path.stroke() {
+        if let Some(rgb) = paint_color(stroke.paint()) {
+            sink.stroke(&outline, rgb, opacity * stroke.opacity().get(), stroke);
+        }
+    }
+
+    sink.end_path();
+}
+
+/// Outline of a path in its own coordinates
+fn path_outline(path: &Path) -> Vec<Segment> {
+    let mut outline = Vec::new();
+    let mut current = Point::zero();
+    let mut start = Point::zero();
+
+    for segment in path.data().segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                outline.push(Segment::MoveTo(p));
+                current = p;
+                start = p;
+            }
+            PathSegment::LineTo(p) => {
+                outline.push(Segment::LineTo(p));
+                current = p;
+            }
+            PathSegment::QuadTo(control, p) => {
+                let c1 = Point::from_xy(
+                    current.x + 2.0 / 3.0 * (control.x - current.x),
+                    current.y + 2.0 / 3.0 * (control.y - current.y),
+                );
+                let c2 = Point::from_xy(
+                    p.x + 2.0 / 3.0 * (control.x - p.x),
+                    p.y + 2.0 / 3.0 * (control.y - p.y),
+                );
+                outline.push(Segment::CubicTo(c1, c2, p));
+                current = p;
+            }
+            PathSegment::CubicTo(c1, c2, p) => {
+                outline.push(Segment::CubicTo(c1, c2, p));
+                current = p;
+            }
+            PathSegment::Close => {
+                outline.push(Segment::Close);
+                current = start;
+            }
+        }
+    }
+
+    outline
+}
+
+/// Solid colour of a paint; gradients fall back to their first stop
+fn paint_color(paint: &Paint) -> Option<[u8; 3]> {
+    let color = match paint {
+        Paint::Color(color) => *color,
+        Paint::LinearGradient(gradient) => gradient.stops().first()?.color(),
+        Paint::RadialGradient(gradient) => gradient.stops().first()?.color(),
+        Paint::Pattern(_) => return None,
+    };
+    Some([color.red, color.green, color.blue])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::raster_service::parse_svg;
+
+    #[derive(Default)]
+    struct Recorder {
+        fills: Vec<([u8; 3], f32, Vec<Segment>)>,
+        strokes: Vec<([u8; 3], f32)>,
+        depth: i32,
+    }
+
+    impl VectorSink for Recorder {
+        fn begin_path(&mut self, _transform: Transform) {
+            self.depth += 1;
+        }
+
+        fn fill(&mut self, outline: &[Segment], rgb: [u8; 3], opacity: f32, _rule: FillRule) {
+            self.fills.push((rgb, opacity, outline.to_vec()));
+        }
+
+        fn stroke(&mut self, _outline: &[Segment], rgb: [u8; 3], opacity: f32, _stroke: &Stroke) {
+            self.strokes.push((rgb, opacity));
+        }
+
+        fn end_path(&mut self) {
+            self.depth -= 1;
+        }
+    }
+
+    #[test]
+    fn test_draw_tree() {
+        let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20"><g opacity="0.5"><path d="M0 0 Q 10 10 20 0" fill="#ff0000" fill-opacity="0.5" stroke="#000000"/></g><rect width="4" height="4" fill="none"/></svg>"##;
+        let tree = parse_svg(svg).unwrap();
+        let mut recorder = Recorder::default();
+        draw_tree(&mut recorder, tree.root());
+
+        assert_eq!(recorder.depth, 0);
+        assert_eq!(recorder.fills.len(), 1);
+        let (rgb, opacity, ref outline) = recorder.fills[0];
+        assert_eq!((rgb, opacity), ([255, 0, 0], 0.25));
+        // The quadratic is raised to a cubic with controls at two thirds
+        let Segment::CubicTo(c1, c2, end) = outline[1] else {
+            panic!("expected a cubic, found {:?}", outline[1]);
+        };
+        let close = |p: Point, x: f32, y: f32| (p.x - x).abs() < 1e-4 && (p.y - y).abs() < 1e-4;
+        assert!(close(c1, 20.0 / 3.0, 20.0 / 3.0), "{:?}", c1);
+        assert!(close(c2, 40.0 / 3.0, 20.0 / 3.0), "{:?}", c2);
+        assert!(close(end, 20.0, 0.0), "{:?}", end);
+        assert_eq!(recorder.strokes, [([0, 0, 0], 0.5)]);
+    }
+}
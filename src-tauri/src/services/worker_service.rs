@@ -0,0 +1,864 @@
+use crate::models::{
+    DiagramFormat, DiagramOptions, RenderWorkerSettings, RenderWorkerStatus, RendererBackend,
+    RendererCapabilities,
+};
+use crate::services::discovery_service::node_for_mmdc;
+use crate::services::mmdc_service::{mermaid_config, mmdc_background, read_css, MmdcVersion};
+use crate::services::renderer_service::{RenderError, RenderJob, Renderer};
+use crate::utils::TempFile;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Node script implementing the worker side of the protocol
+const WORKER_SCRIPT: &str = include_str!("../../resources/render-worker.mjs");
+
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+/// A worker idle for longer than this is pinged before it gets a render
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+/// Delay before starting a worker again after a failed start; doubles with
+/// every further failure up to `MAX_START_BACKOFF`
+const START_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_START_BACKOFF: Duration = Duration::from_secs(300);
+
+/// First mermaid-cli release exporting `renderMermaid`, which the worker uses
+const WORKER_SINCE: MmdcVersion = MmdcVersion(10, 0, 0);
+
+/// One SVG render, mirroring the mmdc options the worker supports
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderRequest {
+    pub code: String,
+    pub config: Value,
+    pub css: Option<String>,
+    pub background: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub svg_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Request<'a> {
+    Render(&'a RenderRequest),
+    Ping,
+    Shutdown,
+}
+
+/// A request as written to the worker: one JSON object per line
+#[derive(Serialize)]
+struct Envelope<'a> {
+    id: u64,
+    #[serde(flatten)]
+    request: Request<'a>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    id: u64,
+    ok: bool,
+    #[serde(default)]
+    ready: bool,
+    svg: Option<String>,
+    error: Option<String>,
+    /// Resident memory of Node and Chromium in bytes
+    memory: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkerError {
+    /// Mermaid rejected the diagram; mmdc would fail the same way
+    Render(String),
+    /// The worker could not be started, crashed or stopped responding
+    Unavailable(String),
+}
+
+impl std::fmt::Display for WorkerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkerError::Render(e) => write!(f, "{}", e),
+            WorkerError::Unavailable(e) => write!(f, "render worker unavailable: {}", e),
+        }
+    }
+}
+
+/// How to start a worker process
+#[derive(Debug, Clone)]
+pub struct WorkerLaunch {
+    pub program: PathBuf,
+    pub args: Vec<OsString>,
+    pub env: Vec<(String, String)>,
+}
+
+/// A running worker process
+struct RenderWorker {
+    child: Child,
+    stdin: ChildStdin,
+    responses: Receiver<String>,
+    last_stderr: Arc<Mutex<Option<String>>>,
+    /// Disconnects once stderr reaches end of file
+    stderr_closed: Receiver<()>,
+    next_id: u64,
+    renders: u32,
+    memory: Option<u64>,
+    last_used: Instant,
+}
+
+impl RenderWorker {
+    /// Start the process and wait for its ready message
+    fn spawn(launch: &WorkerLaunch, startup_timeout: Duration) -> Result<Self, String> {
+        let mut child = Command::new(&launch.program)
+            .args(&launch.args)
+            .envs(launch.env.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", launch.program.display(), e))?;
+
+        let stdin = child.stdin.take().ok_or("Worker stdin is not available")?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or("Worker stdout is not available")?;
+
+        // Reader threads end on their own when the process exits
+        let (sender, responses) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let last_stderr = Arc::new(Mutex::new(None));
+        let (stderr_done, stderr_closed) = mpsc::channel::<()>();
+        if let Some(stderr) = child.stderr.take() {
+            let last_stderr = Arc::clone(&last_stderr);
+            std::thread::spawn(move || {
+                let _stderr_done = stderr_done;
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    log::debug!("render worker: {}", line);
+                    if !line.trim().is_empty() {
+                        *last_stderr.lock().unwrap() = Some(line);
+                    }
+                }
+            });
+        }
+
+        let mut worker = Self {
+            child,
+            stdin,
+            responses,
+            last_stderr,
+            stderr_closed,
+            next_id: 1,
+            renders: 0,
+            memory: None,
+            last_used: Instant::now(),
+        };
+
+        match worker.receive(0, startup_timeout) {
+            Ok(response) if response.ready => {
+                log::info!("Render worker started (pid {})", worker.child.id());
+                Ok(worker)
+            }
+            Ok(_) => Err("Render worker sent an unexpected first message".to_string()),
+            Err(e) => Err(format!("Render worker failed to start: {}", e)),
+        }
+    }
+
+    fn send(&mut self, request: Request) -> Result<u64, WorkerError> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let line = serde_json::to_string(&Envelope { id, request })
+            .map_err(|e| WorkerError::Unavailable(format!("Failed to encode request: {}", e)))?;
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| WorkerError::Unavailable(format!("Failed to write request: {}", e)))?;
+        Ok(id)
+    }
+
+    /// Wait for the response to request `id`; other output is skipped
+    fn receive(&mut self, id: u64, timeout: Duration) -> Result<Response, WorkerError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.responses.recv_timeout(remaining) {
+                Ok(line) => match serde_json::from_str::<Response>(&line) {
+                    Ok(response) if response.id == id => return Ok(response),
+                    Ok(response) => log::debug!("Ignoring stale worker response {}", response.id),
+                    Err(_) => log::debug!("render worker: {}", line),
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(WorkerError::Unavailable(format!(
+                        "no response within {} ms",
+                        timeout.as_millis()
+                    )))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(WorkerError::Unavailable(self.exit_reason()))
+                }
+            }
+        }
+    }
+
+    fn render(
+        &mut self,
+        request: &RenderRequest,
+        timeout: Duration,
+    ) -> Result<String, WorkerError> {
+        let id = self.send(Request::Render(request))?;
+        let response = self.receive(id, timeout)?;
+        self.last_used = Instant::now();
+        self.memory = response.memory.or(self.memory);
+
+        if !response.ok {
+            return Err(WorkerError::Render(
+                response
+                    .error
+                    .unwrap_or_else(|| "Unknown render error".to_string()),
+            ));
+        }
+        self.renders += 1;
+        response
+            .svg
+            .ok_or_else(|| WorkerError::Unavailable("response has no SVG".to_string()))
+    }
+
+    fn ping(&mut self, timeout: Duration) -> Result<(), WorkerError> {
+        let id = self.send(Request::Ping)?;
+        let response = self.receive(id, timeout)?;
+        self.last_used = Instant::now();
+        self.memory = response.memory.or(self.memory);
+        Ok(())
+    }
+
+    fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Describe why a worker that closed its output went away, including
+    /// the last line it wrote to stderr
+    fn exit_reason(&mut self) -> String {
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        let status = loop {
+            match self.child.try_wait() {
+                Ok(None) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(20))
+                }
+                Ok(Some(status)) => break format!("worker exited with {}", status),
+                _ => break "worker closed its output".to_string(),
+            }
+        };
+        let _ = self.stderr_closed.recv_timeout(Duration::from_millis(500));
+
+        match self.last_stderr.lock().unwrap().take() {
+            Some(stderr) => format!("{}: {}", status, stderr),
+            None => status,
+        }
+    }
+
+    /// Ask the worker to close Chromium and exit; it is killed if it does not
+    fn shutdown(mut self) {
+        if self.send(Request::Shutdown).is_ok() {
+            let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+            while Instant::now() < deadline && self.is_alive() {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        }
+    }
+}
+
+impl Drop for RenderWorker {
+    fn drop(&mut self) {
+        if self.is_alive() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+/// Keeps a render worker running: starts it on demand, restarts it after a
+/// crash, recycles it when it grows too large and backs off when it cannot
+/// be started at all.
+pub struct WorkerSupervisor {
+    settings: RenderWorkerSettings,
+    launch: WorkerLaunch,
+    _script: Option<TempFile>,
+    worker: Option<RenderWorker>,
+    started: bool,
+    restarts: u32,
+    start_failures: u32,
+    retry_after: Option<Instant>,
+    last_error: Option<String>,
+}
+
+impl WorkerSupervisor {
+    pub fn new(settings: RenderWorkerSettings, launch: WorkerLaunch) -> Self {
+        Self {
+            settings,
+            launch,
+            _script: None,
+            worker: None,
+            started: false,
+            restarts: 0,
+            start_failures: 0,
+            retry_after: None,
+            last_error: None,
+        }
+    }
+
+    /// Supervisor for the Node worker script, using the mermaid-cli package
+    /// that `mmdc_path` belongs to
+    pub fn for_mermaid_cli(
+        settings: RenderWorkerSettings,
+        mmdc_path: &str,
+    ) -> Result<Self, String> {
+        let cli_dir = find_mermaid_cli_package(mmdc_path)
+            .ok_or_else(|| format!("Could not find the mermaid-cli package for {}", mmdc_path))?;
+        let script =
+            TempFile::with_contents("mermaid_render_worker", "mjs", WORKER_SCRIPT.as_bytes())?;
+
+        let mut env = vec![(
+            "MERMAID_CLI_DIR".to_string(),
+            cli_dir.to_string_lossy().to_string(),
+        )];
+        if let Some(ref config) = settings.puppeteer_config {
+            env.push(("MERMAID_PUPPETEER_CONFIG".to_string(), config.to_string()));
+        }
+
+        let launch = WorkerLaunch {
+            program: match settings.node_path {
+                Some(ref node) => PathBuf::from(node),
+                None => node_for_mmdc(Path::new(mmdc_path)).unwrap_or_else(|| "node".into()),
+            },
+            args: vec![script.path().into()],
+            env,
+        };
+        Ok(Self {
+            _script: Some(script),
+            ..Self::new(settings, launch)
+        })
+    }
+
+    /// Render through the worker. A worker that crashes mid-render is
+    /// restarted and the render retried once; timeouts are not retried.
+    pub fn render(&mut self, request: &RenderRequest) -> Result<String, WorkerError> {
+        let timeout = Duration::from_millis(self.settings.render_timeout_ms);
+        let mut retried = false;
+
+        loop {
+            let worker = self.ensure_worker()?;
+            match worker.render(request, timeout) {
+                Ok(svg) => {
+                    self.recycle_if_needed();
+                    return Ok(svg);
+                }
+                Err(WorkerError::Unavailable(e)) => {
+                    let crashed = !worker.is_alive();
+                    self.worker = None;
+                    self.last_error = Some(e.clone());
+                    if !crashed || retried {
+                        return Err(WorkerError::Unavailable(e));
+                    }
+                    log::warn!("Render worker crashed ({}), restarting", e);
+                    retried = true;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Stop the current worker, if any, and start a new one now
+    pub fn restart(&mut self) -> Result<(), String> {
+        if let Some(worker) = self.worker.take() {
+            worker.shutdown();
+        }
+        self.retry_after = None;
+        self.ensure_worker().map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    /// Current state, after a health check of a running worker
+    pub fn status(&mut self) -> RenderWorkerStatus {
+        if let Some(worker) = self.worker.as_mut() {
+            if let Err(e) = worker.ping(PING_TIMEOUT) {
+                self.last_error = Some(e.to_string());
+                self.worker = None;
+            }
+        }
+
+        RenderWorkerStatus {
+            enabled: self.settings.enabled,
+            running: self.worker.is_some(),
+            pid: self.worker.as_ref().map(|w| w.child.id()),
+            renders: self.worker.as_ref().map_or(0, |w| w.renders),
+            memory_mb: self
+                .worker
+                .as_ref()
+                .and_then(|w| w.memory)
+                .map(|bytes| bytes / (1024 * 1024)),
+            restarts: self.restarts,
+            last_error: self.last_error.clone(),
+        }
+    }
+
+    fn ensure_worker(&mut self) -> Result<&mut RenderWorker, WorkerError> {
+        if let Some(worker) = self.worker.as_mut() {
+            let healthy = worker.is_alive()
+                && (worker.last_used.elapsed() < HEALTH_CHECK_INTERVAL
+                    || worker.ping(PING_TIMEOUT).is_ok());
+            if !healthy {
+                let reason = if worker.is_alive() {
+                    "worker did not answer a health check".to_string()
+                } else {
+                    worker.exit_reason()
+                };
+                log::warn!("Render worker is not healthy ({}), restarting", reason);
+                self.last_error = Some(reason);
+                self.worker = None;
+            }
+        }
+
+        if self.worker.is_none() {
+            if self.retry_after.is_some_and(|at| Instant::now() < at) {
+                return Err(WorkerError::Unavailable(
+                    self.last_error
+                        .clone()
+                        .unwrap_or_else(|| "worker failed to start".to_string()),
+                ));
+            }
+
+            let startup_timeout = Duration::from_millis(self.settings.startup_timeout_ms);
+            match RenderWorker::spawn(&self.launch, startup_timeout) {
+                Ok(worker) => {
+                    if self.started {
+                        self.restarts += 1;
+                    }
+                    self.started = true;
+                    self.start_failures = 0;
+                    self.retry_after = None;
+                    self.worker = Some(worker);
+                }
+                Err(e) => {
+                    self.start_failures += 1;
+                    let backoff = START_BACKOFF
+                        .saturating_mul(1 << (self.start_failures - 1).min(8))
+                        .min(MAX_START_BACKOFF);
+                    log::warn!("{}; retrying in {} s", e, backoff.as_secs());
+                    self.retry_after = Some(Instant::now() + backoff);
+                    self.last_error = Some(e.clone());
+                    return Err(WorkerError::Unavailable(e));
+                }
+            }
+        }
+
+        self.worker
+            .as_mut()
+            .ok_or_else(|| WorkerError::Unavailable("worker is not running".to_string()))
+    }
+
+    /// Replace a worker that has done too many renders or uses too much
+    /// memory; the next render starts a fresh one
+    fn recycle_if_needed(&mut self) {
+        let Some(worker) = self.worker.as_ref() else {
+            return;
+        };
+        let max_memory = self.settings.max_memory_mb.saturating_mul(1024 * 1024);
+        let too_large = worker.memory.is_some_and(|memory| memory > max_memory);
+        let too_old = worker.renders >= self.settings.max_renders;
+
+        if too_large || too_old {
+            log::info!(
+                "Recycling render worker after {} renders ({} MB)",
+                worker.renders,
+                worker.memory.unwrap_or_default() / (1024 * 1024)
+            );
+            if let Some(worker) = self.worker.take() {
+                worker.shutdown();
+            }
+        }
+    }
+}
+
+/// SVG renderer backed by a worker for the mmdc install in use
+pub struct WorkerRenderer {
+    settings: RenderWorkerSettings,
+    mmdc: Option<(String, Option<MmdcVersion>)>,
+    /// Created on the first render
+    supervisor: Option<WorkerSupervisor>,
+    /// Why the worker cannot be used with this mmdc install
+    error: Option<String>,
+}
+
+impl WorkerRenderer {
+    pub fn new(settings: RenderWorkerSettings) -> Self {
+        Self {
+            settings,
+            mmdc: None,
+            supervisor: None,
+            error: None,
+        }
+    }
+
+    /// Replace the settings; a running worker is stopped and the next render
+    /// starts one with the new settings
+    pub fn configure(&mut self, settings: RenderWorkerSettings) {
+        self.supervisor = None;
+        self.error = None;
+        self.settings = settings;
+    }
+
+    /// Render with the mermaid-cli package of this mmdc path and version
+    pub fn use_mmdc(&mut self, mmdc: Option<(String, Option<MmdcVersion>)>) {
+        if mmdc != self.mmdc {
+            // The worker belongs to the previous mmdc install
+            self.supervisor = None;
+            self.error = None;
+            self.mmdc = mmdc;
+        }
+    }
+
+    pub fn status(&mut self) -> RenderWorkerStatus {
+        match self.supervisor.as_mut() {
+            Some(supervisor) => supervisor.status(),
+            None => RenderWorkerStatus {
+                enabled: self.settings.enabled,
+                last_error: self.error.clone(),
+                ..RenderWorkerStatus::default()
+            },
+        }
+    }
+
+    /// Start the worker now, or restart it if it is running
+    pub fn restart(&mut self) -> Result<RenderWorkerStatus, String> {
+        self.error = None;
+        self.supervisor()?.restart()?;
+        Ok(self.status())
+    }
+
+    fn supervisor(&mut self) -> Result<&mut WorkerSupervisor, String> {
+        if !self.settings.enabled {
+            return Err("The render worker is disabled".to_string());
+        }
+        if let Some(ref e) = self.error {
+            return Err(e.clone());
+        }
+
+        if self.supervisor.is_none() {
+            let (mmdc_path, version) = self.mmdc.as_ref().ok_or("mmdc not available")?;
+            let supervisor = match version {
+                Some(version) if *version < WORKER_SINCE => Err(format!(
+                    "mmdc {} is too old for the render worker (needs {})",
+                    version, WORKER_SINCE
+                )),
+                _ => WorkerSupervisor::for_mermaid_cli(self.settings.clone(), mmdc_path),
+            };
+            match supervisor {
+                Ok(supervisor) => self.supervisor = Some(supervisor),
+                Err(e) => {
+                    log::warn!("Render worker disabled: {}", e);
+                    self.error = Some(e.clone());
+                    return Err(e);
+                }
+            }
+        }
+        self.supervisor
+            .as_mut()
+            .ok_or_else(|| "Render worker is not set up".to_string())
+    }
+}
+
+impl Renderer for WorkerRenderer {
+    fn backend(&self) -> RendererBackend {
+        RendererBackend::Worker
+    }
+
+    fn capabilities(&self) -> RendererCapabilities {
+        RendererCapabilities {
+            formats: vec![DiagramFormat::Svg],
+            diagram_types: None,
+        }
+    }
+
+    fn availability(&mut self) -> Result<(), String> {
+        self.supervisor().map(|_| ())
+    }
+
+    fn render(&mut self, job: &RenderJob) -> Result<Vec<u8>, RenderError> {
+        let request =
+            worker_request(job.code, job.options, job.extra_config).map_err(RenderError::Failed)?;
+        let supervisor = self.supervisor().map_err(RenderError::Unavailable)?;
+        match supervisor.render(&request) {
+            Ok(svg) => Ok(svg.into_bytes()),
+            Err(WorkerError::Render(e)) => Err(RenderError::Failed(e)),
+            Err(WorkerError::Unavailable(e)) => Err(RenderError::Unavailable(e)),
+        }
+    }
+}
+
+/// The worker equivalent of the mmdc command line
+fn worker_request(
+    code: &str,
+    options: &DiagramOptions,
+    extra_config: Option<&Value>,
+) -> Result<RenderRequest, String> {
+    Ok(RenderRequest {
+        code: code.to_string(),
+        // Like mmdc, the theme option is overridden by the config
+        config: mermaid_config(options, extra_config)?,
+        css: read_css(options)?,
+        background: mmdc_background(options).unwrap_or("white").to_string(),
+        width: options.width.filter(|w| *w > 0),
+        height: options.height.filter(|h| *h > 0),
+        svg_id: options.svg_id.clone(),
+    })
+}
+
+/// Locate the `@mermaid-js/mermaid-cli` package an mmdc executable belongs to
+pub fn find_mermaid_cli_package(mmdc_path: &str) -> Option<PathBuf> {
+    let mmdc = Path::new(mmdc_path);
+    let mmdc = if mmdc.components().count() > 1 {
+        mmdc.to_path_buf()
+    } else {
+        std::env::var_os("PATH").and_then(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join(mmdc))
+                .find(|path| path.is_file())
+        })?
+    };
+
+    let is_cli_package = |dir: &Path| {
+        std::fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .is_some_and(|manifest| manifest["name"] == "@mermaid-js/mermaid-cli")
+    };
+
+    // On Unix mmdc is usually a symlink to src/cli.js inside the package
+    if let Ok(resolved) = mmdc.canonicalize() {
+        if let Some(dir) = resolved.ancestors().find(|dir| is_cli_package(dir)) {
+            return Some(dir.to_path_buf());
+        }
+    }
+
+    // Otherwise look where npm puts packages relative to its bin directory
+    let bin = mmdc.parent()?;
+    [
+        bin.join("node_modules"),
+        bin.join("../lib/node_modules"),
+        bin.join(".."),
+    ]
+    .into_iter()
+    .map(|modules| modules.join("@mermaid-js").join("mermaid-cli"))
+    .find(|dir| is_cli_package(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp::TempDir;
+
+    #[test]
+    fn test_request_encoding() {
+        let request = RenderRequest {
+            code: "graph TD".to_string(),
+            config: serde_json::json!({"theme": "dark"}),
+            css: None,
+            background: "white".to_string(),
+            width: Some(800),
+            height: None,
+            svg_id: Some("preview".to_string()),
+        };
+
+        let line = serde_json::to_string(&Envelope {
+            id: 7,
+            request: Request::Render(&request),
+        })
+        .unwrap();
+        assert!(line
+            .starts_with(r#"{"id":7,"type":"render","code":"graph TD","config":{"theme":"dark"}"#));
+        assert!(line.contains(r#""svgId":"preview""#));
+
+        let ping = serde_json::to_string(&Envelope {
+            id: 8,
+            request: Request::Ping,
+        })
+        .unwrap();
+        assert_eq!(ping, r#"{"id":8,"type":"ping"}"#);
+    }
+
+    #[test]
+    fn test_worker_request_matches_mmdc_options() {
+        use crate::models::{FlowchartConfig, FlowchartCurve, MermaidConfig};
+
+        let options = DiagramOptions {
+            theme: Some("dark".to_string()),
+            background: "transparent".to_string(),
+            mermaid_config: Some(MermaidConfig {
+                flowchart: Some(FlowchartConfig {
+                    curve: Some(FlowchartCurve::Linear),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            css: Some(".node rect { rx: 4px; }".to_string()),
+            width: Some(0),
+            ..DiagramOptions::svg(None)
+        };
+
+        let request = worker_request("graph TD; A-->B", &options, None).unwrap();
+        assert_eq!(request.config["theme"], "dark");
+        assert_eq!(request.config["flowchart"]["curve"], "linear");
+        assert_eq!(request.background, "white");
+        assert_eq!(request.width, None);
+        assert!(request.css.unwrap().contains("rx: 4px"));
+
+        let mut old = WorkerRenderer::new(RenderWorkerSettings::default());
+        old.use_mmdc(Some(("mmdc".to_string(), Some(MmdcVersion(9, 4, 0)))));
+        assert!(old.availability().is_err());
+        assert!(old.status().last_error.unwrap().contains("too old"));
+    }
+
+    #[test]
+    fn test_find_mermaid_cli_package() {
+        let temp = TempDir::new("mermaid_cli");
+        let root = temp.path();
+        let package = root.join("lib/node_modules/@mermaid-js/mermaid-cli");
+        std::fs::create_dir_all(&package).unwrap();
+        std::fs::create_dir_all(root.join("bin")).unwrap();
+        std::fs::write(
+            package.join("package.json"),
+            r#"{"name": "@mermaid-js/mermaid-cli"}"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("bin/mmdc"), "").unwrap();
+
+        let found = find_mermaid_cli_package(&root.join("bin/mmdc").to_string_lossy()).unwrap();
+        assert_eq!(
+            found.canonicalize().unwrap(),
+            package.canonicalize().unwrap()
+        );
+        assert!(find_mermaid_cli_package(&root.join("missing/mmdc").to_string_lossy()).is_none());
+    }
+
+    /// Shell stand-in for the Node worker that reacts to words in the code
+    #[cfg(unix)]
+    const STUB_WORKER: &str = r#"
+echo '{"id":0,"ok":true,"ready":true}'
+while IFS= read -r line; do
+  id=$(printf '%s\n' "$line" | sed -n 's/^{"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"type":"ping"'*) echo "{\"id\":$id,\"ok\":true,\"memory\":1048576}" ;;
+    *'"type":"shutdown"'*) exit 0 ;;
+    *crash*) echo "out of memory" >&2; exit 3 ;;
+    *hang*) sleep 2 ;;
+    *invalid*) echo "{\"id\":$id,\"ok\":false,\"error\":\"Parse error on line 2\"}" ;;
+    *huge*) echo "{\"id\":$id,\"ok\":true,\"svg\":\"<svg/>\",\"memory\":4294967296}" ;;
+    *) echo "debug output"; echo "{\"id\":$id,\"ok\":true,\"svg\":\"<svg/>\",\"memory\":1048576}" ;;
+  esac
+done
+"#;
+
+    #[cfg(unix)]
+    fn render(supervisor: &mut WorkerSupervisor, code: &str) -> Result<String, WorkerError> {
+        supervisor.render(&RenderRequest {
+            code: code.to_string(),
+            config: serde_json::json!({}),
+            css: None,
+            background: "white".to_string(),
+            width: None,
+            height: None,
+            svg_id: None,
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_supervisor_with_stub_worker() {
+        let script =
+            TempFile::with_contents("mermaid_stub_worker", "sh", STUB_WORKER.as_bytes()).unwrap();
+        let settings = RenderWorkerSettings {
+            max_renders: 3,
+            render_timeout_ms: 300,
+            startup_timeout_ms: 5_000,
+            ..RenderWorkerSettings::default()
+        };
+        let launch = WorkerLaunch {
+            program: PathBuf::from("sh"),
+            args: vec![script.path().into()],
+            env: Vec::new(),
+        };
+        let mut supervisor = WorkerSupervisor::new(settings, launch);
+
+        assert_eq!(render(&mut supervisor, "graph TD").unwrap(), "<svg/>");
+        let status = supervisor.status();
+        assert!(status.running);
+        assert_eq!((status.renders, status.memory_mb), (1, Some(1)));
+        let pid = status.pid;
+
+        // Diagram errors are reported without restarting the worker
+        assert_eq!(
+            render(&mut supervisor, "invalid"),
+            Err(WorkerError::Render("Parse error on line 2".to_string()))
+        );
+        assert_eq!(supervisor.status().pid, pid);
+
+        // A crash is retried once with a fresh worker, then given up
+        match render(&mut supervisor, "crash") {
+            Err(WorkerError::Unavailable(e)) => assert!(e.contains("out of memory"), "{}", e),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(render(&mut supervisor, "graph LR").unwrap(), "<svg/>");
+        let status = supervisor.status();
+        assert_ne!(status.pid, pid);
+        assert_eq!(status.restarts, 2);
+
+        // Timeouts kill the worker without a retry
+        assert!(matches!(
+            render(&mut supervisor, "hang"),
+            Err(WorkerError::Unavailable(_))
+        ));
+        assert!(!supervisor.status().running);
+
+        // Workers over the memory limit are recycled after the render
+        assert_eq!(render(&mut supervisor, "huge").unwrap(), "<svg/>");
+        assert!(!supervisor.status().running);
+    }
+
+    #[test]
+    fn test_supervisor_backs_off_after_failed_start() {
+        let launch = WorkerLaunch {
+            program: PathBuf::from("/nonexistent/node"),
+            args: Vec::new(),
+            env: Vec::new(),
+        };
+        let mut supervisor = WorkerSupervisor::new(RenderWorkerSettings::default(), launch);
+
+        let first = supervisor.render(&RenderRequest {
+            code: "graph TD".to_string(),
+            config: Value::Null,
+            css: None,
+            background: "white".to_string(),
+            width: None,
+            height: None,
+            svg_id: None,
+        });
+        assert!(matches!(first, Err(WorkerError::Unavailable(_))));
+        assert_eq!(supervisor.start_failures, 1);
+        assert!(supervisor.retry_after.is_some());
+
+        let status = supervisor.status();
+        assert!(!status.running);
+        assert!(status.last_error.unwrap().contains("Failed to start"));
+    }
+}
@@ -0,0 +1,508 @@
+//! Calendar arithmetic on naive timestamps: milliseconds since 1970-01-01
+//! without a time zone, which is how mermaid's gantt charts treat dates.
+
+pub const MS_PER_SECOND: i64 = 1000;
+pub const MS_PER_MINUTE: i64 = 60 * MS_PER_SECOND;
+pub const MS_PER_HOUR: i64 = 60 * MS_PER_MINUTE;
+pub const MS_PER_DAY: i64 = 24 * MS_PER_HOUR;
+pub const MS_PER_WEEK: i64 = 7 * MS_PER_DAY;
+/// Furthest timestamp from 1970 that JavaScript dates can hold
+pub const MAX_TIMESTAMP: i64 = 100_000_000 * MS_PER_DAY;
+
+pub const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Starting with Sunday, like JavaScript's `Date.getDay`
+pub const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i64,
+    /// 1 to 12
+    pub month: u32,
+    /// 1 to 31
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub millisecond: u32,
+}
+
+impl DateTime {
+    pub fn date(year: i64, month: u32, day: u32) -> Self {
+        Self {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            millisecond: 0,
+        }
+    }
+
+    pub fn from_timestamp(timestamp: i64) -> Self {
+        let days = timestamp.div_euclid(MS_PER_DAY);
+        let time = timestamp.rem_euclid(MS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year,
+            month,
+            day,
+            hour: (time / MS_PER_HOUR) as u32,
+            minute: (time % MS_PER_HOUR / MS_PER_MINUTE) as u32,
+            second: (time % MS_PER_MINUTE / MS_PER_SECOND) as u32,
+            millisecond: (time % MS_PER_SECOND) as u32,
+        }
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * MS_PER_DAY
+            + self.hour as i64 * MS_PER_HOUR
+            + self.minute as i64 * MS_PER_MINUTE
+            + self.second as i64 * MS_PER_SECOND
+            + self.millisecond as i64
+    }
+
+    fn is_valid(&self) -> bool {
+        (1..=12).contains(&self.month)
+            && self.day >= 1
+            && self.day <= days_in_month(self.year, self.month)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60
+            && self.millisecond < 1000
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month and day of a number of days since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Day of the week, 0 for Sunday
+pub fn weekday(timestamp: i64) -> u32 {
+    (timestamp.div_euclid(MS_PER_DAY) + 4).rem_euclid(7) as u32
+}
+
+/// Midnight at the start of the timestamp's day
+pub fn start_of_day(timestamp: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(MS_PER_DAY)
+}
+
+/// Add calendar months, clamping the day to the end of shorter months
+pub fn add_months(timestamp: i64, months: i64) -> i64 {
+    let mut date = DateTime::from_timestamp(timestamp);
+    let total = date.year * 12 + date.month as i64 - 1 + months;
+    date.year = total.div_euclid(12);
+    date.month = (total.rem_euclid(12) + 1) as u32;
+    date.day = date.day.min(days_in_month(date.year, date.month));
+    date.timestamp()
+}
+
+/// The timestamp as `YYYY-MM-DDTHH:mm:ss`, with milliseconds only when
+/// there are any
+pub fn to_iso(timestamp: i64) -> String {
+    let date = DateTime::from_timestamp(timestamp);
+    let mut text = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        date.year, date.month, date.day, date.hour, date.minute, date.second
+    );
+    if date.millisecond > 0 {
+        text.push_str(&format!(".{:03}", date.millisecond));
+    }
+    text
+}
+
+/// The current time as a naive timestamp in UTC
+pub fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Tokens of a day.js format string such as `YYYY-MM-DD HH:mm`, longest
+/// first so that `MMMM` wins over `MM`
+const FORMAT_TOKENS: &[&str] = &[
+    "YYYY", "MMMM", "dddd", "MMM", "ddd", "SSS", "YY", "MM", "Do", "DD", "HH", "hh", "mm", "ss",
+    "M", "D", "H", "h", "m", "s", "A", "a", "X", "x",
+];
+
+enum FormatPart<'a> {
+    Token(&'a str),
+    Literal(String),
+}
+
+fn format_parts(format: &str) -> Vec<FormatPart<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = format;
+    while !rest.is_empty() {
+        if let Some(escaped) = rest.strip_prefix('[') {
+            let end = escaped.find(']').unwrap_or(escaped.len());
+            parts.push(FormatPart::Literal(escaped[..end].to_string()));
+            rest = escaped.get(end + 1..).unwrap_or_default();
+        } else if let Some(token) = FORMAT_TOKENS.iter().find(|t| rest.starts_with(**t)) {
+            parts.push(FormatPart::Token(token));
+            rest = &rest[token.len()..];
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            match parts.last_mut() {
+                Some(FormatPart::Literal(literal)) => literal.push(ch),
+                _ => parts.push(FormatPart::Literal(ch.to_string())),
+            }
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    parts
+}
+
+/// Parse `text` strictly against a day.js format such as `YYYY-MM-DD`.
+/// Missing date parts default to 1970-01-01.
+pub fn parse_date(text: &str, format: &str) -> Option<i64> {
+    let mut date = DateTime::date(1970, 1, 1);
+    let mut afternoon = None;
+    let mut rest = text.trim();
+
+    // Take up to `max` leading digits, at least `min`
+    fn digits(text: &str, min: usize, max: usize) -> Option<(u32, &str)> {
+        let length = text
+            .char_indices()
+            .take(max)
+            .take_while(|(_, c)| c.is_ascii_digit())
+            .count();
+        if length < min {
+            return None;
+        }
+        Some((text[..length].parse().ok()?, &text[length..]))
+    }
+
+    for part in format_parts(format) {
+        let token = match part {
+            FormatPart::Literal(literal) => {
+                rest = rest.strip_prefix(literal.as_str())?;
+                continue;
+            }
+            FormatPart::Token(token) => token,
+        };
+        match token {
+            "X" | "x" => {
+                let length = rest
+                    .char_indices()
+                    .take_while(|(i, c)| c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-'))
+                    .count();
+                let value: f64 = rest[..length].parse().ok()?;
+                rest = &rest[length..];
+                let timestamp = match token {
+                    "X" => value * 1000.0,
+                    _ => value,
+                };
+                if timestamp.abs() > MAX_TIMESTAMP as f64 {
+                    return None;
+                }
+                let timestamp = timestamp as i64;
+                date = DateTime::from_timestamp(timestamp);
+            }
+            "MMMM" | "MMM" | "dddd" | "ddd" => {
+                let names: &[&str] = if token.starts_with('M') {
+                    &MONTHS
+                } else {
+                    &WEEKDAYS
+                };
+                let length = if token.len() == 4 { None } else { Some(3) };
+                let (index, name_length) = names.iter().enumerate().find_map(|(index, name)| {
+                    let name = length.map_or(*name, |length| &name[..length]);
+                    rest.get(..name.len())
+                        .filter(|prefix| prefix.eq_ignore_ascii_case(name))
+                        .map(|_| (index, name.len()))
+                })?;
+                if token.starts_with('M') {
+                    date.month = index as u32 + 1;
+                }
+                rest = &rest[name_length..];
+            }
+            "A" | "a" => {
+                let prefix = rest.get(..2)?.to_ascii_lowercase();
+                afternoon = Some(match prefix.as_str() {
+                    "am" => false,
+                    "pm" => true,
+                    _ => return None,
+                });
+                rest = &rest[2..];
+            }
+            "Do" => {
+                let (day, tail) = digits(rest, 1, 2)?;
+                date.day = day;
+                rest = ["st", "nd", "rd", "th"]
+                    .iter()
+                    .find_map(|suffix| tail.strip_prefix(suffix))?;
+            }
+            _ => {
+                let (min, max) = match token {
+                    "YYYY" => (4, 4),
+                    "SSS" => (3, 3),
+                    "YY" | "MM" | "DD" | "HH" | "hh" | "mm" | "ss" => (2, 2),
+                    _ => (1, 2),
+                };
+                let (value, tail) = digits(rest, min, max)?;
+                rest = tail;
+                match token {
+                    "YYYY" => date.year = value as i64,
+                    "YY" => date.year = value as i64 + if value > 68 { 1900 } else { 2000 },
+                    "MM" | "M" => date.month = value,
+                    "DD" | "D" => date.day = value,
+                    "HH" | "H" | "hh" | "h" => date.hour = value,
+                    "mm" | "m" => date.minute = value,
+                    "ss" | "s" => date.second = value,
+                    _ => date.millisecond = value,
+                }
+            }
+        }
+    }
+
+    if !rest.is_empty() {
+        return None;
+    }
+    if let Some(afternoon) = afternoon {
+        if date.hour == 0 || date.hour > 12 {
+            return None;
+        }
+        date.hour = date.hour % 12 + if afternoon { 12 } else { 0 };
+    }
+    date.is_valid().then(|| date.timestamp())
+}
+
+/// Format a timestamp with a day.js format such as `YYYY-MM-DD`
+pub fn format_date(timestamp: i64, format: &str) -> String {
+    let date = DateTime::from_timestamp(timestamp);
+    let hour12 = match date.hour % 12 {
+        0 => 12,
+        hour => hour,
+    };
+    let mut text = String::new();
+    for part in format_parts(format) {
+        let token = match part {
+            FormatPart::Literal(literal) => {
+                text.push_str(&literal);
+                continue;
+            }
+            FormatPart::Token(token) => token,
+        };
+        let value = match token {
+            "YYYY" => format!("{:04}", date.year),
+            "YY" => format!("{:02}", date.year.rem_euclid(100)),
+            "MMMM" => MONTHS[date.month as usize - 1].to_string(),
+            "MMM" => MONTHS[date.month as usize - 1][..3].to_string(),
+            "MM" => format!("{:02}", date.month),
+            "M" => date.month.to_string(),
+            "dddd" => WEEKDAYS[weekday(timestamp) as usize].to_string(),
+            "ddd" => WEEKDAYS[weekday(timestamp) as usize][..3].to_string(),
+            "Do" => format!("{}{}", date.day, ordinal_suffix(date.day)),
+            "DD" => format!("{:02}", date.day),
+            "D" => date.day.to_string(),
+            "HH" => format!("{:02}", date.hour),
+            "H" => date.hour.to_string(),
+            "hh" => format!("{:02}", hour12),
+            "h" => hour12.to_string(),
+            "mm" => format!("{:02}", date.minute),
+            "m" => date.minute.to_string(),
+            "ss" => format!("{:02}", date.second),
+            "s" => date.second.to_string(),
+            "SSS" => format!("{:03}", date.millisecond),
+            "A" => if date.hour < 12 { "AM" } else { "PM" }.to_string(),
+            "a" => if date.hour < 12 { "am" } else { "pm" }.to_string(),
+            "X" => timestamp.div_euclid(MS_PER_SECOND).to_string(),
+            _ => timestamp.to_string(),
+        };
+        text.push_str(&value);
+    }
+    text
+}
+
+fn ordinal_suffix(day: u32) -> &'static str {
+    match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// Format a timestamp with a d3 / strftime format such as `%Y-%m-%d`
+pub fn strftime(timestamp: i64, format: &str) -> String {
+    let date = DateTime::from_timestamp(timestamp);
+    let day_of_week = weekday(timestamp);
+    let day_of_year = (days_from_civil(date.year, date.month, date.day)
+        - days_from_civil(date.year, 1, 1)) as u32;
+    let mut text = String::new();
+    let mut chars = format.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            text.push(ch);
+            continue;
+        }
+        let mut directive = chars.next().unwrap_or('%');
+        let padding = match directive {
+            '-' | '_' | '0' => {
+                let padding = Some(directive);
+                directive = chars.next().unwrap_or('%');
+                padding
+            }
+            _ => None,
+        };
+        let pad = |value: i64, width: usize, default: char| match padding.unwrap_or(default) {
+            '-' => value.to_string(),
+            ' ' | '_' => format!("{:>width$}", value, width = width),
+            _ => format!("{:0width$}", value, width = width),
+        };
+        let value = match directive {
+            'a' => WEEKDAYS[day_of_week as usize][..3].to_string(),
+            'A' => WEEKDAYS[day_of_week as usize].to_string(),
+            'b' | 'h' => MONTHS[date.month as usize - 1][..3].to_string(),
+            'B' => MONTHS[date.month as usize - 1].to_string(),
+            'd' => pad(date.day as i64, 2, '0'),
+            'e' => pad(date.day as i64, 2, ' '),
+            'H' => pad(date.hour as i64, 2, '0'),
+            'I' => pad(((date.hour + 11) % 12 + 1) as i64, 2, '0'),
+            'j' => pad(day_of_year as i64 + 1, 3, '0'),
+            'L' => pad(date.millisecond as i64, 3, '0'),
+            'm' => pad(date.month as i64, 2, '0'),
+            'M' => pad(date.minute as i64, 2, '0'),
+            'p' => if date.hour < 12 { "AM" } else { "PM" }.to_string(),
+            'S' => pad(date.second as i64, 2, '0'),
+            's' => timestamp.div_euclid(MS_PER_SECOND).to_string(),
+            'Q' => timestamp.to_string(),
+            'u' => (if day_of_week == 0 { 7 } else { day_of_week }).to_string(),
+            'w' => day_of_week.to_string(),
+            'U' => pad(((day_of_year + 7 - day_of_week) / 7) as i64, 2, '0'),
+            'W' => pad(
+                ((day_of_year + 7 - (day_of_week + 6) % 7) / 7) as i64,
+                2,
+                '0',
+            ),
+            'V' => pad(iso_week(timestamp) as i64, 2, '0'),
+            'y' => pad(date.year.rem_euclid(100), 2, '0'),
+            'Y' => pad(date.year, 4, '0'),
+            'Z' => "+0000".to_string(),
+            '%' => "%".to_string(),
+            other => format!("%{}", other),
+        };
+        text.push_str(&value);
+    }
+    text
+}
+
+/// ISO 8601 week number: weeks start on Monday and week 1 holds the
+/// year's first Thursday
+fn iso_week(timestamp: i64) -> u32 {
+    let days = timestamp.div_euclid(MS_PER_DAY);
+    let monday_based = (weekday(timestamp) + 6) % 7;
+    let thursday = days - monday_based as i64 + 3;
+    let (year, _, _) = civil_from_days(thursday);
+    ((thursday - days_from_civil(year, 1, 1)) / 7 + 1) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_round_trip_and_weekdays() {
+        for days in [-719_468, -1, 0, 59, 10_957, 19_782, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        let timestamp = DateTime::date(2024, 3, 2).timestamp();
+        assert_eq!(weekday(timestamp), 6);
+        assert_eq!(
+            to_iso(add_months(DateTime::date(2024, 1, 31).timestamp(), 1)),
+            "2024-02-29T00:00:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let timestamp = parse_date("2024-03-02 14:05", "YYYY-MM-DD HH:mm").unwrap();
+        assert_eq!(to_iso(timestamp), "2024-03-02T14:05:00");
+        assert_eq!(
+            format_date(timestamp, "ddd D MMM YY, h:mm a"),
+            "Sat 2 Mar 24, 2:05 pm"
+        );
+        assert_eq!(
+            parse_date("2nd March 2024 [x]", "Do MMMM YYYY [[x]]")
+                .map(to_iso)
+                .as_deref(),
+            Some("2024-03-02T00:00:00")
+        );
+        assert_eq!(parse_date("1709337600", "X"), Some(1_709_337_600_000));
+        assert_eq!(parse_date("2024-02-30", "YYYY-MM-DD"), None);
+        assert_eq!(parse_date("2024-3-2", "YYYY-MM-DD"), None);
+
+        assert_eq!(strftime(timestamp, "%Y-%m-%d %H:%M"), "2024-03-02 14:05");
+        assert_eq!(
+            strftime(timestamp, "%a %e %b, %-I%p (%j, W%V)"),
+            "Sat  2 Mar, 2PM (062, W09)"
+        );
+    }
+}
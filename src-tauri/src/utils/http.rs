@@ -0,0 +1,231 @@
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+/// Send a POST request over plain HTTP/1.1. This is meant for services on
+/// the local network, so `https://` URLs are rejected.
+pub fn post(
+    url: &str,
+    content_type: &str,
+    body: &[u8],
+    timeout: Duration,
+) -> Result<HttpResponse, String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("Only http:// URLs are supported: {}", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let (host, port) = split_authority(authority).map_err(|e| format!("{} in URL: {}", e, url))?;
+
+    let mut stream = connect(authority, host, port, timeout)?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| stream.set_write_timeout(Some(timeout)))
+        .map_err(|e| format!("Failed to configure connection: {}", e))?;
+
+    let head = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\nAccept: */*\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        path,
+        authority,
+        content_type,
+        body.len()
+    );
+    stream
+        .write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(body))
+        .map_err(|e| format!("Failed to send request to {}: {}", authority, e))?;
+
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .map_err(|e| format!("Failed to read response from {}: {}", authority, e))?;
+    parse_response(&response)
+}
+
+/// Split `host:port` into its parts, defaulting to port 80. IPv6 hosts are
+/// bracketed, as in `[::1]:8000`, and are returned without the brackets.
+fn split_authority(authority: &str) -> Result<(&str, u16), String> {
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, rest) = bracketed.split_once(']').ok_or("Unclosed IPv6 address")?;
+            match rest {
+                "" => (host, None),
+                _ => (host, Some(rest.strip_prefix(':').ok_or("Invalid port")?)),
+            }
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    if host.is_empty() {
+        return Err("Missing host".to_string());
+    }
+    let port = match port {
+        Some(port) => port.parse::<u16>().map_err(|_| "Invalid port")?,
+        None => 80,
+    };
+    Ok((host, port))
+}
+
+/// Connect to each resolved address in turn, so that a host such as
+/// `localhost` still works when the service only listens on one of its IPv4
+/// and IPv6 addresses
+fn connect(authority: &str, host: &str, port: u16, timeout: Duration) -> Result<TcpStream, String> {
+    let addresses = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?;
+    let mut last_error = None;
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(match last_error {
+        Some(e) => format!("Failed to connect to {}: {}", authority, e),
+        None => format!("Failed to resolve {}", host),
+    })
+}
+
+fn parse_response(response: &[u8]) -> Result<HttpResponse, String> {
+    let header_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or("Incomplete HTTP response")?;
+    let head = String::from_utf8_lossy(&response[..header_end]);
+    let mut body = response[header_end + 4..].to_vec();
+
+    let mut lines = head.lines();
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or("Invalid HTTP status line")?;
+
+    let mut chunked = false;
+    let mut content_length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>().ok();
+        }
+    }
+
+    if chunked {
+        body = decode_chunked(&body)?;
+    } else if let Some(length) = content_length {
+        body.truncate(length);
+    }
+    Ok(HttpResponse { status, body })
+}
+
+fn decode_chunked(mut data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = data
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or("Truncated chunked response")?;
+        let size_line = String::from_utf8_lossy(&data[..line_end]);
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| format!("Invalid chunk size: {}", size_hex))?;
+        data = &data[line_end + 2..];
+        if size == 0 {
+            return Ok(body);
+        }
+        if data.len() < size {
+            return Err("Truncated chunked response".to_string());
+        }
+        body.extend_from_slice(&data[..size]);
+        data = data.get(size + 2..).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        let plain =
+            parse_response(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 5\r\n\r\nerror").unwrap();
+        assert_eq!(plain.status, 400);
+        assert_eq!(plain.text(), "error");
+
+        let chunked = parse_response(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n<svg\r\n3\r\n/>\n\r\n0\r\n\r\n",
+        )
+        .unwrap();
+        assert!(chunked.is_success());
+        assert_eq!(chunked.text(), "<svg/>\n");
+
+        assert!(post(
+            "https://kroki.io",
+            "text/plain",
+            b"",
+            Duration::from_secs(1)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_split_authority() {
+        assert_eq!(split_authority("localhost"), Ok(("localhost", 80)));
+        assert_eq!(split_authority("localhost:8000"), Ok(("localhost", 8000)));
+        assert_eq!(split_authority("[::1]"), Ok(("::1", 80)));
+        assert_eq!(split_authority("[::1]:8000"), Ok(("::1", 8000)));
+        assert!(split_authority("[::1").is_err());
+        assert!(split_authority("[::1]8000").is_err());
+        assert!(split_authority("localhost:http").is_err());
+        assert!(split_authority(":8000").is_err());
+    }
+
+    #[test]
+    fn test_post_tries_every_address() {
+        // Listen on IPv4 only, while `localhost` may resolve to ::1 first
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .unwrap();
+        });
+
+        let response = post(
+            &format!("http://localhost:{}/render", port),
+            "text/plain",
+            b"graph TD",
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        server.join().unwrap();
+        assert_eq!(response.text(), "ok");
+    }
+}
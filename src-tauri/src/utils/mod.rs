@@ -1,6 +1,14 @@
+pub mod date;
+pub mod http;
+pub mod json;
 pub mod path;
 pub mod platform;
+pub mod temp;
 
 // Re-export specific items we use
-pub use path::{get_mmdc_paths, get_npm_global_path, generate_output_path, ensure_directory_exists};
-pub use platform::{DefaultDirectories, PlatformInfo, open_with_default_app};
\ No newline at end of file
+pub use json::merge_json;
+pub use path::{
+    ensure_directory_exists, generate_output_path, get_mmdc_paths, get_npm_global_path, slugify,
+};
+pub use platform::{open_with_default_app, DefaultDirectories, PlatformInfo};
+pub use temp::TempFile;
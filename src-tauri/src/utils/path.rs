@@ -23,23 +23,11 @@ pub fn get_npm_global_path() -> Option<PathBuf> {
     None
 }
 
-/// Get all possible mmdc executable paths for current platform
+/// Usual mmdc install locations for the current platform. `PATH`, version
+/// managers and npm are searched by the discovery service.
 pub fn get_mmdc_paths() -> Vec<String> {
-    let mut paths = vec!["mmdc".to_string()];
-    
-    // Try npm global path first (cross-platform)
-    if let Some(npm_bin) = get_npm_global_path() {
-        let mmdc_path = npm_bin.join("mmdc");
-        paths.push(mmdc_path.to_string_lossy().to_string());
-        
-        #[cfg(target_os = "windows")]
-        {
-            let mmdc_cmd_path = npm_bin.join("mmdc.cmd");
-            paths.push(mmdc_cmd_path.to_string_lossy().to_string());
-        }
-    }
+    let mut paths = Vec::new();
     
-    // Platform-specific fallback paths
     #[cfg(target_os = "windows")]
     {
         paths.extend(get_windows_mmdc_paths());
@@ -60,7 +48,7 @@ pub fn get_mmdc_paths() -> Vec<String> {
 
 #[cfg(target_os = "windows")]
 fn get_windows_mmdc_paths() -> Vec<String> {
-    let mut paths = vec!["mmdc.cmd".to_string()];
+    let mut paths = Vec::new();
     
     if let Ok(home) = env::var("USERPROFILE") {
         let npm_path = PathBuf::from(home).join("AppData").join("Roaming").join("npm");
@@ -132,4 +120,20 @@ pub fn ensure_directory_exists(path: &std::path::Path) -> Result<(), String> {
         return Err("Path exists but is not a directory".to_string());
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Turn a display name into a lowercase file name stem made of
+/// `a-z`, `0-9`, `-` and `_`; returns `None` if nothing usable is left
+pub fn slugify(name: &str) -> Option<String> {
+    let slug: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' | '_' => c,
+            'A'..='Z' => c.to_ascii_lowercase(),
+            _ => '-',
+        })
+        .collect();
+    let slug = slug.trim_matches('-');
+    (!slug.is_empty()).then(|| slug.to_string())
+}
@@ -112,6 +112,11 @@ impl DefaultDirectories {
             }
         }
     }
+
+    /// Get the directory this app keeps its own data in
+    pub fn app_storage() -> Option<PathBuf> {
+        Self::app_data().map(|dir| dir.join("com.mermaidgui.v2"))
+    }
     
     /// Get temporary directory
     pub fn temp() -> PathBuf {
@@ -368,4 +373,4 @@ mod tests {
         assert!(!extensions.is_empty());
         assert!(extensions.contains(&".mmd"));
     }
-}
\ No newline at end of file
+}
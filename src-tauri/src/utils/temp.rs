@@ -0,0 +1,131 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A file in the system temp directory that is removed when dropped
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Create a new file with a unique name and write `contents` to it.
+    /// Existing files are never overwritten.
+    pub fn with_contents(prefix: &str, extension: &str, contents: &[u8]) -> Result<Self, String> {
+        loop {
+            let path = unique_path(prefix, &format!(".{}", extension));
+
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    // Take ownership first so a failed write still cleans up
+                    let temp = Self { path };
+                    file.write_all(contents)
+                        .map_err(|e| format!("Failed to write temporary file: {}", e))?;
+                    return Ok(temp);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(format!("Failed to create temporary file: {}", e)),
+            }
+        }
+    }
+
+    /// Reserve a unique empty file for a tool to write its output to
+    pub fn reserve(prefix: &str, extension: &str) -> Result<Self, String> {
+        Self::with_contents(prefix, extension, &[])
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A directory in the system temp directory that is removed with its
+/// contents when dropped, so test fixtures are cleaned up even when an
+/// assertion fails
+#[cfg(test)]
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+#[cfg(test)]
+impl TempDir {
+    /// Create a new empty directory with a unique name
+    pub fn new(prefix: &str) -> Self {
+        loop {
+            let path = unique_path(prefix, "");
+            match std::fs::create_dir(&path) {
+                Ok(()) => return Self { path },
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => panic!("Failed to create temporary directory: {}", e),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A path in the system temp directory that no other call in this or any
+/// other process returns
+fn unique_path(prefix: &str, suffix: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "{}_{}_{}_{}{}",
+        prefix,
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        suffix
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_file_lifecycle() {
+        let first = TempFile::with_contents("mermaid_test", "json", b"{}").unwrap();
+        let second = TempFile::reserve("mermaid_test", "json").unwrap();
+        assert_ne!(first.path(), second.path());
+        assert_eq!(std::fs::read(first.path()).unwrap(), b"{}");
+
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().exists());
+    }
+
+    #[test]
+    fn test_temp_dir_lifecycle() {
+        let first = TempDir::new("mermaid_test");
+        let second = TempDir::new("mermaid_test");
+        assert_ne!(first.path(), second.path());
+        std::fs::write(first.path().join("file.txt"), "x").unwrap();
+
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().is_dir());
+    }
+}
@@ -57,6 +57,23 @@ export class DiagramService {
     }
   }
 
+  async copyAsText() {
+    const { code } = get(editorStore);
+
+    if (!code.trim()) {
+      showError('Please enter Mermaid code!');
+      return;
+    }
+
+    try {
+      const text = await tauriService.render_text_diagram(code);
+      await navigator.clipboard.writeText(text);
+      showSuccess('Diagram copied to the clipboard as text', 'Copied');
+    } catch (error) {
+      showError(`Failed to copy as text: ${error}`);
+    }
+  }
+
   async generatePreview(code: string) {
     // TODO: Implement preview generation
     // This will be used for live preview functionality
@@ -2,7 +2,7 @@ import { open, save } from '@tauri-apps/plugin-dialog';
 import { tauriService } from './tauri';
 import { editorStore } from '$lib/stores/editor';
 import { fileStore } from '$lib/stores/files';
-import { showSuccess, showError } from '$lib/stores/notifications';
+import { showSuccess, showError, showWarning } from '$lib/stores/notifications';
 import { get } from 'svelte/store';
 import type { MermaidFile } from '$lib/types';
 
@@ -57,6 +57,52 @@ export class FileService {
     showSuccess(`File opened: ${this.getFileName(filePath as string)}`);
   }
 
+  async importDiagram() {
+    const { hasUnsavedChanges } = get(editorStore);
+
+    if (hasUnsavedChanges) {
+      const shouldContinue = confirm('You have unsaved changes. Continue without saving?');
+      if (!shouldContinue) return;
+    }
+
+    const filePath = await open({
+      filters: [
+        {
+          name: 'Diagram Files',
+          extensions: ['puml', 'plantuml', 'pu', 'iuml', 'wsd', 'dot', 'gv', 'drawio', 'xml', 'sql'],
+        },
+      ],
+      multiple: false,
+    });
+
+    if (!filePath) return;
+
+    const path = filePath as string;
+    let options;
+    if (/\.sql$/i.test(path)) {
+      // Patterns such as `public.*` or `orders_*` limit the tables shown
+      const tables = prompt('Tables to include (comma-separated, * wildcards; empty for all)', '');
+      if (tables === null) return;
+      options = { tables };
+    }
+    // The file is read on the Rust side, which picks the importer from its extension
+    const imported = await tauriService.import_diagram_file(path, options);
+
+    // The converted diagram opens as a new, unsaved buffer
+    editorStore.reset();
+    editorStore.setCode(imported.code);
+
+    const name = this.getFileName(filePath as string);
+    if (imported.skipped.length > 0) {
+      const lines = imported.skipped.map((skipped) => skipped.line).join(', ');
+      showWarning(
+        `Imported ${name}; ${imported.skipped.length} constructs were not converted (lines ${lines})`
+      );
+    } else {
+      showSuccess(`Imported ${name}`);
+    }
+  }
+
   async saveFile() {
     const { currentFile, code } = get(editorStore);
 
@@ -2,7 +2,9 @@ import { debounce } from '$lib/utils/debounce'
 import { tauriService } from './tauri'
 import { editorStore } from '$lib/stores/editor'
 import { appStore } from '$lib/stores/app'
+import { settingsStore } from '$lib/stores/settings'
 import { get } from 'svelte/store'
+import type { PreviewOptions } from '$lib/types'
 
 export class PreviewService {
   private previewCache = new Map<string, string>()
@@ -13,6 +15,9 @@ export class PreviewService {
     editorStore.subscribe(({ code }) => {
       this.debouncedPreview(code)
     })
+    settingsStore.subscribe(() => {
+      this.debouncedPreview(get(editorStore).code)
+    })
   }
   
   private debouncedPreview = debounce(async (code: string) => {
@@ -39,7 +44,10 @@ export class PreviewService {
       return
     }
     
-    const cacheKey = this.getCacheKey(code)
+    const { currentTheme } = get(appStore)
+    const { preview } = get(settingsStore)
+    const options: PreviewOptions = { ...preview, theme: currentTheme }
+    const cacheKey = this.getCacheKey(code, options)
     if (this.previewCache.has(cacheKey)) {
       editorStore.setPreview(this.previewCache.get(cacheKey)!)
       editorStore.setError(null)
@@ -51,8 +59,7 @@ export class PreviewService {
     editorStore.setError(null)
     
     try {
-      const { currentTheme } = get(appStore) 
-      const svgContent = await tauriService.generate_preview_svg(code, currentTheme)
+      const svgContent = await tauriService.generate_preview_svg(code, options)
       
       if (this.currentRequest?.signal.aborted) {
         return
@@ -72,8 +79,8 @@ export class PreviewService {
     }
   }
 
-  private getCacheKey(code: string): string {
-    return code.trim()
+  private getCacheKey(code: string, options: PreviewOptions): string {
+    return `${JSON.stringify(options)}\n${code.trim()}`
   }
 
   private isValidForPreview(code: string): boolean {
@@ -3,15 +3,19 @@ import type {
   TauriCommands,
   DiagramOptions,
   DiagramResult,
+  PreviewOptions,
+  TextOptions,
   MermaidFile,
   RecentFile,
   FileOperationResult,
+  ImportedDiagram,
+  SqlImportOptions,
 } from '$lib/types';
 
 class TauriService implements TauriCommands {
-  async generate_preview_svg(code: string, theme?: string): Promise<string> {
+  async generate_preview_svg(code: string, options?: PreviewOptions): Promise<string> {
     try {
-      return await invoke('generate_preview_svg', { code, theme })
+      return await invoke('generate_preview_svg', { code, options })
     } catch (error) {
       throw new Error(`Failed to generate preview: ${error}`)
     }
@@ -25,6 +29,30 @@ class TauriService implements TauriCommands {
     }
   }
 
+  async render_text_diagram(code: string, options?: TextOptions): Promise<string> {
+    try {
+      return await invoke('render_text_diagram', { code, options });
+    } catch (error) {
+      throw new Error(`Failed to render text: ${error}`);
+    }
+  }
+
+  async export_dot_diagram(code: string): Promise<string> {
+    try {
+      return await invoke('export_dot_diagram', { code });
+    } catch (error) {
+      throw new Error(`Failed to export DOT: ${error}`);
+    }
+  }
+
+  async export_drawio_diagram(code: string, options?: DiagramOptions): Promise<string> {
+    try {
+      return await invoke('export_drawio_diagram', { code, options });
+    } catch (error) {
+      throw new Error(`Failed to export draw.io: ${error}`);
+    }
+  }
+
   async read_mermaid_file(path: string): Promise<MermaidFile> {
     try {
       return await invoke('read_mermaid_file', { path });
@@ -49,6 +77,64 @@ class TauriService implements TauriCommands {
     }
   }
 
+  async convert_plantuml(code: string): Promise<ImportedDiagram> {
+    try {
+      return await invoke('convert_plantuml', { code });
+    } catch (error) {
+      throw new Error(`Failed to convert PlantUML: ${error}`);
+    }
+  }
+
+  async convert_dot(code: string): Promise<ImportedDiagram> {
+    try {
+      return await invoke('convert_dot', { code });
+    } catch (error) {
+      throw new Error(`Failed to convert DOT: ${error}`);
+    }
+  }
+
+  async convert_drawio(code: string): Promise<ImportedDiagram> {
+    try {
+      return await invoke('convert_drawio', { code });
+    } catch (error) {
+      throw new Error(`Failed to convert draw.io: ${error}`);
+    }
+  }
+
+  async convert_sql(code: string, options?: SqlImportOptions): Promise<ImportedDiagram> {
+    try {
+      return await invoke('convert_sql', { code, options });
+    } catch (error) {
+      throw new Error(`Failed to convert SQL schema: ${error}`);
+    }
+  }
+
+  async import_diagram_file(path: string, options?: SqlImportOptions): Promise<ImportedDiagram> {
+    try {
+      return await invoke('import_diagram_file', { path, options });
+    } catch (error) {
+      throw new Error(`Failed to import diagram: ${error}`);
+    }
+  }
+
+  async import_plantuml_directory(
+    directoryPath: string,
+    recursive: boolean,
+    outputDirectory?: string,
+    overwrite?: boolean
+  ): Promise<ImportedDiagram[]> {
+    try {
+      return await invoke('import_plantuml_directory', {
+        directoryPath,
+        recursive,
+        outputDirectory,
+        overwrite,
+      });
+    } catch (error) {
+      throw new Error(`Failed to import PlantUML files: ${error}`);
+    }
+  }
+
   async check_mmdc(): Promise<string> {
     try {
       return await invoke('check_mmdc');
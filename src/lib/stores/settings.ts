@@ -7,6 +7,9 @@ const DEFAULT_SETTINGS: SettingsState = {
   autoSaveInterval: 30000,
   defaultOutputFormat: 'png',
   fontSize: 14,
+  preview: {
+    scale: 1,
+  },
 };
 
 function createSettingsStore() {
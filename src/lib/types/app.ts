@@ -1,3 +1,5 @@
+import type { DiagramOptions, PreviewOptions, RecentFile } from './tauri';
+
 export interface AppState {
   isLoading: boolean;
   mmdcStatus: 'checking' | 'available' | 'unavailable';
@@ -33,4 +35,7 @@ export interface SettingsState {
   autoSaveInterval: number;
   defaultOutputFormat: DiagramOptions['format'];
   fontSize: number;
+  // Background, viewport size, zoom and font of the live preview; the
+  // theme follows the app theme
+  preview: Omit<PreviewOptions, 'theme'>;
 }
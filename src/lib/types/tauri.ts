@@ -1,9 +1,250 @@
 export interface DiagramOptions {
-  format: 'png' | 'svg' | 'pdf' | 'jpg';
+  format: 'png' | 'svg' | 'pdf' | 'jpg' | 'webp' | 'avif' | 'tiff' | 'eps' | 'html' | 'text' | 'dot' | 'drawio' | 'excalidraw';
   width?: number;
   height?: number;
   background: string;
   theme?: string;
+  scale?: number;
+  quality?: number;
+  lossless?: boolean;
+  compression?: 'none' | 'lzw' | 'deflate' | 'packbits';
+  color_space?: 'rgb' | 'gray' | 'cmyk';
+  svg_processing?: SvgProcessOptions;
+  pdf_fit?: boolean;
+  config_file?: string;
+  mermaid_config?: MermaidConfig;
+  css_file?: string;
+  css?: string;
+  puppeteer_config_file?: string;
+  puppeteer_config?: Record<string, unknown>;
+  svg_id?: string;
+  theme_pack?: string;
+  renderer?: RendererBackend;
+  text?: TextOptions;
+}
+
+export interface TextOptions {
+  ascii?: boolean;
+  max_width?: number;
+}
+
+export interface SvgProcessOptions {
+  background?: string;
+  minify?: boolean;
+  remove_unused_defs?: boolean;
+  embed_fonts?: boolean;
+  accessibility?: boolean;
+  strip_scripts?: boolean;
+  fit_content?: boolean;
+  scale?: number;
+}
+
+export interface PreviewOptions {
+  theme?: string;
+  background?: string;
+  viewport_width?: number;
+  viewport_height?: number;
+  scale?: number;
+  font_family?: string;
+}
+
+export interface MermaidConfig {
+  theme?: 'default' | 'base' | 'dark' | 'forest' | 'neutral' | 'null';
+  themeVariables?: ThemeVariables;
+  themeCSS?: string;
+  fontFamily?: string;
+  fontSize?: number;
+  securityLevel?: 'strict' | 'loose' | 'antiscript' | 'sandbox';
+  htmlLabels?: boolean;
+  flowchart?: {
+    curve?: 'basis' | 'bumpX' | 'bumpY' | 'cardinal' | 'catmullRom' | 'linear' | 'monotoneX' | 'monotoneY' | 'natural' | 'step' | 'stepAfter' | 'stepBefore';
+    htmlLabels?: boolean;
+    nodeSpacing?: number;
+    rankSpacing?: number;
+    padding?: number;
+    diagramPadding?: number;
+    useMaxWidth?: boolean;
+    defaultRenderer?: string;
+    [key: string]: unknown;
+  };
+  sequence?: {
+    mirrorActors?: boolean;
+    showSequenceNumbers?: boolean;
+    actorMargin?: number;
+    messageAlign?: 'left' | 'center' | 'right';
+    wrap?: boolean;
+    useMaxWidth?: boolean;
+    [key: string]: unknown;
+  };
+  gantt?: {
+    barHeight?: number;
+    barGap?: number;
+    fontSize?: number;
+    axisFormat?: string;
+    topAxis?: boolean;
+    useMaxWidth?: boolean;
+    [key: string]: unknown;
+  };
+  [key: string]: unknown;
+}
+
+export interface ThemeVariables {
+  darkMode?: boolean;
+  background?: string;
+  fontFamily?: string;
+  fontSize?: string;
+  primaryColor?: string;
+  primaryTextColor?: string;
+  primaryBorderColor?: string;
+  secondaryColor?: string;
+  tertiaryColor?: string;
+  lineColor?: string;
+  textColor?: string;
+  noteBkgColor?: string;
+  [key: string]: unknown;
+}
+
+export interface ConfigPreset {
+  name: string;
+  description?: string;
+  config: MermaidConfig;
+}
+
+export interface ResolvedConfig {
+  config: MermaidConfig;
+  sources: ('global' | 'workspace' | 'file')[];
+  warnings: string[];
+}
+
+export interface ThemeManifest {
+  name: string;
+  description?: string;
+  author?: string;
+  version?: string;
+  appearance: 'light' | 'dark';
+  background?: string;
+}
+
+export interface ThemePack {
+  id: string;
+  manifest: ThemeManifest;
+  config: MermaidConfig;
+  css?: string;
+  builtin: boolean;
+}
+
+export interface ThemePreview {
+  id: string;
+  name: string;
+  svg?: string;
+  error?: string;
+}
+
+export type MmdcSource =
+  | 'configured'
+  | 'environment'
+  | 'managed'
+  | 'path'
+  | 'pnpm'
+  | 'yarn'
+  | 'volta'
+  | 'nvm'
+  | 'asdf'
+  | 'npx'
+  | 'known_location'
+  | 'npm_global';
+
+export interface MmdcCandidate {
+  path: string;
+  source: MmdcSource;
+}
+
+export interface MmdcSettings {
+  mmdc_path?: string;
+}
+
+export interface MmdcProbe {
+  path: string;
+  source: MmdcSource;
+  exists: boolean;
+  exit_code?: number;
+  version?: string;
+  stderr: string;
+  error?: string;
+  node_path?: string;
+  node_version?: string;
+  duration_ms: number;
+}
+
+export interface MmdcDiagnosis {
+  settings: MmdcSettings;
+  mmdc_path_env?: string;
+  selected?: MmdcCandidate;
+  probes: MmdcProbe[];
+}
+
+export type InstallSource =
+  | { type: 'registry'; version?: string; registry?: string; offline?: boolean }
+  | { type: 'tarball'; path: string };
+
+export interface InstallProgress {
+  stage: 'preparing' | 'installing' | 'verifying' | 'finished' | 'failed';
+  message: string;
+}
+
+export interface ManagedInstall {
+  version: string;
+  mmdc_path: string;
+  active: boolean;
+  installed_at?: number;
+}
+
+export interface RenderWorkerSettings {
+  enabled: boolean;
+  node_path?: string;
+  max_memory_mb: number;
+  max_renders: number;
+  render_timeout_ms: number;
+  startup_timeout_ms: number;
+  puppeteer_config?: Record<string, unknown>;
+}
+
+export interface RenderWorkerStatus {
+  enabled: boolean;
+  running: boolean;
+  pid?: number;
+  renders: number;
+  memory_mb?: number;
+  restarts: number;
+  last_error?: string;
+}
+
+export type RendererBackend = 'mmdc' | 'worker' | 'kroki' | 'native';
+
+export type RenderPolicy = 'quality' | 'speed' | 'custom';
+
+export interface KrokiSettings {
+  url?: string;
+  timeout_ms: number;
+}
+
+export interface RendererSettings {
+  policy: RenderPolicy;
+  chain: RendererBackend[];
+  worker: RenderWorkerSettings;
+  kroki: KrokiSettings;
+}
+
+export interface RendererCapabilities {
+  formats: DiagramOptions['format'][];
+  diagram_types?: string[];
+}
+
+export interface RendererInfo {
+  backend: RendererBackend;
+  capabilities: RendererCapabilities;
+  available: boolean;
+  reason?: string;
 }
 
 export interface DiagramResult {
@@ -12,6 +253,57 @@ export interface DiagramResult {
   error_message?: string;
   generation_time: number;
   file_size?: number;
+  renderer?: RendererBackend;
+}
+
+export interface PdfDocumentOptions {
+  page_size?: 'a3' | 'a4' | 'a5' | 'letter' | 'legal' | 'tabloid';
+  orientation?: 'portrait' | 'landscape';
+  margin?: number;
+  title?: string;
+  header?: boolean;
+  page_numbers?: boolean;
+  table_of_contents?: boolean;
+  fit_to_page?: boolean;
+  theme?: string;
+  background?: string;
+}
+
+export interface PdfPageEntry {
+  path: string;
+  title: string;
+  page?: number;
+  error?: string;
+}
+
+export interface PdfDocumentResult {
+  output_path: string;
+  page_count: number;
+  entries: PdfPageEntry[];
+  file_size: number;
+  generation_time: number;
+}
+
+export interface ScheduledTask {
+  id: string;
+  name: string;
+  section?: string;
+  start: string;
+  end: string;
+  milestone: boolean;
+  crit: boolean;
+  active: boolean;
+  done: boolean;
+  dependencies: string[];
+}
+
+export interface GanttSchedule {
+  title?: string;
+  date_format: string;
+  excludes: string[];
+  start?: string;
+  end?: string;
+  tasks: ScheduledTask[];
 }
 
 export interface MermaidFile {
@@ -34,14 +326,63 @@ export interface FileOperationResult {
   path?: string;
 }
 
+export interface SkippedConstruct {
+  line: number;
+  source: string;
+  reason: string;
+}
+
+export interface ImportedDiagram {
+  path?: string;
+  output_path?: string;
+  code: string;
+  skipped: SkippedConstruct[];
+  error?: string;
+}
+
+export interface SqlImportOptions {
+  schemas?: string;
+  tables?: string;
+}
+
 // Tauri command wrapper types
 export type TauriCommand<T = any> = (...args: any[]) => Promise<T>;
 
 export interface TauriCommands {
+  generate_preview_svg: (code: string, options?: PreviewOptions) => Promise<string>;
   generate_diagram: (code: string, options: DiagramOptions) => Promise<DiagramResult>;
   read_mermaid_file: (path: string) => Promise<MermaidFile>;
   write_mermaid_file: (path: string, content: string) => Promise<FileOperationResult>;
   get_recent_files: () => Promise<RecentFile[]>;
+  convert_plantuml: (code: string) => Promise<ImportedDiagram>;
+  convert_dot: (code: string) => Promise<ImportedDiagram>;
+  convert_drawio: (code: string) => Promise<ImportedDiagram>;
+  convert_sql: (code: string, options?: SqlImportOptions) => Promise<ImportedDiagram>;
+  import_diagram_file: (path: string, options?: SqlImportOptions) => Promise<ImportedDiagram>;
+  import_plantuml_directory: (
+    directoryPath: string,
+    recursive: boolean,
+    outputDirectory?: string,
+    overwrite?: boolean
+  ) => Promise<ImportedDiagram[]>;
   check_mmdc: () => Promise<string>;
+  diagnose_mmdc: () => Promise<MmdcDiagnosis>;
+  get_mmdc_settings: () => Promise<MmdcSettings>;
+  set_mmdc_settings: (settings: MmdcSettings) => Promise<string>;
+  list_mmdc_installs: () => Promise<ManagedInstall[]>;
+  install_mmdc: (source: InstallSource) => Promise<ManagedInstall>;
+  verify_mmdc_install: (version: string) => Promise<string>;
+  set_active_mmdc_install: (version: string) => Promise<ManagedInstall>;
+  uninstall_mmdc: (version: string) => Promise<void>;
+  get_render_worker_status: () => Promise<RenderWorkerStatus>;
+  configure_render_worker: (settings: RenderWorkerSettings) => Promise<RenderWorkerStatus>;
+  restart_render_worker: () => Promise<RenderWorkerStatus>;
+  list_renderers: () => Promise<RendererInfo[]>;
+  get_renderer_settings: () => Promise<RendererSettings>;
+  set_renderer_settings: (settings: RendererSettings) => Promise<RendererInfo[]>;
+  get_gantt_schedule: (code: string) => Promise<GanttSchedule>;
+  render_text_diagram: (code: string, options?: TextOptions) => Promise<string>;
+  export_dot_diagram: (code: string) => Promise<string>;
+  export_drawio_diagram: (code: string, options?: DiagramOptions) => Promise<string>;
   greet: (name: string) => Promise<string>;
 }
@@ -7,7 +7,7 @@ export const DEFAULT_MERMAID_CODE = `graph TD
     C -->|Yes| D[Result1]
     C -->|No| E[Result2]`;
 
-export const SUPPORTED_FORMATS = ['png', 'svg', 'pdf', 'jpg'] as const;
+export const SUPPORTED_FORMATS = ['png', 'svg', 'pdf', 'jpg', 'webp', 'avif', 'tiff', 'eps', 'html', 'dot', 'drawio', 'excalidraw'] as const;
 export const DEFAULT_FORMAT = 'png';
 
 export const KEYBOARD_SHORTCUTS = {